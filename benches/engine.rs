@@ -0,0 +1,181 @@
+//! Baseline benchmarks of the engine, so that a change, that is meant to make it faster, can be measured.
+//! Covers the win detection, the mirroring of the position key, the heuristic evaluation, the move
+//! ordering and complete searches. The boards are read from move strings, so that every fixture can be
+//! looked at with the game.
+//! Run with `cargo bench --bench engine`.
+
+use connect_4_rust::board_logic::alpha_beta::{AlphaBeta, CLAMP_GUARD_HEURISTIC, SearchConfig};
+use connect_4_rust::board_logic::bit_board::BitBoard;
+use connect_4_rust::board_logic::bit_board_coding::{BoardGeometry, StandardGeometry};
+use connect_4_rust::board_logic::heuristic::{HeuristicWeights, compute_heuristics};
+use connect_4_rust::selfplay::SplitMix64;
+use criterion::{BatchSize, Criterion, black_box, criterion_group, criterion_main};
+
+/// The amount of random boards the win detection is run on.
+const BOARD_COUNT: usize = 1024;
+
+/// The depth of the complete searches.
+const SEARCH_DEPTH: u32 = 11;
+
+/// The mid game, on which the heuristics and the move ordering are measured. It is also the tactical
+/// position of the searches, the depth 11 needs about a hundred thousand nodes on it.
+const MIDGAME: &str = "427324416175";
+
+/// The positions of the complete searches, the empty board, the mid game and a board with 12 free cells.
+const SEARCH_POSITIONS: [(&str, &str); 3] = [
+    ("empty", ""),
+    ("midgame", MIDGAME),
+    ("near_full", "437375376765113412442653743261"),
+];
+
+/// Reads the fixture from its move string.
+fn get_board(moves: &str) -> BitBoard {
+    BitBoard::from_move_string(moves).expect("The fixtures are valid move strings.")
+}
+
+/// Generates the move strings of random legal games of 10 to 30 plies. A game stops early, when the
+/// next move would end it.
+fn generate_move_strings() -> Vec<String> {
+    let mut random = SplitMix64::new(42);
+    let mut move_strings = Vec::with_capacity(BOARD_COUNT);
+    while move_strings.len() < BOARD_COUNT {
+        let mut moves = String::new();
+        let plies = 10 + random.next_below(21);
+        for _ in 0..plies {
+            let column = random.next_below(7) + 1;
+            let candidate = format!("{moves}{column}");
+            match BitBoard::from_move_string(&candidate) {
+                Ok(board) if !board.is_game_over() => moves = candidate,
+                _ => continue,
+            }
+        }
+        move_strings.push(moves);
+    }
+    move_strings
+}
+
+fn win_detection(criterion: &mut Criterion) {
+    let boards: Vec<u64> = generate_move_strings()
+        .iter()
+        .map(|moves| get_board(moves))
+        .flat_map(|board| [board.own_stones, board.opponent_stones])
+        .collect();
+    criterion.bench_function("check_for_winning", |bencher| {
+        bencher.iter(|| {
+            boards
+                .iter()
+                .filter(|&&board| StandardGeometry::check_for_winning(black_box(board)))
+                .count()
+        })
+    });
+}
+
+/// Mirrors the standard board with the shifts written out, as it was done before the geometry became
+/// generic. The generic loop over the columns has to be as fast.
+fn flip_standard_board(input: u64) -> u64 {
+    let column = StandardGeometry::get_column_mask;
+    (input & column(6)) >> 6
+        | (input & column(5)) >> 4
+        | (input & column(4)) >> 2
+        | input & column(3)
+        | (input & column(2)) << 2
+        | (input & column(1)) << 4
+        | (input & column(0)) << 6
+}
+
+fn symmetry(criterion: &mut Criterion) {
+    let boards: Vec<BitBoard> = generate_move_strings()
+        .iter()
+        .map(|moves| get_board(moves))
+        .collect();
+    let stones: Vec<u64> = boards
+        .iter()
+        .flat_map(|board| [board.own_stones, board.opponent_stones])
+        .collect();
+    for &board in &stones {
+        assert_eq!(
+            StandardGeometry::flip_board(board),
+            flip_standard_board(board)
+        );
+    }
+    let mut group = criterion.benchmark_group("flip_board");
+    group.bench_function("generic", |bencher| {
+        bencher.iter(|| {
+            stones.iter().fold(0, |sum, &board| {
+                sum ^ StandardGeometry::flip_board(black_box(board))
+            })
+        })
+    });
+    group.bench_function("written_out", |bencher| {
+        bencher.iter(|| {
+            stones
+                .iter()
+                .fold(0, |sum, &board| sum ^ flip_standard_board(black_box(board)))
+        })
+    });
+    group.finish();
+    // The key of the transposition table, which mirrors the board on every lookup.
+    criterion.bench_function("get_symmetry_independent_key", |bencher| {
+        bencher.iter(|| {
+            boards.iter().fold(0, |sum, board| {
+                sum ^ black_box(board).get_symmetry_independent_key()
+            })
+        })
+    });
+}
+
+fn heuristics(criterion: &mut Criterion) {
+    // The heuristics look at the board from the side, that has just moved.
+    let mut board = get_board(MIDGAME);
+    board.swap_players();
+    let weights = HeuristicWeights::default();
+    criterion.bench_function("compute_heuristics", |bencher| {
+        bencher.iter(|| compute_heuristics(black_box(&board), &weights, CLAMP_GUARD_HEURISTIC))
+    });
+}
+
+fn move_ordering(criterion: &mut Criterion) {
+    let board = get_board(MIDGAME);
+    // An engine, that has not searched yet, orders by the heuristics alone.
+    let mut engine = AlphaBeta::new();
+    criterion.bench_function("get_pre_sorted_move_list", |bencher| {
+        bencher.iter(|| engine.get_move_ordering(black_box(board.clone()), SEARCH_DEPTH))
+    });
+}
+
+fn search(criterion: &mut Criterion) {
+    let config = SearchConfig {
+        max_depth: SEARCH_DEPTH,
+        move_time: None,
+        ..SearchConfig::default()
+    };
+    let mut group = criterion.benchmark_group("get_best_move_depth_11");
+    group.sample_size(10);
+    for (name, moves) in SEARCH_POSITIONS {
+        let board = get_board(moves);
+        // Every search starts with an empty transposition table, as a repeated search would only look up
+        // the result.
+        group.bench_function(name, |bencher| {
+            bencher.iter_batched(
+                || AlphaBeta::with_config(config),
+                |mut engine| {
+                    engine
+                        .get_best_move(board.clone())
+                        .expect("The fixtures are not over.")
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    win_detection,
+    symmetry,
+    heuristics,
+    move_ordering,
+    search
+);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+//! Plays the five in a row variant on the wider board against the engine in the terminal. The board
+//! has nine columns and seven rows and a line of five stones is needed to win. The columns are entered
+//! as numbers starting with 1, the human player starts. Like the engine example this only uses the
+//! library part of the crate:
+//! ```text
+//! cargo run --example connect_five --no-default-features
+//! ```
+
+use connect_4_rust::board_logic::alpha_beta::{GenericAlphaBeta, SearchConfig};
+use connect_4_rust::board_logic::bit_board::{GameResult, GenericBitBoard};
+use connect_4_rust::board_logic::bit_board_coding::{BoardGeometry, ConnectFiveGeometry};
+use std::io::{self, BufRead, Write};
+
+/// The time in seconds the engine may think about a move.
+const MOVE_TIME: f32 = 2.0;
+
+fn main() {
+    let config = SearchConfig {
+        move_time: Some(MOVE_TIME),
+        ..SearchConfig::default()
+    };
+    let mut engine = GenericAlphaBeta::<ConnectFiveGeometry>::with_config(config);
+    let mut board = GenericBitBoard::<ConnectFiveGeometry>::new();
+    let width = ConnectFiveGeometry::WIDTH;
+    let mut lines = io::stdin().lock().lines();
+
+    println!("{}\n", board.to_ascii());
+    while !board.is_game_over() {
+        if board.side_to_move().is_computer() {
+            let column = engine
+                .get_best_move(board.clone())
+                .expect("The game is not over yet.");
+            board
+                .play(column)
+                .expect("The engine only picks possible moves.");
+            println!("The computer plays column {}:\n", column + 1);
+        } else {
+            print!("Your column (1-{width}): ");
+            io::stdout().flush().expect("The terminal is writable.");
+            let Some(Ok(line)) = lines.next() else {
+                return;
+            };
+            let column = match line.trim().parse::<u32>() {
+                Ok(column) if (1..=width).contains(&column) => column - 1,
+                _ => {
+                    println!("Please enter a number from 1 to {width}.");
+                    continue;
+                }
+            };
+            if let Err(error) = board.play(column) {
+                println!("This move is not possible: {error}.");
+                continue;
+            }
+        }
+        println!("{}\n", board.to_ascii());
+    }
+
+    let (result, _) = board.get_winning_status_for_rendering();
+    match result {
+        GameResult::FirstPlayerWon => println!("You have won."),
+        GameResult::SecondPlayerWon => println!("The computer has won."),
+        _ => println!("The game ended in a draw."),
+    }
+}
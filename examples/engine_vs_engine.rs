@@ -0,0 +1,54 @@
+//! Plays a full game between two engines without any graphics and prints the board after every move.
+//! This only uses the library part of the crate, so it also runs when the crate is built without the
+//! gui feature:
+//! ```text
+//! cargo run --example engine_vs_engine --no-default-features
+//! ```
+
+use connect_4_rust::board_logic::alpha_beta::{AlphaBeta, SearchConfig};
+use connect_4_rust::board_logic::bit_board::{BitBoard, GameResult};
+
+/// The depth both engines search to, kept low so that the game finishes quickly.
+const EXAMPLE_DEPTH: u32 = 8;
+
+fn main() {
+    let config = SearchConfig {
+        max_depth: EXAMPLE_DEPTH,
+        move_time: None,
+        ..SearchConfig::default()
+    };
+    // The board is represented from the perspective of the first engine, which is the one to start.
+    let mut first_engine = AlphaBeta::with_config(config);
+    let mut second_engine = AlphaBeta::with_config(config);
+    let mut board = BitBoard::new();
+    board.set_computer_first(true);
+
+    while !board.is_game_over() {
+        let column = if board.side_to_move().is_computer() {
+            first_engine
+                .get_best_move(board.clone())
+                .expect("The game is not over yet.")
+        } else {
+            // The engine always plays the own stones, so the second one gets the swapped board.
+            let mut swapped_board = board.clone();
+            swapped_board.swap_players();
+            second_engine
+                .get_best_move(swapped_board)
+                .expect("The game is not over yet.")
+        };
+        board
+            .play(column)
+            .expect("The engines only pick possible moves.");
+        println!("Column {}:\n{}\n", column + 1, board.to_ascii());
+    }
+
+    let (result, _) = board.get_winning_status_for_rendering();
+    match result {
+        GameResult::FirstPlayerWon => println!("The first engine (X) has won."),
+        GameResult::SecondPlayerWon => println!("The second engine (O) has won."),
+        _ => println!("The game ended in a draw."),
+    }
+    if let Some(moves) = board.to_move_string() {
+        println!("Moves: {moves}");
+    }
+}
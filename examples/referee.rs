@@ -0,0 +1,175 @@
+//! Plays one game between two engine processes, that speak the protocol of
+//! [`connect_4_rust::protocol`]. The referee keeps the board, sends the position before every move and
+//! adjudicates the game. Engines, that answer with an illegal move or no move at all, lose.
+//!
+//! Usage:
+//! ```text
+//! cargo build --release --bin protocol
+//! cargo run --release --example referee -- target/release/protocol target/release/protocol [--movetime MS]
+//! ```
+//! The first engine starts.
+
+use connect_4_rust::board_logic::bit_board::{BitBoard, GameResult};
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitCode, Stdio};
+
+/// The time per move in milliseconds, if none is given on the command line.
+const DEFAULT_MOVE_TIME: u32 = 500;
+
+/// An engine running as a child process.
+struct EngineProcess {
+    child: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+}
+
+impl EngineProcess {
+    /// Starts the engine and begins a new game on it.
+    fn start(path: &str) -> Result<EngineProcess, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|error| format!("Can not start {path}: {error}"))?;
+        let input = child.stdin.take().expect("The input is piped.");
+        let output = BufReader::new(child.stdout.take().expect("The output is piped."));
+        let mut engine = EngineProcess {
+            child,
+            input,
+            output,
+        };
+        engine.send("newgame")?;
+        Ok(engine)
+    }
+
+    /// Sends one line to the engine.
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.input, "{line}")
+            .and_then(|_| self.input.flush())
+            .map_err(|error| format!("Can not send to the engine: {error}"))
+    }
+
+    /// Asks for the move on the position and returns the column counted from 1 as the engine sent it.
+    fn request_move(&mut self, moves: &str, move_time: u32) -> Result<u32, String> {
+        self.send(&format!("position {moves}"))?;
+        self.send(&format!("go movetime {move_time}"))?;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = self
+                .output
+                .read_line(&mut line)
+                .map_err(|error| format!("Can not read from the engine: {error}"))?;
+            if read == 0 {
+                return Err("The engine has ended without a move.".to_string());
+            }
+            let line = line.trim();
+            if let Some(column) = line.strip_prefix("bestmove ") {
+                return column
+                    .parse()
+                    .map_err(|_| format!("The engine sent an invalid move: {line}"));
+            }
+            if line.starts_with("error") {
+                return Err(format!("The engine reported: {line}"));
+            }
+            println!("  {line}");
+        }
+    }
+
+    /// Ends the engine process.
+    fn quit(mut self) {
+        // The engine may already be gone, then there is nothing left to end.
+        let _ = self.send("quit");
+        let _ = self.child.wait();
+    }
+}
+
+/// Parses the command line into the two engine paths and the time per move.
+fn parse_arguments() -> Result<(String, String, u32), String> {
+    let mut arguments = env::args().skip(1);
+    let usage = "Usage: referee <first engine> <second engine> [--movetime MS]";
+    let first = arguments.next().ok_or(usage)?;
+    let second = arguments.next().ok_or(usage)?;
+    let move_time = match (arguments.next().as_deref(), arguments.next()) {
+        (None, _) => DEFAULT_MOVE_TIME,
+        (Some("--movetime"), Some(value)) => value
+            .parse()
+            .map_err(|_| format!("Invalid move time {value}"))?,
+        _ => return Err(usage.to_string()),
+    };
+    Ok((first, second, move_time))
+}
+
+/// Plays the game and returns the index of the winning engine or none for a draw together with the
+/// move string of the game.
+fn play_game(engines: &mut [EngineProcess; 2], move_time: u32) -> (Option<usize>, String) {
+    let mut board = BitBoard::new();
+    let mut moves = String::new();
+
+    while !board.is_game_over() {
+        let engine_index = moves.len() % 2;
+        let column = match engines[engine_index].request_move(&moves, move_time) {
+            Ok(column) => column,
+            Err(message) => {
+                println!("Engine {}: {message}", engine_index + 1);
+                return (Some(1 - engine_index), moves);
+            }
+        };
+        if column == 0 || board.play(column - 1).is_err() {
+            println!(
+                "Engine {} played the illegal move {column}.",
+                engine_index + 1
+            );
+            return (Some(1 - engine_index), moves);
+        }
+        moves.push_str(&column.to_string());
+        println!(
+            "Engine {} plays {column}:\n{}",
+            engine_index + 1,
+            board.to_ascii()
+        );
+    }
+
+    let winner = match board.get_winning_status_for_rendering().0 {
+        GameResult::FirstPlayerWon => Some(0),
+        GameResult::SecondPlayerWon => Some(1),
+        _ => None,
+    };
+    (winner, moves)
+}
+
+/// Starts both engines, plays the game and ends the engines again.
+fn run() -> Result<(), String> {
+    let (first, second, move_time) = parse_arguments()?;
+    let first = EngineProcess::start(&first)?;
+    let second = match EngineProcess::start(&second) {
+        Ok(second) => second,
+        Err(message) => {
+            first.quit();
+            return Err(message);
+        }
+    };
+    let mut engines = [first, second];
+
+    let (winner, moves) = play_game(&mut engines, move_time);
+    match winner {
+        Some(winner) => println!("Engine {} has won.", winner + 1),
+        None => println!("The game ended in a draw."),
+    }
+    println!("Moves: {moves}");
+    for engine in engines {
+        engine.quit();
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
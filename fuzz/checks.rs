@@ -0,0 +1,58 @@
+//! The checks of the fuzz targets. They are shared with the smoke test of the game, which runs the seeds of
+//! the corpus through them in the normal `cargo test`.
+
+use connect_4_rust::board_logic::bit_board::BitBoard;
+use connect_4_rust::records::GameRecord;
+
+/// Feeds the bytes to the move string parser. It may refuse the input, but it may not panic and every
+/// board it accepts has to be valid.
+pub fn check_move_string(data: &[u8]) {
+    let Ok(moves) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(board) = BitBoard::from_move_string(moves) {
+        if let Err(error) = board.validate() {
+            panic!("The move string {moves:?} gives an invalid board: {error}\n{board}");
+        }
+        assert_eq!(
+            board.to_move_string().as_deref(),
+            Some(moves),
+            "The move string does not survive the round trip."
+        );
+    }
+}
+
+/// Feeds the bytes to the ASCII grid parser. It may refuse the input, but it may not panic and every
+/// board it accepts has to be valid.
+pub fn check_ascii_board(data: &[u8]) {
+    let Ok(grid) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(board) = BitBoard::from_ascii(grid) {
+        if let Err(error) = board.validate() {
+            panic!("The grid {grid:?} gives an invalid board: {error}\n{board}");
+        }
+        assert_eq!(
+            BitBoard::from_ascii(&board.to_ascii())
+                .ok()
+                .map(|copy| copy.to_ascii()),
+            Some(board.to_ascii()),
+            "The grid does not survive the round trip."
+        );
+    }
+}
+
+/// Feeds the bytes to the loader of the game records. It may refuse the input, but it may not panic and
+/// every record it accepts has to be written and read again unchanged.
+pub fn check_game_record(data: &[u8]) {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(record) = GameRecord::from_json(text) {
+        assert_eq!(
+            GameRecord::from_json(&record.to_json()).as_ref(),
+            Ok(&record),
+            "The record does not survive the round trip."
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! Fuzzes the ASCII grid parser, see [`checks::check_ascii_board`] for what has to hold.
+
+#![no_main]
+
+// Every target uses only its own check.
+#[allow(dead_code)]
+#[path = "../checks.rs"]
+mod checks;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| checks::check_ascii_board(data));
@@ -0,0 +1,12 @@
+//! Fuzzes the loader of the game records, see [`checks::check_game_record`] for what has to hold.
+
+#![no_main]
+
+// Every target uses only its own check.
+#[allow(dead_code)]
+#[path = "../checks.rs"]
+mod checks;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| checks::check_game_record(data));
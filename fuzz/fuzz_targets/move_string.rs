@@ -0,0 +1,12 @@
+//! Fuzzes the move string parser, see [`checks::check_move_string`] for what has to hold.
+
+#![no_main]
+
+// Every target uses only its own check.
+#[allow(dead_code)]
+#[path = "../checks.rs"]
+mod checks;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| checks::check_move_string(data));
@@ -0,0 +1,81 @@
+#ifndef CONNECT4_H
+#define CONNECT4_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A pointer handed over has been null.
+#define C4_ERROR_NULL_POINTER -1
+
+// The move string is no valid UTF-8.
+#define C4_ERROR_INVALID_UTF8 -2
+
+// The move string contains a character, that is no column, or a move, that can not be played.
+#define C4_ERROR_INVALID_MOVES -3
+
+// The game of the move string is already over, there is no move to search.
+#define C4_ERROR_GAME_OVER -4
+
+// The engine has failed unexpectedly.
+#define C4_ERROR_INTERNAL -5
+
+// The outcome of a solved position for the player to move.
+#define C4_OUTCOME_LOSS -1
+
+#define C4_OUTCOME_DRAW 0
+
+#define C4_OUTCOME_WIN 1
+
+// The engine with its transposition tables, it is opaque for the caller.
+typedef struct Engine Engine;
+
+// The result of solving a position exactly.
+typedef struct C4SolveResult {
+  // One of the outcomes for the player to move.
+  int32_t outcome;
+  // The amount of plies until the game ends including the move to be made.
+  uint32_t distance;
+  // The column of the move, that leads to the outcome, counted from 1.
+  uint32_t best_move;
+} C4SolveResult;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates the engine, that searches up to the depth. Null is returned for the depth zero. The engine
+// has to be released with [`c4_engine_free`].
+Engine *c4_engine_new(uint32_t depth);
+
+// Releases the engine, null is ignored.
+//
+// # Safety
+// The engine has to come from [`c4_engine_new`] and may not be used afterwards.
+void c4_engine_free(Engine *engine);
+
+// Searches the best move in the position of the move string with the length in bytes. Returns the
+// column counted from 1 or a negative error code.
+//
+// # Safety
+// The engine has to come from [`c4_engine_new`] and the move string has to have at least `length`
+// readable bytes, null pointers are reported as errors.
+int32_t c4_best_move(Engine *engine, const uint8_t *moves, uintptr_t length);
+
+// Solves the position of the move string with the length in bytes exactly and writes the outcome into
+// the result. Returns zero or a negative error code. This is only feasible for positions, where a good
+// part of the board is already filled.
+//
+// # Safety
+// The engine has to come from [`c4_engine_new`], the move string has to have at least `length`
+// readable bytes and the result has to be writable, null pointers are reported as errors.
+int32_t c4_solve(Engine *engine, const uint8_t *moves, uintptr_t length, C4SolveResult *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CONNECT4_H */
@@ -0,0 +1,17 @@
+//! Command line front end for the engine protocol, so that the engine can be run as a process by
+//! referees or other engines. The protocol is read from stdin and answered on stdout, see
+//! [`connect_4_rust::protocol`] for the commands.
+
+use connect_4_rust::protocol::run_protocol;
+use std::io;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match run_protocol(io::stdin().lock(), io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Command line front end for the self play harness. Plays a match between two differently configured
+//! engines and prints a result table.
+//!
+//! Usage:
+//! ```text
+//! selfplay [--games N] [--depth-a N] [--depth-b N] [--time-a SECONDS] [--time-b SECONDS]
+//!          [--open-three-a WEIGHT] [--open-three-b WEIGHT] [--seed N] [--opening-moves N]
+//! ```
+//! Without a time the engines search every iteration up to their depth. The open three weights
+//! replace the default heuristic weight for an open triplet.
+
+use connect_4_rust::board_logic::alpha_beta::SearchConfig;
+use connect_4_rust::selfplay::{MatchSettings, play_match};
+use std::process::ExitCode;
+use std::str::FromStr;
+
+/// Parses the value following an option.
+fn parse_value<T: FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {option}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value {value} for {option}"))
+}
+
+/// Turns the command line arguments into the match settings.
+fn parse_arguments() -> Result<MatchSettings, String> {
+    let mut settings = MatchSettings {
+        games: 20,
+        config_a: SearchConfig {
+            max_depth: 9,
+            move_time: None,
+            ..SearchConfig::default()
+        },
+        config_b: SearchConfig {
+            max_depth: 9,
+            move_time: None,
+            ..SearchConfig::default()
+        },
+        seed: 0,
+        opening_moves: 2,
+    };
+
+    let mut arguments = std::env::args().skip(1);
+    while let Some(option) = arguments.next() {
+        let value = arguments.next();
+        match option.as_str() {
+            "--games" => settings.games = parse_value(&option, value)?,
+            "--depth-a" => settings.config_a.max_depth = parse_value(&option, value)?,
+            "--depth-b" => settings.config_b.max_depth = parse_value(&option, value)?,
+            "--time-a" => settings.config_a.move_time = Some(parse_value(&option, value)?),
+            "--time-b" => settings.config_b.move_time = Some(parse_value(&option, value)?),
+            "--open-three-a" => settings.config_a.weights.open_three = parse_value(&option, value)?,
+            "--open-three-b" => settings.config_b.weights.open_three = parse_value(&option, value)?,
+            "--seed" => settings.seed = parse_value(&option, value)?,
+            "--opening-moves" => settings.opening_moves = parse_value(&option, value)?,
+            _ => return Err(format!("Unknown option {option}")),
+        }
+    }
+
+    Ok(settings)
+}
+
+fn main() -> ExitCode {
+    let settings = match parse_arguments() {
+        Ok(settings) => settings,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!(
+        "Playing {} games: a = {:?}, b = {:?}",
+        settings.games, settings.config_a, settings.config_b
+    );
+    let result = play_match(&settings);
+    print!("{result}");
+    ExitCode::SUCCESS
+}
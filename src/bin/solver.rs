@@ -0,0 +1,77 @@
+//! Command line front end for the batch solver. Solves the positions of a file or of stdin, one move
+//! string per line, and prints a line per position as soon as it is solved, see
+//! [`connect_4_rust::solver`] for the format.
+//!
+//! Usage:
+//! ```text
+//! solver [--depth N] [--threads N] [FILE]
+//! ```
+//! With a depth the positions with more moves left are only searched to the depth instead of being
+//! solved exactly. The exit code signals a failure, if any of the lines could not be solved.
+
+use connect_4_rust::solver::{SolverSettings, run_solver};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process::ExitCode;
+use std::str::FromStr;
+
+/// Parses the value following an option.
+fn parse_value<T: FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {option}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value {value} for {option}"))
+}
+
+/// Turns the command line arguments into the settings and the file to read, none for stdin.
+fn parse_arguments() -> Result<(SolverSettings, Option<String>), String> {
+    let mut settings = SolverSettings::default();
+    let mut path = None;
+
+    let mut arguments = std::env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--depth" => settings.depth = Some(parse_value(&argument, arguments.next())?),
+            "--threads" => settings.threads = parse_value(&argument, arguments.next())?,
+            _ if argument.starts_with("--") => return Err(format!("Unknown option {argument}")),
+            _ => path = Some(argument),
+        }
+    }
+    if settings.threads == 0 {
+        return Err("At least one thread is needed".to_string());
+    }
+
+    Ok((settings, path))
+}
+
+fn main() -> ExitCode {
+    let (settings, path) = match parse_arguments() {
+        Ok(arguments) => arguments,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let input: Box<dyn BufRead + Send> = match path {
+        Some(path) => match File::open(&path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(error) => {
+                eprintln!("{path} can not be opened: {error}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+
+    match run_solver(input, &mut io::stdout(), &mut io::stderr(), settings) {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(failures) => {
+            eprintln!("{failures} lines could not be solved");
+            ExitCode::FAILURE
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
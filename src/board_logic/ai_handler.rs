@@ -1,53 +1,1068 @@
 //! This module is the main entrance point to the asynchronous ai. It spawns the worker thread and takes care
-//! of the communication.
+//! of the communication. Besides the computer moves the thread also computes hints for the player with a
+//! reduced search. Every request is tagged with a generation, so that a hint result is never confused with
+//! a move result and results of cancelled requests get dropped. The configuration of the search for the
+//! computer moves can be changed with a request as well, as the thread owns the search. While a computer
+//! move is searched, the statistics of the finished iterations are streamed back over a progress channel.
+//! For the analysis of a finished game positions can be evaluated with a fixed depth search. Many of these
+//! requests can be queued at once, every one is tagged with the ply of the position it belongs to.
+//!
+//! The worker may die, if a search panics. The handler notices this, when the channels to the worker get
+//! closed, and spawns a new worker with the same configuration. The requests, that were pending, are lost
+//! and get reported as an [`AiError`], so that the caller can ask again. A computer move, that takes far
+//! too long, also gets reported and the stuck worker gets replaced.
+//!
+//! A board, on which the game is already over, has no move. The worker does not search it, but answers
+//! with the [`EngineError`], which reaches the caller of the computer move as an [`AiError`].
+//!
+//! For the recording of a session the results can be recorded, in a replay they get fed in again instead
+//! of asking the worker, so that the ai behaves exactly the same.
+//!
+//! On the web there are no threads. There the worker lives in the handler and runs on the main loop, every
+//! frame it searches a slice of [`NODES_PER_SLICE`] nodes with [`AiHandler::run_worker`]. The
+//! communication goes over the same channels, so the rest of the game does not notice the difference.
 
-use crate::board_logic::alpha_beta::AlphaBeta;
+use crate::board_logic::alpha_beta::{AlphaBeta, EngineError, SearchConfig, SearchStats};
 use crate::board_logic::bit_board::BitBoard;
-use std::sync::mpsc;
-use std::thread;
+use crate::board_logic::time_manager::advance_web_clock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
+use std::{fmt, io, mem};
+
+/// The maximum depth of the search for a hint.
+const HINT_SEARCH_DEPTH: u32 = 10;
+
+/// The base time in seconds the search for a hint may take.
+const HINT_MOVE_TIME: f32 = 0.5;
+
+/// The depth of the search, that evaluates a position for the analysis.
+const EVALUATION_SEARCH_DEPTH: u32 = 9;
+
+/// The amount of nodes the worker searches per frame on the web, where it runs on the main loop. It
+/// keeps a frame at a few milliseconds.
+pub const NODES_PER_SLICE: u64 = 4_000;
+
+/// The time a computer move may take at most, before the worker is considered stuck. This is far beyond
+/// the time the time manager allots to a move.
+const MOVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The errors that can occur in the communication with the worker thread.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AiError {
+    /// The worker has stopped, the results of the pending requests are lost. A new worker has been
+    /// spawned, so the requests can be sent again.
+    WorkerDied,
+    /// The request could not be handed over to the worker, not even to a newly spawned one.
+    ChannelClosed,
+    /// The computer move did not arrive in time, the worker has been replaced.
+    Timeout,
+    /// The search could not find a move on the board handed over.
+    Engine { error: EngineError },
+}
+
+impl fmt::Display for AiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AiError::WorkerDied => write!(f, "the ai worker has stopped"),
+            AiError::ChannelClosed => write!(f, "the ai worker can not be reached"),
+            AiError::Timeout => write!(f, "the ai did not find a move in time"),
+            AiError::Engine { error } => write!(f, "the ai has no move: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for AiError {}
+
+/// The purposes a board can be analyzed for.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RequestKind {
+    /// The move of the computer.
+    Move,
+    /// A hint for the player.
+    Hint,
+    /// The evaluation of a position for the analysis.
+    Evaluation,
+}
+
+/// A request to the worker thread.
+enum AiRequest {
+    /// Searches the best move on the board.
+    Analyze {
+        /// The board from the perspective of the side, that wants the move.
+        board: BitBoard,
+        /// The generation the request got tagged with.
+        generation: u64,
+        /// What the result is needed for.
+        kind: RequestKind,
+        /// A bit per column, that may not be chosen.
+        banned_columns: u32,
+    },
+    /// Replaces the configuration of the search for the computer moves.
+    Configure(SearchConfig),
+    /// Asks for the configuration of the search for the computer moves, the answer goes over the channel.
+    Probe(mpsc::Sender<SearchConfig>),
+    /// Seeds the random generator of the search for the computer moves.
+    Seed(u64),
+    /// Forgets the transposition tables of the searches, as a new game starts.
+    NewGame,
+    /// Lets the worker panic, so that the tests can see it die.
+    #[cfg(test)]
+    Poison,
+    /// Lets the worker sleep for the duration, so that the tests can see it get stuck.
+    #[cfg(test)]
+    Stall(Duration),
+}
+
+/// A result of the worker thread, tagged like the request it belongs to. The results can be recorded
+/// and fed in again, see [`AiHandler::set_scripted`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AiResult {
+    /// The column chosen, or the error, why there is none.
+    pub column: Result<u32, EngineError>,
+    /// The score of the column from the perspective of the side, that wanted the move.
+    pub score: f32,
+    /// The generation of the request.
+    pub generation: u64,
+}
+
+/// The intermediate result of a finished iteration of the search for a computer move.
+struct AiProgress {
+    /// The statistics of the iteration, the score is from the perspective of the computer.
+    stats: SearchStats,
+    /// The generation of the request.
+    generation: u64,
+}
+
+/// The ends of the channels, with which the handler talks to a worker thread.
+struct WorkerChannels {
+    receiver: mpsc::Receiver<AiResult>,
+    sender: mpsc::Sender<AiRequest>,
+    progress_receiver: mpsc::Receiver<AiProgress>,
+    /// On the web the worker has no thread of its own, the handler keeps it and lets it run.
+    #[cfg(target_arch = "wasm32")]
+    worker: CooperativeWorker,
+}
+
+/// A search request, that the worker has taken on.
+struct Job {
+    /// The board from the perspective of the side, that wants the move.
+    board: BitBoard,
+    /// The generation the request got tagged with.
+    generation: u64,
+    /// What the result is needed for, it decides on the search.
+    kind: RequestKind,
+}
+
+/// The searches of the worker, which answers the requests one after the other. The hints and the
+/// evaluations run on their own searches, so that they do not disturb the transposition tables of the
+/// computer moves.
+struct Worker {
+    ai: AlphaBeta,
+    hint_ai: AlphaBeta,
+    evaluation_ai: AlphaBeta,
+    /// The generation of the computer move searched, the progress listener needs to know it.
+    current_generation: Arc<AtomicU64>,
+}
+
+impl Worker {
+    /// Creates the searches with the indicated configuration and seed for the computer moves. The
+    /// progress of the computer moves gets sent over the channel.
+    fn new(config: SearchConfig, seed: u64, progress_sender: mpsc::Sender<AiProgress>) -> Worker {
+        let current_generation = Arc::new(AtomicU64::new(0));
+        let listener_generation = Arc::clone(&current_generation);
+        let mut ai = AlphaBeta::with_config(config);
+        ai.set_seed(seed);
+        ai.set_progress_listener(Box::new(move |stats| {
+            // The handler may be gone already, then nobody is interested in the progress.
+            let _ = progress_sender.send(AiProgress {
+                stats: *stats,
+                generation: listener_generation.load(Ordering::Relaxed),
+            });
+        }));
+        Worker {
+            ai,
+            hint_ai: AlphaBeta::with_config(SearchConfig {
+                max_depth: HINT_SEARCH_DEPTH,
+                move_time: Some(HINT_MOVE_TIME),
+                ..SearchConfig::default()
+            }),
+            evaluation_ai: AlphaBeta::with_config(SearchConfig {
+                max_depth: EVALUATION_SEARCH_DEPTH,
+                move_time: None,
+                ..SearchConfig::default()
+            }),
+            current_generation,
+        }
+    }
+
+    /// Takes the request on. A configuration is applied right away, for an analysis the search gets
+    /// prepared and the job is returned, that has to be searched.
+    fn accept(&mut self, request: AiRequest) -> Option<Job> {
+        match request {
+            AiRequest::Analyze {
+                board,
+                generation,
+                kind,
+                banned_columns,
+            } => {
+                if kind == RequestKind::Move {
+                    self.current_generation.store(generation, Ordering::Relaxed);
+                    self.ai.set_banned_root_columns(banned_columns);
+                }
+                Some(Job {
+                    board,
+                    generation,
+                    kind,
+                })
+            }
+            AiRequest::Configure(config) => {
+                self.ai.set_config(config);
+                None
+            }
+            AiRequest::Probe(answer) => {
+                // The prober may have given up already.
+                let _ = answer.send(self.ai.get_config());
+                None
+            }
+            AiRequest::Seed(seed) => {
+                self.ai.set_seed(seed);
+                None
+            }
+            AiRequest::NewGame => {
+                self.ai.clear_table();
+                self.hint_ai.clear_table();
+                self.evaluation_ai.clear_table();
+                None
+            }
+            #[cfg(test)]
+            AiRequest::Poison => panic!("The worker has been poisoned."),
+            #[cfg(test)]
+            AiRequest::Stall(duration) => {
+                std::thread::sleep(duration);
+                None
+            }
+        }
+    }
+
+    /// Gets the search, that answers the requests of the kind.
+    fn get_search(&mut self, kind: RequestKind) -> &mut AlphaBeta {
+        match kind {
+            RequestKind::Move => &mut self.ai,
+            RequestKind::Hint => &mut self.hint_ai,
+            RequestKind::Evaluation => &mut self.evaluation_ai,
+        }
+    }
+
+    /// Answers the requests, until the handler drops the channels.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run(mut self, requests: mpsc::Receiver<AiRequest>, results: mpsc::Sender<AiResult>) {
+        while let Ok(request) = requests.recv() {
+            let Some(job) = self.accept(request) else {
+                continue;
+            };
+            let search = self.get_search(job.kind);
+            let column = search.get_best_move(job.board);
+            // A search, that has not run, has no score.
+            let score = if column.is_ok() {
+                search.get_best_score()
+            } else {
+                0.0
+            };
+            let result = AiResult {
+                column,
+                score,
+                generation: job.generation,
+            };
+            if results.send(result).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// The worker on the web, where it runs on the main loop. It answers the requests in slices and keeps
+/// the search, that is running, in between.
+#[cfg(target_arch = "wasm32")]
+struct CooperativeWorker {
+    worker: Worker,
+    requests: mpsc::Receiver<AiRequest>,
+    results: mpsc::Sender<AiResult>,
+    /// The job, whose search has been started and not finished yet.
+    running_job: Option<Job>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl CooperativeWorker {
+    /// Searches for about [`NODES_PER_SLICE`] nodes. A search, that finishes early, leaves the rest of
+    /// the slice to the next request.
+    fn run_slice(&mut self) {
+        let mut node_budget = NODES_PER_SLICE;
+        while node_budget > 0 {
+            let Some(job) = self.running_job.as_ref() else {
+                let Ok(request) = self.requests.try_recv() else {
+                    return;
+                };
+                if let Some(job) = self.worker.accept(request) {
+                    match self
+                        .worker
+                        .get_search(job.kind)
+                        .start_search(job.board.clone())
+                    {
+                        Ok(()) => self.running_job = Some(job),
+                        Err(error) => {
+                            let _ = self.results.send(AiResult {
+                                column: Err(error),
+                                score: 0.0,
+                                generation: job.generation,
+                            });
+                        }
+                    }
+                }
+                continue;
+            };
+            let generation = job.generation;
+            let search = self.worker.get_search(job.kind);
+            let nodes_before = search.get_nodes_searched();
+            let column = search.continue_search(node_budget);
+            node_budget = node_budget.saturating_sub(search.get_nodes_searched() - nodes_before);
+            let Some(column) = column else {
+                return;
+            };
+            let result = AiResult {
+                column: Ok(column),
+                score: search.get_best_score(),
+                generation,
+            };
+            self.running_job = None;
+            // The handler owns the worker, so it is still listening.
+            let _ = self.results.send(result);
+        }
+    }
+}
+
+/// Spawns the worker, that computes the requests with the indicated configuration and seed for the
+/// computer moves. It gets a thread of its own, which ends, when the handler drops its channels. On the
+/// web the worker is handed over with the channels instead.
+fn spawn_worker(config: SearchConfig, seed: u64) -> io::Result<WorkerChannels> {
+    let (result_sender, result_receiver) = mpsc::channel::<AiResult>();
+    let (request_sender, request_receiver) = mpsc::channel::<AiRequest>();
+    let (progress_sender, progress_receiver) = mpsc::channel::<AiProgress>();
+    let worker = Worker::new(config, seed, progress_sender);
+
+    // Kick of a worker thread, that runs in the background.
+    #[cfg(not(target_arch = "wasm32"))]
+    std::thread::Builder::new()
+        .name("ai worker".to_string())
+        .spawn(move || worker.run(request_receiver, result_sender))?;
+
+    Ok(WorkerChannels {
+        receiver: result_receiver,
+        sender: request_sender,
+        progress_receiver,
+        #[cfg(target_arch = "wasm32")]
+        worker: CooperativeWorker {
+            worker,
+            requests: request_receiver,
+            results: result_sender,
+            running_job: None,
+        },
+    })
+}
 
 /// The handle struct is the entry point to the ai, where one can request
 /// things and can obtain the result.
 pub struct AiHandler {
-    receiver: mpsc::Receiver<u32>,
-    sender: mpsc::Sender<BitBoard>,
+    /// The channels to the current worker.
+    channels: WorkerChannels,
+    /// The configuration of the search for the computer moves, a new worker gets it as well.
+    config: SearchConfig,
+    /// The seed of the search for the computer moves, a new worker gets it as well.
+    seed: u64,
+    /// A new worker has been spawned, which has not been reported yet.
+    worker_restarted: bool,
+    /// The statistics of the latest finished iteration of the last computer move requested.
+    progress: Option<SearchStats>,
+    /// The generation of the last computer move requested, the progress belongs to it.
+    progress_generation: Option<u64>,
+    /// The generation handed out to the last request.
+    generation: u64,
+    /// The generation of the computer move we are waiting for.
+    pending_move: Option<u64>,
+    /// The moment the computer move we are waiting for has been requested.
+    move_requested_at: Option<Instant>,
+    /// The generation of the hint we are waiting for.
+    pending_hint: Option<u64>,
+    /// The computer move, that has arrived and not been picked up yet, or the error, why there is none.
+    move_result: Option<Result<u32, EngineError>>,
+    /// The score of the last computer move, that has arrived, from the perspective of the computer.
+    move_score: Option<f32>,
+    /// The hint, that has arrived and not been picked up yet.
+    hint_result: Option<u32>,
+    /// The plies of the positions, whose evaluations we are waiting for, by their generations.
+    pending_evaluations: HashMap<u64, usize>,
+    /// The evaluations with their plies, that have arrived and not been picked up yet.
+    evaluation_results: Vec<(usize, AiResult)>,
+    /// The results, that have arrived and not been taken for the recording yet, none if the results are
+    /// not recorded.
+    recorded_results: Option<Vec<AiResult>>,
+    /// The requests are not sent to the worker, the results get fed in instead.
+    scripted: bool,
+    /// The failure of the worker fed in, that has not been reported yet.
+    scripted_failure: Option<AiError>,
+}
+
+impl Default for AiHandler {
+    fn default() -> AiHandler {
+        AiHandler::new()
+    }
 }
 
 impl AiHandler {
     /// The constructor spawns a new thread for the ai calculation and keeps a channel pair.
     pub fn new() -> AiHandler {
-        let (result_sender, result_receiver) = mpsc::channel::<u32>();
-        let (request_sender, request_receiver) = mpsc::channel::<BitBoard>();
-
-        // Kick off worker thread.
-        // Kick of a worker thread, that runs in the background.
-        thread::spawn(move || {
-            let mut ai = AlphaBeta::new();
-            loop {
-                let local_board = request_receiver.recv().unwrap();
-                let result = ai.get_best_move(local_board);
-                let content = result_sender.send(result);
-                content.unwrap();
+        let config = SearchConfig::default();
+        AiHandler {
+            channels: spawn_worker(config, 0).expect("The ai worker thread could not be started."),
+            config,
+            seed: 0,
+            worker_restarted: false,
+            progress: None,
+            progress_generation: None,
+            generation: 0,
+            pending_move: None,
+            move_requested_at: None,
+            pending_hint: None,
+            move_result: None,
+            move_score: None,
+            hint_result: None,
+            pending_evaluations: HashMap::new(),
+            evaluation_results: Vec::new(),
+            recorded_results: None,
+            scripted: false,
+            scripted_failure: None,
+        }
+    }
+
+    /// Replaces the worker with a new one, the old one ends, once it notices, that nobody listens any
+    /// more. All pending requests are lost.
+    fn respawn_worker(&mut self) -> Result<(), AiError> {
+        log::warn!("Respawning the ai worker, the pending requests are lost");
+        self.cancel_pending_requests();
+        self.channels = spawn_worker(self.config, self.seed).map_err(|_| AiError::ChannelClosed)?;
+        self.worker_restarted = true;
+        Ok(())
+    }
+
+    /// Tags the request with a new generation and sends it over to the thread. If the worker is gone, a
+    /// new one gets spawned and the request is sent again.
+    fn send_request(
+        &mut self,
+        board: BitBoard,
+        kind: RequestKind,
+        banned_columns: u32,
+    ) -> Result<u64, AiError> {
+        self.generation += 1;
+        let request = AiRequest::Analyze {
+            board,
+            generation: self.generation,
+            kind,
+            banned_columns,
+        };
+        log::debug!("Sending {kind:?} request {}", self.generation);
+        if self.scripted {
+            return Ok(self.generation);
+        }
+        if let Err(mpsc::SendError(request)) = self.channels.sender.send(request) {
+            log::warn!("The ai worker did not take request {}", self.generation);
+            self.respawn_worker()?;
+            self.channels
+                .sender
+                .send(request)
+                .map_err(|_| AiError::ChannelClosed)?;
+        }
+        Ok(self.generation)
+    }
+
+    /// Gives the worker the time of the frame, this has to be called once per frame. On the web the
+    /// worker searches a slice of its requests in here and the time drives the clock of the searches.
+    /// Elsewhere the worker runs in its own thread and this does nothing.
+    pub fn run_worker(&mut self, frame_time: f32) {
+        advance_web_clock(frame_time);
+        #[cfg(target_arch = "wasm32")]
+        self.channels.worker.run_slice();
+    }
+
+    /// Switches the recording of the results on or off. The results, that arrive while it is on, can be
+    /// taken with [`AiHandler::take_recorded_results`].
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recorded_results = recording.then(Vec::new);
+    }
+
+    /// Takes the results, that have arrived since the last call, in the order they arrived.
+    pub fn take_recorded_results(&mut self) -> Vec<AiResult> {
+        self.recorded_results
+            .as_mut()
+            .map(mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Switches the scripted mode on or off. In the scripted mode the requests do not reach the worker,
+    /// instead the results get fed in with [`AiHandler::feed_result`]. As the requests get tagged with
+    /// the generations as usual, a recorded session gets the same results in the same places again.
+    pub fn set_scripted(&mut self, scripted: bool) {
+        self.scripted = scripted;
+    }
+
+    /// Hands a result over as if it came from the worker. It is dropped, if nobody waits for it.
+    pub fn feed_result(&mut self, result: AiResult) {
+        self.sort_result(result);
+    }
+
+    /// Lets the worker fail in the scripted mode, as if it had stopped or got stuck. The pending requests
+    /// are lost like with a real worker, the next look for a result returns the error.
+    pub fn feed_failure(&mut self, error: AiError) {
+        self.cancel_pending_requests();
+        self.worker_restarted = true;
+        self.scripted_failure = Some(error);
+    }
+
+    /// Sorts the result into the computer move, the hint or the evaluations. Results of requests we do not
+    /// wait for any more are dropped.
+    fn sort_result(&mut self, result: AiResult) {
+        log::debug!(
+            "Received result {}: column {:?}, score {:.3}",
+            result.generation,
+            result.column,
+            result.score
+        );
+        if let Some(recorded_results) = self.recorded_results.as_mut() {
+            recorded_results.push(result);
+        }
+        if self.pending_move == Some(result.generation) {
+            self.pending_move = None;
+            self.move_requested_at = None;
+            self.move_result = Some(result.column);
+            self.move_score = Some(result.score);
+        } else if self.pending_hint == Some(result.generation) {
+            self.pending_hint = None;
+            // There is no hint, when the game is over.
+            self.hint_result = result.column.ok();
+        } else if let Some(ply) = self.pending_evaluations.remove(&result.generation) {
+            // A failure is passed on as well, so that the request does not stay pending.
+            if let Err(error) = result.column {
+                log::warn!("The position of ply {ply} can not be evaluated: {error}");
             }
-        });
+            self.evaluation_results.push((ply, result));
+        }
+    }
 
-        AiHandler {
-            receiver: result_receiver,
-            sender: request_sender,
+    /// Checks and resets, if a new worker had to be spawned since the last call, so that this can be
+    /// reported.
+    pub fn take_worker_restarted(&mut self) -> bool {
+        mem::take(&mut self.worker_restarted)
+    }
+
+    /// Gets the configuration of the search for the computer moves.
+    pub fn get_config(&self) -> SearchConfig {
+        self.config
+    }
+
+    /// Changes the configuration of the search for the computer moves. It is used from the next
+    /// request on, a search, that is already running, keeps its configuration.
+    pub fn configure(&mut self, config: SearchConfig) -> Result<(), AiError> {
+        self.config = config;
+        if self
+            .channels
+            .sender
+            .send(AiRequest::Configure(config))
+            .is_err()
+        {
+            // The new worker starts with the configuration.
+            self.respawn_worker()?;
+        }
+        Ok(())
+    }
+
+    /// Asks the worker for the configuration, that its search for the computer moves runs with. The
+    /// requests sent before get taken on first, so this shows, that a configuration has arrived.
+    pub fn probe_worker_config(&mut self) -> Result<SearchConfig, AiError> {
+        let (answer_sender, answer_receiver) = mpsc::channel();
+        self.channels
+            .sender
+            .send(AiRequest::Probe(answer_sender))
+            .map_err(|_| AiError::ChannelClosed)?;
+        #[cfg(target_arch = "wasm32")]
+        self.channels.worker.run_slice();
+        answer_receiver
+            .recv_timeout(MOVE_TIMEOUT)
+            .map_err(|error| match error {
+                mpsc::RecvTimeoutError::Timeout => AiError::Timeout,
+                mpsc::RecvTimeoutError::Disconnected => AiError::WorkerDied,
+            })
+    }
+
+    /// Seeds the random generator of the search for the computer moves, which draws the moves of the
+    /// weaker difficulties. The scripted results do not need it.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        if self.scripted {
+            return;
+        }
+        // A worker, that is gone, gets replaced with the seed on the next request anyway.
+        if self.channels.sender.send(AiRequest::Seed(seed)).is_err() {
+            log::warn!("The ai worker did not take the seed");
+        }
+    }
+
+    /// Tells the worker, that a new game starts. The searches keep their transposition tables over the
+    /// moves of a game, the positions of the last game only take up space in them.
+    pub fn start_new_game(&mut self) {
+        if self.scripted {
+            return;
+        }
+        // A worker, that is gone, gets replaced with empty tables on the next request anyway.
+        if self.channels.sender.send(AiRequest::NewGame).is_err() {
+            log::warn!("The ai worker did not take the new game");
         }
     }
 
     /// Send a request over to the thread, as the board will be consumed by the
     /// channel, you will have to clone it upfront, if you want to keep it.
-    pub fn send_analysis_request(&self, board: BitBoard) {
-        self.sender
-            .send(board)
-            .expect("AiHandler failed to send analysis request");
+    pub fn send_analysis_request(&mut self, board: BitBoard) -> Result<(), AiError> {
+        self.send_restricted_analysis_request(board, 0)
+    }
+
+    /// Sends a request like [`AiHandler::send_analysis_request`], but the computer may not choose the
+    /// columns, whose bits are set. This is used for handicaps.
+    pub fn send_restricted_analysis_request(
+        &mut self,
+        board: BitBoard,
+        banned_columns: u32,
+    ) -> Result<(), AiError> {
+        self.move_result = None;
+        self.move_score = None;
+        self.progress = None;
+        self.pending_move = None;
+        self.pending_move = Some(self.send_request(board, RequestKind::Move, banned_columns)?);
+        // The worker can not get stuck on the web, where it runs on the main loop, and there is no clock.
+        if cfg!(not(target_arch = "wasm32")) {
+            self.move_requested_at = Some(Instant::now());
+        }
+        self.progress_generation = self.pending_move;
+        Ok(())
+    }
+
+    /// Requests a hint for the player, who is to move on the board. The board is handed over as it is
+    /// in the game, the perspective gets swapped here. A hint, that is still pending, gets replaced.
+    pub fn send_hint_request(&mut self, mut board: BitBoard) -> Result<(), AiError> {
+        board.swap_players();
+        self.hint_result = None;
+        self.pending_hint = None;
+        self.pending_hint = Some(self.send_request(board, RequestKind::Hint, 0)?);
+        Ok(())
+    }
+
+    /// Requests the evaluation of the position on the board, the result is tagged with the indicated
+    /// ply. The board is handed over as it is in the game, the perspective gets swapped here, if the
+    /// player is to move. Many evaluations can be pending at once, they get answered in order. Returns the
+    /// generation of the request, which the result carries.
+    pub fn send_evaluation_request(
+        &mut self,
+        mut board: BitBoard,
+        ply: usize,
+    ) -> Result<u64, AiError> {
+        if !board.side_to_move().is_computer() {
+            board.swap_players();
+        }
+        let generation = self.send_request(board, RequestKind::Evaluation, 0)?;
+        self.pending_evaluations.insert(generation, ply);
+        Ok(generation)
+    }
+
+    /// Drops all pending evaluations, their results will be ignored, when they arrive.
+    pub fn cancel_evaluations(&mut self) {
+        self.pending_evaluations.clear();
+        self.evaluation_results.clear();
+    }
+
+    /// Drops the pending hint, its result will be ignored, when it arrives.
+    pub fn cancel_hint(&mut self) {
+        self.pending_hint = None;
+        self.hint_result = None;
+    }
+
+    /// Drops all pending requests, their results will be ignored, when they arrive. This is used when
+    /// the game gets abandoned.
+    pub fn cancel_pending_requests(&mut self) {
+        self.pending_move = None;
+        self.move_requested_at = None;
+        self.move_result = None;
+        self.move_score = None;
+        self.progress = None;
+        self.progress_generation = None;
+        self.cancel_hint();
+        self.cancel_evaluations();
+    }
+
+    /// Checks if a computer move has been requested, that has not arrived yet.
+    pub fn is_move_pending(&self) -> bool {
+        self.pending_move.is_some()
+    }
+
+    /// Gets the generation of the computer move, that has been requested and not arrived yet. A result
+    /// fed in for it in the scripted mode has to carry this generation.
+    pub fn get_pending_move(&self) -> Option<u64> {
+        self.pending_move
+    }
+
+    /// Checks if a hint has been requested, that has not arrived yet.
+    pub fn is_hint_pending(&self) -> bool {
+        self.pending_hint.is_some()
+    }
+
+    /// Sorts the results, that have arrived, see [`AiHandler::sort_result`]. If the worker has stopped, a
+    /// new one gets spawned and the pending requests are reported as lost.
+    fn collect_results(&mut self) -> Result<(), AiError> {
+        if let Some(error) = self.scripted_failure.take() {
+            return Err(error);
+        }
+        loop {
+            match self.channels.receiver.try_recv() {
+                Ok(result) => self.sort_result(result),
+                Err(mpsc::TryRecvError::Empty) => return Ok(()),
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    log::error!("The ai worker has stopped");
+                    self.respawn_worker()?;
+                    return Err(AiError::WorkerDied);
+                }
+            }
+        }
     }
 
     /// Tries to get an answer from the thread, if there is still no available None
-    /// is returned.
-    pub fn try_get_computation_result(&self) -> Option<u32> {
-        self.receiver.try_recv().ok()
+    /// is returned. A computer move, that takes longer than [`MOVE_TIMEOUT`], is given up. A board, on
+    /// which the game is already over, returns the error of the engine.
+    pub fn try_get_computation_result(&mut self) -> Result<Option<u32>, AiError> {
+        self.collect_results()?;
+        // A scripted move arrives, when it is fed in, it can not time out.
+        if !self.scripted
+            && self
+                .move_requested_at
+                .is_some_and(|requested_at| requested_at.elapsed() > MOVE_TIMEOUT)
+        {
+            log::error!(
+                "The computer move {:?} did not arrive within {MOVE_TIMEOUT:?}",
+                self.pending_move
+            );
+            self.respawn_worker()?;
+            return Err(AiError::Timeout);
+        }
+        self.move_result
+            .take()
+            .transpose()
+            .map_err(|error| AiError::Engine { error })
+    }
+
+    /// Gets the score of the last computer move, that has arrived, from the perspective of the computer.
+    /// None is returned, if the move has not arrived yet.
+    pub fn get_last_move_score(&self) -> Option<f32> {
+        self.move_score
+    }
+
+    /// Gets the statistics of the latest finished iteration of the last computer move requested, the
+    /// score is from the perspective of the computer. The progress is kept after the move has arrived,
+    /// until the next move gets requested. None is returned, if no iteration has finished yet.
+    pub fn try_get_progress(&mut self) -> Option<SearchStats> {
+        while let Ok(progress) = self.channels.progress_receiver.try_recv() {
+            // Only the last move requested counts, everything older is outdated.
+            if Some(progress.generation) == self.progress_generation {
+                self.progress = Some(progress.stats);
+            }
+        }
+        self.progress
+    }
+
+    /// Tries to get the hint from the thread, if there is still no available None is returned.
+    pub fn try_get_hint_result(&mut self) -> Result<Option<u32>, AiError> {
+        self.collect_results()?;
+        Ok(self.hint_result.take())
+    }
+
+    /// Gets the evaluations, that have arrived since the last call, with their plies. The score is from
+    /// the perspective of the side to move in the position, the column is its best move or the error, why
+    /// the position could not be evaluated.
+    pub fn try_get_evaluation_results(&mut self) -> Result<Vec<(usize, AiResult)>, AiError> {
+        self.collect_results()?;
+        Ok(mem::take(&mut self.evaluation_results))
+    }
+
+    /// Checks if evaluations have been requested, that have not arrived yet.
+    pub fn is_evaluation_pending(&self) -> bool {
+        !self.pending_evaluations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets a handler, whose results get fed in by the test.
+    fn get_scripted_handler() -> AiHandler {
+        let mut handler = AiHandler::new();
+        handler.set_scripted(true);
+        handler
+    }
+
+    /// Gets a handler with a worker, that searches the computer moves quickly.
+    fn get_quick_handler() -> AiHandler {
+        let mut handler = AiHandler::new();
+        handler
+            .configure(SearchConfig {
+                max_depth: 4,
+                move_time: None,
+                ..SearchConfig::default()
+            })
+            .unwrap();
+        handler
+    }
+
+    /// Polls the computer move, until it arrives or the handler reports an error.
+    fn wait_for_move(handler: &mut AiHandler) -> Result<u32, AiError> {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while Instant::now() < deadline {
+            if let Some(column) = handler.try_get_computation_result()? {
+                return Ok(column);
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("The computer move did not arrive.");
+    }
+
+    /// Gets the result of the request with the generation.
+    fn get_result(column: u32, score: f32, generation: u64) -> AiResult {
+        AiResult {
+            column: Ok(column),
+            score,
+            generation,
+        }
+    }
+
+    #[test]
+    fn test_evaluations_are_tagged_with_their_plies() {
+        let mut handler = get_scripted_handler();
+        let mut board = BitBoard::new();
+        let mut generations = Vec::new();
+        for ply in 0..3 {
+            generations.push(handler.send_evaluation_request(board.clone(), ply).unwrap());
+            board.play(3).unwrap();
+        }
+        assert_eq!(generations, [1, 2, 3]);
+        assert!(handler.is_evaluation_pending());
+
+        // The results arrive in any order and find their plies through the generations.
+        for (ply, &generation) in generations.iter().enumerate().rev() {
+            handler.feed_result(get_result(ply as u32, ply as f32 / 10.0, generation));
+        }
+        let results = handler.try_get_evaluation_results().unwrap();
+        let plies: Vec<(usize, u32)> = results
+            .iter()
+            .map(|(ply, result)| (*ply, result.column.unwrap()))
+            .collect();
+        assert_eq!(plies, [(2, 2), (1, 1), (0, 0)]);
+        assert!(!handler.is_evaluation_pending());
+        assert!(handler.try_get_evaluation_results().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_results_are_sorted_by_their_generations() {
+        let mut handler = get_scripted_handler();
+        let board = BitBoard::new();
+        handler.send_analysis_request(board.clone()).unwrap();
+        let move_generation = handler.get_pending_move().unwrap();
+        handler.send_hint_request(board.clone()).unwrap();
+        let evaluation_generation = handler.send_evaluation_request(board, 0).unwrap();
+        let hint_generation = handler.pending_hint.unwrap();
+        assert_ne!(move_generation, hint_generation);
+
+        // A result nobody waits for is dropped.
+        handler.feed_result(get_result(6, 0.0, evaluation_generation + 1));
+        handler.feed_result(get_result(1, 0.2, hint_generation));
+        assert_eq!(handler.try_get_computation_result(), Ok(None));
+        assert_eq!(handler.try_get_hint_result(), Ok(Some(1)));
+
+        handler.feed_result(get_result(2, 0.5, evaluation_generation));
+        handler.feed_result(get_result(3, 0.7, move_generation));
+        assert_eq!(handler.try_get_computation_result(), Ok(Some(3)));
+        assert_eq!(handler.get_last_move_score(), Some(0.7));
+        let results = handler.try_get_evaluation_results().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 0);
+        assert_eq!(results[0].1.column, Ok(2));
+    }
+
+    #[test]
+    fn test_cancelled_evaluations_are_dropped() {
+        let mut handler = get_scripted_handler();
+        let generation = handler.send_evaluation_request(BitBoard::new(), 0).unwrap();
+        handler.cancel_evaluations();
+        assert!(!handler.is_evaluation_pending());
+        handler.feed_result(get_result(3, 0.0, generation));
+        assert!(handler.try_get_evaluation_results().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_failed_evaluations_are_passed_on() {
+        let mut handler = get_scripted_handler();
+        let generation = handler.send_evaluation_request(BitBoard::new(), 4).unwrap();
+        handler.feed_result(AiResult {
+            column: Err(EngineError::GameOver),
+            score: 0.0,
+            generation,
+        });
+        assert!(!handler.is_evaluation_pending());
+        let results = handler.try_get_evaluation_results().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, 4);
+        assert_eq!(results[0].1.column, Err(EngineError::GameOver));
+    }
+
+    /// Gets the statistics of an iteration of the indicated depth.
+    fn get_stats(depth: u32) -> SearchStats {
+        SearchStats {
+            depth,
+            score: depth as f32 / 10.0,
+            nodes: 100 * depth as u64,
+            elapsed_time: 0.01,
+            table_entries: 0,
+            table_hits: 0,
+        }
+    }
+
+    #[test]
+    fn test_progress_never_outlives_its_generation() {
+        let mut handler = get_scripted_handler();
+        let (progress_sender, progress_receiver) = mpsc::channel();
+        handler.channels.progress_receiver = progress_receiver;
+        let send_progress = |depth: u32, generation: u64| {
+            progress_sender
+                .send(AiProgress {
+                    stats: get_stats(depth),
+                    generation,
+                })
+                .unwrap();
+        };
+
+        handler.send_analysis_request(BitBoard::new()).unwrap();
+        let first = handler.get_pending_move().unwrap();
+        assert_eq!(handler.try_get_progress(), None);
+        send_progress(1, first);
+        send_progress(2, first);
+        // Progress of another request is no progress of the computer move.
+        send_progress(7, first + 1);
+        assert_eq!(handler.try_get_progress(), Some(get_stats(2)));
+
+        // The progress is kept with the move, until the next move is requested.
+        handler.feed_result(get_result(3, 0.2, first));
+        assert_eq!(handler.try_get_computation_result(), Ok(Some(3)));
+        assert_eq!(handler.try_get_progress(), Some(get_stats(2)));
+        handler.send_analysis_request(BitBoard::new()).unwrap();
+        let second = handler.get_pending_move().unwrap();
+        assert_eq!(handler.try_get_progress(), None);
+        send_progress(3, first);
+        assert_eq!(handler.try_get_progress(), None);
+        send_progress(1, second);
+        assert_eq!(handler.try_get_progress(), Some(get_stats(1)));
+
+        handler.cancel_pending_requests();
+        assert_eq!(handler.try_get_progress(), None);
+        send_progress(2, second);
+        assert_eq!(handler.try_get_progress(), None);
+    }
+
+    #[test]
+    fn test_progress_streams_the_iterations() {
+        let mut handler = get_quick_handler();
+        handler.send_analysis_request(BitBoard::new()).unwrap();
+        assert_eq!(wait_for_move(&mut handler), Ok(3));
+        // The last iteration has been sent before the move.
+        let stats = handler.try_get_progress().unwrap();
+        assert_eq!(stats.depth, 4);
+        assert_eq!(Some(stats.score), handler.get_last_move_score());
+    }
+
+    #[test]
+    fn test_configuration_reaches_the_worker() {
+        let mut handler = AiHandler::new();
+        assert_eq!(handler.probe_worker_config(), Ok(handler.get_config()));
+        let config = SearchConfig {
+            max_depth: 5,
+            move_time: Some(0.5),
+            ..SearchConfig::default()
+        };
+        handler.configure(config).unwrap();
+        assert_eq!(handler.probe_worker_config(), Ok(config));
+
+        // A new worker starts with the configuration of the old one.
+        handler.channels.sender.send(AiRequest::Poison).unwrap();
+        assert_eq!(wait_for_move(&mut handler), Err(AiError::WorkerDied));
+        assert_eq!(handler.probe_worker_config(), Ok(config));
+    }
+
+    #[test]
+    fn test_dead_worker_gets_respawned() {
+        let mut handler = get_quick_handler();
+        handler.channels.sender.send(AiRequest::Poison).unwrap();
+        assert_eq!(wait_for_move(&mut handler), Err(AiError::WorkerDied));
+        assert!(handler.take_worker_restarted());
+
+        // The new worker answers with the configuration of the old one.
+        handler.send_analysis_request(BitBoard::new()).unwrap();
+        assert_eq!(wait_for_move(&mut handler), Ok(3));
+        assert!(!handler.take_worker_restarted());
+    }
+
+    #[test]
+    fn test_stuck_worker_times_out() {
+        let mut handler = get_quick_handler();
+        handler
+            .channels
+            .sender
+            .send(AiRequest::Stall(Duration::from_secs(2)))
+            .unwrap();
+        handler.send_analysis_request(BitBoard::new()).unwrap();
+        assert_eq!(handler.try_get_computation_result(), Ok(None));
+
+        // The move has been requested long ago, the worker is still sleeping.
+        handler.move_requested_at = Instant::now().checked_sub(MOVE_TIMEOUT);
+        assert_eq!(handler.try_get_computation_result(), Err(AiError::Timeout));
+        assert!(!handler.is_move_pending());
+        assert!(handler.take_worker_restarted());
+
+        handler.send_analysis_request(BitBoard::new()).unwrap();
+        assert_eq!(wait_for_move(&mut handler), Ok(3));
+    }
+
+    #[test]
+    fn test_finished_game_has_no_move() {
+        let mut handler = get_quick_handler();
+        let won = BitBoard::from_move_string("1212121").unwrap();
+        let full =
+            BitBoard::from_move_string("547125662261271266215743771576315353334444").unwrap();
+        for board in [won, full] {
+            handler.send_analysis_request(board).unwrap();
+            assert_eq!(
+                wait_for_move(&mut handler),
+                Err(AiError::Engine {
+                    error: EngineError::GameOver
+                })
+            );
+            assert!(!handler.is_move_pending());
+        }
+        // The worker is still there for the next game.
+        handler.send_analysis_request(BitBoard::new()).unwrap();
+        assert_eq!(wait_for_move(&mut handler), Ok(3));
+        assert!(!handler.take_worker_restarted());
     }
 }
@@ -4,16 +4,33 @@
 //! The transposition table is enhanced by a canonical board coding and a coding that
 //! accounts for symmetry.
 
-use crate::board_logic::bit_board::{BitBoard, SymmetryIndependentPosition};
-use crate::board_logic::bit_board_coding::BOARD_WIDTH;
-use crate::board_logic::bit_board_coding::{FULL_BOARD_MASK, check_for_winning};
-use crate::board_logic::heuristic::compute_heuristics;
-use crate::debug_check_board_coordinates;
+use crate::board_logic::bit_board::{GenericBitBoard, RuleSet};
+use crate::board_logic::bit_board_coding::{BoardGeometry, MAX_BOARD_WIDTH, StandardGeometry};
+use crate::board_logic::heuristic::{
+    HeuristicWeights, compute_heuristics, compute_heuristics_overlooking,
+};
+use crate::board_logic::time_manager::{Clock, SystemClock, TimeManager};
+use crate::selfplay::SplitMix64;
 use std::collections::HashMap;
+use std::{fmt, mem};
 
-/// The search depth we want to apply.
+/// The default maximum search depth the iterative deepening goes to.
 const SEARCH_DEPTH: u32 = 15;
 
+/// The default base time in seconds we want to spend on a move.
+const BASE_MOVE_TIME: f32 = 1.5;
+
+/// The amount of entries of the transposition table, at which the entries of the older searches get
+/// evicted, when the next search starts or the running one fills the table.
+const MAX_TABLE_ENTRIES: usize = 1 << 20;
+
+/// The seed of the random generator, that draws the moves and the overlooked squares, until
+/// [`GenericAlphaBeta::set_seed`] sets another one.
+const DEFAULT_SEED: u64 = 0x5EED;
+
+/// The amount of nodes after which we ask the time manager again, if we ran out of time.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
 /// We clamp values to the region of 1: guaranteed winn to -1: guaranteed loss.
 const MAX_SCORE: f32 = 1.0;
 
@@ -27,28 +44,337 @@ const DISCOUNT_FACTOR: f32 = 0.99999;
 
 /// The region we want to clamp the heuristics against, that it
 /// can never dominate even overdiscounted win / loss.
-const CLAMP_GUARD_HEURISTIC: f32 = 0.97;
+pub const CLAMP_GUARD_HEURISTIC: f32 = 0.97;
+
+/// Gets the amount of plies until the game ends for the score of a proven win or loss, including the
+/// move to be made. A win or loss in k plies has been discounted k-1 times.
+fn get_proven_distance(score: f32) -> u32 {
+    (score.abs().ln() / DISCOUNT_FACTOR.ln()).round() as u32 + 1
+}
+
+/// The errors, that keep the search from finding a move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EngineError {
+    /// The game on the board is already over, one of the sides has won or the board is full.
+    GameOver,
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::GameOver => write!(f, "the game is already over"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+/// The ways the wins on the spot can force the move at the root, so that little or nothing has to be
+/// searched.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ForcedMove {
+    /// The player to move wins on the spot, the win is played without a search.
+    Win,
+    /// The opponent threatens to win on one square, only the move blocking it gets searched.
+    Block,
+    /// The opponent threatens to win on two squares, one can not block both. The block with the best
+    /// heuristics is played without a search.
+    Lost,
+}
+
+/// The configuration of the search, this is what makes the difference between engines of different strength.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchConfig {
+    /// The maximum depth the iterative deepening goes to.
+    pub max_depth: u32,
+    /// The base time in seconds the time manager allots for a move. If this is None, every
+    /// iteration up to the maximum depth is searched.
+    pub move_time: Option<f32>,
+    /// The weights of the heuristic evaluation.
+    pub weights: HeuristicWeights,
+    /// The temperature of the choice among the moves. At zero the best move is played, above it the
+    /// move gets drawn with a softmax over the scores of all moves, the higher the temperature, the more
+    /// often a worse move gets played. Moves, that lose for sure, are never drawn, unless all do.
+    pub temperature: f32,
+    /// The fraction of the squares, on which the heuristics overlook the threats of the opponent. The
+    /// squares get drawn anew for every search, the search itself still sees every win on the board.
+    pub threat_blindness: f32,
+}
+
+impl Default for SearchConfig {
+    fn default() -> SearchConfig {
+        SearchConfig {
+            max_depth: SEARCH_DEPTH,
+            move_time: Some(BASE_MOVE_TIME),
+            weights: HeuristicWeights::default(),
+            temperature: 0.0,
+            threat_blindness: 0.0,
+        }
+    }
+}
+
+/// The statistics of a finished iteration of the iterative deepening, as they get handed to the
+/// progress listener.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchStats {
+    /// The depth of the iteration.
+    pub depth: u32,
+    /// The score of the best move from the perspective of the player to move.
+    pub score: f32,
+    /// The nodes visited since the start of the search.
+    pub nodes: u64,
+    /// The time in seconds passed since the start of the search.
+    pub elapsed_time: f32,
+    /// The positions in the transposition table, which is kept over the moves of a game. The table grows
+    /// during a search as needed, the entries of older searches get evicted, when the next search starts.
+    pub table_entries: usize,
+    /// The positions, whose value has been taken from the transposition table instead of being searched.
+    pub table_hits: u64,
+}
+
+impl SearchStats {
+    /// Gets the nodes visited per second, zero if no measurable time has passed.
+    pub fn get_nodes_per_second(&self) -> f64 {
+        if self.elapsed_time > 0.0 {
+            self.nodes as f64 / self.elapsed_time as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Gets the table hits per node visited, zero if no node has been visited. The moves, whose value
+    /// is known from the table, are not visited at all, so this is a rate and not a share.
+    pub fn get_table_hit_rate(&self) -> f64 {
+        if self.nodes > 0 {
+            self.table_hits as f64 / self.nodes as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The outcome of a game with perfect play seen from the player to move.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameOutcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// The result of solving a position exactly.
+#[derive(Clone, Copy, Debug)]
+pub struct SolveResult {
+    /// The outcome with perfect play of both sides.
+    pub outcome: GameOutcome,
+    /// The amount of plies until the game ends including the move to be made.
+    pub distance: u32,
+    /// The move that leads to the outcome.
+    pub best_move: u32,
+    /// The raw score as the search has returned it.
+    pub score: f32,
+}
 
-/// Contains a bit-board and two hashmaps. One for the current move and one recycled
-/// from the previous one.
-pub struct AlphaBeta {
+/// Contains a bit-board and the transposition table, which is kept for the whole game. The positions a
+/// search has analyzed are reached again two plies later, so the next search finds their values there.
+/// The geometry decides on the board dimensions, the search for the standard board is [`AlphaBeta`].
+pub struct GenericAlphaBeta<G: BoardGeometry> {
     /// The bit board we play with.
-    bit_board: BitBoard,
-    /// The hash map of the current generation.
-    hash_map: HashMap<SymmetryIndependentPosition, f32>,
-    /// The hash map of the previous move / generation. It may not be used any more for position
-    /// look up but for heuristical evaluation in move ordering.
-    hash_map_old: HashMap<SymmetryIndependentPosition, f32>,
+    bit_board: GenericBitBoard<G>,
+    /// The transposition table, it is kept over the searches, until a new game starts.
+    hash_map: HashMap<G::Key, TranspositionEntry>,
+    /// The age of the running search, every search bumps it. The entries carry the age of the search,
+    /// that has stored them, so that the old ones can be evicted first.
+    age: u8,
+    /// The amount of entries, at which the transposition table is full. This is [`MAX_TABLE_ENTRIES`],
+    /// the tests lower it to fill the table quickly.
+    max_table_entries: usize,
+    /// Gets set, when the running search has filled the table and the old entries have been evicted. A
+    /// search, that fills the table on its own, does not scan it again for every entry it stores.
+    is_table_evicted: bool,
+    /// The number of nodes visited in the last search, useful for profiling the pruning.
+    nodes_searched: u64,
+    /// The positions of the last search, whose value has been taken from the transposition table.
+    table_hits: u64,
+    /// The configuration we search with.
+    config: SearchConfig,
+    /// The depth of the currently running iteration of the iterative deepening.
+    max_depth: u32,
+    /// Decides how long we may search on a move.
+    time_manager: TimeManager,
+    /// Gets set when the time manager ran out of time, the running iteration is invalid then.
+    search_aborted: bool,
+    /// Gets set together with the abort, when the slice of a search in slices is used up. The running
+    /// iteration is invalid as well, but it gets searched again in the next slice.
+    search_paused: bool,
+    /// The amount of nodes, at which the running slice of [`GenericAlphaBeta::continue_search`] pauses.
+    slice_end: u64,
+    /// Indicates if the time manager may abort the search. This is not the case for analysis.
+    is_time_limited: bool,
+    /// The depth of the last iteration of [`GenericAlphaBeta::get_best_move`], that has finished.
+    completed_depth: u32,
+    /// The score of the best move in the last finished iteration.
+    best_score: f32,
+    /// The best move of the last finished iteration of the running search.
+    best_move: Option<u32>,
+    /// The amount of entries of the transposition table in the last finished iteration.
+    table_entries: usize,
+    /// Gets informed about the depth and the score of every finished iteration of the iterative deepening.
+    progress_listener: Option<ProgressListener>,
+    /// A bit per column, that may not be played by [`GenericAlphaBeta::get_best_move`].
+    banned_root_columns: u32,
+    /// The cells of the banned columns while [`GenericAlphaBeta::get_best_move`] runs, the moves into
+    /// them are skipped at the root.
+    root_ban_mask: u64,
+    /// The cells of the columns right of the center, while the search runs on a root, that is its own
+    /// mirror image. Their moves are as good as the mirrored ones, so they are skipped at the root.
+    root_mirror_mask: u64,
+    /// How the move of the last search has been forced, none if it had to be searched as usual.
+    forced_move: Option<ForcedMove>,
+    /// Draws the moves with the temperature of the configuration and the overlooked squares.
+    random: SplitMix64,
+    /// The squares, on which the heuristics overlook the threats of the opponent of the root in the
+    /// running search.
+    overlooked_squares: u64,
+    /// Leaves the moves in the order of the columns, so that the tests can measure, what the move
+    /// ordering saves.
+    #[cfg(test)]
+    is_ordering_disabled: bool,
+    /// Searches both halves of a symmetric root, so that the tests can measure, what skipping the
+    /// mirrored half saves.
+    #[cfg(test)]
+    is_mirroring_disabled: bool,
+}
+
+/// A function, that gets called with the statistics of every finished iteration.
+pub type ProgressListener = Box<dyn FnMut(&SearchStats) + Send>;
+
+/// The search for the standard 7x6 game.
+pub type AlphaBeta = GenericAlphaBeta<StandardGeometry>;
+
+/// The evaluation of one column as it is handed out for analysis purposes like hints.
+#[derive(Clone, Copy, Debug)]
+pub struct ColumnEval {
+    /// The column that has been evaluated.
+    pub column: u32,
+    /// The score from the perspective of the player to move. Meaningless if the move is illegal.
+    pub score: f32,
+    /// Indicates if a stone can be dropped into the column at all.
+    pub is_legal: bool,
+}
+
+/// The search is fail-soft, so a returned value may lie outside the alpha beta window. In that case it
+/// is only a bound of the real value. This indicates how a stored value relates to the real value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Bound {
+    /// The value is the real value of the position.
+    Exact,
+    /// The real value is at least as large as the stored one, we had a beta cutoff.
+    Lower,
+    /// The real value is at most as large as the stored one, no move exceeded alpha.
+    Upper,
+}
+
+/// An entry of the transposition table. The value is relative to the node, a win in k plies from
+/// here is stored as the k-1 times discounted maximum score, no matter at which ply the node has been
+/// reached. The discounting of the way from the root is applied by the callers as the value bubbles up.
+/// What does depend on the ply is the horizon of the search, so we store the remaining depth with it.
+#[derive(Clone, Copy, Debug)]
+struct TranspositionEntry {
+    /// The value of the position from the perspective of the player to move.
+    value: f32,
+    /// How the value relates to the real value.
+    bound: Bound,
+    /// The remaining search depth the value has been computed with.
+    draft: u32,
+    /// The age of the search, that has stored the entry.
+    age: u8,
+}
+
+impl TranspositionEntry {
+    /// Checks if the entry has been searched at least as deep as required. Proven wins and losses
+    /// are valid for any depth, as a deeper search can not find a faster win either. This does not hold
+    /// for an upper bound on a win or a lower bound on a loss, a deeper search may find a faster win
+    /// respectively a faster loss beyond the horizon of the shallower one.
+    fn is_deep_enough(&self, required_draft: u32) -> bool {
+        let is_proven = match self.bound {
+            Bound::Exact => self.value.abs() > CLAMP_GUARD_HEURISTIC,
+            Bound::Lower => self.value > CLAMP_GUARD_HEURISTIC,
+            Bound::Upper => self.value < -CLAMP_GUARD_HEURISTIC,
+        };
+        self.draft >= required_draft || is_proven
+    }
 }
 
 /// The working list are the elements of what we need to do.
+#[derive(Clone, Copy, Default)]
 struct WorkingListEntry {
     /// The coded move is the bit set at where the stone needs to go.
     coded_move: u64,
-    /// The is the slot index that has been chosen.
+    /// The is the slot index that has been chosen. Pops of the Pop Out rules come after the columns.
     slot: u32,
     /// The initial estimate move the move value, to make the choice on where to go.
     evaluation: f32,
+    /// The distance of the column from the center, it decides between moves of the same evaluation.
+    center_distance: u32,
+}
+
+/// The maximum amount of moves of a node, a drop and a pop per column.
+const MAX_MOVES: usize = 2 * MAX_BOARD_WIDTH as usize;
+
+/// The working list of one node. As there are at most two moves per column, this is a fixed size
+/// array on the stack plus a length. This avoids allocating a vector for every node visited.
+struct WorkingList {
+    entries: [WorkingListEntry; MAX_MOVES],
+    length: usize,
+}
+
+impl WorkingList {
+    fn new() -> WorkingList {
+        WorkingList {
+            entries: [WorkingListEntry::default(); MAX_MOVES],
+            length: 0,
+        }
+    }
+
+    /// Appends an entry to the list.
+    fn push(&mut self, entry: WorkingListEntry) {
+        debug_assert!(
+            self.length < self.entries.len(),
+            "There can not be more than two moves per column."
+        );
+        self.entries[self.length] = entry;
+        self.length += 1;
+    }
+
+    /// Sorts the list by evaluation in descending order, the moves closer to the center first on the
+    /// same evaluation. The sort is stable and does not allocate for lists of this size.
+    fn sort_descending(&mut self) {
+        self.entries[..self.length].sort_by(|first, second| {
+            second
+                .evaluation
+                .total_cmp(&first.evaluation)
+                .then(first.center_distance.cmp(&second.center_distance))
+        });
+    }
+
+    /// Sorts the list by the slots, as if there was no move ordering at all.
+    #[cfg(test)]
+    fn sort_by_slot(&mut self) {
+        self.entries[..self.length].sort_by_key(|entry| entry.slot);
+    }
+
+    /// Gets the filled part of the list.
+    fn as_slice(&self) -> &[WorkingListEntry] {
+        &self.entries[..self.length]
+    }
+}
+
+/// What the presorting knows about a move, that does not end the game right away.
+enum MoveValue {
+    /// The score of the move is known from the transposition table.
+    Exact(f32),
+    /// The move needs to be searched, the estimate is used for the ordering.
+    Estimate(f32),
 }
 
 /// A result we get for the presorting. The presort result is used for
@@ -61,82 +387,308 @@ struct PresortResult {
     pub best_move: Option<u32>,
     /// The list with the remainder we still have to process. Contains the coded board,
     /// the move index and the evaluation. The position is to avoid recalculating the has structs.
-    pub working_list: Vec<WorkingListEntry>,
+    pub working_list: WorkingList,
+}
+
+impl<G: BoardGeometry> Default for GenericAlphaBeta<G> {
+    fn default() -> GenericAlphaBeta<G> {
+        GenericAlphaBeta::new()
+    }
 }
 
-impl AlphaBeta {
+impl<G: BoardGeometry> GenericAlphaBeta<G> {
     /// The bit board is handed over intentionally with a move situations.
-    pub fn new() -> AlphaBeta {
-        AlphaBeta {
-            bit_board: BitBoard::new(),
+    pub fn new() -> GenericAlphaBeta<G> {
+        GenericAlphaBeta::with_config(SearchConfig::default())
+    }
+
+    /// Creates the search with a specific configuration.
+    pub fn with_config(config: SearchConfig) -> GenericAlphaBeta<G> {
+        GenericAlphaBeta::with_clock(config, Box::new(SystemClock::new()))
+    }
+
+    /// Creates the search with a specific configuration and the clock the time manager runs on,
+    /// which may for example be a fake clock.
+    pub fn with_clock(config: SearchConfig, clock: Box<dyn Clock>) -> GenericAlphaBeta<G> {
+        let move_time = config.move_time.unwrap_or(f32::INFINITY);
+        GenericAlphaBeta {
+            bit_board: GenericBitBoard::new(),
             hash_map: HashMap::new(),
-            hash_map_old: HashMap::new(),
+            age: 0,
+            max_table_entries: MAX_TABLE_ENTRIES,
+            is_table_evicted: false,
+            nodes_searched: 0,
+            table_hits: 0,
+            config,
+            max_depth: config.max_depth,
+            time_manager: TimeManager::new(clock, move_time),
+            search_aborted: false,
+            search_paused: false,
+            slice_end: u64::MAX,
+            is_time_limited: true,
+            completed_depth: 0,
+            best_score: 0.0,
+            best_move: None,
+            table_entries: 0,
+            progress_listener: None,
+            banned_root_columns: 0,
+            root_ban_mask: 0,
+            root_mirror_mask: 0,
+            forced_move: None,
+            random: SplitMix64::new(DEFAULT_SEED),
+            overlooked_squares: 0,
+            #[cfg(test)]
+            is_ordering_disabled: false,
+            #[cfg(test)]
+            is_mirroring_disabled: false,
+        }
+    }
+
+    /// Sets the function, that gets informed about every finished iteration of
+    /// [`GenericAlphaBeta::get_best_move`]. This way a long search can show intermediate results.
+    pub fn set_progress_listener(&mut self, listener: ProgressListener) {
+        self.progress_listener = Some(listener);
+    }
+
+    /// Seeds the random generator, that draws the moves with the temperature of the configuration and the
+    /// squares, on which the threats get overlooked. With the same seed the same moves get drawn.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random = SplitMix64::new(seed);
+    }
+
+    /// Bans the columns, whose bits are set, for the move returned by the following calls of
+    /// [`GenericAlphaBeta::get_best_move`]. Only the move itself is restricted, deeper in the search both
+    /// sides may play every column. If every legal column is banned, the ban gets ignored.
+    pub fn set_banned_root_columns(&mut self, columns: u32) {
+        self.banned_root_columns = columns;
+    }
+
+    /// Gets the cells of the banned columns, that are used at the root. This is nothing, if the ban would
+    /// leave no legal move.
+    fn get_root_ban_mask(&self) -> u64 {
+        let mask = (0..G::WIDTH)
+            .filter(|column| self.banned_root_columns & (1 << column) != 0)
+            .fold(0, |mask, column| mask | G::get_column_mask(column));
+        let has_allowed_move = self
+            .bit_board
+            .get_all_possible_moves()
+            .any(|(coded_move, _)| coded_move & mask == 0);
+        if has_allowed_move { mask } else { 0 }
+    }
+
+    /// Checks the root for a move, that is forced by the wins on the spot, and gets it with its column.
+    /// The banned columns stay banned, a threat in one of them can not be blocked. Under the Pop Out
+    /// rules a pop may still remove a threat, so they are always searched.
+    fn find_forced_move(&self) -> Option<(ForcedMove, u32)> {
+        if self.bit_board.get_rule_set() == RuleSet::PopOut {
+            return None;
+        }
+        let occupied = self.bit_board.own_stones | self.bit_board.opponent_stones;
+        let wins = G::get_playable_winning_moves(self.bit_board.own_stones, occupied)
+            & !self.root_ban_mask;
+        if wins != 0 {
+            return Some((ForcedMove::Win, wins.trailing_zeros() % G::STRIDE));
         }
+        let threats = G::get_playable_winning_moves(self.bit_board.opponent_stones, occupied);
+        let blocks = threats & !self.root_ban_mask;
+        if threats.count_ones() == 1 && blocks != 0 {
+            return Some((ForcedMove::Block, blocks.trailing_zeros() % G::STRIDE));
+        }
+        if threats == 0 {
+            return None;
+        }
+        // The game is lost anyway, if no threat can be blocked any move will do.
+        let candidates = if blocks != 0 {
+            blocks
+        } else {
+            G::get_playable_squares(occupied) & !self.root_ban_mask
+        };
+        let mut test_board = self.bit_board.clone();
+        let best_candidate = self
+            .bit_board
+            .get_all_possible_moves()
+            .filter(|&(coded_move, _)| coded_move & candidates != 0)
+            .map(|(coded_move, column)| {
+                test_board.own_stones |= coded_move;
+                let evaluation = self.compute_heuristics(&test_board, 0);
+                test_board.own_stones ^= coded_move;
+                (column, evaluation)
+            })
+            .max_by(|first, second| first.1.total_cmp(&second.1));
+        best_candidate.map(|(column, _)| (ForcedMove::Lost, column))
+    }
+
+    /// Applies the forced move to the search, that starts. A win or a loss is decided right away and
+    /// does not touch the transposition table. A block restricts the root to the blocking column, as the
+    /// root is not stored with banned columns, the table only gets the real values below it.
+    fn apply_forced_move(&mut self, forced_move: ForcedMove, column: u32) {
+        self.forced_move = Some(forced_move);
+        match forced_move {
+            ForcedMove::Win => {
+                self.best_move = Some(column);
+                self.best_score = MAX_SCORE;
+                self.completed_depth = 1;
+            }
+            ForcedMove::Block => {
+                self.root_ban_mask = G::FULL_BOARD_MASK & !G::get_column_mask(column);
+            }
+            ForcedMove::Lost => {
+                // The opponent wins with the next move.
+                self.best_move = Some(column);
+                self.best_score = -MAX_SCORE * DISCOUNT_FACTOR;
+                self.completed_depth = 2;
+            }
+        }
+    }
+
+    /// Gets the cells of the columns right of the center, if the board and the banned columns are their
+    /// own mirror image, otherwise nothing. Below the root the transposition table takes care of the
+    /// mirrored positions.
+    fn get_root_mirror_mask(&self) -> u64 {
+        let is_symmetric = G::flip_board(self.bit_board.own_stones) == self.bit_board.own_stones
+            && G::flip_board(self.bit_board.opponent_stones) == self.bit_board.opponent_stones
+            && G::flip_board(self.root_ban_mask) == self.root_ban_mask;
+        #[cfg(test)]
+        let is_symmetric = is_symmetric && !self.is_mirroring_disabled;
+        if !is_symmetric {
+            return 0;
+        }
+        (G::WIDTH.div_ceil(2)..G::WIDTH).fold(0, |mask, column| mask | G::get_column_mask(column))
+    }
+
+    /// Gets the configuration of the following searches.
+    pub fn get_config(&self) -> SearchConfig {
+        self.config
+    }
+
+    /// Replaces the configuration for the following searches. The transposition table is kept, unless the
+    /// weights change, then the values stored in it are no longer the ones the search would find.
+    pub fn set_config(&mut self, config: SearchConfig) {
+        if config.weights != self.config.weights {
+            self.clear_table();
+        }
+        self.config = config;
+        self.time_manager
+            .set_base_time(config.move_time.unwrap_or(f32::INFINITY));
     }
 
     /// Generates a vector of (coded Move, chosen slot, heuristic evaluation) and returns it
     /// sorted by heuristic value in descending order. This can be used to scan the options in an efficient way for
-    /// Alpha-Beta.
-    fn get_pre_sorted_move_list(&mut self) -> PresortResult {
+    /// Alpha-Beta. The depth is the one of the node, the moves are generated for.
+    fn get_pre_sorted_move_list(&mut self, depth: u32) -> PresortResult {
+        // The banned columns are only banned for the move to be chosen, the mirrored half of a symmetric
+        // root is skipped there as well.
+        let banned_cells = if depth == 0 {
+            self.root_ban_mask | self.root_mirror_mask
+        } else {
+            0
+        };
+        // A win on the spot can not be improved on, so the moves need no ordering.
+        let winning_moves = G::get_playable_winning_moves(
+            self.bit_board.own_stones,
+            self.bit_board.own_stones | self.bit_board.opponent_stones,
+        ) & !banned_cells;
+        if winning_moves != 0 {
+            return PresortResult {
+                working_list: WorkingList::new(),
+                max_score: MAX_SCORE,
+                best_move: Some(winning_moves.trailing_zeros() % G::STRIDE),
+            };
+        }
+
+        let child_draft = self.max_depth - depth - 1;
+        let is_pop_out = self.bit_board.get_rule_set() == RuleSet::PopOut;
         let mut local_max = SCORE_GUARD;
         let mut local_move = None;
         let mut test_board = self.bit_board.clone();
-        let mut local_sorter = Vec::<WorkingListEntry>::new();
+        let mut local_sorter = WorkingList::new();
 
         for (coded_move, slot) in self.bit_board.get_all_possible_moves() {
+            if coded_move & banned_cells != 0 {
+                continue;
+            }
             // Test execute the move.
             test_board.own_stones |= coded_move;
-            // First we try the immediate situations, because it is a win a loss or a draw.
-            if check_for_winning(test_board.own_stones) {
-                local_max = MAX_SCORE;
-                local_move = Some(slot);
-            } else if ((test_board.own_stones | test_board.opponent_stones) == FULL_BOARD_MASK)
-                && (local_max < 0.0)
+            // First we try the immediate situations, a draw ends the game as well. Under the Pop Out
+            // rules the opponent may still pop on a full board.
+            if test_board.remaining_moves() == 0
+                && (!is_pop_out || test_board.opponent_stones & G::BOTTOM_FILL_MASK == 0)
             {
-                local_max = 0.0;
-                local_move = Some(slot);
+                if local_max < 0.0 {
+                    local_max = 0.0;
+                    local_move = Some(slot);
+                }
             }
-            // Then we look in the transposition tables.
+            // Then we look in the transposition table.
             else {
-                // As Swap the player to get the values. because we encoded the player from the follow up move.
-                test_board.swap_players();
-                let search_key = test_board.get_symmetry_independent_position();
-                test_board.swap_players();
-
-                // See if it is in the current transposition table.
-                // If we found it here, we can insert the result and do not need to analyze the node any further.
-                if let Some(evaluation) = self.hash_map.get(&search_key) {
-                    let score = -*evaluation;
-                    if score > local_max {
-                        local_max = score;
+                match self.get_move_value(&mut test_board, child_draft, depth) {
+                    MoveValue::Exact(score) => {
+                        self.table_hits += 1;
+                        if score > local_max {
+                            local_max = score;
+                            local_move = Some(slot);
+                        }
+                    }
+                    MoveValue::Estimate(evaluation) => local_sorter.push(WorkingListEntry {
+                        coded_move,
+                        slot,
+                        evaluation,
+                        center_distance: G::get_center_distance(slot),
+                    }),
+                }
+            }
+            // Retake move.
+            test_board.own_stones ^= coded_move;
+        }
+
+        if is_pop_out {
+            for column in (0..G::WIDTH).map(G::get_centered_column) {
+                let is_mirrored =
+                    depth == 0 && self.root_mirror_mask & G::get_column_mask(column) != 0;
+                if is_mirrored || !test_board.can_pop(column, true) {
+                    continue;
+                }
+                let slot = G::WIDTH + column;
+                test_board.pop_own_stone(column);
+                // A pop, that completes a four for both players, wins for the one, who popped.
+                if G::check_for_winning(test_board.own_stones) {
+                    local_max = MAX_SCORE;
+                    local_move = Some(slot);
+                } else if G::check_for_winning(test_board.opponent_stones) {
+                    if -MAX_SCORE > local_max {
+                        local_max = -MAX_SCORE;
                         local_move = Some(slot);
                     }
                 } else {
-                    // Hopefully it is still in the transposition table from last move.
-                    // In this case we take this as a heuristic evaluation.
-                    if let Some(evaluation) = self.hash_map_old.get(&search_key) {
-                        local_sorter.push(WorkingListEntry {
-                            coded_move,
-                            slot,
-                            evaluation: -*evaluation,
-                        });
-                    }
-                    // Heere we have to apply our heuristics.
-                    else {
-                        local_sorter.push(WorkingListEntry {
-                            coded_move,
+                    match self.get_move_value(&mut test_board, child_draft, depth) {
+                        MoveValue::Exact(score) => {
+                            self.table_hits += 1;
+                            if score > local_max {
+                                local_max = score;
+                                local_move = Some(slot);
+                            }
+                        }
+                        MoveValue::Estimate(evaluation) => local_sorter.push(WorkingListEntry {
+                            coded_move: G::get_pop_move(column),
                             slot,
-                            evaluation: compute_heuristics(&test_board, CLAMP_GUARD_HEURISTIC),
-                        });
+                            evaluation,
+                            center_distance: G::get_center_distance(column),
+                        }),
                     }
                 }
+                test_board.unpop_own_stone(column);
+                if local_max == MAX_SCORE {
+                    break;
+                }
             }
-            // Retake move.
-            test_board.own_stones ^= coded_move;
         }
 
         // Do the inverse sort (descending order.).
-        local_sorter.sort_by(|first, second| second.evaluation.total_cmp(&first.evaluation));
+        local_sorter.sort_descending();
+        #[cfg(test)]
+        if self.is_ordering_disabled {
+            local_sorter.sort_by_slot();
+        }
 
         PresortResult {
             working_list: local_sorter,
@@ -145,6 +697,88 @@ impl AlphaBeta {
         }
     }
 
+    /// Looks up the board after a move, that does not end the game, in the transposition table. If we
+    /// find an exact value, that is deep enough, we can take it as the score of the move and do not need
+    /// to analyze the node any further. Otherwise the move gets an estimate for the move ordering. The
+    /// depth is the one of the node, the move is made at.
+    fn get_move_value(
+        &self,
+        test_board: &mut GenericBitBoard<G>,
+        child_draft: u32,
+        depth: u32,
+    ) -> MoveValue {
+        // As Swap the player to get the values. because we encoded the player from the follow up move.
+        test_board.swap_players();
+        let search_key = test_board.get_symmetry_independent_key();
+        test_board.swap_players();
+
+        // A bound or a shallower value is only good enough for the move ordering, there it is a better
+        // estimate than the heuristics.
+        if let Some(entry) = self.hash_map.get(&search_key) {
+            if entry.bound == Bound::Exact && entry.is_deep_enough(child_draft) {
+                return MoveValue::Exact(-entry.value * DISCOUNT_FACTOR);
+            }
+            return MoveValue::Estimate(-entry.value);
+        }
+        // Heere we have to apply our heuristics.
+        MoveValue::Estimate(self.compute_heuristics(test_board, depth))
+    }
+
+    /// Evaluates the board after a move made at a node of the depth with the heuristics. The threats of
+    /// the opponent of the root on the overlooked squares are not seen, these are the opponent stones
+    /// on the even depths, where the root side has just moved, and the own stones on the odd ones.
+    fn compute_heuristics(&self, board: &GenericBitBoard<G>, depth: u32) -> f32 {
+        if self.overlooked_squares == 0 {
+            return compute_heuristics(board, &self.config.weights, CLAMP_GUARD_HEURISTIC);
+        }
+        let (overlooked_own, overlooked_opponent) = if depth.is_multiple_of(2) {
+            (0, self.overlooked_squares)
+        } else {
+            (self.overlooked_squares, 0)
+        };
+        compute_heuristics_overlooking(
+            board,
+            &self.config.weights,
+            CLAMP_GUARD_HEURISTIC,
+            overlooked_own,
+            overlooked_opponent,
+        )
+    }
+
+    /// Draws the squares, on which the threats get overlooked in the search, that starts, each with the
+    /// probability of the threat blindness of the configuration.
+    fn draw_overlooked_squares(&mut self) -> u64 {
+        let blindness = f64::from(self.config.threat_blindness);
+        if blindness <= 0.0 {
+            return 0;
+        }
+        G::get_position_iterator(G::FULL_BOARD_MASK)
+            .filter(|_| self.random.next_f64() < blindness)
+            .fold(0, |squares, (x, y)| {
+                squares | G::get_bit_representation(x, y)
+            })
+    }
+
+    /// Plays the move of the working list for the own stones.
+    #[inline(always)]
+    fn make_move(&mut self, entry: &WorkingListEntry) {
+        if entry.slot < G::WIDTH {
+            self.bit_board.own_stones |= entry.coded_move;
+        } else {
+            self.bit_board.pop_own_stone(entry.slot - G::WIDTH);
+        }
+    }
+
+    /// Takes back the move made with *make_move*.
+    #[inline(always)]
+    fn take_back_move(&mut self, entry: &WorkingListEntry) {
+        if entry.slot < G::WIDTH {
+            self.bit_board.own_stones ^= entry.coded_move;
+        } else {
+            self.bit_board.unpop_own_stone(entry.slot - G::WIDTH);
+        }
+    }
+
     /// Evaluate the next move and returns the applied move and the value. This is the implementation
     /// of the Negamax algorithm.
     ///
@@ -156,7 +790,8 @@ impl AlphaBeta {
     ///
     /// # Returns
     /// A pair of the node evaluation and eventually a chosen move. In the case of a TT hit or max search_depth we do not
-    /// generate this (None).
+    /// generate this (None). The search is fail-soft: a value below the original alpha is an upper bound and a value
+    /// at or above beta a lower bound of the real value.
     fn evaluate_next_move(
         &mut self,
         alpha: f32,
@@ -168,55 +803,110 @@ impl AlphaBeta {
         // because that has already been checked in get_pre_sorted_move_list from previous call. We insert it as
         // debug assert here.
         debug_assert!(
-            !check_for_winning(self.bit_board.opponent_stones),
-            "This should already have been prechecked."
+            !G::check_for_winning(self.bit_board.opponent_stones),
+            "This should already have been prechecked:\n{}",
+            self.bit_board
         );
-        // Same for draw.
+        // Same for draw, under the Pop Out rules we may still pop on a full board.
         debug_assert!(
-            (self.bit_board.own_stones | self.bit_board.opponent_stones) != FULL_BOARD_MASK,
-            "The case that we have have a draw should have also already been prechecked."
+            self.bit_board.remaining_moves() != 0
+                || (self.bit_board.get_rule_set() == RuleSet::PopOut
+                    && self.bit_board.own_stones & G::BOTTOM_FILL_MASK != 0),
+            "The case that we have have a draw should have also already been prechecked:\n{}",
+            self.bit_board
         );
 
-        let search_key = self.bit_board.get_symmetry_independent_position();
-        if let Some(&cached_value) = self.hash_map.get(&search_key) {
-            // Transposition hit!
-            return (cached_value, None);
+        self.nodes_searched += 1;
+
+        // The first iteration always has to finish, that we have a move at all.
+        if self.is_time_limited
+            && self.max_depth > 1
+            && self.nodes_searched.is_multiple_of(TIME_CHECK_INTERVAL)
+            && self.time_manager.is_out_of_time()
+        {
+            self.search_aborted = true;
+        } else if self.nodes_searched >= self.slice_end {
+            self.search_paused = true;
+            self.search_aborted = true;
+        }
+        if self.search_aborted {
+            return (0.0, None);
+        }
+
+        let search_key = self.bit_board.get_symmetry_independent_key();
+        let draft = self.max_depth - depth;
+        // The table is kept over the moves, so it may know the root already, but the root has to be
+        // searched for its move.
+        if let Some(&entry) = self
+            .hash_map
+            .get(&search_key)
+            .filter(|entry| depth > 0 && entry.is_deep_enough(draft))
+        {
+            // Transposition hit, bounds are only usable if they already decide the window.
+            let is_usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.value >= beta,
+                Bound::Upper => entry.value <= alpha,
+            };
+            if is_usable {
+                self.table_hits += 1;
+                return (entry.value, None);
+            }
         }
 
         // If we have reached max depth we simply return the heuristics value.
-        if depth == SEARCH_DEPTH {
+        if depth == self.max_depth {
             return (heuristics, None);
         }
 
         let mut best_value = SCORE_GUARD;
         let mut best_slot = 0;
 
-        let presort_result = self.get_pre_sorted_move_list();
+        let presort_result = self.get_pre_sorted_move_list(depth);
+        let original_alpha = alpha;
         let mut alpha = alpha;
         // The presort result has already filtered out sone moves, that either run into an ending or are already completely analyzed.
-        if presort_result.best_move.is_some() {
-            best_slot = presort_result.best_move.unwrap();
+        if let Some(slot) = presort_result.best_move {
+            best_slot = slot;
             best_value = presort_result.max_score;
         }
 
+        // A win on the spot is the best we can get, there are no siblings left to look at.
+        if best_value == MAX_SCORE {
+            self.store_entry(search_key, best_value, Bound::Exact, depth);
+            return (best_value, Some(best_slot));
+        }
+
         // We may need to do an alpha beta check here and can eventually return.
         if best_value > alpha {
             alpha = best_value;
             if best_value >= beta {
-                self.hash_map.insert(search_key, best_value);
+                self.store_entry(search_key, best_value, Bound::Lower, depth);
                 return (best_value, Some(best_slot));
             }
         }
 
         // We start searching now.
-        for list_entry in presort_result.working_list.iter() {
+        for list_entry in presort_result.working_list.as_slice() {
             // Apply move.
-            self.bit_board.own_stones |= list_entry.coded_move;
+            self.make_move(list_entry);
             self.bit_board.swap_players();
-            let (new_result, _) =
-                self.evaluate_next_move(-beta, -alpha, -list_entry.evaluation, depth + 1);
+            // The result gets discounted on the way up, so the window of the child is the one, that
+            // ends up as our window after the discount. Otherwise a bound of the child, that is just
+            // inside the window, would be taken for an exact value here.
+            let (new_result, _) = self.evaluate_next_move(
+                -beta / DISCOUNT_FACTOR,
+                -alpha / DISCOUNT_FACTOR,
+                -list_entry.evaluation,
+                depth + 1,
+            );
             self.bit_board.swap_players();
-            self.bit_board.own_stones ^= list_entry.coded_move;
+            self.take_back_move(list_entry);
+
+            // The result is garbage, if the search has been aborted in the meantime.
+            if self.search_aborted {
+                return (0.0, None);
+            }
 
             let adjusted_result = -new_result * DISCOUNT_FACTOR;
             if adjusted_result > best_value {
@@ -228,29 +918,1094 @@ impl AlphaBeta {
             }
 
             // Early out here.
-            if adjusted_result > beta {
+            if adjusted_result >= beta {
                 break;
             }
         }
 
-        // Insert value into hashmap.
-        self.hash_map.insert(search_key, best_value);
+        // Insert value into hashmap together with the information, if it is only a bound.
+        let bound = if best_value <= original_alpha {
+            Bound::Upper
+        } else if best_value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.store_entry(search_key, best_value, bound, depth);
 
         (best_value, Some(best_slot))
     }
 
+    /// Stores the value of the node at the depth in the transposition table with the age of the search.
+    /// A root with banned columns has not been searched completely, its value is only valid for this
+    /// search and does not get stored. If the table is full, the entries of the older searches get evicted,
+    /// if the running search has filled it on its own, only the entries already in the table get updated.
+    fn store_entry(&mut self, search_key: G::Key, value: f32, bound: Bound, depth: u32) {
+        if depth == 0 && self.root_ban_mask != 0 {
+            return;
+        }
+        if self.hash_map.len() >= self.max_table_entries && !self.hash_map.contains_key(&search_key)
+        {
+            if !self.is_table_evicted {
+                self.is_table_evicted = true;
+                self.evict_old_entries(0);
+            }
+            if self.hash_map.len() >= self.max_table_entries {
+                return;
+            }
+        }
+        self.hash_map.insert(
+            search_key,
+            TranspositionEntry {
+                value,
+                bound,
+                draft: self.max_depth - depth,
+                age: self.age,
+            },
+        );
+    }
+
     /// Gets the best move for the AI, sets the bit board and does all the computations.
-    pub fn get_best_move(&mut self, bit_board: BitBoard) -> u32 {
+    /// The search runs as an iterative deepening, where the time manager decides how deep we go.
+    /// The transposition table is kept over the iterations and the moves of the game, what a search can
+    /// not take from it, it uses for the move ordering. Every search logs one summary record on the debug level.
+    /// Returns the column of the move, under the Pop Out rules a pop out of a column is returned as the
+    /// board width plus the column. A game, that is already over, has no move and returns an error.
+    pub fn get_best_move(&mut self, bit_board: GenericBitBoard<G>) -> Result<u32, EngineError> {
+        self.start_search(bit_board)?;
+        Ok(self
+            .continue_search(u64::MAX)
+            .expect("A search without a limit of nodes always finishes."))
+    }
+
+    /// Starts the search of [`GenericAlphaBeta::get_best_move`] on the board, but does not run it yet.
+    /// It runs in slices with [`GenericAlphaBeta::continue_search`], this is for platforms without threads,
+    /// where the search has to share the main loop with the rendering. A game, that is already over, is
+    /// not searched and returns an error.
+    pub fn start_search(&mut self, bit_board: GenericBitBoard<G>) -> Result<(), EngineError> {
+        if bit_board.is_game_over() {
+            return Err(EngineError::GameOver);
+        }
+        debug_assert!(
+            bit_board.validate().is_ok(),
+            "The board handed over is invalid: {:?}\n{}",
+            bit_board.validate(),
+            bit_board
+        );
         self.bit_board = bit_board;
+        self.bit_board.clear_move_history();
+        self.nodes_searched = 0;
+        self.table_hits = 0;
+        self.search_aborted = false;
+        self.search_paused = false;
+        self.is_time_limited = true;
+        self.completed_depth = 0;
+        self.best_score = 0.0;
+        self.best_move = None;
+        self.table_entries = 0;
+        self.root_ban_mask = self.get_root_ban_mask();
+        self.forced_move = None;
+        if let Some((forced_move, column)) = self.find_forced_move() {
+            self.apply_forced_move(forced_move, column);
+        }
+        self.root_mirror_mask = self.get_root_mirror_mask();
+        self.overlooked_squares = self.draw_overlooked_squares();
+        self.age_table();
+        self.time_manager.start_move();
+        log::trace!(
+            "Search started: max depth {}, move time {:?}",
+            self.config.max_depth,
+            self.config.move_time
+        );
+        Ok(())
+    }
 
-        let (_, mov) = self.evaluate_next_move(-MAX_SCORE, MAX_SCORE, 0.0, 0);
+    /// Continues the search started with [`GenericAlphaBeta::start_search`] for about the indicated amount
+    /// of nodes. An iteration, that gets interrupted by the end of the slice, runs again in the next one,
+    /// the parts it has already searched are in the transposition table, so little work is lost. Returns
+    /// the best move, once the search has finished, and none, if it needs another slice.
+    pub fn continue_search(&mut self, node_budget: u64) -> Option<u32> {
+        self.slice_end = self.nodes_searched.saturating_add(node_budget);
+        let finished = self.run_iterations();
+        self.slice_end = u64::MAX;
+        finished.then(|| self.finish_search())
+    }
 
-        // Demote hash map.
-        self.hash_map_old = self.hash_map.clone();
-        self.hash_map.clear();
-        debug_assert!(mov.is_some(), "We wound up with an empty move here");
-        let mov = mov.unwrap();
-        debug_check_board_coordinates!(col: mov);
+    /// Runs the iterations of the iterative deepening, until the search has finished or the slice is used
+    /// up. Returns if the search has finished.
+    fn run_iterations(&mut self) -> bool {
+        // A forced win or loss is decided already, there is nothing to search.
+        if matches!(self.forced_move, Some(ForcedMove::Win | ForcedMove::Lost)) {
+            return true;
+        }
+        for depth in self.completed_depth + 1..=self.config.max_depth {
+            self.max_depth = depth;
+            let (score, mov) = self.evaluate_next_move(-MAX_SCORE, MAX_SCORE, 0.0, 0);
+            if self.search_paused {
+                self.search_paused = false;
+                self.search_aborted = false;
+                return false;
+            }
+            if self.search_aborted {
+                break;
+            }
+
+            self.table_entries = self.hash_map.len();
+
+            debug_assert!(mov.is_some(), "We wound up with an empty move here");
+            let mov = mov.unwrap();
+            self.best_move = Some(mov);
+            self.completed_depth = depth;
+            self.best_score = score;
+            log::trace!(
+                "Iteration {depth} finished: move {mov}, score {score:.3}, nodes {}",
+                self.nodes_searched
+            );
+            if let Some(listener) = self.progress_listener.as_mut() {
+                listener(&SearchStats {
+                    depth,
+                    score,
+                    nodes: self.nodes_searched,
+                    elapsed_time: self.time_manager.get_elapsed_time(),
+                    table_entries: self.table_entries,
+                    table_hits: self.table_hits,
+                });
+            }
+            self.time_manager.register_iteration(mov, score);
+
+            // A proven win or loss can not change with a deeper search any more. Only a win, that the
+            // table has proven beyond the horizon of the iteration, may still have a faster one, which
+            // is beyond the horizon as well.
+            let is_proven = score < -CLAMP_GUARD_HEURISTIC
+                || (score > CLAMP_GUARD_HEURISTIC && get_proven_distance(score) <= depth);
+            if is_proven || !self.time_manager.should_start_next_iteration() {
+                break;
+            }
+        }
+        true
+    }
+
+    /// Cleans up after the search has finished and gets the best move.
+    fn finish_search(&mut self) -> u32 {
+        let best_move = self
+            .best_move
+            .expect("The first iteration should always finish.");
+        let mov = self.draw_move(best_move);
+        self.best_move = Some(mov);
+
+        // The entries of an aborted iteration are complete for the nodes, that have been finished, so
+        // the table is kept as it is.
+        self.root_ban_mask = 0;
+        self.root_mirror_mask = 0;
+        self.overlooked_squares = 0;
+
+        log::debug!(
+            "Search finished: move {mov}, best move {best_move}, forced {:?}, depth {}, score {:.3}, nodes {}, table hits {}, time {:.3} s, table entries {}",
+            self.forced_move,
+            self.completed_depth,
+            self.best_score,
+            self.nodes_searched,
+            self.table_hits,
+            self.time_manager.get_elapsed_time(),
+            self.table_entries
+        );
+        debug_assert!(
+            mov < G::WIDTH
+                || (self.bit_board.get_rule_set() == RuleSet::PopOut && mov < 2 * G::WIDTH),
+            "Illegal column: {mov}"
+        );
         mov
     }
+
+    /// Draws the move to play with a softmax over the scores of all moves, if the configuration has a
+    /// temperature. The moves get evaluated with [`GenericAlphaBeta::evaluate_all_moves`] to the depth of
+    /// the search, the best move of the search is played without a temperature, when the move has been
+    /// forced and under the Pop Out rules, as the evaluation leaves out the pops. The overlooked squares
+    /// of the search stay drawn for the evaluation, the scores to choose from are the ones of the player,
+    /// who has overlooked the threats, and not the ones of a player, who sees them all.
+    fn draw_move(&mut self, best_move: u32) -> u32 {
+        let temperature = self.config.temperature;
+        if temperature <= 0.0
+            || self.forced_move.is_some()
+            || self.bit_board.get_rule_set() == RuleSet::PopOut
+        {
+            return best_move;
+        }
+        let nodes_searched = self.nodes_searched;
+        let evaluations = self.evaluate_all_moves(self.bit_board.clone(), self.completed_depth);
+        self.nodes_searched += nodes_searched;
+
+        let mut candidates: Vec<ColumnEval> = evaluations
+            .into_iter()
+            .filter(|eval| {
+                eval.is_legal && G::get_column_mask(eval.column) & self.root_ban_mask == 0
+            })
+            .collect();
+        // A move, that loses for sure, is only played, if all moves do.
+        if candidates
+            .iter()
+            .any(|eval| eval.score >= -CLAMP_GUARD_HEURISTIC)
+        {
+            candidates.retain(|eval| eval.score >= -CLAMP_GUARD_HEURISTIC);
+        }
+        let max_score = candidates
+            .iter()
+            .map(|eval| eval.score)
+            .fold(SCORE_GUARD, f32::max);
+        let weights: Vec<f64> = candidates
+            .iter()
+            .map(|eval| (f64::from(eval.score - max_score) / f64::from(temperature)).exp())
+            .collect();
+
+        let mut remainder = self.random.next_f64() * weights.iter().sum::<f64>();
+        for (candidate, weight) in candidates.iter().zip(&weights) {
+            if remainder < *weight {
+                self.best_score = candidate.score;
+                return candidate.column;
+            }
+            remainder -= weight;
+        }
+        // Only the rounding of the sum can get here.
+        best_move
+    }
+
+    /// Evaluates every column of the board with a fixed depth search from the perspective of the player
+    /// to move. In contrast to [`GenericAlphaBeta::get_best_move`] siblings are not pruned away, so every legal
+    /// column gets a real score. This is meant for hints and evaluation displays. The search runs on
+    /// its own transposition table, so the table of the real move computation stays untouched.
+    /// Only drops get evaluated, under the Pop Out rules the pops are left out.
+    pub fn evaluate_all_moves(&mut self, board: GenericBitBoard<G>, depth: u32) -> Vec<ColumnEval> {
+        let saved_hash_map = mem::take(&mut self.hash_map);
+        let was_table_evicted = mem::take(&mut self.is_table_evicted);
+        self.bit_board = board;
+        self.bit_board.clear_move_history();
+        self.nodes_searched = 0;
+        self.max_depth = depth.max(1);
+        self.search_aborted = false;
+        self.is_time_limited = false;
+
+        let mut result = Vec::with_capacity(G::WIDTH as usize);
+        for column in 0..G::WIDTH {
+            let coded_move = self.bit_board.get_possible_move(column);
+            if coded_move == 0 {
+                result.push(ColumnEval {
+                    column,
+                    score: SCORE_GUARD,
+                    is_legal: false,
+                });
+                continue;
+            }
+
+            self.bit_board.own_stones |= coded_move;
+            let score = if G::check_for_winning(self.bit_board.own_stones) {
+                MAX_SCORE
+            } else if self.bit_board.check_for_draw_if_not_winning() {
+                0.0
+            } else {
+                let heuristics = self.compute_heuristics(&self.bit_board, 0);
+                self.bit_board.swap_players();
+                let (value, _) = self.evaluate_next_move(-MAX_SCORE, MAX_SCORE, -heuristics, 1);
+                self.bit_board.swap_players();
+                -value * DISCOUNT_FACTOR
+            };
+            self.bit_board.own_stones ^= coded_move;
+
+            result.push(ColumnEval {
+                column,
+                score,
+                is_legal: true,
+            });
+        }
+
+        self.hash_map = saved_hash_map;
+        self.is_table_evicted = was_table_evicted;
+        result
+    }
+
+    /// Solves the position exactly, by searching down to the end of the game. This is only feasible for
+    /// positions, where a good part of the board is already filled. The board may not be over already.
+    /// As the analysis, the solving runs on its own transposition table. Only the classic rules can be
+    /// solved, as under the Pop Out rules a game does not end with a full board.
+    pub fn solve(&mut self, board: GenericBitBoard<G>) -> SolveResult {
+        debug_assert!(
+            !board.is_game_over(),
+            "Can not solve a finished game:\n{board}"
+        );
+        debug_assert!(
+            board.get_rule_set() == RuleSet::Classic,
+            "Only games with the classic rules can be solved."
+        );
+        let saved_hash_map = mem::take(&mut self.hash_map);
+        let was_table_evicted = mem::take(&mut self.is_table_evicted);
+        let free_cells = board.remaining_moves();
+        self.bit_board = board;
+        self.bit_board.clear_move_history();
+        self.nodes_searched = 0;
+        self.max_depth = free_cells;
+        self.search_aborted = false;
+        self.is_time_limited = false;
+        self.root_mirror_mask = self.get_root_mirror_mask();
+
+        let (score, mov) = self.evaluate_next_move(-MAX_SCORE, MAX_SCORE, 0.0, 0);
+
+        self.hash_map = saved_hash_map;
+        self.is_table_evicted = was_table_evicted;
+        self.root_mirror_mask = 0;
+
+        // A draw lasts until the board is full.
+        let (outcome, distance) = if score == 0.0 {
+            (GameOutcome::Draw, free_cells)
+        } else {
+            let distance = get_proven_distance(score);
+            if score > 0.0 {
+                (GameOutcome::Win, distance)
+            } else {
+                (GameOutcome::Loss, distance)
+            }
+        };
+
+        SolveResult {
+            outcome,
+            distance,
+            best_move: mov.expect("The root of the search always has a move."),
+            score,
+        }
+    }
+
+    /// Gets, how the move of the last search has been forced, none if it has been searched as usual.
+    /// A lost position is flagged with [`ForcedMove::Lost`].
+    pub fn get_forced_move(&self) -> Option<ForcedMove> {
+        self.forced_move
+    }
+
+    /// Gets the number of nodes that have been visited in the last search.
+    pub fn get_nodes_searched(&self) -> u64 {
+        self.nodes_searched
+    }
+
+    /// Gets the number of positions of the last search, whose value has been taken from the transposition
+    /// table.
+    pub fn get_table_hits(&self) -> u64 {
+        self.table_hits
+    }
+
+    /// Forgets the transposition table, this has to be called, when a new game starts. The entries of
+    /// the old game are not wrong, but they are unlikely to be reached again and only take up space.
+    pub fn clear_table(&mut self) {
+        self.hash_map.clear();
+        self.age = 0;
+        self.is_table_evicted = false;
+    }
+
+    /// Bumps the age for the search, that starts. If the table is full, the entries of the searches
+    /// before the last one get evicted, they belong to positions, that have already been played past.
+    fn age_table(&mut self) {
+        self.age = self.age.wrapping_add(1);
+        self.is_table_evicted = false;
+        if self.hash_map.len() >= self.max_table_entries {
+            self.evict_old_entries(1);
+        }
+    }
+
+    /// Evicts the entries of the table from the oldest searches on, until the table is no longer full.
+    /// The entries of the searches down to the indicated amount of searches before the running one are
+    /// always kept.
+    fn evict_old_entries(&mut self, kept_searches: u8) {
+        let age = self.age;
+        for searches in (kept_searches..=2).rev() {
+            self.hash_map
+                .retain(|_, entry| age.wrapping_sub(entry.age) <= searches);
+            if self.hash_map.len() < self.max_table_entries {
+                break;
+            }
+        }
+        log::debug!(
+            "Transposition table aged, {} entries kept",
+            self.hash_map.len()
+        );
+    }
+
+    /// Gets the depth of the deepest iteration, that has finished in the last call of
+    /// [`GenericAlphaBeta::get_best_move`].
+    pub fn get_completed_depth(&self) -> u32 {
+        self.completed_depth
+    }
+
+    /// Gets the score of the move returned by the last call of [`GenericAlphaBeta::get_best_move`]
+    /// from the perspective of the player to move.
+    pub fn get_best_score(&self) -> f32 {
+        self.best_score
+    }
+
+    /// Gets the slots of the board in the order, in which a search of the depth tries them at the root.
+    /// The best of the moves, whose score the presorting already knows from a win or the transposition
+    /// table, comes first, the others of them are not searched and left out. So are the columns right of
+    /// the center on a symmetric board. This exposes the move ordering to the benchmarks.
+    pub fn get_move_ordering(&mut self, board: GenericBitBoard<G>, depth: u32) -> Vec<u32> {
+        self.bit_board = board;
+        self.max_depth = depth.max(1);
+        self.root_ban_mask = 0;
+        self.root_mirror_mask = self.get_root_mirror_mask();
+        let presort = self.get_pre_sorted_move_list(0);
+        presort
+            .best_move
+            .into_iter()
+            .chain(
+                presort
+                    .working_list
+                    .as_slice()
+                    .iter()
+                    .map(|entry| entry.slot),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::bit_board::{BitBoard, GameResult, MoveError};
+    use crate::board_logic::bit_board_coding::{BOARD_WIDTH, ConnectFiveGeometry, Geometry};
+    use std::sync::{Arc, Mutex, Once};
+    use std::thread::{self, ThreadId};
+
+    /// Keeps the levels of the records logged by the threads, that capture them. The tests run in
+    /// parallel, each one only gets the records of its own thread.
+    struct CapturingLogger {
+        records: Mutex<Vec<(ThreadId, Vec<log::Level>)>>,
+    }
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let thread_id = thread::current().id();
+            let mut records = self.records.lock().unwrap();
+            if let Some((_, levels)) = records.iter_mut().find(|(id, _)| *id == thread_id) {
+                levels.push(record.level());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger {
+        records: Mutex::new(Vec::new()),
+    };
+
+    /// Runs the function and gets the levels of the records, that it has logged.
+    fn get_logged_levels(function: impl FnOnce()) -> Vec<log::Level> {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&LOGGER).expect("No other logger is installed in the tests.");
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        let thread_id = thread::current().id();
+        LOGGER.records.lock().unwrap().push((thread_id, Vec::new()));
+        function();
+        let mut records = LOGGER.records.lock().unwrap();
+        let index = records.iter().position(|(id, _)| *id == thread_id).unwrap();
+        records.swap_remove(index).1
+    }
+
+    /// Gets an engine, that searches every iteration up to the depth without a time limit.
+    fn get_engine(max_depth: u32) -> AlphaBeta {
+        AlphaBeta::with_config(SearchConfig {
+            max_depth,
+            move_time: None,
+            ..SearchConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_win_on_the_spot_needs_no_search() {
+        // The first player has three stones stacked in the fourth column.
+        let board = BitBoard::from_move_string("414141").unwrap();
+        let mut engine = get_engine(SEARCH_DEPTH);
+        assert_eq!(engine.get_best_move(board.clone()), Ok(3));
+        assert_eq!(engine.get_best_score(), MAX_SCORE);
+        assert!(engine.get_nodes_searched() < 10);
+
+        // Solving goes through the search itself, the presort finds the win at the root.
+        let result = engine.solve(board);
+        assert_eq!(result.best_move, 3);
+        assert_eq!(result.distance, 1);
+        assert!(engine.get_nodes_searched() < 10);
+    }
+
+    #[test]
+    fn test_search_logs_one_summary() {
+        let mut engine = get_engine(6);
+        for moves in ["", "4453", "121212"] {
+            let board = BitBoard::from_move_string(moves).unwrap();
+            let levels = get_logged_levels(|| {
+                engine.get_best_move(board).unwrap();
+            });
+            // The start and the iterations only get traced.
+            assert!(levels.contains(&log::Level::Trace));
+            assert!(levels.iter().all(|&level| level >= log::Level::Debug));
+            let summaries = levels
+                .iter()
+                .filter(|&&level| level == log::Level::Debug)
+                .count();
+            assert_eq!(summaries, 1, "{moves}");
+        }
+    }
+
+    #[test]
+    fn test_forced_moves() {
+        // The first player completes the four in the first column without a search.
+        let mut engine = get_engine(SEARCH_DEPTH);
+        assert_eq!(
+            engine.get_best_move(BitBoard::from_move_string("121212").unwrap()),
+            Ok(0)
+        );
+        assert_eq!(engine.get_forced_move(), Some(ForcedMove::Win));
+        assert_eq!(engine.get_best_score(), MAX_SCORE);
+        assert_eq!(engine.get_nodes_searched(), 0);
+
+        // The second player has to block the first column, only that move gets searched.
+        let mut engine = get_engine(6);
+        assert_eq!(
+            engine.get_best_move(BitBoard::from_move_string("12121").unwrap()),
+            Ok(0)
+        );
+        assert_eq!(engine.get_forced_move(), Some(ForcedMove::Block));
+        assert!(engine.get_nodes_searched() > 0);
+
+        // The three in the bottom row are open on both sides, the block of either one loses.
+        let mut engine = get_engine(SEARCH_DEPTH);
+        let mov = engine
+            .get_best_move(BitBoard::from_move_string("44553").unwrap())
+            .unwrap();
+        assert!(mov == 1 || mov == 5, "{mov}");
+        assert_eq!(engine.get_forced_move(), Some(ForcedMove::Lost));
+        assert_eq!(engine.get_best_score(), -MAX_SCORE * DISCOUNT_FACTOR);
+        assert_eq!(engine.get_nodes_searched(), 0);
+
+        // Without a threat the move is searched as usual.
+        let mut engine = get_engine(4);
+        engine
+            .get_best_move(BitBoard::from_move_string("4453").unwrap())
+            .unwrap();
+        assert_eq!(engine.get_forced_move(), None);
+    }
+
+    /// Draws the moves of the board many times with the temperature and gets how often each column came.
+    fn get_drawn_frequencies(board: &BitBoard, temperature: f32, draws: u32) -> Vec<f64> {
+        let mut engine = AlphaBeta::with_config(SearchConfig {
+            max_depth: 4,
+            move_time: None,
+            temperature,
+            ..SearchConfig::default()
+        });
+        engine.set_seed(1);
+        let mut counts = vec![0; 7];
+        for _ in 0..draws {
+            counts[engine.get_best_move(board.clone()).unwrap() as usize] += 1;
+        }
+        counts
+            .into_iter()
+            .map(|count| f64::from(count) / f64::from(draws))
+            .collect()
+    }
+
+    #[test]
+    fn test_temperature_follows_the_softmax() {
+        const DRAWS: u32 = 200;
+        let board = BitBoard::from_move_string("4453").unwrap();
+        let scores: Vec<f64> = get_engine(4)
+            .evaluate_all_moves(board.clone(), 4)
+            .iter()
+            .map(|evaluation| f64::from(evaluation.score))
+            .collect();
+        let best_column = 3;
+        assert_eq!(get_drawn_frequencies(&board, 0.0, 10)[best_column], 1.0);
+
+        let mut last_best_frequency = 1.0;
+        for temperature in [0.01, 0.03, 0.1] {
+            let frequencies = get_drawn_frequencies(&board, temperature, DRAWS);
+            let weights: Vec<f64> = scores
+                .iter()
+                .map(|score| ((score - scores[best_column]) / f64::from(temperature)).exp())
+                .collect();
+            let total: f64 = weights.iter().sum();
+            for (frequency, weight) in frequencies.iter().zip(&weights) {
+                assert!(
+                    (frequency - weight / total).abs() < 0.08,
+                    "{temperature}: {frequencies:?} {weights:?}"
+                );
+            }
+            // The hotter the choice, the less often the best move gets played.
+            assert!(frequencies[best_column] < last_best_frequency);
+            last_best_frequency = frequencies[best_column];
+        }
+    }
+
+    #[test]
+    fn test_temperature_does_not_draw_a_loss() {
+        // The opponent wins on top of a stone dropped into the second or the sixth column, the other
+        // moves are safe.
+        let board = BitBoard::from_move_string("56437467751543").unwrap();
+        let frequencies = get_drawn_frequencies(&board, 1.0, 100);
+        for column in [1, 5] {
+            assert_eq!(frequencies[column], 0.0, "{frequencies:?}");
+        }
+        // The temperature is high enough to draw most of the safe moves.
+        assert!(
+            frequencies
+                .iter()
+                .filter(|&&frequency| frequency > 0.0)
+                .count()
+                >= 3
+        );
+    }
+
+    #[test]
+    fn test_pops_are_searched() {
+        let get_board = |grid: &str| {
+            let mut board = BitBoard::from_ascii(grid).unwrap();
+            board.set_rule_set(RuleSet::PopOut);
+            board
+        };
+        // Popping the fourth column completes a four for both players, which wins for the one popping.
+        let board = get_board(".......\n.......\n.......\n...X...\nXXXO...\nOOOX..O\n");
+        let mut engine = get_engine(4);
+        assert_eq!(engine.get_best_move(board), Ok(BOARD_WIDTH + 3));
+        assert_eq!(engine.get_best_score(), MAX_SCORE);
+
+        // Here the same pop only completes the four of the opponent.
+        let board = get_board(".......\n.......\n.......\n.......\nXX.O...\nOOOXX..\n");
+        let mut engine = get_engine(4);
+        assert_ne!(engine.get_best_move(board), Ok(BOARD_WIDTH + 3));
+        assert!(engine.get_best_score() > -MAX_SCORE * DISCOUNT_FACTOR);
+    }
+
+    #[test]
+    fn test_banned_column_is_never_chosen() {
+        const CENTER: u32 = 3;
+        // The empty board, a win on the spot in the center column and a center column, that has to be
+        // blocked.
+        for moves in ["", "414141", "41414"] {
+            let board = BitBoard::from_move_string(moves).unwrap();
+            let mut engine = get_engine(6);
+            engine.set_banned_root_columns(1 << CENTER);
+            let mov = engine.get_best_move(board).unwrap();
+            assert_ne!(mov, CENTER, "{moves}");
+        }
+
+        // The drawn moves keep away from it as well.
+        let mut engine = AlphaBeta::with_config(SearchConfig {
+            max_depth: 4,
+            move_time: None,
+            temperature: 1.0,
+            ..SearchConfig::default()
+        });
+        engine.set_seed(1);
+        engine.set_banned_root_columns(1 << CENTER);
+        for _ in 0..100 {
+            assert_ne!(engine.get_best_move(BitBoard::new()), Ok(CENTER));
+        }
+
+        // Without the ban the center column gets played again.
+        let mut engine = get_engine(6);
+        engine.set_banned_root_columns(1 << CENTER);
+        engine.set_banned_root_columns(0);
+        assert_eq!(engine.get_best_move(BitBoard::new()), Ok(CENTER));
+
+        // If all columns are banned, the ban gets ignored.
+        let mut engine = get_engine(6);
+        engine.set_banned_root_columns(0b111_1111);
+        assert_eq!(
+            engine.get_best_move(BitBoard::from_move_string("414141").unwrap()),
+            Ok(CENTER)
+        );
+    }
+
+    #[test]
+    fn test_drawn_move_overlooks_the_threats() {
+        let board = BitBoard::from_move_string("4453").unwrap();
+        let config = SearchConfig {
+            max_depth: 4,
+            move_time: None,
+            temperature: 0.1,
+            threat_blindness: 1.0,
+            ..SearchConfig::default()
+        };
+        // With all squares overlooked the scores to draw from are the blind ones.
+        let mut blind = AlphaBeta::with_config(config);
+        blind.overlooked_squares = StandardGeometry::FULL_BOARD_MASK;
+        let blind_scores = blind.evaluate_all_moves(board.clone(), 4);
+        let seeing_scores = get_engine(4).evaluate_all_moves(board.clone(), 4);
+
+        let mut engine = AlphaBeta::with_config(config);
+        engine.set_seed(1);
+        let mut has_drawn_blind_score = false;
+        for _ in 0..40 {
+            let mov = engine.get_best_move(board.clone()).unwrap() as usize;
+            assert_eq!(engine.get_best_score(), blind_scores[mov].score);
+            has_drawn_blind_score |= blind_scores[mov].score != seeing_scores[mov].score;
+            assert_eq!(engine.overlooked_squares, 0);
+        }
+        assert!(has_drawn_blind_score);
+    }
+
+    #[test]
+    fn test_move_ordering_saves_nodes() {
+        for moves in ["4453", "3344256", "44444361"] {
+            let board = BitBoard::from_move_string(moves).unwrap();
+            let mut ordered = get_engine(8);
+            let ordered_move = ordered.get_best_move(board.clone()).unwrap();
+            let mut unordered = get_engine(8);
+            unordered.is_ordering_disabled = true;
+            let unordered_move = unordered.get_best_move(board).unwrap();
+
+            assert_eq!(
+                ordered.get_best_score(),
+                unordered.get_best_score(),
+                "{moves}"
+            );
+            assert_eq!(ordered_move, unordered_move, "{moves}");
+            assert!(
+                2 * ordered.get_nodes_searched() < unordered.get_nodes_searched(),
+                "{moves}: {} nodes ordered, {} unordered",
+                ordered.get_nodes_searched(),
+                unordered.get_nodes_searched()
+            );
+        }
+    }
+
+    #[test]
+    fn test_equal_moves_are_sorted_from_the_center() {
+        let mut list = WorkingList::new();
+        for (slot, evaluation) in [(0, 1.0), (1, 2.0), (2, 1.0), (3, 1.0), (5, 2.0), (6, 0.0)] {
+            list.push(WorkingListEntry {
+                coded_move: 0,
+                slot,
+                evaluation,
+                center_distance: StandardGeometry::get_center_distance(slot),
+            });
+        }
+        list.sort_descending();
+        let slots: Vec<u32> = list.as_slice().iter().map(|entry| entry.slot).collect();
+        assert_eq!(slots, [1, 5, 3, 2, 0, 6]);
+    }
+
+    #[test]
+    fn test_symmetric_root_saves_nodes() {
+        // The empty board and one with two stones of each player mirrored around the center column.
+        for moves in ["", "4345"] {
+            let board = BitBoard::from_move_string(moves).unwrap();
+            let mut mirrored = get_engine(8);
+            let mirrored_move = mirrored.get_best_move(board.clone()).unwrap();
+            let mut unmirrored = get_engine(8);
+            unmirrored.is_mirroring_disabled = true;
+            let unmirrored_move = unmirrored.get_best_move(board).unwrap();
+
+            assert_eq!(mirrored_move, 3, "{moves}");
+            assert_eq!(unmirrored_move, 3, "{moves}");
+            assert_eq!(
+                mirrored.get_best_score(),
+                unmirrored.get_best_score(),
+                "{moves}"
+            );
+            assert!(
+                mirrored.get_nodes_searched() < unmirrored.get_nodes_searched(),
+                "{moves}: {} nodes mirrored, {} unmirrored",
+                mirrored.get_nodes_searched(),
+                unmirrored.get_nodes_searched()
+            );
+        }
+    }
+
+    #[test]
+    fn test_full_columns_are_illegal() {
+        let mut board = BitBoard::from_move_string("444444111111").unwrap();
+        assert_eq!(board.play(3), Err(MoveError::ColumnFull { column: 3 }));
+        assert_eq!(board.play(7), Err(MoveError::InvalidColumn { column: 7 }));
+        let evaluations = get_engine(4).evaluate_all_moves(board, 4);
+        assert_eq!(evaluations.len(), 7);
+        for evaluation in evaluations {
+            let is_full = evaluation.column == 0 || evaluation.column == 3;
+            assert_eq!(evaluation.is_legal, !is_full, "{evaluation:?}");
+        }
+    }
+
+    #[test]
+    fn test_evaluations_are_antisymmetric() {
+        // The score of a move is the negated and discounted best score of the opponent after it.
+        for moves in ["", "4453", "3344256", "44444361", "1234567"] {
+            let board = BitBoard::from_move_string(moves).unwrap();
+            let mut engine = get_engine(4);
+            for evaluation in engine.evaluate_all_moves(board.clone(), 4) {
+                let mut child = board.clone();
+                child.own_stones |= child.get_possible_move(evaluation.column);
+                if child.is_game_over() {
+                    continue;
+                }
+                child.swap_players();
+                let best_reply = engine
+                    .evaluate_all_moves(child, 3)
+                    .iter()
+                    .filter(|reply| reply.is_legal)
+                    .map(|reply| reply.score)
+                    .fold(SCORE_GUARD, f32::max);
+                assert!(
+                    (evaluation.score + best_reply * DISCOUNT_FACTOR).abs() < 1e-6,
+                    "{moves}: {evaluation:?}, best reply {best_reply}"
+                );
+            }
+        }
+    }
+
+    /// Gets the value of the board for the player to move with a plain negamax over the remaining
+    /// plies, without pruning and without a table. It scores the way the search does: a win in k plies
+    /// is discounted k-1 times and the leaves get the discounted heuristics of the move leading to them.
+    fn get_minimax_value(board: &mut BitBoard, remaining_plies: u32) -> f32 {
+        let moves: Vec<u64> = board
+            .get_all_possible_moves()
+            .map(|(coded_move, _)| coded_move)
+            .collect();
+        let mut best_value = SCORE_GUARD;
+        for coded_move in moves {
+            board.own_stones |= coded_move;
+            let value = if StandardGeometry::check_for_winning(board.own_stones) {
+                MAX_SCORE
+            } else if board.remaining_moves() == 0 {
+                0.0
+            } else if remaining_plies == 1 {
+                compute_heuristics(board, &HeuristicWeights::default(), CLAMP_GUARD_HEURISTIC)
+                    * DISCOUNT_FACTOR
+            } else {
+                board.swap_players();
+                let value = -get_minimax_value(board, remaining_plies - 1) * DISCOUNT_FACTOR;
+                board.swap_players();
+                value
+            };
+            board.own_stones ^= coded_move;
+            best_value = best_value.max(value);
+        }
+        best_value
+    }
+
+    /// Searches the board with a single iteration of the depth and gets the value of the root.
+    fn search_root(engine: &mut AlphaBeta, board: &BitBoard, depth: u32) -> f32 {
+        engine.bit_board = board.clone();
+        engine.max_depth = depth;
+        engine.is_time_limited = false;
+        engine.search_aborted = false;
+        engine.evaluate_next_move(-MAX_SCORE, MAX_SCORE, 0.0, 0).0
+    }
+
+    #[test]
+    fn test_known_wins_and_losses() {
+        // The first player has two stones next to each other on the bottom row, a third one makes
+        // two threats, that can not both be blocked. This is a win in three plies.
+        let board = BitBoard::from_move_string("3344").unwrap();
+        for depth in 3..6 {
+            let value = search_root(&mut get_engine(depth), &board, depth);
+            assert_eq!(
+                value,
+                MAX_SCORE * DISCOUNT_FACTOR * DISCOUNT_FACTOR,
+                "depth {depth}"
+            );
+        }
+        // Now the second player is the one with the double threat and the first player loses in two.
+        let board = BitBoard::from_move_string("3344517").unwrap();
+        assert_eq!(
+            search_root(&mut get_engine(4), &board, 4),
+            -MAX_SCORE * DISCOUNT_FACTOR
+        );
+    }
+
+    #[test]
+    fn test_search_finds_minimax_value() {
+        for moves in ["", "4", "4453", "3344256", "44444361", "1234567", "3344"] {
+            let mut board = BitBoard::from_move_string(moves).unwrap();
+            for depth in 1..=3 {
+                let expected = get_minimax_value(&mut board, depth);
+                let value = search_root(&mut get_engine(depth), &board, depth);
+                assert!(
+                    (value - expected).abs() < 1e-6,
+                    "{moves} depth {depth}: {value} instead of {expected}"
+                );
+
+                // The columns evaluated one by one, each with its own window.
+                for evaluation in get_engine(depth).evaluate_all_moves(board.clone(), depth) {
+                    if !evaluation.is_legal {
+                        continue;
+                    }
+                    let mut child = board.clone();
+                    child.own_stones |= child.get_possible_move(evaluation.column);
+                    let expected = if child.is_game_over() {
+                        evaluation.score
+                    } else if depth == 1 {
+                        compute_heuristics(
+                            &child,
+                            &HeuristicWeights::default(),
+                            CLAMP_GUARD_HEURISTIC,
+                        ) * DISCOUNT_FACTOR
+                    } else {
+                        child.swap_players();
+                        -get_minimax_value(&mut child, depth - 1) * DISCOUNT_FACTOR
+                    };
+                    assert!(
+                        (evaluation.score - expected).abs() < 1e-6,
+                        "{moves} depth {depth}: {evaluation:?} instead of {expected}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_warm_table_does_not_prefer_slower_win() {
+        // Column 5 wins in seven plies, columns 0, 2, 4 and 6 only in nine. With the table filled by
+        // the earlier moves, the engine used to stop on a win proven by the table and played column 0.
+        let line = "737466214122644652113564225334177";
+        let mut engine = get_engine(42);
+        for length in 30..=line.len() {
+            let board = BitBoard::from_move_string(&line[..length]).unwrap();
+            engine.get_best_move(board).unwrap();
+        }
+        let board = BitBoard::from_move_string(line).unwrap();
+        assert_eq!(engine.get_best_move(board), Ok(5));
+        assert_eq!(get_proven_distance(engine.get_best_score()), 7);
+    }
+
+    /// Searches the board and gets the statistics of the last iteration.
+    fn get_search_stats(engine: &mut AlphaBeta, board: &BitBoard) -> SearchStats {
+        let stats = Arc::new(Mutex::new(None));
+        let listener_stats = Arc::clone(&stats);
+        engine.set_progress_listener(Box::new(move |iteration_stats| {
+            *listener_stats.lock().unwrap() = Some(*iteration_stats);
+        }));
+        engine.get_best_move(board.clone()).unwrap();
+        stats.lock().unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_table_is_reused_on_the_next_move() {
+        let board = BitBoard::from_move_string("3452").unwrap();
+        let mut engine = get_engine(10);
+        let cold_stats = get_search_stats(&mut engine, &board);
+
+        // The same position again is taken from the table almost entirely.
+        let warm_stats = get_search_stats(&mut engine, &board);
+        assert!(warm_stats.get_table_hit_rate() > 0.9, "{warm_stats:?}");
+        assert!(warm_stats.nodes * 100 < cold_stats.nodes, "{warm_stats:?}");
+
+        // Two plies later a good part of the tree has been searched on the move before.
+        let mut later = board;
+        later
+            .play(engine.get_best_move(later.clone()).unwrap())
+            .unwrap();
+        later
+            .play(engine.get_best_move(later.clone()).unwrap())
+            .unwrap();
+        let warm_stats = get_search_stats(&mut engine, &later);
+        let cold_stats = get_search_stats(&mut get_engine(10), &later);
+        assert_eq!(warm_stats.depth, cold_stats.depth);
+        assert!(warm_stats.get_table_hit_rate() > cold_stats.get_table_hit_rate());
+        assert!(warm_stats.nodes < cold_stats.nodes);
+    }
+
+    #[test]
+    fn test_full_table_keeps_its_size() {
+        const MAX_ENTRIES: usize = 2000;
+        let mut engine = get_engine(8);
+        engine.max_table_entries = MAX_ENTRIES;
+        let mut board = BitBoard::from_move_string("4453").unwrap();
+        for _ in 0..6 {
+            let mov = engine.get_best_move(board.clone()).unwrap();
+            assert!(engine.hash_map.len() <= MAX_ENTRIES);
+            board.play(mov).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_solve_gets_the_distance() {
+        let board = BitBoard::from_move_string("737466214122644652113564225334177").unwrap();
+        let result = get_engine(42).solve(board);
+        assert_eq!(result.outcome, GameOutcome::Win);
+        assert_eq!((result.best_move, result.distance), (5, 7));
+        // The distance used to be reported as eleven plies.
+        let board = BitBoard::from_move_string("11514544414535551633346772617").unwrap();
+        let result = get_engine(42).solve(board);
+        assert_eq!(result.outcome, GameOutcome::Win);
+        assert_eq!((result.best_move, result.distance), (2, 9));
+    }
+
+    /// Lets the engine play a whole game against itself on the board and checks, that every move is
+    /// legal and that the game ends with the board showing the result.
+    fn play_full_game<G: BoardGeometry>(max_depth: u32) -> GameResult {
+        let mut engine = GenericAlphaBeta::<G>::with_config(SearchConfig {
+            max_depth,
+            move_time: None,
+            ..SearchConfig::default()
+        });
+        let mut board = GenericBitBoard::<G>::new();
+        while !board.is_game_over() {
+            let column = engine.get_best_move(board.clone()).unwrap();
+            board.play(column).unwrap();
+            assert_eq!(board.validate(), Ok(()));
+        }
+        let (result, lines) = board.get_winning_status_for_rendering();
+        assert_ne!(result, GameResult::Pending);
+        assert_eq!(lines.is_some(), result != GameResult::Draw);
+        result
+    }
+
+    #[test]
+    fn test_full_games_on_other_boards() {
+        play_full_game::<Geometry<5, 4>>(8);
+        play_full_game::<Geometry<8, 7>>(4);
+        play_full_game::<ConnectFiveGeometry>(3);
+    }
+
+    #[test]
+    fn test_wins_on_other_boards() {
+        // The first player has three in a row on the bottom and takes the fourth stone.
+        let board = GenericBitBoard::<Geometry<5, 4>>::from_move_string("112233").unwrap();
+        let mut engine = GenericAlphaBeta::<Geometry<5, 4>>::with_config(SearchConfig {
+            max_depth: 4,
+            move_time: None,
+            ..SearchConfig::default()
+        });
+        assert_eq!(engine.get_best_move(board), Ok(3));
+        let board = GenericBitBoard::<Geometry<8, 7>>::from_move_string("818283").unwrap();
+        let mut engine = GenericAlphaBeta::<Geometry<8, 7>>::with_config(SearchConfig {
+            max_depth: 4,
+            move_time: None,
+            ..SearchConfig::default()
+        });
+        // The first player completes the vertical four in the last column.
+        assert_eq!(engine.get_best_move(board), Ok(7));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Can not solve a finished game:\n.......")]
+    fn test_failed_assert_shows_the_board() {
+        // The first player completes the vertical four.
+        let mut board = BitBoard::from_move_string("414141").unwrap();
+        board.play(3).unwrap();
+        AlphaBeta::new().solve(board);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(
+        expected = "The board handed over is invalid: Err(FloatingStone { column: 2, row: 2 })"
+    )]
+    fn test_invalid_board_is_refused() {
+        let mut board = BitBoard::new();
+        board.set_computer_first(true);
+        board.own_stones = StandardGeometry::get_bit_representation(3, 0)
+            | StandardGeometry::get_bit_representation(2, 2);
+        board.opponent_stones = StandardGeometry::get_bit_representation(2, 0);
+        let _ = AlphaBeta::new().get_best_move(board);
+    }
 }
@@ -1,76 +1,585 @@
 //! This module contains the game board represented as a bit board.
 
-use crate::board_logic::bit_board_coding::{
-    BOARD_HEIGHT, BOARD_WIDTH, FULL_BOARD_MASK, check_for_winning, get_all_possible_moves,
-    get_bit_representation, get_winning_board,
-};
-use crate::board_logic::bit_board_coding::{flip_board, get_position_iterator, get_possible_move};
-use crate::debug_check_board_coordinates;
-use std::hash::Hash;
+use crate::board_logic::bit_board_coding::{BoardGeometry, StandardGeometry, WinningLine};
+use std::fmt;
 use std::iter::Iterator;
+use std::marker::PhantomData;
 use std::mem;
 
-/// Encodes the game result needed for the drawing and state system.
+/// Encodes the game result needed for the drawing and state system. The timeouts are never derived
+/// from the board, they are set by games played with a clock.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum GameResult {
     Pending,
     Draw,
     FirstPlayerWon,
     SecondPlayerWon,
+    /// The first player ran out of time, so the second player has won.
+    FirstPlayerTimeout,
+    /// The second player ran out of time, so the first player has won.
+    SecondPlayerTimeout,
 }
 
-/// The bitboard has two representations for own and opponent stones.
+impl GameResult {
+    /// Checks if the first player has won, on the board or on time.
+    pub fn is_first_player_win(self) -> bool {
+        matches!(
+            self,
+            GameResult::FirstPlayerWon | GameResult::SecondPlayerTimeout
+        )
+    }
+
+    /// Checks if the second player has won, on the board or on time.
+    pub fn is_second_player_win(self) -> bool {
+        matches!(
+            self,
+            GameResult::SecondPlayerWon | GameResult::FirstPlayerTimeout
+        )
+    }
+}
+
+/// The phase of the game, derived from the amount of stones on the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GamePhase {
+    Opening,
+    Midgame,
+    Endgame,
+}
+
+/// The opening lasts, while less than this fraction of the board is filled.
+const OPENING_FRACTION: f32 = 0.25;
+
+/// The endgame starts, when at least this fraction of the board is filled.
+const ENDGAME_FRACTION: f32 = 0.65;
+
+/// The side of a player on the board. The computer owns the own stones.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Side {
+    Computer,
+    Player,
+}
+
+impl Side {
+    /// Converts into the flag the move functions of the board expect.
+    pub fn is_computer(self) -> bool {
+        self == Side::Computer
+    }
+}
+
+/// The rules the game is played with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RuleSet {
+    /// The stones can only be dropped into the columns.
+    #[default]
+    Classic,
+    /// Instead of dropping a stone a player may pop an own stone out of the bottom row, the stones above
+    /// fall down one row. If a pop completes a four for both players, the player who popped wins. The game
+    /// is a draw, if the player to move can neither drop nor pop.
+    PopOut,
+}
+
+/// The errors that can occur when a move is played on the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    /// The column does not exist.
+    InvalidColumn { column: u32 },
+    /// The column is already full.
+    ColumnFull { column: u32 },
+    /// The game is already over.
+    GameOver,
+    /// Stones can only be popped under the Pop Out rules.
+    PopNotAllowed,
+    /// The bottom stone of the column does not belong to the player, who wants to pop it.
+    NotOwnBottomStone { column: u32 },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::InvalidColumn { column } => write!(f, "column {column} does not exist"),
+            MoveError::ColumnFull { column } => write!(f, "column {column} is full"),
+            MoveError::GameOver => write!(f, "the game is already over"),
+            MoveError::PopNotAllowed => write!(f, "the rules do not allow to pop stones"),
+            MoveError::NotOwnBottomStone { column } => {
+                write!(f, "the bottom stone of column {column} is not an own stone")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// The errors that can occur when a move string gets parsed. Positions are the zero based character index.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveStringError {
+    /// The character is not a column digit from 1 to the board width.
+    InvalidCharacter { position: usize, character: char },
+    /// The column of the move is already full.
+    ColumnFull { position: usize, column: u32 },
+    /// The game was already over before this move.
+    GameAlreadyOver { position: usize },
+    /// The string has more moves than there are cells on the board.
+    TooLong { maximum: usize },
+}
+
+impl fmt::Display for MoveStringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveStringError::InvalidCharacter {
+                position,
+                character,
+            } => write!(f, "invalid column '{character}' at position {position}"),
+            MoveStringError::ColumnFull { position, column } => {
+                write!(f, "column {} is full at position {position}", column + 1)
+            }
+            MoveStringError::GameAlreadyOver { position } => {
+                write!(f, "move at position {position} after the game is over")
+            }
+            MoveStringError::TooLong { maximum } => {
+                write!(f, "more than {maximum} moves do not fit on the board")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MoveStringError {}
+
+/// The errors that can occur when an ASCII grid gets parsed. Rows are counted from the top, columns from the
+/// left, both zero based.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BoardParseError {
+    /// The grid does not have as many rows as the board is high.
+    WrongRowCount { rows: usize, expected: usize },
+    /// The row does not have as many cells as the board is wide.
+    WrongRowLength {
+        row: usize,
+        length: usize,
+        expected: usize,
+    },
+    /// The cell is neither 'X', 'O' nor '.'.
+    InvalidCharacter {
+        row: usize,
+        column: usize,
+        character: char,
+    },
+    /// The stone has an empty cell below it.
+    FloatingStone { row: usize, column: usize },
+    /// The first player needs to have as many stones as the second player or one more.
+    StoneCountMismatch { first: u32, second: u32 },
+    /// Both players have a four, which no game can get to.
+    BothPlayersWon,
+}
+
+impl fmt::Display for BoardParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardParseError::WrongRowCount { rows, expected } => {
+                write!(f, "expected {expected} rows, found {rows}")
+            }
+            BoardParseError::WrongRowLength {
+                row,
+                length,
+                expected,
+            } => write!(f, "row {row} has {length} cells instead of {expected}"),
+            BoardParseError::InvalidCharacter {
+                row,
+                column,
+                character,
+            } => write!(
+                f,
+                "invalid cell '{character}' in row {row}, column {column}"
+            ),
+            BoardParseError::FloatingStone { row, column } => {
+                write!(
+                    f,
+                    "stone in row {row}, column {column} has nothing below it"
+                )
+            }
+            BoardParseError::StoneCountMismatch { first, second } => write!(
+                f,
+                "the first player has {first} stones and the second player {second}"
+            ),
+            BoardParseError::BothPlayersWon => write!(f, "both players have a four"),
+        }
+    }
+}
+
+impl std::error::Error for BoardParseError {}
+
+/// The errors that can occur when a position gets read from a text, that can be a move string or an
+/// ASCII grid.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PositionTextError {
+    /// The text has nothing but whitespace.
+    Empty,
+    /// The text has been taken for a move string, which can not be parsed.
+    MoveString(MoveStringError),
+    /// The text has been taken for an ASCII grid, which can not be parsed.
+    Grid(BoardParseError),
+}
+
+impl fmt::Display for PositionTextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionTextError::Empty => write!(f, "there is no position"),
+            PositionTextError::MoveString(error) => write!(f, "move string: {error}"),
+            PositionTextError::Grid(error) => write!(f, "grid: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for PositionTextError {}
+
+/// The invariants a board can violate, when it has been constructed from the outside. Columns and rows
+/// are counted zero based from the left and from the bottom.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BoardInvariantError {
+    /// Stones are set on the bits above the squares of the board.
+    StonesOutsideBoard,
+    /// The own and opponent stones occupy the same squares.
+    OverlappingStones,
+    /// The stone has an empty square below it.
+    FloatingStone { column: u32, row: u32 },
+    /// The first player needs to have made as many turns as the second player or one more. The turns
+    /// are the stones, the passes for the handicap of the other side and the pops count as well.
+    StoneCountMismatch { first: u32, second: u32 },
+    /// Both players have a four on the board, which only a pop can lead to.
+    BothPlayersWon,
+}
+
+impl fmt::Display for BoardInvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardInvariantError::StonesOutsideBoard => write!(f, "stones outside of the board"),
+            BoardInvariantError::OverlappingStones => {
+                write!(f, "both players have stones on the same square")
+            }
+            BoardInvariantError::FloatingStone { column, row } => {
+                write!(
+                    f,
+                    "stone in column {column}, row {row} has nothing below it"
+                )
+            }
+            BoardInvariantError::StoneCountMismatch { first, second } => write!(
+                f,
+                "the first player has made {first} turns and the second player {second}"
+            ),
+            BoardInvariantError::BothPlayersWon => write!(f, "both players have a four"),
+        }
+    }
+}
+
+impl std::error::Error for BoardInvariantError {}
+
+/// The bitboard has two representations for own and opponent stones. The geometry decides on the
+/// dimensions of the board, the standard board is [`BitBoard`].
 #[derive(Clone)]
-pub struct BitBoard {
+pub struct GenericBitBoard<G: BoardGeometry> {
     pub own_stones: u64,
     pub opponent_stones: u64,
     // The boards represents from the perspective of the computer in default.
     computer_first: bool,
+    // The moves applied through the UI functions as coded move and the computer flag in the order played.
+    move_history: Vec<(u64, bool)>,
+    // The side, that places extra stones in its first turn as a handicap, and the amount of extra stones.
+    handicap: Option<(Side, u32)>,
+    // The rules decide, if stones may be popped.
+    rule_set: RuleSet,
+    // The stones the own and the opponent side have popped out of the board, needed to count the turns.
+    own_pops: u32,
+    opponent_pops: u32,
+    geometry: PhantomData<G>,
 }
 
-/// This is the symmetry independent coding that can be used for hash keys in the transposition table.
-/// It has a representation for own and opponent stones.
-#[derive(Hash, PartialEq, Eq, Clone)]
-pub struct SymmetryIndependentPosition {
-    pub own: u64,
-    pub opp: u64,
+/// The bit board of the standard 7x6 game.
+pub type BitBoard = GenericBitBoard<StandardGeometry>;
+
+impl<G: BoardGeometry> Default for GenericBitBoard<G> {
+    fn default() -> GenericBitBoard<G> {
+        GenericBitBoard::new()
+    }
+}
+
+/// Shows the ASCII grid of [`GenericBitBoard::to_ascii`] followed by the side to move, so that the
+/// board can be put into assertion messages.
+impl<G: BoardGeometry> fmt::Display for GenericBitBoard<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{:?} to move, computer first: {}",
+            self.to_ascii(),
+            self.side_to_move(),
+            self.computer_first
+        )
+    }
+}
+
+/// Shows the raw bit masks and the symmetry independent key next to the grid, which is what is needed,
+/// when the transposition table is involved.
+impl<G: BoardGeometry> fmt::Debug for GenericBitBoard<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "own: {:#018x}, opponent: {:#018x}, key: {:#018x}",
+            self.own_stones,
+            self.opponent_stones,
+            self.get_symmetry_independent_key()
+        )?;
+        write!(f, "{self}")
+    }
 }
 
-impl BitBoard {
-    pub fn new() -> BitBoard {
-        BitBoard {
+impl<G: BoardGeometry> GenericBitBoard<G> {
+    pub fn new() -> GenericBitBoard<G> {
+        GenericBitBoard {
             own_stones: 0,
             opponent_stones: 0,
             computer_first: false,
+            move_history: Vec::new(),
+            handicap: None,
+            rule_set: RuleSet::Classic,
+            own_pops: 0,
+            opponent_pops: 0,
+            geometry: PhantomData,
         }
     }
 
+    /// Parses the notation used by most Connect-4 solvers, where a string of digits from 1 to 7 contains
+    /// the columns in the order they have been played, starting with the first player. The board is set up
+    /// from the perspective of the player to move, these are the own stones like the ai expects it.
+    pub fn from_move_string(moves: &str) -> Result<GenericBitBoard<G>, MoveStringError> {
+        // Checked first, so that an overlong input is not read to its end.
+        let maximum = (G::WIDTH * G::HEIGHT) as usize;
+        if moves.chars().nth(maximum).is_some() {
+            return Err(MoveStringError::TooLong { maximum });
+        }
+        let mut board = GenericBitBoard::new();
+        // The first player is the one to move, whenever an even amount of stones has been played.
+        let first_player_to_move = moves.chars().count().is_multiple_of(2);
+        board.set_computer_first(first_player_to_move);
+
+        for (position, character) in moves.chars().enumerate() {
+            let column = match character.to_digit(10) {
+                Some(digit) if (1..=G::WIDTH).contains(&digit) => digit - 1,
+                _ => {
+                    return Err(MoveStringError::InvalidCharacter {
+                        position,
+                        character,
+                    });
+                }
+            };
+            if board.is_game_over() {
+                return Err(MoveStringError::GameAlreadyOver { position });
+            }
+            let coded_move = board.get_possible_move(column);
+            if coded_move == 0 {
+                return Err(MoveStringError::ColumnFull { position, column });
+            }
+            let is_first_player = position % 2 == 0;
+            board.apply_move(coded_move, is_first_player == first_player_to_move);
+        }
+
+        Ok(board)
+    }
+
+    /// Generates the move string that can be read in with [`BitBoard::from_move_string`] from the move
+    /// history. Returns none, if the history does not contain all stones, like for a board read in from
+    /// an ASCII grid, or if stones have been popped.
+    pub fn to_move_string(&self) -> Option<String> {
+        let stones = (self.own_stones | self.opponent_stones).count_ones() as usize;
+        if self.move_history.len() != stones {
+            return None;
+        }
+
+        let moves = self
+            .move_history
+            .iter()
+            .map(|&(coded_move, _)| {
+                char::from_digit(coded_move.trailing_zeros() % G::STRIDE + 1, 10)
+                    .expect("Columns are single digits.")
+            })
+            .collect();
+        Some(moves)
+    }
+
+    /// Parses a board from an ASCII grid with the top row first. 'X' are the stones of the first player,
+    /// 'O' the ones of the second player and '.' empty cells. Blank lines and surrounding whitespace are
+    /// ignored. As for the move string, the board is set up from the perspective of the player to move.
+    /// The grid has to obey gravity, the stone counts have to fit to alternating moves and only one of
+    /// the players may have a four.
+    pub fn from_ascii(grid: &str) -> Result<GenericBitBoard<G>, BoardParseError> {
+        let rows: Vec<&str> = grid
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        if rows.len() != G::HEIGHT as usize {
+            return Err(BoardParseError::WrongRowCount {
+                rows: rows.len(),
+                expected: G::HEIGHT as usize,
+            });
+        }
+
+        let mut first_stones = 0;
+        let mut second_stones = 0;
+        for (row, line) in rows.iter().enumerate() {
+            let length = line.chars().count();
+            if length != G::WIDTH as usize {
+                return Err(BoardParseError::WrongRowLength {
+                    row,
+                    length,
+                    expected: G::WIDTH as usize,
+                });
+            }
+            let y = G::HEIGHT - 1 - row as u32;
+            for (column, character) in line.chars().enumerate() {
+                let bit = G::get_bit_representation(column as u32, y);
+                match character {
+                    'X' => first_stones |= bit,
+                    'O' => second_stones |= bit,
+                    '.' => {}
+                    _ => {
+                        return Err(BoardParseError::InvalidCharacter {
+                            row,
+                            column,
+                            character,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Every stone needs a stone or the ground below it.
+        let occupied = first_stones | second_stones;
+        let floating =
+            occupied & !(G::clip_shift(occupied, G::DIR_INCREMENT[1]) | G::BOTTOM_FILL_MASK);
+        if let Some((column, y)) = G::get_position_iterator(floating).next() {
+            return Err(BoardParseError::FloatingStone {
+                row: (G::HEIGHT - 1 - y) as usize,
+                column: column as usize,
+            });
+        }
+
+        let first = first_stones.count_ones();
+        let second = second_stones.count_ones();
+        if first != second && first != second + 1 {
+            return Err(BoardParseError::StoneCountMismatch { first, second });
+        }
+        // The game ends with the first four, so there can not be one on both sides.
+        if G::check_for_winning(first_stones) && G::check_for_winning(second_stones) {
+            return Err(BoardParseError::BothPlayersWon);
+        }
+
+        // The first player is the one to move, if both have the same amount of stones.
+        let first_player_to_move = first == second;
+        let mut board = GenericBitBoard::new();
+        board.set_computer_first(first_player_to_move);
+        if first_player_to_move {
+            board.own_stones = first_stones;
+            board.opponent_stones = second_stones;
+        } else {
+            board.own_stones = second_stones;
+            board.opponent_stones = first_stones;
+        }
+
+        Ok(board)
+    }
+
+    /// Parses a position from a text, that is either a move string or an ASCII grid, the format is found out
+    /// from the text. A text with several lines or the cells of a grid, which are 'X', 'O' and '.', is read
+    /// as an ASCII grid with [`BitBoard::from_ascii`], any other text as a move string with
+    /// [`BitBoard::from_move_string`]. Whitespace around the text is ignored, so that a text pasted with a
+    /// line break at its end can be read.
+    pub fn from_position_text(text: &str) -> Result<GenericBitBoard<G>, PositionTextError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(PositionTextError::Empty);
+        }
+        let is_grid = text.lines().nth(1).is_some()
+            || text
+                .chars()
+                .any(|character| matches!(character, 'X' | 'O' | '.'));
+        if is_grid {
+            GenericBitBoard::from_ascii(text).map_err(PositionTextError::Grid)
+        } else {
+            GenericBitBoard::from_move_string(text).map_err(PositionTextError::MoveString)
+        }
+    }
+
+    /// Generates the ASCII grid with the top row first, that can be read in with [`BitBoard::from_ascii`].
+    /// 'X' are the stones of the first player, 'O' the ones of the second player and '.' empty cells.
+    pub fn to_ascii(&self) -> String {
+        let mut cells = vec![vec!['.'; G::WIDTH as usize]; G::HEIGHT as usize];
+        for (x, y, first) in self.get_board_positioning() {
+            cells[(G::HEIGHT - 1 - y) as usize][x as usize] = if first { 'X' } else { 'O' };
+        }
+
+        let mut result = String::with_capacity(((G::WIDTH + 1) * G::HEIGHT) as usize);
+        for row in cells {
+            result.extend(row);
+            result.push('\n');
+        }
+        result
+    }
+
     /// Resets the board at the end of the game.
     pub fn reset(&mut self) {
         self.own_stones = 0;
         self.opponent_stones = 0;
+        self.own_pops = 0;
+        self.opponent_pops = 0;
+        self.move_history.clear();
     }
 
-    /// Generates a structure that looks the same with its symmetrically identical board.
-    /// This is meant to be used for the transposition table.  
-    pub fn get_symmetry_independent_position(&self) -> SymmetryIndependentPosition {
-        let flipped_own = flip_board(self.own_stones);
-        let flipped_opp = flip_board(self.opponent_stones);
+    /// Gets the moves applied with *apply_move* as coded move and computer flag in the order played.
+    /// Pops are coded as described in [`BoardGeometry::get_pop_move`]. Boards read from an ASCII grid
+    /// have no history.
+    pub fn get_move_history(&self) -> &[(u64, bool)] {
+        &self.move_history
+    }
 
-        // Sort lexicographically.
-        if self.own_stones < flipped_own
-            || (self.own_stones == flipped_own && self.opponent_stones < flipped_opp)
-        {
-            SymmetryIndependentPosition {
-                own: self.own_stones,
-                opp: self.opponent_stones,
-            }
+    /// Forgets the move history, the stones stay on the board. The ai does this, as it clones the board
+    /// a lot and has no use for the history.
+    pub fn clear_move_history(&mut self) {
+        self.move_history.clear();
+    }
+
+    /// Takes back the last move applied with *apply_move* or *pop_bottom*. Returns the column of the move
+    /// or none, if there is no move left in the history.
+    pub fn undo_last_move(&mut self) -> Option<u32> {
+        let (coded_move, is_computer) = self.move_history.pop()?;
+        let column = coded_move.trailing_zeros() % G::STRIDE;
+        if G::is_pop_move(coded_move) {
+            self.unpop_stone(column, is_computer);
         } else {
-            SymmetryIndependentPosition {
-                own: flipped_own,
-                opp: flipped_opp,
-            }
+            self.own_stones &= !coded_move;
+            self.opponent_stones &= !coded_move;
+        }
+        Some(column)
+    }
+
+    /// Takes back the last move of both players, so that the same player is to move again. Returns the
+    /// amount of moves taken back, which is less than two at the beginning of the game.
+    pub fn undo_last_full_turn(&mut self) -> u32 {
+        let mut undone = 0;
+        while undone < 2 && self.undo_last_move().is_some() {
+            undone += 1;
         }
+        undone
+    }
+
+    /// Encodes the position from the perspective of the player to move uniquely in one number.
+    pub fn to_key(&self) -> G::Key {
+        G::get_position_key(self.own_stones, self.own_stones | self.opponent_stones)
+    }
+
+    /// Generates a key that is the same for the symmetrically identical board, by taking the smaller one
+    /// of both keys. This is meant to be used for the transposition table.
+    pub fn get_symmetry_independent_key(&self) -> G::Key {
+        let flipped_own = G::flip_board(self.own_stones);
+        let flipped_occupied = G::flip_board(self.own_stones | self.opponent_stones);
+        self.to_key()
+            .min(G::get_position_key(flipped_own, flipped_occupied))
     }
 
     /// Gets adjusted from the outside to get the coloring right.
@@ -83,9 +592,64 @@ impl BitBoard {
         self.computer_first
     }
 
-    /// Swaps the players needed for the NEGAMAX algorithm.
+    /// Lets the side place the indicated amount of extra stones in its first turn, the other side passes
+    /// for that long. Like who moves first, the handicap is kept, when the board gets reset.
+    pub fn set_handicap(&mut self, side: Side, extra_stones: u32) {
+        self.handicap = (extra_stones > 0).then_some((side, extra_stones));
+    }
+
+    /// Gets the side with the handicap and the amount of extra stones it places in its first turn.
+    pub fn get_handicap(&self) -> Option<(Side, u32)> {
+        self.handicap
+    }
+
+    /// Sets the rules the game is played with. Like the handicap, the rules are kept, when the board gets
+    /// reset.
+    pub fn set_rule_set(&mut self, rule_set: RuleSet) {
+        self.rule_set = rule_set;
+    }
+
+    /// Gets the rules the game is played with.
+    pub fn get_rule_set(&self) -> RuleSet {
+        self.rule_set
+    }
+
+    /// Swaps the players needed for the NEGAMAX algorithm. The computer becomes the other player, so
+    /// whether it moved first, who has the handicap and who has popped flip as well and the board stays
+    /// valid.
     pub fn swap_players(&mut self) {
         mem::swap(&mut self.own_stones, &mut self.opponent_stones);
+        mem::swap(&mut self.own_pops, &mut self.opponent_pops);
+        self.computer_first = !self.computer_first;
+        self.handicap = self.handicap.map(|(side, extra_stones)| {
+            let other_side = if side.is_computer() {
+                Side::Player
+            } else {
+                Side::Computer
+            };
+            (other_side, extra_stones)
+        });
+    }
+
+    /// Gets the amount of turns the first and the second player have made. This is the amount of their
+    /// stones, only the turns of a side passing for the handicap of the other side count as well. A pop
+    /// is a turn, that takes a stone away, so it counts twice.
+    fn get_turn_counts(&self) -> (u32, u32) {
+        let mut computer_turns = self.own_stones.count_ones() + 2 * self.own_pops;
+        let mut player_turns = self.opponent_stones.count_ones() + 2 * self.opponent_pops;
+        // Every extra stone of the handicap lets the other side pass right after it.
+        match self.handicap {
+            Some((Side::Computer, extra_stones)) => {
+                player_turns += computer_turns.min(extra_stones)
+            }
+            Some((Side::Player, extra_stones)) => computer_turns += player_turns.min(extra_stones),
+            None => {}
+        }
+        if self.computer_first {
+            (computer_turns, player_turns)
+        } else {
+            (player_turns, computer_turns)
+        }
     }
 
     /// Returns a list of stones of positions and indications, if they are first player stones.
@@ -94,11 +658,11 @@ impl BitBoard {
         let first_stones;
         let second_stones;
         if self.computer_first {
-            first_stones = get_position_iterator(self.own_stones);
-            second_stones = get_position_iterator(self.opponent_stones);
+            first_stones = G::get_position_iterator(self.own_stones);
+            second_stones = G::get_position_iterator(self.opponent_stones);
         } else {
-            first_stones = get_position_iterator(self.opponent_stones);
-            second_stones = get_position_iterator(self.own_stones);
+            first_stones = G::get_position_iterator(self.opponent_stones);
+            second_stones = G::get_position_iterator(self.own_stones);
         }
 
         first_stones
@@ -110,17 +674,199 @@ impl BitBoard {
     /// Gets in general a possible move for the board, Returns eiter 0 if column is full or returns
     /// the correctly set bit.
     pub fn get_possible_move(&self, column: u32) -> u64 {
-        debug_check_board_coordinates!(col: column);
-        get_possible_move(self.own_stones | self.opponent_stones, column)
+        G::get_possible_move(self.own_stones | self.opponent_stones, column)
     }
 
     /// Gets the destination height for a move. This is the slot number,
     /// where the move will wind up. The method is slow and only be intended to be used
     /// for rendering purposes. Returns none of the move is not possible.
     pub fn get_move_destination(&self, column: u32) -> Option<u32> {
-        debug_check_board_coordinates!(col: column);
-        let move_spot = get_possible_move(self.own_stones | self.opponent_stones, column);
-        (0..BOARD_HEIGHT).find(|&y| move_spot & get_bit_representation(column, y) != 0)
+        let move_spot = G::get_possible_move(self.own_stones | self.opponent_stones, column);
+        (0..G::HEIGHT).find(|&y| move_spot & G::get_bit_representation(column, y) != 0)
+    }
+
+    /// Gets the amount of stones on the board.
+    #[inline(always)]
+    pub fn stone_count(&self) -> u32 {
+        (self.own_stones | self.opponent_stones).count_ones()
+    }
+
+    /// Gets the amount of plies played so far, which is the same as the amount of stones.
+    pub fn ply(&self) -> u32 {
+        self.stone_count()
+    }
+
+    /// Checks if no stone has been played yet.
+    pub fn is_empty(&self) -> bool {
+        self.stone_count() == 0
+    }
+
+    /// Gets the amount of moves, that can still be made until the board is full.
+    #[inline(always)]
+    pub fn remaining_moves(&self) -> u32 {
+        G::WIDTH * G::HEIGHT - self.stone_count()
+    }
+
+    /// Gets the phase of the game from the fraction of the board, that is filled.
+    pub fn phase(&self) -> GamePhase {
+        let filled = self.stone_count() as f32 / (G::WIDTH * G::HEIGHT) as f32;
+        if filled < OPENING_FRACTION {
+            GamePhase::Opening
+        } else if filled < ENDGAME_FRACTION {
+            GamePhase::Midgame
+        } else {
+            GamePhase::Endgame
+        }
+    }
+
+    /// Gets the side, whose turn it is. This is derived from the amount of stones, who started and the
+    /// handicap.
+    pub fn side_to_move(&self) -> Side {
+        let (first_turns, second_turns) = self.get_turn_counts();
+        let first_to_move = first_turns == second_turns;
+        if first_to_move == self.computer_first {
+            Side::Computer
+        } else {
+            Side::Player
+        }
+    }
+
+    /// Checks if the board could have been reached in a real game. This is meant for boards constructed
+    /// from the outside, as the ai produces nonsense on invalid boards.
+    pub fn validate(&self) -> Result<(), BoardInvariantError> {
+        let occupied = self.own_stones | self.opponent_stones;
+        if occupied & !G::FULL_BOARD_MASK != 0 {
+            return Err(BoardInvariantError::StonesOutsideBoard);
+        }
+        if self.own_stones & self.opponent_stones != 0 {
+            return Err(BoardInvariantError::OverlappingStones);
+        }
+
+        let floating =
+            occupied & !(G::clip_shift(occupied, G::DIR_INCREMENT[1]) | G::BOTTOM_FILL_MASK);
+        if let Some((column, row)) = G::get_position_iterator(floating).next() {
+            return Err(BoardInvariantError::FloatingStone { column, row });
+        }
+
+        let (first, second) = self.get_turn_counts();
+        if first != second && first != second + 1 {
+            return Err(BoardInvariantError::StoneCountMismatch { first, second });
+        }
+
+        if self.rule_set == RuleSet::Classic
+            && G::check_for_winning(self.own_stones)
+            && G::check_for_winning(self.opponent_stones)
+        {
+            return Err(BoardInvariantError::BothPlayersWon);
+        }
+
+        Ok(())
+    }
+
+    /// Plays the column for the side to move and returns the coded move. This is the safe way for the UI
+    /// to make moves, as a side can not move twice in a row.
+    pub fn play(&mut self, column: u32) -> Result<u64, MoveError> {
+        if column >= G::WIDTH {
+            return Err(MoveError::InvalidColumn { column });
+        }
+        if self.is_game_over() {
+            return Err(MoveError::GameOver);
+        }
+        let coded_move = self.get_possible_move(column);
+        if coded_move == 0 {
+            return Err(MoveError::ColumnFull { column });
+        }
+        self.apply_move(coded_move, self.side_to_move().is_computer());
+        Ok(coded_move)
+    }
+
+    /// Plays the slot for the side to move and returns the coded move. The slots are the columns for
+    /// the drops followed by the columns for the pops, which is how the ai hands out its moves.
+    pub fn play_slot(&mut self, slot: u32) -> Result<u64, MoveError> {
+        if slot < G::WIDTH {
+            return self.play(slot);
+        }
+        let column = slot - G::WIDTH;
+        self.pop_bottom(column, self.side_to_move().is_computer())?;
+        Ok(G::get_pop_move(column))
+    }
+
+    /// Checks if the side may pop the bottom stone out of the column, which needs the Pop Out rules and
+    /// an own stone at the bottom.
+    pub fn can_pop(&self, column: u32, is_computer: bool) -> bool {
+        let stones = if is_computer {
+            self.own_stones
+        } else {
+            self.opponent_stones
+        };
+        self.rule_set == RuleSet::PopOut
+            && column < G::WIDTH
+            && stones & G::get_bit_representation(column, 0) != 0
+    }
+
+    /// Pops the bottom stone of the side out of the column, the stones above fall down one row. This is
+    /// meant for the UI, the pop is recorded in the move history.
+    pub fn pop_bottom(&mut self, column: u32, is_computer: bool) -> Result<(), MoveError> {
+        if self.rule_set != RuleSet::PopOut {
+            return Err(MoveError::PopNotAllowed);
+        }
+        if column >= G::WIDTH {
+            return Err(MoveError::InvalidColumn { column });
+        }
+        if self.is_game_over() {
+            return Err(MoveError::GameOver);
+        }
+        if !self.can_pop(column, is_computer) {
+            return Err(MoveError::NotOwnBottomStone { column });
+        }
+        debug_assert!(
+            self.side_to_move().is_computer() == is_computer,
+            "The same side tries to move twice in a row."
+        );
+        self.pop_stone(column, is_computer);
+        self.move_history
+            .push((G::get_pop_move(column), is_computer));
+        Ok(())
+    }
+
+    /// Pops the bottom stone of the own stones out of the column without recording it. This is meant for
+    /// the ai, the stone has to be there.
+    #[inline(always)]
+    pub fn pop_own_stone(&mut self, column: u32) {
+        self.pop_stone(column, true);
+    }
+
+    /// Takes back *pop_own_stone*.
+    #[inline(always)]
+    pub fn unpop_own_stone(&mut self, column: u32) {
+        self.unpop_stone(column, true);
+    }
+
+    /// Lets the stones of both sides in the column fall down one row, the bottom one belongs to the side.
+    #[inline(always)]
+    fn pop_stone(&mut self, column: u32, is_computer: bool) {
+        self.own_stones = G::pop_column(self.own_stones, column);
+        self.opponent_stones = G::pop_column(self.opponent_stones, column);
+        if is_computer {
+            self.own_pops += 1;
+        } else {
+            self.opponent_pops += 1;
+        }
+    }
+
+    /// Lets the stones in the column rise one row again and puts the stone of the side back to the bottom.
+    #[inline(always)]
+    fn unpop_stone(&mut self, column: u32, is_computer: bool) {
+        self.own_stones = G::unpop_column(self.own_stones, column);
+        self.opponent_stones = G::unpop_column(self.opponent_stones, column);
+        let bottom = G::get_bit_representation(column, 0);
+        if is_computer {
+            self.own_stones |= bottom;
+            self.own_pops -= 1;
+        } else {
+            self.opponent_stones |= bottom;
+            self.opponent_pops -= 1;
+        }
     }
 
     /// Simplifies making a move on a column on the outside. It has to be guarantied that move is possible.
@@ -131,42 +877,88 @@ impl BitBoard {
         self.apply_move(coded_move, is_computer);
     }
 
-    /// Applies an encoded move has handed out by the function *get_possible_move*.
-    /// This function is meant to be used for UI interaction and not the AI.
-    pub fn apply_move(&mut self, coded_move: u64, is_computer: bool) {
+    /// Places a stone of the side in the column, no matter whose turn it is. This is meant for setting up
+    /// positions in the board editor, the board may get invalid this way and has to be checked with
+    /// *validate*. The stone is recorded in the move history, so that *undo_last_move* takes it back.
+    /// Returns false, if the column is full.
+    pub fn place_stone(&mut self, column: u32, is_computer: bool) -> bool {
+        let coded_move = self.get_possible_move(column);
+        if coded_move == 0 {
+            return false;
+        }
         if is_computer {
             self.own_stones |= coded_move;
         } else {
             self.opponent_stones |= coded_move;
         }
+        self.move_history.push((coded_move, is_computer));
+        true
+    }
+
+    /// Applies an encoded move has handed out by the function *get_possible_move*. Pops from the move
+    /// history get applied as well. This function is meant to be used for UI interaction and not the AI.
+    /// The move is recorded in the move history.
+    pub fn apply_move(&mut self, coded_move: u64, is_computer: bool) {
+        debug_assert!(
+            self.side_to_move().is_computer() == is_computer,
+            "The same side tries to move twice in a row."
+        );
+        if G::is_pop_move(coded_move) {
+            self.pop_stone(coded_move.trailing_zeros() % G::STRIDE, is_computer);
+        } else if is_computer {
+            self.own_stones |= coded_move;
+        } else {
+            self.opponent_stones |= coded_move;
+        }
+        self.move_history.push((coded_move, is_computer));
     }
 
     /// Checks if we have a draw situation under the assumption that we do not have a winning
-    /// one.
+    /// one. Under the Pop Out rules the side to move may still pop, when the board is full.
     #[inline(always)]
     pub fn check_for_draw_if_not_winning(&self) -> bool {
-        let compound = self.opponent_stones | self.own_stones;
-        compound == FULL_BOARD_MASK
+        self.remaining_moves() == 0
+            && (self.rule_set == RuleSet::Classic
+                || !(0..G::WIDTH)
+                    .any(|column| self.can_pop(column, self.side_to_move().is_computer())))
     }
 
     /// Gets an iterator of all possible moves. This method is meant for the ai.
     /// The iterator returns the move and the original move index.
     #[inline(always)]
     pub fn get_all_possible_moves(&self) -> impl Iterator<Item = (u64, u32)> {
-        get_all_possible_moves(self.opponent_stones | self.own_stones)
+        G::get_all_possible_moves(self.opponent_stones | self.own_stones)
+    }
+
+    /// Gets the free squares, where a stone of the indicated player would complete a four. The squares
+    /// do not need to be playable right now, the UI can use it to warn about threats.
+    pub fn winning_squares(&self, is_computer: bool) -> u64 {
+        let free_spots = !(self.own_stones | self.opponent_stones) & G::FULL_BOARD_MASK;
+        if is_computer {
+            G::get_winning_squares(self.own_stones, free_spots)
+        } else {
+            G::get_winning_squares(self.opponent_stones, free_spots)
+        }
+    }
+
+    /// Gets the playable squares, where a stone of the indicated player wins on the spot. Squares with an
+    /// empty square below are not contained. The UI can use it to warn about ignored threats.
+    pub fn immediate_winning_moves(&self, is_computer: bool) -> u64 {
+        self.winning_squares(is_computer)
+            & G::get_playable_squares(self.own_stones | self.opponent_stones)
     }
 
     /// Easy game over method to be used for the game state system to determine the follow-up states.
     pub fn is_game_over(&self) -> bool {
         self.check_for_draw_if_not_winning()
-            || check_for_winning(self.opponent_stones)
-            || check_for_winning(self.own_stones)
+            || G::check_for_winning(self.opponent_stones)
+            || G::check_for_winning(self.own_stones)
     }
 
     /// Analyzes the winning condition for the game board to be used in combination with the user interface
-    /// system. It returns the situation and if one party has won. It also returns the stone coordinates of the
-    /// stones generating four stones. The result may be more than four stones.
-    pub fn get_winning_status_for_rendering(&self) -> (GameResult, Option<Vec<(u32, u32)>>) {
+    /// system. It returns the situation and if one party has won. It also returns the lines of four stones,
+    /// there may be more than one of them.
+    pub fn get_winning_status_for_rendering(&self) -> (GameResult, Option<Vec<WinningLine>>) {
         let first_board;
         let second_board;
 
@@ -178,15 +970,18 @@ impl BitBoard {
             second_board = self.own_stones;
         }
 
-        if check_for_winning(first_board) {
+        // After a pop both players may have a four, then the player who popped and is not to move wins.
+        let first_to_move = self.side_to_move().is_computer() == self.computer_first;
+        let second_won = G::check_for_winning(second_board);
+        if G::check_for_winning(first_board) && !(second_won && first_to_move) {
             (
                 GameResult::FirstPlayerWon,
-                Some(get_position_iterator(get_winning_board(first_board)).collect()),
+                Some(G::get_winning_lines(first_board)),
             )
-        } else if check_for_winning(second_board) {
+        } else if second_won {
             (
                 GameResult::SecondPlayerWon,
-                Some(get_position_iterator(get_winning_board(second_board)).collect()),
+                Some(G::get_winning_lines(second_board)),
             )
         } else if self.check_for_draw_if_not_winning() {
             (GameResult::Draw, None)
@@ -195,3 +990,707 @@ impl BitBoard {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::bit_board_coding::{ConnectFiveGeometry, Direction, Geometry};
+    use crate::selfplay::SplitMix64;
+    use std::collections::HashMap;
+
+    /// Visits every position up to the amount of plies and checks, that no two of them share a key and
+    /// that the symmetry independent key only joins a position with its mirror image.
+    fn assert_keys_are_unique<G: BoardGeometry>(max_plies: u32) {
+        let mut positions: HashMap<G::Key, (u64, u64)> = HashMap::new();
+        let mut mirror_classes: HashMap<G::Key, (u64, u64)> = HashMap::new();
+        let mut frontier = vec![GenericBitBoard::<G>::new()];
+        for ply in 0..=max_plies {
+            let mut next_frontier = Vec::new();
+            for board in frontier {
+                let stones = (board.own_stones, board.opponent_stones);
+                if let Some(other) = positions.insert(board.to_key(), stones) {
+                    assert_eq!(other, stones, "The key is shared:\n{board:?}");
+                    continue;
+                }
+                let mirrored = (G::flip_board(stones.0), G::flip_board(stones.1));
+                let class = stones.min(mirrored);
+                if let Some(other) =
+                    mirror_classes.insert(board.get_symmetry_independent_key(), class)
+                {
+                    assert_eq!(other, class, "The symmetric key is shared:\n{board:?}");
+                }
+
+                if ply == max_plies || board.is_game_over() {
+                    continue;
+                }
+                for column in 0..G::WIDTH {
+                    let mut child = board.clone();
+                    if child.play(column).is_ok() {
+                        next_frontier.push(child);
+                    }
+                }
+            }
+            frontier = next_frontier;
+        }
+    }
+
+    #[test]
+    fn test_position_keys_are_unique() {
+        assert_keys_are_unique::<StandardGeometry>(8);
+    }
+
+    #[test]
+    fn test_position_keys_are_unique_on_larger_boards() {
+        // The markers of full columns use the last bits of the key here.
+        assert_keys_are_unique::<Geometry<8, 7>>(7);
+        assert_keys_are_unique::<ConnectFiveGeometry>(7);
+    }
+
+    /// Plays the moves on the Connect 5 board and checks, that only the last one wins the game for the
+    /// side, that made it, with a single line of five running into the direction.
+    fn assert_five_wins(moves: &str, direction: Direction) {
+        let mut board = GenericBitBoard::<ConnectFiveGeometry>::from_move_string(moves).unwrap();
+        let first_player_won = moves.len() % 2 == 1;
+        let (result, lines) = board.get_winning_status_for_rendering();
+        assert_eq!(result.is_first_player_win(), first_player_won);
+        assert_eq!(result.is_second_player_win(), !first_player_won);
+        let lines = lines.unwrap();
+        assert_eq!(lines.len(), 1, "{moves}");
+        assert_eq!(lines[0].direction, direction, "{moves}");
+        assert_eq!(lines[0].cells.len(), 5);
+
+        board.undo_last_move();
+        assert!(!board.is_game_over(), "{moves}");
+    }
+
+    #[test]
+    fn test_five_in_a_row_wins_in_all_directions() {
+        assert_five_wins("492966593", Direction::Horizontal);
+        assert_five_wins("121518121", Direction::Vertical);
+        assert_five_wins("78675964488816187715", Direction::RisingDiagonal);
+        assert_five_wins("12214315523172738481", Direction::FallingDiagonal);
+    }
+
+    #[test]
+    fn test_four_in_a_row_does_not_win_connect_five() {
+        for moves in ["1122334", "1212121", "12233435444"] {
+            let board = GenericBitBoard::<ConnectFiveGeometry>::from_move_string(moves).unwrap();
+            assert!(!board.is_game_over(), "{moves}");
+        }
+    }
+
+    #[test]
+    fn test_full_connect_five_board_is_a_draw() {
+        let moves = "814393295122181545236523359355979921687846248738714186469777646";
+        assert_eq!(moves.len(), 63);
+        let mut board = GenericBitBoard::<ConnectFiveGeometry>::from_move_string(moves).unwrap();
+        assert_eq!(
+            board.get_winning_status_for_rendering(),
+            (GameResult::Draw, None)
+        );
+        assert!(board.is_game_over());
+        assert_eq!(board.play(0), Err(MoveError::GameOver));
+
+        board.undo_last_move();
+        assert!(!board.is_game_over());
+        assert_eq!(board.get_all_possible_moves().count(), 1);
+    }
+
+    /// Gets the error of reading the position from the text, none if it can be read.
+    fn get_position_text_error(text: &str) -> Option<PositionTextError> {
+        BitBoard::from_position_text(text).err()
+    }
+
+    #[test]
+    fn test_position_text_tells_the_formats_apart() {
+        let moves = BitBoard::from_move_string("4453").unwrap();
+        let pasted = BitBoard::from_position_text(" 4453\n").unwrap();
+        assert_eq!(pasted.get_move_history(), moves.get_move_history());
+        let grid = "
+            .......
+            .......
+            .......
+            .......
+            ...O...
+            ..OXX..";
+        assert_eq!(
+            BitBoard::from_position_text(grid).unwrap().to_ascii(),
+            moves.to_ascii()
+        );
+        // A single line with cells of a grid is taken for a grid, which lacks the other rows.
+        assert_eq!(
+            get_position_text_error("...X..."),
+            Some(PositionTextError::Grid(BoardParseError::WrongRowCount {
+                rows: 1,
+                expected: 6
+            }))
+        );
+    }
+
+    #[test]
+    fn test_position_text_errors() {
+        for text in ["", " \n\t "] {
+            assert_eq!(
+                get_position_text_error(text),
+                Some(PositionTextError::Empty)
+            );
+        }
+        assert_eq!(
+            get_position_text_error("44x"),
+            Some(PositionTextError::MoveString(
+                MoveStringError::InvalidCharacter {
+                    position: 2,
+                    character: 'x'
+                }
+            ))
+        );
+        assert_eq!(
+            get_position_text_error("4444444"),
+            Some(PositionTextError::MoveString(MoveStringError::ColumnFull {
+                position: 6,
+                column: 3
+            }))
+        );
+        let error = get_position_text_error(".......\n.......\n.......\n.......\n.......\n...Z...")
+            .unwrap();
+        assert_eq!(
+            error,
+            PositionTextError::Grid(BoardParseError::InvalidCharacter {
+                row: 5,
+                column: 3,
+                character: 'Z'
+            })
+        );
+        assert!(error.to_string().starts_with("grid: "), "{error}");
+        assert_eq!(PositionTextError::Empty.to_string(), "there is no position");
+    }
+
+    #[test]
+    fn test_stone_counts() {
+        let board = BitBoard::new();
+        assert!(board.is_empty());
+        assert_eq!((board.stone_count(), board.ply()), (0, 0));
+        assert_eq!(board.remaining_moves(), 42);
+
+        let mut board = BitBoard::from_move_string("4453").unwrap();
+        assert!(!board.is_empty());
+        assert_eq!((board.stone_count(), board.ply()), (4, 4));
+        assert_eq!(board.remaining_moves(), 38);
+        board.undo_last_move();
+        assert_eq!(board.stone_count(), 3);
+        board.undo_last_full_turn();
+        assert_eq!(board.stone_count(), 1);
+    }
+
+    #[test]
+    fn test_game_phases() {
+        // A game, that fills the whole board without a win.
+        let moves = "547125662261271266215743771576315353334444";
+        let get_phase = |plies: usize| BitBoard::from_move_string(&moves[..plies]).unwrap().phase();
+        assert_eq!(get_phase(0), GamePhase::Opening);
+        assert_eq!(get_phase(10), GamePhase::Opening);
+        assert_eq!(get_phase(11), GamePhase::Midgame);
+        assert_eq!(get_phase(27), GamePhase::Midgame);
+        assert_eq!(get_phase(28), GamePhase::Endgame);
+
+        let full_board = BitBoard::from_move_string(moves).unwrap();
+        assert_eq!(full_board.phase(), GamePhase::Endgame);
+        assert_eq!(full_board.remaining_moves(), 0);
+        assert!(full_board.check_for_draw_if_not_winning());
+        assert!(
+            !BitBoard::from_move_string(&moves[..41])
+                .unwrap()
+                .check_for_draw_if_not_winning()
+        );
+    }
+
+    #[test]
+    fn test_board_formatting() {
+        let board = BitBoard::from_move_string("4453").unwrap();
+        let grid = ".......\n.......\n.......\n.......\n...O...\n..OXX..\n";
+        assert_eq!(
+            board.to_string(),
+            format!("{grid}Computer to move, computer first: true")
+        );
+        // The stones of the side to move are own, the mirrored board has the same key.
+        assert_eq!(
+            format!("{board:?}"),
+            format!(
+                "own: 0x0000000000000018, opponent: 0x0000000000000404, key: 0x0000000000020a6f\n\
+                 {board}"
+            )
+        );
+        let mirrored = BitBoard::from_move_string("4435").unwrap();
+        assert!(format!("{mirrored:?}").contains("key: 0x0000000000020a6f\n"));
+    }
+
+    #[test]
+    fn test_winning_squares() {
+        let board = BitBoard::from_ascii(
+            "
+            .......
+            .......
+            .......
+            .......
+            .XXX...
+            XOOO.OX",
+        )
+        .unwrap();
+        let bit = StandardGeometry::get_bit_representation;
+        let x_is_computer = board.get_computer_first();
+        // The three of X can be completed on both sides, even if e2 is not playable yet.
+        assert_eq!(board.winning_squares(x_is_computer), bit(0, 1) | bit(4, 1));
+        assert_eq!(board.winning_squares(!x_is_computer), bit(4, 0));
+    }
+
+    /// Gets the immediate winning moves of X and O on the grid.
+    fn get_immediate_wins(grid: &str) -> (u64, u64) {
+        let board = BitBoard::from_ascii(grid).unwrap();
+        let x_is_computer = board.get_computer_first();
+        (
+            board.immediate_winning_moves(x_is_computer),
+            board.immediate_winning_moves(!x_is_computer),
+        )
+    }
+
+    #[test]
+    fn test_immediate_winning_moves() {
+        let bit = StandardGeometry::get_bit_representation;
+        let horizontal = "
+            .......
+            .......
+            .......
+            .......
+            OOO....
+            XXX....";
+        // The three of O waits for d1 to be filled.
+        assert_eq!(get_immediate_wins(horizontal), (bit(3, 0), 0));
+        let vertical = "
+            .......
+            .......
+            .......
+            XO.....
+            XO.....
+            XO.....";
+        assert_eq!(get_immediate_wins(vertical), (bit(0, 3), bit(1, 3)));
+        let rising = "
+            .......
+            .......
+            .......
+            ..XX...
+            .XOO...
+            XOOX...";
+        assert_eq!(get_immediate_wins(rising), (bit(3, 3), 0));
+        let falling = "
+            .......
+            .......
+            .......
+            ...XX..
+            ...OOX.
+            ...XOOX";
+        assert_eq!(get_immediate_wins(falling), (bit(3, 3), 0));
+        // The same diagonal, before d3 has been filled.
+        let not_playable = "
+            .......
+            .......
+            .......
+            ..X....
+            .XOO...
+            XOOX...";
+        let board = BitBoard::from_ascii(not_playable).unwrap();
+        let x_is_computer = board.get_computer_first();
+        assert_eq!(board.winning_squares(x_is_computer), bit(3, 3));
+        assert_eq!(board.immediate_winning_moves(x_is_computer), 0);
+    }
+
+    #[test]
+    fn test_ascii_round_trip_in_random_games() {
+        let mut random = SplitMix64::new(2290);
+        for _ in 0..200 {
+            let mut board = BitBoard::new();
+            loop {
+                let grid = board.to_ascii();
+                let parsed = BitBoard::from_ascii(&grid).unwrap();
+                assert_eq!(parsed.to_ascii(), grid);
+                assert_eq!(parsed.stone_count(), board.stone_count());
+                // The grid does not tell, who is the computer, the player to move is taken for it.
+                assert!(parsed.side_to_move().is_computer(), "{grid}");
+                assert_eq!(
+                    parsed.get_winning_status_for_rendering().0,
+                    board.get_winning_status_for_rendering().0,
+                    "{grid}"
+                );
+                if board.is_game_over() {
+                    break;
+                }
+                let columns: Vec<u32> = (0..7)
+                    .filter(|&column| board.get_possible_move(column) != 0)
+                    .collect();
+                let column = columns[random.next_below(columns.len() as u32) as usize];
+                board.play(column).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_grids_are_refused() {
+        let cases = [
+            (
+                ".......\n.......\n.......\n.......\n...X...\n.......",
+                BoardParseError::FloatingStone { row: 4, column: 3 },
+            ),
+            (
+                ".......\n.......\n.......\n.......\n.......\nXXO.X..",
+                BoardParseError::StoneCountMismatch {
+                    first: 3,
+                    second: 1,
+                },
+            ),
+            (
+                ".......\n.......\n.......\n.......\n.......\n.OO.X..",
+                BoardParseError::StoneCountMismatch {
+                    first: 1,
+                    second: 2,
+                },
+            ),
+            (
+                ".......\n.......\nXO.....\nXO.....\nXO.....\nXO.....",
+                BoardParseError::BothPlayersWon,
+            ),
+            (
+                ".......\n.......\n.......\n.......\n.......",
+                BoardParseError::WrongRowCount {
+                    rows: 5,
+                    expected: 6,
+                },
+            ),
+            (
+                ".......\n.......\n.......\n.......\n.......\n........",
+                BoardParseError::WrongRowLength {
+                    row: 5,
+                    length: 8,
+                    expected: 7,
+                },
+            ),
+            (
+                ".......\n.......\n.......\n.......\n.......\n...x...",
+                BoardParseError::InvalidCharacter {
+                    row: 5,
+                    column: 3,
+                    character: 'x',
+                },
+            ),
+        ];
+        for (grid, error) in cases {
+            assert_eq!(BitBoard::from_ascii(grid).err(), Some(error), "{grid}");
+        }
+    }
+
+    #[test]
+    fn test_undo_gets_back_to_the_replayed_board() {
+        let mut board = BitBoard::new();
+        for column in [3, 3, 4, 2, 5] {
+            board.play(column).unwrap();
+        }
+        assert_eq!(board.get_move_history().len(), 5);
+        assert_eq!(board.undo_last_move(), Some(5));
+        assert_eq!(board.undo_last_move(), Some(2));
+
+        let replayed = BitBoard::from_move_string("445").unwrap();
+        assert_eq!(
+            (board.own_stones, board.opponent_stones),
+            (replayed.own_stones, replayed.opponent_stones)
+        );
+        assert_eq!(board.get_move_history(), replayed.get_move_history());
+        assert_eq!(board.side_to_move(), replayed.side_to_move());
+    }
+
+    #[test]
+    fn test_undo_full_turns() {
+        let mut board = BitBoard::from_move_string("445").unwrap();
+        assert_eq!(board.undo_last_full_turn(), 2);
+        assert_eq!(
+            board.to_ascii(),
+            BitBoard::from_move_string("4").unwrap().to_ascii()
+        );
+        // Only one move is left to take back, then there is none.
+        assert_eq!(board.undo_last_full_turn(), 1);
+        assert!(board.is_empty());
+        assert_eq!(board.undo_last_full_turn(), 0);
+        assert_eq!(board.undo_last_move(), None);
+    }
+
+    #[test]
+    fn test_history_is_cloned_and_reset() {
+        let mut board = BitBoard::from_move_string("4453").unwrap();
+        let mut clone = board.clone();
+        assert_eq!(clone.get_move_history(), board.get_move_history());
+        assert_eq!(clone.undo_last_move(), Some(2));
+        assert_eq!(board.get_move_history().len(), 4);
+
+        board.reset();
+        assert!(board.is_empty());
+        assert!(board.get_move_history().is_empty());
+        assert_eq!(board.undo_last_move(), None);
+    }
+
+    #[test]
+    fn test_illegal_move_strings() {
+        let cases = [
+            (
+                "48",
+                MoveStringError::InvalidCharacter {
+                    position: 1,
+                    character: '8',
+                },
+            ),
+            (
+                "0",
+                MoveStringError::InvalidCharacter {
+                    position: 0,
+                    character: '0',
+                },
+            ),
+            (
+                "4444444",
+                MoveStringError::ColumnFull {
+                    position: 6,
+                    column: 3,
+                },
+            ),
+            ("41414141", MoveStringError::GameAlreadyOver { position: 7 }),
+            (
+                &"1234567".repeat(7),
+                MoveStringError::TooLong { maximum: 42 },
+            ),
+        ];
+        for (moves, error) in cases {
+            assert_eq!(
+                BitBoard::from_move_string(moves).err(),
+                Some(error),
+                "{moves}"
+            );
+        }
+        assert_eq!(
+            MoveStringError::GameAlreadyOver { position: 7 }.to_string(),
+            "move at position 7 after the game is over"
+        );
+    }
+
+    #[test]
+    fn test_move_string_round_trip() {
+        // A complete game, that ends in a draw.
+        let moves = "547125662261271266215743771576315353334444";
+        let board = BitBoard::from_move_string(moves).unwrap();
+        assert_eq!(board.to_move_string().as_deref(), Some(moves));
+        assert_eq!(board.get_winning_status_for_rendering().0, GameResult::Draw);
+        for length in [0, 1, 20] {
+            let board = BitBoard::from_move_string(&moves[..length]).unwrap();
+            assert_eq!(board.to_move_string().as_deref(), Some(&moves[..length]));
+        }
+        // A grid does not tell the order of the moves.
+        let grid = BitBoard::from_ascii(&board.to_ascii()).unwrap();
+        assert_eq!(grid.to_move_string(), None);
+    }
+
+    #[test]
+    fn test_sides_alternate() {
+        for computer_first in [true, false] {
+            let mut board = BitBoard::new();
+            board.set_computer_first(computer_first);
+            let first = if computer_first {
+                Side::Computer
+            } else {
+                Side::Player
+            };
+            let second = if computer_first {
+                Side::Player
+            } else {
+                Side::Computer
+            };
+            for (column, side) in [(3, first), (3, second), (2, first), (4, second)] {
+                assert_eq!(board.side_to_move(), side);
+                let coded_move = board.play(column).unwrap();
+                assert_eq!(
+                    board.get_move_history().last(),
+                    Some(&(coded_move, side.is_computer()))
+                );
+            }
+            assert_eq!(board.side_to_move(), first);
+        }
+    }
+
+    #[test]
+    fn test_play_refuses_impossible_moves() {
+        let mut board = BitBoard::from_move_string("444444").unwrap();
+        assert_eq!(board.play(7), Err(MoveError::InvalidColumn { column: 7 }));
+        assert_eq!(board.play(3), Err(MoveError::ColumnFull { column: 3 }));
+        let mut won = BitBoard::from_move_string("4141414").unwrap();
+        assert_eq!(won.play(0), Err(MoveError::GameOver));
+        // Nothing has been played by the refused moves.
+        assert_eq!((board.stone_count(), won.stone_count()), (6, 7));
+    }
+
+    /// Parses the grid as a board played with the Pop Out rules.
+    fn get_pop_out_board(grid: &str) -> BitBoard {
+        let mut board = BitBoard::from_ascii(grid).unwrap();
+        board.set_rule_set(RuleSet::PopOut);
+        board
+    }
+
+    #[test]
+    fn test_pop_refuses_impossible_pops() {
+        let mut classic = BitBoard::from_move_string("43").unwrap();
+        assert_eq!(classic.pop_bottom(3, false), Err(MoveError::PopNotAllowed));
+
+        let mut board = BitBoard::from_move_string("43").unwrap();
+        board.set_rule_set(RuleSet::PopOut);
+        let is_computer = board.side_to_move().is_computer();
+        assert_eq!(
+            board.pop_bottom(7, is_computer),
+            Err(MoveError::InvalidColumn { column: 7 })
+        );
+        // The bottom stone of the third column belongs to the other side, the first one is empty.
+        for column in [2, 0] {
+            assert_eq!(
+                board.pop_bottom(column, is_computer),
+                Err(MoveError::NotOwnBottomStone { column })
+            );
+        }
+        let mut won = BitBoard::from_move_string("4141414").unwrap();
+        won.set_rule_set(RuleSet::PopOut);
+        let is_computer = won.side_to_move().is_computer();
+        assert_eq!(won.pop_bottom(1, is_computer), Err(MoveError::GameOver));
+        // Nothing has been popped by the refused pops.
+        assert_eq!((board.stone_count(), won.stone_count()), (2, 7));
+    }
+
+    #[test]
+    fn test_pop_lets_the_column_fall_down() {
+        // The fourth column is full, the stones of its neighbors may not move with it.
+        let mut board = BitBoard::from_move_string("44444456").unwrap();
+        board.set_rule_set(RuleSet::PopOut);
+        let is_computer = board.side_to_move().is_computer();
+        let before = board.to_ascii();
+        board.pop_bottom(3, is_computer).unwrap();
+        assert_eq!(
+            board.to_ascii(),
+            ".......\n...O...\n...X...\n...O...\n...X...\n...OXO.\n"
+        );
+        assert_eq!(
+            board.get_move_history().last(),
+            Some(&(StandardGeometry::get_pop_move(3), is_computer))
+        );
+        // The pop is a turn, the other side moves next and may use the freed top cell.
+        assert_ne!(board.side_to_move().is_computer(), is_computer);
+        assert_eq!(board.validate(), Ok(()));
+        board.play(3).unwrap();
+        assert_eq!(board.undo_last_move(), Some(3));
+
+        assert_eq!(board.undo_last_move(), Some(3));
+        assert_eq!(board.to_ascii(), before);
+        assert_eq!(board.side_to_move().is_computer(), is_computer);
+    }
+
+    #[test]
+    fn test_pop_can_win_for_either_side() {
+        // The pop lets the stone of the second player fall into the gap of its bottom row.
+        let mut board = get_pop_out_board(".......\n.......\n.......\n.......\nXX.O...\nOOOXX..\n");
+        let is_computer = board.side_to_move().is_computer();
+        board.pop_bottom(3, is_computer).unwrap();
+        assert!(board.is_game_over());
+        assert_eq!(
+            board.get_winning_status_for_rendering().0,
+            GameResult::SecondPlayerWon
+        );
+
+        // This time the stones falling down complete a four for both, the player who popped wins.
+        let mut board = get_pop_out_board(".......\n.......\n.......\n...X...\nXXXO...\nOOOX..O\n");
+        let is_computer = board.side_to_move().is_computer();
+        board.pop_bottom(3, is_computer).unwrap();
+        let (result, lines) = board.get_winning_status_for_rendering();
+        assert_eq!(result, GameResult::FirstPlayerWon);
+        assert_eq!(lines.map(|lines| lines.len()), Some(1));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "The same side tries to move twice in a row.")]
+    fn test_double_move_is_detected() {
+        let mut board = BitBoard::new();
+        let is_computer = board.side_to_move().is_computer();
+        board.apply_move_on_column(3, is_computer);
+        board.apply_move_on_column(4, is_computer);
+    }
+
+    /// Gets a board with the computer moving first and the stones set directly.
+    fn get_crafted_board(own_stones: u64, opponent_stones: u64) -> BitBoard {
+        let mut board = BitBoard::new();
+        board.set_computer_first(true);
+        board.own_stones = own_stones;
+        board.opponent_stones = opponent_stones;
+        board
+    }
+
+    #[test]
+    fn test_board_violations() {
+        let bit = StandardGeometry::get_bit_representation;
+        let column = StandardGeometry::get_column_mask;
+        let outside = 1 << (!StandardGeometry::FULL_BOARD_MASK).trailing_zeros();
+        let cases = [
+            (
+                get_crafted_board(outside, 0),
+                BoardInvariantError::StonesOutsideBoard,
+            ),
+            (
+                get_crafted_board(bit(3, 0), bit(3, 0)),
+                BoardInvariantError::OverlappingStones,
+            ),
+            (
+                get_crafted_board(bit(3, 0) | bit(2, 2), bit(2, 0)),
+                BoardInvariantError::FloatingStone { column: 2, row: 2 },
+            ),
+            (
+                get_crafted_board(bit(0, 0) | bit(1, 0) | bit(2, 0), 0),
+                BoardInvariantError::StoneCountMismatch {
+                    first: 3,
+                    second: 0,
+                },
+            ),
+            (
+                get_crafted_board(0, bit(3, 0)),
+                BoardInvariantError::StoneCountMismatch {
+                    first: 0,
+                    second: 1,
+                },
+            ),
+            (
+                get_crafted_board(
+                    column(0) & !bit(0, 4) & !bit(0, 5),
+                    column(1) & !bit(1, 4) & !bit(1, 5),
+                ),
+                BoardInvariantError::BothPlayersWon,
+            ),
+        ];
+        for (board, error) in cases {
+            assert_eq!(board.validate(), Err(error), "{board:?}");
+        }
+    }
+
+    #[test]
+    fn test_valid_boards() {
+        let mut board = BitBoard::from_move_string("4453").unwrap();
+        assert_eq!(board.validate(), Ok(()));
+        board.swap_players();
+        assert_eq!(board.validate(), Ok(()));
+        let won = BitBoard::from_move_string("4141414").unwrap();
+        assert_eq!(won.validate(), Ok(()));
+        // The extra stone of the handicap lets the player pass once.
+        let mut handicap = BitBoard::new();
+        handicap.set_computer_first(true);
+        handicap.set_handicap(Side::Computer, 1);
+        handicap.place_stone(3, true);
+        handicap.place_stone(2, true);
+        assert_eq!(handicap.validate(), Ok(()));
+    }
+}
@@ -1,53 +1,75 @@
 //! This module contains bit board coding helper functions and a lot of
 //! constants. Those are calculated at compile time. As for loops are not allowed
 //! in const functions they have been reformulated to while loops.
+//! The functions are collected in the [`BoardGeometry`] trait, so that they work on any board
+//! dimension that fits into the bit layout and any length of the winning line. The standard 7x6 board
+//! with four in a row is [`StandardGeometry`].
 
 //! Uses a bit board along the following structure:  
 //!
-//! | (48) | (49) | (50) | (51) | (52) | (53) | (54) | (55) |
-//! |------|------|------|------|------|------|------|------|
-//! |  40  |  41  |  42  |  43  |  44  |  45  |  46  | (47) |
-//! |  32  |  33  |  34  |  35  |  36  |  37  |  38  | (39) |
-//! |  24  |  25  |  26  |  27  |  28  |  29  |  30  | (31) |
-//! |  16  |  17  |  18  |  19  |  20  |  21  |  22  | (23) |
-//! |   8  |   9  |  10  |  11  |  12  |  13  |  14  | (15) |
-//! |   0  |   1  |   2  |   3  |   4  |   5  |   6  |  (7) |
+//! |  35  |  36  |  37  |  38  |  39  |  40  |  41  |
+//! |------|------|------|------|------|------|------|
+//! |  28  |  29  |  30  |  31  |  32  |  33  |  34  |
+//! |  21  |  22  |  23  |  24  |  25  |  26  |  27  |
+//! |  14  |  15  |  16  |  17  |  18  |  19  |  20  |
+//! |   7  |   8  |   9  |  10  |  11  |  12  |  13  |
+//! |   0  |   1  |   2  |   3  |   4  |   5  |   6  |
 //!  
-//! The number in parentheses are sentinel guards.
+//! The rows follow each other without a gap, so that a 9x7 board still fits into the 64 bits. Shifts,
+//! that would wrap around from one row into the next, get clipped with the masks of the edge columns,
+//! see [`BoardGeometry::clip_shift`]. Other board dimensions use the same layout with a row stride of
+//! the width.
 
-use crate::debug_check_board_coordinates;
+use std::fmt;
+use std::hash::Hash;
 
-/// The width of the board.
-pub const BOARD_WIDTH: u32 = 7;
+/// The largest supported board width. Move strings need a single digit per column and the move lists
+/// of the search live on the stack.
+pub const MAX_BOARD_WIDTH: u32 = 9;
 
-/// The height of the board.
-pub const BOARD_HEIGHT: u32 = 6;
+/// The largest supported length of the winning line. The gaps of a line are computed in arrays on the
+/// stack.
+pub const MAX_WIN_LENGTH: u32 = 6;
 
-/// Gets a mask, where the bit at the indicated position is set.
+/// Gets a mask, where the bit at the indicated position is set, for a board of the indicated width.
 #[inline(always)]
-pub const fn get_bit_representation(x: u32, y: u32) -> u64 {
-    1 << (x + 8 * y)
+pub(crate) const fn get_bit(x: u32, y: u32, width: u32) -> u64 {
+    1 << (x + width * y)
 }
 
-/// Generates a mask for one specific column.
-pub const fn get_column_mask(col: u32) -> u64 {
+/// Generates a mask for the first column.
+const fn get_first_column_mask(width: u32, height: u32) -> u64 {
     let mut result: u64 = 0;
     let mut y = 0;
-    while y < BOARD_HEIGHT {
-        result |= get_bit_representation(col, y);
+    while y < height {
+        result |= get_bit(0, y, width);
         y += 1;
     }
     result
 }
 
-/// Helpful to mask out the sentinel.
-const fn get_full_board_mask() -> u64 {
+/// Helpful to mask out the bits above the board. This is also the place, where unsupported dimensions
+/// and winning lengths get rejected at compile time, as every geometry needs the mask. The board has to
+/// be at least three columns wide, so that the shifts of the four directions are all different.
+const fn get_full_board_mask(width: u32, height: u32, win_length: u32) -> u64 {
+    assert!(
+        width >= 3 && width <= MAX_BOARD_WIDTH && height >= 1,
+        "Unsupported board dimension."
+    );
+    assert!(
+        win_length >= 2 && win_length <= MAX_WIN_LENGTH,
+        "Unsupported winning length."
+    );
+    assert!(
+        width * height <= 64,
+        "The board does not fit into the bit layout."
+    );
     let mut result: u64 = 0;
     let mut x = 0;
-    while x < BOARD_WIDTH {
+    while x < width {
         let mut y = 0;
-        while y < BOARD_HEIGHT {
-            result |= get_bit_representation(x, y);
+        while y < height {
+            result |= get_bit(x, y, width);
             y += 1;
         }
         x += 1;
@@ -56,138 +78,987 @@ const fn get_full_board_mask() -> u64 {
 }
 
 /// Generates a mask for the lowest line.
-const fn get_bottom_filler_mask() -> u64 {
+const fn get_bottom_filler_mask(width: u32) -> u64 {
     let mut result: u64 = 0;
     let mut x = 0;
-    while x < BOARD_WIDTH {
-        result |= get_bit_representation(x, 0);
+    while x < width {
+        result |= get_bit(x, 0, width);
         x += 1;
     }
     result
 }
 
-/// Flags out the seven different columns.
-pub const COLUMN_MASK: [u64; 7] = [
-    get_column_mask(0),
-    get_column_mask(1),
-    get_column_mask(2),
-    get_column_mask(3),
-    get_column_mask(4),
-    get_column_mask(5),
-    get_column_mask(6),
-];
+/// The most winning lines a board can have, a line may start on each of the at most 64 squares in each of
+/// the four directions.
+pub const MAX_WINNING_LINES: usize = 256;
 
-/// Flags the full board except for the sentinel.
-pub const FULL_BOARD_MASK: u64 = get_full_board_mask();
-/// Flags the bottom line helpful to determine possible legal moves.
-pub const BOTTOM_FILL_MASK: u64 = get_bottom_filler_mask();
-
-/// Bit shift increment:  
-/// 0  1   2  
-/// \  |  /  
-///    X -  3  
-pub const DIR_INCREMENT: [u8; 4] = [7, 8, 9, 1];
-
-/// Method to mirror a board along the y-axis.
-pub fn flip_board(input: u64) -> u64 {
-    let mut result: u64;
-    result = (input & COLUMN_MASK[6]) >> 6;
-    result |= (input & COLUMN_MASK[5]) >> 4;
-    result |= (input & COLUMN_MASK[4]) >> 2;
-    result |= input & COLUMN_MASK[3];
-    result |= (input & COLUMN_MASK[2]) << 2;
-    result |= (input & COLUMN_MASK[1]) << 4;
-    result |= (input & COLUMN_MASK[0]) << 6;
+/// Generates the masks of all lines of winning length on the board, the horizontal ones first, then the
+/// vertical, the rising and the falling diagonal ones. Returns them in an array of the largest size
+/// together with their amount.
+const fn get_winning_line_table(
+    width: u32,
+    height: u32,
+    win_length: u32,
+) -> ([u64; MAX_WINNING_LINES], usize) {
+    // The steps along the line in the x and y direction, the falling diagonal runs to the left upwards.
+    const STEPS: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (-1, 1)];
+    let mut lines = [0; MAX_WINNING_LINES];
+    let mut count = 0;
+    let mut direction = 0;
+    while direction < STEPS.len() {
+        let (step_x, step_y) = STEPS[direction];
+        let mut y = 0;
+        while y < height as i32 {
+            let mut x = 0;
+            while x < width as i32 {
+                let end_x = x + step_x * (win_length as i32 - 1);
+                let end_y = y + step_y * (win_length as i32 - 1);
+                if end_x >= 0 && end_x < width as i32 && end_y < height as i32 {
+                    let mut line = 0;
+                    let mut cell = 0;
+                    while cell < win_length as i32 {
+                        line |= get_bit(
+                            (x + step_x * cell) as u32,
+                            (y + step_y * cell) as u32,
+                            width,
+                        );
+                        cell += 1;
+                    }
+                    lines[count] = line;
+                    count += 1;
+                }
+                x += 1;
+            }
+            y += 1;
+        }
+        direction += 1;
+    }
+    (lines, count)
+}
 
-    result
+/// The direction of a winning line, seen from its first stone.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    /// Going to the left upwards.
+    FallingDiagonal,
+    Vertical,
+    /// Going to the right upwards.
+    RisingDiagonal,
+    Horizontal,
 }
-/// Slow method only to be used for board drawing, gets all elements from the boards as coordinates.
-pub fn get_position_iterator(board: u64) -> impl Iterator<Item = (u32, u32)> {
-    (0..BOARD_WIDTH)
-        .flat_map(|x| (0..BOARD_HEIGHT).map(move |y| (x, y)))
-        .filter(move |&(x, y)| board & (1 << (x + 8 * y)) != 0)
+
+/// The directions in the same order as the shifts in the direction increment.
+const DIRECTIONS: [Direction; 4] = [
+    Direction::FallingDiagonal,
+    Direction::Vertical,
+    Direction::RisingDiagonal,
+    Direction::Horizontal,
+];
+
+/// One line of stones, that wins the game. The cells are ordered along the direction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WinningLine {
+    /// The coordinates of the stones as column and row, there are as many as the winning length.
+    pub cells: Vec<(u32, u32)>,
+    /// The direction the line runs into.
+    pub direction: Direction,
 }
 
-/// Applies the indicated shift for movement by the shift value and clips
-/// the value against the sentinel.
-#[inline(always)]
-pub fn clip_shift(input: u64, amount: u8) -> u64 {
-    (input << amount) & FULL_BOARD_MASK
+/// The number a position gets encoded in, see [`BoardGeometry::get_position_key`].
+pub trait PositionKey: Copy + Eq + Ord + Hash + fmt::Debug + fmt::LowerHex {
+    /// The amount of bits of the key.
+    const BITS: u32;
+
+    /// Takes over a key computed in 128 bits, the bits beyond the ones of the key are not set.
+    fn from_wide(key: u128) -> Self;
 }
 
-/// Does the inverse clip shift.
-#[inline(always)]
-pub fn clip_shift_inverse(input: u64, amount: u8) -> u64 {
-    (input >> amount) & FULL_BOARD_MASK
+impl PositionKey for u64 {
+    const BITS: u32 = u64::BITS;
+
+    #[inline(always)]
+    fn from_wide(key: u128) -> u64 {
+        key as u64
+    }
 }
 
-/// Gets a  representation, where the bit for the specific column is set where a move would wind up.
-/// If it is not possible to make move in that column, a 0 is returned.
-pub fn get_possible_move(board: u64, column: u32) -> u64 {
-    debug_check_board_coordinates!(col: column);
-    // Safely upshifted board extended with a bottom row.
-    ((clip_shift(board, DIR_INCREMENT[1]) | BOTTOM_FILL_MASK) ^
-        // The original board.
-        board )
-        // Filter out the desired column.
-        & COLUMN_MASK[column as usize]
+impl PositionKey for u128 {
+    const BITS: u32 = u128::BITS;
+
+    #[inline(always)]
+    fn from_wide(key: u128) -> u128 {
+        key
+    }
 }
 
-/// Checks if the game board contains a winning constellation.
-/// Here the bit board representation really shines. Returns true
-/// if the board has one sequence of rows.
-///
-/// The idea is: <br>
-/// board: <br>
-/// 001111000 <br>
-/// d: <br>
-/// 000111000 <br>
-/// dd: <br>
-/// 000001110 <br>
-/// dd & d <br>
-/// 000001000 <br>
-#[inline(always)]
-pub fn check_for_winning(board: u64) -> bool {
-    for bit_shift in DIR_INCREMENT {
-        let d = clip_shift(board, bit_shift) & board;
-        let dd = clip_shift(clip_shift(d, bit_shift), bit_shift);
-        if (dd & d) != 0 {
-            return true;
+/// The bit, that flags a coded move as a pop under the Pop Out rules. It lies above all the squares of
+/// the board.
+const POP_FLAG: u64 = 1 << 63;
+
+/// The dimensions of a board together with all the masks and bit operations, that depend on them.
+/// Implementors only provide the width, the height, the winning length and the type of the position key,
+/// everything else is derived at compile time.
+pub trait BoardGeometry: Clone {
+    /// The number the position key is stored in, it needs a bit for every square of the board and the
+    /// row above it.
+    type Key: PositionKey;
+
+    /// The width of the board.
+    const WIDTH: u32;
+    /// The height of the board.
+    const HEIGHT: u32;
+    /// The amount of stones in a row, that win the game.
+    const WIN_LENGTH: u32 = 4;
+
+    /// The distance in bits between two rows.
+    const STRIDE: u32 = Self::WIDTH;
+    /// Flags all the squares of the board.
+    const FULL_BOARD_MASK: u64 = get_full_board_mask(Self::WIDTH, Self::HEIGHT, Self::WIN_LENGTH);
+    /// Flags the bottom line helpful to determine possible legal moves.
+    const BOTTOM_FILL_MASK: u64 = get_bottom_filler_mask(Self::WIDTH);
+    /// Flags the first column, the other columns are shifted versions of it.
+    const FIRST_COLUMN_MASK: u64 = get_first_column_mask(Self::WIDTH, Self::HEIGHT);
+    /// Flags the last column.
+    const LAST_COLUMN_MASK: u64 = Self::FIRST_COLUMN_MASK << (Self::WIDTH - 1);
+    /// The winning lines of [`BoardGeometry::WINNING_LINES`] in an array of the largest size together
+    /// with their amount.
+    const WINNING_LINE_TABLE: ([u64; MAX_WINNING_LINES], usize) =
+        get_winning_line_table(Self::WIDTH, Self::HEIGHT, Self::WIN_LENGTH);
+    /// The masks of all lines of winning length on the board. The shifts of the bit board treat the lines
+    /// all at once, this is for going through them one by one.
+    const WINNING_LINES: &'static [u64] = Self::WINNING_LINE_TABLE
+        .0
+        .split_at(Self::WINNING_LINE_TABLE.1)
+        .0;
+
+    /// Bit shift increment:  
+    /// 0  1   2  
+    /// \  |  /  
+    ///    X -  3  
+    const DIR_INCREMENT: [u8; 4] = [
+        Self::STRIDE as u8 - 1,
+        Self::STRIDE as u8,
+        Self::STRIDE as u8 + 1,
+        1,
+    ];
+
+    /// Gets a mask, where the bit at the indicated position is set.
+    #[inline(always)]
+    fn get_bit_representation(x: u32, y: u32) -> u64 {
+        get_bit(x, y, Self::WIDTH)
+    }
+
+    /// Gets the mask for one specific column.
+    #[inline(always)]
+    fn get_column_mask(column: u32) -> u64 {
+        Self::FIRST_COLUMN_MASK << column
+    }
+
+    /// Gets the column at the index, when the columns are ordered from the center outwards, the left one
+    /// first on the same distance. On the standard board this is 3, 2, 4, 1, 5, 0, 6.
+    #[inline(always)]
+    fn get_centered_column(index: u32) -> u32 {
+        let center = (Self::WIDTH - 1) / 2;
+        if index % 2 == Self::WIDTH % 2 {
+            center - (index + Self::WIDTH % 2) / 2
+        } else {
+            center + index.div_ceil(2)
         }
     }
 
-    false
-}
+    /// Gets the distance of the column from the center of the board in half columns, so that it is a
+    /// whole number on boards of even width as well.
+    #[inline(always)]
+    fn get_center_distance(column: u32) -> u32 {
+        (2 * column).abs_diff(Self::WIDTH - 1)
+    }
+
+    /// Gets the winning lines, that contain at least one of the squares of the mask.
+    #[inline(always)]
+    fn get_lines_through(cell_mask: u64) -> impl Iterator<Item = u64> {
+        Self::WINNING_LINES
+            .iter()
+            .copied()
+            .filter(move |line| line & cell_mask != 0)
+    }
+
+    /// Method to mirror a board along the y-axis.
+    #[inline(always)]
+    fn flip_board(input: u64) -> u64 {
+        let mut result = 0;
+        for column in 0..Self::WIDTH {
+            let mirrored = Self::WIDTH - 1 - column;
+            let stones = input & Self::get_column_mask(column);
+            result |= if mirrored >= column {
+                stones << (mirrored - column)
+            } else {
+                stones >> (column - mirrored)
+            };
+        }
+
+        result
+    }
+
+    /// Slow method only to be used for board drawing, gets all elements from the boards as coordinates.
+    fn get_position_iterator(board: u64) -> impl Iterator<Item = (u32, u32)> {
+        (0..Self::WIDTH)
+            .flat_map(|x| (0..Self::HEIGHT).map(move |y| (x, y)))
+            .filter(move |&(x, y)| board & Self::get_bit_representation(x, y) != 0)
+    }
+
+    /// Gets the squares, that a shift by one of the direction increments may land on. A step to the right,
+    /// horizontally or on the rising diagonal, would wrap around from the last column into the first one of
+    /// the next row, a step to the left on the falling diagonal the other way round. The inverse shift
+    /// steps into the opposite direction. The vertical shift only needs to stay on the board.
+    #[inline(always)]
+    fn get_clip_mask(amount: u8, is_inverse: bool) -> u64 {
+        let amount = amount as u32;
+        let steps_right = amount == 1 || amount == Self::STRIDE + 1;
+        let steps_left = amount == Self::STRIDE - 1;
+        let (goes_right, goes_left) = if is_inverse {
+            (steps_left, steps_right)
+        } else {
+            (steps_right, steps_left)
+        };
+        if goes_right {
+            Self::FULL_BOARD_MASK & !Self::FIRST_COLUMN_MASK
+        } else if goes_left {
+            Self::FULL_BOARD_MASK & !Self::LAST_COLUMN_MASK
+        } else {
+            Self::FULL_BOARD_MASK
+        }
+    }
+
+    /// Applies the indicated shift for movement by the shift value and clips
+    /// the value against the edges of the board.
+    #[inline(always)]
+    fn clip_shift(input: u64, amount: u8) -> u64 {
+        (input << amount) & Self::get_clip_mask(amount, false)
+    }
+
+    /// Does the inverse clip shift.
+    #[inline(always)]
+    fn clip_shift_inverse(input: u64, amount: u8) -> u64 {
+        (input >> amount) & Self::get_clip_mask(amount, true)
+    }
+
+    /// Gets a representation, where the bits are set where a move would wind up in any of the columns.
+    /// Full columns do not contribute a bit.
+    #[inline(always)]
+    fn get_playable_squares(board: u64) -> u64 {
+        // Safely upshifted board extended with a bottom row.
+        (Self::clip_shift(board, Self::DIR_INCREMENT[1]) | Self::BOTTOM_FILL_MASK) ^
+            // The original board.
+            board
+    }
+
+    /// Gets a  representation, where the bit for the specific column is set where a move would wind up.
+    /// If it is not possible to make move in that column, a 0 is returned.
+    #[inline(always)]
+    fn get_possible_move(board: u64, column: u32) -> u64 {
+        debug_assert!(
+            column < Self::WIDTH,
+            "Illegal column: {column} (valid: col < {})",
+            Self::WIDTH
+        );
+        Self::get_playable_squares(board) & Self::get_column_mask(column)
+    }
+
+    /// Flags the last stone of every line of winning length in the direction of the shift. Every step
+    /// shifts the line ends found so far by one square and keeps the ones, that land on a stone again.
+    ///
+    /// The idea for a length of four is: <br>
+    /// board: <br>
+    /// 001111000 <br>
+    /// after one step: <br>
+    /// 000111000 <br>
+    /// after two steps: <br>
+    /// 000011000 <br>
+    /// after three steps: <br>
+    /// 000001000 <br>
+    #[inline(always)]
+    fn get_line_ends(board: u64, bit_shift: u8) -> u64 {
+        let mut ends = board;
+        for _ in 1..Self::WIN_LENGTH {
+            ends = Self::clip_shift(ends, bit_shift) & board;
+        }
+        ends
+    }
 
-/// Generates a board representation, where bits are set that belong to a winning combination.
-/// Makes use of the fact, that *check_for_winning* effectively collapsed a winning combination
-/// into one bit that is the furthest out in shift direction. So we invert the shift three times
-/// and ore it together.
-pub fn get_winning_board(board: u64) -> u64 {
-    let mut result = 0;
-    for bit_shift in DIR_INCREMENT {
-        let d = clip_shift(board, bit_shift) & board;
-        let dd = clip_shift(clip_shift(d, bit_shift), bit_shift);
-        let mut flag = dd & d;
-
-        // Now the last bit of every winning constellation is set.
-        result |= flag;
-        // We can safely shift back, because we came from there.
-        for _ in 0..3 {
-            flag >>= bit_shift;
+    /// Checks if the game board contains a winning constellation.
+    /// Here the bit board representation really shines. Returns true
+    /// if the board has one line of the winning length in any direction, see *get_line_ends*.
+    #[inline(always)]
+    fn check_for_winning(board: u64) -> bool {
+        for bit_shift in Self::DIR_INCREMENT {
+            if Self::get_line_ends(board, bit_shift) != 0 {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Generates a board representation, where bits are set that belong to a winning combination.
+    /// Makes use of the fact, that *get_line_ends* effectively collapsed a winning combination
+    /// into one bit that is the furthest out in shift direction. So we invert the shift for the rest of
+    /// the line and ore it together.
+    fn get_winning_board(board: u64) -> u64 {
+        let mut result = 0;
+        for bit_shift in Self::DIR_INCREMENT {
+            let mut flag = Self::get_line_ends(board, bit_shift);
+
+            // Now the last bit of every winning constellation is set.
             result |= flag;
+            // We can safely shift back, because we came from there.
+            for _ in 1..Self::WIN_LENGTH {
+                flag >>= bit_shift;
+                result |= flag;
+            }
         }
+
+        result
     }
 
-    result
+    /// Gets every winning line on the board separately together with its direction. Works like
+    /// *get_winning_board*, but walks back from every collapsed bit on its own. A line longer than the
+    /// winning length contains several winning lines and is reported as such.
+    fn get_winning_lines(board: u64) -> Vec<WinningLine> {
+        let mut result = Vec::new();
+        for (bit_shift, direction) in Self::DIR_INCREMENT.into_iter().zip(DIRECTIONS) {
+            let mut flags = Self::get_line_ends(board, bit_shift);
+
+            while flags != 0 {
+                let last = flags.trailing_zeros();
+                flags &= flags - 1;
+
+                let cells = (0..Self::WIN_LENGTH)
+                    .map(|step| {
+                        let position = last - (Self::WIN_LENGTH - 1 - step) * bit_shift as u32;
+                        (position % Self::STRIDE, position / Self::STRIDE)
+                    })
+                    .collect();
+                result.push(WinningLine { cells, direction });
+            }
+        }
+
+        result
+    }
+
+    /// Gets for every position of the gap in a line of winning length the squares, that would complete
+    /// the line in the direction of the shift, as all the other squares of the line hold stones. The
+    /// entry at index i flags the squares with i stones before them and the rest of the line after them.
+    /// The squares do not need to be free, only the first winning length entries are used.
+    #[inline(always)]
+    fn get_line_gaps(board: u64, bit_shift: u8) -> [u64; MAX_WIN_LENGTH as usize] {
+        // The squares with the amount of stones of the index directly before respectively after them.
+        let mut before = [u64::MAX; MAX_WIN_LENGTH as usize];
+        let mut after = [u64::MAX; MAX_WIN_LENGTH as usize];
+        let mut shifted = board;
+        let mut shifted_back = board;
+        for count in 1..Self::WIN_LENGTH as usize {
+            shifted = Self::clip_shift(shifted, bit_shift);
+            shifted_back = Self::clip_shift_inverse(shifted_back, bit_shift);
+            before[count] = before[count - 1] & shifted;
+            after[count] = after[count - 1] & shifted_back;
+        }
+
+        let mut gaps = [0; MAX_WIN_LENGTH as usize];
+        for gap in 0..Self::WIN_LENGTH as usize {
+            gaps[gap] = before[gap] & after[Self::WIN_LENGTH as usize - 1 - gap];
+        }
+        gaps
+    }
+
+    /// Gets the free spots, where placing a stone would complete a winning line for the board. The spots
+    /// do not need to be playable right now. For a length of four these are the patterns XXX_, XX_X,
+    /// X_XX and _XXX in every direction, see *get_line_gaps*.
+    fn get_winning_squares(board: u64, free_spots: u64) -> u64 {
+        let mut result = 0;
+        for bit_shift in Self::DIR_INCREMENT {
+            let gaps = Self::get_line_gaps(board, bit_shift);
+            for gap in &gaps[..Self::WIN_LENGTH as usize] {
+                result |= gap;
+            }
+        }
+
+        result & free_spots
+    }
+
+    /// Gets the playable squares, where a stone of the player completes a winning line. This combines the move
+    /// generation with the win detection, so no move has to be tried out on the board.
+    #[inline(always)]
+    fn get_playable_winning_moves(own: u64, occupied: u64) -> u64 {
+        Self::get_winning_squares(own, !occupied & Self::FULL_BOARD_MASK)
+            & Self::get_playable_squares(occupied)
+    }
+
+    /// Checks if the player can win with the next move.
+    #[inline(always)]
+    fn has_any_playable_winning_move(own: u64, occupied: u64) -> bool {
+        Self::get_playable_winning_moves(own, occupied) != 0
+    }
+
+    /// Gets the squares, that still lie on at least one line of winning length, which does not contain a
+    /// stone of the opponent. Only those squares can still contribute to a win of the player. The start of
+    /// every open line is flagged by shifting the non blocked squares back along the line, the clipping
+    /// makes sure lines do not wrap around. Afterwards the starts get expanded to the full lines again.
+    fn get_live_squares(opponent: u64) -> u64 {
+        let open = !opponent & Self::FULL_BOARD_MASK;
+        let mut result = 0;
+        for bit_shift in Self::DIR_INCREMENT {
+            let mut line_start = open;
+            let mut shifted_back = open;
+            for _ in 1..Self::WIN_LENGTH {
+                shifted_back = Self::clip_shift_inverse(shifted_back, bit_shift);
+                line_start &= shifted_back;
+            }
+
+            let mut line = line_start;
+            result |= line;
+            for _ in 1..Self::WIN_LENGTH {
+                line = Self::clip_shift(line, bit_shift);
+                result |= line;
+            }
+        }
+
+        result
+    }
+
+    /// Encodes a position uniquely in one number. Besides the own stones a marker bit is set on the lowest
+    /// free square of every column, for full columns this is the square in the row above the board. So the
+    /// highest bit of a column tells the amount of stones and the bits below are the own ones. This needs
+    /// the row above the board to fit into the key, which is checked at compile time.
+    #[inline(always)]
+    fn get_position_key(own: u64, occupied: u64) -> Self::Key {
+        const {
+            assert!(
+                Self::WIDTH * (Self::HEIGHT + 1) <= Self::Key::BITS,
+                "The position key needs a row above the board, use a wider key."
+            )
+        };
+        let occupied = occupied as u128;
+        let markers = ((occupied << Self::STRIDE) | Self::BOTTOM_FILL_MASK as u128) & !occupied;
+        Self::Key::from_wide(own as u128 | markers)
+    }
+
+    /// Gets the coded move for popping the bottom stone out of the column under the Pop Out rules. This is
+    /// the bottom square of the column together with a flag above the board, so a pop can not be mixed up
+    /// with a drop, but the column can be read off the same way.
+    #[inline(always)]
+    fn get_pop_move(column: u32) -> u64 {
+        const {
+            assert!(
+                Self::WIDTH * Self::HEIGHT < 64,
+                "The pop moves need a free bit above the board."
+            )
+        };
+        Self::get_bit_representation(column, 0) | POP_FLAG
+    }
+
+    /// Checks if the coded move pops a stone instead of dropping one.
+    #[inline(always)]
+    fn is_pop_move(coded_move: u64) -> bool {
+        coded_move & POP_FLAG != 0
+    }
+
+    /// Removes the bottom square of the column and lets the squares above fall down one row.
+    #[inline(always)]
+    fn pop_column(board: u64, column: u32) -> u64 {
+        let column_mask = Self::get_column_mask(column);
+        (board & !column_mask) | ((board & column_mask) >> Self::STRIDE)
+    }
+
+    /// Does the inverse of *pop_column*, the squares of the column rise one row and the bottom square
+    /// is empty afterwards.
+    #[inline(always)]
+    fn unpop_column(board: u64, column: u32) -> u64 {
+        let column_mask = Self::get_column_mask(column);
+        (board & !column_mask) | (((board & column_mask) << Self::STRIDE) & column_mask)
+    }
+
+    /// Gets an iterator for all possible moves for the AI. The iterator returns the move and the original
+    /// move index. The moves come from the center outwards, as the central columns tend to be the better
+    /// ones, which gives the search a good order, where nothing else tells the moves apart.
+    #[inline(always)]
+    fn get_all_possible_moves(board: u64) -> impl Iterator<Item = (u64, u32)> {
+        let comb = Self::get_playable_squares(board);
+        (0..Self::WIDTH)
+            .map(Self::get_centered_column)
+            .map(move |x| (comb & Self::get_column_mask(x), x))
+            .filter(|&x| x.0 != 0)
+    }
 }
 
-/// Gets an iterator for all possible moves for the AI. The iterator returns the move and the original
-/// move index.
-#[inline(always)]
-pub fn get_all_possible_moves(board: u64) -> impl Iterator<Item = (u64, u32)> {
-    let comb = (clip_shift(board, DIR_INCREMENT[1]) | BOTTOM_FILL_MASK) ^ board;
-    (0..7)
-        .map(move |x| (comb & COLUMN_MASK[x], x as u32))
-        .filter(|&x| x.0 != 0)
+/// A board of the given dimensions and winning length, which is four if not given. It does not carry
+/// any data, it only selects the masks. The position key is a 64 bit number, this holds up to 8x7.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Geometry<const WIDTH: u32, const HEIGHT: u32, const WIN_LENGTH: u32 = 4>;
+
+impl<const WIDTH: u32, const HEIGHT: u32, const WIN_LENGTH: u32> BoardGeometry
+    for Geometry<WIDTH, HEIGHT, WIN_LENGTH>
+{
+    type Key = u64;
+
+    const WIDTH: u32 = WIDTH;
+    const HEIGHT: u32 = HEIGHT;
+    const WIN_LENGTH: u32 = WIN_LENGTH;
+}
+
+/// A board like [`Geometry`], whose position key does not fit into 64 bits together with the row above
+/// the board, like 9x7. The key is a 128 bit number, which makes the transposition table larger.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WideGeometry<const WIDTH: u32, const HEIGHT: u32, const WIN_LENGTH: u32 = 4>;
+
+impl<const WIDTH: u32, const HEIGHT: u32, const WIN_LENGTH: u32> BoardGeometry
+    for WideGeometry<WIDTH, HEIGHT, WIN_LENGTH>
+{
+    type Key = u128;
+
+    const WIDTH: u32 = WIDTH;
+    const HEIGHT: u32 = HEIGHT;
+    const WIN_LENGTH: u32 = WIN_LENGTH;
+}
+
+/// The standard Connect-4 board with seven columns and six rows.
+pub type StandardGeometry = Geometry<7, 6>;
+
+/// The Connect-5 variant, where five stones in a row win on a board with nine columns and seven rows.
+pub type ConnectFiveGeometry = WideGeometry<9, 7, 5>;
+
+/// The width of the standard board.
+pub const BOARD_WIDTH: u32 = StandardGeometry::WIDTH;
+
+/// The height of the standard board.
+pub const BOARD_HEIGHT: u32 = StandardGeometry::HEIGHT;
+
+/// The amount of winning lines on the standard board: 24 horizontal, 21 vertical and 12 on each of the
+/// diagonals.
+pub const WINNING_LINE_COUNT: usize = 69;
+
+/// The winning lines of the standard board as an array, see [`BoardGeometry::WINNING_LINES`].
+pub const WINNING_LINES: [u64; WINNING_LINE_COUNT] = get_standard_winning_lines();
+
+/// Copies the winning lines of the standard board into an array of their amount.
+const fn get_standard_winning_lines() -> [u64; WINNING_LINE_COUNT] {
+    let table = StandardGeometry::WINNING_LINE_TABLE;
+    assert!(
+        table.1 == WINNING_LINE_COUNT,
+        "The standard board has a different amount of winning lines."
+    );
+    let mut lines = [0; WINNING_LINE_COUNT];
+    let mut index = 0;
+    while index < WINNING_LINE_COUNT {
+        lines[index] = table.0[index];
+        index += 1;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::bit_board::GenericBitBoard;
+    use crate::selfplay::SplitMix64;
+
+    /// The steps of the direction increments as column and row.
+    const STEPS: [(i32, i32); 4] = [(-1, 1), (0, 1), (1, 1), (1, 0)];
+
+    /// Moves every stone by the step in coordinates, the stones leaving the board get lost.
+    fn shift_by_coordinates<G: BoardGeometry>(board: u64, (step_x, step_y): (i32, i32)) -> u64 {
+        G::get_position_iterator(board)
+            .map(|(x, y)| (x as i32 + step_x, y as i32 + step_y))
+            .filter(|&(x, y)| x >= 0 && x < G::WIDTH as i32 && y >= 0 && y < G::HEIGHT as i32)
+            .fold(0, |result, (x, y)| {
+                result | G::get_bit_representation(x as u32, y as u32)
+            })
+    }
+
+    /// Checks, that the shifts of all directions do not wrap around the edges of the board.
+    fn assert_shifts_stay_on_board<G: BoardGeometry>() {
+        let mut random = SplitMix64::new(G::WIDTH as u64);
+        let boards = [
+            G::FULL_BOARD_MASK,
+            G::FIRST_COLUMN_MASK,
+            G::LAST_COLUMN_MASK,
+            G::BOTTOM_FILL_MASK,
+        ]
+        .into_iter()
+        .chain((0..100).map(|_| random.next_u64() & G::FULL_BOARD_MASK));
+        for board in boards {
+            for (amount, (step_x, step_y)) in G::DIR_INCREMENT.into_iter().zip(STEPS) {
+                assert_eq!(
+                    G::clip_shift(board, amount),
+                    shift_by_coordinates::<G>(board, (step_x, step_y)),
+                    "{board:#x} shifted by {amount}"
+                );
+                assert_eq!(
+                    G::clip_shift_inverse(board, amount),
+                    shift_by_coordinates::<G>(board, (-step_x, -step_y)),
+                    "{board:#x} shifted back by {amount}"
+                );
+            }
+        }
+    }
+
+    /// Checks, that the pops can be told apart from the drops and have the right column.
+    fn assert_pops_are_coded<G: BoardGeometry>() {
+        for column in 0..G::WIDTH {
+            let pop = G::get_pop_move(column);
+            assert!(G::is_pop_move(pop));
+            assert_eq!(pop.trailing_zeros() % G::STRIDE, column);
+            for row in 0..G::HEIGHT {
+                assert!(!G::is_pop_move(G::get_bit_representation(column, row)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_shifts_stay_on_board() {
+        assert_shifts_stay_on_board::<StandardGeometry>();
+        assert_shifts_stay_on_board::<Geometry<5, 4>>();
+        assert_shifts_stay_on_board::<Geometry<8, 7>>();
+        assert_shifts_stay_on_board::<ConnectFiveGeometry>();
+    }
+
+    #[test]
+    fn test_pops_are_coded() {
+        assert_pops_are_coded::<StandardGeometry>();
+        assert_pops_are_coded::<Geometry<8, 7>>();
+        assert_pops_are_coded::<ConnectFiveGeometry>();
+    }
+
+    #[test]
+    fn test_moves_come_from_the_center() {
+        let columns: Vec<u32> = StandardGeometry::get_all_possible_moves(0)
+            .map(|(_, column)| column)
+            .collect();
+        assert_eq!(columns, [3, 2, 4, 1, 5, 0, 6]);
+        let columns: Vec<u32> = ConnectFiveGeometry::get_all_possible_moves(0)
+            .map(|(_, column)| column)
+            .collect();
+        assert_eq!(columns, [4, 3, 5, 2, 6, 1, 7, 0, 8]);
+        // On an even width there are two center columns, the left one comes first.
+        let columns: Vec<u32> = (0..8).map(Geometry::<8, 7>::get_centered_column).collect();
+        assert_eq!(columns, [3, 4, 2, 5, 1, 6, 0, 7]);
+
+        // A full column is left out, the order of the others stays.
+        let board = StandardGeometry::get_column_mask(2);
+        let columns: Vec<u32> = StandardGeometry::get_all_possible_moves(board)
+            .map(|(_, column)| column)
+            .collect();
+        assert_eq!(columns, [3, 4, 1, 5, 0, 6]);
+    }
+
+    #[test]
+    fn test_winning_line_count() {
+        assert_eq!(StandardGeometry::WINNING_LINES.len(), WINNING_LINE_COUNT);
+        // 5 horizontal and 3 vertical per column or row, 3 on each diagonal per start.
+        assert_eq!(
+            ConnectFiveGeometry::WINNING_LINES.len(),
+            5 * 7 + 9 * 3 + 2 * 5 * 3
+        );
+    }
+
+    #[test]
+    fn test_winning_lines_are_distinct() {
+        assert_eq!(WINNING_LINES, StandardGeometry::WINNING_LINES);
+        let mut lines = WINNING_LINES.to_vec();
+        lines.sort_unstable();
+        lines.dedup();
+        assert_eq!(lines.len(), WINNING_LINE_COUNT);
+        for line in lines {
+            assert_eq!(line.count_ones(), 4, "{line:#x}");
+            assert_eq!(line & !StandardGeometry::FULL_BOARD_MASK, 0, "{line:#x}");
+        }
+    }
+
+    #[test]
+    fn test_lines_through() {
+        let center = StandardGeometry::get_bit_representation(3, 2);
+        // 4 horizontal, 3 vertical and 3 on each diagonal.
+        assert_eq!(StandardGeometry::get_lines_through(center).count(), 13);
+        // A corner has one line in each direction but the other diagonal.
+        let corner = StandardGeometry::get_bit_representation(0, 0);
+        assert_eq!(StandardGeometry::get_lines_through(corner).count(), 3);
+        for line in StandardGeometry::get_lines_through(center | corner) {
+            assert_ne!(line & (center | corner), 0, "{line:#x}");
+        }
+        assert_eq!(
+            StandardGeometry::get_lines_through(StandardGeometry::FULL_BOARD_MASK).count(),
+            WINNING_LINE_COUNT
+        );
+        assert_eq!(StandardGeometry::get_lines_through(0).count(), 0);
+    }
+
+    /// Checks the live squares against the winning lines without a stone of the opponent.
+    fn assert_live_squares_match_the_lines<G: BoardGeometry>() {
+        assert_eq!(G::get_live_squares(0), G::FULL_BOARD_MASK);
+        assert_eq!(G::get_live_squares(G::FULL_BOARD_MASK), 0);
+        let mut random = SplitMix64::new(u64::from(G::WIDTH * G::HEIGHT + 1));
+        for _ in 0..1000 {
+            let opponent = random.next_u64() & random.next_u64() & G::FULL_BOARD_MASK;
+            let live: u64 = G::WINNING_LINES
+                .iter()
+                .filter(|&&line| line & opponent == 0)
+                .fold(0, |result, line| result | line);
+            assert_eq!(G::get_live_squares(opponent), live, "{opponent:#x}");
+        }
+    }
+
+    #[test]
+    fn test_live_squares_match_the_lines() {
+        assert_live_squares_match_the_lines::<StandardGeometry>();
+        assert_live_squares_match_the_lines::<ConnectFiveGeometry>();
+        assert_live_squares_match_the_lines::<Geometry<5, 4>>();
+    }
+
+    /// Gets the winning lines of the stones at the positions on the standard board.
+    fn get_lines(cells: &[(u32, u32)]) -> Vec<WinningLine> {
+        let board = cells.iter().fold(0, |board, &(x, y)| {
+            board | StandardGeometry::get_bit_representation(x, y)
+        });
+        StandardGeometry::get_winning_lines(board)
+    }
+
+    #[test]
+    fn test_winning_lines_are_told_apart() {
+        let cells = |x: u32, y: u32, dx: i32, dy: i32| -> Vec<(u32, u32)> {
+            (0..4)
+                .map(|step| ((x as i32 + dx * step) as u32, (y as i32 + dy * step) as u32))
+                .collect()
+        };
+        assert_eq!(
+            get_lines(&cells(1, 2, 1, 1)),
+            [WinningLine {
+                cells: cells(1, 2, 1, 1),
+                direction: Direction::RisingDiagonal
+            }]
+        );
+        assert_eq!(
+            get_lines(&cells(3, 1, -1, 1)),
+            [WinningLine {
+                cells: cells(3, 1, -1, 1),
+                direction: Direction::FallingDiagonal
+            }]
+        );
+
+        // Two fours crossing in d1.
+        let mut crossing = cells(0, 0, 1, 0);
+        crossing.extend(cells(3, 0, 0, 1));
+        let mut lines = get_lines(&crossing);
+        lines.sort_by_key(|line| line.cells.clone());
+        assert_eq!(
+            lines,
+            [
+                WinningLine {
+                    cells: cells(0, 0, 1, 0),
+                    direction: Direction::Horizontal
+                },
+                WinningLine {
+                    cells: cells(3, 0, 0, 1),
+                    direction: Direction::Vertical
+                },
+            ]
+        );
+
+        // Five in a row are two overlapping fours in the same direction.
+        let mut five = cells(0, 0, 1, 0);
+        five.push((4, 0));
+        let mut lines = get_lines(&five);
+        lines.sort_by_key(|line| line.cells.clone());
+        assert_eq!(
+            lines,
+            [
+                WinningLine {
+                    cells: cells(0, 0, 1, 0),
+                    direction: Direction::Horizontal
+                },
+                WinningLine {
+                    cells: cells(1, 0, 1, 0),
+                    direction: Direction::Horizontal
+                },
+            ]
+        );
+        assert!(get_lines(&cells(0, 0, 1, 0)[..3]).is_empty());
+    }
+
+    #[test]
+    fn test_every_line_is_found_on_its_own() {
+        for &line in StandardGeometry::WINNING_LINES {
+            let lines = StandardGeometry::get_winning_lines(line);
+            assert_eq!(lines.len(), 1, "{line:#x}");
+            let board = lines[0].cells.iter().fold(0, |board, &(x, y)| {
+                board | StandardGeometry::get_bit_representation(x, y)
+            });
+            assert_eq!(board, line);
+        }
+    }
+
+    /// Checks the win detection against the winning lines, that are built from coordinates. Every line
+    /// wins on its own, but not without any of its stones. Random boards win exactly, if they contain one
+    /// of the lines.
+    fn assert_wins_are_detected<G: BoardGeometry>() {
+        for &line in G::WINNING_LINES {
+            assert!(G::check_for_winning(line), "{line:#x}");
+            assert_eq!(G::get_winning_board(line), line);
+            for (x, y) in G::get_position_iterator(line) {
+                let broken = line & !G::get_bit_representation(x, y);
+                assert!(!G::check_for_winning(broken), "{broken:#x}");
+            }
+        }
+
+        let mut random = SplitMix64::new(u64::from(G::WIDTH * G::HEIGHT));
+        for _ in 0..1000 {
+            // Sparse boards, so that both outcomes come up.
+            let board = random.next_u64() & random.next_u64() & G::FULL_BOARD_MASK;
+            let contained: u64 = G::WINNING_LINES
+                .iter()
+                .filter(|&&line| line & board == line)
+                .fold(0, |result, line| result | line);
+            assert_eq!(G::check_for_winning(board), contained != 0, "{board:#x}");
+            assert_eq!(G::get_winning_board(board), contained, "{board:#x}");
+        }
+    }
+
+    /// Fills the board with moves into random columns and checks after every move, that there is a move
+    /// for every column, that is not full, on the lowest free square.
+    fn assert_moves_are_generated<G: BoardGeometry>() {
+        let mut random = SplitMix64::new(u64::from(G::WIDTH + G::HEIGHT));
+        for _ in 0..20 {
+            let mut heights = vec![0; G::WIDTH as usize];
+            let mut board = 0;
+            loop {
+                let expected: Vec<(u64, u32)> = (0..G::WIDTH)
+                    .filter(|&column| heights[column as usize] < G::HEIGHT)
+                    .map(|column| {
+                        let square = G::get_bit_representation(column, heights[column as usize]);
+                        (square, column)
+                    })
+                    .collect();
+                let mut generated: Vec<(u64, u32)> = G::get_all_possible_moves(board).collect();
+                generated.sort_unstable_by_key(|&(_, column)| column);
+                assert_eq!(generated, expected, "{board:#x}");
+                for column in 0..G::WIDTH {
+                    let square = expected
+                        .iter()
+                        .find(|&&(_, free)| free == column)
+                        .map_or(0, |&(square, _)| square);
+                    assert_eq!(G::get_possible_move(board, column), square);
+                }
+
+                if expected.is_empty() {
+                    break;
+                }
+                let (square, column) = expected[random.next_below(expected.len() as u32) as usize];
+                board |= square;
+                heights[column as usize] += 1;
+            }
+            assert_eq!(board, G::FULL_BOARD_MASK);
+        }
+    }
+
+    #[test]
+    fn test_wins_are_detected() {
+        assert_wins_are_detected::<StandardGeometry>();
+        assert_wins_are_detected::<Geometry<5, 4>>();
+        assert_wins_are_detected::<Geometry<8, 7>>();
+        assert_wins_are_detected::<ConnectFiveGeometry>();
+    }
+
+    #[test]
+    fn test_moves_are_generated() {
+        assert_moves_are_generated::<StandardGeometry>();
+        assert_moves_are_generated::<Geometry<5, 4>>();
+        assert_moves_are_generated::<Geometry<8, 7>>();
+    }
+
+    /// Checks the properties of the primitives, that have to hold on every position of a game.
+    fn assert_invariants<G: BoardGeometry>(own: u64, opponent: u64) {
+        let occupied = own | opponent;
+        for board in [own, opponent, occupied] {
+            let flipped = G::flip_board(board);
+            assert_eq!(flipped & !G::FULL_BOARD_MASK, 0, "{board:#x}");
+            assert_eq!(G::flip_board(flipped), board, "{board:#x}");
+            assert_eq!(
+                G::check_for_winning(board),
+                G::check_for_winning(flipped),
+                "{board:#x}"
+            );
+
+            let winning = G::get_winning_board(board);
+            assert_eq!(winning & !board, 0, "{board:#x}");
+            assert_eq!(winning != 0, G::check_for_winning(board), "{board:#x}");
+            if winning != 0 {
+                assert!(G::check_for_winning(winning), "{board:#x}");
+            }
+
+            for amount in G::DIR_INCREMENT {
+                let there_and_back = G::clip_shift_inverse(G::clip_shift(board, amount), amount);
+                assert_eq!(there_and_back & !board, 0, "{board:#x} shifted by {amount}");
+            }
+        }
+
+        for (coded_move, column) in G::get_all_possible_moves(occupied) {
+            let column_mask = G::get_column_mask(column);
+            assert_eq!(coded_move.count_ones(), 1);
+            assert_eq!(coded_move & !column_mask, 0);
+            assert_eq!(coded_move & occupied, 0);
+            // The square below is either occupied or the floor, the ones above are all free.
+            assert!(
+                coded_move & G::BOTTOM_FILL_MASK != 0
+                    || G::clip_shift_inverse(coded_move, G::DIR_INCREMENT[1]) & occupied != 0
+            );
+            assert!(occupied & column_mask < coded_move);
+        }
+    }
+
+    /// Plays random legal games and checks the invariants after every move, then takes the moves back
+    /// one by one and checks, that the positions of the game come back on the way.
+    fn assert_invariants_in_random_games<G: BoardGeometry>(seed: u64) {
+        let mut random = SplitMix64::new(seed);
+        for _ in 0..200 {
+            let mut board = GenericBitBoard::<G>::new();
+            let mut positions = Vec::new();
+            while !board.is_game_over() {
+                positions.push((board.own_stones, board.opponent_stones));
+                assert_invariants::<G>(board.own_stones, board.opponent_stones);
+                let columns: Vec<u32> = (0..G::WIDTH)
+                    .filter(|&column| board.get_possible_move(column) != 0)
+                    .collect();
+                let column = columns[random.next_below(columns.len() as u32) as usize];
+                board.play(column).unwrap();
+            }
+            assert_invariants::<G>(board.own_stones, board.opponent_stones);
+            while let Some(position) = positions.pop() {
+                assert!(board.undo_last_move().is_some());
+                assert_eq!((board.own_stones, board.opponent_stones), position);
+                assert_invariants::<G>(board.own_stones, board.opponent_stones);
+            }
+            assert!(board.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_invariants_in_random_games() {
+        assert_invariants_in_random_games::<StandardGeometry>(1);
+        assert_invariants_in_random_games::<Geometry<5, 4>>(2);
+        assert_invariants_in_random_games::<ConnectFiveGeometry>(3);
+    }
+
+    /// Checks the win detection on every possible board of the geometry against the winning lines.
+    fn assert_wins_are_detected_exhaustively<G: BoardGeometry>() {
+        for board in 0..=G::FULL_BOARD_MASK {
+            let has_line = G::WINNING_LINES.iter().any(|&line| line & !board == 0);
+            assert_eq!(G::check_for_winning(board), has_line, "{board:#x}");
+        }
+    }
+
+    #[test]
+    fn test_wins_are_detected_exhaustively() {
+        assert_wins_are_detected_exhaustively::<Geometry<5, 4>>();
+        assert_wins_are_detected_exhaustively::<Geometry<4, 4>>();
+        assert_wins_are_detected_exhaustively::<Geometry<4, 4, 3>>();
+        assert_wins_are_detected_exhaustively::<Geometry<3, 5, 3>>();
+    }
 }
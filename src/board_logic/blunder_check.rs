@@ -0,0 +1,147 @@
+//! The blunder check is a learning aid for the player. Before a move gets played, it looks a few plies
+//! ahead and checks, whether the opponent can force a win against it. This covers ignoring an immediate
+//! win of the opponent as well as moves, that run into a combination. The check only knows won and lost,
+//! there is no heuristic, so it is fast enough to run synchronously, while the player is to move.
+//!
+//! A move is only reported as a blunder, if there is a move, that does not lose within the horizon. When
+//! every move loses, there is nothing to warn about.
+
+use crate::board_logic::bit_board::{GameResult, GenericBitBoard, Side};
+use crate::board_logic::bit_board_coding::BoardGeometry;
+
+/// The plies the check looks ahead after the move of the player, the opponent may win in its first or
+/// its second move.
+pub const BLUNDER_CHECK_DEPTH: u32 = 4;
+
+/// Gets the side, that has won the game on the board, none if the game is not won.
+fn get_winner<G: BoardGeometry>(board: &GenericBitBoard<G>) -> Option<Side> {
+    let first_player = if board.get_computer_first() {
+        Side::Computer
+    } else {
+        Side::Player
+    };
+    let second_player = if board.get_computer_first() {
+        Side::Player
+    } else {
+        Side::Computer
+    };
+    match board.get_winning_status_for_rendering().0 {
+        GameResult::FirstPlayerWon => Some(first_player),
+        GameResult::SecondPlayerWon => Some(second_player),
+        _ => None,
+    }
+}
+
+/// Gets the slots, that can be played on the board by the side to move. These are the columns for the
+/// drops followed by the columns for the pops under the Pop Out rules.
+fn get_playable_slots<G: BoardGeometry>(board: &GenericBitBoard<G>) -> Vec<u32> {
+    let is_computer = board.side_to_move().is_computer();
+    let drops = (0..G::WIDTH).filter(|&column| board.get_possible_move(column) != 0);
+    let pops = (0..G::WIDTH)
+        .filter(|&column| board.can_pop(column, is_computer))
+        .map(|column| G::WIDTH + column);
+    drops.chain(pops).collect()
+}
+
+/// Checks, if the side can force a win within the indicated amount of plies, whoever is to move. The
+/// board gets played on and is restored in the end.
+fn can_force_win<G: BoardGeometry>(board: &mut GenericBitBoard<G>, side: Side, plies: u32) -> bool {
+    if board.is_game_over() {
+        return get_winner(board) == Some(side);
+    }
+    if plies == 0 {
+        return false;
+    }
+    let attacking = board.side_to_move() == side;
+    let mut outcomes = get_playable_slots(board).into_iter().map(|slot| {
+        board
+            .play_slot(slot)
+            .expect("The slot has been checked to be playable.");
+        let result = can_force_win(board, side, plies - 1);
+        board.undo_last_move();
+        result
+    });
+    // The attacker needs one winning move, the defender must not have any escape.
+    if attacking {
+        outcomes.any(|won| won)
+    } else {
+        outcomes.all(|won| won)
+    }
+}
+
+/// Gets the slot, with which the opponent starts a forced win after the slot got played on the board,
+/// none if there is no forced win within [`BLUNDER_CHECK_DEPTH`] plies. The slot has to be playable.
+fn get_refutation<G: BoardGeometry>(board: &GenericBitBoard<G>, slot: u32) -> Option<u32> {
+    let mover = board.side_to_move();
+    let mut board = board.clone();
+    board.play_slot(slot).ok()?;
+    // With the double move handicap the same side moves again, the opponent has not got a move yet.
+    if board.is_game_over() || board.side_to_move() == mover {
+        return None;
+    }
+    let opponent = board.side_to_move();
+    get_playable_slots(&board).into_iter().find(|&reply| {
+        board
+            .play_slot(reply)
+            .expect("The slot has been checked to be playable.");
+        let won = can_force_win(&mut board, opponent, BLUNDER_CHECK_DEPTH - 1);
+        board.undo_last_move();
+        won
+    })
+}
+
+/// Checks the slot the side to move wants to play on the board. Returns the slot of the opponent, that
+/// starts a forced win against it, none if the move is fine or if every other move loses as well.
+pub fn find_blunder<G: BoardGeometry>(board: &GenericBitBoard<G>, slot: u32) -> Option<u32> {
+    let refutation = get_refutation(board, slot)?;
+    let has_alternative = get_playable_slots(board)
+        .into_iter()
+        .any(|other| other != slot && get_refutation(board, other).is_none());
+    has_alternative.then_some(refutation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::bit_board::BitBoard;
+
+    #[test]
+    fn test_ignored_win_gets_warned() {
+        // The first player has three stones in the left column, the second player is to move.
+        let board = BitBoard::from_move_string("12121").unwrap();
+        assert_eq!(find_blunder(&board, 3), Some(0));
+        assert_eq!(find_blunder(&board, 6), Some(0));
+        assert_eq!(find_blunder(&board, 0), None);
+    }
+
+    #[test]
+    fn test_combination_gets_warned() {
+        // The first player has two stones in the middle of the bottom row with room on both sides.
+        // Unless the second player closes the right side, the third stone makes two threats at once.
+        let board = BitBoard::from_move_string("413").unwrap();
+        assert_eq!(find_blunder(&board, 6), Some(4));
+        assert_eq!(find_blunder(&board, 4), None);
+    }
+
+    #[test]
+    fn test_safe_moves_are_not_warned() {
+        let board = BitBoard::from_move_string("").unwrap();
+        for column in 0..7 {
+            assert_eq!(find_blunder(&board, column), None);
+        }
+        let board = BitBoard::from_move_string("4444").unwrap();
+        for column in 0..7 {
+            assert_eq!(find_blunder(&board, column), None);
+        }
+    }
+
+    #[test]
+    fn test_nothing_gets_warned_when_every_move_loses() {
+        // The first player has three stones in the bottom row, that are open on both sides.
+        let board = BitBoard::from_move_string("33445").unwrap();
+        for column in 0..7 {
+            assert!(get_refutation(&board, column).is_some());
+            assert_eq!(find_blunder(&board, column), None);
+        }
+    }
+}
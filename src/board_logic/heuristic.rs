@@ -1,43 +1,47 @@
 //! This is the place for all functions related to heuristically evaluations of the game situation
-//! Heuristics are kept relatively simple. We account for open positions of three stones, doublets
+//! Heuristics are kept relatively simple. We account for open lines, that lack one stone to win, doublets
 //! whether dead or not and a board scoring that favours positions close to the central column.
+//! Double threats, that can not be blocked any more, get a large bonus. Stones, that can not be part
+//! of a four any more, because all lines through them are blocked, do not count for the board scoring.
 
-use crate::board_logic::bit_board::BitBoard;
-use crate::board_logic::bit_board_coding::{
-    DIR_INCREMENT, FULL_BOARD_MASK, clip_shift, clip_shift_inverse, get_bit_representation,
-};
-
-/// Returns the number of open triplets we have.
-fn count_open_three(board: u64, free_spots: u64) -> u32 {
-    let mut triplets = 0;
-
-    for bit_shift in DIR_INCREMENT {
-        // XXX_ Pattern
-        let double_pos = clip_shift(board, bit_shift) & board;
-        let dd = clip_shift(double_pos, bit_shift) & board;
-        let triplets_after = clip_shift(dd, bit_shift) & free_spots;
-        triplets += triplets_after.count_ones();
-
-        // XX_X pattern
-        let free_match = clip_shift(double_pos, bit_shift) & free_spots;
-        let spot_right = clip_shift(free_match, bit_shift) & board;
-        triplets += spot_right.count_ones();
-
-        // X_XX pattern
-        let free_left_match =
-            clip_shift_inverse(clip_shift_inverse(double_pos, bit_shift), bit_shift) & free_spots;
-        let spot_left = clip_shift_inverse(free_left_match, bit_shift) & board;
-        triplets += spot_left.count_ones();
-
-        // _XXX Pattern
-        let triplets_before = clip_shift_inverse(
-            clip_shift_inverse(clip_shift_inverse(dd, bit_shift), bit_shift),
-            bit_shift,
-        ) & free_spots;
-        triplets += triplets_before.count_ones();
-    }
-
-    triplets
+use crate::board_logic::bit_board::GenericBitBoard;
+use crate::board_logic::bit_board_coding::{BoardGeometry, get_bit};
+use std::marker::PhantomData;
+
+/// Returns the number of open lines we have, these are lines of winning length with one free square and
+/// stones on all the others. For the standard game these are the open triplets.
+fn count_open_lines<G: BoardGeometry>(board: u64, free_spots: u64) -> u32 {
+    let mut lines = 0;
+
+    for bit_shift in G::DIR_INCREMENT {
+        // For a length of four these are the patterns XXX_, XX_X, X_XX and _XXX.
+        let gaps = G::get_line_gaps(board, bit_shift);
+        for gap in &gaps[..G::WIN_LENGTH as usize] {
+            lines += (gap & free_spots).count_ones();
+        }
+    }
+
+    lines
+}
+
+/// Counts the same open lines as [`count_open_lines`], but goes through the winning lines one by one.
+/// This is slower and only serves as a cross-check of the shifts in debug builds.
+fn count_open_lines_by_table<G: BoardGeometry>(board: u64, free_spots: u64) -> u32 {
+    G::WINNING_LINES
+        .iter()
+        .filter(|&&line| {
+            let missing = line & !board;
+            missing.count_ones() == 1 && missing & free_spots != 0
+        })
+        .count() as u32
+}
+
+/// Checks if the winning squares of a player form a double threat. This is either the case, when two
+/// of them are playable at once, or when two of them are stacked on top of each other in one column.
+/// In both cases blocking one of them does not help any more.
+fn has_double_threat<G: BoardGeometry>(winning_squares: u64, playable_squares: u64) -> bool {
+    (winning_squares & playable_squares).count_ones() >= 2
+        || (winning_squares & G::clip_shift(winning_squares, G::DIR_INCREMENT[1])) != 0
 }
 
 /// This function turns standard values from the literature into representations
@@ -58,21 +62,23 @@ const fn make_adjusted_value() -> [f32; 12] {
     local
 }
 
-/// This generates the bit mask to be able to read out the value table from above.
-const fn make_value_bitmask() -> [u64; 12] {
+/// This generates the bit mask to be able to read out the value table from above. The table is made for
+/// the standard board, where the squares are classified by their distance to the center. On other
+/// board dimensions the distances are clamped against the ones of the table.
+const fn make_value_bitmask(width: u32, height: u32) -> [u64; 12] {
     let mut mask: [u64; 12] = [0; 12];
-    let mut y_scan = 0;
-    while y_scan < 3 {
-        let mut x_scan = 0;
-        while x_scan < 4 {
-            mask[((3 - x_scan) + 4 * (2 - y_scan)) as usize] =
-                get_bit_representation(3 - x_scan, 2 - y_scan)
-                    | get_bit_representation(3 + x_scan, 2 - y_scan)
-                    | get_bit_representation(3 - x_scan, 3 + y_scan)
-                    | get_bit_representation(3 + x_scan, 3 + y_scan);
-            x_scan += 1;
-        }
-        y_scan += 1;
+    let mut y = 0;
+    while y < height {
+        let y_scan = (2 * y).abs_diff(height - 1) / 2;
+        let y_scan = if y_scan > 2 { 2 } else { y_scan };
+        let mut x = 0;
+        while x < width {
+            let x_scan = (2 * x).abs_diff(width - 1) / 2;
+            let x_scan = if x_scan > 3 { 3 } else { x_scan };
+            mask[((3 - x_scan) + 4 * (2 - y_scan)) as usize] |= get_bit(x, y, width);
+            x += 1;
+        }
+        y += 1;
     }
     mask
 }
@@ -80,44 +86,725 @@ const fn make_value_bitmask() -> [u64; 12] {
 /// This contains the values for the different board positions.
 const BOARD_POSITION_CODING_VALUE: [f32; 12] = make_adjusted_value();
 
-/// This is the bit masking to index the value mask.
-const VALUE_POSITION_BITMASK: [u64; 12] = make_value_bitmask();
+/// This is the bit masking to index the value mask for every board geometry.
+struct ValuePositionBitmask<G: BoardGeometry>(PhantomData<G>);
+
+impl<G: BoardGeometry> ValuePositionBitmask<G> {
+    const MASK: [u64; 12] = make_value_bitmask(G::WIDTH, G::HEIGHT);
+}
+
+/// The default value of an open triplet.
+const OPEN_THREE_VALUE: f32 = 0.04;
+
+/// The default value of a double threat.
+const DOUBLE_THREAT_VALUE: f32 = 0.5;
+
+/// The default value of a threat, that decides the game with the next move.
+const TEMPO_VALUE: f32 = 0.9;
+
+/// All the weights of the heuristic evaluation, so that they can be experimented with at runtime.
+/// The clamp guard is not part of it, because it is a property of the search: terminal scores must
+/// always dominate the heuristics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeuristicWeights {
+    /// The value of every open triplet, respectively open line one stone short of the winning length.
+    pub open_three: f32,
+    /// The value of having a double threat.
+    pub double_threat: f32,
+    /// The value of a threat, that decides the game with the next move, because of who is to move.
+    pub tempo: f32,
+    /// The value of a stone on the positions of the quarter board. The entries run from the center
+    /// outwards, four columns per row, starting with the rows closest to the vertical center.
+    pub position_values: [f32; 12],
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> HeuristicWeights {
+        HeuristicWeights {
+            open_three: OPEN_THREE_VALUE,
+            double_threat: DOUBLE_THREAT_VALUE,
+            tempo: TEMPO_VALUE,
+            position_values: BOARD_POSITION_CODING_VALUE,
+        }
+    }
+}
 
 /// Evaluates the stones by their position on the board. Gives center stones a higher
 /// value, because they can generate more possibilities in the future.
-fn get_board_scoring(board: u64) -> f32 {
+fn get_board_scoring<G: BoardGeometry>(board: u64, position_values: &[f32; 12]) -> f32 {
     let mut score = 0.0;
 
-    for i in 0..12 {
-        let pos_ind = (board & VALUE_POSITION_BITMASK[i]).count_ones();
-        score += BOARD_POSITION_CODING_VALUE[i] * pos_ind as f32;
+    for (mask, value) in ValuePositionBitmask::<G>::MASK.iter().zip(position_values) {
+        let pos_ind = (board & mask).count_ones();
+        score += value * pos_ind as f32;
     }
 
     score
 }
 
-/// Does the complete heuristic evaluation of the game board.
-pub fn compute_heuristics(board_analyzed: &BitBoard, clamp_guard: f32) -> f32 {
+/// Does the complete heuristic evaluation of the game board with the weights handed over.
+/// The evaluation is from the perspective of the own stones, just after they made their move. So it
+/// is the opponent, who is to move. This is how the search uses it and what the tempo term relies on.
+pub fn compute_heuristics<G: BoardGeometry>(
+    board_analyzed: &GenericBitBoard<G>,
+    weights: &HeuristicWeights,
+    clamp_guard: f32,
+) -> f32 {
+    compute_heuristics_overlooking(board_analyzed, weights, clamp_guard, 0, 0)
+}
+
+/// Does the heuristic evaluation of [`compute_heuristics`], but overlooks the threats of the own stones
+/// on the squares of the first mask and the ones of the opponent on the squares of the second mask. The
+/// open lines, that miss a stone on such a square, do not count, neither do the double threats and the
+/// tempo built from them. The weaker difficulties use it to miss some of the threats like a beginner.
+pub fn compute_heuristics_overlooking<G: BoardGeometry>(
+    board_analyzed: &GenericBitBoard<G>,
+    weights: &HeuristicWeights,
+    clamp_guard: f32,
+    overlooked_own: u64,
+    overlooked_opponent: u64,
+) -> f32 {
     debug_assert!(
         !board_analyzed.is_game_over(),
-        "The game over state should have already been prechecked."
+        "The game over state should have already been prechecked:\n{board_analyzed}"
     );
 
     let free_spots =
-        !(board_analyzed.opponent_stones | board_analyzed.own_stones) & FULL_BOARD_MASK;
+        !(board_analyzed.opponent_stones | board_analyzed.own_stones) & G::FULL_BOARD_MASK;
     let mut score = 0.0;
 
+    // The overlooked squares are not seen as free for the threats.
+    let own_free_spots = free_spots & !overlooked_own;
+    let opp_free_spots = free_spots & !overlooked_opponent;
+
     // 1. Pairing combination
-    let own_triplets = count_open_three(board_analyzed.own_stones, free_spots);
-    score += own_triplets as f32 * 0.04;
-    let opp_triplets = count_open_three(board_analyzed.opponent_stones, free_spots);
-    score -= opp_triplets as f32 * 0.04;
+    let own_lines = count_open_lines::<G>(board_analyzed.own_stones, own_free_spots);
+    score += own_lines as f32 * weights.open_three;
+    let opp_lines = count_open_lines::<G>(board_analyzed.opponent_stones, opp_free_spots);
+    score -= opp_lines as f32 * weights.open_three;
+    debug_assert_eq!(
+        (own_lines, opp_lines),
+        (
+            count_open_lines_by_table::<G>(board_analyzed.own_stones, own_free_spots),
+            count_open_lines_by_table::<G>(board_analyzed.opponent_stones, opp_free_spots)
+        ),
+        "The shifts and the winning lines count different open lines."
+    );
 
-    // 2. board control.
-    score += get_board_scoring(board_analyzed.own_stones);
-    score -= get_board_scoring(board_analyzed.opponent_stones);
+    // 2. Double threats.
+    let playable_squares =
+        G::get_playable_squares(board_analyzed.opponent_stones | board_analyzed.own_stones);
+    let own_winning = G::get_winning_squares(board_analyzed.own_stones, own_free_spots);
+    let opp_winning = G::get_winning_squares(board_analyzed.opponent_stones, opp_free_spots);
+    if has_double_threat::<G>(own_winning, playable_squares) {
+        score += weights.double_threat;
+    }
+    if has_double_threat::<G>(opp_winning, playable_squares) {
+        score -= weights.double_threat;
+    }
+
+    // 3. Tempo, the opponent to move wins with a playable threat. Otherwise they have to block, which
+    // fails if we have more than one playable threat.
+    if opp_winning & playable_squares != 0 {
+        score -= weights.tempo;
+    } else if (own_winning & playable_squares).count_ones() >= 2 {
+        score += weights.tempo;
+    }
+
+    // 4. board control.
+    let own_live = G::get_live_squares(board_analyzed.opponent_stones);
+    let opp_live = G::get_live_squares(board_analyzed.own_stones);
+    score += get_board_scoring::<G>(
+        board_analyzed.own_stones & own_live,
+        &weights.position_values,
+    );
+    score -= get_board_scoring::<G>(
+        board_analyzed.opponent_stones & opp_live,
+        &weights.position_values,
+    );
 
     // We do not clamp against exactly one, so that whatever the outcome is,
     // it will always be dominated by a guaranteed win or loss.
     score.clamp(-clamp_guard, clamp_guard)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::alpha_beta::{AlphaBeta, CLAMP_GUARD_HEURISTIC, SearchConfig};
+    use crate::board_logic::bit_board::BitBoard;
+    use crate::board_logic::bit_board_coding::{ConnectFiveGeometry, Geometry, StandardGeometry};
+    use crate::selfplay::{MatchSettings, SplitMix64, play_match};
+
+    /// The first stone in the center.
+    const CENTER: &str = "
+        .......
+        .......
+        .......
+        .......
+        .......
+        ...X...";
+    /// The first stone next to the center.
+    const BESIDE_CENTER: &str = "
+        .......
+        .......
+        .......
+        .......
+        .......
+        ..X....";
+    /// The first stone in the corner.
+    const CORNER: &str = "
+        .......
+        .......
+        .......
+        .......
+        .......
+        X......";
+    /// The center gets answered on top.
+    const ANSWER_ON_TOP: &str = "
+        .......
+        .......
+        .......
+        .......
+        ...O...
+        ...X...";
+    /// The center gets answered in the corner.
+    const ANSWER_IN_CORNER: &str = "
+        .......
+        .......
+        .......
+        .......
+        .......
+        O..X...";
+    /// Yellow has three in a row on the bottom with both ends open and is to move.
+    const OPEN_ROW_TO_MOVE: &str = "
+        .......
+        .......
+        .......
+        .......
+        ..OOO..
+        ..XXX..";
+    /// Yellow has a vertical three and is to move.
+    const VERTICAL_THREE_TO_MOVE: &str = "
+        .......
+        .......
+        .......
+        ..XO...
+        ..XO...
+        ..XO...";
+    /// Yellow has a threat on the bottom row, that Blue has to block now.
+    const THREAT_TO_BLOCK: &str = "
+        .......
+        .......
+        ...O...
+        ...X...
+        ...O...
+        X.XX.O.";
+    /// The threat of Yellow has been blocked.
+    const THREAT_BLOCKED: &str = "
+        .......
+        .......
+        ...O...
+        ...X...
+        ...O...
+        XOXX.O.";
+    /// Blue has not blocked the three of Yellow, who is to move.
+    const THREE_NOT_BLOCKED: &str = "
+        .......
+        .......
+        .......
+        .......
+        ...O...
+        .OOXXX.";
+    /// Yellow has just made three in a row on the bottom with both ends open.
+    const DOUBLE_THREAT: &str = "
+        .......
+        .......
+        .......
+        .......
+        ..OO...
+        ..XXX..";
+
+    /// The positions, that all the general properties get checked on.
+    const CORPUS: [&str; 23] = [
+        CENTER,
+        BESIDE_CENTER,
+        CORNER,
+        ANSWER_ON_TOP,
+        ANSWER_IN_CORNER,
+        OPEN_ROW_TO_MOVE,
+        VERTICAL_THREE_TO_MOVE,
+        THREAT_TO_BLOCK,
+        THREAT_BLOCKED,
+        THREE_NOT_BLOCKED,
+        DOUBLE_THREAT,
+        "
+        .......
+        .......
+        .......
+        .......
+        ..XX...
+        ..XOO..",
+        "
+        .......
+        .......
+        .......
+        .......
+        ....X.O
+        OXO.X.X",
+        "
+        .......
+        .......
+        ...X...
+        ...X...
+        ...O...
+        .O.X.XO",
+        "
+        .......
+        .......
+        .......
+        .X.....
+        .XO....
+        .XOO...",
+        "
+        .......
+        .......
+        .......
+        ...X...
+        ..OXO..
+        ..XOX..",
+        "
+        .......
+        .......
+        .......
+        .......
+        XOO...X
+        XOXO.XO",
+        "
+        .......
+        .......
+        .......
+        ...X...
+        ..OOX..
+        ..OXOX.",
+        "
+        .......
+        .......
+        .......
+        .......
+        ......X
+        O....OX",
+        "
+        ...O...
+        ...X...
+        ...O...
+        ...X...
+        ...O...
+        ..XX.O.",
+        "
+        .......
+        .......
+        .......
+        ..X....
+        ..OX...
+        OXOX...",
+        "
+        .......
+        .......
+        ...X...
+        ...O...
+        ...X...
+        ...XO..",
+        "
+        .......
+        .......
+        .......
+        .......
+        X..O...
+        XXXOO..",
+    ];
+
+    /// Reads the grid and sets the board up from the perspective of the player, who made the last move,
+    /// as the heuristics expect it.
+    fn get_board(grid: &str) -> BitBoard {
+        let mut board = BitBoard::from_ascii(grid).unwrap();
+        board.swap_players();
+        board
+    }
+
+    fn get_value(board: &BitBoard) -> f32 {
+        compute_heuristics(board, &HeuristicWeights::default(), CLAMP_GUARD_HEURISTIC)
+    }
+
+    /// Gets the tempo term of the evaluation, the only part, that depends on who is to move.
+    fn get_tempo(board: &BitBoard) -> f32 {
+        let occupied = board.own_stones | board.opponent_stones;
+        let free_spots = !occupied & StandardGeometry::FULL_BOARD_MASK;
+        let playable = StandardGeometry::get_playable_squares(occupied);
+        let own_winning = StandardGeometry::get_winning_squares(board.own_stones, free_spots);
+        let opp_winning = StandardGeometry::get_winning_squares(board.opponent_stones, free_spots);
+        if opp_winning & playable != 0 {
+            -TEMPO_VALUE
+        } else if (own_winning & playable).count_ones() >= 2 {
+            TEMPO_VALUE
+        } else {
+            0.0
+        }
+    }
+
+    #[test]
+    fn test_corpus_is_mirror_invariant() {
+        for grid in CORPUS {
+            let board = get_board(grid);
+            let mut flipped = board.clone();
+            flipped.own_stones = StandardGeometry::flip_board(board.own_stones);
+            flipped.opponent_stones = StandardGeometry::flip_board(board.opponent_stones);
+            let mirrored_grid: Vec<String> = grid
+                .lines()
+                .map(|line| line.trim().chars().rev().collect())
+                .collect();
+            let mirrored = get_board(&mirrored_grid.join("\n"));
+            assert_eq!(
+                (flipped.own_stones, flipped.opponent_stones),
+                (mirrored.own_stones, mirrored.opponent_stones)
+            );
+            assert_eq!(get_value(&board), get_value(&flipped), "{grid}");
+        }
+    }
+
+    #[test]
+    fn test_corpus_is_antisymmetric() {
+        for grid in CORPUS {
+            let board = get_board(grid);
+            let mut swapped = board.clone();
+            swapped.swap_players();
+            let (value, swapped_value) = (get_value(&board), get_value(&swapped));
+            if value.abs() == CLAMP_GUARD_HEURISTIC || swapped_value.abs() == CLAMP_GUARD_HEURISTIC
+            {
+                continue;
+            }
+            // Apart from the tempo, who is to move does not matter.
+            let tempo = get_tempo(&board) + get_tempo(&swapped);
+            assert!(
+                (value + swapped_value - tempo).abs() < 1e-5,
+                "{grid}\n{value} and {swapped_value}"
+            );
+        }
+        // Without any threats the evaluation is exactly antisymmetric.
+        for grid in [CENTER, CORNER, ANSWER_ON_TOP, ANSWER_IN_CORNER] {
+            let mut board = get_board(grid);
+            let value = get_value(&board);
+            board.swap_players();
+            assert!((value + get_value(&board)).abs() < 1e-6, "{grid}");
+        }
+    }
+
+    #[test]
+    fn test_corpus_signs() {
+        for grid in [CENTER, BESIDE_CENTER, THREAT_TO_BLOCK, DOUBLE_THREAT] {
+            assert!(get_value(&get_board(grid)) > 0.0, "{grid}");
+        }
+        let losing = [OPEN_ROW_TO_MOVE, VERTICAL_THREE_TO_MOVE, THREE_NOT_BLOCKED];
+        for grid in [ANSWER_IN_CORNER].iter().chain(&losing) {
+            assert!(get_value(&get_board(grid)) < 0.0, "{grid}");
+        }
+        // A threat, that can be played at once, decides the game, as does a double threat.
+        for grid in losing {
+            assert!(get_value(&get_board(grid)) < -TEMPO_VALUE + 0.1, "{grid}");
+        }
+        assert!(get_value(&get_board(DOUBLE_THREAT)) > TEMPO_VALUE - 0.1);
+    }
+
+    #[test]
+    fn test_corpus_ordering() {
+        let pairs = [
+            (CENTER, BESIDE_CENTER),
+            (BESIDE_CENTER, CORNER),
+            (ANSWER_ON_TOP, ANSWER_IN_CORNER),
+            (THREAT_BLOCKED, OPEN_ROW_TO_MOVE),
+        ];
+        for (better, worse) in pairs {
+            assert!(
+                get_value(&get_board(better)) > get_value(&get_board(worse)),
+                "{better}\nis not better than\n{worse}"
+            );
+        }
+        // The threat is worth more, before it has been blocked.
+        let mut blocked = get_board(THREAT_BLOCKED);
+        blocked.swap_players();
+        assert!(get_value(&get_board(THREAT_TO_BLOCK)) > get_value(&blocked));
+    }
+
+    /// Counts the open lines of random boards with the shifts and with the winning lines.
+    fn assert_open_lines_match_the_table<G: BoardGeometry>() {
+        let mut random = SplitMix64::new(u64::from(G::WIDTH * G::HEIGHT));
+        for _ in 0..1000 {
+            let occupied = random.next_u64() & random.next_u64() & G::FULL_BOARD_MASK;
+            let board = occupied & random.next_u64();
+            let free_spots = !occupied & G::FULL_BOARD_MASK;
+            assert_eq!(
+                count_open_lines::<G>(board, free_spots),
+                count_open_lines_by_table::<G>(board, free_spots),
+                "{board:#x} {free_spots:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_open_lines_match_the_table() {
+        assert_open_lines_match_the_table::<StandardGeometry>();
+        assert_open_lines_match_the_table::<ConnectFiveGeometry>();
+        assert_open_lines_match_the_table::<Geometry<6, 5>>();
+    }
+
+    /// The evaluations of the corpus with the default weights as bits, so that a change of the weights
+    /// or of the way they are applied shows up, even if it only touches the last bit.
+    const CORPUS_VALUES: [u32; 23] = [
+        0x3d1374bd, 0x3c1374bd, 0x00000000, 0x3ce56042, 0xbd1374bd, 0xbf7851ec, 0xbf4f1a9f,
+        0x3ca3d708, 0x3cac0830, 0xbf6978d5, 0x3f7851ec, 0x3d83126f, 0x3d16872c, 0x3e3020c4,
+        0xbf5f7ced, 0x3d978d50, 0x3d883128, 0xbdc28f5c, 0x3a449ba6, 0xbc031270, 0x3e041894,
+        0x3dba5e37, 0xbdca3d71,
+    ];
+
+    #[test]
+    fn test_default_weights_are_the_former_constants() {
+        #[rustfmt::skip]
+        let position_values: [f32; 12] = [
+            0.0, 0.001, 0.009, 0.036,
+            0.00025, 0.004, 0.036, 0.064,
+            0.00225, 0.009, 0.064, 0.1,
+        ];
+        let weights = HeuristicWeights::default();
+        assert_eq!(weights.open_three, 0.04);
+        assert_eq!(weights.double_threat, 0.5);
+        assert_eq!(weights.tempo, 0.9);
+        for (value, expected) in weights.position_values.iter().zip(position_values) {
+            assert!((value - expected).abs() < 1e-7, "{value} {expected}");
+        }
+    }
+
+    #[test]
+    fn test_default_weights_evaluate_the_corpus_unchanged() {
+        for (grid, bits) in CORPUS.iter().zip(CORPUS_VALUES) {
+            let value = get_value(&get_board(grid));
+            assert_eq!(value.to_bits(), bits, "{grid}\n{value}");
+        }
+    }
+
+    #[test]
+    fn test_weights_change_the_evaluation() {
+        let board = get_board(THREAT_TO_BLOCK);
+        let default_value = get_value(&board);
+        let weights = HeuristicWeights {
+            open_three: 0.2,
+            ..HeuristicWeights::default()
+        };
+        // Yellow has one open three more than Blue.
+        let value = compute_heuristics(&board, &weights, CLAMP_GUARD_HEURISTIC);
+        assert!((value - default_value - 0.16).abs() < 1e-5, "{value}");
+    }
+
+    #[test]
+    fn test_weights_reach_the_search() {
+        // With the position values turned around the edges are worth most.
+        let weights = HeuristicWeights {
+            position_values: BOARD_POSITION_CODING_VALUE.map(|value| -value),
+            ..HeuristicWeights::default()
+        };
+        let mut engine = AlphaBeta::with_config(SearchConfig {
+            max_depth: 1,
+            move_time: None,
+            weights,
+            ..SearchConfig::default()
+        });
+        let column = engine.get_best_move(BitBoard::new()).unwrap();
+        assert!(column == 0 || column == 6, "{column}");
+        engine.set_config(SearchConfig {
+            max_depth: 1,
+            move_time: None,
+            ..SearchConfig::default()
+        });
+        assert_eq!(engine.get_best_move(BitBoard::new()), Ok(3));
+    }
+
+    #[test]
+    fn test_double_threats_are_detected() {
+        let bit = StandardGeometry::get_bit_representation;
+        let playable = StandardGeometry::get_playable_squares(0);
+        // Two playable threats on the bottom row, or just one of them.
+        assert!(has_double_threat::<StandardGeometry>(
+            bit(1, 0) | bit(5, 0),
+            playable
+        ));
+        assert!(!has_double_threat::<StandardGeometry>(bit(1, 0), playable));
+        // Two threats on top of each other in one column, none of them needs to be playable.
+        assert!(has_double_threat::<StandardGeometry>(
+            bit(4, 2) | bit(4, 3),
+            playable
+        ));
+        assert!(!has_double_threat::<StandardGeometry>(
+            bit(4, 1) | bit(4, 3),
+            playable
+        ));
+        assert!(!has_double_threat::<StandardGeometry>(
+            bit(4, 2) | bit(5, 3),
+            playable
+        ));
+    }
+
+    #[test]
+    fn test_double_threat_beats_a_single_threat() {
+        // The same stones as the double threat, but the left end of the three is blocked.
+        let single_threat = "
+            .......
+            .......
+            .......
+            .......
+            ..OO...
+            .OXXX..";
+        let double_value = get_value(&get_board(DOUBLE_THREAT));
+        let single_value = get_value(&get_board(single_threat));
+        assert!(
+            double_value - single_value > DOUBLE_THREAT_VALUE,
+            "{double_value} against {single_value}"
+        );
+        // The single threat can still be blocked, it is only worth the open three.
+        assert!(single_value < OPEN_THREE_VALUE * 2.0, "{single_value}");
+    }
+
+    /// Gets the board scoring of the stones, that are still live against the stones of the opponent.
+    fn get_live_scoring(stones: u64, opponent: u64) -> f32 {
+        let live = StandardGeometry::get_live_squares(opponent);
+        get_board_scoring::<StandardGeometry>(stones & live, &BOARD_POSITION_CODING_VALUE)
+    }
+
+    #[test]
+    fn test_dead_stones_do_not_score() {
+        let bit = StandardGeometry::get_bit_representation;
+        // Every line through d6 contains one of the stones around it.
+        let top_center = bit(3, 5);
+        let around = bit(2, 5) | bit(4, 5) | bit(2, 4) | bit(3, 4) | bit(4, 4);
+        assert!(
+            get_board_scoring::<StandardGeometry>(top_center, &BOARD_POSITION_CODING_VALUE) > 0.0
+        );
+        assert_eq!(get_live_scoring(top_center, around), 0.0);
+        // A single gap keeps the horizontal line to the right open.
+        let open_right = around & !bit(4, 5);
+        assert!(get_live_scoring(top_center, open_right) > 0.0);
+
+        // The stones at the bottom of the left column are blocked upwards and to the right. They do not
+        // score, the open center is not touched by them.
+        let left_column = bit(0, 0) | bit(0, 1);
+        let blocking = bit(0, 2) | bit(1, 0) | bit(1, 1) | bit(1, 2);
+        let center = bit(3, 0) | bit(3, 1);
+        assert!(
+            get_board_scoring::<StandardGeometry>(left_column, &BOARD_POSITION_CODING_VALUE) > 0.0
+        );
+        assert_eq!(get_live_scoring(left_column, blocking), 0.0);
+        assert_eq!(
+            get_live_scoring(left_column | center, blocking),
+            get_board_scoring::<StandardGeometry>(center, &BOARD_POSITION_CODING_VALUE)
+        );
+    }
+
+    #[test]
+    fn test_tempo_before_and_after() {
+        let without_tempo = HeuristicWeights {
+            tempo: 0.0,
+            ..HeuristicWeights::default()
+        };
+        // Blue is to move and wins with its playable threat, Blue can not block the two threats of Yellow
+        // and after the block nothing can be won at once. The clamp guard still caps the value.
+        let cases = [
+            (OPEN_ROW_TO_MOVE, -TEMPO_VALUE),
+            (DOUBLE_THREAT, TEMPO_VALUE),
+            (THREAT_BLOCKED, 0.0),
+        ];
+        for (grid, tempo) in cases {
+            let board = get_board(grid);
+            let before = compute_heuristics(&board, &without_tempo, CLAMP_GUARD_HEURISTIC);
+            let after = get_value(&board);
+            let expected = (before + tempo).clamp(-CLAMP_GUARD_HEURISTIC, CLAMP_GUARD_HEURISTIC);
+            assert!(
+                (after - expected).abs() < 1e-5,
+                "{grid}\n{before} to {after}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_tempo_stays_inside_the_clamp_guard() {
+        let weights = HeuristicWeights {
+            tempo: 100.0,
+            ..HeuristicWeights::default()
+        };
+        for (grid, sign) in [(OPEN_ROW_TO_MOVE, -1.0), (DOUBLE_THREAT, 1.0)] {
+            let value = compute_heuristics(&get_board(grid), &weights, CLAMP_GUARD_HEURISTIC);
+            assert_eq!(value, sign * CLAMP_GUARD_HEURISTIC, "{grid}");
+        }
+    }
+
+    /// Tries random changes of the weights and keeps a change, if it wins a short self play match against
+    /// the weights so far. Returns the weights found after the amount of rounds.
+    fn tune_weights(
+        start: HeuristicWeights,
+        rounds: u32,
+        games: u32,
+        seed: u64,
+    ) -> HeuristicWeights {
+        let mut random = SplitMix64::new(seed);
+        let mut best = start;
+        for round in 0..rounds {
+            // Every weight gets scaled by a random factor between 0.5 and 1.5.
+            let mut scale = || 0.5 + random.next_f64() as f32;
+            let candidate = HeuristicWeights {
+                open_three: best.open_three * scale(),
+                double_threat: best.double_threat * scale(),
+                tempo: best.tempo * scale(),
+                position_values: best.position_values.map(|value| value * scale()),
+            };
+            let config = |weights| SearchConfig {
+                max_depth: 4,
+                move_time: None,
+                weights,
+                ..SearchConfig::default()
+            };
+            let result = play_match(&MatchSettings {
+                games,
+                config_a: config(candidate),
+                config_b: config(best),
+                seed: seed ^ u64::from(round),
+                opening_moves: 4,
+            });
+            if result.engine_a.wins > result.engine_a.losses {
+                best = candidate;
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn test_tuner_keeps_only_winning_weights() {
+        // Without position values and threats the candidates are scaled zeros, so nothing can win.
+        let zero = HeuristicWeights {
+            open_three: 0.0,
+            double_threat: 0.0,
+            tempo: 0.0,
+            position_values: [0.0; 12],
+        };
+        assert_eq!(tune_weights(zero, 2, 2, 5), zero);
+        // A tuned weight stays within the range of the random factors.
+        let tuned = tune_weights(HeuristicWeights::default(), 2, 2, 5);
+        let factor = tuned.open_three / OPEN_THREE_VALUE;
+        assert!((0.25..=2.25).contains(&factor), "{tuned:?}");
+    }
+
+    /// Tunes the weights with longer matches, which takes a while. Run it with
+    /// `cargo test --release -- --ignored --nocapture` and copy the weights printed.
+    #[test]
+    #[ignore]
+    fn test_tune_weights() {
+        let tuned = tune_weights(HeuristicWeights::default(), 20, 20, 1);
+        println!("{tuned:#?}");
+    }
+}
@@ -7,4 +7,9 @@ pub mod ai_handler;
 pub mod alpha_beta;
 pub mod bit_board;
 pub mod bit_board_coding;
-mod heuristic;
+pub mod blunder_check;
+pub mod heuristic;
+pub mod notation;
+pub mod perft;
+pub mod player;
+pub mod time_manager;
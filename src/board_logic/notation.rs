@@ -0,0 +1,289 @@
+//! The names of the squares, as they are shown to the player. The columns are letters from a on the
+//! left, the rows are numbers from 1 at the bottom, like on a chess board. So the first stone in the
+//! central column of the standard board lands on d1. The names agree with the coordinates of the bit
+//! board, where x runs from left to right and y from the bottom up. A game is written as the squares
+//! the stones have landed on, like "d1 d2 c1", a pop under the Pop Out rules as the bottom square with a
+//! leading x, like "xd1".
+
+use crate::board_logic::bit_board_coding::{BOARD_HEIGHT, BOARD_WIDTH, Direction, WinningLine};
+
+/// The words for the small numbers, the larger ones are written as digits.
+const NUMBER_WORDS: [&str; 8] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven",
+];
+
+/// Gets the letter of the column counted from 0.
+pub fn get_column_name(column: u32) -> char {
+    char::from(b'a' + column as u8)
+}
+
+/// Gets the name of the square in the column and the row counted from 0, like d1.
+pub fn get_square_name(x: u32, y: u32) -> String {
+    format!("{}{}", get_column_name(x), y + 1)
+}
+
+/// Reads the square from its name, like d3, as column and row counted from 0. None if it is no square of
+/// the standard board.
+pub fn parse_square(name: &str) -> Option<(u32, u32)> {
+    let mut characters = name.chars();
+    let column = characters.next()?;
+    if !column.is_ascii_lowercase() {
+        return None;
+    }
+    let x = column as u32 - 'a' as u32;
+    // Only plain digits without a leading zero, as the parse of the number would also take a sign.
+    let row = characters.as_str();
+    if row.starts_with('0') || row.is_empty() || !row.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let y = row.parse::<u32>().ok()?.checked_sub(1)?;
+    (x < BOARD_WIDTH && y < BOARD_HEIGHT).then_some((x, y))
+}
+
+/// Writes the moves as the squares, the stones land on, separated by spaces. The moves are the slots of
+/// the standard board, the columns for the drops followed by the columns for the pops. The rows are found
+/// by replaying the moves, a pop lets the stones of its column fall down one row. None if a move does not
+/// fit, as its column is full or empty or the slot does not exist.
+pub fn move_list_to_algebraic(moves: &[u32]) -> Option<String> {
+    let mut heights = [0; BOARD_WIDTH as usize];
+    let mut squares = Vec::with_capacity(moves.len());
+    for &slot in moves {
+        if slot < BOARD_WIDTH {
+            let height = &mut heights[slot as usize];
+            if *height == BOARD_HEIGHT {
+                return None;
+            }
+            squares.push(get_square_name(slot, *height));
+            *height += 1;
+        } else {
+            let column = slot - BOARD_WIDTH;
+            let height = heights.get_mut(column as usize)?;
+            if *height == 0 {
+                return None;
+            }
+            squares.push(format!("x{}", get_square_name(column, 0)));
+            *height -= 1;
+        }
+    }
+    Some(squares.join(" "))
+}
+
+/// Gets the word for the number, digits if there is none.
+fn get_number_word(number: usize) -> String {
+    NUMBER_WORDS
+        .get(number)
+        .map_or_else(|| number.to_string(), |word| word.to_string())
+}
+
+/// Gets the name of the direction, as it reads from left to right.
+fn get_direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Horizontal => "horizontal line",
+        Direction::Vertical => "vertical line",
+        Direction::RisingDiagonal => "rising diagonal",
+        Direction::FallingDiagonal => "falling diagonal",
+    }
+}
+
+/// Describes a line in the direction through the cells, from its left end to its right one, a vertical
+/// line from the bottom up. The length is only mentioned, if it is longer than the winning length.
+fn describe_line(direction: Direction, cells: &[(u32, u32)], win_length: usize) -> String {
+    let (Some(from), Some(to)) = (cells.iter().min(), cells.iter().max()) else {
+        return get_direction_name(direction).to_string();
+    };
+    let length = if cells.len() > win_length {
+        format!(" of {}", get_number_word(cells.len()))
+    } else {
+        String::new()
+    };
+    format!(
+        "a {}{length} from {} to {}",
+        get_direction_name(direction),
+        get_square_name(from.0, from.1),
+        get_square_name(to.0, to.1)
+    )
+}
+
+/// Describes, how the game has been won with the lines, like "a rising diagonal from c2 to f5". Several
+/// lines, that complete at once, are described by the square they share, which is the one played last,
+/// like "two connected fours through d4". Lines without a common square can only come from a pop under
+/// the Pop Out rules. None if there is no line.
+pub fn describe_win(lines: &[WinningLine]) -> Option<String> {
+    let (first_line, other_lines) = lines.split_first()?;
+    let win_length = first_line.cells.len();
+    if other_lines.is_empty() {
+        return Some(describe_line(
+            first_line.direction,
+            &first_line.cells,
+            win_length,
+        ));
+    }
+    let common_square = first_line
+        .cells
+        .iter()
+        .find(|cell| other_lines.iter().all(|line| line.cells.contains(cell)));
+    // A row longer than the winning length contains several lines in the same direction.
+    let is_one_row = other_lines
+        .iter()
+        .all(|line| line.direction == first_line.direction);
+    if common_square.is_some() && is_one_row {
+        let mut cells: Vec<(u32, u32)> = lines.iter().flat_map(|line| line.cells.clone()).collect();
+        cells.sort_unstable();
+        cells.dedup();
+        return Some(describe_line(first_line.direction, &cells, win_length));
+    }
+    let amount = get_number_word(lines.len());
+    let kind = match win_length {
+        4 => "fours".to_string(),
+        5 => "fives".to_string(),
+        length => format!("lines of {length}"),
+    };
+    Some(match common_square {
+        Some(&(x, y)) => format!(
+            "{amount} connected {kind} through {}",
+            get_square_name(x, y)
+        ),
+        None => format!("{amount} {kind} at once"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::bit_board::BitBoard;
+
+    /// Gets the winning lines on the board after the moves.
+    fn get_lines(moves: &str) -> Vec<WinningLine> {
+        let board = BitBoard::from_move_string(moves).unwrap();
+        board.get_winning_status_for_rendering().1.unwrap()
+    }
+
+    /// Gets a line in the direction through the cells.
+    fn get_line(direction: Direction, cells: &[(u32, u32)]) -> WinningLine {
+        WinningLine {
+            cells: cells.to_vec(),
+            direction,
+        }
+    }
+
+    #[test]
+    fn test_win_in_every_direction_gets_described() {
+        let cases = [
+            ("1122334", "a horizontal line from a1 to d1"),
+            ("1212121", "a vertical line from a1 to a4"),
+            ("12233434544", "a rising diagonal from a1 to d4"),
+            ("76655454344", "a falling diagonal from d4 to g1"),
+        ];
+        for (moves, description) in cases {
+            assert_eq!(
+                describe_win(&get_lines(moves)).as_deref(),
+                Some(description),
+                "{moves}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_lines_at_the_edges_get_described() {
+        let top_right = get_line(Direction::Vertical, &[(6, 2), (6, 3), (6, 4), (6, 5)]);
+        assert_eq!(
+            describe_win(&[top_right]).as_deref(),
+            Some("a vertical line from g3 to g6")
+        );
+        let top_row = get_line(Direction::Horizontal, &[(3, 5), (4, 5), (5, 5), (6, 5)]);
+        assert_eq!(
+            describe_win(&[top_row]).as_deref(),
+            Some("a horizontal line from d6 to g6")
+        );
+        let falling = get_line(
+            Direction::FallingDiagonal,
+            &[(0, 5), (1, 4), (2, 3), (3, 2)],
+        );
+        assert_eq!(
+            describe_win(&[falling]).as_deref(),
+            Some("a falling diagonal from a6 to d3")
+        );
+    }
+
+    #[test]
+    fn test_several_lines_get_described() {
+        // The stone in the middle of the bottom row completes a row of five.
+        assert_eq!(
+            describe_win(&get_lines("112244553")).as_deref(),
+            Some("a horizontal line of five from a1 to e1")
+        );
+        let horizontal = get_line(Direction::Horizontal, &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        let vertical = get_line(Direction::Vertical, &[(3, 0), (3, 1), (3, 2), (3, 3)]);
+        assert_eq!(
+            describe_win(&[horizontal.clone(), vertical]).as_deref(),
+            Some("two connected fours through d1")
+        );
+        let apart = get_line(Direction::Vertical, &[(6, 0), (6, 1), (6, 2), (6, 3)]);
+        assert_eq!(
+            describe_win(&[horizontal, apart]).as_deref(),
+            Some("two fours at once")
+        );
+        assert_eq!(describe_win(&[]), None);
+    }
+
+    #[test]
+    fn test_every_square_survives_the_round_trip() {
+        let mut names = Vec::new();
+        for x in 0..BOARD_WIDTH {
+            for y in 0..BOARD_HEIGHT {
+                let name = get_square_name(x, y);
+                assert_eq!(parse_square(&name), Some((x, y)), "{name}");
+                names.push(name);
+            }
+        }
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), 42);
+        assert_eq!(get_square_name(0, 0), "a1");
+        assert_eq!(get_square_name(3, 2), "d3");
+        assert_eq!(get_square_name(6, 5), "g6");
+        assert_eq!(get_column_name(6), 'g');
+    }
+
+    #[test]
+    fn test_invalid_squares_are_refused() {
+        for name in [
+            "", "d", "3", "h1", "a0", "a7", "D3", "d03", "d+3", "d3 ", "d3x", "ä1",
+        ] {
+            assert_eq!(parse_square(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_rows_are_found_by_replaying() {
+        assert_eq!(
+            move_list_to_algebraic(&[3, 3, 2, 3, 6]).as_deref(),
+            Some("d1 d2 c1 d3 g1")
+        );
+        assert_eq!(move_list_to_algebraic(&[]).as_deref(), Some(""));
+        // The pop lets the stone above fall down, the next stone lands on the second row again.
+        assert_eq!(
+            move_list_to_algebraic(&[3, 3, 10, 3]).as_deref(),
+            Some("d1 d2 xd1 d2")
+        );
+        // The column is full, the column to pop is empty or the slot does not exist.
+        assert_eq!(move_list_to_algebraic(&[0; 7]), None);
+        assert_eq!(move_list_to_algebraic(&[7]), None);
+        assert_eq!(move_list_to_algebraic(&[14]), None);
+    }
+
+    #[test]
+    fn test_rows_agree_with_the_board() {
+        let moves = [3, 3, 2, 4, 2, 2, 5, 1, 6, 6];
+        let mut board = BitBoard::new();
+        for (&slot, square) in moves
+            .iter()
+            .zip(move_list_to_algebraic(&moves).unwrap().split(' '))
+        {
+            let (x, y) = parse_square(square).unwrap();
+            assert_eq!(x, slot);
+            assert_eq!(board.get_move_destination(slot), Some(y), "{square}");
+            board.play_slot(slot).unwrap();
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Move path counting as it is known from chess engines under the name perft. It walks the game tree
+//! with the same primitives as the search does, so any encoding bug in the move generation or the win
+//! detection shows up as a count that differs from an independent computation.
+//!
+//! The counts on the empty standard board are 1, 7, 49, 343, 2401, 16807, 117649, 823536 and 5673234
+//! for the depths 0 to 8. From depth 7 on full columns and from depth 8 on finished games reduce the
+//! count below the powers of seven.
+
+use crate::board_logic::bit_board::GenericBitBoard;
+use crate::board_logic::bit_board_coding::BoardGeometry;
+
+/// Counts the move sequences of the indicated length starting from the board. A sequence ends as soon as
+/// a move wins the game, so it only counts if that was the last move of the sequence. Full boards have no
+/// moves left.
+pub fn perft<G: BoardGeometry>(board: &GenericBitBoard<G>, depth: u32) -> u64 {
+    let mut board = board.clone();
+    board.clear_move_history();
+    count_paths(&mut board, depth)
+}
+
+/// Does the recursive counting with the own stones being the ones of the player to move.
+fn count_paths<G: BoardGeometry>(board: &mut GenericBitBoard<G>, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut paths = 0;
+    for (coded_move, _) in G::get_all_possible_moves(board.own_stones | board.opponent_stones) {
+        // Apply move.
+        board.own_stones |= coded_move;
+        if G::check_for_winning(board.own_stones) {
+            // The game ends here.
+            if depth == 1 {
+                paths += 1;
+            }
+        } else {
+            board.swap_players();
+            paths += count_paths(board, depth - 1);
+            board.swap_players();
+        }
+        // Retake move.
+        board.own_stones ^= coded_move;
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::bit_board::BitBoard;
+
+    #[test]
+    fn test_perft_on_empty_board() {
+        let counts = [1, 7, 49, 343, 2401, 16807, 117649, 823536];
+        for (depth, count) in counts.into_iter().enumerate() {
+            assert_eq!(
+                perft(&BitBoard::new(), depth as u32),
+                count,
+                "depth {depth}"
+            );
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! The players are the sources of the moves in a game. The game asks the player, who is to move, for a
+//! move and polls it every frame, until the move has been chosen. This way it does not matter, whether
+//! the move comes from the mouse, from the ai in its worker thread or from somewhere else later on.
+
+use crate::board_logic::ai_handler::{AiError, AiHandler};
+use crate::board_logic::alpha_beta::{AlphaBeta, EngineError, SearchConfig};
+use crate::board_logic::bit_board::BitBoard;
+
+/// The depth of the synchronous search, that picks the move of the engine, when the worker fails.
+const FALLBACK_SEARCH_DEPTH: u32 = 4;
+
+/// Gets the board from the perspective of the side to move, as the search needs it.
+fn get_search_board(board: &BitBoard) -> BitBoard {
+    let mut board = board.clone();
+    if !board.side_to_move().is_computer() {
+        board.swap_players();
+    }
+    board
+}
+
+/// A side in the game, that chooses its moves on its own.
+pub trait Player {
+    /// Asks the player for a move on the board, where it is to move. A move chosen for an earlier
+    /// request, that has not been polled, is dropped.
+    fn request_move(&mut self, board: &BitBoard);
+
+    /// Gets the slot the player has chosen, none as long as it is still choosing. The slots behind the
+    /// columns pop the bottom stone out of the column under the Pop Out rules.
+    fn poll_move(&mut self) -> Option<u32>;
+
+    /// Gets the name of the player, that is shown to the user.
+    fn name(&self) -> &str;
+
+    /// Checks, if the moves are chosen by a person in front of the screen, who needs the input and the
+    /// help of the user interface.
+    fn is_interactive(&self) -> bool;
+}
+
+/// The person in front of the screen. The game state feeds the slots clicked on the board in, it only
+/// hands them on, the checks if the move is possible are up to the state.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct HumanMousePlayer {
+    /// The slot clicked last, that has not been polled yet.
+    chosen_slot: Option<u32>,
+}
+
+impl HumanMousePlayer {
+    pub fn new() -> HumanMousePlayer {
+        HumanMousePlayer::default()
+    }
+
+    /// Hands the slot over, that has been clicked. A later click replaces an earlier one, that has not
+    /// been polled.
+    pub fn feed_slot(&mut self, slot: u32) {
+        self.chosen_slot = Some(slot);
+    }
+}
+
+impl Player for HumanMousePlayer {
+    fn request_move(&mut self, _: &BitBoard) {
+        self.chosen_slot = None;
+    }
+
+    fn poll_move(&mut self) -> Option<u32> {
+        self.chosen_slot.take()
+    }
+
+    fn name(&self) -> &str {
+        "You"
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+/// The ai, that searches its moves in the worker thread of the [`AiHandler`]. The handler is also used
+/// for the hints and the analysis, so it can be accessed directly. When the worker fails, the error is
+/// kept, so that the game can ask again or fall back on [`EnginePlayer::get_fallback_move`].
+pub struct EnginePlayer {
+    handler: AiHandler,
+    /// A bit per column, that may not be chosen for the next move requested.
+    banned_columns: u32,
+    /// The error of the last request or poll, that has not been picked up yet.
+    error: Option<AiError>,
+}
+
+impl Default for EnginePlayer {
+    fn default() -> EnginePlayer {
+        EnginePlayer::new()
+    }
+}
+
+impl EnginePlayer {
+    /// Creates the player with its own worker thread.
+    pub fn new() -> EnginePlayer {
+        EnginePlayer {
+            handler: AiHandler::new(),
+            banned_columns: 0,
+            error: None,
+        }
+    }
+
+    /// Gets the handler of the worker thread.
+    pub fn get_ai_handler(&self) -> &AiHandler {
+        &self.handler
+    }
+
+    /// Gets the handler of the worker thread to send requests and to collect the results.
+    pub fn get_ai_handler_mut(&mut self) -> &mut AiHandler {
+        &mut self.handler
+    }
+
+    /// Bans the columns, whose bits are set, for the next move requested. This is used for handicaps.
+    pub fn set_banned_columns(&mut self, banned_columns: u32) {
+        self.banned_columns = banned_columns;
+    }
+
+    /// Takes the error, that made the move get lost, none if the move is still being searched.
+    pub fn take_error(&mut self) -> Option<AiError> {
+        self.error.take()
+    }
+
+    /// Searches the move on the board with a shallow search right away, without the worker. This keeps
+    /// the game going, when the worker fails to deliver the move. The banned columns are respected. A
+    /// game, that is already over, has no move and returns an error.
+    pub fn get_fallback_move(&self, board: &BitBoard) -> Result<u32, EngineError> {
+        let mut search = AlphaBeta::with_config(SearchConfig {
+            max_depth: FALLBACK_SEARCH_DEPTH,
+            move_time: None,
+            ..SearchConfig::default()
+        });
+        search.set_banned_root_columns(self.banned_columns);
+        search.get_best_move(get_search_board(board))
+    }
+}
+
+impl Player for EnginePlayer {
+    /// Kicks off the search in the worker thread. The search needs the side to move as the computer, so
+    /// the perspective gets swapped, if the engine plays for the other side.
+    fn request_move(&mut self, board: &BitBoard) {
+        self.error = self
+            .handler
+            .send_restricted_analysis_request(get_search_board(board), self.banned_columns)
+            .err();
+    }
+
+    /// Gets the move, once it has arrived. If it got lost, the error is kept for
+    /// [`EnginePlayer::take_error`].
+    fn poll_move(&mut self) -> Option<u32> {
+        self.handler
+            .try_get_computation_result()
+            .unwrap_or_else(|error| {
+                self.error = Some(error);
+                None
+            })
+    }
+
+    fn name(&self) -> &str {
+        "Computer"
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board_logic::bit_board::{GameResult, Side};
+    use std::collections::VecDeque;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// A player, that plays the moves of its script. It takes the indicated number of polls for each
+    /// move, as if it had to think about it.
+    struct ScriptedPlayer {
+        moves: VecDeque<u32>,
+        polls_per_move: u32,
+        polls_left: u32,
+        requests: u32,
+    }
+
+    impl ScriptedPlayer {
+        fn new(moves: &[u32], polls_per_move: u32) -> ScriptedPlayer {
+            ScriptedPlayer {
+                moves: moves.iter().copied().collect(),
+                polls_per_move,
+                polls_left: 0,
+                requests: 0,
+            }
+        }
+    }
+
+    impl Player for ScriptedPlayer {
+        fn request_move(&mut self, _: &BitBoard) {
+            self.requests += 1;
+            self.polls_left = self.polls_per_move;
+        }
+
+        fn poll_move(&mut self) -> Option<u32> {
+            if self.polls_left > 0 {
+                self.polls_left -= 1;
+                return None;
+            }
+            self.moves.pop_front()
+        }
+
+        fn name(&self) -> &str {
+            "Script"
+        }
+
+        fn is_interactive(&self) -> bool {
+            false
+        }
+    }
+
+    /// Plays the game between the two players like the states do: the side to move gets asked and
+    /// polled, until it has chosen. Returns the board at the end.
+    fn play_game(player: &mut dyn Player, computer: &mut dyn Player) -> BitBoard {
+        let mut board = BitBoard::new();
+        while !board.is_game_over() {
+            let side_player: &mut dyn Player = match board.side_to_move() {
+                Side::Player => &mut *player,
+                Side::Computer => &mut *computer,
+            };
+            side_player.request_move(&board);
+            let slot = (0..100)
+                .find_map(|_| side_player.poll_move())
+                .expect("The player has chosen a move.");
+            board.play_slot(slot).unwrap();
+        }
+        board
+    }
+
+    /// Gets an engine, that searches only a few plies, so that its moves arrive quickly.
+    fn get_quick_engine() -> EnginePlayer {
+        let mut engine = EnginePlayer::new();
+        engine
+            .get_ai_handler_mut()
+            .configure(SearchConfig {
+                max_depth: 4,
+                move_time: None,
+                ..SearchConfig::default()
+            })
+            .unwrap();
+        engine
+    }
+
+    /// Polls the engine, until its move arrives.
+    fn wait_for_move(engine: &mut EnginePlayer) -> u32 {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while Instant::now() < deadline {
+            if let Some(slot) = engine.poll_move() {
+                return slot;
+            }
+            assert_eq!(engine.take_error(), None);
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("The move of the engine did not arrive.");
+    }
+
+    #[test]
+    fn test_scripted_players_play_a_game() {
+        let mut player = ScriptedPlayer::new(&[0, 1, 2, 3], 3);
+        let mut computer = ScriptedPlayer::new(&[6, 6, 6], 0);
+        let board = play_game(&mut player, &mut computer);
+        assert_eq!(board.to_move_string().as_deref(), Some("1727374"));
+        assert_eq!(
+            board.get_winning_status_for_rendering().0,
+            GameResult::FirstPlayerWon
+        );
+        assert_eq!((player.requests, computer.requests), (4, 3));
+        assert!(player.moves.is_empty() && computer.moves.is_empty());
+    }
+
+    #[test]
+    fn test_human_hands_on_the_clicks() {
+        let mut human = HumanMousePlayer::new();
+        assert!(human.is_interactive());
+        assert_eq!(human.name(), "You");
+        human.request_move(&BitBoard::new());
+        assert_eq!(human.poll_move(), None);
+        // The last click counts and it is handed out once.
+        human.feed_slot(2);
+        human.feed_slot(4);
+        assert_eq!(human.poll_move(), Some(4));
+        assert_eq!(human.poll_move(), None);
+        // A click, that has not been polled, is dropped by the next request.
+        human.feed_slot(5);
+        human.request_move(&BitBoard::new());
+        assert_eq!(human.poll_move(), None);
+    }
+
+    #[test]
+    fn test_engine_takes_the_win_for_both_sides() {
+        let mut engine = get_quick_engine();
+        assert!(!engine.is_interactive());
+        // The side to move completes its line, first the one, that started, then the other one.
+        for (moves, winning_column) in [("172737", 3), ("1727375", 6)] {
+            let board = BitBoard::from_move_string(moves).unwrap();
+            engine.request_move(&board);
+            assert_eq!(wait_for_move(&mut engine), winning_column, "{moves}");
+        }
+    }
+
+    #[test]
+    fn test_engine_respects_the_banned_columns() {
+        let mut engine = get_quick_engine();
+        let board = BitBoard::new();
+        engine.set_banned_columns(1 << 3);
+        engine.request_move(&board);
+        assert_ne!(wait_for_move(&mut engine), 3);
+        assert_ne!(engine.get_fallback_move(&board), Ok(3));
+    }
+
+    #[test]
+    fn test_lost_move_keeps_its_error() {
+        let mut engine = EnginePlayer::new();
+        engine.get_ai_handler_mut().set_scripted(true);
+        engine.request_move(&BitBoard::new());
+        assert_eq!(engine.poll_move(), None);
+        assert_eq!(engine.take_error(), None);
+        engine.get_ai_handler_mut().feed_failure(AiError::Timeout);
+        assert_eq!(engine.poll_move(), None);
+        assert_eq!(engine.take_error(), Some(AiError::Timeout));
+        assert_eq!(engine.take_error(), None);
+    }
+
+    #[test]
+    fn test_fallback_move() {
+        let engine = EnginePlayer::new();
+        let board = BitBoard::from_move_string("121212").unwrap();
+        assert_eq!(engine.get_fallback_move(&board), Ok(0));
+        let won = BitBoard::from_move_string("1212121").unwrap();
+        assert_eq!(engine.get_fallback_move(&won), Err(EngineError::GameOver));
+    }
+}
@@ -0,0 +1,293 @@
+//! The time manager decides how long the iterative deepening in the alpha-beta search may run.
+//! Every move gets a base time. This time gets extended when the position looks unstable, meaning
+//! the best move changed between the last two iterations or the score dropped sharply. When the
+//! same move has been the best for several iterations in a row, the search is cut short.
+
+#[cfg(target_arch = "wasm32")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// The factor the allotted time gets multiplied with on an unstable iteration.
+const EXTENSION_FACTOR: f32 = 1.5;
+
+/// The allotted time may never grow beyond this multiple of the base time.
+const MAX_EXTENSION: f32 = 3.0;
+
+/// A score drop between two iterations above this value counts as unstable.
+const SCORE_DROP_THRESHOLD: f32 = 0.1;
+
+/// After this amount of iterations with the same best move we stop searching.
+const STABLE_ITERATIONS: u32 = 4;
+
+/// Shallow iterations are always stable, so an early stop is only allowed after this fraction of the
+/// base time has been spent.
+const EARLY_STOP_FRACTION: f32 = 0.2;
+
+/// A new iteration usually takes longer than all the previous ones together. So we only start one,
+/// if we have not used up this fraction of the allotted time yet.
+const NEXT_ITERATION_FRACTION: f32 = 0.4;
+
+/// Source of time for the time manager. It is a trait, so that it can be replaced with a fake clock
+/// for deterministic behaviour.
+pub trait Clock: Send {
+    /// Returns the seconds passed since an arbitrary but fixed point in time.
+    fn now(&self) -> f32;
+}
+
+/// The seconds, that have passed on the web, as the bits of an f64. The standard library has no clock
+/// there, so the times of the frames get added up instead.
+#[cfg(target_arch = "wasm32")]
+static WEB_TIME: AtomicU64 = AtomicU64::new(0);
+
+/// Gets the seconds, that have passed on the web.
+#[cfg(target_arch = "wasm32")]
+fn get_web_time() -> f64 {
+    f64::from_bits(WEB_TIME.load(Ordering::Relaxed))
+}
+
+/// Adds the time of a frame in seconds to the clock on the web, where the [`SystemClock`] can not read
+/// the system time. Elsewhere this does nothing.
+pub fn advance_web_clock(seconds: f32) {
+    #[cfg(target_arch = "wasm32")]
+    WEB_TIME.store(
+        (get_web_time() + seconds.max(0.0) as f64).to_bits(),
+        Ordering::Relaxed,
+    );
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = seconds;
+}
+
+/// The clock based on the system time, that is used in the game. On the web it runs on the times of the
+/// frames, see [`advance_web_clock`], so it stands still during a frame.
+pub struct SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: Instant,
+    #[cfg(target_arch = "wasm32")]
+    start: f64,
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: Instant::now(),
+            #[cfg(target_arch = "wasm32")]
+            start: get_web_time(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn now(&self) -> f32 {
+        self.start.elapsed().as_secs_f32()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn now(&self) -> f32 {
+        (get_web_time() - self.start) as f32
+    }
+}
+
+/// Keeps track of the time spent on the current move and of the stability of the iterations.
+pub struct TimeManager {
+    /// The clock we measure the time with.
+    clock: Box<dyn Clock>,
+    /// The time every move gets in seconds.
+    base_time: f32,
+    /// The time the current move may take in seconds.
+    allotted_time: f32,
+    /// The point in time, when the current move started.
+    start_time: f32,
+    /// The best move of the last finished iteration.
+    last_best_move: Option<u32>,
+    /// The score of the last finished iteration.
+    last_score: f32,
+    /// The number of iterations in a row, that returned the same best move.
+    stable_iterations: u32,
+}
+
+impl TimeManager {
+    /// Creates a time manager with the clock to use and the base time per move in seconds.
+    pub fn new(clock: Box<dyn Clock>, base_time: f32) -> TimeManager {
+        TimeManager {
+            clock,
+            base_time,
+            allotted_time: base_time,
+            start_time: 0.0,
+            last_best_move: None,
+            last_score: 0.0,
+            stable_iterations: 0,
+        }
+    }
+
+    /// Changes the base time per move in seconds, it is used from the next move on.
+    pub fn set_base_time(&mut self, base_time: f32) {
+        self.base_time = base_time;
+    }
+
+    /// Starts the time measurement for a new move.
+    pub fn start_move(&mut self) {
+        self.start_time = self.clock.now();
+        self.allotted_time = self.base_time;
+        self.last_best_move = None;
+        self.last_score = 0.0;
+        self.stable_iterations = 0;
+    }
+
+    /// Gets the time in seconds, that has been spent on the current move.
+    pub fn get_elapsed_time(&self) -> f32 {
+        self.clock.now() - self.start_time
+    }
+
+    /// Informs the time manager about the result of a finished iteration. Unstable iterations extend
+    /// the allotted time, stable ones are counted to stop early.
+    pub fn register_iteration(&mut self, best_move: u32, score: f32) {
+        if let Some(last_move) = self.last_best_move {
+            let move_changed = last_move != best_move;
+            let score_dropped = self.last_score - score > SCORE_DROP_THRESHOLD;
+            if move_changed || score_dropped {
+                self.allotted_time =
+                    (self.allotted_time * EXTENSION_FACTOR).min(self.base_time * MAX_EXTENSION);
+            }
+            if move_changed {
+                self.stable_iterations = 0;
+            } else {
+                self.stable_iterations += 1;
+            }
+        }
+
+        self.last_best_move = Some(best_move);
+        self.last_score = score;
+    }
+
+    /// Checks if it is worth to start another iteration.
+    pub fn should_start_next_iteration(&self) -> bool {
+        let elapsed = self.get_elapsed_time();
+        let is_stable = self.stable_iterations >= STABLE_ITERATIONS
+            && elapsed >= self.base_time * EARLY_STOP_FRACTION;
+        !is_stable && elapsed < self.allotted_time * NEXT_ITERATION_FRACTION
+    }
+
+    /// Checks if the running iteration has to be aborted.
+    pub fn is_out_of_time(&self) -> bool {
+        self.get_elapsed_time() >= self.allotted_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// A clock, that only moves, when the test sets the time. The time is shared with the test as the
+    /// bits of an f32.
+    struct FakeClock(Arc<AtomicU32>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> f32 {
+            f32::from_bits(self.0.load(Ordering::Relaxed))
+        }
+    }
+
+    /// Gets a time manager with the base time running on a fake clock together with the function
+    /// setting the time of the clock.
+    fn get_time_manager(base_time: f32) -> (TimeManager, impl Fn(f32)) {
+        let time = Arc::new(AtomicU32::new(0.0f32.to_bits()));
+        let clock_time = Arc::clone(&time);
+        let set_time = move |seconds: f32| time.store(seconds.to_bits(), Ordering::Relaxed);
+        (
+            TimeManager::new(Box::new(FakeClock(clock_time)), base_time),
+            set_time,
+        )
+    }
+
+    #[test]
+    fn test_changed_move_extends_time() {
+        let (mut time_manager, set_time) = get_time_manager(1.0);
+        time_manager.start_move();
+        time_manager.register_iteration(3, 0.1);
+        set_time(1.2);
+        assert!(time_manager.is_out_of_time());
+
+        time_manager.start_move();
+        set_time(1.2);
+        time_manager.register_iteration(3, 0.1);
+        time_manager.register_iteration(4, 0.1);
+        assert_eq!(time_manager.get_elapsed_time(), 0.0);
+        set_time(2.4);
+        assert!(!time_manager.is_out_of_time());
+        set_time(2.7);
+        assert!(time_manager.is_out_of_time());
+    }
+
+    #[test]
+    fn test_score_drop_extends_time() {
+        let (mut time_manager, set_time) = get_time_manager(1.0);
+        time_manager.start_move();
+        time_manager.register_iteration(3, 0.5);
+        time_manager.register_iteration(3, 0.45);
+        set_time(1.2);
+        assert!(time_manager.is_out_of_time());
+
+        time_manager.start_move();
+        time_manager.register_iteration(3, 0.5);
+        time_manager.register_iteration(3, 0.3);
+        set_time(2.4);
+        assert!(!time_manager.is_out_of_time());
+    }
+
+    #[test]
+    fn test_extension_is_capped() {
+        let (mut time_manager, set_time) = get_time_manager(1.0);
+        time_manager.start_move();
+        for iteration in 0..10 {
+            time_manager.register_iteration(iteration % 2, 0.0);
+        }
+        set_time(MAX_EXTENSION - 0.01);
+        assert!(!time_manager.is_out_of_time());
+        set_time(MAX_EXTENSION);
+        assert!(time_manager.is_out_of_time());
+        // The next move starts with the base time again.
+        time_manager.start_move();
+        set_time(MAX_EXTENSION + 1.0);
+        assert!(time_manager.is_out_of_time());
+    }
+
+    #[test]
+    fn test_stable_move_stops_early() {
+        let (mut time_manager, set_time) = get_time_manager(1.0);
+        time_manager.start_move();
+        time_manager.register_iteration(3, 0.2);
+        for _ in 1..STABLE_ITERATIONS {
+            time_manager.register_iteration(3, 0.2);
+        }
+        set_time(0.25);
+        assert!(time_manager.should_start_next_iteration());
+
+        time_manager.register_iteration(3, 0.2);
+        assert!(!time_manager.should_start_next_iteration());
+        // The shallow iterations right at the start do not stop the search yet.
+        set_time(0.1);
+        assert!(time_manager.should_start_next_iteration());
+    }
+
+    #[test]
+    fn test_no_iteration_after_most_of_the_time() {
+        let (mut time_manager, set_time) = get_time_manager(1.0);
+        time_manager.start_move();
+        time_manager.register_iteration(3, 0.2);
+        set_time(0.39);
+        assert!(time_manager.should_start_next_iteration());
+        set_time(0.41);
+        assert!(!time_manager.should_start_next_iteration());
+    }
+}
@@ -1,15 +1,18 @@
 //! Here we collect a couple of custom debug macros.
 
 /// Verifier macros for coordinates, can be used with x and y coordinates for a position, or a
-/// column only. Checks for the type to be usize and if they do not exceed the desired range,
+/// column only. Checks for the type to be u32 and if they do not exceed the desired range,
 ///
 /// # Example
 /// ```
-/// let x : usize = 2;
-/// let y : usize = 3;
-/// debug_check_board_coordinates!(x, y);     
+/// use connect_4_rust::board_logic::bit_board_coding::{BOARD_HEIGHT, BOARD_WIDTH};
+/// use connect_4_rust::debug_check_board_coordinates;
+///
+/// let x: u32 = 2;
+/// let y: u32 = 3;
+/// debug_check_board_coordinates!(x, y);
 /// debug_check_board_coordinates!(col: x);
-/// ```    
+/// ```
 #[macro_export]
 macro_rules! debug_check_board_coordinates {
     ($x:expr, $y:expr) => {
@@ -0,0 +1,165 @@
+//! The C interface of the engine, so that it can be called from other languages like Python. It is only
+//! compiled with the `cffi` feature and gets built as a shared library with
+//! `cargo rustc --release --lib --features cffi --crate-type cdylib`. The header `include/connect4.h`
+//! is generated from this module with cbindgen.
+//!
+//! The positions are handed over as move strings, the columns 1 to 7 in the order they have been played,
+//! like [`BitBoard::from_move_string`] reads them. The columns handed back are counted from 1 as well.
+//! Every input gets checked and a panic never crosses the boundary, the functions return one of the
+//! negative error codes instead.
+
+use crate::board_logic::alpha_beta::{AlphaBeta, GameOutcome, SearchConfig};
+use crate::board_logic::bit_board::BitBoard;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice, str};
+
+/// A pointer handed over has been null.
+pub const C4_ERROR_NULL_POINTER: i32 = -1;
+/// The move string is no valid UTF-8.
+pub const C4_ERROR_INVALID_UTF8: i32 = -2;
+/// The move string contains a character, that is no column, or a move, that can not be played.
+pub const C4_ERROR_INVALID_MOVES: i32 = -3;
+/// The game of the move string is already over, there is no move to search.
+pub const C4_ERROR_GAME_OVER: i32 = -4;
+/// The engine has failed unexpectedly.
+pub const C4_ERROR_INTERNAL: i32 = -5;
+
+/// The outcome of a solved position for the player to move.
+pub const C4_OUTCOME_LOSS: i32 = -1;
+pub const C4_OUTCOME_DRAW: i32 = 0;
+pub const C4_OUTCOME_WIN: i32 = 1;
+
+/// The engine with its transposition table, which it keeps over the searches, it is opaque for the caller.
+pub struct Engine {
+    search: AlphaBeta,
+}
+
+/// The result of solving a position exactly.
+#[repr(C)]
+pub struct C4SolveResult {
+    /// One of the outcomes for the player to move.
+    pub outcome: i32,
+    /// The amount of plies until the game ends including the move to be made.
+    pub distance: u32,
+    /// The column of the move, that leads to the outcome, counted from 1.
+    pub best_move: u32,
+}
+
+/// Runs the body and turns a panic into [`C4_ERROR_INTERNAL`].
+fn guard(body: impl FnOnce() -> Result<i32, i32>) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(body))
+        .unwrap_or(Err(C4_ERROR_INTERNAL))
+        .unwrap_or_else(|error| error)
+}
+
+/// Reads the board from the move string of the indicated length in bytes. The game may not be over.
+///
+/// # Safety
+/// The pointer has to point to at least `length` readable bytes, unless it is null.
+unsafe fn read_board(moves: *const u8, length: usize) -> Result<BitBoard, i32> {
+    let bytes = if length == 0 {
+        &[]
+    } else if moves.is_null() {
+        return Err(C4_ERROR_NULL_POINTER);
+    } else {
+        // SAFETY: The caller guarantees the length of the buffer.
+        unsafe { slice::from_raw_parts(moves, length) }
+    };
+    let text = str::from_utf8(bytes).map_err(|_| C4_ERROR_INVALID_UTF8)?;
+    let board = BitBoard::from_move_string(text).map_err(|_| C4_ERROR_INVALID_MOVES)?;
+    if board.is_game_over() {
+        return Err(C4_ERROR_GAME_OVER);
+    }
+    Ok(board)
+}
+
+/// Creates the engine, that searches up to the depth. Null is returned for the depth zero. The engine
+/// has to be released with [`c4_engine_free`].
+#[unsafe(no_mangle)]
+pub extern "C" fn c4_engine_new(depth: u32) -> *mut Engine {
+    if depth == 0 {
+        return ptr::null_mut();
+    }
+    panic::catch_unwind(|| {
+        let search = AlphaBeta::with_config(SearchConfig {
+            max_depth: depth,
+            move_time: None,
+            ..SearchConfig::default()
+        });
+        Box::into_raw(Box::new(Engine { search }))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Releases the engine, null is ignored.
+///
+/// # Safety
+/// The engine has to come from [`c4_engine_new`] and may not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn c4_engine_free(engine: *mut Engine) {
+    if !engine.is_null() {
+        // SAFETY: The engine has been created by `c4_engine_new` and is released only once.
+        drop(unsafe { Box::from_raw(engine) });
+    }
+}
+
+/// Searches the best move in the position of the move string with the length in bytes. Returns the
+/// column counted from 1 or a negative error code.
+///
+/// # Safety
+/// The engine has to come from [`c4_engine_new`] and the move string has to have at least `length`
+/// readable bytes, null pointers are reported as errors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn c4_best_move(engine: *mut Engine, moves: *const u8, length: usize) -> i32 {
+    guard(|| {
+        // SAFETY: The caller hands over an engine from `c4_engine_new` or null.
+        let engine = unsafe { engine.as_mut() }.ok_or(C4_ERROR_NULL_POINTER)?;
+        // SAFETY: The caller guarantees the length of the move string.
+        let board = unsafe { read_board(moves, length) }?;
+        let column = engine
+            .search
+            .get_best_move(board)
+            .map_err(|_| C4_ERROR_GAME_OVER)?;
+        Ok(column as i32 + 1)
+    })
+}
+
+/// Solves the position of the move string with the length in bytes exactly and writes the outcome into
+/// the result. Returns zero or a negative error code. This is only feasible for positions, where a good
+/// part of the board is already filled.
+///
+/// # Safety
+/// The engine has to come from [`c4_engine_new`], the move string has to have at least `length`
+/// readable bytes and the result has to be writable, null pointers are reported as errors.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn c4_solve(
+    engine: *mut Engine,
+    moves: *const u8,
+    length: usize,
+    result: *mut C4SolveResult,
+) -> i32 {
+    guard(|| {
+        // SAFETY: The caller hands over an engine from `c4_engine_new` or null.
+        let engine = unsafe { engine.as_mut() }.ok_or(C4_ERROR_NULL_POINTER)?;
+        if result.is_null() {
+            return Err(C4_ERROR_NULL_POINTER);
+        }
+        // SAFETY: The caller guarantees the length of the move string.
+        let board = unsafe { read_board(moves, length) }?;
+        let solved = engine.search.solve(board);
+        let outcome = match solved.outcome {
+            GameOutcome::Win => C4_OUTCOME_WIN,
+            GameOutcome::Draw => C4_OUTCOME_DRAW,
+            GameOutcome::Loss => C4_OUTCOME_LOSS,
+        };
+        // SAFETY: The result has been checked for null, the caller guarantees it is writable.
+        unsafe {
+            result.write(C4SolveResult {
+                outcome,
+                distance: solved.distance,
+                best_move: solved.best_move + 1,
+            })
+        };
+        Ok(0)
+    })
+}
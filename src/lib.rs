@@ -0,0 +1,17 @@
+//! The library part of the Four Connect game. It contains the bit board representation and the
+//! alpha-beta pruned negamax algorithm with transposition tables. It does not depend on any
+//! graphics, so it can be used by the game as well as by the tools like the self play harness.
+//!
+#![doc(html_logo_url = "https://www.rust-lang.org/logos/rust-logo-128x128.png")]
+#![doc(html_favicon_url = "https://www.rust-lang.org/favicon.ico")]
+
+pub mod board_logic;
+mod debug_macros;
+#[cfg(feature = "cffi")]
+pub mod ffi;
+pub mod network;
+pub mod protocol;
+pub mod records;
+pub mod selfplay;
+pub mod solver;
+pub mod test_set;
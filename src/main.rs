@@ -1,55 +1,398 @@
 //! Program is an adaption of the Four Connect game. It features an alpha-beta pruned negamax algorithm
 //! with transposition tables and a thread based asynchronous user interface.
+//! The game logic and the ai live in the library part of the crate, this is the user interface.
 //!
 #![doc(html_logo_url = "https://www.rust-lang.org/logos/rust-logo-128x128.png")]
 #![doc(html_favicon_url = "https://www.rust-lang.org/favicon.ico")]
 
 mod state_system;
 
-mod board_logic;
-mod debug_macros;
 mod render_system;
 
+#[cfg(not(target_arch = "wasm32"))]
 use macroquad::miniquad::window::set_window_size;
 use state_system::*;
 
-use crate::game_state::{Blackboard, GameStateIndex, generate_state_collection};
-use crate::render_system::graphics::{WINDOW_DIMENSION, create_board_texture};
+use crate::game_state::Blackboard;
+use crate::idle_governor::{FrameActivity, get_idle_sleep, is_redraw_needed};
+use crate::render_system::audio::SoundBank;
+use crate::render_system::debug_hud::render_debug_hud;
+use crate::render_system::graphics::{
+    BoardLayout, STANDARD_LAYOUT, WINDOW_DIMENSION, create_board_texture, create_frame_cache,
+    create_layout_camera, draw_frame_cache, get_layout_scale,
+};
+use crate::render_system::svg::{SVG_EXPORT_PATH, board_to_svg, get_winning_cells};
+use crate::render_system::theme::{ThemeKind, get_active_theme, set_active_theme};
+use crate::render_system::toast::TOAST_DURATION;
+use crate::session_record::{
+    InputSource, LiveInput, SESSION_RECORD_PATH, SessionRecorder, SessionReplay,
+};
+use crate::settings::{Difficulty, LOG_PATH, SETTINGS_PATH, Settings};
+use crate::state_driver::{StateDriver, run_headless_replay};
+use connect_4_rust::board_logic::alpha_beta::AlphaBeta;
+use connect_4_rust::board_logic::bit_board::BitBoard;
+use connect_4_rust::board_logic::bit_board_coding::ConnectFiveGeometry;
+use connect_4_rust::network::RemotePlayer;
+use connect_4_rust::selfplay::SplitMix64;
+use env_logger::{Env, Target};
 use macroquad::prelude::*;
+use std::env;
+use std::fs::{self, File};
+use std::process;
+use std::thread;
 
-#[macroquad::main("Connect four")]
-async fn main() {
+/// The options of the command line.
+#[derive(Default)]
+struct Arguments {
+    /// The session gets recorded with `--record`.
+    record: bool,
+    /// The file of the session to replay with `--replay <file>`.
+    replay_path: Option<String>,
+    /// The address to wait for the opponent on with `--host <address>`.
+    host_address: Option<String>,
+    /// The address of the host to play against with `--join <address>`.
+    join_address: Option<String>,
+    /// The file a game played headless gets exported to as SVG with `--export-svg <file>`.
+    svg_path: Option<String>,
+    /// The moves the headless game starts with, given with `--moves <movestring>`.
+    moves: Option<String>,
+    /// The replay runs without a window with `--headless`.
+    headless: bool,
+}
+
+impl Arguments {
+    /// Reads the options from the command line, unknown ones are ignored.
+    fn parse() -> Arguments {
+        let mut arguments = Arguments::default();
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => arguments.record = true,
+                "--replay" => arguments.replay_path = args.next(),
+                "--host" => arguments.host_address = args.next(),
+                "--join" => arguments.join_address = args.next(),
+                "--export-svg" => arguments.svg_path = args.next(),
+                "--moves" => arguments.moves = args.next(),
+                "--headless" => arguments.headless = true,
+                _ => log::warn!("Unknown option {arg}"),
+            }
+        }
+        arguments
+    }
+}
+
+/// The log level used, when it is not set with the `RUST_LOG` variable.
+const DEFAULT_LOG_LEVEL: &str = "info";
+/// The log level of the log file, which is meant for reports of problems, so it is more detailed.
+const DEFAULT_FILE_LOG_LEVEL: &str = "debug";
+
+/// Sets up the logger. The log goes to the terminal or, if switched on in the settings, into the log
+/// file. The level can be chosen with the `RUST_LOG` variable as usual.
+fn init_logging(settings: &Settings) {
+    let default_level = if settings.log_to_file {
+        DEFAULT_FILE_LOG_LEVEL
+    } else {
+        DEFAULT_LOG_LEVEL
+    };
+    let mut builder =
+        env_logger::Builder::from_env(Env::default().default_filter_or(default_level));
+    let mut file_error = None;
+    if settings.log_to_file {
+        match File::create(LOG_PATH) {
+            Ok(file) => {
+                builder.target(Target::Pipe(Box::new(file)));
+            }
+            Err(error) => file_error = Some(error),
+        }
+    }
+    builder.init();
+    if let Some(error) = file_error {
+        log::error!("The log file {LOG_PATH} could not be created: {error}");
+    }
+}
+
+/// The difficulty the engine plays the headless game with on both sides.
+const HEADLESS_DIFFICULTY: Difficulty = Difficulty::Medium;
+
+/// Plays the game from the moves to its end with the engine on both sides, without opening a window,
+/// and exports the final board with the winning stones highlighted as SVG. Returns if the export has
+/// succeeded.
+fn export_headless_game(path: &str, moves: &str) -> bool {
+    let mut board = match BitBoard::from_move_string(moves) {
+        Ok(board) => board,
+        Err(error) => {
+            log::error!("The moves {moves} can not be played: {error}");
+            return false;
+        }
+    };
+    let mut engine = AlphaBeta::with_config(HEADLESS_DIFFICULTY.get_search_config());
+    while !board.is_game_over() {
+        // The engine searches for the computer, so the side to move has to be the computer.
+        let mut search_board = board.clone();
+        if !search_board.side_to_move().is_computer() {
+            search_board.swap_players();
+        }
+        let column = engine
+            .get_best_move(search_board)
+            .expect("The game is not over yet.");
+        board
+            .play(column)
+            .expect("The engine only picks possible moves.");
+    }
+    let svg = board_to_svg(&board, Some(&get_winning_cells(&board)));
+    match fs::write(path, svg) {
+        Ok(()) => {
+            log::info!("The final board has been exported to {path}");
+            true
+        }
+        Err(error) => {
+            log::error!("The board could not be exported to {path}: {error}");
+            false
+        }
+    }
+}
+
+/// Reads the settings and the command line, exports a headless game, if asked to, and runs the game
+/// otherwise. The logger gets set up first, so that it is there in both cases.
+fn main() {
+    let settings = Settings::load(SETTINGS_PATH);
+    init_logging(&settings);
+    let arguments = Arguments::parse();
+    if let Some(path) = &arguments.svg_path {
+        set_active_theme(settings.theme);
+        if !export_headless_game(path, arguments.moves.as_deref().unwrap_or("")) {
+            process::exit(1);
+        }
+        return;
+    }
+    if arguments.headless {
+        let Some(path) = &arguments.replay_path else {
+            log::error!("A headless run needs a session to replay with --replay <file>");
+            process::exit(1);
+        };
+        if !run_headless_replay(path) {
+            process::exit(1);
+        }
+        return;
+    }
+    macroquad::Window::new("Connect four", run_game(settings, arguments));
+}
+
+/// Opens the window and runs the main loop of the game.
+async fn run_game(settings: Settings, arguments: Arguments) {
+    // On the web the page decides on the size of the canvas.
+    #[cfg(not(target_arch = "wasm32"))]
     set_window_size(WINDOW_DIMENSION as u32, WINDOW_DIMENSION as u32);
 
-    let board_texture = create_board_texture();
-    // Origin is in the lower left corner
-    let camera =
-        Camera2D::from_display_rect(Rect::new(0.0, 0.0, WINDOW_DIMENSION, WINDOW_DIMENSION));
-    set_camera(&camera);
+    // The board texture has the board color of this theme.
+    let mut texture_theme = ThemeKind::default();
 
-    let mut state_array = generate_state_collection();
-    let mut current_index: usize = GameStateIndex::StartSelection as usize;
-    let mut black_board: Blackboard = Blackboard::new(board_texture);
+    let mut driver = StateDriver::new();
+    let mut black_board = Blackboard::new();
+    black_board.settings = settings;
+    black_board.apply_settings();
+    black_board.sound_bank = SoundBank::load().await;
+
+    let replay = arguments.replay_path.and_then(|path| {
+        SessionReplay::load(&path)
+            .inspect_err(|error| log::error!("The session {path} can not be replayed: {error}"))
+            .ok()
+    });
+    let mut recorder = None;
+    let mut replaying = replay.is_some();
+    let mut input_source: Box<dyn InputSource> = match replay {
+        Some(replay) => {
+            // The replay runs with the recorded settings and the recorded results of the ai.
+            black_board.settings = replay.get_settings();
+            black_board.apply_settings();
+            black_board.persist_settings = false;
+            black_board.random = SplitMix64::new(replay.get_seed());
+            black_board
+                .computer_player
+                .get_ai_handler_mut()
+                .set_scripted(true);
+            Box::new(replay)
+        }
+        None => {
+            // The time of the start makes every session draw other numbers.
+            let seed = (macroquad::miniquad::date::now() * 1000.0) as u64;
+            black_board.random = SplitMix64::new(seed);
+            black_board
+                .computer_player
+                .get_ai_handler_mut()
+                .set_seed(seed);
+            if arguments.record || black_board.settings.record_session {
+                recorder =
+                    SessionRecorder::create(SESSION_RECORD_PATH, &black_board.settings, seed)
+                        .inspect_err(|error| {
+                            log::error!("The session can not be recorded: {error}")
+                        })
+                        .ok();
+                black_board
+                    .computer_player
+                    .get_ai_handler_mut()
+                    .set_recording(recorder.is_some());
+            }
+            // The opponent on the network can not be replayed, so it only plays along live.
+            black_board.remote_player = match (&arguments.host_address, &arguments.join_address) {
+                (Some(address), _) => Some(RemotePlayer::host(address)),
+                (None, Some(address)) => Some(RemotePlayer::join(address)),
+                (None, None) => None,
+            };
+            black_board.load_player_stats();
+            Box::new(LiveInput)
+        }
+    };
+    let mut last_mouse_position = Vec2::ZERO;
+    // The picture of the last frame, it is shown again, while nothing changes.
+    let mut frame_cache: Option<RenderTarget> = None;
+    let mut frame_size = Vec2::ZERO;
+    let mut previous_activity = FrameActivity::default();
 
     loop {
-        // First do the mouse clicks:
-        if is_mouse_button_pressed(MouseButton::Left) {
-            let mouse_pos = mouse_position();
-            let drawing_pos = camera.screen_to_world(Vec2::from(mouse_pos));
-            state_array[current_index].mouse_click(drawing_pos);
+        let frame_start = get_time();
+        set_active_theme(black_board.settings.theme);
+        // The board texture is created in the first frame. The window may have been resized, the board
+        // texture only gets regenerated when it grows or the theme has changed.
+        let layout_scale = get_layout_scale();
+        if black_board
+            .board_texture
+            .as_ref()
+            .is_none_or(|texture| WINDOW_DIMENSION * layout_scale > texture.width())
+            || texture_theme != black_board.settings.theme
+        {
+            black_board.board_texture = Some(create_board_texture(&STANDARD_LAYOUT, layout_scale));
+            black_board.connect_five_texture = Some(create_board_texture(
+                &BoardLayout::of::<ConnectFiveGeometry>(),
+                layout_scale,
+            ));
+            texture_theme = black_board.settings.theme;
+            black_board.request_redraw();
+        }
+        let window_size = Vec2::new(screen_width(), screen_height());
+        if frame_cache.is_none() || window_size != frame_size {
+            frame_cache = Some(create_frame_cache());
+            frame_size = window_size;
+            black_board.request_redraw();
         }
+        // Origin is in the lower left corner
+        let camera = create_layout_camera();
+        set_camera(&camera);
 
-        // Update logic-
-        let update_result = state_array[current_index].update(get_frame_time(), &mut black_board);
-        if let Some(follow_index) = update_result {
-            current_index = follow_index as usize;
-            state_array[current_index].enter(&black_board);
+        // First do the input, it comes from the player or from the replay.
+        let frame = match input_source.read_frame(&camera) {
+            Some(frame) => frame,
+            None => {
+                log::info!("The replay is over, the input comes from the player again");
+                black_board
+                    .computer_player
+                    .get_ai_handler_mut()
+                    .set_scripted(false);
+                input_source = Box::new(LiveInput);
+                replaying = false;
+                input_source
+                    .read_frame(&camera)
+                    .expect("The live input has a frame every time.")
+            }
+        };
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.record_frame(&frame);
         }
+        let input_happened = frame.mouse_position != last_mouse_position
+            || frame.mouse_pressed
+            || frame.mouse_released
+            || frame.key.is_some();
+        last_mouse_position = frame.mouse_position;
+        driver.dispatch_input(&frame);
+        if let Some(key) = frame.key {
+            // F2 exports the board and F3 toggles the debug HUD in every state.
+            if key == KeyCode::F2 {
+                let board = &black_board.game_board;
+                let svg = board_to_svg(board, Some(&get_winning_cells(board)));
+                let message = match fs::write(SVG_EXPORT_PATH, svg) {
+                    Ok(()) => "Board exported",
+                    Err(error) => {
+                        log::error!("The board could not be exported: {error}");
+                        "The board could not be exported"
+                    }
+                };
+                black_board.toasts.push(message, TOAST_DURATION);
+            } else if key == KeyCode::F3 {
+                black_board.settings.debug_hud = !black_board.settings.debug_hud;
+                let message = if black_board.settings.debug_hud {
+                    "Debug HUD on"
+                } else {
+                    "Debug HUD off"
+                };
+                black_board.toasts.push(message, TOAST_DURATION);
+                black_board.save_settings();
+            }
+        }
+
+        // Update logic in fixed steps, a replay feeds the results of the ai in the step they arrived in,
+        // when recorded.
+        let transitions = driver.advance(&frame, &mut black_board, recorder.as_mut());
+        if let Some(recorder) = recorder.as_mut() {
+            recorder.flush();
+        }
+        if replaying && transitions != frame.transitions {
+            log::warn!(
+                "The replay diverges, the states went {transitions:?} instead of {:?}",
+                frame.transitions
+            );
+        }
+
+        // The states leave the text for the clipboard on the blackboard, as only the main loop has the
+        // window.
+        if let Some(text) = black_board.clipboard_text.take() {
+            miniquad::window::clipboard_set(&text);
+        }
+
+        let ai_handler = black_board.computer_player.get_ai_handler();
+        let activity = FrameActivity {
+            input_happened,
+            state_idle: driver.get_current_state().is_idle(&black_board),
+            ai_pending: ai_handler.is_move_pending()
+                || ai_handler.is_hint_pending()
+                || ai_handler.is_evaluation_pending(),
+            toasts_shown: !black_board.toasts.is_empty(),
+        };
 
-        // First we do the logic.
-        clear_background(BLACK);
-        // Render stuff.
-        state_array[current_index].draw(&black_board);
+        // The frame gets drawn into the cache, unless the picture stays the same, then the cache is shown
+        // as it is.
+        let cached_frame = frame_cache
+            .as_ref()
+            .expect("The frame cache is created at the start of the frame.");
+        if is_redraw_needed(
+            activity,
+            previous_activity,
+            black_board.take_redraw_request(),
+        ) {
+            let mut cache_camera = create_layout_camera();
+            cache_camera.render_target = Some(cached_frame.clone());
+            set_camera(&cache_camera);
+            clear_background(get_active_theme().background);
+            driver.get_current_state().draw(&black_board);
+            if black_board.settings.debug_hud {
+                render_debug_hud(
+                    black_board
+                        .computer_player
+                        .get_ai_handler_mut()
+                        .try_get_progress()
+                        .as_ref(),
+                );
+            }
+            black_board.toasts.draw();
+            set_camera(&camera);
+        }
+        clear_background(get_active_theme().background);
+        draw_frame_cache(cached_frame);
+        previous_activity = activity;
+
+        // Nothing going on, so the loop slows down instead of rendering the same picture again.
+        if let Some(sleep_time) = get_idle_sleep(activity, (get_time() - frame_start) as f32) {
+            thread::sleep(sleep_time);
+        }
 
         next_frame().await
     }
@@ -0,0 +1,831 @@
+//! Network play against a friend over TCP. One side hosts the game and waits for the other side to join.
+//! Both sides talk with a line protocol, every message is one line:
+//! ```text
+//! HELLO 1          The first line of both sides with the version of the protocol they speak.
+//! START host       The host starts a new game, the side named moves first, which is host or guest.
+//! MOVE 4           A drop into the column counted from 1 like in the move string.
+//! RESIGN           The side gives up the game.
+//! PING             Keeps the connection alive, it is not answered.
+//! ```
+//! Network games are played with the classic rules and without handicaps, so that both sides agree on
+//! the board.
+//!
+//! The [`Session`] is the protocol of one side, it does not know about the connection. It keeps the
+//! board of the game and checks every move received on it. The [`Connection`] reads the lines in a
+//! background thread and hands them over with a channel, like the [`AiHandler`] does with its worker.
+//! Both come together in the [`RemotePlayer`], who is a [`Player`] like the ai.
+//!
+//! [`AiHandler`]: crate::board_logic::ai_handler::AiHandler
+
+use crate::board_logic::bit_board::{BitBoard, MoveError, Side};
+use crate::board_logic::player::Player;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::{fmt, mem, thread};
+
+/// The version of the protocol, both sides have to speak the same.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The time in seconds between two pings, that are sent, while nothing else is sent.
+pub const PING_INTERVAL: f32 = 5.0;
+
+/// The side of the connection.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    /// The side, that waits for the other one to join and starts the games.
+    Host,
+    /// The side, that joins the host.
+    Guest,
+}
+
+impl Role {
+    /// Gets the name of the role in the protocol.
+    fn get_name(self) -> &'static str {
+        match self {
+            Role::Host => "host",
+            Role::Guest => "guest",
+        }
+    }
+
+    /// Gets the role on the other side of the connection.
+    fn get_other(self) -> Role {
+        match self {
+            Role::Host => Role::Guest,
+            Role::Guest => Role::Host,
+        }
+    }
+}
+
+/// A message of the protocol.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Message {
+    Hello {
+        version: u32,
+    },
+    Start {
+        starter: Role,
+    },
+    /// A drop into the column, which is counted from 0 here.
+    Move {
+        column: u32,
+    },
+    Resign,
+    Ping,
+}
+
+impl Message {
+    /// Parses one line of the protocol.
+    pub fn parse(line: &str) -> Result<Message, NetworkError> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let message = match tokens.as_slice() {
+            ["HELLO", version] => version
+                .parse()
+                .ok()
+                .map(|version| Message::Hello { version }),
+            ["START", "host"] => Some(Message::Start {
+                starter: Role::Host,
+            }),
+            ["START", "guest"] => Some(Message::Start {
+                starter: Role::Guest,
+            }),
+            ["MOVE", column] => column
+                .parse::<u32>()
+                .ok()
+                .filter(|&column| column > 0)
+                .map(|column| Message::Move { column: column - 1 }),
+            ["RESIGN"] => Some(Message::Resign),
+            ["PING"] => Some(Message::Ping),
+            _ => None,
+        };
+        message.ok_or_else(|| NetworkError::InvalidMessage {
+            line: line.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Message::Hello { version } => write!(f, "HELLO {version}"),
+            Message::Start { starter } => write!(f, "START {}", starter.get_name()),
+            Message::Move { column } => write!(f, "MOVE {}", column + 1),
+            Message::Resign => write!(f, "RESIGN"),
+            Message::Ping => write!(f, "PING"),
+        }
+    }
+}
+
+/// The errors, that end the network game.
+#[derive(Clone, PartialEq, Debug)]
+pub enum NetworkError {
+    /// The connection could not be set up or broke down.
+    Connection { reason: String },
+    /// The other side has closed the connection.
+    Disconnected,
+    /// The other side speaks another version of the protocol.
+    VersionMismatch { version: u32 },
+    /// A line, that is not a message of the protocol, has been received.
+    InvalidMessage { line: String },
+    /// A message has been received or sent, that is not allowed at this point of the game.
+    UnexpectedMessage { message: Message },
+    /// The other side has sent a move, that is not possible on the board.
+    IllegalMove { column: u32, error: MoveError },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Connection { reason } => write!(f, "the connection failed: {reason}"),
+            NetworkError::Disconnected => write!(f, "the opponent has left"),
+            NetworkError::VersionMismatch { version } => write!(
+                f,
+                "the opponent speaks version {version} instead of {PROTOCOL_VERSION}"
+            ),
+            NetworkError::InvalidMessage { line } => write!(f, "invalid message {line}"),
+            NetworkError::UnexpectedMessage { message } => {
+                write!(f, "unexpected message {message}")
+            }
+            NetworkError::IllegalMove { column, error } => {
+                write!(f, "illegal move in column {}: {error}", column + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl From<io::Error> for NetworkError {
+    fn from(error: io::Error) -> NetworkError {
+        NetworkError::Connection {
+            reason: error.to_string(),
+        }
+    }
+}
+
+/// What the game has to know about a message received.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NetworkEvent {
+    /// Both sides have greeted each other, games can be started now.
+    Connected,
+    /// A new game has been started, the flag tells, if the own side moves first.
+    GameStarted { own_start: bool },
+    /// The other side has played the move.
+    MoveReceived { column: u32 },
+    /// The other side has given up the game.
+    Resigned,
+}
+
+/// The protocol of one side of the connection. The board of the game is kept from the perspective of
+/// the other side as the computer, just as the game treats its opponent.
+pub struct Session {
+    role: Role,
+    /// The other side has greeted us.
+    connected: bool,
+    /// A game is running.
+    playing: bool,
+    board: BitBoard,
+}
+
+impl Session {
+    pub fn new(role: Role) -> Session {
+        Session {
+            role,
+            connected: false,
+            playing: false,
+            board: BitBoard::new(),
+        }
+    }
+
+    pub fn get_role(&self) -> Role {
+        self.role
+    }
+
+    /// Checks if both sides have greeted each other.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Checks if a game is running.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Gets the board of the game, the other side is the computer on it.
+    pub fn get_board(&self) -> &BitBoard {
+        &self.board
+    }
+
+    /// Gets the greeting, that has to be sent first.
+    pub fn get_hello(&self) -> Message {
+        Message::Hello {
+            version: PROTOCOL_VERSION,
+        }
+    }
+
+    /// Sets up a new game, the starter moves first.
+    fn begin_game(&mut self, starter: Role) -> bool {
+        let own_start = starter == self.role;
+        self.board.reset();
+        self.board.set_computer_first(!own_start);
+        self.playing = true;
+        own_start
+    }
+
+    /// Plays the move on the board, if it is possible there. The game ends with a full board or a win.
+    fn play(&mut self, column: u32) -> Result<(), MoveError> {
+        self.board.play(column)?;
+        self.playing = !self.board.is_game_over();
+        Ok(())
+    }
+
+    /// Handles a message received from the other side and gets the event for the game. Messages, that
+    /// are not allowed at this point, and moves, that are not possible on the board, are errors.
+    pub fn receive(&mut self, message: Message) -> Result<Option<NetworkEvent>, NetworkError> {
+        let unexpected = Err(NetworkError::UnexpectedMessage { message });
+        match message {
+            Message::Hello { version } if !self.connected => {
+                if version != PROTOCOL_VERSION {
+                    return Err(NetworkError::VersionMismatch { version });
+                }
+                self.connected = true;
+                Ok(Some(NetworkEvent::Connected))
+            }
+            Message::Ping if self.connected => Ok(None),
+            Message::Start { starter } if self.connected && self.role == Role::Guest => {
+                let own_start = self.begin_game(starter);
+                Ok(Some(NetworkEvent::GameStarted { own_start }))
+            }
+            Message::Move { column }
+                if self.playing && self.board.side_to_move() == Side::Computer =>
+            {
+                self.play(column)
+                    .map_err(|error| NetworkError::IllegalMove { column, error })?;
+                Ok(Some(NetworkEvent::MoveReceived { column }))
+            }
+            Message::Resign if self.playing => {
+                self.playing = false;
+                Ok(Some(NetworkEvent::Resigned))
+            }
+            _ => unexpected,
+        }
+    }
+
+    /// Starts a new game, which only the host may do, and gets the message for the other side.
+    pub fn start_game(&mut self, own_start: bool) -> Result<Message, NetworkError> {
+        let starter = if own_start {
+            self.role
+        } else {
+            self.role.get_other()
+        };
+        let message = Message::Start { starter };
+        if !self.connected || self.role != Role::Host {
+            return Err(NetworkError::UnexpectedMessage { message });
+        }
+        self.begin_game(starter);
+        Ok(message)
+    }
+
+    /// Plays the own move and gets the message for the other side.
+    pub fn play_own_move(&mut self, column: u32) -> Result<Message, NetworkError> {
+        let message = Message::Move { column };
+        if !self.playing || self.board.side_to_move() != Side::Player {
+            return Err(NetworkError::UnexpectedMessage { message });
+        }
+        self.play(column)
+            .map_err(|_| NetworkError::UnexpectedMessage { message })?;
+        Ok(message)
+    }
+
+    /// Gives up the running game and gets the message for the other side.
+    pub fn resign(&mut self) -> Result<Message, NetworkError> {
+        if !self.playing {
+            return Err(NetworkError::UnexpectedMessage {
+                message: Message::Resign,
+            });
+        }
+        self.playing = false;
+        Ok(Message::Resign)
+    }
+}
+
+/// What the background thread of the connection hands over.
+enum ConnectionEvent {
+    /// The connection has been set up, the writing end is handed over.
+    Connected(Box<dyn Write + Send>),
+    Received(Message),
+    /// The connection has ended, nothing follows.
+    Failed(NetworkError),
+}
+
+/// Reads the messages from the stream and hands them over, until the stream ends or a line can not be
+/// read.
+fn read_messages(stream: impl Read, sender: &mpsc::Sender<ConnectionEvent>) -> NetworkError {
+    for line in BufReader::new(stream).lines() {
+        let message = match line {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => Message::parse(&line),
+            Err(error) => return error.into(),
+        };
+        match message {
+            Ok(message) => {
+                log::trace!("Received {message}");
+                if sender.send(ConnectionEvent::Received(message)).is_err() {
+                    // Nobody listens any more.
+                    return NetworkError::Disconnected;
+                }
+            }
+            Err(error) => return error,
+        }
+    }
+    NetworkError::Disconnected
+}
+
+/// The writing end of a TCP connection. Dropping it shuts the whole connection down, so that the reader
+/// thread ends and the other side notices.
+struct TcpWriter(TcpStream);
+
+impl Write for TcpWriter {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.0.write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Drop for TcpWriter {
+    fn drop(&mut self) {
+        let _ = self.0.shutdown(Shutdown::Both);
+    }
+}
+
+/// Sets up the TCP connection as the indicated role, the host waits for the guest to join.
+fn connect(role: Role, address: &str) -> io::Result<TcpStream> {
+    let stream = match role {
+        Role::Host => {
+            let listener = TcpListener::bind(address)?;
+            log::info!("Waiting for the opponent on {}", listener.local_addr()?);
+            listener.accept()?.0
+        }
+        Role::Guest => TcpStream::connect(address)?,
+    };
+    log::info!("Connected to {}", stream.peer_addr()?);
+    // The moves are single short lines, they should not wait for more data.
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+/// The connection to the other side. The lines are read in a background thread, so that waiting for
+/// the other side never blocks the game.
+pub struct Connection {
+    receiver: mpsc::Receiver<ConnectionEvent>,
+    /// The writing end, none until the connection has been set up or after it has failed.
+    writer: Option<Box<dyn Write + Send>>,
+}
+
+impl Connection {
+    /// Sets up the connection in the background thread, the host waits for the guest on the address,
+    /// the guest connects to the host at the address.
+    pub fn open(role: Role, address: &str) -> Connection {
+        let (sender, receiver) = mpsc::channel();
+        let spawn_error_sender = sender.clone();
+        let address = address.to_string();
+        let spawned = thread::Builder::new()
+            .name("network reader".to_string())
+            .spawn(move || {
+                let error = match connect(role, &address).and_then(|stream| {
+                    let writer = stream.try_clone()?;
+                    Ok((stream, writer))
+                }) {
+                    Ok((stream, writer)) => {
+                        let _ =
+                            sender.send(ConnectionEvent::Connected(Box::new(TcpWriter(writer))));
+                        read_messages(stream, &sender)
+                    }
+                    Err(error) => error.into(),
+                };
+                let _ = sender.send(ConnectionEvent::Failed(error));
+            });
+        if let Err(error) = spawned {
+            let _ = spawn_error_sender.send(ConnectionEvent::Failed(error.into()));
+        }
+        Connection {
+            receiver,
+            writer: None,
+        }
+    }
+
+    /// Creates the connection on streams, that are already connected. This way the protocol can run
+    /// over other transports than TCP.
+    pub fn from_streams(
+        reader: impl Read + Send + 'static,
+        writer: impl Write + Send + 'static,
+    ) -> io::Result<Connection> {
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("network reader".to_string())
+            .spawn(move || {
+                let error = read_messages(reader, &sender);
+                let _ = sender.send(ConnectionEvent::Failed(error));
+            })?;
+        Ok(Connection {
+            receiver,
+            writer: Some(Box::new(writer)),
+        })
+    }
+
+    /// Checks if the connection has been set up.
+    pub fn is_open(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Closes the connection, nothing can be sent any more.
+    pub fn close(&mut self) {
+        self.writer = None;
+    }
+
+    /// Sends the message, the connection has to be open.
+    pub fn send(&mut self, message: Message) -> Result<(), NetworkError> {
+        let writer = self.writer.as_mut().ok_or(NetworkError::Disconnected)?;
+        log::trace!("Sending {message}");
+        writeln!(writer, "{message}")
+            .and_then(|_| writer.flush())
+            .map_err(|error| {
+                self.writer = None;
+                error.into()
+            })
+    }
+
+    /// Gets the next message, that has arrived, none if there is none yet.
+    pub fn try_receive(&mut self) -> Result<Option<Message>, NetworkError> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(ConnectionEvent::Connected(writer)) => self.writer = Some(writer),
+                Ok(ConnectionEvent::Received(message)) => return Ok(Some(message)),
+                Ok(ConnectionEvent::Failed(error)) => {
+                    self.writer = None;
+                    return Err(error);
+                }
+                Err(mpsc::TryRecvError::Empty) => return Ok(None),
+                Err(mpsc::TryRecvError::Disconnected) => return Err(NetworkError::Disconnected),
+            }
+        }
+    }
+}
+
+/// The opponent on the other side of a network connection. The own moves have to be handed over with
+/// [`RemotePlayer::send_move`], the moves of the opponent get checked on the board before they are
+/// handed out. Once something went wrong, the connection is over, the error is kept for
+/// [`RemotePlayer::take_error`] and the player does not deliver any more moves.
+pub struct RemotePlayer {
+    connection: Connection,
+    session: Session,
+    /// The greeting has been sent.
+    hello_sent: bool,
+    /// The moves received and not polled yet.
+    received_moves: VecDeque<u32>,
+    /// The game started by the host, that has not been picked up yet, with the flag if the own side
+    /// moves first.
+    started_game: Option<bool>,
+    /// The opponent has resigned and this has not been picked up yet.
+    resigned: bool,
+    /// Something went wrong, the connection is over.
+    failed: bool,
+    /// The error, that has ended the connection and has not been picked up yet.
+    error: Option<NetworkError>,
+    /// The time since the last message has been sent.
+    time_since_send: f32,
+}
+
+impl RemotePlayer {
+    /// Creates the player on a connection, that gets opened or has been opened already.
+    pub fn new(connection: Connection, role: Role) -> RemotePlayer {
+        RemotePlayer {
+            connection,
+            session: Session::new(role),
+            hello_sent: false,
+            received_moves: VecDeque::new(),
+            started_game: None,
+            resigned: false,
+            failed: false,
+            error: None,
+            time_since_send: 0.0,
+        }
+    }
+
+    /// Waits for the opponent to join on the address, like `0.0.0.0:4444`.
+    pub fn host(address: &str) -> RemotePlayer {
+        RemotePlayer::new(Connection::open(Role::Host, address), Role::Host)
+    }
+
+    /// Joins the opponent, who hosts the game on the address.
+    pub fn join(address: &str) -> RemotePlayer {
+        RemotePlayer::new(Connection::open(Role::Guest, address), Role::Guest)
+    }
+
+    pub fn get_role(&self) -> Role {
+        self.session.get_role()
+    }
+
+    /// Checks if something has gone wrong, which has ended the connection.
+    pub fn has_failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Checks if both sides are connected and nothing has gone wrong.
+    pub fn is_connected(&self) -> bool {
+        !self.failed && self.session.is_connected()
+    }
+
+    /// Keeps the error, the first one ends the connection.
+    fn fail(&mut self, error: NetworkError) {
+        if !self.failed {
+            log::warn!("The network game ends: {error}");
+            self.failed = true;
+            self.error = Some(error);
+            self.connection.close();
+        }
+    }
+
+    /// Sends the message, a failure ends the connection.
+    fn send(&mut self, message: Result<Message, NetworkError>) {
+        if self.failed {
+            return;
+        }
+        match message.and_then(|message| self.connection.send(message)) {
+            Ok(()) => self.time_since_send = 0.0,
+            Err(error) => self.fail(error),
+        }
+    }
+
+    /// Handles the messages, that have arrived, and greets the other side, once connected.
+    fn receive_messages(&mut self) {
+        while !self.failed {
+            let event = match self.connection.try_receive() {
+                Ok(Some(message)) => self.session.receive(message),
+                Ok(None) => break,
+                Err(error) => Err(error),
+            };
+            match event {
+                Ok(Some(NetworkEvent::GameStarted { own_start })) => {
+                    self.received_moves.clear();
+                    self.started_game = Some(own_start);
+                }
+                Ok(Some(NetworkEvent::MoveReceived { column })) => {
+                    self.received_moves.push_back(column)
+                }
+                Ok(Some(NetworkEvent::Resigned)) => self.resigned = true,
+                Ok(Some(NetworkEvent::Connected) | None) => {}
+                Err(error) => self.fail(error),
+            }
+        }
+        if !self.hello_sent && self.connection.is_open() {
+            self.hello_sent = true;
+            self.send(Ok(self.session.get_hello()));
+        }
+    }
+
+    /// Handles the messages, that have arrived, and pings the other side, when nothing has been sent for
+    /// a while. This has to be called regularly with the time passed in seconds, so that a lost
+    /// connection gets noticed.
+    pub fn update(&mut self, delta_time: f32) {
+        self.receive_messages();
+        self.time_since_send += delta_time;
+        if self.session.is_connected() && self.time_since_send >= PING_INTERVAL {
+            self.send(Ok(Message::Ping));
+        }
+    }
+
+    /// Starts a new game, only the host can do this.
+    pub fn start_game(&mut self, own_start: bool) {
+        self.received_moves.clear();
+        let message = self.session.start_game(own_start);
+        self.send(message);
+    }
+
+    /// Takes the game, the host has started, with the flag if the own side moves first.
+    pub fn take_started_game(&mut self) -> Option<bool> {
+        self.receive_messages();
+        self.started_game.take()
+    }
+
+    /// Sends the own move to the opponent.
+    pub fn send_move(&mut self, column: u32) {
+        let message = self.session.play_own_move(column);
+        self.send(message);
+    }
+
+    /// Gives up the running game. Without a running game nothing gets sent.
+    pub fn resign(&mut self) {
+        if self.session.is_playing() {
+            let message = self.session.resign();
+            self.send(message);
+        }
+    }
+
+    /// Checks and resets, if the opponent has resigned.
+    pub fn take_resigned(&mut self) -> bool {
+        mem::take(&mut self.resigned)
+    }
+
+    /// Takes the error, that has ended the connection.
+    pub fn take_error(&mut self) -> Option<NetworkError> {
+        self.error.take()
+    }
+}
+
+impl Player for RemotePlayer {
+    /// The opponent knows the board from the moves, so there is nothing to hand over.
+    fn request_move(&mut self, _: &BitBoard) {}
+
+    fn poll_move(&mut self) -> Option<u32> {
+        self.receive_messages();
+        self.received_moves.pop_front()
+    }
+
+    fn name(&self) -> &str {
+        "Opponent"
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets a session of the role, that has greeted the other side.
+    fn get_connected_session(role: Role) -> Session {
+        let mut session = Session::new(role);
+        let hello = session.get_hello();
+        assert_eq!(session.receive(hello), Ok(Some(NetworkEvent::Connected)));
+        session
+    }
+
+    /// Gets the guest in a game, the host has started and moves first in.
+    fn get_guest_in_game() -> Session {
+        let mut guest = get_connected_session(Role::Guest);
+        let start = Message::Start {
+            starter: Role::Host,
+        };
+        assert_eq!(
+            guest.receive(start),
+            Ok(Some(NetworkEvent::GameStarted { own_start: false }))
+        );
+        guest
+    }
+
+    #[test]
+    fn test_messages_survive_the_line() {
+        let messages = [
+            Message::Hello { version: 1 },
+            Message::Start {
+                starter: Role::Host,
+            },
+            Message::Start {
+                starter: Role::Guest,
+            },
+            Message::Move { column: 0 },
+            Message::Move { column: 6 },
+            Message::Resign,
+            Message::Ping,
+        ];
+        for message in messages {
+            assert_eq!(Message::parse(&message.to_string()), Ok(message));
+        }
+        for line in [
+            "",
+            "MOVE 0",
+            "MOVE -1",
+            "MOVE x",
+            "START nobody",
+            "HELLO",
+            "PING 1",
+        ] {
+            assert_eq!(
+                Message::parse(line),
+                Err(NetworkError::InvalidMessage {
+                    line: line.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_other_version_is_refused() {
+        let mut session = Session::new(Role::Host);
+        assert_eq!(
+            session.receive(Message::Hello { version: 2 }),
+            Err(NetworkError::VersionMismatch { version: 2 })
+        );
+        assert!(!session.is_connected());
+    }
+
+    #[test]
+    fn test_messages_out_of_order_are_refused() {
+        // Nothing but the greeting comes first.
+        let mut session = Session::new(Role::Guest);
+        assert_eq!(
+            session.receive(Message::Ping),
+            Err(NetworkError::UnexpectedMessage {
+                message: Message::Ping
+            })
+        );
+        // Only the host starts the games.
+        let mut host = get_connected_session(Role::Host);
+        let start = Message::Start {
+            starter: Role::Guest,
+        };
+        assert_eq!(
+            host.receive(start),
+            Err(NetworkError::UnexpectedMessage { message: start })
+        );
+        let mut guest = get_connected_session(Role::Guest);
+        assert!(guest.start_game(true).is_err());
+        // No moves or resignations outside of a game.
+        for message in [Message::Move { column: 3 }, Message::Resign] {
+            assert_eq!(
+                guest.receive(message),
+                Err(NetworkError::UnexpectedMessage { message })
+            );
+        }
+    }
+
+    #[test]
+    fn test_received_moves_are_checked_on_the_board() {
+        let mut guest = get_guest_in_game();
+        for (column, error) in [
+            (7, MoveError::InvalidColumn { column: 7 }),
+            (
+                u32::MAX - 1,
+                MoveError::InvalidColumn {
+                    column: u32::MAX - 1,
+                },
+            ),
+        ] {
+            assert_eq!(
+                guest.receive(Message::Move { column }),
+                Err(NetworkError::IllegalMove { column, error })
+            );
+        }
+        assert!(guest.get_board().is_empty());
+
+        // The host may not move twice in a row.
+        let message = Message::Move { column: 3 };
+        assert_eq!(
+            guest.receive(message),
+            Ok(Some(NetworkEvent::MoveReceived { column: 3 }))
+        );
+        assert_eq!(
+            guest.receive(message),
+            Err(NetworkError::UnexpectedMessage { message })
+        );
+
+        // A full column is refused.
+        for _ in 0..2 {
+            guest.play_own_move(3).unwrap();
+            guest.receive(message).unwrap();
+        }
+        guest.play_own_move(3).unwrap();
+        assert_eq!(
+            guest.receive(message),
+            Err(NetworkError::IllegalMove {
+                column: 3,
+                error: MoveError::ColumnFull { column: 3 }
+            })
+        );
+        assert_eq!(guest.get_board().stone_count(), 6);
+    }
+
+    #[test]
+    fn test_game_ends_with_the_win() {
+        let mut guest = get_guest_in_game();
+        for column in 0..3 {
+            guest.receive(Message::Move { column }).unwrap();
+            guest.play_own_move(6).unwrap();
+        }
+        assert_eq!(
+            guest.receive(Message::Move { column: 3 }),
+            Ok(Some(NetworkEvent::MoveReceived { column: 3 }))
+        );
+        assert!(!guest.is_playing());
+        assert!(guest.play_own_move(6).is_err());
+        assert!(guest.resign().is_err());
+    }
+
+    #[test]
+    fn test_resignation_ends_the_game() {
+        let mut guest = get_guest_in_game();
+        assert_eq!(
+            guest.receive(Message::Resign),
+            Ok(Some(NetworkEvent::Resigned))
+        );
+        assert!(!guest.is_playing());
+        assert!(guest.receive(Message::Move { column: 0 }).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! A minimal text protocol, so that the engine can play against other Connect-4 engines. Every command
+//! is one line and the engine only answers to *go*:
+//! ```text
+//! newgame                  Starts a new game with an empty board and a fresh transposition table.
+//! position [movestring]    Sets up the position from the columns 1 to 7 played so far.
+//! go depth N               Searches up to the depth N.
+//! go movetime MS           Searches with a base time of MS milliseconds, that may get extended.
+//! go                       Searches with the default settings of the engine.
+//! quit                     Ends the session.
+//! ```
+//! The answer to *go* is an `info depth D score S nodes N square Q` line followed by `bestmove C`,
+//! where the square Q is the name of the square the stone lands on, like d1, and the column C is
+//! counted from 1 like in the move string. Commands, that can not be executed, are
+//! answered with an `error` line.
+
+use crate::board_logic::alpha_beta::{AlphaBeta, SearchConfig};
+use crate::board_logic::bit_board::{BitBoard, MoveStringError};
+use crate::board_logic::notation::get_square_name;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+/// A command of the protocol.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    NewGame,
+    Position(String),
+    Go(SearchLimit),
+    Quit,
+}
+
+/// The limit the *go* command puts on the search.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchLimit {
+    Default,
+    Depth(u32),
+    MoveTime(u32),
+}
+
+/// The reasons, why a command can not be executed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProtocolError {
+    /// The command is not known.
+    UnknownCommand { command: String },
+    /// The arguments of a known command can not be read.
+    InvalidArguments { command: String },
+    /// The move string of the position command is invalid.
+    InvalidPosition(MoveStringError),
+    /// The search has been requested on a finished game.
+    GameOver,
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::UnknownCommand { command } => write!(f, "unknown command {command}"),
+            ProtocolError::InvalidArguments { command } => {
+                write!(f, "invalid arguments for {command}")
+            }
+            ProtocolError::InvalidPosition(error) => write!(f, "invalid position: {error}"),
+            ProtocolError::GameOver => write!(f, "the game is already over"),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl Command {
+    /// Parses one line of the protocol. Empty lines are not a command and return none.
+    pub fn parse(line: &str) -> Result<Option<Command>, ProtocolError> {
+        let mut tokens = line.split_whitespace();
+        let Some(command) = tokens.next() else {
+            return Ok(None);
+        };
+        let arguments: Vec<&str> = tokens.collect();
+        let invalid_arguments = || ProtocolError::InvalidArguments {
+            command: command.to_string(),
+        };
+
+        let result = match (command, arguments.as_slice()) {
+            ("newgame", []) => Command::NewGame,
+            ("position", []) => Command::Position(String::new()),
+            ("position", [moves]) => Command::Position(moves.to_string()),
+            ("go", []) => Command::Go(SearchLimit::Default),
+            ("go", ["depth", depth]) => match depth.parse() {
+                Ok(depth) if depth > 0 => Command::Go(SearchLimit::Depth(depth)),
+                _ => return Err(invalid_arguments()),
+            },
+            ("go", ["movetime", milliseconds]) => match milliseconds.parse() {
+                Ok(milliseconds) => Command::Go(SearchLimit::MoveTime(milliseconds)),
+                _ => return Err(invalid_arguments()),
+            },
+            ("quit", []) => Command::Quit,
+            ("newgame" | "position" | "go" | "quit", _) => return Err(invalid_arguments()),
+            _ => {
+                return Err(ProtocolError::UnknownCommand {
+                    command: command.to_string(),
+                });
+            }
+        };
+        Ok(Some(result))
+    }
+}
+
+/// The engine side of the protocol, it keeps the position and the search between the commands.
+pub struct ProtocolEngine {
+    /// The search, it keeps its transposition table until the next new game.
+    ai: AlphaBeta,
+    /// The position set up with the last position command, from the perspective of the player to move.
+    board: BitBoard,
+}
+
+impl Default for ProtocolEngine {
+    fn default() -> ProtocolEngine {
+        ProtocolEngine::new()
+    }
+}
+
+impl ProtocolEngine {
+    pub fn new() -> ProtocolEngine {
+        ProtocolEngine {
+            ai: AlphaBeta::new(),
+            board: BitBoard::new(),
+        }
+    }
+
+    /// Executes a command and writes the answer to the output. Returns false, if the session is over.
+    pub fn execute(&mut self, command: Command, output: &mut impl Write) -> io::Result<bool> {
+        match command {
+            Command::NewGame => {
+                self.ai = AlphaBeta::new();
+                self.board = BitBoard::new();
+            }
+            Command::Position(moves) => match BitBoard::from_move_string(&moves) {
+                Ok(board) => self.board = board,
+                Err(error) => writeln!(output, "error {}", ProtocolError::InvalidPosition(error))?,
+            },
+            Command::Go(limit) => self.search(limit, output)?,
+            Command::Quit => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Searches the best move on the current position within the limit and reports it.
+    fn search(&mut self, limit: SearchLimit, output: &mut impl Write) -> io::Result<()> {
+        let mut config = SearchConfig::default();
+        match limit {
+            SearchLimit::Default => {}
+            SearchLimit::Depth(depth) => {
+                config.max_depth = depth;
+                config.move_time = None;
+            }
+            SearchLimit::MoveTime(milliseconds) => {
+                config.move_time = Some(milliseconds as f32 / 1000.0);
+            }
+        }
+        self.ai.set_config(config);
+
+        let Ok(column) = self.ai.get_best_move(self.board.clone()) else {
+            return writeln!(output, "error {}", ProtocolError::GameOver);
+        };
+        let row = self
+            .board
+            .get_move_destination(column)
+            .expect("The best move is a possible one.");
+        writeln!(
+            output,
+            "info depth {} score {:.4} nodes {} square {}",
+            self.ai.get_completed_depth(),
+            self.ai.get_best_score(),
+            self.ai.get_nodes_searched(),
+            get_square_name(column, row)
+        )?;
+        writeln!(output, "bestmove {}", column + 1)
+    }
+}
+
+/// Runs the protocol on the input until it ends or the quit command arrives. The output is flushed
+/// after every command, as the other side waits for the answer.
+pub fn run_protocol(input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut engine = ProtocolEngine::new();
+    for line in input.lines() {
+        let keep_running = match Command::parse(&line?) {
+            Ok(Some(command)) => engine.execute(command, &mut output)?,
+            Ok(None) => true,
+            Err(error) => {
+                writeln!(output, "error {error}")?;
+                true
+            }
+        };
+        output.flush()?;
+        if !keep_running {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Executes the commands of the lines on the engine and gets the lines it has written.
+    fn execute_lines(engine: &mut ProtocolEngine, lines: &[&str]) -> Vec<String> {
+        let mut output = Vec::new();
+        for line in lines {
+            let command = Command::parse(line).unwrap().unwrap();
+            assert!(engine.execute(command, &mut output).unwrap());
+        }
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_commands_are_parsed() {
+        let cases = [
+            ("newgame", Command::NewGame),
+            ("position", Command::Position(String::new())),
+            ("  position   4453 ", Command::Position("4453".to_string())),
+            ("go", Command::Go(SearchLimit::Default)),
+            ("go depth 7", Command::Go(SearchLimit::Depth(7))),
+            ("go movetime 250", Command::Go(SearchLimit::MoveTime(250))),
+            ("quit", Command::Quit),
+        ];
+        for (line, command) in cases {
+            assert_eq!(Command::parse(line), Ok(Some(command)), "{line}");
+        }
+        assert_eq!(Command::parse(""), Ok(None));
+        assert_eq!(Command::parse(" \t "), Ok(None));
+    }
+
+    #[test]
+    fn test_invalid_commands_are_refused() {
+        for line in [
+            "newgame now",
+            "position 44 53",
+            "go depth",
+            "go depth 0",
+            "go depth -3",
+            "go movetime fast",
+            "go nodes 100",
+            "quit now",
+        ] {
+            let command = line.split(' ').next().unwrap().to_string();
+            assert_eq!(
+                Command::parse(line),
+                Err(ProtocolError::InvalidArguments { command }),
+                "{line}"
+            );
+        }
+        assert_eq!(
+            Command::parse("Go depth 3"),
+            Err(ProtocolError::UnknownCommand {
+                command: "Go".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_search_answers_with_info_and_bestmove() {
+        let mut engine = ProtocolEngine::new();
+        // The second player has to block the three in the fourth column.
+        let answers = execute_lines(&mut engine, &["position 41414", "go depth 3"]);
+        let [info, best_move] = &answers[..] else {
+            panic!("Two lines are expected: {answers:?}");
+        };
+        let tokens: Vec<&str> = info.split(' ').collect();
+        let [
+            "info",
+            "depth",
+            depth,
+            "score",
+            score,
+            "nodes",
+            nodes,
+            "square",
+            "d4",
+        ] = tokens[..]
+        else {
+            panic!("The info line {info} has not the expected form.");
+        };
+        assert_eq!(depth, "3");
+        assert_eq!(score.split_once('.').unwrap().1.len(), 4, "{info}");
+        assert!(nodes.parse::<u64>().unwrap() > 0);
+        assert_eq!(best_move, "bestmove 4");
+
+        let answers = execute_lines(&mut engine, &["go movetime 20"]);
+        assert_eq!(answers.last().unwrap(), "bestmove 4");
+    }
+
+    #[test]
+    fn test_invalid_position_keeps_the_last_one() {
+        let mut engine = ProtocolEngine::new();
+        let answers = execute_lines(
+            &mut engine,
+            &["position 414141", "position 4x", "go depth 2"],
+        );
+        assert_eq!(
+            answers[0],
+            "error invalid position: invalid column 'x' at position 1"
+        );
+        assert_eq!(answers[2], "bestmove 4");
+    }
+
+    #[test]
+    fn test_new_game_clears_the_position() {
+        let mut engine = ProtocolEngine::new();
+        let answers = execute_lines(&mut engine, &["position 4141414", "go depth 2"]);
+        assert_eq!(answers, ["error the game is already over"]);
+        let answers = execute_lines(&mut engine, &["newgame", "go depth 2"]);
+        // On the empty board every stone lands in the bottom row.
+        assert!(answers[0].starts_with("info depth 2 "), "{answers:?}");
+        assert!(answers[0].ends_with('1'), "{answers:?}");
+        assert!(answers[1].starts_with("bestmove "), "{answers:?}");
+    }
+
+    #[test]
+    fn test_session_ends_with_quit() {
+        let mut output = Vec::new();
+        let mut engine = ProtocolEngine::new();
+        assert!(!engine.execute(Command::Quit, &mut output).unwrap());
+        assert!(output.is_empty());
+
+        let input = "bogus\nquit\ngo depth 1\n";
+        let mut output = Vec::new();
+        run_protocol(input.as_bytes(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "error unknown command bogus\n"
+        );
+    }
+}
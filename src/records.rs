@@ -0,0 +1,476 @@
+//! The records of the finished games. Every game, that ends, can be written into a JSON file with the
+//! players, the starter, the moves with the time they were made at, the result and the duration, so
+//! that the games can be looked at in other tools like a spreadsheet. A record looks like this:
+//! ```text
+//! {
+//!   "version": 3,
+//!   "started_at": 1760612345,
+//!   "player": { "kind": "human" },
+//!   "computer": { "kind": "engine", "max_depth": 7, "move_time": null },
+//!   "starter": "player",
+//!   "rule_set": "classic",
+//!   "moves": [
+//!     { "side": "player", "slot": 3, "time": 1.25, "evaluation": null },
+//!     { "side": "computer", "slot": 3, "time": 2.5, "evaluation": 0.1 },
+//!     ...
+//!   ],
+//!   "result": "player-won",
+//!   "win": "a horizontal line from a1 to d1",
+//!   "notation": "d1 d2 c1 c2 b1 b2 a1",
+//!   "duration": 31.75
+//! }
+//! ```
+//! The records carry the version of their schema. Later versions only add fields, so a reader skips
+//! the fields it does not know, but it refuses a record with a version newer than its own.
+
+use crate::board_logic::alpha_beta::SearchConfig;
+use crate::board_logic::bit_board::{GameResult, RuleSet, Side};
+use crate::board_logic::notation::move_list_to_algebraic;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The version of the schema, the records get written with.
+pub const RECORD_VERSION: u32 = 3;
+
+/// The errors, that make a record unreadable.
+#[derive(Clone, PartialEq, Debug)]
+pub enum RecordError {
+    /// The text is no record.
+    Invalid { reason: String },
+    /// The record does not tell the version of its schema.
+    MissingVersion,
+    /// The record has been written by a later version, that this one does not know.
+    UnsupportedVersion { version: u64 },
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordError::Invalid { reason } => write!(f, "invalid record: {reason}"),
+            RecordError::MissingVersion => write!(f, "the record has no version"),
+            RecordError::UnsupportedVersion { version } => write!(
+                f,
+                "the record has version {version}, only up to {RECORD_VERSION} can be read"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<serde_json::Error> for RecordError {
+    fn from(error: serde_json::Error) -> RecordError {
+        RecordError::Invalid {
+            reason: error.to_string(),
+        }
+    }
+}
+
+/// Who chooses the moves of a side.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum PlayerKind {
+    /// The person in front of the screen.
+    Human,
+    /// The ai with the configuration of its search, the move time is in seconds.
+    Engine {
+        max_depth: u32,
+        move_time: Option<f32>,
+    },
+    /// The opponent on the other side of the network.
+    Remote,
+}
+
+impl PlayerKind {
+    /// Gets the engine, that searches with the configuration.
+    pub fn from_search_config(config: &SearchConfig) -> PlayerKind {
+        PlayerKind::Engine {
+            max_depth: config.max_depth,
+            move_time: config.move_time,
+        }
+    }
+}
+
+/// The side of a move in the record.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordSide {
+    Player,
+    Computer,
+}
+
+impl From<Side> for RecordSide {
+    fn from(side: Side) -> RecordSide {
+        match side {
+            Side::Player => RecordSide::Player,
+            Side::Computer => RecordSide::Computer,
+        }
+    }
+}
+
+/// The rules of the game in the record.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordRuleSet {
+    Classic,
+    PopOut,
+}
+
+impl From<RuleSet> for RecordRuleSet {
+    fn from(rule_set: RuleSet) -> RecordRuleSet {
+        match rule_set {
+            RuleSet::Classic => RecordRuleSet::Classic,
+            RuleSet::PopOut => RecordRuleSet::PopOut,
+        }
+    }
+}
+
+/// How the game has ended, seen from the sides instead of the colors.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecordResult {
+    /// The game has not ended yet.
+    Pending,
+    Draw,
+    PlayerWon,
+    ComputerWon,
+    /// The player ran out of time.
+    PlayerTimeout,
+    /// The computer ran out of time.
+    ComputerTimeout,
+}
+
+impl RecordResult {
+    /// Gets the result of the game, in which the computer has moved first or not.
+    pub fn from_game_result(result: GameResult, computer_first: bool) -> RecordResult {
+        // The side the result is about, as the side moving first, and if it is a timeout.
+        let (about_first, timeout) = match result {
+            GameResult::Pending => return RecordResult::Pending,
+            GameResult::Draw => return RecordResult::Draw,
+            GameResult::FirstPlayerWon => (true, false),
+            GameResult::SecondPlayerWon => (false, false),
+            GameResult::FirstPlayerTimeout => (true, true),
+            GameResult::SecondPlayerTimeout => (false, true),
+        };
+        match (about_first == computer_first, timeout) {
+            (true, false) => RecordResult::ComputerWon,
+            (false, false) => RecordResult::PlayerWon,
+            (true, true) => RecordResult::ComputerTimeout,
+            (false, true) => RecordResult::PlayerTimeout,
+        }
+    }
+}
+
+/// A move of the game.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct MoveRecord {
+    pub side: RecordSide,
+    /// The slot played, the slots behind the columns pop the bottom stone out under the Pop Out rules.
+    pub slot: u32,
+    /// The time in seconds since the start of the game, when the move was made.
+    pub time: f64,
+    /// The score the engine has given its move from its own perspective, none for the other players.
+    pub evaluation: Option<f32>,
+}
+
+/// The record of a game, it is filled during the game and written once the game has ended.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// The version of the schema, see [`RECORD_VERSION`].
+    pub version: u32,
+    /// The moment the game started in seconds since the Unix epoch, zero if the time is not known.
+    pub started_at: u64,
+    pub player: PlayerKind,
+    pub computer: PlayerKind,
+    /// The side, that moved first.
+    pub starter: RecordSide,
+    pub rule_set: RecordRuleSet,
+    pub moves: Vec<MoveRecord>,
+    pub result: RecordResult,
+    /// How the game has been won on the board, see [`describe_win`], none if it has not. It has been
+    /// added with version 2.
+    ///
+    /// [`describe_win`]: crate::board_logic::notation::describe_win
+    #[serde(default)]
+    pub win: Option<String>,
+    /// The moves as the squares the stones have landed on, see [`move_list_to_algebraic`], so that the
+    /// game can be read without replaying the slots. None while the game is running. It has been added
+    /// with version 3.
+    #[serde(default)]
+    pub notation: Option<String>,
+    /// The time in seconds the game took.
+    pub duration: f64,
+}
+
+impl GameRecord {
+    /// Creates the record of a game, that starts now.
+    pub fn new(
+        player: PlayerKind,
+        computer: PlayerKind,
+        starter: Side,
+        rule_set: RuleSet,
+        started_at: u64,
+    ) -> GameRecord {
+        GameRecord {
+            version: RECORD_VERSION,
+            started_at,
+            player,
+            computer,
+            starter: starter.into(),
+            rule_set: rule_set.into(),
+            moves: Vec::new(),
+            result: RecordResult::Pending,
+            win: None,
+            notation: None,
+            duration: 0.0,
+        }
+    }
+
+    /// Adds the move of the side made the indicated seconds after the start of the game.
+    pub fn record_move(&mut self, side: Side, slot: u32, time: f64, evaluation: Option<f32>) {
+        self.moves.push(MoveRecord {
+            side: side.into(),
+            slot,
+            time,
+            evaluation,
+        });
+    }
+
+    /// Drops the moves, that have been taken back, so that the indicated amount of moves is left.
+    pub fn truncate_moves(&mut self, move_count: usize) {
+        self.moves.truncate(move_count);
+    }
+
+    /// Ends the game with the result after the indicated seconds. The description of the win is none,
+    /// if the game has not been won on the board. The moves get written in the notation of the squares.
+    pub fn finish(&mut self, result: RecordResult, win: Option<String>, duration: f64) {
+        self.result = result;
+        self.win = win;
+        let slots: Vec<u32> = self.moves.iter().map(|record| record.slot).collect();
+        self.notation = move_list_to_algebraic(&slots);
+        self.duration = duration;
+    }
+
+    /// Writes the record as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A game record can always be written as JSON.")
+    }
+
+    /// Reads the record from the JSON. The version gets checked first, so that a later version is
+    /// refused with its own error, instead of failing on a field, that has changed. A number, that is too
+    /// large for its field, is refused as well, as it could not be written again.
+    pub fn from_json(text: &str) -> Result<GameRecord, RecordError> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        let record: GameRecord = match value.get("version").and_then(serde_json::Value::as_u64) {
+            None => return Err(RecordError::MissingVersion),
+            Some(version) if version > u64::from(RECORD_VERSION) => {
+                return Err(RecordError::UnsupportedVersion { version });
+            }
+            Some(_) => serde_json::from_value(value)?,
+        };
+        if !record.has_finite_numbers() {
+            return Err(RecordError::Invalid {
+                reason: "a number is out of range".to_string(),
+            });
+        }
+        Ok(record)
+    }
+
+    /// Checks, that all the times and scores are finite. JSON has no infinity, it would be written as null.
+    fn has_finite_numbers(&self) -> bool {
+        let move_time_is_finite = |player: &PlayerKind| match player {
+            PlayerKind::Engine {
+                move_time: Some(move_time),
+                ..
+            } => move_time.is_finite(),
+            _ => true,
+        };
+        self.duration.is_finite()
+            && move_time_is_finite(&self.player)
+            && move_time_is_finite(&self.computer)
+            && self.moves.iter().all(|record| {
+                record.time.is_finite() && record.evaluation.is_none_or(f32::is_finite)
+            })
+    }
+
+    /// Writes the record into a new file in the directory, which gets created if needed. The file is named
+    /// after the start of the game, an existing file is never overwritten. Returns the path of the file.
+    pub fn write_to_directory(&self, directory: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let directory = directory.as_ref();
+        std::fs::create_dir_all(directory)?;
+        let mut index = 0;
+        loop {
+            let file_name = if index == 0 {
+                format!("game-{}.json", self.started_at)
+            } else {
+                format!("game-{}-{index}.json", self.started_at)
+            };
+            let path = directory.join(file_name);
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(self.to_json().as_bytes())?;
+                    return Ok(path);
+                }
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => index += 1,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Gets the seconds since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Gets the seconds since the Unix epoch. The web has no system time, there it is zero.
+#[cfg(target_arch = "wasm32")]
+pub fn get_unix_time() -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets the record of a short game, that the player has won.
+    fn get_finished_record() -> GameRecord {
+        let mut record = GameRecord::new(
+            PlayerKind::Human,
+            PlayerKind::Engine {
+                max_depth: 7,
+                move_time: Some(1.5),
+            },
+            Side::Player,
+            RuleSet::Classic,
+            1760612345,
+        );
+        for (index, slot) in [0, 6, 1, 6, 2, 6, 3].into_iter().enumerate() {
+            let side = if index % 2 == 0 {
+                Side::Player
+            } else {
+                Side::Computer
+            };
+            let evaluation = (side == Side::Computer).then_some(-0.25);
+            record.record_move(side, slot, index as f64 * 1.5, evaluation);
+        }
+        record.finish(
+            RecordResult::PlayerWon,
+            Some("a horizontal line from a1 to d1".to_string()),
+            12.75,
+        );
+        record
+    }
+
+    #[test]
+    fn test_record_survives_the_round_trip() {
+        let record = get_finished_record();
+        assert_eq!(record.notation.as_deref(), Some("a1 g1 b1 g2 c1 g3 d1"));
+        let json = record.to_json();
+        assert!(json.contains("\"result\": \"player-won\""), "{json}");
+        assert_eq!(GameRecord::from_json(&json), Ok(record));
+    }
+
+    #[test]
+    fn test_long_numbers_survive_the_round_trip() {
+        // The fuzzer found a duration, whose last digit got lost on the way back.
+        let mut record = get_finished_record();
+        record.duration = 3.1555555555555556e60;
+        let json = record.to_json().replace(
+            "3.1555555555555556e+60",
+            "3155555555555555555555555555555555555555555555555555555555555.75",
+        );
+        assert!(json.contains(".75\n"), "{json}");
+        let read = GameRecord::from_json(&json).unwrap();
+        assert_eq!(GameRecord::from_json(&read.to_json()), Ok(read));
+    }
+
+    #[test]
+    fn test_numbers_out_of_range_are_refused() {
+        // The fuzzer found a score, that is too large for its field.
+        let json = get_finished_record().to_json();
+        for (field, value) in [
+            ("\"evaluation\": -0.25", "\"evaluation\": 5e84"),
+            ("\"move_time\": 1.5", "\"move_time\": -1e40"),
+        ] {
+            assert!(json.contains(field), "{json}");
+            let error = GameRecord::from_json(&json.replacen(field, value, 1)).unwrap_err();
+            assert_eq!(
+                error,
+                RecordError::Invalid {
+                    reason: "a number is out of range".to_string()
+                }
+            );
+        }
+        assert!(
+            GameRecord::from_json(&json.replace("\"duration\": 12.75", "\"duration\": 1e400"))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_versions() {
+        let json = get_finished_record().to_json();
+        assert_eq!(
+            GameRecord::from_json(&json.replace("\"version\": 3", "\"version\": 4")),
+            Err(RecordError::UnsupportedVersion { version: 4 })
+        );
+        assert_eq!(
+            GameRecord::from_json(&json.replace("\"version\": 3,", "")),
+            Err(RecordError::MissingVersion)
+        );
+
+        // The first version has neither the win nor the notation, they are read as none.
+        let mut first_version = get_finished_record();
+        first_version.version = 1;
+        first_version.win = None;
+        first_version.notation = None;
+        let json = first_version
+            .to_json()
+            .replace("  \"win\": null,\n", "")
+            .replace("  \"notation\": null,\n", "");
+        assert!(!json.contains("notation"), "{json}");
+        assert_eq!(GameRecord::from_json(&json), Ok(first_version));
+    }
+
+    #[test]
+    fn test_invalid_records() {
+        for text in ["", "[]", "{ \"version\": 3 }", "{ \"version\": \"3\" }"] {
+            let error = GameRecord::from_json(text).unwrap_err();
+            assert!(
+                matches!(
+                    error,
+                    RecordError::Invalid { .. } | RecordError::MissingVersion
+                ),
+                "{text}: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_result_from_the_sides() {
+        assert_eq!(
+            RecordResult::from_game_result(GameResult::FirstPlayerWon, true),
+            RecordResult::ComputerWon
+        );
+        assert_eq!(
+            RecordResult::from_game_result(GameResult::FirstPlayerWon, false),
+            RecordResult::PlayerWon
+        );
+        assert_eq!(
+            RecordResult::from_game_result(GameResult::SecondPlayerTimeout, true),
+            RecordResult::PlayerTimeout
+        );
+        assert_eq!(
+            RecordResult::from_game_result(GameResult::Draw, true),
+            RecordResult::Draw
+        );
+    }
+}
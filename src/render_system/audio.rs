@@ -0,0 +1,88 @@
+//! The sound effects of the game. They are loaded once at startup into a sound bank, that is kept on the
+//! blackboard. Playing sounds needs the *sound* feature, as the audio backend needs ALSA on Linux. Without
+//! the feature and for sound files, that can not be loaded, the game simply stays silent.
+
+#[cfg(feature = "sound")]
+use macroquad::audio::{Sound, load_sound, play_sound_once};
+
+/// The sound effects, that can be played.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundEffect {
+    /// A stone has landed in the board.
+    Drop,
+    /// The game is over.
+    Win,
+    /// A button got pressed.
+    Click,
+    /// The player tried a move, that is not possible.
+    IllegalMove,
+}
+
+/// All sound effects in the order they are kept in the bank.
+#[cfg(feature = "sound")]
+const SOUND_EFFECTS: [SoundEffect; 4] = [
+    SoundEffect::Drop,
+    SoundEffect::Win,
+    SoundEffect::Click,
+    SoundEffect::IllegalMove,
+];
+
+#[cfg(feature = "sound")]
+impl SoundEffect {
+    /// Gets the file the sound gets loaded from, relative to the working directory.
+    pub fn get_path(self) -> &'static str {
+        match self {
+            SoundEffect::Drop => "assets/drop.wav",
+            SoundEffect::Win => "assets/win.wav",
+            SoundEffect::Click => "assets/click.wav",
+            SoundEffect::IllegalMove => "assets/illegal.wav",
+        }
+    }
+}
+
+/// The loaded sound effects, a sound, that could not be loaded, is missing and stays silent.
+#[derive(Default)]
+pub struct SoundBank {
+    #[cfg(feature = "sound")]
+    sounds: Vec<Option<Sound>>,
+}
+
+impl SoundBank {
+    /// Loads all the sound effects. Files, that can not be loaded, are reported and skipped.
+    #[cfg(feature = "sound")]
+    pub async fn load() -> SoundBank {
+        let mut sounds = Vec::with_capacity(SOUND_EFFECTS.len());
+        for effect in SOUND_EFFECTS {
+            let path = effect.get_path();
+            sounds.push(match load_sound(path).await {
+                Ok(sound) => Some(sound),
+                Err(error) => {
+                    log::warn!("The sound {path} could not be loaded: {error}");
+                    None
+                }
+            });
+        }
+        SoundBank { sounds }
+    }
+
+    /// Without the sound feature there is nothing to load.
+    #[cfg(not(feature = "sound"))]
+    pub async fn load() -> SoundBank {
+        SoundBank::default()
+    }
+
+    /// Plays the sound effect once, if it has been loaded.
+    #[cfg(feature = "sound")]
+    pub fn play(&self, effect: SoundEffect) {
+        let index = SOUND_EFFECTS
+            .iter()
+            .position(|&candidate| candidate == effect);
+        if let Some(Some(sound)) = index.and_then(|index| self.sounds.get(index)) {
+            play_sound_once(sound);
+        }
+    }
+
+    /// Without the sound feature the game stays silent.
+    #[cfg(not(feature = "sound"))]
+    pub fn play(&self, _effect: SoundEffect) {}
+}
@@ -0,0 +1,124 @@
+//! The debug HUD is meant for the development of the engine. It is toggled with F3 and shows the
+//! statistics of the latest finished iteration of the search for the computer move in a translucent panel
+//! in the upper left corner of the board. Nothing gets formatted, while the HUD is switched off.
+
+use crate::render_system::graphics::{BOARD_DRAWING_HEIGHT, print_text_sized};
+use connect_4_rust::board_logic::alpha_beta::SearchStats;
+use macroquad::prelude::*;
+
+/// The area of the panel.
+const PANEL: Rect = Rect {
+    x: 10.0,
+    y: BOARD_DRAWING_HEIGHT - 175.0,
+    w: 230.0,
+    h: 165.0,
+};
+/// The color of the panel, the board stays visible below it.
+const PANEL_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+/// The font size of the lines.
+const LINE_SIZE: u16 = 22;
+/// The distance between two lines.
+const LINE_DISTANCE: f32 = 25.0;
+
+/// Formats an amount with a metric prefix and three significant digits, the unit is appended.
+/// 1_234_567 nodes are written as "1.23 Mn".
+pub fn format_metric(value: f64, unit: &str) -> String {
+    let (scaled, prefix) = if value >= 1e9 {
+        (value / 1e9, "G")
+    } else if value >= 1e6 {
+        (value / 1e6, "M")
+    } else if value >= 1e3 {
+        (value / 1e3, "k")
+    } else {
+        return format!("{value:.0} {unit}");
+    };
+    let decimals = if scaled >= 100.0 {
+        0
+    } else if scaled >= 10.0 {
+        1
+    } else {
+        2
+    };
+    format!("{scaled:.decimals$} {prefix}{unit}")
+}
+
+/// Gets the lines of the HUD for the statistics of the search.
+pub fn get_hud_lines(stats: &SearchStats) -> [String; 6] {
+    [
+        format!("Depth {}", stats.depth),
+        format!("Nodes {}", format_metric(stats.nodes as f64, "n")),
+        format!(
+            "Speed {}",
+            format_metric(stats.get_nodes_per_second(), "n/s")
+        ),
+        format!("Table {}", format_metric(stats.table_entries as f64, "n")),
+        format!("Hits {:.1} %", stats.get_table_hit_rate() * 100.0),
+        format!("Score {:+.3}", stats.score),
+    ]
+}
+
+/// Draws the panel with the statistics of the search, none if no iteration has finished yet.
+pub fn render_debug_hud(stats: Option<&SearchStats>) {
+    draw_rectangle(PANEL.x, PANEL.y, PANEL.w, PANEL.h, PANEL_COLOR);
+    // The y axis points upwards, so the first line is at the upper edge of the panel.
+    let top_line = Vec2::new(PANEL.x + 10.0, PANEL.y + PANEL.h - LINE_DISTANCE);
+    let Some(stats) = stats else {
+        print_text_sized("No search yet", top_line, LINE_SIZE);
+        return;
+    };
+    for (index, line) in get_hud_lines(stats).iter().enumerate() {
+        print_text_sized(
+            line,
+            top_line - Vec2::new(0.0, LINE_DISTANCE * index as f32),
+            LINE_SIZE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric_prefixes() {
+        assert_eq!(format_metric(0.0, "n"), "0 n");
+        assert_eq!(format_metric(999.0, "n"), "999 n");
+        assert_eq!(format_metric(1_000.0, "n"), "1.00 kn");
+        assert_eq!(format_metric(1_234_567.0, "n"), "1.23 Mn");
+        assert_eq!(format_metric(12_345_678.0, "n/s"), "12.3 Mn/s");
+        assert_eq!(format_metric(123_456_789.0, "n/s"), "123 Mn/s");
+        assert_eq!(format_metric(4.5e9, "n"), "4.50 Gn");
+    }
+
+    #[test]
+    fn test_hud_lines() {
+        let stats = SearchStats {
+            depth: 12,
+            score: -0.25,
+            nodes: 2_500_000,
+            elapsed_time: 0.5,
+            table_entries: 40_000,
+            table_hits: 250_000,
+        };
+        assert_eq!(
+            get_hud_lines(&stats),
+            [
+                "Depth 12",
+                "Nodes 2.50 Mn",
+                "Speed 5.00 Mn/s",
+                "Table 40.0 kn",
+                "Hits 10.0 %",
+                "Score -0.250",
+            ]
+        );
+        // A search, that has not taken measurable time, has no speed and no hit rate.
+        let lines = get_hud_lines(&SearchStats {
+            nodes: 0,
+            elapsed_time: 0.0,
+            table_hits: 0,
+            ..stats
+        });
+        assert_eq!(lines[2], "Speed 0 n/s");
+        assert_eq!(lines[4], "Hits 0.0 %");
+    }
+}
@@ -1,80 +1,242 @@
 //! This module contains everything to drawing boards, stones and simple ui elements.
 
-use crate::board_logic::bit_board::BitBoard;
-use crate::board_logic::bit_board_coding::{BOARD_HEIGHT, BOARD_WIDTH};
-use crate::debug_check_board_coordinates;
+use crate::render_system::theme::get_active_theme;
+use connect_4_rust::board_logic::bit_board::GenericBitBoard;
+use connect_4_rust::board_logic::bit_board_coding::{
+    BOARD_HEIGHT, BOARD_WIDTH, BoardGeometry, StandardGeometry, WinningLine,
+};
+use connect_4_rust::debug_check_board_coordinates;
 use macroquad::prelude::*;
 
 /// The window dimension that will be used for rendering.
 pub const WINDOW_DIMENSION: f32 = 700.0;
 
-/// The radius with which we want to draw the stones in the below function.
-pub const CIRCLE_RADIUS: f32 = WINDOW_DIMENSION / BOARD_WIDTH as f32 * 0.8 * 0.5;
-
-/// Represents color types we can draw elements with.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub enum SymbolColor {
-    Brown,
-    Yellow,
-    LightYellow,
-    Blue,
-    LightBlue,
+/// The share of the width of a cell, that the stones take up.
+const STONE_SHARE: f32 = 0.8;
+
+/// The size of the glyphs on the stones in relation to the radius of the stones.
+const GLYPH_SHARE: f32 = 0.4;
+
+/// Where a board gets drawn. The board spans the width of the drawing area with square cells, so its
+/// height follows from the amount of rows. The constants and functions without a layout are the ones of
+/// the standard board, see [`STANDARD_LAYOUT`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BoardLayout {
+    /// The amount of columns.
+    pub width: u32,
+    /// The amount of rows.
+    pub height: u32,
 }
 
+impl BoardLayout {
+    /// Gets the layout for the boards of the geometry.
+    pub const fn of<G: BoardGeometry>() -> BoardLayout {
+        BoardLayout {
+            width: G::WIDTH,
+            height: G::HEIGHT,
+        }
+    }
+
+    /// Gets the edge length of a cell in drawing coordinates.
+    pub const fn get_cell_size(&self) -> f32 {
+        WINDOW_DIMENSION / self.width as f32
+    }
+
+    /// Gets the height of the board in drawing coordinates, the area above is free for ui elements.
+    pub const fn get_drawing_height(&self) -> f32 {
+        self.get_cell_size() * self.height as f32
+    }
+
+    /// Gets the radius the stones are drawn with.
+    pub const fn get_circle_radius(&self) -> f32 {
+        self.get_cell_size() * STONE_SHARE * 0.5
+    }
 
-/// Static array with colors that can be queried.
-const COLOR_ARRAY: [Color; 5] = [
-    Color::new(0.48, 0.25, 0.0, 1.0),
-    Color::new(0.75, 0.55, 0.06, 1.0),
-    Color::new(1.0, 0.91, 0.0, 1.0),
-    Color::new(0.0, 0.28, 0.67, 1.0),
-    Color::new(0.0, 0.58, 1.0, 1.0),
-];
+    /// Returns the drawing coordinates for an indicated stone position.
+    pub const fn get_drawing_coordinates(&self, x_stone: u32, y_stone: u32) -> Vec2 {
+        self.get_drawing_coordinates_fractional(x_stone as f32, y_stone as f32)
+    }
 
-/// Generates an RGB value for any of the Colors indicated.
-pub fn get_color(color: SymbolColor) -> &'static Color {
-    match color {
-        SymbolColor::Brown => &COLOR_ARRAY[0],
-        SymbolColor::Yellow => &COLOR_ARRAY[1],
-        SymbolColor::LightYellow => &COLOR_ARRAY[2],
-        SymbolColor::Blue => &COLOR_ARRAY[3],
-        SymbolColor::LightBlue => &COLOR_ARRAY[4],
+    /// Returns the drawing coordinates for a position in board coordinates, that may lie between the
+    /// cells or outside of the board, like below the lowest row.
+    pub const fn get_drawing_coordinates_fractional(&self, x_stone: f32, y_stone: f32) -> Vec2 {
+        Vec2 {
+            x: (x_stone + 0.5) * self.get_cell_size(),
+            y: (y_stone + 0.5) * self.get_cell_size(),
+        }
+    }
+
+    /// Gets the column of the board at the drawing position. Positions outside of the board, also the
+    /// ones in the area above it, have no column.
+    pub fn screen_to_column(&self, position: Vec2) -> Option<u32> {
+        if !(0.0..WINDOW_DIMENSION).contains(&position.x)
+            || !(0.0..self.get_drawing_height()).contains(&position.y)
+        {
+            return None;
+        }
+        let column = (position.x / self.get_cell_size()) as u32;
+        Some(column.min(self.width - 1))
     }
 }
 
+/// The layout of the standard board.
+pub const STANDARD_LAYOUT: BoardLayout = BoardLayout::of::<StandardGeometry>();
+
+/// The height of the board in drawing coordinates, the area above is free for ui elements.
+pub const BOARD_DRAWING_HEIGHT: f32 = STANDARD_LAYOUT.get_drawing_height();
+
+/// The radius with which we want to draw the stones in the below function.
+pub const CIRCLE_RADIUS: f32 = STANDARD_LAYOUT.get_circle_radius();
+
+pub use crate::render_system::theme::{SymbolColor, get_color};
+
 /// Returns the drawing coordinates for an indicated stone position.
 pub const fn get_drawing_coordinates(x_stone: u32, y_stone: u32) -> Vec2 {
-    Vec2 {
-        x: (x_stone as f32 + 0.5) * WINDOW_DIMENSION / BOARD_WIDTH as f32,
-        y: (y_stone as f32 + 0.5) * WINDOW_DIMENSION / BOARD_WIDTH as f32,
-    }
+    STANDARD_LAYOUT.get_drawing_coordinates(x_stone, y_stone)
+}
+
+/// Gets the column of the board at the drawing position. Positions outside of the board, also the ones
+/// in the area above it, have no column.
+pub fn screen_to_column(position: Vec2) -> Option<u32> {
+    STANDARD_LAYOUT.screen_to_column(position)
+}
+
+/// Gets the slot of the board at the drawing position. This is the column, only clicks on the bottom
+/// row come as the board width plus the column, as they may pop the stone there under the Pop Out rules.
+pub fn screen_to_slot(position: Vec2) -> Option<u32> {
+    let column = screen_to_column(position)?;
+    let row_height = BOARD_DRAWING_HEIGHT / BOARD_HEIGHT as f32;
+    Some(if position.y < row_height {
+        BOARD_WIDTH + column
+    } else {
+        column
+    })
 }
 
 /// Gets a painting position above the column.
 pub const fn get_drawing_coordinates_above_column(column: u32) -> Vec2 {
     // One column above the maximum.
-    get_drawing_coordinates(column, 7)
+    get_drawing_coordinates(column, BOARD_HEIGHT + 1)
+}
+
+/// Gets the factor, by which the drawing area gets scaled up to fit into the window.
+pub fn get_layout_scale() -> f32 {
+    screen_width().min(screen_height()) / WINDOW_DIMENSION
 }
 
-/// Renders the board as is with all the stones in there.
-pub fn render_board(board: &BitBoard, board_texture: &Texture2D) {
-    draw_texture(board_texture, 0.0, 0.0, WHITE);
+/// Creates the camera, that shows the drawing area centered in the window without distorting it. The
+/// window may be larger in one direction, the margin stays empty then. The origin is in the lower left
+/// corner of the drawing area.
+pub fn create_layout_camera() -> Camera2D {
+    Camera2D::from_display_rect(get_visible_rect())
+}
 
+/// Gets the part of the layout coordinates, that is visible in the window, including the margin.
+fn get_visible_rect() -> Rect {
+    let visible_size = Vec2::new(screen_width(), screen_height()) / get_layout_scale();
+    let margin = 0.5 * (visible_size - Vec2::splat(WINDOW_DIMENSION));
+    Rect::new(-margin.x, -margin.y, visible_size.x, visible_size.y)
+}
+
+/// Creates the texture, that keeps the last picture of the screen, at the resolution of the window. It
+/// has to be created anew, when the size of the window changes.
+pub fn create_frame_cache() -> RenderTarget {
+    let dpi_scale = screen_dpi_scale();
+    let frame_cache = render_target(
+        (screen_width() * dpi_scale).ceil() as u32,
+        (screen_height() * dpi_scale).ceil() as u32,
+    );
+    frame_cache.texture.set_filter(FilterMode::Linear);
+    frame_cache
+}
+
+/// Draws the kept picture over the whole window with the layout camera, the camera has to be set.
+pub fn draw_frame_cache(frame_cache: &RenderTarget) {
+    let visible_rect = get_visible_rect();
+    draw_texture_ex(
+        &frame_cache.texture,
+        visible_rect.x,
+        visible_rect.y,
+        WHITE,
+        DrawTextureParams {
+            dest_size: Some(visible_rect.size()),
+            ..Default::default()
+        },
+    );
+}
+
+/// Renders the board as is with all the stones in there and the column labels. The layout follows from
+/// the geometry of the board. The board itself is only drawn, once its texture has been created, which
+/// has to be the one of the same layout.
+pub fn render_board<G: BoardGeometry>(
+    board: &GenericBitBoard<G>,
+    board_texture: Option<&Texture2D>,
+) {
+    let layout = BoardLayout::of::<G>();
+    // The texture may have a higher resolution than the drawing area.
+    if let Some(board_texture) = board_texture {
+        draw_texture_ex(
+            board_texture,
+            0.0,
+            0.0,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(Vec2::new(WINDOW_DIMENSION, layout.get_drawing_height())),
+                ..Default::default()
+            },
+        );
+    }
+
+    let radius = layout.get_circle_radius();
     for (x, y, first) in board.get_board_positioning() {
-        debug_check_board_coordinates!(x, y);
         let color = if first {
             get_color(SymbolColor::Yellow)
         } else {
             get_color(SymbolColor::Blue)
         };
-        let draw_pos = get_drawing_coordinates(x, y);
-        draw_circle(draw_pos.x, draw_pos.y, CIRCLE_RADIUS, *color);
+        let draw_pos = layout.get_drawing_coordinates(x, y);
+        draw_circle(draw_pos.x, draw_pos.y, radius, *color);
+        draw_stone_glyph(draw_pos, radius * GLYPH_SHARE, first);
+    }
+    render_column_labels(&layout);
+}
+
+/// The size of the glyphs on the stones of the standard board.
+pub const GLYPH_SIZE: f32 = CIRCLE_RADIUS * GLYPH_SHARE;
+
+/// Gets the endpoints of the two strokes of the cross drawn on the stones of the second player.
+pub fn get_cross_glyph_strokes(position: Vec2, glyph_size: f32) -> [(Vec2, Vec2); 2] {
+    [
+        (
+            position + Vec2::new(-glyph_size, -glyph_size),
+            position + Vec2::new(glyph_size, glyph_size),
+        ),
+        (
+            position + Vec2::new(-glyph_size, glyph_size),
+            position + Vec2::new(glyph_size, -glyph_size),
+        ),
+    ]
+}
+
+/// Draws the glyph of the player on the stone at the position, if the active theme has glyphs. The first
+/// player gets a ring, the second one a cross.
+fn draw_stone_glyph(position: Vec2, glyph_size: f32, is_first_player: bool) {
+    let Some(color) = get_active_theme().glyph else {
+        return;
+    };
+    let thickness = glyph_size * 0.3;
+    if is_first_player {
+        draw_circle_lines(position.x, position.y, glyph_size, thickness, color);
+    } else {
+        for (start, end) in get_cross_glyph_strokes(position, glyph_size) {
+            draw_line(start.x, start.y, end.x, end.y, thickness, color);
+        }
     }
 }
 
 /// Renders the indicated stones into the stone array with highlighted color. Indicates
 /// if this is the first player who is winning to pick the right color.
-pub fn render_winning_stones(is_first_player_winning: bool, list_of_positions: &Vec<(u32, u32)>) {
+pub fn render_winning_stones(is_first_player_winning: bool, list_of_positions: &[(u32, u32)]) {
     let color = get_color(if is_first_player_winning {
         SymbolColor::LightYellow
     } else {
@@ -84,9 +246,82 @@ pub fn render_winning_stones(is_first_player_winning: bool, list_of_positions: &
     for (column, row) in list_of_positions {
         let draw_pos = get_drawing_coordinates(*column, *row);
         draw_circle(draw_pos.x, draw_pos.y, CIRCLE_RADIUS, *color);
+        draw_stone_glyph(draw_pos, GLYPH_SIZE, is_first_player_winning);
     }
 }
 
+/// The width of the stroke connecting the winning stones in relation to the radius of the stones.
+const WINNING_STROKE_SHARE: f32 = 0.4;
+
+/// Gets the drawing coordinates of the centers of the first and the last stone of the winning line.
+pub fn get_winning_stroke_endpoints(line: &WinningLine, layout: &BoardLayout) -> (Vec2, Vec2) {
+    let (first_column, first_row) = line.cells[0];
+    let (last_column, last_row) = line.cells[line.cells.len() - 1];
+    (
+        layout.get_drawing_coordinates(first_column, first_row),
+        layout.get_drawing_coordinates(last_column, last_row),
+    )
+}
+
+/// Draws a thick stroke with rounded ends through the stones of the winning line, so that the win can not
+/// be overlooked. The color contrasts the stones of the winning player.
+pub fn render_winning_stroke(
+    line: &WinningLine,
+    is_first_player_winning: bool,
+    layout: &BoardLayout,
+) {
+    let color = *get_color(if is_first_player_winning {
+        SymbolColor::LightBlue
+    } else {
+        SymbolColor::LightYellow
+    });
+    let width = layout.get_circle_radius() * WINNING_STROKE_SHARE;
+    let (start, end) = get_winning_stroke_endpoints(line, layout);
+    draw_line(start.x, start.y, end.x, end.y, width, color);
+    for point in [start, end] {
+        draw_circle(point.x, point.y, width * 0.5, color);
+    }
+}
+
+/// The radius of the ring marking the last move.
+const LAST_MOVE_MARKER_RADIUS: f32 = CIRCLE_RADIUS * 0.75;
+
+/// The thickness of the ring marking the last move.
+const LAST_MOVE_MARKER_THICKNESS: f32 = CIRCLE_RADIUS * 0.1;
+
+/// Draws a thin ring in the text color around the stone of the coded move, so that the last move can be spotted.
+pub fn render_last_move_marker(coded_move: u64) {
+    for (column, row) in StandardGeometry::get_position_iterator(coded_move) {
+        debug_check_board_coordinates!(column, row);
+        let draw_pos = get_drawing_coordinates(column, row);
+        draw_circle_lines(
+            draw_pos.x,
+            draw_pos.y,
+            LAST_MOVE_MARKER_RADIUS,
+            LAST_MOVE_MARKER_THICKNESS,
+            get_active_theme().text,
+        );
+    }
+}
+
+/// The color a column flashes in, when the player tries to drop a stone into it, while it is full.
+const COLUMN_FLASH_COLOR: Color = Color::new(0.9, 0.1, 0.1, 1.0);
+
+/// Draws a red tint over the whole height of the column with the indicated opacity.
+pub fn render_column_flash(column: u32, alpha: f32) {
+    debug_check_board_coordinates!(col: column);
+    let column_width = WINDOW_DIMENSION / BOARD_WIDTH as f32;
+    let mut color = COLUMN_FLASH_COLOR;
+    color.a = alpha.clamp(0.0, 1.0);
+    draw_rectangle(
+        column as f32 * column_width,
+        0.0,
+        column_width,
+        BOARD_DRAWING_HEIGHT,
+        color,
+    );
+}
+
 /// Draws the stone at the indicated coordinates, this is meant for drawing an animated stone.
 pub fn draw_stone_at_coordinates(position: Vec2, is_first_player: bool) {
     let color = get_color(if is_first_player {
@@ -96,26 +331,218 @@ pub fn draw_stone_at_coordinates(position: Vec2, is_first_player: bool) {
     });
 
     draw_circle(position.x, position.y, CIRCLE_RADIUS, *color);
+    draw_stone_glyph(position, GLYPH_SIZE, is_first_player);
 }
 
+/// Draws a semi-transparent stone at the indicated coordinates, this is meant for previews like hints.
+/// The alpha value is the opacity of the stone.
+pub fn draw_ghost_stone_at_coordinates(position: Vec2, is_first_player: bool, alpha: f32) {
+    let mut color = *get_color(if is_first_player {
+        SymbolColor::LightYellow
+    } else {
+        SymbolColor::LightBlue
+    });
+    color.a = alpha;
+
+    draw_circle(position.x, position.y, CIRCLE_RADIUS, color);
+}
+
+/// The font size of the standard text.
+pub const TEXT_SIZE: u16 = 50;
+
+/// The font size of small text like the score.
+pub const SMALL_TEXT_SIZE: u16 = 30;
 
 /// A standardized way on how to write text in the game.
 pub fn print_text(text: &str, position: Vec2) {
+    print_text_sized(text, position, TEXT_SIZE);
+}
+
+/// Writes text like [`print_text`] does, but with the indicated font size. The font gets rasterized
+/// in the size it has in the window, so that it stays sharp, when the window gets enlarged.
+pub fn print_text_sized(text: &str, position: Vec2, font_size: u16) {
+    print_text_colored(text, position, font_size, get_active_theme().text);
+}
+
+/// Writes text like [`print_text_sized`] does, but in the indicated color.
+pub fn print_text_colored(text: &str, position: Vec2, font_size: u16, color: Color) {
+    let scale = get_layout_scale();
     draw_text_ex(
         text,
         position.x,
         position.y,
         TextParams {
             font: None,
-            font_size: 50,
-            font_scale: -1.0,
+            font_size: (font_size as f32 * scale).round() as u16,
+            font_scale: -1.0 / scale,
             font_scale_aspect: -1.0,
             rotation: 0.0,
-            color: WHITE,
+            color,
         },
     );
 }
 
+/// Measures the size the text has in drawing coordinates with the standard font size.
+pub fn measure_game_text(text: &str) -> Vec2 {
+    measure_game_text_sized(text, TEXT_SIZE)
+}
+
+/// Measures the size the text has in drawing coordinates with the indicated font size.
+pub fn measure_game_text_sized(text: &str, font_size: u16) -> Vec2 {
+    let dimensions = measure_text(text, None, font_size, 1.0);
+    Vec2::new(dimensions.width, dimensions.height)
+}
+
+/// Gets the position to print a text of the measured size at, so that it is centered on the indicated
+/// center. As the camera inverts the y axis, the text extends upwards from the position.
+pub fn get_centered_text_position(center: Vec2, text_size: Vec2) -> Vec2 {
+    center - 0.5 * text_size
+}
+
+/// The center of the title of the screens, that are not showing the board.
+pub const TITLE_CENTER: Vec2 = Vec2 {
+    x: 0.5 * WINDOW_DIMENSION,
+    y: 590.0,
+};
+
+/// Writes the text with the standard font size centered on the indicated position.
+pub fn print_text_centered(text: &str, center: Vec2) {
+    print_text(
+        text,
+        get_centered_text_position(center, measure_game_text(text)),
+    );
+}
+
+/// Writes the text with the indicated font size centered on the indicated position.
+pub fn print_text_centered_sized(text: &str, center: Vec2, font_size: u16) {
+    let position = get_centered_text_position(center, measure_game_text_sized(text, font_size));
+    print_text_sized(text, position, font_size);
+}
+
+/// The font size of the column labels.
+const COLUMN_LABEL_SIZE: u16 = 20;
+
+/// Draws faint numbers from 1 on under the columns, they are the numbers used in move strings.
+pub fn render_column_labels(layout: &BoardLayout) {
+    let mut color = get_active_theme().text;
+    color.a = 0.5;
+    for column in 0..layout.width {
+        let label = (column + 1).to_string();
+        // The labels sit in the frame below the lowest row of holes.
+        let center = layout.get_drawing_coordinates_fractional(column as f32, -0.45);
+        let position =
+            get_centered_text_position(center, measure_game_text_sized(&label, COLUMN_LABEL_SIZE));
+        print_text_colored(&label, position, COLUMN_LABEL_SIZE, color);
+    }
+}
+
+/// The width of the evaluation bar at the right edge of the board.
+const EVALUATION_BAR_WIDTH: f32 = 10.0;
+
+/// Draws the evaluation bar at the right edge of the board. The share of the first player is drawn in
+/// yellow from the bottom, the rest in blue.
+pub fn render_evaluation_bar(first_player_share: f32) {
+    let x = WINDOW_DIMENSION - EVALUATION_BAR_WIDTH;
+    let first_height = first_player_share.clamp(0.0, 1.0) * BOARD_DRAWING_HEIGHT;
+    draw_rectangle(
+        x,
+        0.0,
+        EVALUATION_BAR_WIDTH,
+        first_height,
+        *get_color(SymbolColor::Yellow),
+    );
+    draw_rectangle(
+        x,
+        first_height,
+        EVALUATION_BAR_WIDTH,
+        BOARD_DRAWING_HEIGHT - first_height,
+        *get_color(SymbolColor::Blue),
+    );
+}
+
+/// Gets the point of a line graph in the area. The indices of the values spread over the width, the value
+/// range over the height. Values outside of the range are clamped to the border of the area.
+pub fn get_graph_point(
+    area: Rect,
+    index: usize,
+    count: usize,
+    value: f32,
+    value_range: (f32, f32),
+) -> Vec2 {
+    let x_share = if count > 1 {
+        index as f32 / (count - 1) as f32
+    } else {
+        0.5
+    };
+    let (min_value, max_value) = value_range;
+    let y_share = ((value - min_value) / (max_value - min_value)).clamp(0.0, 1.0);
+    Vec2::new(area.x + x_share * area.w, area.y + y_share * area.h)
+}
+
+/// Draws the values as a line graph scaled into the area, every value gets a dot. Missing values leave a
+/// gap, so that a graph can be drawn, while its values are still coming in.
+pub fn draw_scaled_polyline(
+    values: &[Option<f32>],
+    area: Rect,
+    value_range: (f32, f32),
+    thickness: f32,
+    color: Color,
+) {
+    let get_point =
+        |index: usize, value: f32| get_graph_point(area, index, values.len(), value, value_range);
+    for (index, pair) in values.windows(2).enumerate() {
+        if let [Some(start), Some(end)] = *pair {
+            let start = get_point(index, start);
+            let end = get_point(index + 1, end);
+            draw_line(start.x, start.y, end.x, end.y, thickness, color);
+        }
+    }
+    for (index, value) in values.iter().enumerate() {
+        if let Some(value) = *value {
+            let point = get_point(index, value);
+            draw_circle(point.x, point.y, thickness, color);
+        }
+    }
+}
+
+/// Draws a rectangular button with the label centered on it. Buttons, that can not be used, are greyed out.
+pub fn draw_button(button: Rect, label: &str, font_size: u16, is_enabled: bool) {
+    let color = get_color(if is_enabled {
+        SymbolColor::Brown
+    } else {
+        SymbolColor::Grey
+    });
+    draw_rectangle(button.x, button.y, button.w, button.h, *color);
+    print_text_centered_sized(label, button.center(), font_size);
+}
+
+/// The amount of teeth of the gear symbol.
+const GEAR_TEETH: u32 = 8;
+
+/// Draws a gear symbol with the indicated outer radius, it marks the button for the settings.
+pub fn draw_gear(center: Vec2, radius: f32) {
+    let color = *get_color(SymbolColor::Grey);
+    let tooth_size = radius * 0.25;
+    for tooth in 0..GEAR_TEETH {
+        let angle = tooth as f32 * std::f32::consts::TAU / GEAR_TEETH as f32;
+        let tooth_center = center + Vec2::from_angle(angle) * (radius - tooth_size);
+        draw_poly(
+            tooth_center.x,
+            tooth_center.y,
+            4,
+            tooth_size,
+            angle.to_degrees() + 45.0,
+            color,
+        );
+    }
+    draw_circle(center.x, center.y, radius - tooth_size, color);
+    draw_circle(
+        center.x,
+        center.y,
+        radius * 0.3,
+        get_active_theme().background,
+    );
+}
 
 /// Creates an internal material for the offscreen texture of the game board.
 /// Simply paints black with an alpha of zero and replaces the content.
@@ -158,15 +585,19 @@ fn create_cutout_material() -> Material {
     .unwrap()
 }
 
-/// Creates the board texture with holes. Is done once and can then be reused for the remainder of the game.
-pub fn create_board_texture() -> Texture2D {
-    let board_height = WINDOW_DIMENSION * (6.0 / 7.0);
-    let render_target = render_target(WINDOW_DIMENSION as u32, board_height as u32);
+/// Creates the texture with holes for the board of the layout at the indicated layout scale. It can be
+/// reused until the window grows larger than the resolution of the texture.
+pub fn create_board_texture(layout: &BoardLayout, scale: f32) -> Texture2D {
+    let drawing_height = layout.get_drawing_height();
+    let render_target = render_target(
+        (WINDOW_DIMENSION * scale).ceil() as u32,
+        (drawing_height * scale).ceil() as u32,
+    );
     render_target.texture.set_filter(FilterMode::Linear);
 
     // Set render target.
     let mut target_cam =
-        Camera2D::from_display_rect(Rect::new(0.0, 0.0, WINDOW_DIMENSION, board_height));
+        Camera2D::from_display_rect(Rect::new(0.0, 0.0, WINDOW_DIMENSION, drawing_height));
     target_cam.render_target = Some(render_target.clone());
     set_camera(&target_cam);
 
@@ -178,10 +609,10 @@ pub fn create_board_texture() -> Texture2D {
     gl_use_material(&cutout_material);
 
     // 3. Create wholes
-    for row in 0..6 {
-        for col in 0..7 {
-            let pos = get_drawing_coordinates(col, row);
-            draw_circle(pos.x, pos.y, CIRCLE_RADIUS, WHITE);
+    for row in 0..layout.height {
+        for col in 0..layout.width {
+            let pos = layout.get_drawing_coordinates(col, row);
+            draw_circle(pos.x, pos.y, layout.get_circle_radius(), WHITE);
         }
     }
 
@@ -193,3 +624,154 @@ pub fn create_board_texture() -> Texture2D {
 
     render_target.texture
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_at_the_boundaries() {
+        let cell_size = WINDOW_DIMENSION / BOARD_WIDTH as f32;
+        let column_at = |x: f32| screen_to_column(Vec2::new(x, 100.0));
+        assert_eq!(column_at(0.0), Some(0));
+        assert_eq!(column_at(cell_size - 0.01), Some(0));
+        // A position exactly on a boundary belongs to the column right of it.
+        assert_eq!(column_at(cell_size), Some(1));
+        assert_eq!(column_at(3.0 * cell_size), Some(3));
+        assert_eq!(column_at(WINDOW_DIMENSION - 0.01), Some(BOARD_WIDTH - 1));
+        assert_eq!(column_at(WINDOW_DIMENSION), None);
+        assert_eq!(column_at(-0.01), None);
+        assert_eq!(column_at(f32::NAN), None);
+    }
+
+    #[test]
+    fn test_column_outside_of_the_board() {
+        let x = 350.0;
+        assert_eq!(screen_to_column(Vec2::new(x, 0.0)), Some(3));
+        assert_eq!(
+            screen_to_column(Vec2::new(x, BOARD_DRAWING_HEIGHT - 0.01)),
+            Some(3)
+        );
+        // The area of the buttons above the board has no column.
+        assert_eq!(screen_to_column(Vec2::new(x, BOARD_DRAWING_HEIGHT)), None);
+        assert_eq!(screen_to_column(Vec2::new(x, -1.0)), None);
+    }
+
+    #[test]
+    fn test_bottom_row_gets_the_pop_slot() {
+        let row_height = BOARD_DRAWING_HEIGHT / BOARD_HEIGHT as f32;
+        assert_eq!(
+            screen_to_slot(Vec2::new(50.0, row_height - 0.01)),
+            Some(BOARD_WIDTH)
+        );
+        assert_eq!(screen_to_slot(Vec2::new(650.0, row_height)), Some(6));
+        assert_eq!(screen_to_slot(Vec2::new(750.0, 10.0)), None);
+        for column in 0..BOARD_WIDTH {
+            for row in 0..BOARD_HEIGHT {
+                let slot = screen_to_slot(get_drawing_coordinates(column, row));
+                let expected = if row == 0 {
+                    BOARD_WIDTH + column
+                } else {
+                    column
+                };
+                assert_eq!(slot, Some(expected));
+            }
+        }
+    }
+
+    /// Gets the endpoints of the stroke through the line of the winning stones at the positions.
+    fn get_endpoints(cells: &[(u32, u32)]) -> Vec<(Vec2, Vec2)> {
+        let board = cells.iter().fold(0, |board, &(x, y)| {
+            board | StandardGeometry::get_bit_representation(x, y)
+        });
+        StandardGeometry::get_winning_lines(board)
+            .iter()
+            .map(|line| get_winning_stroke_endpoints(line, &STANDARD_LAYOUT))
+            .collect()
+    }
+
+    #[test]
+    fn test_stroke_runs_from_the_first_to_the_last_stone() {
+        let stroke = |start: (u32, u32), end: (u32, u32)| {
+            (
+                get_drawing_coordinates(start.0, start.1),
+                get_drawing_coordinates(end.0, end.1),
+            )
+        };
+        // The cells are 100 wide, the centers lie in the middle of them.
+        assert_eq!(
+            stroke((0, 0), (3, 0)),
+            (Vec2::new(50.0, 50.0), Vec2::new(350.0, 50.0))
+        );
+        assert_eq!(
+            get_endpoints(&[(1, 0), (2, 0), (3, 0), (4, 0)]),
+            [stroke((1, 0), (4, 0))]
+        );
+        assert_eq!(
+            get_endpoints(&[(6, 2), (6, 3), (6, 4), (6, 5)]),
+            [stroke((6, 2), (6, 5))]
+        );
+        assert_eq!(
+            get_endpoints(&[(2, 1), (3, 2), (4, 3), (5, 4)]),
+            [stroke((2, 1), (5, 4))]
+        );
+        // The falling diagonal starts with its lowest stone.
+        assert_eq!(
+            get_endpoints(&[(0, 5), (1, 4), (2, 3), (3, 2)]),
+            [stroke((3, 2), (0, 5))]
+        );
+    }
+
+    #[test]
+    fn test_every_line_gets_its_own_stroke() {
+        let mut endpoints =
+            get_endpoints(&[(0, 0), (1, 0), (2, 0), (3, 0), (3, 1), (3, 2), (3, 3)]);
+        endpoints.sort_by(|a, b| a.1.y.total_cmp(&b.1.y));
+        assert_eq!(
+            endpoints,
+            [
+                (get_drawing_coordinates(0, 0), get_drawing_coordinates(3, 0)),
+                (get_drawing_coordinates(3, 0), get_drawing_coordinates(3, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_labels_sit_below_the_holes() {
+        for column in 0..BOARD_WIDTH {
+            let label = STANDARD_LAYOUT.get_drawing_coordinates_fractional(column as f32, -0.45);
+            let hole = get_drawing_coordinates(column, 0);
+            assert_eq!(label.x, hole.x);
+            assert!(label.y > 0.0 && label.y < hole.y - CIRCLE_RADIUS, "{label}");
+        }
+    }
+
+    /// Measures the text like a font, where every character is 12 wide and the line is 20 high.
+    fn measure_mocked(text: &str) -> Vec2 {
+        Vec2::new(12.0 * text.chars().count() as f32, 20.0)
+    }
+
+    #[test]
+    fn test_text_is_centered_on_the_position() {
+        let center = Vec2::new(200.0, 300.0);
+        assert_eq!(
+            get_centered_text_position(center, measure_mocked("Draw")),
+            Vec2::new(176.0, 290.0)
+        );
+        assert_eq!(get_centered_text_position(center, Vec2::ZERO), center);
+        // The text extends upwards from the position, as the camera inverts the y axis.
+        let size = measure_mocked("Yellow wins!");
+        let position = get_centered_text_position(center, size);
+        assert_eq!(position + 0.5 * size, center);
+    }
+
+    #[test]
+    fn test_title_is_centered_in_the_window() {
+        let title = "Welcome to Connect Four";
+        let size = measure_mocked(title);
+        let position = get_centered_text_position(TITLE_CENTER, size);
+        let right_margin = WINDOW_DIMENSION - (position.x + size.x);
+        assert_eq!(position.x, right_margin);
+        assert_eq!(position.x, 212.0);
+    }
+}
@@ -1,6 +1,16 @@
 //! This module contains everything that has something to do with rendering, animation and UI
 //! in the widest sense.
 
+pub mod audio;
+
+pub mod debug_hud;
+
 pub mod graphics;
 
 pub mod stone_animator;
+
+pub mod svg;
+
+pub mod theme;
+
+pub mod toast;
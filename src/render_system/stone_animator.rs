@@ -1,67 +1,234 @@
-//! This module is in charge of dropping stones into the game board.
+//! This module is in charge of dropping stones into the game board. The stone falls with gravitational
+//! acceleration and bounces up a little, when it lands, before it settles in its cell.
 
-use crate::board_logic::bit_board::BitBoard;
-use crate::board_logic::bit_board_coding::BOARD_WIDTH;
-use crate::debug_check_board_coordinates;
 use crate::render_system::graphics::*;
+use connect_4_rust::board_logic::bit_board::BitBoard;
+use connect_4_rust::board_logic::bit_board_coding::BOARD_WIDTH;
+use connect_4_rust::debug_check_board_coordinates;
 use macroquad::prelude::Vec2;
 
 /// An animator that takes care on animating a stone into the drawing arena.
 /// It can render itself and update itself and indicates if it is finished or not.
+/// The position is computed from the time passed since the start, so that the animation looks the same at
+/// any frame rate.
 pub struct StoneAnimator {
-    remaining_way_length: f32,
     current_position: Vec2,
+    /// The height the stone starts falling at.
+    start_height: f32,
+    /// The height the stone comes to rest at.
+    destination_height: f32,
+    /// The time passed since the start of the animation, scaled with the speed multiplier.
+    elapsed_time: f32,
+    /// The multiplier for the speed of the animation.
+    speed_multiplier: f32,
     is_animating: bool,
     first_player: bool,
 }
 
-/// The velocity the stone falls down with.
-const FALLING_VELOCITY: f32 = 700.00;
+/// The acceleration of the falling stone at normal speed, a drop over the full height takes about a second.
+const GRAVITY: f32 = 1400.0;
+
+/// The height the stone bounces up after landing in relation to its radius.
+const BOUNCE_HEIGHT: f32 = 0.15 * CIRCLE_RADIUS;
+
+/// The time the bounce takes at normal speed.
+const BOUNCE_TIME: f32 = 0.15;
+
+/// The maximum time a single update may advance the animation, so that a hiccup of the frame rate
+/// does not let the stone jump.
+const MAX_DELTA_TIME: f32 = 1.0 / 30.0;
 
 impl StoneAnimator {
     pub fn new() -> StoneAnimator {
         StoneAnimator {
-            remaining_way_length: 0.0,
+            current_position: Vec2::new(0.0, 0.0),
+            start_height: 0.0,
+            destination_height: 0.0,
+            elapsed_time: 0.0,
+            speed_multiplier: 1.0,
             is_animating: false,
             first_player: false,
-            current_position: Vec2::new(0.0, 0.0),
         }
     }
 
     /// Starts animating a stone. Needs the board to find out where to go to in height, the column where to animate,
-    /// and an indication if this is the computer player to determine the color.
-    pub fn start_animating(&mut self, board: &BitBoard, column: u32, is_computer: bool) {
+    /// an indication if this is the computer player to determine the color and the multiplier for the falling
+    /// velocity.
+    pub fn start_animating(
+        &mut self,
+        board: &BitBoard,
+        column: u32,
+        is_computer: bool,
+        speed_multiplier: f32,
+    ) {
         debug_assert!(
             !self.is_animating,
             "Cannot start animating while animating."
         );
         debug_check_board_coordinates!(col: column);
         self.first_player = is_computer == board.get_computer_first();
-        let height_chosen = board
-            .get_move_destination(column)
-            .expect("The column handed over does not present a legal move.");
+        let height_chosen = board.get_move_destination(column).unwrap_or_else(|| {
+            panic!("The column {column} handed over does not present a legal move:\n{board}")
+        });
         self.current_position = get_drawing_coordinates_above_column(column);
-        let destination = get_drawing_coordinates(column, height_chosen);
-        self.remaining_way_length = self.current_position[1] - destination[1];
+        self.start_height = self.current_position.y;
+        self.destination_height = get_drawing_coordinates(column, height_chosen).y;
+        self.elapsed_time = 0.0;
+        self.speed_multiplier = speed_multiplier;
         self.is_animating = true;
     }
 
+    /// Fast-forwards the animation, the stone snaps to its destination and the animation is over.
+    pub fn finish_now(&mut self) {
+        debug_assert!(self.is_animating, "Only finish during animation.");
+        self.current_position.y = self.destination_height;
+        self.is_animating = false;
+    }
+
+    /// Stops the animation without letting the stone land, the stone is not drawn any more. This is for
+    /// a state, that gets left, while the stone is still falling.
+    pub fn stop(&mut self) {
+        self.is_animating = false;
+    }
+
     /// Draws the stone at the current position with the graphics painter handed over.
     pub fn draw(&self) {
         draw_stone_at_coordinates(self.current_position, self.first_player);
     }
 
-    /// Updates the animation and moves the stone downwards.
+    /// Gets the time at normal speed the stone needs to fall down to its destination.
+    fn get_landing_time(&self) -> f32 {
+        (2.0 * (self.start_height - self.destination_height) / GRAVITY).sqrt()
+    }
+
+    /// Updates the animation, the stone gets accelerated downwards until it lands and bounces afterwards.
+    /// The time passed is clamped, so that the stone does not jump after a hiccup.
     pub fn update(&mut self, delta_time: f32) {
         debug_assert!(self.is_animating, "Only update during animation.");
-        let delta_way = -delta_time * FALLING_VELOCITY;
-        self.remaining_way_length += delta_way;
-        self.is_animating = self.remaining_way_length >= -delta_way;
-        self.current_position[1] += delta_way;
+        self.elapsed_time += delta_time.clamp(0.0, MAX_DELTA_TIME) * self.speed_multiplier;
+
+        let landing_time = self.get_landing_time();
+        let bounce_progress = (self.elapsed_time - landing_time) / BOUNCE_TIME;
+        self.current_position.y = if bounce_progress < 0.0 {
+            // The y axis points upwards, so falling reduces the height.
+            let fallen_way = 0.5 * GRAVITY * self.elapsed_time * self.elapsed_time;
+            (self.start_height - fallen_way).max(self.destination_height)
+        } else if bounce_progress < 1.0 {
+            // A parabolic hop, that peaks at the bounce height in the middle.
+            self.destination_height
+                + 4.0 * BOUNCE_HEIGHT * bounce_progress * (1.0 - bounce_progress)
+        } else {
+            self.is_animating = false;
+            self.destination_height
+        };
     }
 
-    /// Checks if we are still animating.
+    /// Checks if we are still animating, this includes the bounce after landing.
     pub fn is_animating(&self) -> bool {
         self.is_animating
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stone_lands_on_the_board() {
+        let mut board = BitBoard::new();
+        board.play(3).unwrap();
+        let mut animator = StoneAnimator::new();
+        animator.start_animating(&board, 3, true, 1.0);
+        assert_eq!(
+            animator.current_position,
+            get_drawing_coordinates_above_column(3)
+        );
+        animator.finish_now();
+        assert_eq!(animator.current_position, get_drawing_coordinates(3, 1));
+        assert!(!animator.is_animating());
+    }
+
+    #[test]
+    fn test_long_pause_does_not_let_the_stone_jump() {
+        let mut animator = StoneAnimator::new();
+        animator.start_animating(&BitBoard::new(), 0, false, 1.0);
+        animator.update(10.0);
+        assert!(animator.is_animating());
+        assert_eq!(animator.elapsed_time, MAX_DELTA_TIME);
+        assert!(animator.current_position.y > get_drawing_coordinates(0, 0).y);
+    }
+
+    /// Gets the frames the animation of a stone dropped into the empty column takes at the speed.
+    fn count_frames(speed_multiplier: f32) -> u32 {
+        let mut animator = StoneAnimator::new();
+        animator.start_animating(&BitBoard::new(), 2, false, speed_multiplier);
+        let mut frames = 0;
+        while animator.is_animating() {
+            animator.update(1.0 / 60.0);
+            frames += 1;
+        }
+        assert_eq!(animator.current_position, get_drawing_coordinates(2, 0));
+        frames
+    }
+
+    #[test]
+    fn test_faster_animation_takes_fewer_frames() {
+        let normal = count_frames(1.0);
+        let fast = count_frames(2.0);
+        assert!(normal > 30, "{normal}");
+        assert!(fast.abs_diff(normal / 2) <= 1, "{fast} {normal}");
+    }
+
+    /// Animates a stone dropped into the empty fourth column with the frame times repeated, until it
+    /// has settled. Returns the lowest height the stone has been drawn at and the final position.
+    fn animate_with(frame_times: &[f32]) -> (f32, Vec2) {
+        let mut animator = StoneAnimator::new();
+        animator.start_animating(&BitBoard::new(), 3, true, 1.0);
+        let mut lowest = animator.current_position.y;
+        for &frame_time in frame_times.iter().cycle() {
+            if !animator.is_animating() {
+                break;
+            }
+            animator.update(frame_time);
+            lowest = lowest.min(animator.current_position.y);
+        }
+        (lowest, animator.current_position)
+    }
+
+    #[test]
+    fn test_pathological_frame_times_end_the_same() {
+        let destination = get_drawing_coordinates(3, 0);
+        for frame_times in [
+            &[1.0 / 60.0][..],
+            &[1.0 / 1200.0],
+            &[0.5],
+            &[1.0 / 60.0, 0.5, 1.0 / 240.0],
+            &[0.0, 1.0 / 1200.0, 0.25],
+        ] {
+            let (lowest, position) = animate_with(frame_times);
+            assert_eq!(position, destination, "{frame_times:?}");
+            // The stone never falls through its cell.
+            assert!(lowest >= destination.y, "{frame_times:?}");
+        }
+    }
+
+    #[test]
+    fn test_position_only_depends_on_the_time_passed() {
+        let mut slow = StoneAnimator::new();
+        let mut fast = StoneAnimator::new();
+        slow.start_animating(&BitBoard::new(), 3, true, 1.0);
+        fast.start_animating(&BitBoard::new(), 3, true, 1.0);
+        for _ in 0..20 {
+            slow.update(1.0 / 60.0);
+            for _ in 0..20 {
+                fast.update(1.0 / 1200.0);
+            }
+            assert!(
+                (slow.current_position.y - fast.current_position.y).abs() < 0.01,
+                "{} {}",
+                slow.current_position,
+                fast.current_position
+            );
+        }
+    }
+}
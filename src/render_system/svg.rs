@@ -0,0 +1,195 @@
+//! Exports a board position as a standalone SVG picture. Unlike a screenshot it does not need the GPU,
+//! so it works headless, and it stays sharp in documentation and bug reports. The picture uses the
+//! geometry of [`get_drawing_coordinates`] and the colors of the active theme, so it looks like the board
+//! on the screen.
+
+use crate::render_system::graphics::{
+    BOARD_DRAWING_HEIGHT, CIRCLE_RADIUS, GLYPH_SIZE, SymbolColor, WINDOW_DIMENSION, get_color,
+    get_cross_glyph_strokes, get_drawing_coordinates,
+};
+use crate::render_system::theme::get_active_theme;
+use connect_4_rust::board_logic::bit_board::BitBoard;
+use connect_4_rust::board_logic::bit_board_coding::{BOARD_HEIGHT, BOARD_WIDTH};
+use macroquad::prelude::*;
+use std::fmt::Write;
+
+/// The file the board gets exported to with the key binding, relative to the working directory.
+pub const SVG_EXPORT_PATH: &str = "connect-4-board.svg";
+
+/// The radius of the rounded corners of the board.
+const CORNER_RADIUS: f32 = 20.0;
+
+/// Gets the color in the hexadecimal notation of SVG.
+fn get_hex_color(color: Color) -> String {
+    let [red, green, blue, _]: [u8; 4] = color.into();
+    format!("#{red:02x}{green:02x}{blue:02x}")
+}
+
+/// Gets the position in SVG coordinates, whose y axis points downwards, unlike the one of the drawing
+/// coordinates.
+fn to_svg_position(position: Vec2) -> Vec2 {
+    Vec2::new(position.x, BOARD_DRAWING_HEIGHT - position.y)
+}
+
+/// Writes the glyph of the player on the stone at the drawing position, if the active theme has glyphs.
+/// The first player gets a ring, the second one a cross, like on the screen.
+fn write_stone_glyph(svg: &mut String, position: Vec2, is_first_player: bool) {
+    let Some(color) = get_active_theme().glyph else {
+        return;
+    };
+    let color = get_hex_color(color);
+    let thickness = GLYPH_SIZE * 0.3;
+    if is_first_player {
+        let center = to_svg_position(position);
+        let _ = writeln!(
+            svg,
+            r#"  <circle cx="{}" cy="{}" r="{GLYPH_SIZE}" fill="none" stroke="{color}" stroke-width="{thickness}"/>"#,
+            center.x, center.y
+        );
+    } else {
+        for (start, end) in get_cross_glyph_strokes(position, GLYPH_SIZE) {
+            let (start, end) = (to_svg_position(start), to_svg_position(end));
+            let _ = writeln!(
+                svg,
+                r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{color}" stroke-width="{thickness}"/>"#,
+                start.x, start.y, end.x, end.y
+            );
+        }
+    }
+}
+
+/// Generates the SVG picture of the board. The stones at the highlighted cells, given as column and row,
+/// are drawn in the light variant of their color, like the winning stones on the screen.
+pub fn board_to_svg(board: &BitBoard, highlight: Option<&[(u32, u32)]>) -> String {
+    let mut stones = [[None; BOARD_HEIGHT as usize]; BOARD_WIDTH as usize];
+    for (column, row, is_first_player) in board.get_board_positioning() {
+        stones[column as usize][row as usize] = Some(is_first_player);
+    }
+    let highlight = highlight.unwrap_or(&[]);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WINDOW_DIMENSION}" height="{BOARD_DRAWING_HEIGHT}" viewBox="0 0 {WINDOW_DIMENSION} {BOARD_DRAWING_HEIGHT}">"#
+    );
+    let _ = writeln!(
+        svg,
+        r#"  <rect x="0" y="0" width="{WINDOW_DIMENSION}" height="{BOARD_DRAWING_HEIGHT}" rx="{CORNER_RADIUS}" fill="{}"/>"#,
+        get_hex_color(*get_color(SymbolColor::Brown))
+    );
+    for column in 0..BOARD_WIDTH {
+        for row in 0..BOARD_HEIGHT {
+            let position = get_drawing_coordinates(column, row);
+            let stone = stones[column as usize][row as usize];
+            let highlighted = highlight.contains(&(column, row));
+            let color = match stone {
+                None => get_active_theme().background,
+                Some(true) if highlighted => *get_color(SymbolColor::LightYellow),
+                Some(true) => *get_color(SymbolColor::Yellow),
+                Some(false) if highlighted => *get_color(SymbolColor::LightBlue),
+                Some(false) => *get_color(SymbolColor::Blue),
+            };
+            let center = to_svg_position(position);
+            let _ = writeln!(
+                svg,
+                r#"  <circle cx="{}" cy="{}" r="{CIRCLE_RADIUS}" fill="{}"/>"#,
+                center.x,
+                center.y,
+                get_hex_color(color)
+            );
+            if let Some(is_first_player) = stone {
+                write_stone_glyph(&mut svg, position, is_first_player);
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Gets the cells of the winning lines on the board, which get highlighted in the export.
+pub fn get_winning_cells(board: &BitBoard) -> Vec<(u32, u32)> {
+    let (_, lines) = board.get_winning_status_for_rendering();
+    lines
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|line| line.cells.iter().copied())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_system::theme::{ThemeKind, set_active_theme};
+
+    /// The board, on which the first player has won with the bottom row, as the snapshot shows it.
+    const SNAPSHOT_MOVES: &str = "1726374";
+
+    /// Gets the picture of the snapshot board with its winning stones highlighted.
+    fn get_snapshot_svg() -> String {
+        let board = BitBoard::from_move_string(SNAPSHOT_MOVES).unwrap();
+        board_to_svg(&board, Some(&get_winning_cells(&board)))
+    }
+
+    #[test]
+    fn test_picture_matches_the_snapshot() {
+        // The color-blind theme draws the glyphs as well.
+        set_active_theme(ThemeKind::Deuteranopia);
+        let svg = get_snapshot_svg();
+        set_active_theme(ThemeKind::Classic);
+        assert_eq!(
+            svg,
+            include_str!("../../tests/fixtures/winning_board.svg"),
+            "The picture has changed, if this is intended, write it into tests/fixtures/winning_board.svg."
+        );
+    }
+
+    #[test]
+    fn test_picture_is_well_formed() {
+        for theme in [
+            ThemeKind::Classic,
+            ThemeKind::HighContrast,
+            ThemeKind::Deuteranopia,
+        ] {
+            set_active_theme(theme);
+            let svg = get_snapshot_svg();
+            let document = roxmltree::Document::parse(&svg).unwrap();
+            let root = document.root_element();
+            assert_eq!(root.tag_name().name(), "svg");
+            assert_eq!(
+                root.tag_name().namespace(),
+                Some("http://www.w3.org/2000/svg")
+            );
+
+            // Every cell has its circle, the rings of the glyphs are circles as well.
+            let count = |name: &str| {
+                root.children()
+                    .filter(|node| node.tag_name().name() == name)
+                    .count()
+            };
+            let glyph_count = |stones: usize| {
+                if theme.get_theme().glyph.is_some() {
+                    stones
+                } else {
+                    0
+                }
+            };
+            assert_eq!(count("rect"), 1);
+            assert_eq!(count("circle"), 42 + glyph_count(4));
+            assert_eq!(count("line"), 2 * glyph_count(3));
+        }
+        set_active_theme(ThemeKind::Classic);
+    }
+
+    #[test]
+    fn test_winning_cells_get_highlighted() {
+        let board = BitBoard::from_move_string(SNAPSHOT_MOVES).unwrap();
+        let mut cells = get_winning_cells(&board);
+        cells.sort_unstable();
+        assert_eq!(cells, [(0, 0), (1, 0), (2, 0), (3, 0)]);
+
+        let light_yellow = get_hex_color(*get_color(SymbolColor::LightYellow));
+        let svg = board_to_svg(&board, Some(&cells));
+        assert_eq!(svg.matches(&light_yellow).count(), 4);
+        assert!(!board_to_svg(&board, None).contains(&light_yellow));
+    }
+}
@@ -0,0 +1,220 @@
+//! The themes define the colors everything gets drawn with. The theme chosen by the player is stored on the
+//! blackboard and activated every frame, the drawing functions look their colors up in the active theme.
+//! The color-blind friendly theme additionally marks the stones with glyphs, so that they can be told apart
+//! without their color.
+
+use macroquad::prelude::*;
+use std::cell::Cell;
+
+/// Represents color types we can draw elements with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SymbolColor {
+    Brown,
+    Yellow,
+    LightYellow,
+    Blue,
+    LightBlue,
+    Grey,
+}
+
+/// The colors of a theme.
+pub struct Theme {
+    /// The colors for the symbol colors in the order of the enum.
+    colors: [Color; 6],
+    /// The color the window gets cleared with.
+    pub background: Color,
+    /// The color of the texts and markers.
+    pub text: Color,
+    /// The color of the glyphs on the stones, none if the stones have no glyphs.
+    pub glyph: Option<Color>,
+}
+
+impl Theme {
+    /// Gets the RGB value for the color indicated.
+    pub fn get_color(&self, color: SymbolColor) -> &Color {
+        &self.colors[color as usize]
+    }
+}
+
+/// The original colors of the game.
+const CLASSIC_THEME: Theme = Theme {
+    colors: [
+        Color::new(0.48, 0.25, 0.0, 1.0),
+        Color::new(0.75, 0.55, 0.06, 1.0),
+        Color::new(1.0, 0.91, 0.0, 1.0),
+        Color::new(0.0, 0.28, 0.67, 1.0),
+        Color::new(0.0, 0.58, 1.0, 1.0),
+        Color::new(0.4, 0.4, 0.4, 1.0),
+    ],
+    background: BLACK,
+    text: WHITE,
+    glyph: None,
+};
+
+/// Saturated stones on a dark board, the highlights are close to white.
+const HIGH_CONTRAST_THEME: Theme = Theme {
+    colors: [
+        Color::new(0.15, 0.15, 0.15, 1.0),
+        Color::new(1.0, 0.85, 0.0, 1.0),
+        Color::new(1.0, 1.0, 0.75, 1.0),
+        Color::new(0.1, 0.35, 1.0, 1.0),
+        Color::new(0.7, 0.85, 1.0, 1.0),
+        Color::new(0.5, 0.5, 0.5, 1.0),
+    ],
+    background: BLACK,
+    text: WHITE,
+    glyph: None,
+};
+
+/// Orange and blue, that can be told apart with a red-green deficiency, and glyphs on the stones.
+const DEUTERANOPIA_THEME: Theme = Theme {
+    colors: [
+        Color::new(0.25, 0.25, 0.25, 1.0),
+        Color::new(0.9, 0.6, 0.0, 1.0),
+        Color::new(1.0, 0.85, 0.55, 1.0),
+        Color::new(0.0, 0.45, 0.7, 1.0),
+        Color::new(0.55, 0.8, 1.0, 1.0),
+        Color::new(0.5, 0.5, 0.5, 1.0),
+    ],
+    background: BLACK,
+    text: WHITE,
+    glyph: Some(BLACK),
+};
+
+/// The themes, that can be chosen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ThemeKind {
+    #[default]
+    Classic,
+    HighContrast,
+    Deuteranopia,
+}
+
+impl ThemeKind {
+    /// Gets the colors of the theme.
+    pub fn get_theme(self) -> &'static Theme {
+        match self {
+            ThemeKind::Classic => &CLASSIC_THEME,
+            ThemeKind::HighContrast => &HIGH_CONTRAST_THEME,
+            ThemeKind::Deuteranopia => &DEUTERANOPIA_THEME,
+        }
+    }
+
+    /// Gets the text shown, where the theme gets chosen.
+    pub fn get_label(self) -> &'static str {
+        match self {
+            ThemeKind::Classic => "Theme: Classic",
+            ThemeKind::HighContrast => "Theme: Contrast",
+            ThemeKind::Deuteranopia => "Theme: Color-blind",
+        }
+    }
+}
+
+thread_local! {
+    /// The theme used for drawing, the rendering all happens on the main thread.
+    static ACTIVE_THEME: Cell<ThemeKind> = const { Cell::new(ThemeKind::Classic) };
+}
+
+/// Activates the theme for all following drawing.
+pub fn set_active_theme(theme: ThemeKind) {
+    ACTIVE_THEME.with(|active| active.set(theme));
+}
+
+/// Gets the theme used for drawing.
+pub fn get_active_theme() -> &'static Theme {
+    ACTIVE_THEME.with(|active| active.get()).get_theme()
+}
+
+/// Generates an RGB value for any of the Colors indicated from the active theme.
+pub fn get_color(color: SymbolColor) -> &'static Color {
+    get_active_theme().get_color(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// All themes, that can be chosen.
+    const THEME_KINDS: [ThemeKind; 3] = [
+        ThemeKind::Classic,
+        ThemeKind::HighContrast,
+        ThemeKind::Deuteranopia,
+    ];
+
+    /// Gets the relative luminance of the color, the weights follow the perceived brightness.
+    fn get_luminance(color: &Color) -> f32 {
+        0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+    }
+
+    #[test]
+    fn test_colors_are_looked_up_by_their_symbol() {
+        let theme = ThemeKind::Classic.get_theme();
+        assert_eq!(
+            *theme.get_color(SymbolColor::Brown),
+            Color::new(0.48, 0.25, 0.0, 1.0)
+        );
+        assert_eq!(
+            *theme.get_color(SymbolColor::Grey),
+            Color::new(0.4, 0.4, 0.4, 1.0)
+        );
+        let theme = ThemeKind::Deuteranopia.get_theme();
+        assert_eq!(
+            *theme.get_color(SymbolColor::Yellow),
+            Color::new(0.9, 0.6, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_active_theme_gets_switched() {
+        // Every test runs on its own thread, which starts with the classic theme.
+        let board_color = |theme: &Theme| *theme.get_color(SymbolColor::Brown);
+        assert_eq!(board_color(get_active_theme()), board_color(&CLASSIC_THEME));
+        set_active_theme(ThemeKind::HighContrast);
+        assert_eq!(
+            board_color(get_active_theme()),
+            board_color(&HIGH_CONTRAST_THEME)
+        );
+        assert_eq!(
+            get_color(SymbolColor::Blue),
+            HIGH_CONTRAST_THEME.get_color(SymbolColor::Blue)
+        );
+        set_active_theme(ThemeKind::Classic);
+        assert_eq!(board_color(get_active_theme()), board_color(&CLASSIC_THEME));
+    }
+
+    #[test]
+    fn test_only_the_color_blind_theme_has_glyphs() {
+        for kind in THEME_KINDS {
+            assert_eq!(
+                kind.get_theme().glyph.is_some(),
+                kind == ThemeKind::Deuteranopia,
+                "{kind:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_stones_and_highlights_can_be_told_apart() {
+        for kind in THEME_KINDS {
+            let theme = kind.get_theme();
+            let luminance = |color| get_luminance(theme.get_color(color));
+            // The highlight of a winning stone is brighter than the stone.
+            assert!(luminance(SymbolColor::LightYellow) > luminance(SymbolColor::Yellow));
+            assert!(luminance(SymbolColor::LightBlue) > luminance(SymbolColor::Blue));
+            // The stones of both players differ in brightness, not only in hue.
+            assert!(
+                (luminance(SymbolColor::Yellow) - luminance(SymbolColor::Blue)).abs() > 0.2,
+                "{kind:?}"
+            );
+            // The glyphs stand out on the stones of both players.
+            if let Some(glyph) = &theme.glyph {
+                for stone in [SymbolColor::Yellow, SymbolColor::Blue] {
+                    assert!(
+                        (luminance(stone) - get_luminance(glyph)).abs() > 0.3,
+                        "{kind:?} {stone:?}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Toasts are short messages, that tell the player something without interrupting the game, like a
+//! confirmation or an error, that is not fatal. They are queued on the blackboard, so that every state can
+//! push them and they survive the state transitions. The main loop updates and draws them on top of the
+//! states, the newest one at the bottom of the board with the older ones stacked above.
+
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, WINDOW_DIMENSION, get_centered_text_position, measure_game_text_sized,
+    print_text_colored,
+};
+use crate::render_system::theme::get_active_theme;
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+
+/// The time in seconds a toast is usually shown.
+pub const TOAST_DURATION: f32 = 2.5;
+/// The maximum number of toasts shown at once, when more get pushed the oldest ones are dropped.
+const MAX_TOASTS: usize = 3;
+/// The time in seconds a toast takes to fade out at the end of its duration.
+const FADE_TIME: f32 = 0.5;
+/// The center of the newest toast.
+const FIRST_TOAST_CENTER: Vec2 = Vec2 {
+    x: 0.5 * WINDOW_DIMENSION,
+    y: 40.0,
+};
+/// The vertical distance between two toasts.
+const TOAST_DISTANCE: f32 = 40.0;
+/// The color of the panel behind a toast, so that it can be read on top of the board.
+const PANEL_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.7);
+/// The margin of the panel around the text.
+const PANEL_MARGIN: f32 = 8.0;
+
+/// A message shown for some time.
+struct Toast {
+    message: String,
+    /// The time in seconds, that is left until the toast vanishes.
+    remaining_time: f32,
+}
+
+/// Gets the opacity of a toast with the indicated remaining time. It is fully visible until the last
+/// [`FADE_TIME`] seconds, in which it fades out linearly.
+pub fn get_fade_alpha(remaining_time: f32) -> f32 {
+    (remaining_time / FADE_TIME).clamp(0.0, 1.0)
+}
+
+/// The toasts currently shown, the oldest one first.
+#[derive(Default)]
+pub struct ToastQueue {
+    toasts: VecDeque<Toast>,
+}
+
+impl ToastQueue {
+    /// Shows the message for the indicated time in seconds. If there are too many toasts, the oldest one
+    /// gets dropped.
+    pub fn push(&mut self, message: impl Into<String>, duration: f32) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            message: message.into(),
+            remaining_time: duration,
+        });
+    }
+
+    /// Runs the timers of the toasts and drops the ones, whose time is over.
+    pub fn update(&mut self, delta_time: f32) {
+        for toast in &mut self.toasts {
+            toast.remaining_time -= delta_time;
+        }
+        self.toasts.retain(|toast| toast.remaining_time > 0.0);
+    }
+
+    /// Checks, if no toast is shown.
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    /// Draws the toasts on a dark panel each, the newest one at the bottom.
+    pub fn draw(&self) {
+        for (index, toast) in self.toasts.iter().rev().enumerate() {
+            let alpha = get_fade_alpha(toast.remaining_time);
+            let center = FIRST_TOAST_CENTER + Vec2::new(0.0, TOAST_DISTANCE * index as f32);
+            let text_size = measure_game_text_sized(&toast.message, SMALL_TEXT_SIZE);
+            let mut panel_color = PANEL_COLOR;
+            panel_color.a *= alpha;
+            draw_rectangle(
+                center.x - 0.5 * text_size.x - PANEL_MARGIN,
+                center.y - 0.5 * text_size.y - PANEL_MARGIN,
+                text_size.x + 2.0 * PANEL_MARGIN,
+                text_size.y + 2.0 * PANEL_MARGIN,
+                panel_color,
+            );
+            let mut text_color = get_active_theme().text;
+            text_color.a *= alpha;
+            print_text_colored(
+                &toast.message,
+                get_centered_text_position(center, text_size),
+                SMALL_TEXT_SIZE,
+                text_color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets the messages of the toasts shown, the oldest one first.
+    fn get_messages(queue: &ToastQueue) -> Vec<&str> {
+        queue
+            .toasts
+            .iter()
+            .map(|toast| toast.message.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_fade_alpha() {
+        assert_eq!(get_fade_alpha(TOAST_DURATION), 1.0);
+        assert_eq!(get_fade_alpha(FADE_TIME), 1.0);
+        assert_eq!(get_fade_alpha(0.5 * FADE_TIME), 0.5);
+        assert_eq!(get_fade_alpha(0.0), 0.0);
+        assert_eq!(get_fade_alpha(-1.0), 0.0);
+    }
+
+    #[test]
+    fn test_oldest_toast_makes_room() {
+        let mut queue = ToastQueue::default();
+        assert!(queue.is_empty());
+        for message in ["first", "second", "third", "fourth"] {
+            queue.push(message, TOAST_DURATION);
+        }
+        assert_eq!(get_messages(&queue), ["second", "third", "fourth"]);
+    }
+
+    #[test]
+    fn test_toasts_vanish_after_their_duration() {
+        let mut queue = ToastQueue::default();
+        queue.push("short", 1.0);
+        queue.push(String::from("long"), 2.0);
+        queue.update(0.75);
+        assert_eq!(get_messages(&queue), ["short", "long"]);
+        queue.update(0.25);
+        assert_eq!(get_messages(&queue), ["long"]);
+        queue.update(1.0);
+        assert!(queue.is_empty());
+    }
+}
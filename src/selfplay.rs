@@ -0,0 +1,310 @@
+//! Self play harness to find out, if a change made the engine stronger or weaker. Two differently
+//! configured engines play a series of games against each other. Every random opening is played twice
+//! with alternating start, so that none of the engines gets an advantage from the opening.
+
+use crate::board_logic::alpha_beta::{AlphaBeta, SearchConfig};
+use crate::board_logic::bit_board::{BitBoard, GameResult};
+use crate::board_logic::bit_board_coding::BOARD_WIDTH;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// A small random generator (SplitMix64), good enough to generate varying openings from a seed.
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    /// Gets the next random number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Gets a random number in the range 0..upper_bound.
+    pub fn next_below(&mut self, upper_bound: u32) -> u32 {
+        (self.next_u64() % upper_bound as u64) as u32
+    }
+
+    /// Gets a random number in the range 0..1.
+    pub fn next_f64(&mut self) -> f64 {
+        // The upper 53 bits fill the mantissa exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Everything needed to set up a match between two engines.
+#[derive(Clone, Copy, Debug)]
+pub struct MatchSettings {
+    /// The amount of games to play.
+    pub games: u32,
+    /// The configuration of the first engine.
+    pub config_a: SearchConfig,
+    /// The configuration of the second engine.
+    pub config_b: SearchConfig,
+    /// The seed for the random openings.
+    pub seed: u64,
+    /// The amount of random moves played before the engines take over.
+    pub opening_moves: u32,
+}
+
+/// Statistics of one engine collected during a match.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EngineStatistics {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// The amount of moves the engine has computed.
+    pub moves: u32,
+    /// The time the engine spent on all its moves.
+    pub thinking_time: Duration,
+}
+
+impl EngineStatistics {
+    /// Gets the average time spent on a move.
+    pub fn average_move_time(&self) -> Duration {
+        if self.moves == 0 {
+            Duration::ZERO
+        } else {
+            self.thinking_time / self.moves
+        }
+    }
+}
+
+/// The result of a match seen from both engines.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MatchResult {
+    pub engine_a: EngineStatistics,
+    pub engine_b: EngineStatistics,
+}
+
+impl fmt::Display for MatchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<10}{:>8}{:>8}{:>8}{:>16}",
+            "engine", "wins", "draws", "losses", "avg move time"
+        )?;
+        for (name, statistics) in [("a", &self.engine_a), ("b", &self.engine_b)] {
+            writeln!(
+                f,
+                "{:<10}{:>8}{:>8}{:>8}{:>13.1} ms",
+                name,
+                statistics.wins,
+                statistics.draws,
+                statistics.losses,
+                statistics.average_move_time().as_secs_f64() * 1000.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates a random opening of legal moves, that does not end the game.
+fn generate_opening(random: &mut SplitMix64, opening_moves: u32) -> Vec<u32> {
+    loop {
+        let mut board = BitBoard::new();
+        let mut opening = Vec::new();
+        for _ in 0..opening_moves {
+            let column = loop {
+                let column = random.next_below(BOARD_WIDTH);
+                if board.get_possible_move(column) != 0 {
+                    break column;
+                }
+            };
+            board.play(column).expect("The column has been checked.");
+            opening.push(column);
+            if board.is_game_over() {
+                break;
+            }
+        }
+
+        if !board.is_game_over() {
+            return opening;
+        }
+    }
+}
+
+/// Plays one game between the engines. The board is represented from the perspective of engine a.
+fn play_game(settings: &MatchSettings, opening: &[u32], a_starts: bool, result: &mut MatchResult) {
+    let mut engine_a = AlphaBeta::with_config(settings.config_a);
+    let mut engine_b = AlphaBeta::with_config(settings.config_b);
+    let mut board = BitBoard::new();
+    board.set_computer_first(a_starts);
+
+    for &column in opening {
+        board
+            .play(column)
+            .expect("Openings only contain possible moves.");
+    }
+
+    while !board.is_game_over() {
+        let a_to_move = board.side_to_move().is_computer();
+        let start = Instant::now();
+        let column = if a_to_move {
+            let column = engine_a
+                .get_best_move(board.clone())
+                .expect("The game is not over yet.");
+            result.engine_a.moves += 1;
+            result.engine_a.thinking_time += start.elapsed();
+            column
+        } else {
+            let mut swapped_board = board.clone();
+            swapped_board.swap_players();
+            let column = engine_b
+                .get_best_move(swapped_board)
+                .expect("The game is not over yet.");
+            result.engine_b.moves += 1;
+            result.engine_b.thinking_time += start.elapsed();
+            column
+        };
+        board
+            .play(column)
+            .expect("The engines only pick possible moves.");
+    }
+
+    let (game_result, _) = board.get_winning_status_for_rendering();
+    match (game_result, a_starts) {
+        (GameResult::FirstPlayerWon, true) | (GameResult::SecondPlayerWon, false) => {
+            result.engine_a.wins += 1;
+            result.engine_b.losses += 1;
+        }
+        (GameResult::FirstPlayerWon, false) | (GameResult::SecondPlayerWon, true) => {
+            result.engine_a.losses += 1;
+            result.engine_b.wins += 1;
+        }
+        _ => {
+            result.engine_a.draws += 1;
+            result.engine_b.draws += 1;
+        }
+    }
+}
+
+/// Plays the complete match. Every opening is used for two consecutive games with alternating start.
+pub fn play_match(settings: &MatchSettings) -> MatchResult {
+    let mut random = SplitMix64::new(settings.seed);
+    let mut result = MatchResult::default();
+    let mut opening = Vec::new();
+
+    for game in 0..settings.games {
+        if game % 2 == 0 {
+            opening = generate_opening(&mut random, settings.opening_moves);
+        }
+        play_game(settings, &opening, game % 2 == 0, &mut result);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets the configuration of an engine, that searches to the depth without a time limit.
+    fn get_config(max_depth: u32) -> SearchConfig {
+        SearchConfig {
+            max_depth,
+            move_time: None,
+            ..SearchConfig::default()
+        }
+    }
+
+    /// Gets the settings of a match between engines searching to the depths.
+    fn get_settings(games: u32, depth_a: u32, depth_b: u32) -> MatchSettings {
+        MatchSettings {
+            games,
+            config_a: get_config(depth_a),
+            config_b: get_config(depth_b),
+            seed: 42,
+            opening_moves: 4,
+        }
+    }
+
+    #[test]
+    fn test_random_numbers_follow_the_seed() {
+        // The first number of SplitMix64 with seed 0, as given by its reference implementation.
+        assert_eq!(SplitMix64::new(0).next_u64(), 0xE220_A839_7B1D_CDAF);
+        let mut first = SplitMix64::new(7);
+        let mut second = SplitMix64::new(7);
+        for _ in 0..100 {
+            assert_eq!(first.next_u64(), second.next_u64());
+            assert!(first.next_below(7) < 7);
+            assert!((0.0..1.0).contains(&first.next_f64()));
+            second.next_below(7);
+            second.next_f64();
+        }
+    }
+
+    #[test]
+    fn test_openings_are_legal_and_open() {
+        let mut random = SplitMix64::new(3);
+        for _ in 0..200 {
+            let opening = generate_opening(&mut random, 10);
+            assert_eq!(opening.len(), 10);
+            let mut board = BitBoard::new();
+            for &column in &opening {
+                board.play(column).unwrap();
+            }
+            assert!(!board.is_game_over(), "{opening:?}");
+        }
+    }
+
+    #[test]
+    fn test_match_counts_every_game_for_both_engines() {
+        let result = play_match(&get_settings(6, 2, 3));
+        for (own, other) in [
+            (result.engine_a, result.engine_b),
+            (result.engine_b, result.engine_a),
+        ] {
+            assert_eq!(own.wins + own.draws + own.losses, 6);
+            assert_eq!(own.wins, other.losses);
+            assert_eq!(own.draws, other.draws);
+            assert!(own.moves > 0);
+        }
+    }
+
+    #[test]
+    fn test_stronger_engine_wins_the_match() {
+        let result = play_match(&get_settings(8, 7, 1));
+        assert!(result.engine_a.wins > result.engine_b.wins, "{result:?}");
+    }
+
+    #[test]
+    fn test_result_table() {
+        let result = MatchResult {
+            engine_a: EngineStatistics {
+                wins: 3,
+                draws: 1,
+                losses: 0,
+                moves: 4,
+                thinking_time: Duration::from_millis(10),
+            },
+            engine_b: EngineStatistics::default(),
+        };
+        assert_eq!(
+            result.to_string(),
+            "engine        wins   draws  losses   avg move time\n\
+             a                3       1       0          2.5 ms\n\
+             b                0       0       0          0.0 ms\n"
+        );
+    }
+
+    /// Plays the match of `selfplay --games 200 --depth-a 9 --depth-b 11`, which takes several minutes.
+    /// Run it with `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_regression_match() {
+        let result = play_match(&get_settings(200, 9, 11));
+        println!("{result}");
+        assert_eq!(
+            result.engine_a.wins + result.engine_a.draws + result.engine_a.losses,
+            200
+        );
+    }
+}
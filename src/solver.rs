@@ -0,0 +1,364 @@
+//! Batch solver for positions given as move strings, one per line. This is meant to validate changes of
+//! the engine against the public test sets without the game. Everything after the move string on a line
+//! is ignored, so the test set files can be read as they are. The positions are solved by several
+//! workers in parallel, each with its own [`AlphaBeta`], and every result is written as soon as it is
+//! there as a line
+//! ```text
+//! movestring score best_move nodes time_ms
+//! ```
+//! where the score is from the perspective of the player to move and the best move is counted from 1.
+//! Lines, that can not be read, are reported and skipped.
+
+use crate::board_logic::alpha_beta::{AlphaBeta, SearchConfig};
+use crate::board_logic::bit_board::{BitBoard, MoveStringError};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How the positions get solved.
+#[derive(Clone, Copy, Debug)]
+pub struct SolverSettings {
+    /// Positions with more moves left than the depth are searched to the depth only, instead of being
+    /// solved exactly. None solves every position exactly.
+    pub depth: Option<u32>,
+    /// The amount of workers solving positions in parallel.
+    pub threads: usize,
+}
+
+impl Default for SolverSettings {
+    fn default() -> SolverSettings {
+        SolverSettings {
+            depth: None,
+            threads: 1,
+        }
+    }
+}
+
+/// The errors, that make a line of the input fail. Lines are counted from 1.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SolverError {
+    /// The move string of the line could not be parsed.
+    MoveString { line: usize, error: MoveStringError },
+    /// The game of the line is already over, there is nothing to solve.
+    GameOver { line: usize },
+    /// The input could not be read any further.
+    Read { reason: String },
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::MoveString { line, error } => write!(f, "line {line}: {error}"),
+            SolverError::GameOver { line } => write!(f, "line {line}: the game is already over"),
+            SolverError::Read { reason } => write!(f, "the input can not be read: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+/// A position, that has been solved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolvedPosition {
+    /// The moves as they appear in the input.
+    pub moves: String,
+    /// The score from the perspective of the player to move.
+    pub score: f32,
+    /// The best move, as column counted from 0.
+    pub best_move: u32,
+    /// The nodes searched for the position.
+    pub nodes: u64,
+    /// The time spent on the position.
+    pub time: Duration,
+}
+
+impl fmt::Display for SolvedPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Adding zero turns the negative zero of a draw into a plain one.
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.moves,
+            self.score + 0.0,
+            self.best_move + 1,
+            self.nodes,
+            self.time.as_millis()
+        )
+    }
+}
+
+/// Reads the position from the line with the indicated number. Empty lines have no position and
+/// return none.
+pub fn parse_position(
+    line_number: usize,
+    line: &str,
+) -> Result<Option<(String, BitBoard)>, SolverError> {
+    let Some(moves) = line.split_whitespace().next() else {
+        return Ok(None);
+    };
+    let board = BitBoard::from_move_string(moves).map_err(|error| SolverError::MoveString {
+        line: line_number,
+        error,
+    })?;
+    if board.is_game_over() {
+        return Err(SolverError::GameOver { line: line_number });
+    }
+    Ok(Some((moves.to_string(), board)))
+}
+
+/// Creates the engine of a worker. Its depth only matters for the positions, that are not solved
+/// exactly.
+fn create_engine(settings: &SolverSettings) -> AlphaBeta {
+    let mut config = SearchConfig {
+        move_time: None,
+        ..SearchConfig::default()
+    };
+    if let Some(depth) = settings.depth {
+        config.max_depth = depth;
+    }
+    AlphaBeta::with_config(config)
+}
+
+/// Solves the position exactly, unless it has more moves left than the depth of the settings. Then it
+/// is searched to the depth only.
+pub fn solve_position(
+    engine: &mut AlphaBeta,
+    moves: String,
+    board: BitBoard,
+    settings: &SolverSettings,
+) -> SolvedPosition {
+    // The positions are unrelated, a fresh table keeps the nodes independent of the order they come in.
+    engine.clear_table();
+    let start = Instant::now();
+    let (score, best_move) = if settings
+        .depth
+        .is_none_or(|depth| board.remaining_moves() <= depth)
+    {
+        let result = engine.solve(board);
+        (result.score, result.best_move)
+    } else {
+        let best_move = engine
+            .get_best_move(board)
+            .expect("Positions, whose game is over, are not solved.");
+        (engine.get_best_score(), best_move)
+    };
+    SolvedPosition {
+        moves,
+        score,
+        best_move,
+        nodes: engine.get_nodes_searched(),
+        time: start.elapsed(),
+    }
+}
+
+/// What the workers and the reader of the input report.
+type Report = Result<SolvedPosition, SolverError>;
+
+/// Solves the positions of the input with the workers of the settings. The solved positions are written
+/// to the output in the order they get finished, the lines, that can not be solved, are reported to the
+/// errors. Returns the amount of lines, that failed.
+pub fn run_solver(
+    input: impl BufRead + Send + 'static,
+    output: &mut impl Write,
+    errors: &mut impl Write,
+    settings: SolverSettings,
+) -> io::Result<u32> {
+    let (job_sender, job_receiver) = mpsc::channel::<(String, BitBoard)>();
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    let (report_sender, report_receiver) = mpsc::channel::<Report>();
+
+    // The input gets read in its own thread, so that the results are written while it is being read.
+    let reader_sender = report_sender.clone();
+    thread::spawn(move || {
+        for (index, line) in input.lines().enumerate() {
+            let position = line
+                .map_err(|error| SolverError::Read {
+                    reason: error.to_string(),
+                })
+                .and_then(|line| parse_position(index + 1, &line));
+            match position {
+                Ok(Some(position)) => {
+                    if job_sender.send(position).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    let stop = matches!(error, SolverError::Read { .. });
+                    if reader_sender.send(Err(error)).is_err() || stop {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    for _ in 0..settings.threads.max(1) {
+        let job_receiver = Arc::clone(&job_receiver);
+        let report_sender = report_sender.clone();
+        thread::spawn(move || {
+            let mut engine = create_engine(&settings);
+            loop {
+                // The lock is only held for taking the job, not while solving it.
+                let job = job_receiver
+                    .lock()
+                    .expect("A worker does not panic while holding the jobs.")
+                    .recv();
+                let Ok((moves, board)) = job else {
+                    return;
+                };
+                let solved = solve_position(&mut engine, moves, board, &settings);
+                if report_sender.send(Ok(solved)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+    // The reports end, once the reader and all workers are done.
+    drop(report_sender);
+
+    let mut failures = 0;
+    for report in report_receiver {
+        match report {
+            Ok(solved) => {
+                writeln!(output, "{solved}")?;
+                output.flush()?;
+            }
+            Err(error) => {
+                failures += 1;
+                writeln!(errors, "{error}")?;
+            }
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Read};
+
+    /// A position from the test sets with 12 moves left, that is solved quickly.
+    const MID_GAME: &str = "214677454114757442122267175123";
+
+    #[test]
+    fn test_positions_are_parsed() {
+        assert!(matches!(parse_position(1, ""), Ok(None)));
+        assert!(matches!(parse_position(1, "  \t"), Ok(None)));
+        let (moves, board) = parse_position(1, "414141 1 and more").unwrap().unwrap();
+        assert_eq!(moves, "414141");
+        assert_eq!(
+            board.to_key(),
+            BitBoard::from_move_string("414141").unwrap().to_key()
+        );
+        assert_eq!(
+            parse_position(3, "4x").err(),
+            Some(SolverError::MoveString {
+                line: 3,
+                error: MoveStringError::InvalidCharacter {
+                    position: 1,
+                    character: 'x'
+                }
+            })
+        );
+        assert_eq!(
+            parse_position(5, "4141414").err(),
+            Some(SolverError::GameOver { line: 5 })
+        );
+    }
+
+    #[test]
+    fn test_solved_position_is_written_as_a_line() {
+        let mut solved = SolvedPosition {
+            moves: "4455".to_string(),
+            score: -0.0,
+            best_move: 0,
+            nodes: 1234,
+            time: Duration::from_micros(56_789),
+        };
+        assert_eq!(solved.to_string(), "4455 0 1 1234 56");
+        solved.score = -0.5;
+        solved.best_move = 6;
+        assert_eq!(solved.to_string(), "4455 -0.5 7 1234 56");
+    }
+
+    #[test]
+    fn test_depth_caps_the_search() {
+        let board = BitBoard::from_move_string(MID_GAME).unwrap();
+        let mut engine = create_engine(&SolverSettings::default());
+        let exact = solve_position(
+            &mut engine,
+            MID_GAME.to_string(),
+            board.clone(),
+            &SolverSettings::default(),
+        );
+        assert!(exact.score > 0.0);
+
+        // The depth is below the moves left, so the position is only searched to the depth.
+        let settings = SolverSettings {
+            depth: Some(2),
+            threads: 1,
+        };
+        let mut engine = create_engine(&settings);
+        let capped = solve_position(&mut engine, MID_GAME.to_string(), board, &settings);
+        assert!(capped.nodes < exact.nodes);
+
+        // The immediate win is found either way.
+        let board = BitBoard::from_move_string("414141").unwrap();
+        let solved = solve_position(&mut engine, "414141".to_string(), board, &settings);
+        assert_eq!(solved.best_move, 3);
+        assert!(solved.score > 0.0);
+    }
+
+    #[test]
+    fn test_failed_lines_are_counted() {
+        let input = "4x\n\n414141\n4141414\n";
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        let failures = run_solver(
+            input.as_bytes(),
+            &mut output,
+            &mut errors,
+            SolverSettings::default(),
+        )
+        .unwrap();
+        assert_eq!(failures, 2);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("414141 1 4 "), "{output}");
+        assert_eq!(output.lines().count(), 1);
+        assert_eq!(
+            String::from_utf8(errors).unwrap(),
+            "line 1: invalid column 'x' at position 1\nline 4: the game is already over\n"
+        );
+    }
+
+    #[test]
+    fn test_results_are_written_before_the_input_ends() {
+        let (input_reader, mut input_writer) = io::pipe().unwrap();
+        let (output_reader, mut output_writer) = io::pipe().unwrap();
+        let solver = thread::spawn(move || {
+            run_solver(
+                BufReader::new(input_reader),
+                &mut output_writer,
+                &mut io::sink(),
+                SolverSettings::default(),
+            )
+        });
+
+        // The first result arrives, while the input is still open.
+        writeln!(input_writer, "414141").unwrap();
+        let mut output_reader = BufReader::new(output_reader);
+        let mut line = String::new();
+        output_reader.read_line(&mut line).unwrap();
+        assert!(line.starts_with("414141 1 4 "), "{line}");
+
+        writeln!(input_writer, "{MID_GAME}").unwrap();
+        drop(input_writer);
+        assert_eq!(solver.join().unwrap().unwrap(), 0);
+        let mut rest = String::new();
+        output_reader.read_to_string(&mut rest).unwrap();
+        assert!(rest.starts_with(MID_GAME), "{rest}");
+    }
+}
@@ -0,0 +1,303 @@
+//! The analysis of a finished game. Every position of the game gets evaluated by the ai in the background,
+//! the evaluations are cached by the ply of the position, so that the replay can show them as a graph,
+//! while they are still arriving, and stepping back and forth does not search a position twice. A move,
+//! after which the score drops a lot for the side, that made it, counts as a blunder.
+
+use connect_4_rust::board_logic::bit_board::{BitBoard, GameResult};
+use std::collections::HashMap;
+
+/// The drop of the score a move has to cause for the side, that made it, to count as a blunder. The
+/// scores range from -1 for a lost to 1 for a won game.
+pub const BLUNDER_THRESHOLD: f32 = 0.5;
+
+/// Gets the score of a finished game from the perspective of the first player.
+pub fn get_final_score(result: GameResult) -> f32 {
+    if result.is_first_player_win() {
+        1.0
+    } else if result.is_second_player_win() {
+        -1.0
+    } else {
+        0.0
+    }
+}
+
+/// Converts the score of a position from the perspective of the side to move into the one of the first
+/// player, which the graph shows.
+pub fn get_first_player_score(board: &BitBoard, side_to_move_score: f32) -> f32 {
+    if board.side_to_move().is_computer() == board.get_computer_first() {
+        side_to_move_score
+    } else {
+        -side_to_move_score
+    }
+}
+
+/// Checks, if the move from the position with the score before to the one with the score after is a
+/// blunder. Both scores are from the perspective of the first player.
+pub fn is_blunder(score_before: f32, score_after: f32, mover_is_first: bool) -> bool {
+    let drop = if mover_is_first {
+        score_before - score_after
+    } else {
+        score_after - score_before
+    };
+    drop > BLUNDER_THRESHOLD
+}
+
+/// The evaluation of a position of the game by the ai.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EvalEntry {
+    /// The score from the perspective of the first player.
+    pub score: f32,
+    /// The best move in the position, none if the game is over in it.
+    pub best_move: Option<u32>,
+}
+
+/// The evaluations of the positions of a game, indexed by the ply. The first entry belongs to the empty
+/// board, or the position set up in the board editor. The entries get filled lazily by the results of the
+/// ai, a position, that is missing or still being evaluated, does not get requested again. Every request
+/// is remembered with the generation of the ai, so only the result of the latest request for a ply gets
+/// stored, no matter in which order the results arrive. The cache gets cleared for a new game and
+/// truncated, when moves are taken back.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct EvalCache {
+    entries: Vec<Option<EvalEntry>>,
+    /// The plies, whose evaluations have been requested and not arrived yet, with the generations of the
+    /// requests.
+    pending: HashMap<usize, u64>,
+}
+
+impl EvalCache {
+    /// Drops all evaluations and forgets the pending requests, their results get ignored.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.pending.clear();
+    }
+
+    /// Keeps the evaluations of the positions up to the indicated ply, the positions after it are no
+    /// longer part of the game. The pending requests for them are forgotten as well.
+    pub fn truncate(&mut self, ply: usize) {
+        self.entries.truncate(ply + 1);
+        self.pending.retain(|&pending_ply, _| pending_ply <= ply);
+    }
+
+    /// Gets the evaluation of the position after the indicated amount of moves, none if it is not there
+    /// yet.
+    pub fn get(&self, ply: usize) -> Option<EvalEntry> {
+        self.entries.get(ply).copied().flatten()
+    }
+
+    /// Checks, if the position after the indicated amount of moves has to be requested, which is the
+    /// case, if it is neither evaluated nor requested already.
+    pub fn needs_request(&self, ply: usize) -> bool {
+        self.get(ply).is_none() && !self.pending.contains_key(&ply)
+    }
+
+    /// Remembers, that the evaluation of the position has been requested with the generation.
+    pub fn mark_requested(&mut self, ply: usize, generation: u64) {
+        self.pending.insert(ply, generation);
+    }
+
+    /// Forgets the request with the generation, whose evaluation has failed, so that the position gets
+    /// requested again. A failure of a request, that has been replaced by a later one, is ignored.
+    pub fn forget(&mut self, ply: usize, generation: u64) {
+        if self.pending.get(&ply) == Some(&generation) {
+            self.pending.remove(&ply);
+        }
+    }
+
+    /// Stores the evaluation of the position, that is known without the ai.
+    pub fn insert(&mut self, ply: usize, entry: EvalEntry) {
+        self.pending.remove(&ply);
+        if self.entries.len() <= ply {
+            self.entries.resize(ply + 1, None);
+        }
+        self.entries[ply] = Some(entry);
+    }
+
+    /// Stores the evaluation, that has arrived for the request with the generation. Results of requests,
+    /// that have been forgotten or replaced by a later one, are dropped. Returns, if the evaluation has
+    /// been stored.
+    pub fn fill(&mut self, ply: usize, generation: u64, entry: EvalEntry) -> bool {
+        if self.pending.get(&ply) != Some(&generation) {
+            return false;
+        }
+        self.insert(ply, entry);
+        true
+    }
+
+    /// Gets the scores of the positions up to the indicated ply, the ones not evaluated yet are none.
+    pub fn get_scores(&self, ply: usize) -> Vec<Option<f32>> {
+        (0..=ply)
+            .map(|index| self.get(index).map(|entry| entry.score))
+            .collect()
+    }
+
+    /// Gets the plies after the moves, that are blunders. A move can only be classified, once the
+    /// positions before and after it have been evaluated. The history tells, who made the moves.
+    pub fn get_blunders(&self, move_history: &[(u64, bool)], computer_first: bool) -> Vec<usize> {
+        move_history
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &(_, is_computer))| {
+                let before = self.get(index)?.score;
+                let after = self.get(index + 1)?.score;
+                is_blunder(before, after, is_computer == computer_first).then_some(index + 1)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gets a cache with the evaluations of the positions, none for the ones not evaluated.
+    fn get_cache(scores: &[Option<f32>]) -> EvalCache {
+        let mut cache = EvalCache::default();
+        for (ply, score) in scores.iter().enumerate() {
+            if let Some(score) = *score {
+                let entry = EvalEntry {
+                    score,
+                    best_move: None,
+                };
+                cache.insert(ply, entry);
+            }
+        }
+        cache
+    }
+
+    #[test]
+    fn test_blunder_is_a_drop_for_the_mover() {
+        assert!(is_blunder(0.0, -0.6, true));
+        assert!(!is_blunder(0.0, -0.4, true));
+        assert!(!is_blunder(0.0, 0.9, true));
+        assert!(is_blunder(0.0, 0.6, false));
+        assert!(!is_blunder(0.3, 0.7, false));
+        assert!(!is_blunder(0.9, -0.9, false));
+    }
+
+    #[test]
+    fn test_scores_are_from_the_first_player() {
+        assert_eq!(get_final_score(GameResult::FirstPlayerWon), 1.0);
+        assert_eq!(get_final_score(GameResult::SecondPlayerTimeout), 1.0);
+        assert_eq!(get_final_score(GameResult::SecondPlayerWon), -1.0);
+        assert_eq!(get_final_score(GameResult::Draw), 0.0);
+
+        for computer_first in [false, true] {
+            let mut board = BitBoard::new();
+            board.set_computer_first(computer_first);
+            assert_eq!(get_first_player_score(&board, 0.4), 0.4);
+            board.play(3).unwrap();
+            assert_eq!(get_first_player_score(&board, 0.4), -0.4);
+        }
+    }
+
+    #[test]
+    fn test_blunders_of_the_game() {
+        // The second player blunders with the first move, the first player with the second one.
+        let scores = [0.0, 0.1, 0.8, 0.1, 0.3].map(Some);
+        for computer_first in [false, true] {
+            let mut board = BitBoard::new();
+            board.set_computer_first(computer_first);
+            for column in [3, 3, 4, 4] {
+                board.play(column).unwrap();
+            }
+            let history = board.get_move_history();
+            assert_eq!(
+                get_cache(&scores).get_blunders(history, computer_first),
+                [2, 3]
+            );
+        }
+
+        // A move can only be classified, when both positions around it are evaluated.
+        let board = BitBoard::from_move_string("4455").unwrap();
+        let history = board.get_move_history();
+        let scores = [Some(0.0), None, Some(0.8), Some(0.1), Some(0.3)];
+        assert_eq!(
+            get_cache(&scores).get_blunders(history, board.get_computer_first()),
+            [3]
+        );
+    }
+
+    /// Gets the evaluation with the score and no best move.
+    fn get_entry(score: f32) -> EvalEntry {
+        EvalEntry {
+            score,
+            best_move: None,
+        }
+    }
+
+    #[test]
+    fn test_in_flight_ply_is_not_requested_again() {
+        let mut cache = EvalCache::default();
+        assert!(cache.needs_request(2));
+        cache.mark_requested(2, 7);
+        assert!(!cache.needs_request(2));
+        assert!(cache.needs_request(1));
+        assert!(cache.fill(2, 7, get_entry(0.5)));
+        assert!(!cache.needs_request(2));
+        assert_eq!(cache.get(2), Some(get_entry(0.5)));
+        assert_eq!(cache.get_scores(2), [None, None, Some(0.5)]);
+    }
+
+    #[test]
+    fn test_results_fill_the_same_cache_in_any_order() {
+        let results = [(0, 1, 0.0), (1, 2, 0.1), (2, 3, -0.2), (3, 4, 0.3)];
+        let mut caches = Vec::new();
+        for order in [[0, 1, 2, 3], [3, 2, 1, 0], [2, 0, 3, 1]] {
+            let mut cache = EvalCache::default();
+            for &(ply, generation, _) in &results {
+                cache.mark_requested(ply, generation);
+            }
+            for index in order {
+                let (ply, generation, score) = results[index];
+                assert!(cache.fill(ply, generation, get_entry(score)));
+            }
+            caches.push(cache);
+        }
+        assert_eq!(caches[0], caches[1]);
+        assert_eq!(caches[0], caches[2]);
+        assert_eq!(caches[0].get_scores(3), [0.0, 0.1, -0.2, 0.3].map(Some));
+    }
+
+    #[test]
+    fn test_stale_results_are_dropped() {
+        let mut cache = EvalCache::default();
+        cache.mark_requested(1, 5);
+        cache.mark_requested(1, 6);
+        assert!(!cache.fill(1, 5, get_entry(0.9)));
+        assert!(cache.fill(1, 6, get_entry(0.2)));
+        assert_eq!(cache.get(1), Some(get_entry(0.2)));
+
+        // A ply, that has not been requested, takes no result.
+        assert!(!cache.fill(2, 6, get_entry(0.9)));
+        assert_eq!(cache.get(2), None);
+    }
+
+    #[test]
+    fn test_undo_truncates_the_cache() {
+        let mut cache = get_cache(&[0.0, 0.1, 0.2].map(Some));
+        cache.mark_requested(3, 8);
+        cache.mark_requested(4, 9);
+        cache.truncate(1);
+        assert_eq!(cache.get_scores(2), [Some(0.0), Some(0.1), None]);
+        // The requests for the taken back positions are forgotten, their late results dropped.
+        assert!(!cache.fill(3, 8, get_entry(0.3)));
+        assert!(cache.needs_request(2) && cache.needs_request(3) && cache.needs_request(4));
+
+        cache.clear();
+        assert_eq!(cache, EvalCache::default());
+    }
+
+    #[test]
+    fn test_failed_request_gets_requested_again() {
+        let mut cache = EvalCache::default();
+        cache.mark_requested(3, 4);
+        cache.mark_requested(3, 5);
+        // The failure of the replaced request does not touch the later one.
+        cache.forget(3, 4);
+        assert!(!cache.needs_request(3));
+        cache.forget(3, 5);
+        assert!(cache.needs_request(3));
+        assert!(!cache.fill(3, 5, get_entry(0.1)));
+    }
+}
@@ -0,0 +1,67 @@
+//! Buttons get activated like in most user interfaces: the mouse has to be pressed and released over the
+//! same button. A press, that gets dragged off the button before the release, cancels the click, so that
+//! misclicks can be taken back.
+
+/// Keeps track of the button the mouse got pressed on. The states hand over the button under the mouse,
+/// when it gets pressed and released, and get the button back, that is to be activated.
+pub struct ButtonPress<T> {
+    /// The button the mouse got pressed on, none if it got pressed elsewhere.
+    pressed: Option<T>,
+}
+
+impl<T: Copy + PartialEq> ButtonPress<T> {
+    pub fn new() -> ButtonPress<T> {
+        ButtonPress { pressed: None }
+    }
+
+    /// Remembers the button under the mouse, when it gets pressed.
+    pub fn press(&mut self, button: Option<T>) {
+        self.pressed = button;
+    }
+
+    /// Gets the button to activate, when the mouse gets released over the indicated button. This is only
+    /// the case, if it is the same button the mouse got pressed on.
+    pub fn release(&mut self, button: Option<T>) -> Option<T> {
+        let pressed = self.pressed.take()?;
+        (button == Some(pressed)).then_some(pressed)
+    }
+
+    /// Forgets the press, used when a state gets entered.
+    pub fn cancel(&mut self) {
+        self.pressed = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_press_and_release_on_the_same_button() {
+        let mut button_press = ButtonPress::new();
+        button_press.press(Some(1));
+        assert_eq!(button_press.release(Some(1)), Some(1));
+        // The release is only counted once.
+        assert_eq!(button_press.release(Some(1)), None);
+    }
+
+    #[test]
+    fn test_dragging_off_cancels_the_click() {
+        let mut button_press = ButtonPress::new();
+        button_press.press(Some(1));
+        assert_eq!(button_press.release(Some(2)), None);
+        button_press.press(Some(1));
+        assert_eq!(button_press.release(None), None);
+        // A press beside the buttons activates nothing, wherever the mouse gets released.
+        button_press.press(None);
+        assert_eq!(button_press.release(Some(1)), None);
+    }
+
+    #[test]
+    fn test_cancelled_press_is_forgotten() {
+        let mut button_press = ButtonPress::new();
+        button_press.press(Some('a'));
+        button_press.cancel();
+        assert_eq!(button_press.release(Some('a')), None);
+    }
+}
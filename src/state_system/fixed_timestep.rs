@@ -0,0 +1,115 @@
+//! The logic of the states advances in fixed time steps, independent of the frame rate of the monitor.
+//! The time of the frames gets accumulated and as many steps are taken, as fit into it, the rest is
+//! carried over to the next frame. This way animations, timers and clocks behave the same at 60 Hz and at
+//! 144 Hz. After a long stall, like a window drag, only a limited amount of steps is taken, so that the
+//! game does not spend the next frames catching up.
+
+/// The time in seconds one update of the logic advances.
+pub const FIXED_DELTA_TIME: f32 = 1.0 / 120.0;
+
+/// The maximum amount of updates per frame, the time beyond that is dropped.
+pub const MAX_STEPS_PER_FRAME: u32 = 8;
+
+/// Accumulates the time of the frames and hands it out in fixed steps.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct FixedTimestep {
+    /// The time, that has passed and not been handed out as a step yet.
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new() -> FixedTimestep {
+        FixedTimestep::default()
+    }
+
+    /// Adds the time of the frame and gets the amount of fixed steps the logic has to be updated. With
+    /// more than [`MAX_STEPS_PER_FRAME`] steps the remaining time is dropped. Negative frame times, as a
+    /// clock may report them, count as no time.
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time.max(0.0);
+        let steps = (self.accumulator / FIXED_DELTA_TIME) as u32;
+        if steps > MAX_STEPS_PER_FRAME {
+            self.accumulator = 0.0;
+            return MAX_STEPS_PER_FRAME;
+        }
+        self.accumulator -= steps as f32 * FIXED_DELTA_TIME;
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::HeadlessGame;
+    use crate::state_player_start_selection::PLAYER_STARTS_BUTTON;
+
+    /// Gets the time in steps, that has not been handed out yet.
+    fn get_carried_steps(timestep: &FixedTimestep) -> f32 {
+        timestep.accumulator / FIXED_DELTA_TIME
+    }
+
+    #[test]
+    fn test_frames_add_up_to_steps() {
+        let mut timestep = FixedTimestep::new();
+        // Two short frames make one step, the rest is carried over.
+        assert_eq!(timestep.advance(FIXED_DELTA_TIME * 0.6), 0);
+        assert_eq!(timestep.advance(FIXED_DELTA_TIME * 0.6), 1);
+        assert!((get_carried_steps(&timestep) - 0.2).abs() < 1e-4);
+        assert_eq!(timestep.advance(FIXED_DELTA_TIME * 2.5), 2);
+        assert_eq!(timestep.advance(FIXED_DELTA_TIME * 0.5), 1);
+        assert!((get_carried_steps(&timestep) - 0.2).abs() < 1e-4);
+
+        // A negative frame time counts as none.
+        assert_eq!(timestep.advance(-1.0), 0);
+        assert!((get_carried_steps(&timestep) - 0.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_tiny_frames_are_not_lost() {
+        // The rounding may hold a step back for a frame, but the time is kept.
+        let mut timestep = FixedTimestep::new();
+        let steps: u32 = (0..1000)
+            .map(|_| timestep.advance(FIXED_DELTA_TIME / 100.0))
+            .sum();
+        assert!((steps as f32 + get_carried_steps(&timestep) - 10.0).abs() < 1e-3);
+        assert_eq!(timestep.advance(FIXED_DELTA_TIME / 100.0) + steps, 10);
+    }
+
+    #[test]
+    fn test_spike_is_capped() {
+        let mut timestep = FixedTimestep::new();
+        assert_eq!(timestep.advance(2.0), MAX_STEPS_PER_FRAME);
+        // The time beyond the cap is dropped, the next frame starts afresh.
+        assert_eq!(timestep.advance(FIXED_DELTA_TIME), 1);
+
+        // Just at the cap nothing gets dropped.
+        let mut timestep = FixedTimestep::new();
+        let cap_time = MAX_STEPS_PER_FRAME as f32 * FIXED_DELTA_TIME;
+        assert_eq!(
+            timestep.advance(cap_time + FIXED_DELTA_TIME / 2.0),
+            MAX_STEPS_PER_FRAME
+        );
+        assert_eq!(timestep.advance(FIXED_DELTA_TIME / 2.0), 1);
+    }
+
+    /// Plays a short game at the frame rate and gets the transitions of the states and the moves.
+    fn play_at_frame_rate(
+        frame_rate: f32,
+    ) -> (Vec<crate::state_driver::Transition>, Vec<(u64, bool)>) {
+        let mut game = HeadlessGame::new();
+        game.frame_time = 1.0 / frame_rate;
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&[3, 3, 4, 4, 5, 5, 6]);
+        let moves = game.black_board.game_board.get_move_history().to_vec();
+        (game.transitions, moves)
+    }
+
+    #[test]
+    fn test_frame_rate_does_not_change_the_game() {
+        let (transitions, moves) = play_at_frame_rate(30.0);
+        assert_eq!(moves.len(), 7);
+        assert_eq!(play_at_frame_rate(144.0), (transitions, moves));
+    }
+}
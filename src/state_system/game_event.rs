@@ -0,0 +1,89 @@
+//! The events are the way states hand information over to each other. A state posts them on the
+//! blackboard and the state, that runs next, consumes them in its update. Every event gets consumed
+//! exactly once and in the order it has been posted, events a state is not interested in are dropped.
+
+use connect_4_rust::board_logic::bit_board::{GameResult, Side};
+use std::mem;
+
+/// Something that happened in one state, that a later state needs to know about.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    /// A side has chosen the slot to play, before it could move. This is a click of the player during
+    /// the move of the computer, which gets played as soon as the player is to move.
+    MoveChosen {
+        slot: u32,
+        by: Side,
+        /// The logic time of the blackboard, when the choice was made.
+        chosen_at: f64,
+    },
+    /// A side has played the slot, the board, the move history and the record of the game have been
+    /// updated with it.
+    MoveCommitted { slot: u32, by: Side },
+    /// The game has ended with the result, it has to be counted in the scores.
+    GameEnded { result: GameResult },
+}
+
+/// The events, that have been posted and not been consumed yet, in the order they got posted.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct EventQueue {
+    events: Vec<GameEvent>,
+}
+
+impl EventQueue {
+    /// Appends the event at the end of the queue.
+    pub fn post(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// Takes all events out of the queue in the order they got posted.
+    pub fn drain(&mut self) -> Vec<GameEvent> {
+        mem::take(&mut self.events)
+    }
+
+    /// Drops all events, that have not been consumed.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_drained_once_in_order() {
+        let events = [
+            GameEvent::MoveCommitted {
+                slot: 3,
+                by: Side::Player,
+            },
+            GameEvent::MoveChosen {
+                slot: 2,
+                by: Side::Player,
+                chosen_at: 1.5,
+            },
+            GameEvent::MoveCommitted {
+                slot: 4,
+                by: Side::Computer,
+            },
+            GameEvent::GameEnded {
+                result: GameResult::Draw,
+            },
+        ];
+        let mut queue = EventQueue::default();
+        for event in events {
+            queue.post(event);
+        }
+        assert_eq!(queue.drain(), events);
+        assert!(queue.drain().is_empty());
+
+        // The events posted after a drain come with the next one.
+        queue.post(events[3]);
+        queue.post(events[0]);
+        assert_eq!(queue.drain(), [events[3], events[0]]);
+
+        queue.post(events[1]);
+        queue.clear();
+        assert!(queue.drain().is_empty());
+    }
+}
@@ -1,52 +1,978 @@
 //! This module contains the trait of all states and contains a blackboard,
 //! over which states can exchange information.
 
-use crate::board_logic::ai_handler::AiHandler;
-use crate::board_logic::bit_board::BitBoard;
+use crate::render_system::audio::{SoundBank, SoundEffect};
+use crate::render_system::graphics::{SMALL_TEXT_SIZE, print_text_sized};
+use crate::render_system::toast::{TOAST_DURATION, ToastQueue};
 use crate::state_game_over::StateGameOver;
 use crate::state_player_start_selection::StatePlayerStartSelection;
+use crate::state_system::analysis::EvalCache;
+use crate::state_system::game_event::{EventQueue, GameEvent};
+use crate::state_system::player_stats::{Outcome, PlayerStats, STATS_PATH};
+use crate::state_system::settings::{Handicap, SETTINGS_PATH, Settings};
+use crate::state_system::state_board_editor::StateBoardEditor;
 use crate::state_system::state_computer_execution::StateComputerExecution;
+use crate::state_system::state_connect_five::StateConnectFive;
+use crate::state_system::state_difficulty_selection::StateDifficultySelection;
+use crate::state_system::state_help::StateHelp;
+use crate::state_system::state_match_score::StateMatchScore;
 use crate::state_system::state_player_input::StatePlayerInput;
+use crate::state_system::state_replay::StateReplay;
+use crate::state_system::state_settings::StateSettings;
+use connect_4_rust::board_logic::alpha_beta::SearchConfig;
+use connect_4_rust::board_logic::bit_board::{BitBoard, GameResult, MoveError, RuleSet, Side};
+use connect_4_rust::board_logic::bit_board_coding::{BOARD_WIDTH, WinningLine};
+use connect_4_rust::board_logic::notation::describe_win;
+use connect_4_rust::board_logic::player::{EnginePlayer, HumanMousePlayer, Player};
+use connect_4_rust::network::{RemotePlayer, Role};
+use connect_4_rust::records::{GameRecord, PlayerKind, RecordResult, get_unix_time};
+use connect_4_rust::selfplay::SplitMix64;
 use macroquad::math::Vec2;
-use macroquad::prelude::Texture2D;
+use macroquad::prelude::{KeyCode, Texture2D};
+use std::collections::HashMap;
+use std::fmt;
 
-/// All implemented game states get an index, with which they can refer to each other.
+/// All implemented game states get an index, with which they can refer to each other. The states are
+/// looked up by the index in the [`StateRegistry`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum GameStateIndex {
-    StartSelection = 0,
-    ComputerExecutionState = 1,
-    PlayerInputState = 2,
-    GameOverState = 3,
-}
-
-/// Generates a vector with all the required game states.
-pub fn generate_state_collection() -> Vec<Box<dyn GameState>> {
-    let result: Vec<Box<dyn GameState>> = vec![
-        Box::new(StatePlayerStartSelection::new()),
-        Box::new(StateComputerExecution::new()),
-        Box::new(StatePlayerInput::new()),
-        Box::new(StateGameOver::new()),
+    StartSelection,
+    ComputerExecutionState,
+    PlayerInputState,
+    GameOverState,
+    ReplayState,
+    DifficultySelection,
+    Settings,
+    Help,
+    MatchScore,
+    BoardEditor,
+    ConnectFive,
+}
+
+impl GameStateIndex {
+    /// All indices, every one of them needs a registered state.
+    pub const ALL: [GameStateIndex; 11] = [
+        GameStateIndex::StartSelection,
+        GameStateIndex::ComputerExecutionState,
+        GameStateIndex::PlayerInputState,
+        GameStateIndex::GameOverState,
+        GameStateIndex::ReplayState,
+        GameStateIndex::DifficultySelection,
+        GameStateIndex::Settings,
+        GameStateIndex::Help,
+        GameStateIndex::MatchScore,
+        GameStateIndex::BoardEditor,
+        GameStateIndex::ConnectFive,
     ];
-    result
 }
 
-/// A helper structure that is used by game states to exchange information.
+/// Holds the game states by their index, so that a transition returned by a state can be resolved.
+pub struct StateRegistry {
+    states: HashMap<GameStateIndex, Box<dyn GameState>>,
+}
+
+impl StateRegistry {
+    /// Creates the registry with all the required game states.
+    pub fn new() -> StateRegistry {
+        let states: [(GameStateIndex, Box<dyn GameState>); 11] = [
+            (
+                GameStateIndex::StartSelection,
+                Box::new(StatePlayerStartSelection::new()),
+            ),
+            (
+                GameStateIndex::ComputerExecutionState,
+                Box::new(StateComputerExecution::new()),
+            ),
+            (
+                GameStateIndex::PlayerInputState,
+                Box::new(StatePlayerInput::new()),
+            ),
+            (
+                GameStateIndex::GameOverState,
+                Box::new(StateGameOver::new()),
+            ),
+            (GameStateIndex::ReplayState, Box::new(StateReplay::new())),
+            (
+                GameStateIndex::DifficultySelection,
+                Box::new(StateDifficultySelection::new()),
+            ),
+            (GameStateIndex::Settings, Box::new(StateSettings::new())),
+            (GameStateIndex::Help, Box::new(StateHelp::new())),
+            (GameStateIndex::MatchScore, Box::new(StateMatchScore::new())),
+            (
+                GameStateIndex::BoardEditor,
+                Box::new(StateBoardEditor::new()),
+            ),
+            (
+                GameStateIndex::ConnectFive,
+                Box::new(StateConnectFive::new()),
+            ),
+        ];
+        let registry = StateRegistry {
+            states: HashMap::from(states),
+        };
+        debug_assert!(
+            GameStateIndex::ALL
+                .iter()
+                .all(|index| registry.states.contains_key(index)),
+            "Every game state index needs a registered state."
+        );
+        registry
+    }
+
+    /// Gets the state with the indicated index.
+    pub fn get_mut(&mut self, index: GameStateIndex) -> &mut dyn GameState {
+        self.states
+            .get_mut(&index)
+            .unwrap_or_else(|| panic!("No state registered for {index:?}."))
+            .as_mut()
+    }
+}
+
+/// Counts the results of the games played in the session.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SessionScore {
+    pub player_wins: u32,
+    pub computer_wins: u32,
+    pub draws: u32,
+}
+
+impl SessionScore {
+    /// Counts the result of a finished game. The flag, if the computer went first, is needed to find
+    /// out who the winner is.
+    pub fn record(&mut self, result: GameResult, computer_first: bool) {
+        match result {
+            GameResult::Pending => panic!("Only finished games can be recorded."),
+            GameResult::Draw => self.draws += 1,
+            _ if result.is_first_player_win() == computer_first => self.computer_wins += 1,
+            _ => self.player_wins += 1,
+        }
+    }
+
+    /// Starts counting from zero again.
+    pub fn reset(&mut self) {
+        *self = SessionScore::default();
+    }
+}
+
+impl fmt::Display for SessionScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "You {}, Computer {}, Draws {}",
+            self.player_wins, self.computer_wins, self.draws
+        )
+    }
+}
+
+/// Gets the side, that plays against the indicated one.
+fn get_other_side(side: Side) -> Side {
+    if side.is_computer() {
+        Side::Player
+    } else {
+        Side::Computer
+    }
+}
+
+/// A match over several games, the side that first wins the target amount of games wins the match.
+/// The sides take turns in starting the games. Draws do not count for either side, so a match may take
+/// more games than its length.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MatchState {
+    /// The amount of won games needed to win the match.
+    pub target_wins: u32,
+    /// The results of the finished games in the order they got played.
+    pub games: Vec<GameResult>,
+    /// The side starting the game after the finished ones.
+    pub next_starter: Side,
+}
+
+impl MatchState {
+    /// Creates a match, that is played as best of the indicated amount of games. The starter begins
+    /// the first game.
+    pub fn new(best_of: u32, starter: Side) -> MatchState {
+        MatchState {
+            target_wins: best_of / 2 + 1,
+            games: Vec::new(),
+            next_starter: starter,
+        }
+    }
+
+    /// Gets the amount of games the match is played as best of.
+    pub fn get_best_of(&self) -> u32 {
+        2 * self.target_wins - 1
+    }
+
+    /// Gets the side, that started the finished game with the indicated index.
+    pub fn get_starter(&self, index: usize) -> Side {
+        if (self.games.len() - index).is_multiple_of(2) {
+            self.next_starter
+        } else {
+            get_other_side(self.next_starter)
+        }
+    }
+
+    /// Counts the result of a finished game and lets the other side start the next game.
+    pub fn record(&mut self, result: GameResult) {
+        assert_ne!(
+            result,
+            GameResult::Pending,
+            "Only finished games can be recorded."
+        );
+        debug_assert!(!self.is_decided(), "The match is over already.");
+        self.games.push(result);
+        self.next_starter = get_other_side(self.next_starter);
+    }
+
+    /// Gets the side, that won the finished game with the indicated index, none for a draw.
+    pub fn get_game_winner(&self, index: usize) -> Option<Side> {
+        let result = self.games[index];
+        if result == GameResult::Draw {
+            return None;
+        }
+        let computer_first = self.get_starter(index).is_computer();
+        Some(if result.is_first_player_win() == computer_first {
+            Side::Computer
+        } else {
+            Side::Player
+        })
+    }
+
+    /// Gets the amount of games won by the player and by the computer.
+    pub fn get_wins(&self) -> (u32, u32) {
+        let winners = (0..self.games.len()).filter_map(|index| self.get_game_winner(index));
+        winners.fold((0, 0), |(player_wins, computer_wins), winner| {
+            if winner.is_computer() {
+                (player_wins, computer_wins + 1)
+            } else {
+                (player_wins + 1, computer_wins)
+            }
+        })
+    }
+
+    /// Gets the side, that has won the match, none while the match is still running.
+    pub fn get_winner(&self) -> Option<Side> {
+        let (player_wins, computer_wins) = self.get_wins();
+        if player_wins >= self.target_wins {
+            Some(Side::Player)
+        } else if computer_wins >= self.target_wins {
+            Some(Side::Computer)
+        } else {
+            None
+        }
+    }
+
+    /// Checks, if one side has won enough games and the match is over.
+    pub fn is_decided(&self) -> bool {
+        self.get_winner().is_some()
+    }
+}
+
+impl fmt::Display for MatchState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (player_wins, computer_wins) = self.get_wins();
+        write!(
+            f,
+            "Best of {}: You {}, Computer {}",
+            self.get_best_of(),
+            player_wins,
+            computer_wins
+        )
+    }
+}
+
+/// The remaining thinking time of both sides in seconds for games played with a clock.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameClock {
+    pub player_remaining: f32,
+    pub computer_remaining: f32,
+}
+
+/// The position of the clock of the player.
+const PLAYER_CLOCK_POSITION: Vec2 = Vec2 { x: 110.0, y: 640.0 };
+/// The position of the clock of the computer.
+const COMPUTER_CLOCK_POSITION: Vec2 = Vec2 { x: 270.0, y: 640.0 };
+/// The position of the status line above the clocks.
+const STATUS_POSITION: Vec2 = Vec2 { x: 110.0, y: 668.0 };
+
+impl GameClock {
+    /// Creates a clock, where both sides have the indicated time in seconds.
+    pub fn new(time_per_side: f32) -> GameClock {
+        GameClock {
+            player_remaining: time_per_side,
+            computer_remaining: time_per_side,
+        }
+    }
+
+    /// Gets the seconds the clock shows for the remaining time, they are rounded up, so that a clock
+    /// only shows zero, when the time is over.
+    fn get_shown_seconds(seconds: f32) -> u32 {
+        seconds.max(0.0).ceil() as u32
+    }
+
+    /// Formats the time as minutes and seconds.
+    fn format_time(seconds: f32) -> String {
+        let seconds = GameClock::get_shown_seconds(seconds);
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+
+    /// Draws both clocks above the board.
+    pub fn draw(&self) {
+        print_text_sized(
+            &format!("You {}", GameClock::format_time(self.player_remaining)),
+            PLAYER_CLOCK_POSITION,
+            SMALL_TEXT_SIZE,
+        );
+        print_text_sized(
+            &format!("AI {}", GameClock::format_time(self.computer_remaining)),
+            COMPUTER_CLOCK_POSITION,
+            SMALL_TEXT_SIZE,
+        );
+    }
+}
+
+/// Converts the score of the search from the perspective of the computer into the share of the first
+/// player for the evaluation bar.
+pub fn get_first_player_share(computer_score: f32, computer_first: bool) -> f32 {
+    let first_player_score = if computer_first {
+        computer_score
+    } else {
+        -computer_score
+    };
+    (first_player_score + 1.0) * 0.5
+}
+
+/// A helper structure that is used by game states to exchange information. What happens in one state and
+/// matters for the next one gets posted as an event, the board, the ai and the settings are plain fields.
 pub struct Blackboard {
     /// The general board, that show the current game.
     pub game_board: BitBoard,
-    /// The ai handler for the threaded Ai.
-    pub ai_system: AiHandler,
-    /// The pre-computed board texture with holes.
-    pub board_texture: Texture2D,
+    /// The person in front of the screen, the player input feeds the clicks in.
+    pub human_player: HumanMousePlayer,
+    /// The threaded ai, it also computes the hints and the analysis.
+    pub computer_player: EnginePlayer,
+    /// The opponent on the other side of the network, who takes the place of the computer. None, if the
+    /// computer is the opponent.
+    pub remote_player: Option<RemotePlayer>,
+    /// The pre-computed board texture with holes. None, until the main loop creates it, a headless run
+    /// renders nothing and never does.
+    pub board_texture: Option<Texture2D>,
+    /// The texture of the Connect 5 board, it gets created along with the one of the standard board.
+    pub connect_five_texture: Option<Texture2D>,
+    /// The configuration of the search chosen in the difficulty selection.
+    pub search_config: SearchConfig,
+    /// The results of the games played so far.
+    pub session_score: SessionScore,
+    /// The side chosen to start on the start screen the last time, it gets preselected there for the next
+    /// game. None, before the first choice of the session.
+    pub last_starter: Option<Side>,
+    /// The match being played, none if single games are played.
+    pub match_state: Option<MatchState>,
+    /// The result of a game, that did not end on the board, like a resignation.
+    pub game_result_override: Option<GameResult>,
+    /// The explanation shown on game over, if the game ended for a reason, that can not be seen on the
+    /// board, like a lost connection.
+    pub game_over_message: Option<String>,
+    /// The time per side in seconds chosen in the difficulty selection, none if we play without clock.
+    pub time_control: Option<f32>,
+    /// The clock of the running game, if it is played with a clock.
+    pub game_clock: Option<GameClock>,
+    /// The coded move the player made last, none if the player has not moved yet.
+    pub last_player_move: Option<u64>,
+    /// The coded move the computer made last, none if the computer has not moved yet.
+    pub last_computer_move: Option<u64>,
+    /// The events posted by the states, that have not been consumed yet.
+    events: EventQueue,
+    /// The settings chosen by the player, they are kept on disk between the sessions.
+    pub settings: Settings,
+    /// The statistics of the player over all sessions, they are kept on disk like the settings.
+    pub player_stats: PlayerStats,
+    /// The sound effects, they are loaded at startup.
+    pub sound_bank: SoundBank,
+    /// The short messages shown on top of every state.
+    pub toasts: ToastQueue,
+    /// The evaluations of the positions of the game by ply, they are computed for the replay.
+    pub eval_cache: EvalCache,
+    /// The random generator of the game, it gets seeded at the start. The seed is recorded with the
+    /// session, so that a replay draws the same numbers.
+    pub random: SplitMix64,
+    /// The time in seconds the logic has advanced since the start, every update adds its fixed step.
+    /// Unlike the time of the system it is the same, when a recorded session gets replayed.
+    pub logic_time: f64,
+    /// The settings get stored on disk, when they are saved, the statistics of the player get loaded and
+    /// stored and the records of the games get exported. This is switched off in a replay, which runs
+    /// with the recorded settings, and on the web, where there is no disk.
+    pub persist_settings: bool,
+    /// The record of the running game, it is started with the first turn. None, before the first game.
+    pub game_record: Option<GameRecord>,
+    /// The position set up in the board editor. It is kept, when the editor is left, so that the next
+    /// visit starts with it, and can be replaced by a position pasted from the clipboard.
+    pub editor_position: BitBoard,
+    /// The text, that has to be copied to the clipboard. The main loop takes it, as the states can not
+    /// reach the clipboard.
+    pub clipboard_text: Option<String>,
+    /// The running game started from a position set up in the board editor. It has no record and does not
+    /// count in the statistics of the player.
+    pub edited_position: bool,
+    /// The logic time, when the running game started.
+    game_started_at: f64,
+    /// A state has changed its picture, while it is idle, so the main loop has to draw it anew, see
+    /// [`crate::idle_governor`].
+    redraw_requested: bool,
 }
 
 impl Blackboard {
-    pub fn new(texture: Texture2D) -> Blackboard {
+    pub fn new() -> Blackboard {
         Blackboard {
             game_board: BitBoard::new(),
-            ai_system: AiHandler::new(),
-            board_texture: texture,
+            human_player: HumanMousePlayer::new(),
+            computer_player: EnginePlayer::new(),
+            remote_player: None,
+            board_texture: None,
+            connect_five_texture: None,
+            search_config: SearchConfig::default(),
+            session_score: SessionScore::default(),
+            last_starter: None,
+            match_state: None,
+            game_result_override: None,
+            game_over_message: None,
+            time_control: None,
+            game_clock: None,
+            last_player_move: None,
+            last_computer_move: None,
+            events: EventQueue::default(),
+            settings: Settings::default(),
+            player_stats: PlayerStats::default(),
+            sound_bank: SoundBank::default(),
+            toasts: ToastQueue::default(),
+            eval_cache: EvalCache::default(),
+            random: SplitMix64::new(0),
+            logic_time: 0.0,
+            persist_settings: cfg!(not(target_arch = "wasm32")),
+            game_record: None,
+            editor_position: BitBoard::new(),
+            clipboard_text: None,
+            edited_position: false,
+            game_started_at: 0.0,
+            redraw_requested: false,
+        }
+    }
+
+    /// Gets the result of the game, which is the overridden one, if the game did not end on the board.
+    pub fn get_game_result(&self) -> GameResult {
+        self.game_result_override
+            .unwrap_or_else(|| self.game_board.get_winning_status_for_rendering().0)
+    }
+
+    /// Gets the lines, with which the game has been won on the board. There are none, if the game did not
+    /// end on the board, like when a player ran out of time.
+    pub fn get_winning_lines(&self) -> Vec<WinningLine> {
+        if self.game_result_override.is_some() {
+            return Vec::new();
+        }
+        let (_, lines) = self.game_board.get_winning_status_for_rendering();
+        lines.unwrap_or_default()
+    }
+
+    /// Gets the description of the win on the board, see [`describe_win`], none if the game has not been
+    /// won on the board.
+    pub fn get_win_description(&self) -> Option<String> {
+        describe_win(&self.get_winning_lines())
+    }
+
+    /// Gets the text telling, whose turn it is or how the game has ended.
+    pub fn status_text(&self) -> String {
+        let first_color = |is_first: bool| if is_first { "yellow" } else { "blue" };
+        match self.get_game_result() {
+            GameResult::Pending => {
+                let computer_first = self.game_board.get_computer_first();
+                if self.game_board.side_to_move().is_computer() {
+                    format!(
+                        "{}'s turn - {}",
+                        self.get_opponent_name(),
+                        first_color(computer_first)
+                    )
+                } else {
+                    format!("Your turn - {}", first_color(!computer_first))
+                }
+            }
+            GameResult::FirstPlayerWon => "Yellow wins!".to_string(),
+            GameResult::SecondPlayerWon => "Blue wins!".to_string(),
+            GameResult::Draw => "Draw".to_string(),
+            GameResult::FirstPlayerTimeout => "Blue on time".to_string(),
+            GameResult::SecondPlayerTimeout => "Yellow on time".to_string(),
+        }
+    }
+
+    /// Draws the status line above the clocks during the game.
+    pub fn draw_status_line(&self) {
+        print_text_sized(&self.status_text(), STATUS_POSITION, SMALL_TEXT_SIZE);
+    }
+
+    /// Posts the event for the states, that run next.
+    pub fn post(&mut self, event: GameEvent) {
+        self.events.post(event);
+    }
+
+    /// Takes all events posted so far in the order they got posted, so that every event is consumed
+    /// exactly once.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        self.events.drain()
+    }
+
+    /// Posts the end of the game with its result on the transition into game over, which counts it in
+    /// the scores.
+    pub fn post_game_end(&mut self) {
+        let result = self.get_game_result();
+        self.post(GameEvent::GameEnded { result });
+    }
+
+    /// Counts the result of a finished game in the session score and in the match, if one is played.
+    pub fn record_game(&mut self, result: GameResult) {
+        self.session_score
+            .record(result, self.game_board.get_computer_first());
+        if let Some(match_state) = self.match_state.as_mut() {
+            match_state.record(result);
+        }
+    }
+
+    /// Counts the game, that has ended with the result, in the statistics of the player against the
+    /// difficulty played and stores them. The game end is only posted once per game, so a game can not be
+    /// counted twice. Games on the network are not played against the engine and do not count, neither do
+    /// games from a position of the board editor. A failure to store them only costs the statistics, the
+    /// player gets told with a toast.
+    pub fn update_player_stats(&mut self, result: GameResult) {
+        if self.remote_player.is_some() || self.edited_position {
+            return;
+        }
+        let outcome = Outcome::from_game_result(result, self.game_board.get_computer_first());
+        let moves = self.game_board.get_move_history().len() as u32;
+        self.player_stats
+            .record(self.settings.difficulty, outcome, moves);
+        let games = self.player_stats.get(self.settings.difficulty);
+        log::info!(
+            "Rating {:.0}, win streak {} with {} at best, {} games against {:?} with {:.1} moves on average",
+            self.player_stats.rating,
+            self.player_stats.current_streak,
+            self.player_stats.best_streak,
+            games.get_games(),
+            self.settings.difficulty,
+            games.get_average_length()
+        );
+        if !self.persist_settings {
+            return;
+        }
+        if let Err(error) = self.player_stats.save(STATS_PATH) {
+            log::error!("The statistics could not be saved: {error}");
+            self.toasts
+                .push("The statistics could not be saved", TOAST_DURATION);
         }
     }
+
+    /// Loads the statistics of the player from disk. Statistics, that can not be read, are started over
+    /// empty, the player gets told with a toast. Nothing gets loaded, where nothing gets stored.
+    pub fn load_player_stats(&mut self) {
+        if !self.persist_settings {
+            return;
+        }
+        self.player_stats = PlayerStats::load(STATS_PATH).unwrap_or_else(|error| {
+            log::error!("Starting with empty statistics, {error}");
+            self.toasts
+                .push("The statistics could not be read", TOAST_DURATION);
+            PlayerStats::default()
+        });
+    }
+
+    /// Clears the board and starts a new game right away with the indicated starter. When the computer
+    /// starts the first calculation is kicked off. Returns the state the game continues in. A network
+    /// game is played with the classic rules and without handicap, the host tells the guest about it.
+    pub fn start_game(&mut self, computer_first: bool) -> GameStateIndex {
+        self.reset_game();
+        self.game_board.set_computer_first(computer_first);
+        if let Some(remote) = self.remote_player.as_mut() {
+            self.game_board.set_rule_set(RuleSet::Classic);
+            self.game_board.set_handicap(Side::Player, 0);
+            if remote.get_role() == Role::Host {
+                remote.start_game(!computer_first);
+            }
+        }
+        self.begin_turn()
+    }
+
+    /// Starts a game from the position set up in the board editor, the side to move follows from the stones
+    /// on the board. The position has to be validated before. A record can only replay a game from the
+    /// empty board, so the game gets no record.
+    pub fn start_from_position(&mut self, board: &BitBoard) -> GameStateIndex {
+        self.reset_game();
+        self.game_board = board.clone();
+        self.game_board.clear_move_history();
+        self.edited_position = true;
+        self.begin_turn()
+    }
+
+    /// Reads the position from the text pasted from the clipboard into the board editor, it can be a move
+    /// string or an ASCII grid. The player gets told with a toast, how many stones it has and whose turn it
+    /// is, or why it can not be read. Returns if the position has been read.
+    pub fn import_position(&mut self, text: &str) -> bool {
+        match BitBoard::from_position_text(text) {
+            Ok(position) => {
+                let first_to_move =
+                    position.side_to_move().is_computer() == position.get_computer_first();
+                let message = format!(
+                    "Position imported ({} stones, {} to move)",
+                    position.stone_count(),
+                    if first_to_move { "yellow" } else { "blue" }
+                );
+                self.toasts.push(message, TOAST_DURATION);
+                self.editor_position = position;
+                true
+            }
+            Err(error) => {
+                log::warn!("The pasted position can not be read, {error}");
+                self.toasts
+                    .push(format!("Import failed, {error}"), TOAST_DURATION);
+                false
+            }
+        }
+    }
+
+    /// Gets the player, that chooses the moves of the side. The opponent on the network plays for the
+    /// computer, if there is one.
+    pub fn get_player_mut(&mut self, side: Side) -> &mut dyn Player {
+        match (side, self.remote_player.as_mut()) {
+            (Side::Computer, Some(remote)) => remote,
+            (Side::Computer, None) => &mut self.computer_player,
+            (Side::Player, _) => &mut self.human_player,
+        }
+    }
+
+    /// Gets the name of the opponent of the player.
+    pub fn get_opponent_name(&self) -> &str {
+        match &self.remote_player {
+            Some(remote) => remote.name(),
+            None => self.computer_player.name(),
+        }
+    }
+
+    /// Checks if the games are started by the host on the other side of the network.
+    pub fn is_network_guest(&self) -> bool {
+        self.remote_player
+            .as_ref()
+            .is_some_and(|remote| remote.get_role() == Role::Guest)
+    }
+
+    /// Keeps the connection to the opponent on the network going. When the opponent has resigned or the
+    /// connection is lost, the game is over and won by the player, the game over state is returned then.
+    pub fn update_remote_player(&mut self, delta_time: f32) -> Option<GameStateIndex> {
+        let remote = self.remote_player.as_mut()?;
+        remote.update(delta_time);
+        let message = if remote.take_resigned() {
+            "The opponent has resigned".to_string()
+        } else {
+            format!("Game over, {}", remote.take_error()?)
+        };
+        self.game_result_override = Some(if self.game_board.get_computer_first() {
+            GameResult::SecondPlayerWon
+        } else {
+            GameResult::FirstPlayerWon
+        });
+        self.game_over_message = Some(message);
+        self.post_game_end();
+        Some(GameStateIndex::GameOverState)
+    }
+
+    /// Asks the player, who is to move on the game board, for its move. Returns the state, that waits for
+    /// the move.
+    pub fn begin_turn(&mut self) -> GameStateIndex {
+        if self.game_board.get_move_history().is_empty() {
+            if self.edited_position {
+                self.game_record = None;
+            } else {
+                self.start_game_record();
+            }
+        }
+        let side = self.game_board.side_to_move();
+        if side.is_computer() {
+            self.request_computer_move(self.game_board.clone());
+            GameStateIndex::ComputerExecutionState
+        } else {
+            let board = self.game_board.clone();
+            self.get_player_mut(side).request_move(&board);
+            GameStateIndex::PlayerInputState
+        }
+    }
+
+    /// Starts the record of the game, that begins on the empty board.
+    fn start_game_record(&mut self) {
+        let computer = if self.remote_player.is_some() {
+            PlayerKind::Remote
+        } else {
+            PlayerKind::from_search_config(&self.search_config)
+        };
+        let starter = if self.game_board.get_computer_first() {
+            Side::Computer
+        } else {
+            Side::Player
+        };
+        self.game_record = Some(GameRecord::new(
+            PlayerKind::Human,
+            computer,
+            starter,
+            self.game_board.get_rule_set(),
+            get_unix_time(),
+        ));
+        self.game_started_at = self.logic_time;
+    }
+
+    /// Writes the record of the game, that has ended with the result, into the records directory, if
+    /// this is switched on in the settings. A failure only costs the record, the player gets told with a
+    /// toast.
+    pub fn export_game_record(&mut self, result: GameResult) {
+        let win = self.get_win_description();
+        let Some(record) = self.game_record.as_mut() else {
+            return;
+        };
+        record.finish(
+            RecordResult::from_game_result(result, self.game_board.get_computer_first()),
+            win,
+            self.logic_time - self.game_started_at,
+        );
+        if !self.persist_settings || !self.settings.export_records {
+            return;
+        }
+        match record.write_to_directory(&self.settings.records_directory) {
+            Ok(path) => log::info!("The game has been exported to {}", path.display()),
+            Err(error) => {
+                log::error!("The game could not be exported: {error}");
+                self.toasts
+                    .push("The game could not be exported", TOAST_DURATION);
+            }
+        }
+    }
+
+    /// Clears the board, the overridden result, the flag of the edited position, the analysis, the
+    /// transposition tables of the ai and the events not consumed for the next game and sets up the clock
+    /// again.
+    pub fn reset_game(&mut self) {
+        self.game_board.reset();
+        let ai_handler = self.computer_player.get_ai_handler_mut();
+        ai_handler.cancel_evaluations();
+        ai_handler.start_new_game();
+        self.eval_cache.clear();
+        self.game_result_override = None;
+        self.game_over_message = None;
+        self.edited_position = false;
+        self.last_player_move = None;
+        self.last_computer_move = None;
+        self.events.clear();
+        self.reset_clock();
+    }
+
+    /// Commits the move in the slot for the side, which has to be the side to move. This is the only
+    /// place, where the moves of a game get applied, the states call it once per move. The move is played
+    /// on the board, which adds it to the move history, remembered as the last move of the side and added
+    /// to the record of the game, then [`GameEvent::MoveCommitted`] gets posted. The slots behind the
+    /// columns pop the bottom stone out of the column under the Pop Out rules. In a network game the move
+    /// of the player gets sent to the opponent. A move, that gets committed twice, is no longer for the
+    /// side to move, which trips the assertion.
+    pub fn commit_move(&mut self, slot: u32, side: Side) -> Result<(), MoveError> {
+        debug_assert_eq!(
+            side,
+            self.game_board.side_to_move(),
+            "The move in slot {slot} is committed for the side, that is not to move:\n{}",
+            self.game_board
+        );
+        let is_computer = side.is_computer();
+        let coded_move = self.game_board.play_slot(slot)?;
+        if let Some(record) = self.game_record.as_mut() {
+            let evaluation = if is_computer && self.remote_player.is_none() {
+                self.computer_player.get_ai_handler().get_last_move_score()
+            } else {
+                None
+            };
+            record.record_move(
+                side,
+                slot,
+                self.logic_time - self.game_started_at,
+                evaluation,
+            );
+        }
+        if is_computer {
+            self.last_computer_move = Some(coded_move);
+        } else {
+            self.last_player_move = Some(coded_move);
+            if let Some(remote) = self.remote_player.as_mut() {
+                remote.send_move(slot);
+            }
+        }
+        self.post(GameEvent::MoveCommitted { slot, by: side });
+        Ok(())
+    }
+
+    /// Decides what a click on the board does. A click on the bottom row comes as a slot behind the
+    /// columns and pops the stone there, if the rules allow it and it is a stone of the player. Otherwise
+    /// the stone gets dropped into the column.
+    pub fn resolve_slot(&self, slot: u32) -> u32 {
+        if slot < BOARD_WIDTH {
+            return slot;
+        }
+        let column = slot - BOARD_WIDTH;
+        if self.game_board.can_pop(column, false) {
+            slot
+        } else {
+            column
+        }
+    }
+
+    /// Takes back the last move of both sides, also in the record of the game and the evaluations. The
+    /// last moves are looked up again in the move history.
+    pub fn undo_last_full_turn(&mut self) {
+        self.game_board.undo_last_full_turn();
+        let history = self.game_board.get_move_history();
+        if let Some(record) = self.game_record.as_mut() {
+            record.truncate_moves(history.len());
+        }
+        self.eval_cache.truncate(history.len());
+        let find_last_move = |computer: bool| {
+            history
+                .iter()
+                .rev()
+                .find(|&&(_, is_computer)| is_computer == computer)
+                .map(|&(coded_move, _)| coded_move)
+        };
+        self.last_player_move = find_last_move(false);
+        self.last_computer_move = find_last_move(true);
+    }
+
+    /// Gets the coded move, that has been made last in the game, that is the one of the side not to move.
+    pub fn get_most_recent_move(&self) -> Option<u64> {
+        if self.game_board.side_to_move().is_computer() {
+            self.last_player_move
+        } else {
+            self.last_computer_move
+        }
+    }
+
+    /// Plays the sound effect, unless the sound is switched off in the settings.
+    pub fn play_sound(&self, effect: SoundEffect) {
+        if self.settings.sound_enabled {
+            self.sound_bank.play(effect);
+        }
+    }
+
+    /// Hands the difficulty of the settings over to the ai and takes over the clock, the handicap and the
+    /// rules for the next game.
+    pub fn apply_settings(&mut self) {
+        self.search_config = self.settings.difficulty.get_search_config();
+        self.configure_ai();
+        self.time_control = self.settings.clock.get_time_per_side();
+        self.game_board
+            .set_handicap(Side::Player, self.settings.handicap.get_extra_stones());
+        self.game_board.set_rule_set(self.settings.rule_set);
+    }
+
+    /// Hands the configuration of the search over to the ai. If the worker can not be reached, the
+    /// computer keeps playing with the configuration it has.
+    pub fn configure_ai(&mut self) {
+        if let Err(error) = self
+            .computer_player
+            .get_ai_handler_mut()
+            .configure(self.search_config)
+        {
+            log::warn!("The difficulty could not be handed over to the ai: {error}");
+        }
+    }
+
+    /// Kicks off the search for the computer move on the board handed over. With the center handicap the
+    /// computer may not play the center column with its first stone, but not in a position of the board
+    /// editor, where the stones are already there. In a network game the opponent gets asked instead.
+    pub fn request_computer_move(&mut self, board: BitBoard) {
+        if let Some(remote) = self.remote_player.as_mut() {
+            remote.request_move(&board);
+            return;
+        }
+        let is_first_computer_move = !self.edited_position
+            && !board
+                .get_move_history()
+                .iter()
+                .any(|&(_, is_computer)| is_computer);
+        let banned_columns =
+            if self.settings.handicap == Handicap::NoCenter && is_first_computer_move {
+                1 << (BOARD_WIDTH / 2)
+            } else {
+                0
+            };
+        self.computer_player.set_banned_columns(banned_columns);
+        self.computer_player.request_move(&board);
+    }
+
+    /// Stores the settings on disk, so that they are there in the next session. A failure only costs the
+    /// settings, so the game goes on and the player gets told with a toast. Returns if the settings got stored.
+    /// In a replay the settings are not written and this counts as stored.
+    pub fn save_settings(&mut self) -> bool {
+        if !self.persist_settings {
+            return true;
+        }
+        match self.settings.save(SETTINGS_PATH) {
+            Ok(()) => true,
+            Err(error) => {
+                log::error!("The settings could not be saved: {error}");
+                self.toasts
+                    .push("The settings could not be saved", TOAST_DURATION);
+                false
+            }
+        }
+    }
+
+    /// Sets up the clock for a new game according to the time control. Network games are played without
+    /// a clock, as the two sides could not agree on the time.
+    pub fn reset_clock(&mut self) {
+        self.game_clock = self
+            .time_control
+            .filter(|_| self.remote_player.is_none())
+            .map(GameClock::new);
+    }
+
+    /// Requests the main loop to draw the screen anew, as the picture of an idle state has changed. The
+    /// states, that are busy, get drawn in every frame anyway.
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    /// Gets, if a redraw has been requested since the last call, and resets the request.
+    pub fn take_redraw_request(&mut self) -> bool {
+        std::mem::take(&mut self.redraw_requested)
+    }
+
+    /// Runs the clock of the indicated side for the passed time. When the time is over the game is lost
+    /// on time, in that case the end of the game gets posted and true is returned.
+    pub fn run_clock(&mut self, delta_time: f32, is_computer: bool) -> bool {
+        let Some(clock) = self.game_clock.as_mut() else {
+            return false;
+        };
+        let remaining = if is_computer {
+            &mut clock.computer_remaining
+        } else {
+            &mut clock.player_remaining
+        };
+        let shown_seconds = GameClock::get_shown_seconds(*remaining);
+        *remaining -= delta_time;
+        if GameClock::get_shown_seconds(*remaining) != shown_seconds {
+            self.redraw_requested = true;
+        }
+        if *remaining > 0.0 {
+            return false;
+        }
+
+        let first_player_timed_out = is_computer == self.game_board.get_computer_first();
+        self.game_result_override = Some(if first_player_timed_out {
+            GameResult::FirstPlayerTimeout
+        } else {
+            GameResult::SecondPlayerTimeout
+        });
+        self.post_game_end();
+        true
+    }
 }
 
 /// A general interface for a game state, to administrate the different phases we can be in.
@@ -59,13 +985,531 @@ pub trait GameState {
     /// May read and update the blackboard.
     fn update(&mut self, delta_time: f32, black_board: &mut Blackboard) -> Option<GameStateIndex>;
 
-    /// Informs the game state when a mouse has been clicked with the position.
+    /// Informs the game state when the mouse button has been pressed with the position.
     /// The blackboard is not handed over intentionally. Mouse interaction information
     /// should be stored in struct and processed in the update method. We have done this to avoid
-    /// common state confusion errors.
-    fn mouse_click(&mut self, position: Vec2);
+    /// common state confusion errors. Buttons should only remember the press here, see
+    /// [`crate::state_system::button_press::ButtonPress`].
+    fn mouse_down(&mut self, _position: Vec2) {}
+
+    /// Informs the game state when the mouse button has been released with the position. Buttons get
+    /// activated here, if they got pressed as well.
+    fn mouse_up(&mut self, _position: Vec2) {}
+
+    /// Informs the game state when the right mouse button has been pressed with the position. Like the
+    /// left button it should only be remembered here. Most states do not use it.
+    fn right_mouse_down(&mut self, _position: Vec2) {}
+
+    /// Informs the game state when the right mouse button has been released with the position.
+    fn right_mouse_up(&mut self, _position: Vec2) {}
+
+    /// Informs the game state about the position of the mouse in every frame, so that it can show
+    /// previews. Most states do not need it.
+    fn mouse_move(&mut self, _position: Vec2) {}
+
+    /// Informs the game state when a key has been pressed. As with the mouse click the information
+    /// should be stored and processed in the update method. Most states do not use the keyboard.
+    fn key_pressed(&mut self, _key: KeyCode) {}
+
+    /// Informs the game state about the text pasted from the clipboard with Control and V. It should be
+    /// stored and processed in the update method as well. Only the states, that import positions, use it.
+    fn text_pasted(&mut self, _text: &str) {}
 
     /// The rendering of the screen, it may read information
     /// from the black-board.
     fn draw(&self, black_board: &Blackboard);
+
+    /// Checks, if the state only waits for input and has nothing to animate, so that the main loop may
+    /// slow down, see [`crate::state_system::idle_governor`]. States are busy by default.
+    fn is_idle(&self, _black_board: &Blackboard) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_system::graphics::get_drawing_coordinates;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::{FRAME_TIME, HeadlessGame};
+    use crate::state_game_over::REMATCH_BUTTON;
+    use crate::state_player_input::{MENU_BUTTON, RESIGN_BUTTON};
+    use crate::state_player_start_selection::{COMPUTER_STARTS_BUTTON, PLAYER_STARTS_BUTTON};
+    use crate::state_system::session_record::FrameInput;
+    use crate::state_system::settings::ClockSetting;
+    use connect_4_rust::board_logic::bit_board_coding::{BoardGeometry, StandardGeometry};
+    use connect_4_rust::network::Connection;
+    use connect_4_rust::records::RecordSide;
+    use macroquad::math::Rect;
+    use std::io;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_match_length() {
+        for (best_of, target_wins) in [(1, 1), (3, 2), (5, 3), (7, 4)] {
+            let match_state = MatchState::new(best_of, Side::Player);
+            assert_eq!(match_state.target_wins, target_wins);
+            assert_eq!(match_state.get_best_of(), best_of);
+        }
+    }
+
+    #[test]
+    fn test_match_alternates_the_starter() {
+        let mut match_state = MatchState::new(7, Side::Computer);
+        for result in [
+            GameResult::FirstPlayerWon,
+            GameResult::Draw,
+            GameResult::SecondPlayerTimeout,
+        ] {
+            match_state.record(result);
+        }
+        assert_eq!(match_state.get_starter(0), Side::Computer);
+        assert_eq!(match_state.get_starter(1), Side::Player);
+        assert_eq!(match_state.get_starter(2), Side::Computer);
+        assert_eq!(match_state.next_starter, Side::Player);
+    }
+
+    #[test]
+    fn test_match_winner() {
+        let mut match_state = MatchState::new(3, Side::Player);
+        // The player starts and wins, the draw does not count.
+        match_state.record(GameResult::FirstPlayerWon);
+        match_state.record(GameResult::Draw);
+        assert_eq!(match_state.get_game_winner(0), Some(Side::Player));
+        assert_eq!(match_state.get_game_winner(1), None);
+        assert_eq!(match_state.get_wins(), (1, 0));
+        assert!(!match_state.is_decided());
+
+        // The player starts the third game again, the computer loses on time.
+        match_state.record(GameResult::SecondPlayerTimeout);
+        assert_eq!(match_state.get_game_winner(2), Some(Side::Player));
+        assert_eq!(match_state.get_wins(), (2, 0));
+        assert_eq!(match_state.get_winner(), Some(Side::Player));
+        assert!(match_state.is_decided());
+        assert_eq!(match_state.to_string(), "Best of 3: You 2, Computer 0");
+    }
+
+    #[test]
+    fn test_session_score() {
+        let mut score = SessionScore::default();
+        score.record(GameResult::FirstPlayerWon, true);
+        score.record(GameResult::FirstPlayerWon, false);
+        score.record(GameResult::SecondPlayerWon, false);
+        score.record(GameResult::Draw, false);
+        assert_eq!(score.to_string(), "You 1, Computer 2, Draws 1");
+        score.reset();
+        assert_eq!(score, SessionScore::default());
+    }
+
+    /// Gets a blackboard, whose settings are not stored and whose computer moves are not searched.
+    fn get_test_blackboard() -> Blackboard {
+        let mut black_board = Blackboard::new();
+        black_board.persist_settings = false;
+        black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .set_scripted(true);
+        black_board
+    }
+
+    #[test]
+    fn test_committed_moves_stay_consistent() {
+        let mut black_board = get_test_blackboard();
+        assert_eq!(
+            black_board.start_game(false),
+            GameStateIndex::PlayerInputState
+        );
+        black_board.drain_events();
+
+        let moves = [(3, Side::Player), (4, Side::Computer), (3, Side::Player)];
+        for (slot, side) in moves {
+            black_board.commit_move(slot, side).unwrap();
+        }
+
+        let history = black_board.game_board.get_move_history().to_vec();
+        let sides: Vec<bool> = history
+            .iter()
+            .map(|&(_, is_computer)| is_computer)
+            .collect();
+        assert_eq!(sides, [false, true, false]);
+        assert_eq!(black_board.last_player_move, Some(history[2].0));
+        assert_eq!(black_board.last_computer_move, Some(history[1].0));
+        assert_eq!(black_board.get_most_recent_move(), Some(history[2].0));
+
+        let committed: Vec<GameEvent> = moves
+            .iter()
+            .map(|&(slot, by)| GameEvent::MoveCommitted { slot, by })
+            .collect();
+        assert_eq!(black_board.drain_events(), committed);
+        let recorded: Vec<(u32, RecordSide)> = black_board
+            .game_record
+            .as_ref()
+            .unwrap()
+            .moves
+            .iter()
+            .map(|record| (record.slot, record.side))
+            .collect();
+        let expected: Vec<(u32, RecordSide)> = moves
+            .iter()
+            .map(|&(slot, side)| (slot, side.into()))
+            .collect();
+        assert_eq!(recorded, expected);
+    }
+
+    #[test]
+    fn test_last_moves_follow_the_scripted_game() {
+        let mut game = HeadlessGame::new();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        let bit = StandardGeometry::get_bit_representation;
+        assert_eq!(game.black_board.get_most_recent_move(), None);
+
+        game.click_column(3);
+        assert_eq!(game.black_board.last_player_move, Some(bit(3, 0)));
+        assert_eq!(game.black_board.get_most_recent_move(), Some(bit(3, 0)));
+        game.answer_computer_move(4);
+        assert_eq!(game.black_board.last_computer_move, Some(bit(4, 0)));
+        assert_eq!(game.black_board.get_most_recent_move(), Some(bit(4, 0)));
+
+        // The marker stays on the move of the computer, until the stone of the player has landed.
+        for (mouse_pressed, mouse_released) in [(true, false), (false, true)] {
+            game.run_frame(FrameInput {
+                frame_time: FRAME_TIME,
+                mouse_position: get_drawing_coordinates(3, 2),
+                mouse_pressed,
+                mouse_released,
+                ..FrameInput::default()
+            });
+        }
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.get_most_recent_move(), Some(bit(4, 0)));
+        game.wait(1.5);
+        assert_eq!(game.black_board.last_player_move, Some(bit(3, 1)));
+        assert_eq!(game.black_board.get_most_recent_move(), Some(bit(3, 1)));
+
+        // Taking back a turn marks the moves before it again.
+        game.answer_computer_move(2);
+        game.black_board.undo_last_full_turn();
+        assert_eq!(game.black_board.last_player_move, Some(bit(3, 0)));
+        assert_eq!(game.black_board.last_computer_move, Some(bit(4, 0)));
+        game.black_board.reset_game();
+        assert_eq!(game.black_board.last_player_move, None);
+        assert_eq!(game.black_board.last_computer_move, None);
+    }
+
+    #[test]
+    fn test_status_follows_the_scripted_game() {
+        let mut game = HeadlessGame::new();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        let mut statuses = vec![game.black_board.status_text()];
+        for column in [0, 1, 0, 1, 0, 1, 0] {
+            game.play_columns(&[column]);
+            statuses.push(game.black_board.status_text());
+        }
+        let player_turn = "Your turn - yellow";
+        let computer_turn = "Computer's turn - blue";
+        assert_eq!(
+            statuses,
+            [
+                player_turn,
+                computer_turn,
+                player_turn,
+                computer_turn,
+                player_turn,
+                computer_turn,
+                player_turn,
+                "Yellow wins!"
+            ]
+        );
+
+        // With the roles swapped the colors go along.
+        game.click(REMATCH_BUTTON.center());
+        assert_eq!(game.black_board.status_text(), "Computer's turn - yellow");
+        game.answer_computer_move(3);
+        assert_eq!(game.black_board.status_text(), "Your turn - blue");
+        game.click(MENU_BUTTON.center());
+        game.click(RESIGN_BUTTON.center());
+        assert_eq!(game.black_board.status_text(), "Yellow wins!");
+    }
+
+    #[test]
+    fn test_illegal_move_is_not_committed() {
+        let mut black_board = get_test_blackboard();
+        black_board.start_game(false);
+        for side in [Side::Player, Side::Computer].repeat(3) {
+            black_board.commit_move(0, side).unwrap();
+        }
+        black_board.drain_events();
+
+        assert_eq!(
+            black_board.commit_move(0, Side::Player),
+            Err(MoveError::ColumnFull { column: 0 })
+        );
+        assert_eq!(black_board.game_board.get_move_history().len(), 6);
+        assert_eq!(black_board.game_record.as_ref().unwrap().moves.len(), 6);
+        assert!(black_board.drain_events().is_empty());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is committed for the side, that is not to move")]
+    fn test_move_can_not_be_committed_twice() {
+        let mut black_board = get_test_blackboard();
+        black_board.start_game(false);
+        black_board.commit_move(3, Side::Player).unwrap();
+        let _ = black_board.commit_move(3, Side::Player);
+    }
+
+    #[test]
+    fn test_played_game_gets_recorded() {
+        let mut game = HeadlessGame::new();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&[0, 6, 1, 6, 2, 6, 3]);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+
+        let record = game.black_board.game_record.as_ref().unwrap();
+        let moves: Vec<(RecordSide, u32)> = record
+            .moves
+            .iter()
+            .map(|record| (record.side, record.slot))
+            .collect();
+        assert_eq!(
+            moves,
+            [
+                (RecordSide::Player, 0),
+                (RecordSide::Computer, 6),
+                (RecordSide::Player, 1),
+                (RecordSide::Computer, 6),
+                (RecordSide::Player, 2),
+                (RecordSide::Computer, 6),
+                (RecordSide::Player, 3),
+            ]
+        );
+        assert!(
+            record
+                .moves
+                .is_sorted_by(|first, second| first.time <= second.time)
+        );
+        assert_eq!(record.starter, RecordSide::Player);
+        assert_eq!(record.result, RecordResult::PlayerWon);
+        assert_eq!(record.notation.as_deref(), Some("a1 g1 b1 g2 c1 g3 d1"));
+        assert_eq!(
+            record.win.as_deref(),
+            Some("a horizontal line from a1 to d1")
+        );
+        assert!(record.duration >= record.moves[6].time);
+    }
+
+    #[test]
+    fn test_clock_requests_a_redraw_every_second() {
+        let mut black_board = get_test_blackboard();
+        black_board.time_control = Some(10.0);
+        black_board.reset_clock();
+        // The clock shows 0:10, until a whole second has passed.
+        assert!(!black_board.run_clock(0.5, false));
+        assert!(!black_board.take_redraw_request());
+        assert!(!black_board.run_clock(0.6, false));
+        assert!(black_board.take_redraw_request());
+        assert!(!black_board.take_redraw_request());
+        assert!(!black_board.run_clock(0.5, true));
+        assert!(!black_board.take_redraw_request());
+    }
+
+    #[test]
+    fn test_clock_is_shown_in_minutes_and_seconds() {
+        assert_eq!(GameClock::format_time(180.0), "3:00");
+        assert_eq!(GameClock::format_time(61.0), "1:01");
+        // The seconds are rounded up, only a clock, that has run out, shows zero.
+        assert_eq!(GameClock::format_time(59.2), "1:00");
+        assert_eq!(GameClock::format_time(0.1), "0:01");
+        assert_eq!(GameClock::format_time(-0.5), "0:00");
+    }
+
+    /// Gets a game with three minutes per side, that the indicated side starts.
+    fn get_game_with_clock(start_button: Rect) -> HeadlessGame {
+        let mut game = HeadlessGame::new();
+        game.black_board.settings.clock = ClockSetting::ThreeMinutes;
+        game.click(start_button.center());
+        game.click(BUTTON_CENTERS[0]);
+        game
+    }
+
+    /// Gets the remaining times of the player and the computer.
+    fn get_remaining(black_board: &Blackboard) -> (f32, f32) {
+        let clock = black_board.game_clock.unwrap();
+        (clock.player_remaining, clock.computer_remaining)
+    }
+
+    /// Checks, that the clock of the indicated side has run for the time up to the inaccuracy of the
+    /// frames, while the other clock has stood still.
+    fn assert_clock_ran(before: (f32, f32), after: (f32, f32), is_computer: bool, seconds: f32) {
+        let (running, standing) = if is_computer {
+            ((before.1, after.1), (before.0, after.0))
+        } else {
+            ((before.0, after.0), (before.1, after.1))
+        };
+        assert!(
+            (running.0 - running.1 - seconds).abs() < 0.1,
+            "{before:?} {after:?}"
+        );
+        assert_eq!(standing.0, standing.1);
+    }
+
+    #[test]
+    fn test_player_loses_on_time() {
+        let mut game = get_game_with_clock(PLAYER_STARTS_BUTTON);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        let start = get_remaining(&game.black_board);
+        // The clock has started with the game, after the highlight of the difficulty.
+        assert!(start.0 > 178.0 && start.0 < 180.0, "{start:?}");
+        assert_eq!(start.1, 180.0);
+        game.wait(100.0);
+        assert_clock_ran(start, get_remaining(&game.black_board), false, 100.0);
+        game.wait(start.0 - 100.5);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        game.wait(1.0);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        assert_eq!(
+            game.black_board.get_game_result(),
+            GameResult::FirstPlayerTimeout
+        );
+        assert_eq!(game.black_board.session_score.computer_wins, 1);
+    }
+
+    #[test]
+    fn test_computer_clock_only_runs_during_the_calculation() {
+        let mut game = get_game_with_clock(COMPUTER_STARTS_BUTTON);
+        let start = get_remaining(&game.black_board);
+        game.wait(10.0);
+        let thought = get_remaining(&game.black_board);
+        assert_clock_ran(start, thought, true, 10.0);
+
+        // The clock of the computer stops with the arrival of its move.
+        game.answer_computer_move(3);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        let answered = get_remaining(&game.black_board);
+        assert!(thought.1 - answered.1 < 0.1, "{thought:?} {answered:?}");
+        game.wait(5.0);
+        let waited = get_remaining(&game.black_board);
+        assert_clock_ran(answered, waited, false, 5.0);
+
+        game.click_column(3);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        let moved = get_remaining(&game.black_board);
+        game.wait(moved.1 - 0.5);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        assert_eq!(get_remaining(&game.black_board).0, moved.0);
+        game.wait(1.0);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        assert_eq!(
+            game.black_board.get_game_result(),
+            GameResult::FirstPlayerTimeout
+        );
+        assert_eq!(game.black_board.session_score.player_wins, 1);
+    }
+
+    #[test]
+    fn test_transition_requests_a_redraw() {
+        let mut game = HeadlessGame::new();
+        game.black_board.take_redraw_request();
+        game.wait(1.0);
+        assert!(!game.black_board.take_redraw_request());
+        game.click(PLAYER_STARTS_BUTTON.center());
+        assert!(game.black_board.take_redraw_request());
+    }
+
+    #[test]
+    fn test_center_is_only_banned_for_the_first_computer_move() {
+        let mut game = HeadlessGame::new();
+        game.black_board.settings.handicap = Handicap::NoCenter;
+        game.black_board.apply_settings();
+        game.click(COMPUTER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        // The ban goes along with the request, the fallback of the engine keeps to it as well.
+        let board = game.black_board.game_board.clone();
+        let first_move = game
+            .black_board
+            .computer_player
+            .get_fallback_move(&board)
+            .unwrap();
+        assert_ne!(first_move, BOARD_WIDTH / 2);
+
+        game.answer_computer_move(first_move);
+        game.click_column(0);
+        let board = BitBoard::new();
+        assert_eq!(
+            game.black_board.computer_player.get_fallback_move(&board),
+            Ok(BOARD_WIDTH / 2)
+        );
+    }
+
+    /// Runs the game frame by frame, until the condition holds. The messages of a network game take a
+    /// moment through the pipes.
+    fn run_until(game: &mut HeadlessGame, mut condition: impl FnMut(&mut HeadlessGame) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !condition(game) {
+            assert!(Instant::now() < deadline, "The game did not get there.");
+            game.wait(FRAME_TIME);
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_lost_connection_ends_the_game_with_a_message() {
+        // The game hosts, the test plays the guest.
+        let (host_reader, guest_writer) = io::pipe().unwrap();
+        let (guest_reader, host_writer) = io::pipe().unwrap();
+        let mut game = HeadlessGame::new();
+        game.black_board.remote_player = Some(RemotePlayer::new(
+            Connection::from_streams(host_reader, host_writer).unwrap(),
+            Role::Host,
+        ));
+        let mut guest = RemotePlayer::new(
+            Connection::from_streams(guest_reader, guest_writer).unwrap(),
+            Role::Guest,
+        );
+        run_until(&mut game, |game| {
+            guest.update(FRAME_TIME);
+            guest.is_connected()
+                && game
+                    .black_board
+                    .remote_player
+                    .as_ref()
+                    .is_some_and(RemotePlayer::is_connected)
+        });
+
+        // A network game starts right away, without choosing the difficulty.
+        game.click(PLAYER_STARTS_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        run_until(&mut game, |_| guest.take_started_game() == Some(false));
+        game.click_column(3);
+        run_until(&mut game, |_| guest.poll_move() == Some(3));
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+
+        // The opponent leaves, while the game waits for its move.
+        drop(guest);
+        run_until(&mut game, |game| {
+            game.get_current_index() == GameStateIndex::GameOverState
+        });
+        assert_eq!(
+            game.black_board.game_over_message.as_deref(),
+            Some("Game over, the opponent has left")
+        );
+        assert_eq!(
+            game.black_board.get_game_result(),
+            GameResult::FirstPlayerWon
+        );
+    }
 }
@@ -0,0 +1,141 @@
+//! The idle governor slows the main loop down, while nothing happens on the screen. This is a turn
+//! based game, most of the time it waits for the player, and rendering at the full rate of the display
+//! would only heat up the machine. The loop is idle, when the state has nothing to animate, the ai is not
+//! computing and there was no input in the frame. Then it sleeps, so that the frame takes
+//! [`IDLE_FRAME_TIME`]. As the decision is taken anew in every frame, any input or a request to the ai
+//! returns to the full rate right away. On the web the browser paces the frames itself and a sleep would
+//! block the page, so the loop never sleeps there.
+//!
+//! The picture of an idle frame is kept and shown again instead of drawing the states anew. It only gets
+//! redrawn, when a state requests it with [`crate::game_state::Blackboard::request_redraw`], like the
+//! clock does, when it shows another second.
+
+use std::time::Duration;
+
+/// The time in seconds a frame takes at least, while the loop is idle.
+pub const IDLE_FRAME_TIME: f32 = 1.0 / 30.0;
+
+/// What happened in a frame, it decides, whether the loop may slow down.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FrameActivity {
+    /// The mouse got moved or clicked or a key got pressed.
+    pub input_happened: bool,
+    /// The current state has nothing to animate and no input to process.
+    pub state_idle: bool,
+    /// The ai has a request, whose result has not arrived yet.
+    pub ai_pending: bool,
+    /// There are toasts shown, that fade out.
+    pub toasts_shown: bool,
+}
+
+impl FrameActivity {
+    /// Checks, if nothing is going on, that needs the full frame rate.
+    pub fn is_idle(&self) -> bool {
+        !self.input_happened && self.state_idle && !self.ai_pending && !self.toasts_shown
+    }
+}
+
+/// Gets the time the loop has to sleep at the end of the frame, which already took the indicated time in
+/// seconds. None is returned, if the loop is not idle or the frame took long enough anyway.
+pub fn get_idle_sleep(activity: FrameActivity, frame_time: f32) -> Option<Duration> {
+    let remaining = IDLE_FRAME_TIME - frame_time;
+    (cfg!(not(target_arch = "wasm32")) && activity.is_idle() && remaining > 0.0)
+        .then(|| Duration::from_secs_f32(remaining))
+}
+
+/// Checks, if the states have to be drawn anew in the frame or the kept picture may be shown again. The
+/// picture may only be kept, if the previous frame has been idle as well, the update of the first idle
+/// frame may still have changed the picture, like the last step of an animation.
+pub fn is_redraw_needed(
+    activity: FrameActivity,
+    previous_activity: FrameActivity,
+    redraw_requested: bool,
+) -> bool {
+    redraw_requested || !activity.is_idle() || !previous_activity.is_idle()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The activity of a frame, in which nothing happens.
+    const IDLE: FrameActivity = FrameActivity {
+        input_happened: false,
+        state_idle: true,
+        ai_pending: false,
+        toasts_shown: false,
+    };
+
+    /// Gets the activities, which only differ from the idle one in one reason to keep the full rate.
+    fn get_busy_activities() -> [FrameActivity; 4] {
+        [
+            FrameActivity {
+                input_happened: true,
+                ..IDLE
+            },
+            FrameActivity {
+                state_idle: false,
+                ..IDLE
+            },
+            FrameActivity {
+                ai_pending: true,
+                ..IDLE
+            },
+            FrameActivity {
+                toasts_shown: true,
+                ..IDLE
+            },
+        ]
+    }
+
+    /// Gets the number of frames per second, when every frame takes the indicated time in seconds.
+    fn get_frame_rate(activity: FrameActivity, frame_time: f32) -> f32 {
+        let sleep_time = get_idle_sleep(activity, frame_time).unwrap_or_default();
+        1.0 / (frame_time + sleep_time.as_secs_f32())
+    }
+
+    #[test]
+    fn test_idle_frame_sleeps_the_rest_of_the_frame_time() {
+        let sleep_time = get_idle_sleep(IDLE, 0.01).unwrap();
+        assert!((sleep_time.as_secs_f32() - (IDLE_FRAME_TIME - 0.01)).abs() < 1e-6);
+        assert_eq!(get_idle_sleep(IDLE, IDLE_FRAME_TIME), None);
+        assert_eq!(get_idle_sleep(IDLE, 0.1), None);
+    }
+
+    #[test]
+    fn test_any_activity_keeps_the_full_rate() {
+        for activity in get_busy_activities() {
+            assert!(!activity.is_idle(), "{activity:?}");
+            assert_eq!(get_idle_sleep(activity, 0.001), None, "{activity:?}");
+        }
+    }
+
+    #[test]
+    fn test_frame_rate_drops_while_idle() {
+        // A frame, that is drawn in two milliseconds, would run at 500 frames per second.
+        let busy_rate = get_frame_rate(get_busy_activities()[0], 0.002);
+        let idle_rate = get_frame_rate(IDLE, 0.002);
+        assert!((busy_rate - 500.0).abs() < 1.0, "{busy_rate}");
+        assert!(
+            (idle_rate - 1.0 / IDLE_FRAME_TIME).abs() < 0.1,
+            "{idle_rate}"
+        );
+        // The input in the next frame brings the full rate back at once.
+        let back_rate = get_frame_rate(get_busy_activities()[0], 0.002);
+        assert_eq!(back_rate, busy_rate);
+    }
+
+    #[test]
+    fn test_redraw_decision() {
+        // The picture of an idle frame is kept, unless a state asks for a new one.
+        assert!(!is_redraw_needed(IDLE, IDLE, false));
+        assert!(is_redraw_needed(IDLE, IDLE, true));
+        for activity in get_busy_activities() {
+            assert!(is_redraw_needed(activity, IDLE, false), "{activity:?}");
+            // The update of the first idle frame may have changed the picture.
+            assert!(is_redraw_needed(IDLE, activity, false), "{activity:?}");
+        }
+        // The first frame is never kept, as there is no picture yet.
+        assert!(is_redraw_needed(IDLE, FrameActivity::default(), false));
+    }
+}
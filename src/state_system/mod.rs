@@ -1,26 +1,185 @@
 //! At the highest level this game is a state machine. These states get reflected in this module.
-//! Every new implemented state has to implement the trait *game_state::GameState*. It has to be added
-//! into the function [`game_state::generate_state_collection`] and needs to get
-//! a corresponding index in [`game_state::GameStateIndex`], that it cen be referred to from other states.
+//! Every new implemented state has to implement the trait *game_state::GameState*. It needs to get
+//! a corresponding index in [`game_state::GameStateIndex`], that it can be referred to from other states,
+//! and has to be registered under that index in [`game_state::StateRegistry::new`].
+//! The moves come from the players, the blackboard holds the human player, that the player input feeds
+//! with clicks, and the engine player. [`game_state::Blackboard::begin_turn`] asks the side to move for its
+//! move and picks the state waiting for it. The states get updated in the fixed steps of
+//! [`fixed_timestep::FixedTimestep`], so they may be updated several times or not at all in one frame.
+//! A state, that only waits for input, reports itself as idle, so that the main loop slows down.
+//! The states are run by [`state_driver::StateDriver`], which needs no window, so a recorded session can
+//! be replayed headless.
 //!
-//! We have 5 states:
+//! We have 11 states:
 //! 1. The player select state, where the player can choose when to start.
 //! 2. The computer execution state, where a determined move gets executed.
 //! 3. The player input state. Input is processed here and also the animation is shown, when this would end ending the game.
 //!    A calculation of the move is also kicked off here.
-//! 4. The game end state, that shows the game situation and asks for a confirmation button to start over.
+//! 4. The game end state, that shows the game situation and offers buttons to start over, for a rematch or a replay.
+//! 5. The replay state, where the player can step through the finished game.
+//! 6. The difficulty selection state, where the player chooses how strong the computer plays.
+//! 7. The settings state, where the player changes the settings, that are kept between the sessions.
+//! 8. The help state, that explains the rules and the controls.
+//! 9. The match score state, that shows the score between the games of a match and its result.
+//! 10. The board editor state, where the player sets up a position to play from.
+//! 11. The Connect 5 state, where a game with five in a row on a larger board is played.
 //!
 //! Transitions are
-//! * 1->2 : If player chooses to be second, the computer starts executing.
-//! * 1->3 : When the player chooses to start, we wind up here.
+//! * 1->6 : When the player has chosen who starts, the difficulty gets chosen.
+//! * 1->7 : When the player opens the settings.
+//! * 7->1 : When the player leaves the settings.
+//! * 1->8 : When the player opens the help.
+//! * 8->1 : When the player leaves the help.
+//! * 1->10 : When the player opens the board editor.
+//! * 10->1 : When the player leaves the board editor.
+//! * 1->11 : When the player opens Connect 5.
+//! * 11->1 : When the player leaves Connect 5.
+//! * 10->2 : When the game starts from the position and the computer is to move, the computer starts executing.
+//! * 10->3 : When the game starts from the position and the player is to move, we wind up here.
+//! * 6->2 : If player chooses to be second, the computer starts executing.
+//! * 6->3 : When the player chooses to start, we wind up here.
 //! * 2->3: When the computer move is executed (animation) and the game end is not reached we go to player input.
 //! * 2->4: Computer move resulted in win or draw.
 //! * 3->2: When the player has made the input and the input does not result in ending the game, we go over to 2.
 //! * 3->4: When the player input would result in ending the game, the animation is still played and then the transfer happens.
+//! * 3->4: When the player resigns in the menu, the game is over as well.
+//! * 3->1: When the player restarts in the menu, the game gets abandoned and we go to selection again.
 //! * 4->1: When the player has acknowledged the result, we go to selection again.
+//! * 4->9: When the game is part of a match, the score of the match is shown instead.
+//! * 4->2: When the player wants a rematch and the computer starts now, the computer starts executing.
+//! * 4->3: When the player wants a rematch and starts now, we wind up here.
+//! * 4->5: When the player wants to review the game, we go to the replay.
+//! * 5->1: When the player leaves the replay, we go to selection again.
+//! * 5->9: When the player leaves the replay during a match, the score of the match is shown.
+//! * 9->2: When the match goes on and the computer starts the next game, the computer starts executing.
+//! * 9->3: When the match goes on and the player starts the next game, we wind up here.
+//! * 9->1: When the match is decided, we go to selection again.
 
+pub mod analysis;
+pub mod button_press;
+pub mod fixed_timestep;
+pub mod game_event;
 pub mod game_state;
+pub mod idle_governor;
+pub mod player_stats;
+pub mod session_record;
+pub mod settings;
+pub mod state_board_editor;
 pub mod state_computer_execution;
+pub mod state_connect_five;
+pub mod state_difficulty_selection;
+pub mod state_driver;
 pub mod state_game_over;
+pub mod state_help;
+pub mod state_match_score;
 pub mod state_player_input;
 pub mod state_player_start_selection;
+pub mod state_replay;
+pub mod state_settings;
+pub mod think_pacer;
+
+#[cfg(test)]
+mod tests {
+    use crate::game_state::GameStateIndex;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::Transition;
+    use crate::state_driver::tests::HeadlessGame;
+    use crate::state_game_over::{MENU_BUTTON, REMATCH_BUTTON, REPLAY_BUTTON};
+    use crate::state_player_input;
+    use crate::state_player_start_selection::{
+        BOARD_EDITOR_BUTTON, COMPUTER_STARTS_BUTTON, CONNECT_FIVE_BUTTON, HELP_BUTTON_CENTER,
+        MATCH_LENGTH_BUTTON, PLAYER_STARTS_BUTTON, SETTINGS_BUTTON_CENTER,
+    };
+    use macroquad::math::Vec2;
+    use macroquad::prelude::KeyCode;
+    use std::collections::HashSet;
+
+    /// The columns of a game, in which the starter wins in the first column.
+    const STARTER_WINS: [u32; 7] = [0, 1, 0, 1, 0, 1, 0];
+
+    /// Gets the transitions listed in the documentation of this module, the states are numbered like
+    /// the indices.
+    fn get_documented_transitions() -> HashSet<Transition> {
+        let to_index =
+            |number: &str| GameStateIndex::ALL[number.trim().parse::<usize>().unwrap() - 1];
+        include_str!("mod.rs")
+            .lines()
+            .filter_map(|line| line.strip_prefix("//! * "))
+            .map(|line| {
+                let (transition, _) = line.split_once(':').unwrap();
+                let (from, to) = transition.split_once("->").unwrap();
+                (to_index(from), to_index(to))
+            })
+            .collect()
+    }
+
+    /// Opens the menu of the player input and picks the choice.
+    fn choose_in_menu(game: &mut HeadlessGame, choice: Vec2) {
+        game.click(state_player_input::MENU_BUTTON.center());
+        game.click(choice);
+    }
+
+    /// Visits the side screens, plays games from the board editor, single games with rematches and a
+    /// replay and finally a match, which together go along every transition.
+    fn play_session(game: &mut HeadlessGame) {
+        game.click(SETTINGS_BUTTON_CENTER);
+        game.press_key(KeyCode::Escape);
+        game.click(HELP_BUTTON_CENTER);
+        game.press_key(KeyCode::Escape);
+        game.click(CONNECT_FIVE_BUTTON.center());
+        game.press_key(KeyCode::Escape);
+        game.click(BOARD_EDITOR_BUTTON.center());
+        game.press_key(KeyCode::Escape);
+
+        // From the empty board the player starts and restarts, after a stone the computer is to move.
+        game.click(BOARD_EDITOR_BUTTON.center());
+        game.press_key(KeyCode::Enter);
+        choose_in_menu(game, state_player_input::RESTART_BUTTON.center());
+        game.click(BOARD_EDITOR_BUTTON.center());
+        game.click_column(3);
+        game.press_key(KeyCode::Enter);
+        game.play_columns(&[3]);
+        choose_in_menu(game, state_player_input::RESIGN_BUTTON.center());
+        game.click(MENU_BUTTON.center());
+
+        // Single games, the rematches swap the starter.
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&STARTER_WINS);
+        game.click(REMATCH_BUTTON.center());
+        game.play_columns(&STARTER_WINS);
+        game.click(REMATCH_BUTTON.center());
+        game.play_columns(&STARTER_WINS);
+        game.click(REPLAY_BUTTON.center());
+        game.press_key(KeyCode::Escape);
+
+        // A best of 3, which the computer wins 2:1.
+        game.click(MATCH_LENGTH_BUTTON.center());
+        game.click(COMPUTER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&STARTER_WINS);
+        game.click(REPLAY_BUTTON.center());
+        game.press_key(KeyCode::Escape);
+        for _ in 0..2 {
+            game.click(Vec2::new(350.0, 300.0));
+            game.play_columns(&STARTER_WINS);
+            game.click(MENU_BUTTON.center());
+        }
+        game.click(Vec2::new(350.0, 300.0));
+    }
+
+    #[test]
+    fn test_transitions_follow_the_documented_graph() {
+        let documented = get_documented_transitions();
+        assert_eq!(documented.len(), 28);
+
+        let mut game = HeadlessGame::new();
+        play_session(&mut game);
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        let taken: HashSet<Transition> = game.transitions.iter().copied().collect();
+        let undocumented: Vec<_> = taken.difference(&documented).collect();
+        assert!(undocumented.is_empty(), "Undocumented: {undocumented:?}");
+        let missed: Vec<_> = documented.difference(&taken).collect();
+        assert!(missed.is_empty(), "Not taken: {missed:?}");
+    }
+}
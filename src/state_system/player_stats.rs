@@ -0,0 +1,351 @@
+//! The statistics of the player over all sessions. They are kept next to the settings in a small JSON file
+//! with the games against every difficulty, the win streaks and a rating. The rating gets updated like an
+//! Elo rating after every game against the engine, which has a nominal rating per difficulty. A file,
+//! that can not be read, gets replaced with empty statistics, it never stops the game.
+
+use crate::state_system::settings::Difficulty;
+use connect_4_rust::board_logic::bit_board::GameResult;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+
+/// The file the statistics are kept in, relative to the working directory.
+pub const STATS_PATH: &str = "connect-4-stats.json";
+
+/// The rating of a player, who has not played yet.
+pub const INITIAL_RATING: f32 = 1200.0;
+
+/// The change of the rating after a game, that ended as unexpected as possible.
+const RATING_FACTOR: f32 = 32.0;
+
+/// The difference of the ratings, at which the stronger side is expected to score ten times as much.
+const RATING_SCALE: f32 = 400.0;
+
+/// The outcome of a game for the player.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    /// Gets the outcome of the finished game for the player. The flag, if the computer went first, is
+    /// needed to find out who the winner is.
+    pub fn from_game_result(result: GameResult, computer_first: bool) -> Outcome {
+        match result {
+            GameResult::Pending => panic!("Only finished games have an outcome."),
+            GameResult::Draw => Outcome::Draw,
+            _ if result.is_first_player_win() == computer_first => Outcome::Loss,
+            _ => Outcome::Win,
+        }
+    }
+
+    /// Gets the score of the outcome, as it counts for the rating.
+    pub fn get_score(self) -> f32 {
+        match self {
+            Outcome::Win => 1.0,
+            Outcome::Draw => 0.5,
+            Outcome::Loss => 0.0,
+        }
+    }
+}
+
+/// Gets the score the player is expected to make against the opponent with the ratings, from 0 for a sure
+/// loss to 1 for a sure win.
+pub fn get_expected_score(rating: f32, opponent_rating: f32) -> f32 {
+    1.0 / (1.0 + 10.0_f32.powf((opponent_rating - rating) / RATING_SCALE))
+}
+
+/// Gets the rating of the player after the game with the outcome against the opponent. The rating gains,
+/// what the outcome is better than expected, a win against a far stronger opponent is worth the most.
+pub fn get_updated_rating(rating: f32, opponent_rating: f32, outcome: Outcome) -> f32 {
+    rating + RATING_FACTOR * (outcome.get_score() - get_expected_score(rating, opponent_rating))
+}
+
+/// Gets the current and the best win streak after the game with the outcome. A draw ends the streak like
+/// a loss does.
+pub fn get_updated_streaks(current_streak: u32, best_streak: u32, outcome: Outcome) -> (u32, u32) {
+    match outcome {
+        Outcome::Win => {
+            let current_streak = current_streak + 1;
+            (current_streak, best_streak.max(current_streak))
+        }
+        Outcome::Draw | Outcome::Loss => (0, best_streak),
+    }
+}
+
+/// The errors, that make the file of the statistics unreadable.
+#[derive(Clone, PartialEq, Debug)]
+pub enum StatsError {
+    /// The file exists, but can not be read.
+    Read { reason: String },
+    /// The text of the file are no statistics.
+    Invalid { reason: String },
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatsError::Read { reason } => write!(f, "the statistics can not be read: {reason}"),
+            StatsError::Invalid { reason } => write!(f, "invalid statistics: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+/// The games played against one difficulty.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DifficultyStats {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// The moves of all games together, the moves of both sides count.
+    pub moves: u64,
+}
+
+impl DifficultyStats {
+    /// Gets the amount of games played.
+    pub fn get_games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Gets the average amount of moves of a game, zero if no game has been played.
+    pub fn get_average_length(&self) -> f32 {
+        match self.get_games() {
+            0 => 0.0,
+            games => self.moves as f32 / games as f32,
+        }
+    }
+
+    /// Counts the game with the outcome and the amount of moves.
+    fn record(&mut self, outcome: Outcome, moves: u32) {
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Loss => self.losses += 1,
+        }
+        self.moves += u64::from(moves);
+    }
+}
+
+/// The statistics of the player, as they are kept on disk. Fields missing in the file keep their defaults,
+/// so that a file of an older version can still be read.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerStats {
+    /// The rating of the player, it starts at [`INITIAL_RATING`].
+    pub rating: f32,
+    /// The games won in a row up to the last one.
+    pub current_streak: u32,
+    /// The most games won in a row so far.
+    pub best_streak: u32,
+    pub easy: DifficultyStats,
+    pub medium: DifficultyStats,
+    pub hard: DifficultyStats,
+}
+
+impl Default for PlayerStats {
+    fn default() -> PlayerStats {
+        PlayerStats {
+            rating: INITIAL_RATING,
+            current_streak: 0,
+            best_streak: 0,
+            easy: DifficultyStats::default(),
+            medium: DifficultyStats::default(),
+            hard: DifficultyStats::default(),
+        }
+    }
+}
+
+impl PlayerStats {
+    /// Gets the games played against the difficulty.
+    pub fn get(&self, difficulty: Difficulty) -> &DifficultyStats {
+        match difficulty {
+            Difficulty::Easy => &self.easy,
+            Difficulty::Medium => &self.medium,
+            Difficulty::Hard => &self.hard,
+        }
+    }
+
+    fn get_mut(&mut self, difficulty: Difficulty) -> &mut DifficultyStats {
+        match difficulty {
+            Difficulty::Easy => &mut self.easy,
+            Difficulty::Medium => &mut self.medium,
+            Difficulty::Hard => &mut self.hard,
+        }
+    }
+
+    /// Counts the game against the difficulty, that ended with the outcome after the amount of moves, and
+    /// updates the rating against the nominal rating of the difficulty and the streaks.
+    pub fn record(&mut self, difficulty: Difficulty, outcome: Outcome, moves: u32) {
+        self.rating = get_updated_rating(self.rating, difficulty.get_nominal_rating(), outcome);
+        (self.current_streak, self.best_streak) =
+            get_updated_streaks(self.current_streak, self.best_streak, outcome);
+        self.get_mut(difficulty).record(outcome, moves);
+    }
+
+    /// Gets the line shown on the start screen for the difficulty, like "You: 1240, 12W/30L/2D vs Hard".
+    pub fn get_summary(&self, difficulty: Difficulty) -> String {
+        let games = self.get(difficulty);
+        format!(
+            "You: {:.0}, {}W/{}L/{}D vs {}",
+            self.rating,
+            games.wins,
+            games.losses,
+            games.draws,
+            difficulty.get_label()
+        )
+    }
+
+    /// Loads the statistics from the file. A missing file is no error, nobody has played yet then.
+    pub fn load(path: impl AsRef<Path>) -> Result<PlayerStats, StatsError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(PlayerStats::default());
+            }
+            Err(error) => {
+                return Err(StatsError::Read {
+                    reason: error.to_string(),
+                });
+            }
+        };
+        serde_json::from_str(&text).map_err(|error| StatsError::Invalid {
+            reason: error.to_string(),
+        })
+    }
+
+    /// Stores the statistics in the file. They are written into a temporary file of this process first,
+    /// which then replaces the file. So a crash or a second game, that writes at the same time, never
+    /// leaves a half written file behind, the last one to finish wins.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let temporary_path = path.with_extension(format!("{}.tmp", process::id()));
+        let text =
+            serde_json::to_string_pretty(self).expect("The statistics can always be written.");
+        fs::write(&temporary_path, text)?;
+        fs::rename(&temporary_path, path).inspect_err(|_| {
+            // The temporary file is of no use any more, if it can not be removed, it only takes up space.
+            let _ = fs::remove_file(&temporary_path);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks, that the values are the same up to the rounding of the floats.
+    fn assert_close(value: f32, expected: f32) {
+        assert!((value - expected).abs() < 1e-4, "{value} is not {expected}");
+    }
+
+    #[test]
+    fn test_outcome() {
+        assert_eq!(
+            Outcome::from_game_result(GameResult::FirstPlayerWon, false),
+            Outcome::Win
+        );
+        assert_eq!(
+            Outcome::from_game_result(GameResult::FirstPlayerWon, true),
+            Outcome::Loss
+        );
+        assert_eq!(
+            Outcome::from_game_result(GameResult::SecondPlayerWon, true),
+            Outcome::Win
+        );
+        assert_eq!(
+            Outcome::from_game_result(GameResult::FirstPlayerTimeout, false),
+            Outcome::Loss
+        );
+        assert_eq!(
+            Outcome::from_game_result(GameResult::Draw, true),
+            Outcome::Draw
+        );
+        assert_eq!(
+            [Outcome::Win, Outcome::Draw, Outcome::Loss].map(Outcome::get_score),
+            [1.0, 0.5, 0.0]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only finished games have an outcome.")]
+    fn test_pending_game_has_no_outcome() {
+        Outcome::from_game_result(GameResult::Pending, false);
+    }
+
+    #[test]
+    fn test_expected_score() {
+        assert_close(get_expected_score(1200.0, 1200.0), 0.5);
+        // A lead of the rating scale lets one expect ten times the score of the opponent.
+        assert_close(get_expected_score(1800.0, 1400.0), 10.0 / 11.0);
+        assert_close(get_expected_score(1400.0, 1800.0), 1.0 / 11.0);
+        for (rating, opponent_rating) in [(1000.0, 1800.0), (1234.0, 1400.0), (2000.0, 1000.0)] {
+            assert_close(
+                get_expected_score(rating, opponent_rating)
+                    + get_expected_score(opponent_rating, rating),
+                1.0,
+            );
+        }
+    }
+
+    #[test]
+    fn test_updated_rating() {
+        assert_close(get_updated_rating(1200.0, 1200.0, Outcome::Win), 1216.0);
+        assert_close(get_updated_rating(1200.0, 1200.0, Outcome::Draw), 1200.0);
+        assert_close(get_updated_rating(1200.0, 1200.0, Outcome::Loss), 1184.0);
+        // The win against the stronger engine is worth more, but never more than the factor.
+        let gain_hard = get_updated_rating(1200.0, 1800.0, Outcome::Win) - 1200.0;
+        let gain_easy = get_updated_rating(1200.0, 1000.0, Outcome::Win) - 1200.0;
+        assert!(gain_easy < gain_hard && gain_hard < RATING_FACTOR);
+        // A draw against the stronger engine gains, one against the weaker engine loses.
+        assert!(get_updated_rating(1200.0, 1800.0, Outcome::Draw) > 1200.0);
+        assert!(get_updated_rating(1200.0, 1000.0, Outcome::Draw) < 1200.0);
+    }
+
+    #[test]
+    fn test_updated_streaks() {
+        assert_eq!(get_updated_streaks(0, 0, Outcome::Win), (1, 1));
+        assert_eq!(get_updated_streaks(2, 5, Outcome::Win), (3, 5));
+        assert_eq!(get_updated_streaks(5, 5, Outcome::Win), (6, 6));
+        assert_eq!(get_updated_streaks(3, 5, Outcome::Draw), (0, 5));
+        assert_eq!(get_updated_streaks(3, 5, Outcome::Loss), (0, 5));
+    }
+
+    #[test]
+    fn test_record_and_round_trip() {
+        let mut stats = PlayerStats::default();
+        stats.record(Difficulty::Hard, Outcome::Win, 21);
+        stats.record(Difficulty::Hard, Outcome::Loss, 30);
+        stats.record(Difficulty::Easy, Outcome::Draw, 42);
+        assert_eq!(stats.hard.get_games(), 2);
+        assert_close(stats.hard.get_average_length(), 25.5);
+        assert_eq!(stats.medium.get_average_length(), 0.0);
+        assert_eq!((stats.current_streak, stats.best_streak), (0, 1));
+        assert_eq!(
+            stats.get_summary(Difficulty::Hard),
+            format!("You: {:.0}, 1W/1L/0D vs Hard", stats.rating)
+        );
+
+        let path = std::env::temp_dir().join(format!("connect-4-stats-{}.json", process::id()));
+        stats.save(&path).unwrap();
+        assert_eq!(PlayerStats::load(&path), Ok(stats));
+        fs::write(&path, "{\"rating\": 1500.0}").unwrap();
+        let partial = PlayerStats::load(&path).unwrap();
+        assert_eq!(partial.rating, 1500.0);
+        assert_eq!(partial.hard, DifficultyStats::default());
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            PlayerStats::load(&path),
+            Err(StatsError::Invalid { .. })
+        ));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(PlayerStats::load(&path), Ok(PlayerStats::default()));
+    }
+}
@@ -0,0 +1,433 @@
+//! The recording of a session makes weird moves of the ai and hangs of the state machine reproducible.
+//! Every input, that reaches the states, is read through an [`InputSource`]. While playing it is the
+//! [`LiveInput`], the recorder writes every frame of it into a file together with the results of the ai
+//! and the transitions of the states. A replay reads the file back as the input source and feeds the
+//! recorded results to the ai instead of searching again, so the session runs exactly the same way.
+//!
+//! The file has one entry per line, a frame starts with its frame line and the entries up to the next
+//! frame line belong to it:
+//! ```text
+//! setting difficulty = "hard"      a line of the settings file, the replay runs with these settings
+//! seed 1760612345678               the seed of the random generator, the replay draws the same numbers
+//! frame 0.016667 350 210.5         the time of the frame and the mouse position in layout coordinates
+//! down                             the mouse button got pressed
+//! up                               the mouse button got released
+//! rdown                            the right mouse button got pressed
+//! rup                              the right mouse button got released
+//! key Escape                       a key got pressed, V and C stand for them pressed with Control
+//! paste 4453                       a line of the text pasted from the clipboard with Control and V
+//! ai 2 14 3 0.125                  the step of the frame, the generation, the column and the score, the
+//!                                  column is `over`, if the game handed to the ai was already over
+//! state 2 StartSelection Help      the step of the frame and the states of a transition
+//! start computer                   a game starts, the computer or the player moves first
+//! ```
+
+use crate::game_state::GameStateIndex;
+use crate::state_system::settings::Settings;
+use connect_4_rust::board_logic::ai_handler::AiResult;
+use connect_4_rust::board_logic::alpha_beta::EngineError;
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// The file a session gets recorded into, relative to the working directory.
+pub const SESSION_RECORD_PATH: &str = "connect-4-session.txt";
+
+/// The keys, that get recorded, the game does not react to any other key. A key, that gets used by a
+/// state, has to be added here.
+const RECORDED_KEYS: [KeyCode; 12] = [
+    KeyCode::C,
+    KeyCode::End,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::H,
+    KeyCode::Home,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Space,
+    KeyCode::V,
+];
+
+/// The keys, that only count, when they get pressed together with Control, or Command on a Mac. They are
+/// recorded without it.
+const SHORTCUT_KEYS: [KeyCode; 2] = [KeyCode::C, KeyCode::V];
+
+/// The keys, that have to be held down for the shortcut keys.
+const SHORTCUT_MODIFIERS: [KeyCode; 4] = [
+    KeyCode::LeftControl,
+    KeyCode::RightControl,
+    KeyCode::LeftSuper,
+    KeyCode::RightSuper,
+];
+
+/// Gets the key with the name, as it is written into the recording.
+fn parse_key(name: &str) -> Option<KeyCode> {
+    RECORDED_KEYS
+        .into_iter()
+        .find(|key| format!("{key:?}") == name)
+}
+
+/// The column of a result of the ai, that has no move, as the game was already over.
+const GAME_OVER_COLUMN: &str = "over";
+
+/// Gets the column of a result of the ai, as it is written into the recording.
+fn get_column_name(column: Result<u32, EngineError>) -> String {
+    match column {
+        Ok(column) => column.to_string(),
+        Err(EngineError::GameOver) => GAME_OVER_COLUMN.to_string(),
+    }
+}
+
+/// Gets the column of a result of the ai from its name in the recording.
+fn parse_column(name: &str) -> Option<Result<u32, EngineError>> {
+    if name == GAME_OVER_COLUMN {
+        return Some(Err(EngineError::GameOver));
+    }
+    name.parse().ok().map(Ok)
+}
+
+/// Gets the state index with the name, as it is written into the recording.
+fn parse_state_index(name: &str) -> Option<GameStateIndex> {
+    GameStateIndex::ALL
+        .into_iter()
+        .find(|index| format!("{index:?}") == name)
+}
+
+/// The input of one frame, the states get it in the main loop.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct FrameInput {
+    /// The time in seconds the frame took.
+    pub frame_time: f32,
+    /// The position of the mouse in layout coordinates.
+    pub mouse_position: Vec2,
+    /// The mouse button got pressed in the frame.
+    pub mouse_pressed: bool,
+    /// The mouse button got released in the frame.
+    pub mouse_released: bool,
+    /// The right mouse button got pressed in the frame.
+    pub right_mouse_pressed: bool,
+    /// The right mouse button got released in the frame.
+    pub right_mouse_released: bool,
+    /// The key pressed in the frame.
+    pub key: Option<KeyCode>,
+    /// The text of the clipboard, when it got pasted in the frame.
+    pub pasted_text: Option<String>,
+    /// The results of the ai, that have to be fed in before the update with the step index of the frame.
+    /// Only a replay has them.
+    pub ai_results: Vec<(u32, AiResult)>,
+    /// The transitions of the states, that happened in the frame, when it got recorded. Only a replay has
+    /// them, they are used to check, that the replay does not diverge.
+    pub transitions: Vec<(GameStateIndex, GameStateIndex)>,
+}
+
+/// A source of the input of the frames.
+pub trait InputSource {
+    /// Reads the input of the next frame, the camera converts the mouse position into layout coordinates.
+    /// None is returned, if the source has run dry.
+    fn read_frame(&mut self, camera: &Camera2D) -> Option<FrameInput>;
+}
+
+/// The input of the player in front of the screen.
+pub struct LiveInput;
+
+impl InputSource for LiveInput {
+    /// Reads the input from the window. The shortcut keys are dropped without the modifier, the text of
+    /// the clipboard is read, when it gets pasted.
+    fn read_frame(&mut self, camera: &Camera2D) -> Option<FrameInput> {
+        let key = get_last_key_pressed().filter(|key| {
+            !SHORTCUT_KEYS.contains(key)
+                || SHORTCUT_MODIFIERS
+                    .iter()
+                    .any(|&modifier| is_key_down(modifier))
+        });
+        let pasted_text = if key == Some(KeyCode::V) {
+            miniquad::window::clipboard_get()
+        } else {
+            None
+        };
+        Some(FrameInput {
+            frame_time: get_frame_time(),
+            mouse_position: camera.screen_to_world(Vec2::from(mouse_position())),
+            mouse_pressed: is_mouse_button_pressed(MouseButton::Left),
+            mouse_released: is_mouse_button_released(MouseButton::Left),
+            right_mouse_pressed: is_mouse_button_pressed(MouseButton::Right),
+            right_mouse_released: is_mouse_button_released(MouseButton::Right),
+            key,
+            pasted_text,
+            ..FrameInput::default()
+        })
+    }
+}
+
+/// Writes the session into the recording file. Every frame gets flushed, so that the recording is
+/// complete, even if the game hangs or crashes. If writing fails, the recording stops with an error in
+/// the log and the game goes on.
+pub struct SessionRecorder {
+    /// The file, none once writing has failed.
+    writer: Option<BufWriter<File>>,
+}
+
+impl SessionRecorder {
+    /// Creates the recording file and writes the settings, the session starts with, and the seed of the
+    /// random generator.
+    pub fn create(
+        path: impl AsRef<Path>,
+        settings: &Settings,
+        seed: u64,
+    ) -> io::Result<SessionRecorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for line in settings.get_file_text().lines() {
+            writeln!(writer, "setting {line}")?;
+        }
+        writeln!(writer, "seed {seed}")?;
+        Ok(SessionRecorder {
+            writer: Some(writer),
+        })
+    }
+
+    /// Writes the line, if the recording is still running.
+    fn write_line(&mut self, line: &str) {
+        let Some(writer) = self.writer.as_mut() else {
+            return;
+        };
+        if let Err(error) = writeln!(writer, "{line}") {
+            log::error!("The session recording stops: {error}");
+            self.writer = None;
+        }
+    }
+
+    /// Records the input of the frame. The results of the ai and the transitions are recorded separately,
+    /// once the states have been updated.
+    pub fn record_frame(&mut self, input: &FrameInput) {
+        let position = input.mouse_position;
+        self.write_line(&format!(
+            "frame {} {} {}",
+            input.frame_time, position.x, position.y
+        ));
+        if input.mouse_pressed {
+            self.write_line("down");
+        }
+        if input.mouse_released {
+            self.write_line("up");
+        }
+        if input.right_mouse_pressed {
+            self.write_line("rdown");
+        }
+        if input.right_mouse_released {
+            self.write_line("rup");
+        }
+        if let Some(key) = input.key {
+            self.write_line(&format!("key {key:?}"));
+        }
+        // Every line of the text gets its own entry, so that the lines of the recording stay intact. An
+        // empty text still needs its entry.
+        if let Some(text) = &input.pasted_text {
+            if text.is_empty() {
+                self.write_line("paste");
+            }
+            for line in text.lines() {
+                self.write_line(&format!("paste {line}"));
+            }
+        }
+    }
+
+    /// Records the result of the ai, that arrived during the update with the step index.
+    pub fn record_ai_result(&mut self, step: u32, result: AiResult) {
+        self.write_line(&format!(
+            "ai {step} {} {} {}",
+            result.generation,
+            get_column_name(result.column),
+            result.score
+        ));
+    }
+
+    /// Records the transition of the states in the update with the step index.
+    pub fn record_transition(&mut self, step: u32, from: GameStateIndex, to: GameStateIndex) {
+        self.write_line(&format!("state {step} {from:?} {to:?}"));
+    }
+
+    /// Records, who moves first in the game, that starts.
+    pub fn record_start(&mut self, computer_first: bool) {
+        let starter = if computer_first { "computer" } else { "player" };
+        self.write_line(&format!("start {starter}"));
+    }
+
+    /// Writes everything recorded so far into the file.
+    pub fn flush(&mut self) {
+        if let Some(writer) = self.writer.as_mut()
+            && let Err(error) = writer.flush()
+        {
+            log::error!("The session recording stops: {error}");
+            self.writer = None;
+        }
+    }
+}
+
+/// Gets the error for a line of the recording, that can not be read.
+fn get_parse_error(line_number: usize, line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {line_number} can not be read: {line}"),
+    )
+}
+
+/// Reads the entry of the line into the frame, returns none if it can not be read.
+fn parse_frame_entry(frame: &mut FrameInput, keyword: &str, values: &[&str]) -> Option<()> {
+    match (keyword, values) {
+        ("down", []) => frame.mouse_pressed = true,
+        ("up", []) => frame.mouse_released = true,
+        ("rdown", []) => frame.right_mouse_pressed = true,
+        ("rup", []) => frame.right_mouse_released = true,
+        ("key", [name]) => frame.key = Some(parse_key(name)?),
+        ("ai", [step, generation, column, score]) => frame.ai_results.push((
+            step.parse().ok()?,
+            AiResult {
+                generation: generation.parse().ok()?,
+                column: parse_column(column)?,
+                score: score.parse().ok()?,
+            },
+        )),
+        ("state", [_, from, to]) => frame
+            .transitions
+            .push((parse_state_index(from)?, parse_state_index(to)?)),
+        _ => return None,
+    }
+    Some(())
+}
+
+/// A recorded session, that gets played back frame by frame.
+pub struct SessionReplay {
+    /// The settings the session has been recorded with.
+    settings: Settings,
+    /// The seed of the random generator, recordings without it have been made with 0.
+    seed: u64,
+    /// The frames, that have not been played back yet.
+    frames: VecDeque<FrameInput>,
+}
+
+impl SessionReplay {
+    /// Reads the recording from the file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<SessionReplay> {
+        let text = fs::read_to_string(path)?;
+        let mut settings_text = String::new();
+        let mut seed = 0;
+        let mut frames = VecDeque::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let values: Vec<&str> = rest.split_whitespace().collect();
+            match keyword {
+                "" | "start" => {}
+                "setting" => {
+                    settings_text.push_str(rest);
+                    settings_text.push('\n');
+                }
+                "seed" => {
+                    seed = rest
+                        .parse()
+                        .map_err(|_| get_parse_error(line_number, line))?;
+                }
+                // The text is taken as it is, its whitespace is not split up like the values.
+                "paste" => {
+                    let frame: &mut FrameInput = frames
+                        .back_mut()
+                        .ok_or_else(|| get_parse_error(line_number, line))?;
+                    let text = frame.pasted_text.get_or_insert_with(String::new);
+                    text.push_str(rest);
+                    text.push('\n');
+                }
+                "frame" => {
+                    let [frame_time, x, y] = values[..] else {
+                        return Err(get_parse_error(line_number, line));
+                    };
+                    let parse = |value: &str| {
+                        value
+                            .parse::<f32>()
+                            .map_err(|_| get_parse_error(line_number, line))
+                    };
+                    frames.push_back(FrameInput {
+                        frame_time: parse(frame_time)?,
+                        mouse_position: Vec2::new(parse(x)?, parse(y)?),
+                        ..FrameInput::default()
+                    });
+                }
+                _ => frames
+                    .back_mut()
+                    .and_then(|frame| parse_frame_entry(frame, keyword, &values))
+                    .ok_or_else(|| get_parse_error(line_number, line))?,
+            }
+        }
+        Ok(SessionReplay {
+            settings: Settings::parse(&settings_text),
+            seed,
+            frames,
+        })
+    }
+
+    /// Gets the settings the session has been recorded with.
+    pub fn get_settings(&self) -> Settings {
+        self.settings.clone()
+    }
+
+    /// Gets the seed of the random generator, the session has been recorded with.
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Takes the next frame, that has not been played back yet. A replay needs no camera, the positions
+    /// have been recorded in layout coordinates.
+    pub fn take_frame(&mut self) -> Option<FrameInput> {
+        self.frames.pop_front()
+    }
+}
+
+impl InputSource for SessionReplay {
+    fn read_frame(&mut self, _: &Camera2D) -> Option<FrameInput> {
+        self.take_frame()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_survive_the_recording() {
+        let frames = [
+            FrameInput {
+                frame_time: 0.5,
+                mouse_position: Vec2::new(350.0, 210.5),
+                mouse_pressed: true,
+                key: Some(KeyCode::Escape),
+                ..FrameInput::default()
+            },
+            FrameInput {
+                frame_time: 0.25,
+                right_mouse_pressed: true,
+                right_mouse_released: true,
+                pasted_text: Some("4453\n".to_string()),
+                ..FrameInput::default()
+            },
+        ];
+        let path =
+            std::env::temp_dir().join(format!("connect-4-session-{}.txt", std::process::id()));
+        let mut recorder = SessionRecorder::create(&path, &Settings::default(), 42).unwrap();
+        for frame in &frames {
+            recorder.record_frame(frame);
+        }
+        recorder.flush();
+
+        let mut replay = SessionReplay::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(replay.get_seed(), 42);
+        assert_eq!(replay.get_settings(), Settings::default());
+        for frame in frames {
+            assert_eq!(replay.take_frame(), Some(frame));
+        }
+        assert_eq!(replay.take_frame(), None);
+    }
+}
@@ -0,0 +1,554 @@
+//! The settings the player can choose. They are stored on the blackboard and kept on disk between the
+//! sessions in a small TOML file with one `key = "value"` line per setting. A missing or broken file, as
+//! well as unknown values, fall back to the defaults.
+
+use crate::render_system::theme::ThemeKind;
+use crate::state_system::think_pacer::DEFAULT_THINK_TIME_MS;
+use connect_4_rust::board_logic::alpha_beta::SearchConfig;
+use connect_4_rust::board_logic::bit_board::RuleSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The file the settings are kept in, relative to the working directory.
+pub const SETTINGS_PATH: &str = "connect-4-settings.toml";
+
+/// The path of the file the log gets written to, when the log file is switched on.
+pub const LOG_PATH: &str = "connect-4.log";
+
+/// The directory the records of the finished games get written to, unless another one is set.
+pub const DEFAULT_RECORDS_DIRECTORY: &str = "games";
+
+/// A setting, that takes one of a few values, which are stored by name.
+pub trait SettingValue: Copy + PartialEq + 'static {
+    /// All values in the order they get cycled through.
+    const VALUES: &'static [Self];
+
+    /// Gets the name the value is stored with.
+    fn get_name(self) -> &'static str;
+
+    /// Finds the value stored with the name.
+    fn from_name(name: &str) -> Option<Self> {
+        Self::VALUES
+            .iter()
+            .copied()
+            .find(|value| value.get_name() == name)
+    }
+
+    /// Gets the value, that follows when cycling through them.
+    fn get_next(self) -> Self {
+        let index = Self::VALUES
+            .iter()
+            .position(|&value| value == self)
+            .unwrap_or(0);
+        Self::VALUES[(index + 1) % Self::VALUES.len()]
+    }
+}
+
+/// The speed of the falling stone animation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AnimationSpeed {
+    #[default]
+    Normal,
+    Fast,
+    Instant,
+}
+
+impl AnimationSpeed {
+    /// Gets the multiplier for the falling velocity, none if the stone is not animated at all.
+    pub fn get_speed_multiplier(self) -> Option<f32> {
+        match self {
+            AnimationSpeed::Normal => Some(1.0),
+            AnimationSpeed::Fast => Some(3.0),
+            AnimationSpeed::Instant => None,
+        }
+    }
+
+    /// Gets the text shown in the settings.
+    pub fn get_label(self) -> &'static str {
+        match self {
+            AnimationSpeed::Normal => "Speed: Normal",
+            AnimationSpeed::Fast => "Speed: Fast",
+            AnimationSpeed::Instant => "Speed: Instant",
+        }
+    }
+}
+
+impl SettingValue for AnimationSpeed {
+    const VALUES: &'static [AnimationSpeed] = &[
+        AnimationSpeed::Normal,
+        AnimationSpeed::Fast,
+        AnimationSpeed::Instant,
+    ];
+
+    fn get_name(self) -> &'static str {
+        match self {
+            AnimationSpeed::Normal => "normal",
+            AnimationSpeed::Fast => "fast",
+            AnimationSpeed::Instant => "instant",
+        }
+    }
+}
+
+impl SettingValue for ThemeKind {
+    const VALUES: &'static [ThemeKind] = &[
+        ThemeKind::Classic,
+        ThemeKind::HighContrast,
+        ThemeKind::Deuteranopia,
+    ];
+
+    fn get_name(self) -> &'static str {
+        match self {
+            ThemeKind::Classic => "classic",
+            ThemeKind::HighContrast => "high-contrast",
+            ThemeKind::Deuteranopia => "deuteranopia",
+        }
+    }
+}
+
+/// The difficulties, that can be chosen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    #[default]
+    Hard,
+}
+
+impl Difficulty {
+    /// Gets the text shown for the difficulty.
+    pub fn get_label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// Gets the rating the engine is taken to have on the difficulty, the rating of the player gets
+    /// updated against it.
+    pub fn get_nominal_rating(self) -> f32 {
+        match self {
+            Difficulty::Easy => 1000.0,
+            Difficulty::Medium => 1400.0,
+            Difficulty::Hard => 1800.0,
+        }
+    }
+
+    /// Gets the search configuration, the easier ones look less far ahead, do not always play the best
+    /// move and overlook some of the threats.
+    pub fn get_search_config(self) -> SearchConfig {
+        match self {
+            Difficulty::Easy => SearchConfig {
+                max_depth: 5,
+                move_time: None,
+                temperature: 0.03,
+                threat_blindness: 0.3,
+                ..SearchConfig::default()
+            },
+            Difficulty::Medium => SearchConfig {
+                max_depth: 7,
+                move_time: None,
+                temperature: 0.02,
+                threat_blindness: 0.1,
+                ..SearchConfig::default()
+            },
+            Difficulty::Hard => SearchConfig::default(),
+        }
+    }
+}
+
+impl SettingValue for Difficulty {
+    const VALUES: &'static [Difficulty] = &[Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    fn get_name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+/// The clock options for a game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ClockSetting {
+    #[default]
+    NoClock,
+    ThreeMinutes,
+    TenMinutes,
+}
+
+impl ClockSetting {
+    /// Gets the time per side in seconds, none if we play without clock.
+    pub fn get_time_per_side(self) -> Option<f32> {
+        match self {
+            ClockSetting::NoClock => None,
+            ClockSetting::ThreeMinutes => Some(180.0),
+            ClockSetting::TenMinutes => Some(600.0),
+        }
+    }
+
+    /// Gets the text shown for the clock option.
+    pub fn get_label(self) -> &'static str {
+        match self {
+            ClockSetting::NoClock => "No clock",
+            ClockSetting::ThreeMinutes => "3 min",
+            ClockSetting::TenMinutes => "10 min",
+        }
+    }
+}
+
+impl SettingValue for ClockSetting {
+    const VALUES: &'static [ClockSetting] = &[
+        ClockSetting::NoClock,
+        ClockSetting::ThreeMinutes,
+        ClockSetting::TenMinutes,
+    ];
+
+    fn get_name(self) -> &'static str {
+        match self {
+            ClockSetting::NoClock => "none",
+            ClockSetting::ThreeMinutes => "3min",
+            ClockSetting::TenMinutes => "10min",
+        }
+    }
+}
+
+/// The handicaps, that make the game easier for the player.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Handicap {
+    #[default]
+    Off,
+    /// The player places two stones in the first turn.
+    DoubleMove,
+    /// The computer may not play the center column with its first stone.
+    NoCenter,
+}
+
+impl Handicap {
+    /// Gets the text shown in the settings.
+    pub fn get_label(self) -> &'static str {
+        match self {
+            Handicap::Off => "Handicap: None",
+            Handicap::DoubleMove => "Handicap: Double move",
+            Handicap::NoCenter => "Handicap: No center",
+        }
+    }
+
+    /// Gets the amount of extra stones the player places in the first turn.
+    pub fn get_extra_stones(self) -> u32 {
+        if self == Handicap::DoubleMove { 1 } else { 0 }
+    }
+}
+
+impl SettingValue for Handicap {
+    const VALUES: &'static [Handicap] = &[Handicap::Off, Handicap::DoubleMove, Handicap::NoCenter];
+
+    fn get_name(self) -> &'static str {
+        match self {
+            Handicap::Off => "none",
+            Handicap::DoubleMove => "double-move",
+            Handicap::NoCenter => "no-center",
+        }
+    }
+}
+
+/// Gets the text of the rules shown in the settings.
+pub fn get_rule_set_label(rule_set: RuleSet) -> &'static str {
+    match rule_set {
+        RuleSet::Classic => "Rules: Classic",
+        RuleSet::PopOut => "Rules: Pop Out",
+    }
+}
+
+impl SettingValue for RuleSet {
+    const VALUES: &'static [RuleSet] = &[RuleSet::Classic, RuleSet::PopOut];
+
+    fn get_name(self) -> &'static str {
+        match self {
+            RuleSet::Classic => "classic",
+            RuleSet::PopOut => "pop-out",
+        }
+    }
+}
+
+impl SettingValue for bool {
+    const VALUES: &'static [bool] = &[true, false];
+
+    fn get_name(self) -> &'static str {
+        if self { "true" } else { "false" }
+    }
+}
+
+/// All the settings of the game.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Settings {
+    pub animation_speed: AnimationSpeed,
+    pub theme: ThemeKind,
+    pub difficulty: Difficulty,
+    pub sound_enabled: bool,
+    pub clock: ClockSetting,
+    /// The debug HUD with the statistics of the search is shown.
+    pub debug_hud: bool,
+    pub handicap: Handicap,
+    /// The rules the next game is played with.
+    pub rule_set: RuleSet,
+    /// The moves of the player get checked for blunders, before they are played.
+    pub training_mode: bool,
+    /// The log gets written to [`LOG_PATH`] instead of the terminal. It takes effect with the next
+    /// start, as the logger is set up only once.
+    pub log_to_file: bool,
+    /// Every session gets recorded, so that it can be replayed, see
+    /// [`crate::state_system::session_record`]. There is no row in the settings screen for it.
+    pub record_session: bool,
+    /// Every finished game gets written as a JSON record into the records directory, see
+    /// [`connect_4_rust::records`].
+    pub export_records: bool,
+    /// The directory the records of the games get written to. There is no row in the settings screen
+    /// for it, it can only be changed in the file.
+    pub records_directory: String,
+    /// The minimum time in milliseconds the computer seems to think, before its stone falls. There is no
+    /// row in the settings screen for it, it can only be changed in the file.
+    pub think_time_ms: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            animation_speed: AnimationSpeed::default(),
+            theme: ThemeKind::default(),
+            difficulty: Difficulty::default(),
+            sound_enabled: true,
+            clock: ClockSetting::default(),
+            debug_hud: false,
+            handicap: Handicap::default(),
+            rule_set: RuleSet::default(),
+            training_mode: false,
+            log_to_file: false,
+            record_session: false,
+            export_records: false,
+            records_directory: DEFAULT_RECORDS_DIRECTORY.to_string(),
+            think_time_ms: DEFAULT_THINK_TIME_MS,
+        }
+    }
+}
+
+/// Reads a value from the text of the settings file, strings may be quoted.
+fn parse_value<T: SettingValue>(text: &str) -> Option<T> {
+    T::from_name(text.trim().trim_matches('"'))
+}
+
+impl Settings {
+    /// Reads the settings from the text of a settings file. Lines, that can not be read, are skipped and
+    /// the settings not found keep their defaults.
+    pub fn parse(text: &str) -> Settings {
+        let mut settings = Settings::default();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                "animation_speed" => {
+                    settings.animation_speed =
+                        parse_value(value).unwrap_or(settings.animation_speed)
+                }
+                "theme" => settings.theme = parse_value(value).unwrap_or(settings.theme),
+                "difficulty" => {
+                    settings.difficulty = parse_value(value).unwrap_or(settings.difficulty)
+                }
+                "sound" => {
+                    settings.sound_enabled = parse_value(value).unwrap_or(settings.sound_enabled)
+                }
+                "clock" => settings.clock = parse_value(value).unwrap_or(settings.clock),
+                "handicap" => settings.handicap = parse_value(value).unwrap_or(settings.handicap),
+                "rules" => settings.rule_set = parse_value(value).unwrap_or(settings.rule_set),
+                "training" => {
+                    settings.training_mode = parse_value(value).unwrap_or(settings.training_mode)
+                }
+                "debug_hud" => {
+                    settings.debug_hud = parse_value(value).unwrap_or(settings.debug_hud)
+                }
+                "log_file" => {
+                    settings.log_to_file = parse_value(value).unwrap_or(settings.log_to_file)
+                }
+                "record" => {
+                    settings.record_session = parse_value(value).unwrap_or(settings.record_session)
+                }
+                "export_records" => {
+                    settings.export_records = parse_value(value).unwrap_or(settings.export_records)
+                }
+                "records_directory" => {
+                    let directory = value.trim().trim_matches('"');
+                    if !directory.is_empty() {
+                        settings.records_directory = directory.to_string();
+                    }
+                }
+                "think_time" => {
+                    settings.think_time_ms = value.trim().parse().unwrap_or(settings.think_time_ms)
+                }
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    /// Writes the settings in the format of the settings file.
+    pub fn get_file_text(&self) -> String {
+        format!(
+            "animation_speed = \"{}\"\ntheme = \"{}\"\ndifficulty = \"{}\"\nsound = {}\nclock = \"{}\"\ndebug_hud = {}\nhandicap = \"{}\"\nrules = \"{}\"\ntraining = {}\nlog_file = {}\nrecord = {}\nexport_records = {}\nrecords_directory = \"{}\"\nthink_time = {}\n",
+            self.animation_speed.get_name(),
+            self.theme.get_name(),
+            self.difficulty.get_name(),
+            self.sound_enabled.get_name(),
+            self.clock.get_name(),
+            self.debug_hud.get_name(),
+            self.handicap.get_name(),
+            self.rule_set.get_name(),
+            self.training_mode.get_name(),
+            self.log_to_file.get_name(),
+            self.record_session.get_name(),
+            self.export_records.get_name(),
+            self.records_directory,
+            self.think_time_ms
+        )
+    }
+
+    /// Loads the settings from the file. If the file can not be read, the defaults are used.
+    pub fn load(path: impl AsRef<Path>) -> Settings {
+        fs::read_to_string(path)
+            .map(|text| Settings::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// Stores the settings in the file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.get_file_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use connect_4_rust::board_logic::alpha_beta::AlphaBeta;
+    use connect_4_rust::board_logic::bit_board::{BitBoard, GameResult};
+    use connect_4_rust::board_logic::bit_board_coding::BOARD_WIDTH;
+    use connect_4_rust::selfplay::SplitMix64;
+
+    /// Checks if the opponent can win on the spot after the move into the column.
+    fn loses_at_once(board: &BitBoard, column: u32) -> bool {
+        let mut child = board.clone();
+        if child.play(column).is_err() || child.is_game_over() {
+            return false;
+        }
+        (0..BOARD_WIDTH).any(|reply| {
+            let mut grandchild = child.clone();
+            grandchild.play(reply).is_ok()
+                && !matches!(
+                    grandchild.get_winning_status_for_rendering().0,
+                    GameResult::Pending | GameResult::Draw
+                )
+        })
+    }
+
+    /// Gets a board after random moves, on which one move loses on the spot and another does not.
+    fn get_random_position(random: &mut SplitMix64) -> BitBoard {
+        loop {
+            let mut board = BitBoard::new();
+            for _ in 0..6 + random.next_below(16) {
+                if board.play(random.next_below(BOARD_WIDTH)).is_err() || board.is_game_over() {
+                    break;
+                }
+            }
+            let legal: Vec<u32> = (0..BOARD_WIDTH)
+                .filter(|&column| board.clone().play(column).is_ok())
+                .collect();
+            if board.is_game_over()
+                || legal.iter().all(|&column| loses_at_once(&board, column))
+                || !legal.iter().any(|&column| loses_at_once(&board, column))
+            {
+                continue;
+            }
+            return board;
+        }
+    }
+
+    #[test]
+    fn test_difficulties_above_easy_do_not_lose_at_once() {
+        let mut random = SplitMix64::new(7);
+        let positions: Vec<BitBoard> = (0..20).map(|_| get_random_position(&mut random)).collect();
+        // The hard difficulty plays the best move of the search, it has no temperature to draw another.
+        assert_eq!(Difficulty::Hard.get_search_config().temperature, 0.0);
+        assert_eq!(Difficulty::Hard.get_search_config().threat_blindness, 0.0);
+
+        let mut engine = AlphaBeta::with_config(Difficulty::Medium.get_search_config());
+        engine.set_seed(11);
+        for board in positions {
+            // Several draws on each position, so that the temperature gets the chance to pick a bad move.
+            for _ in 0..5 {
+                let column = engine.get_best_move(board.clone()).unwrap();
+                assert!(!loses_at_once(&board, column), "{column}\n{board}");
+            }
+        }
+    }
+
+    /// Gets settings, where every value differs from the default.
+    fn get_changed_settings() -> Settings {
+        let defaults = Settings::default();
+        Settings {
+            animation_speed: defaults.animation_speed.get_next(),
+            theme: defaults.theme.get_next(),
+            difficulty: defaults.difficulty.get_next(),
+            sound_enabled: !defaults.sound_enabled,
+            clock: defaults.clock.get_next(),
+            debug_hud: !defaults.debug_hud,
+            handicap: defaults.handicap.get_next(),
+            rule_set: defaults.rule_set.get_next(),
+            training_mode: !defaults.training_mode,
+            log_to_file: !defaults.log_to_file,
+            record_session: !defaults.record_session,
+            export_records: !defaults.export_records,
+            records_directory: "old games".to_string(),
+            think_time_ms: 250,
+        }
+    }
+
+    #[test]
+    fn test_settings_survive_the_file() {
+        let settings = get_changed_settings();
+        assert_eq!(Settings::parse(&settings.get_file_text()), settings);
+
+        let path =
+            std::env::temp_dir().join(format!("connect-4-settings-{}.toml", std::process::id()));
+        settings.save(&path).unwrap();
+        let loaded = Settings::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, settings);
+    }
+
+    #[test]
+    fn test_unreadable_settings_fall_back_to_the_defaults() {
+        let path = std::env::temp_dir().join(format!(
+            "connect-4-missing-settings-{}.toml",
+            std::process::id()
+        ));
+        assert_eq!(Settings::load(&path), Settings::default());
+        // A file, that is no text at all.
+        fs::write(&path, [0xff, 0xfe, 0x00, 0x80]).unwrap();
+        let loaded = Settings::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Settings::default());
+
+        // Only the lines, that can be read, change the settings.
+        let text = "animation_speed = \"warp\"\ntheme\n= \"dark\"\nthink_time = -5\nrecords_directory = \"\"\n\
+                    sound = false\nunknown = 3\n[section]\n";
+        let settings = Settings::parse(text);
+        assert_eq!(
+            settings,
+            Settings {
+                sound_enabled: false,
+                ..Settings::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,441 @@
+//! The board editor lets the player set up a position and play from it, for example to study an endgame.
+//! A click on a column drops a stone of the selected color into it, the palette button, a right click or
+//! Space switch between the colors. Undo takes back the last stone and Clear empties the board. Play from here checks,
+//! that the position could have come up in a game, which is not over yet, and starts the game from it. The
+//! side to move follows from the stones on the board. The player plays the color of the starter chosen the
+//! last time on the start screen, yellow if there has been no choice. Why a position can not be played
+//! gets told with a toast. Back or Escape go back to the start screen, the position stays for the next
+//! visit. Control and V replace it with a move string or an ASCII grid from the clipboard.
+
+use crate::game_state::{Blackboard, GameState, GameStateIndex};
+use crate::render_system::audio::SoundEffect;
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, SymbolColor, draw_button, get_color, print_text_sized, render_board,
+    screen_to_column,
+};
+use crate::render_system::toast::TOAST_DURATION;
+use crate::state_system::button_press::ButtonPress;
+use connect_4_rust::board_logic::bit_board::{BitBoard, BoardInvariantError, GameResult, Side};
+use macroquad::prelude::*;
+use std::mem;
+
+/// The buttons of the editor, the columns of the board count as buttons as well.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum EditorButton {
+    Column(u32),
+    Palette,
+    Undo,
+    Clear,
+    Play,
+    Back,
+}
+
+/// The button to switch the color of the stones, it is the first one in the upper row of the band above
+/// the board.
+const PALETTE_BUTTON: Rect = Rect {
+    x: 10.0,
+    y: 652.0,
+    w: 120.0,
+    h: 40.0,
+};
+/// The button to take back the last stone.
+const UNDO_BUTTON: Rect = Rect {
+    x: 140.0,
+    y: 652.0,
+    w: 90.0,
+    h: 40.0,
+};
+/// The button to empty the board.
+const CLEAR_BUTTON: Rect = Rect {
+    x: 240.0,
+    y: 652.0,
+    w: 90.0,
+    h: 40.0,
+};
+/// The button to start the game from the position.
+const PLAY_BUTTON: Rect = Rect {
+    x: 340.0,
+    y: 652.0,
+    w: 220.0,
+    h: 40.0,
+};
+/// The button to go back to the start screen, at the end of the upper row.
+const BACK_BUTTON: Rect = Rect {
+    x: 570.0,
+    y: 652.0,
+    w: 120.0,
+    h: 40.0,
+};
+/// The radius of the stone, that shows the selected color on the palette button.
+const STONE_RADIUS: f32 = 14.0;
+/// The distance of the center of the stone from the left edge of the palette button.
+const STONE_OFFSET: f32 = 24.0;
+/// The distance of the label from the left edge of the palette button.
+const LABEL_OFFSET: f32 = 46.0;
+/// The distance of the label above the lower edge of the palette button.
+const TEXT_OFFSET: f32 = 10.0;
+/// The position of the line telling, who is to move, it is in the lower row of the band.
+const STATUS_POSITION: Vec2 = Vec2 { x: 10.0, y: 618.0 };
+
+/// Gets the name of the color of the first or the second player.
+fn get_color_name(is_first_player: bool) -> &'static str {
+    if is_first_player { "Yellow" } else { "Blue" }
+}
+
+/// Gets the button at the indicated position, none if there is no button.
+fn get_button_at(position: Vec2) -> Option<EditorButton> {
+    if PALETTE_BUTTON.contains(position) {
+        Some(EditorButton::Palette)
+    } else if UNDO_BUTTON.contains(position) {
+        Some(EditorButton::Undo)
+    } else if CLEAR_BUTTON.contains(position) {
+        Some(EditorButton::Clear)
+    } else if PLAY_BUTTON.contains(position) {
+        Some(EditorButton::Play)
+    } else if BACK_BUTTON.contains(position) {
+        Some(EditorButton::Back)
+    } else {
+        screen_to_column(position).map(EditorButton::Column)
+    }
+}
+
+/// Explains, why no game can be started from the position, none if it can. The position has to be one,
+/// that can come up in a game, and the game must not be over yet. The texts are short, so that they fit
+/// into a toast.
+fn get_position_problem(board: &BitBoard) -> Option<String> {
+    match board.validate() {
+        Ok(()) => {}
+        Err(BoardInvariantError::StoneCountMismatch { first, second }) => {
+            return Some(format!(
+                "{} has too many stones",
+                get_color_name(first > second)
+            ));
+        }
+        Err(BoardInvariantError::BothPlayersWon) => {
+            return Some("Both colors have a four".to_string());
+        }
+        Err(error) => return Some(format!("Invalid position, {error}")),
+    }
+    match board.get_winning_status_for_rendering().0 {
+        GameResult::Pending => None,
+        GameResult::Draw => Some("The board is already full".to_string()),
+        result => Some(format!(
+            "{} has already won",
+            get_color_name(result.is_first_player_win())
+        )),
+    }
+}
+
+pub struct StateBoardEditor {
+    /// The position set up so far, the stones are in the move history in the order they got placed.
+    board: BitBoard,
+    /// Stones of the first player get placed, otherwise stones of the second player.
+    first_player_selected: bool,
+    /// The button, that got activated, it gets executed in the next update.
+    action_requested: Option<EditorButton>,
+    /// The button the mouse got pressed on.
+    button_press: ButtonPress<EditorButton>,
+    /// The right mouse button got pressed in the editor, its release switches the color.
+    right_pressed: bool,
+    /// The text pasted from the clipboard, it gets imported in the next update.
+    pasted_text: Option<String>,
+}
+
+impl StateBoardEditor {
+    pub fn new() -> StateBoardEditor {
+        StateBoardEditor {
+            board: BitBoard::new(),
+            first_player_selected: true,
+            action_requested: None,
+            button_press: ButtonPress::new(),
+            right_pressed: false,
+            pasted_text: None,
+        }
+    }
+
+    /// Sets up the position, the stones keep their colors, even if the player plays the other side. The
+    /// stones are dropped row by row from the bottom, so that every one lands on its square.
+    fn load_position(&mut self, position: &BitBoard) {
+        self.board.reset();
+        let mut stones: Vec<(u32, u32, bool)> = position.get_board_positioning().collect();
+        stones.sort_unstable_by_key(|&(x, y, _)| (y, x));
+        let computer_first = self.board.get_computer_first();
+        for (x, _, is_first_player) in stones {
+            self.board.place_stone(x, is_first_player == computer_first);
+        }
+    }
+
+    /// Drops a stone of the selected color into the column, if it is not full.
+    fn place_stone(&mut self, column: u32, black_board: &Blackboard) {
+        // The first player is the computer, if the computer went first.
+        let is_computer = self.first_player_selected == self.board.get_computer_first();
+        if self.board.place_stone(column, is_computer) {
+            black_board.play_sound(SoundEffect::Drop);
+        } else {
+            black_board.play_sound(SoundEffect::IllegalMove);
+        }
+    }
+}
+
+impl GameState for StateBoardEditor {
+    /// Starts with the position of the last visit on a board, that has the rules and the handicap of the
+    /// game board. The player takes the side of the starter chosen the last time.
+    fn enter(&mut self, black_board: &Blackboard) {
+        self.board = black_board.game_board.clone();
+        self.board
+            .set_computer_first(black_board.last_starter == Some(Side::Computer));
+        self.load_position(&black_board.editor_position);
+        self.first_player_selected = true;
+        self.action_requested = None;
+        self.button_press.cancel();
+        self.right_pressed = false;
+        self.pasted_text = None;
+    }
+
+    /// Imports the pasted position and executes the requested button. The game gets only started from a
+    /// position, that can be played, otherwise the player gets told why not. The position gets kept on
+    /// the blackboard for the next visit.
+    fn update(&mut self, _: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        if let Some(text) = self.pasted_text.take()
+            && black_board.import_position(&text)
+        {
+            let position = black_board.editor_position.clone();
+            self.load_position(&position);
+        }
+        let action = self.action_requested.take()?;
+        if !matches!(action, EditorButton::Column(_)) {
+            black_board.play_sound(SoundEffect::Click);
+        }
+        match action {
+            EditorButton::Column(column) => self.place_stone(column, black_board),
+            EditorButton::Palette => self.first_player_selected = !self.first_player_selected,
+            EditorButton::Undo => {
+                self.board.undo_last_move();
+            }
+            EditorButton::Clear => self.board.reset(),
+            EditorButton::Play => {
+                if let Some(problem) = get_position_problem(&self.board) {
+                    black_board.toasts.push(problem, TOAST_DURATION);
+                    return None;
+                }
+                black_board.match_state = None;
+                return Some(black_board.start_from_position(&self.board));
+            }
+            EditorButton::Back => return Some(GameStateIndex::StartSelection),
+        }
+        black_board.editor_position = self.board.clone();
+        None
+    }
+
+    /// Remembers the button the mouse got pressed on.
+    fn mouse_down(&mut self, position: Vec2) {
+        self.button_press.press(get_button_at(position));
+    }
+
+    /// Mouse release on the pressed button requests its action.
+    fn mouse_up(&mut self, position: Vec2) {
+        if let Some(button) = self.button_press.release(get_button_at(position)) {
+            self.action_requested = Some(button);
+        }
+    }
+
+    /// Remembers, that the right mouse button got pressed in the editor.
+    fn right_mouse_down(&mut self, _position: Vec2) {
+        self.right_pressed = true;
+    }
+
+    /// A right click anywhere switches the color, like the palette button.
+    fn right_mouse_up(&mut self, _position: Vec2) {
+        if mem::take(&mut self.right_pressed) {
+            self.action_requested = Some(EditorButton::Palette);
+        }
+    }
+
+    /// Space switches the color, Enter plays from the position and Escape goes back.
+    fn key_pressed(&mut self, key: KeyCode) {
+        self.action_requested = match key {
+            KeyCode::Space => Some(EditorButton::Palette),
+            KeyCode::Enter => Some(EditorButton::Play),
+            KeyCode::Escape => Some(EditorButton::Back),
+            _ => return,
+        };
+    }
+
+    /// Remembers the text to import it in the update.
+    fn text_pasted(&mut self, text: &str) {
+        self.pasted_text = Some(text.to_string());
+    }
+
+    /// Renders the position with the buttons in the band above it. The palette button shows a stone of
+    /// the selected color, the line below tells the color of the player and the side to move.
+    fn draw(&self, black_board: &Blackboard) {
+        render_board(&self.board, black_board.board_texture.as_ref());
+
+        draw_button(PALETTE_BUTTON, "", SMALL_TEXT_SIZE, true);
+        draw_circle(
+            PALETTE_BUTTON.x + STONE_OFFSET,
+            PALETTE_BUTTON.center().y,
+            STONE_RADIUS,
+            *get_color(if self.first_player_selected {
+                SymbolColor::Yellow
+            } else {
+                SymbolColor::Blue
+            }),
+        );
+        print_text_sized(
+            get_color_name(self.first_player_selected),
+            Vec2::new(
+                PALETTE_BUTTON.x + LABEL_OFFSET,
+                PALETTE_BUTTON.y + TEXT_OFFSET,
+            ),
+            SMALL_TEXT_SIZE,
+        );
+        draw_button(UNDO_BUTTON, "Undo", SMALL_TEXT_SIZE, !self.board.is_empty());
+        draw_button(
+            CLEAR_BUTTON,
+            "Clear",
+            SMALL_TEXT_SIZE,
+            !self.board.is_empty(),
+        );
+        draw_button(PLAY_BUTTON, "Play from here", SMALL_TEXT_SIZE, true);
+        draw_button(BACK_BUTTON, "Back", SMALL_TEXT_SIZE, true);
+
+        let computer_first = self.board.get_computer_first();
+        let to_move = self.board.side_to_move().is_computer() == computer_first;
+        print_text_sized(
+            &format!(
+                "You play {}, {} to move",
+                get_color_name(!computer_first).to_lowercase(),
+                get_color_name(to_move).to_lowercase()
+            ),
+            STATUS_POSITION,
+            SMALL_TEXT_SIZE,
+        );
+    }
+
+    /// The editor is static, until a button gets clicked.
+    fn is_idle(&self, _: &Blackboard) -> bool {
+        self.action_requested.is_none() && self.pasted_text.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_driver::tests::HeadlessGame;
+    use crate::state_player_start_selection::BOARD_EDITOR_BUTTON;
+    use connect_4_rust::board_logic::bit_board_coding::{BoardGeometry, StandardGeometry};
+
+    /// Gets a board with the stones of the first player and then the ones of the second player dropped
+    /// into the columns. The player goes first.
+    fn get_board(first_columns: &[u32], second_columns: &[u32]) -> BitBoard {
+        let mut board = BitBoard::new();
+        for &column in first_columns {
+            assert!(board.place_stone(column, false));
+        }
+        for &column in second_columns {
+            assert!(board.place_stone(column, true));
+        }
+        board
+    }
+
+    #[test]
+    fn test_position_problem() {
+        assert_eq!(get_position_problem(&BitBoard::new()), None);
+        assert_eq!(get_position_problem(&get_board(&[3, 2], &[3])), None);
+        assert_eq!(
+            get_position_problem(&get_board(&[], &[3])),
+            Some("Blue has too many stones".to_string())
+        );
+        assert_eq!(
+            get_position_problem(&get_board(&[3, 3], &[])),
+            Some("Yellow has too many stones".to_string())
+        );
+        assert_eq!(
+            get_position_problem(&get_board(&[0, 1, 2, 3], &[0, 1, 2])),
+            Some("Yellow has already won".to_string())
+        );
+        assert_eq!(
+            get_position_problem(&get_board(&[0, 1, 2, 3], &[0, 1, 2, 3])),
+            Some("Both colors have a four".to_string())
+        );
+        let full =
+            BitBoard::from_move_string("547125662261271266215743771576315353334444").unwrap();
+        assert_eq!(
+            get_position_problem(&full),
+            Some("The board is already full".to_string())
+        );
+        // A stone of the player, that hangs above the empty bottom square of the column.
+        let mut floating = get_board(&[], &[2]);
+        floating.opponent_stones = StandardGeometry::get_bit_representation(3, 1);
+        let problem = get_position_problem(&floating).unwrap();
+        assert!(problem.starts_with("Invalid position, "), "{problem}");
+    }
+
+    /// Opens the board editor from the start screen.
+    fn open_editor() -> HeadlessGame {
+        let mut game = HeadlessGame::new();
+        game.click(BOARD_EDITOR_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::BoardEditor);
+        game
+    }
+
+    #[test]
+    fn test_full_column_takes_no_stone() {
+        let mut game = open_editor();
+        for _ in 0..7 {
+            game.click_column(0);
+            game.right_click(Vec2::new(350.0, 300.0));
+        }
+        let position = &game.black_board.editor_position;
+        assert_eq!(position.stone_count(), 6);
+        assert!(position.validate().is_ok());
+        game.press_key(KeyCode::Escape);
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+    }
+
+    #[test]
+    fn test_invalid_position_stays_in_the_editor() {
+        let mut game = open_editor();
+        // The right click switches to blue, one blue stone alone can not come up in a game.
+        game.right_click(Vec2::new(350.0, 300.0));
+        game.click_column(3);
+        assert!(game.black_board.toasts.is_empty());
+        game.press_key(KeyCode::Enter);
+        assert_eq!(game.get_current_index(), GameStateIndex::BoardEditor);
+        assert!(!game.black_board.toasts.is_empty());
+    }
+
+    #[test]
+    fn test_player_to_move_gets_the_input() {
+        let mut game = open_editor();
+        game.right_click(Vec2::new(350.0, 300.0));
+        game.click_column(3);
+        game.right_click(Vec2::new(350.0, 300.0));
+        game.click_column(3);
+        game.press_key(KeyCode::Enter);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 2);
+
+        // The game goes on from the position like any other.
+        game.click_column(2);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+    }
+
+    #[test]
+    fn test_computer_to_move_gets_asked() {
+        let mut game = open_editor();
+        game.click_column(3);
+        game.press_key(KeyCode::Enter);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        game.answer_computer_move(3);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 2);
+    }
+}
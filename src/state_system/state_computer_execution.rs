@@ -1,70 +1,239 @@
-//! In this state we are awaiting the computation result, that has been kicked off in the
-//! player input state and perform the dropping stone animation. 
+//! In this state we are awaiting the move of the computer player, whose calculation has been kicked
+//! off in the player input state, and perform the dropping stone animation. The clock of the computer
+//! runs, while we are waiting. Meanwhile the evaluation bar shows the progress of the search and a text indicates,
+//! that the computer is thinking. Clicks on the board are posted as chosen moves, so that the player can
+//! click the next column, while the stone is still falling. If the ai fails to deliver the move, it
+//! gets requested again or picked by a shallow search, so that the game always goes on. A board, on which
+//! the game is already over, has no move, then the game over state follows right away. In a network game
+//! the move comes from the opponent instead, the game ends, if the opponent resigns or the connection is lost.
+//! A move of the computer, that arrives right away, is held back by the [`ThinkPacer`] for the think time
+//! of the settings, so that its stone does not fall the moment the one of the player has landed.
 
-use crate::render_system::graphics::render_board;
+use crate::render_system::audio::SoundEffect;
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, print_text_sized, render_board, render_evaluation_bar,
+    render_last_move_marker, screen_to_slot,
+};
 use crate::render_system::stone_animator::StoneAnimator;
-use crate::state_system::game_state::{Blackboard, GameState, GameStateIndex};
+use crate::state_system::game_event::GameEvent;
+use crate::state_system::game_state::{
+    Blackboard, GameState, GameStateIndex, get_first_player_share,
+};
+use crate::state_system::think_pacer::ThinkPacer;
+use connect_4_rust::board_logic::ai_handler::AiError;
+use connect_4_rust::board_logic::alpha_beta::{EngineError, SearchStats};
+use connect_4_rust::board_logic::bit_board::Side;
+use connect_4_rust::board_logic::bit_board_coding::BOARD_WIDTH;
 use macroquad::math::Vec2;
 
 pub struct StateComputerExecution {
     animator: StoneAnimator,
     slot_picked: u32,
     result_received: bool,
+    /// The speed multiplier of the animation, that starts with the next update. The destination of the
+    /// stone is taken from the board at that moment, not when the result arrived, as the frames may
+    /// have stalled in between, like with a minimized window.
+    pending_animation: Option<f32>,
+    /// The latest statistics the search has reported.
+    progress: Option<SearchStats>,
+    /// The time we are waiting for the result, used to animate the thinking indicator.
+    thinking_time: f32,
+    /// The slot clicked by the player, it gets posted for the player input.
+    clicked_slot: Option<u32>,
+    /// A click during the animation fast-forwards it.
+    skip_requested: bool,
+    /// The move got lost once and has been requested again.
+    retried: bool,
+    /// Holds the move back for the think time and watches for a move, that does not arrive.
+    pacer: ThinkPacer,
 }
 
+/// The position of the thinking indicator.
+const THINKING_POSITION: Vec2 = Vec2 { x: 450.0, y: 640.0 };
+/// The amount of dots of the thinking indicator shown per second.
+const THINKING_DOTS_PER_SECOND: f32 = 3.0;
+/// The time in seconds, after which a move of the computer, that has not arrived yet, gets reported in the
+/// log. The ai handler gives up on it only much later.
+const WATCHDOG_TIME: f32 = 10.0;
+
 impl StateComputerExecution {
     pub fn new() -> StateComputerExecution {
         StateComputerExecution {
             animator: StoneAnimator::new(),
             slot_picked: 0,
             result_received: false,
+            pending_animation: None,
+            progress: None,
+            thinking_time: 0.0,
+            clicked_slot: None,
+            skip_requested: false,
+            retried: false,
+            pacer: ThinkPacer::new(0.0, None),
+        }
+    }
+}
+
+impl StateComputerExecution {
+    /// Keeps the game going, when the move of the computer got lost. A lost worker has been replaced, so
+    /// the move gets requested once more. If this fails as well or the worker got stuck, the move is
+    /// picked by a shallow search right away. If the engine has no move, as the game is already over,
+    /// its error is returned.
+    fn recover_from_error(
+        &mut self,
+        error: AiError,
+        black_board: &mut Blackboard,
+    ) -> Result<Option<u32>, EngineError> {
+        if let AiError::Engine { error } = error {
+            return Err(error);
+        }
+        log::warn!("The computer move got lost: {error}");
+        if !self.retried && error != AiError::Timeout {
+            self.retried = true;
+            black_board.request_computer_move(black_board.game_board.clone());
+            return Ok(None);
+        }
+        black_board
+            .computer_player
+            .get_fallback_move(&black_board.game_board)
+            .map(Some)
+    }
+
+    /// Plays the move picked by the ai on the game board and decides, whether the game goes on.
+    fn play_picked_move(&self, black_board: &mut Blackboard) -> GameStateIndex {
+        black_board
+            .commit_move(self.slot_picked, Side::Computer)
+            .expect("The ai only picks possible moves.");
+        black_board.play_sound(SoundEffect::Drop);
+
+        if black_board.game_board.is_game_over() {
+            black_board.post_game_end();
+            GameStateIndex::GameOverState
+        } else {
+            black_board.begin_turn()
         }
     }
 }
 
 impl GameState for StateComputerExecution {
-    /// Here we start the animation of the stone and feed the new situation to the worker
-    /// thread to perform the computations.
-    fn enter(&mut self, _: &Blackboard) {
+    /// Here we wait for the move of the computer, whose calculation has already been kicked off. An
+    /// animation, that was still running, when the state was left the last time, like when the opponent
+    /// on the network resigned, is dropped. The move of an opponent on the network is neither held back
+    /// nor watched, as a human takes the time it needs.
+    fn enter(&mut self, black_board: &Blackboard) {
+        self.animator.stop();
+        self.pending_animation = None;
         self.result_received = false;
+        self.thinking_time = 0.0;
+        self.clicked_slot = None;
+        self.skip_requested = false;
+        self.retried = false;
+        self.pacer = if black_board.remote_player.is_some() {
+            ThinkPacer::new(0.0, None)
+        } else {
+            let think_time = black_board.settings.think_time_ms as f32 / 1000.0;
+            ThinkPacer::new(think_time, Some(WATCHDOG_TIME))
+        };
     }
 
     /// In the update we perform the animation and once it is finished we check with the worker
     /// thread, if the results are present and if so leave the thread for execution.
     fn update(&mut self, delta_time: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        if let Some(follow_index) = black_board.update_remote_player(delta_time) {
+            return Some(follow_index);
+        }
+        self.progress = black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .try_get_progress();
+        if let Some(slot) = self.clicked_slot.take() {
+            black_board.post(GameEvent::MoveChosen {
+                slot,
+                by: Side::Player,
+                chosen_at: black_board.logic_time,
+            });
+        }
         if !self.result_received {
-            if let Some(slot_choice) = black_board.ai_system.try_get_computation_result() {
-                self.slot_picked = slot_choice;
-                self.animator
-                    .start_animating(&black_board.game_board, slot_choice, true);
-                self.result_received = true;
+            self.thinking_time += delta_time;
+            if self.pacer.update(delta_time) {
+                log::warn!(
+                    "The computer move has not arrived after {:.0} seconds",
+                    self.pacer.get_waited()
+                );
+            }
+            // The clock stops, once the move is there, the time it is held back is not charged.
+            if !self.pacer.has_move_arrived() {
+                if black_board.run_clock(delta_time, true) {
+                    black_board
+                        .computer_player
+                        .get_ai_handler_mut()
+                        .cancel_pending_requests();
+                    return Some(GameStateIndex::GameOverState);
+                }
+                let slot_choice = match black_board.get_player_mut(Side::Computer).poll_move() {
+                    Some(slot_choice) => Some(slot_choice),
+                    None => match black_board.computer_player.take_error() {
+                        Some(error) => match self.recover_from_error(error, black_board) {
+                            Ok(slot_choice) => slot_choice,
+                            // The game is over without a move of the computer, it gets evaluated as it
+                            // is on the board.
+                            Err(error) => {
+                                log::error!("The computer has no move: {error}");
+                                black_board.post_game_end();
+                                return Some(GameStateIndex::GameOverState);
+                            }
+                        },
+                        None => None,
+                    },
+                };
+                self.slot_picked = slot_choice?;
+                self.pacer.set_move_arrived();
+            }
+            if !self.pacer.is_ready() {
+                return None;
             }
 
+            let Some(speed_multiplier) = black_board
+                .settings
+                .animation_speed
+                .get_speed_multiplier()
+                .filter(|_| self.slot_picked < BOARD_WIDTH)
+            else {
+                // In instant mode and for pops the move gets played right away.
+                return Some(self.play_picked_move(black_board));
+            };
+            self.pending_animation = Some(speed_multiplier);
+            self.result_received = true;
             return None;
         }
 
-        if self.animator.is_animating() {
+        if let Some(speed_multiplier) = self.pending_animation.take() {
+            self.animator.start_animating(
+                &black_board.game_board,
+                self.slot_picked,
+                true,
+                speed_multiplier,
+            );
+        }
+        if self.skip_requested {
+            self.animator.finish_now();
+        } else {
             self.animator.update(delta_time);
-            if !self.animator.is_animating() {
-                black_board
-                    .game_board
-                    .apply_move_on_column(self.slot_picked, true);
-
-                if black_board.game_board.is_game_over() {
-                    return Some(GameStateIndex::GameOverState);
-                } else {
-                    return Some(GameStateIndex::PlayerInputState);
-                }
-            }
+        }
+        if self.animator.is_animating() {
             return None;
         }
-
-        None
+        Some(self.play_picked_move(black_board))
     }
 
-    /// We do not process mouse clicks here.
-    fn mouse_click(&mut self, _: Vec2) {
-        // Nothing to do here.
+    /// The player can not move here, a click on the board gets posted, so that it is not lost, when the
+    /// player clicks a bit early. During the animation a click also lets the stone drop right away.
+    fn mouse_down(&mut self, position: Vec2) {
+        if self.animator.is_animating() {
+            self.skip_requested = true;
+        }
+        if let Some(slot) = screen_to_slot(position) {
+            self.clicked_slot = Some(slot);
+        }
     }
 
     /// Draws the board and eventually the falling stone.
@@ -73,6 +242,274 @@ impl GameState for StateComputerExecution {
             self.animator.draw();
         }
 
-        render_board(&black_board.game_board, &black_board.board_texture);
+        render_board(&black_board.game_board, black_board.board_texture.as_ref());
+        if let Some(coded_move) = black_board.get_most_recent_move() {
+            render_last_move_marker(coded_move);
+        }
+        if let Some(clock) = &black_board.game_clock {
+            clock.draw();
+        }
+        black_board.draw_status_line();
+        if let Some(stats) = &self.progress {
+            render_evaluation_bar(get_first_player_share(
+                stats.score,
+                black_board.game_board.get_computer_first(),
+            ));
+        }
+        if !self.result_received {
+            let dots = (self.thinking_time * THINKING_DOTS_PER_SECOND) as usize % 4;
+            print_text_sized(
+                &format!("Thinking{}", ".".repeat(dots)),
+                THINKING_POSITION,
+                SMALL_TEXT_SIZE,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_system::graphics::get_drawing_coordinates;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::{
+        FRAME_TIME, HeadlessGame, SETTLE_TIME, start_log_capture, take_logged_records,
+    };
+    use crate::state_game_over::REMATCH_BUTTON;
+    use crate::state_player_start_selection::PLAYER_STARTS_BUTTON;
+    use crate::state_system::session_record::FrameInput;
+    use crate::state_system::settings::AnimationSpeed;
+
+    /// Gets a game, where the player has dropped a stone into the fourth column and the computer is to move.
+    fn get_game_awaiting_the_computer() -> HeadlessGame {
+        let mut game = HeadlessGame::new();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.click_column(3);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        game
+    }
+
+    /// Lets the worker fail with the error and waits for the game to recover.
+    fn fail_worker(game: &mut HeadlessGame, error: AiError) {
+        game.black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .feed_failure(error);
+        game.wait(SETTLE_TIME);
+    }
+
+    #[test]
+    fn test_lost_move_is_requested_again() {
+        let mut game = get_game_awaiting_the_computer();
+        fail_worker(&mut game, AiError::WorkerDied);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        let handler = game.black_board.computer_player.get_ai_handler();
+        assert!(handler.is_move_pending());
+
+        game.answer_computer_move(4);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 2);
+    }
+
+    #[test]
+    fn test_fallback_move_keeps_the_game_going() {
+        // A move, that gets lost twice, is picked by the fallback search.
+        let mut game = get_game_awaiting_the_computer();
+        fail_worker(&mut game, AiError::WorkerDied);
+        fail_worker(&mut game, AiError::WorkerDied);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 2);
+        assert!(
+            !game
+                .black_board
+                .computer_player
+                .get_ai_handler()
+                .is_move_pending()
+        );
+
+        // A worker, that got stuck, is not asked again.
+        game.click_column(3);
+        fail_worker(&mut game, AiError::Timeout);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 4);
+        assert!(
+            !game
+                .black_board
+                .computer_player
+                .get_ai_handler()
+                .is_move_pending()
+        );
+    }
+
+    #[test]
+    fn test_move_survives_a_long_pause_after_arriving() {
+        let mut game = HeadlessGame::new();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.click_column(3);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+
+        // The move arrives and the frames stop for ten seconds, like with a minimized window.
+        game.feed_computer_move(3, FRAME_TIME);
+        game.run_frame(FrameInput {
+            frame_time: 10.0,
+            ..FrameInput::default()
+        });
+        // The stone has only started falling, it did not jump to the end.
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        assert_eq!(game.black_board.game_board.stone_count(), 1);
+
+        game.wait(SETTLE_TIME);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        let mut stones: Vec<_> = game
+            .black_board
+            .game_board
+            .get_board_positioning()
+            .collect();
+        stones.sort_unstable();
+        assert_eq!(stones, [(3, 0, true), (3, 1, false)]);
+    }
+
+    /// Drops the stone of the player into the column without waiting afterwards.
+    fn drop_stone_at_once(game: &mut HeadlessGame, column: u32) {
+        for (mouse_pressed, mouse_released) in [(true, false), (false, true)] {
+            game.run_frame(FrameInput {
+                frame_time: FRAME_TIME,
+                mouse_position: get_drawing_coordinates(column, 2),
+                mouse_pressed,
+                mouse_released,
+                ..FrameInput::default()
+            });
+        }
+    }
+
+    #[test]
+    fn test_move_is_held_back_for_the_think_time() {
+        let mut game = HeadlessGame::new();
+        game.black_board.settings.think_time_ms = 500;
+        game.black_board.settings.animation_speed = AnimationSpeed::Instant;
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        drop_stone_at_once(&mut game, 3);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+
+        // The move arrives right away, the stone only gets placed after the think time.
+        game.feed_computer_move(2, FRAME_TIME);
+        game.wait(0.4);
+        assert_eq!(game.black_board.game_board.stone_count(), 1);
+        game.wait(0.1);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 2);
+
+        // A move, that arrives after the think time, is placed at once.
+        drop_stone_at_once(&mut game, 3);
+        game.wait(0.6);
+        game.feed_computer_move(2, FRAME_TIME);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 4);
+    }
+
+    #[test]
+    fn test_missing_move_gets_reported() {
+        let mut game = get_game_awaiting_the_computer();
+        start_log_capture();
+        game.wait(WATCHDOG_TIME - 0.5);
+        game.wait(1.0);
+        let records = take_logged_records();
+        let reports: Vec<_> = records
+            .iter()
+            .filter(|(_, message)| message.contains("has not arrived"))
+            .collect();
+        assert_eq!(
+            reports,
+            [&(
+                log::Level::Warn,
+                "The computer move has not arrived after 10 seconds".to_string()
+            )]
+        );
+
+        // The move is still awaited.
+        game.answer_computer_move(4);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+    }
+
+    #[test]
+    fn test_click_during_the_animation_is_played_once() {
+        let mut game = get_game_awaiting_the_computer();
+        game.feed_computer_move(4, FRAME_TIME);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        // The click lets the stone drop right away and is played, as soon as the player is to move.
+        drop_stone_at_once(&mut game, 2);
+        game.wait(SETTLE_TIME);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "453");
+
+        game.answer_computer_move(5);
+        game.wait(SETTLE_TIME);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(
+            game.black_board.game_board.to_move_string().unwrap(),
+            "4536"
+        );
+    }
+
+    #[test]
+    fn test_stale_click_is_dropped() {
+        let mut game = get_game_awaiting_the_computer();
+        drop_stone_at_once(&mut game, 2);
+        game.wait(1.0);
+        game.answer_computer_move(4);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "45");
+    }
+
+    #[test]
+    fn test_click_on_game_over_is_not_buffered() {
+        let mut game = HeadlessGame::new();
+        game.black_board.settings.animation_speed = AnimationSpeed::Instant;
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&[3, 0, 3, 0, 3, 0, 3]);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+
+        // A click on the board right before the rematch, where the computer starts, is not played,
+        // although the player gets to move shortly after.
+        drop_stone_at_once(&mut game, 2);
+        for (mouse_pressed, mouse_released) in [(true, false), (false, true)] {
+            game.run_frame(FrameInput {
+                frame_time: FRAME_TIME,
+                mouse_position: REMATCH_BUTTON.center(),
+                mouse_pressed,
+                mouse_released,
+                ..FrameInput::default()
+            });
+        }
+        game.feed_computer_move(3, FRAME_TIME);
+        game.wait(0.1);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        game.wait(SETTLE_TIME);
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "4");
     }
 }
@@ -0,0 +1,243 @@
+//! The Connect 5 state plays a game on the board with nine columns and seven rows, where five stones in a
+//! row are needed to win. The player clicks a column to drop a stone, the computer answers with a search,
+//! that runs in slices on the main loop, so the board stays responsive. The player starts, if the player
+//! chose to start on the start screen the last time, which is the default. The search plays with the
+//! weights of the chosen difficulty, but goes less deep, as the board is larger. The size of the board and
+//! its stones are derived from the geometry. New game starts over and Back or Escape go back to the start
+//! screen, the game does not count in the statistics of the player.
+
+use crate::game_state::{Blackboard, GameState, GameStateIndex};
+use crate::render_system::audio::SoundEffect;
+use crate::render_system::graphics::{
+    BoardLayout, SMALL_TEXT_SIZE, draw_button, print_text_sized, render_board,
+    render_winning_stroke,
+};
+use crate::state_system::button_press::ButtonPress;
+use connect_4_rust::board_logic::ai_handler::NODES_PER_SLICE;
+use connect_4_rust::board_logic::alpha_beta::{GenericAlphaBeta, SearchConfig};
+use connect_4_rust::board_logic::bit_board::{GameResult, GenericBitBoard, Side};
+use connect_4_rust::board_logic::bit_board_coding::ConnectFiveGeometry;
+use macroquad::prelude::*;
+
+/// The board, on which Connect 5 is played.
+type ConnectFiveBoard = GenericBitBoard<ConnectFiveGeometry>;
+
+/// The layout of the Connect 5 board.
+const LAYOUT: BoardLayout = BoardLayout::of::<ConnectFiveGeometry>();
+
+/// The deepest the search goes on the larger board. It searches every iteration up to it, so that a
+/// replay of the session gets the same moves.
+const MAX_SEARCH_DEPTH: u32 = 8;
+
+/// The buttons of the state, the columns of the board count as buttons as well.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ConnectFiveButton {
+    Column(u32),
+    NewGame,
+    Back,
+}
+
+/// The button to start over, in the upper row of the band above the board.
+const NEW_GAME_BUTTON: Rect = Rect {
+    x: 440.0,
+    y: 652.0,
+    w: 120.0,
+    h: 40.0,
+};
+/// The button to go back to the start screen, at the end of the upper row.
+const BACK_BUTTON: Rect = Rect {
+    x: 570.0,
+    y: 652.0,
+    w: 120.0,
+    h: 40.0,
+};
+/// The position of the title, it is at the start of the upper row.
+const TITLE_POSITION: Vec2 = Vec2 { x: 10.0, y: 662.0 };
+/// The position of the line telling, who is to move or how the game ended, in the lower row of the band.
+const STATUS_POSITION: Vec2 = Vec2 { x: 10.0, y: 618.0 };
+
+/// Gets the button at the indicated position, none if there is no button.
+fn get_button_at(position: Vec2) -> Option<ConnectFiveButton> {
+    if NEW_GAME_BUTTON.contains(position) {
+        Some(ConnectFiveButton::NewGame)
+    } else if BACK_BUTTON.contains(position) {
+        Some(ConnectFiveButton::Back)
+    } else {
+        LAYOUT
+            .screen_to_column(position)
+            .map(ConnectFiveButton::Column)
+    }
+}
+
+/// Gets the configuration of the search from the one of the chosen difficulty. The depth is capped and
+/// there is no time limit.
+fn get_search_config(config: &SearchConfig) -> SearchConfig {
+    SearchConfig {
+        max_depth: config.max_depth.min(MAX_SEARCH_DEPTH),
+        move_time: None,
+        ..*config
+    }
+}
+
+pub struct StateConnectFive {
+    /// The game being played.
+    board: ConnectFiveBoard,
+    /// The engine searching the moves of the computer.
+    engine: GenericAlphaBeta<ConnectFiveGeometry>,
+    /// The search for the move of the computer has been started and not finished yet.
+    searching: bool,
+    /// The state has been entered, the game gets started in the next update.
+    start_pending: bool,
+    /// The button, that got activated, it gets executed in the next update.
+    action_requested: Option<ConnectFiveButton>,
+    /// The button the mouse got pressed on.
+    button_press: ButtonPress<ConnectFiveButton>,
+}
+
+impl StateConnectFive {
+    pub fn new() -> StateConnectFive {
+        StateConnectFive {
+            board: ConnectFiveBoard::new(),
+            engine: GenericAlphaBeta::new(),
+            searching: false,
+            start_pending: false,
+            action_requested: None,
+            button_press: ButtonPress::new(),
+        }
+    }
+
+    /// Starts over with an empty board, the computer starts, if it has been chosen to start the last time.
+    fn start_game(&mut self, black_board: &mut Blackboard) {
+        self.board.reset();
+        self.board
+            .set_computer_first(black_board.last_starter == Some(Side::Computer));
+        self.engine
+            .set_config(get_search_config(&black_board.search_config));
+        // The seed comes from the blackboard, so that a replay of the session draws the same moves.
+        self.engine.set_seed(black_board.random.next_u64());
+        self.engine.clear_table();
+        self.searching = false;
+    }
+
+    /// Drops a stone of the player into the column, if it is the turn of the player.
+    fn play_column(&mut self, column: u32, black_board: &Blackboard) {
+        if self.board.side_to_move().is_computer() || self.board.is_game_over() {
+            return;
+        }
+        if self.board.play(column).is_ok() {
+            self.play_move_sound(black_board);
+        } else {
+            black_board.play_sound(SoundEffect::IllegalMove);
+        }
+    }
+
+    /// Plays the sound of the stone, that just landed, or the one of the end of the game.
+    fn play_move_sound(&self, black_board: &Blackboard) {
+        black_board.play_sound(if self.board.is_game_over() {
+            SoundEffect::Win
+        } else {
+            SoundEffect::Drop
+        });
+    }
+
+    /// Searches a slice for the move of the computer and plays it, once the search has finished.
+    fn run_computer(&mut self, black_board: &Blackboard) {
+        if !self.searching {
+            self.searching = self.engine.start_search(self.board.clone()).is_ok();
+            if !self.searching {
+                return;
+            }
+        }
+        if let Some(slot) = self.engine.continue_search(NODES_PER_SLICE) {
+            self.searching = false;
+            self.board
+                .play_slot(slot)
+                .expect("The engine only returns legal moves.");
+            self.play_move_sound(black_board);
+        }
+    }
+
+    /// Gets the line telling, who is to move or how the game ended.
+    fn get_status(&self) -> &'static str {
+        let computer_first = self.board.get_computer_first();
+        match self.board.get_winning_status_for_rendering().0 {
+            GameResult::Pending if self.board.side_to_move().is_computer() => "The computer thinks",
+            GameResult::Pending => "Your move, five in a row win",
+            GameResult::Draw => "Draw, the board is full",
+            result if result.is_first_player_win() == computer_first => "The computer won",
+            _ => "You won",
+        }
+    }
+}
+
+impl GameState for StateConnectFive {
+    /// Starts a new game, the state always begins with an empty board.
+    fn enter(&mut self, _: &Blackboard) {
+        self.start_pending = true;
+        self.action_requested = None;
+        self.button_press.cancel();
+    }
+
+    /// Executes the requested button and lets the computer search, when it is its turn.
+    fn update(&mut self, _: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        if self.start_pending {
+            self.start_pending = false;
+            self.start_game(black_board);
+        }
+        if let Some(action) = self.action_requested.take() {
+            if !matches!(action, ConnectFiveButton::Column(_)) {
+                black_board.play_sound(SoundEffect::Click);
+            }
+            match action {
+                ConnectFiveButton::Column(column) => self.play_column(column, black_board),
+                ConnectFiveButton::NewGame => self.start_game(black_board),
+                ConnectFiveButton::Back => return Some(GameStateIndex::StartSelection),
+            }
+        }
+        if !self.board.is_game_over() && self.board.side_to_move().is_computer() {
+            self.run_computer(black_board);
+        }
+        None
+    }
+
+    /// Remembers the button the mouse got pressed on.
+    fn mouse_down(&mut self, position: Vec2) {
+        self.button_press.press(get_button_at(position));
+    }
+
+    /// Mouse release on the pressed button requests its action.
+    fn mouse_up(&mut self, position: Vec2) {
+        if let Some(button) = self.button_press.release(get_button_at(position)) {
+            self.action_requested = Some(button);
+        }
+    }
+
+    /// Escape goes back to the start screen.
+    fn key_pressed(&mut self, key: KeyCode) {
+        if key == KeyCode::Escape {
+            self.action_requested = Some(ConnectFiveButton::Back);
+        }
+    }
+
+    /// Renders the board with the buttons and the status in the band above it. A won game shows the
+    /// winning lines.
+    fn draw(&self, black_board: &Blackboard) {
+        render_board(&self.board, black_board.connect_five_texture.as_ref());
+        let (result, lines) = self.board.get_winning_status_for_rendering();
+        for line in lines.iter().flatten() {
+            render_winning_stroke(line, result.is_first_player_win(), &LAYOUT);
+        }
+
+        print_text_sized("Connect 5", TITLE_POSITION, SMALL_TEXT_SIZE);
+        draw_button(NEW_GAME_BUTTON, "New game", SMALL_TEXT_SIZE, true);
+        draw_button(BACK_BUTTON, "Back", SMALL_TEXT_SIZE, true);
+        print_text_sized(self.get_status(), STATUS_POSITION, SMALL_TEXT_SIZE);
+    }
+
+    /// The state waits for the player, unless the computer is to move.
+    fn is_idle(&self, _: &Blackboard) -> bool {
+        !self.start_pending
+            && self.action_requested.is_none()
+            && (self.board.is_game_over() || !self.board.side_to_move().is_computer())
+    }
+}
@@ -0,0 +1,248 @@
+//! Contains the state, where the player selects the difficulty after choosing who starts. The chosen
+//! search configuration is handed over to the ai and stored on the blackboard. Below the difficulties
+//! the clock can be chosen, before the difficulty gets clicked. When the computer starts the first
+//! calculation is kicked off. The choices are kept in the settings.
+
+use crate::game_state::{Blackboard, GameState, GameStateIndex};
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, SymbolColor, TITLE_CENTER, draw_button, get_color, print_text_centered,
+};
+use crate::state_system::button_press::ButtonPress;
+use crate::state_system::settings::{ClockSetting, Difficulty};
+use macroquad::prelude::*;
+
+/// All difficulties in the order of the buttons.
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+/// The clock options in the order of the buttons.
+const CLOCK_OPTIONS: [ClockSetting; 3] = [
+    ClockSetting::NoClock,
+    ClockSetting::ThreeMinutes,
+    ClockSetting::TenMinutes,
+];
+
+/// The buttons for the clock options from left to right.
+const CLOCK_BUTTONS: [Rect; 3] = [
+    Rect {
+        x: 60.0,
+        y: 30.0,
+        w: 170.0,
+        h: 60.0,
+    },
+    Rect {
+        x: 265.0,
+        y: 30.0,
+        w: 170.0,
+        h: 60.0,
+    },
+    Rect {
+        x: 470.0,
+        y: 30.0,
+        w: 170.0,
+        h: 60.0,
+    },
+];
+
+/// The buttons of the difficulty selection.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum DifficultyButton {
+    Clock(ClockSetting),
+    Difficulty(Difficulty),
+}
+
+pub struct StateDifficultySelection {
+    difficulty_selected: Difficulty,
+    clock_selected: ClockSetting,
+    time_passed_after_selection: f32,
+    selection_happened: bool,
+    /// The button the mouse got pressed on.
+    button_press: ButtonPress<DifficultyButton>,
+}
+
+impl StateDifficultySelection {
+    pub fn new() -> StateDifficultySelection {
+        StateDifficultySelection {
+            difficulty_selected: Difficulty::Hard,
+            clock_selected: ClockSetting::NoClock,
+            time_passed_after_selection: 0.0,
+            selection_happened: false,
+            button_press: ButtonPress::new(),
+        }
+    }
+}
+
+/// Gets the button at the indicated position, none if there is no button.
+fn get_button_at(position: Vec2) -> Option<DifficultyButton> {
+    if let Some(index) = CLOCK_BUTTONS
+        .iter()
+        .position(|button| button.contains(position))
+    {
+        return Some(DifficultyButton::Clock(CLOCK_OPTIONS[index]));
+    }
+    BUTTON_CENTERS
+        .iter()
+        .zip(DIFFICULTIES)
+        .find(|(center, _)| center.distance(position) < RADIUS)
+        .map(|(_, difficulty)| DifficultyButton::Difficulty(difficulty))
+}
+
+/// The positions where the buttons should be drawn from left to right.
+pub const BUTTON_CENTERS: [Vec2; 3] = [
+    Vec2 { x: 117.0, y: 350.0 },
+    Vec2 { x: 350.0, y: 350.0 },
+    Vec2 { x: 583.0, y: 350.0 },
+];
+/// The radius of the buttons.
+const RADIUS: f32 = 90.0;
+/// The distance of the center of the labels below the center of the buttons.
+const LABEL_DISTANCE: f32 = 1.45 * RADIUS;
+/// The highlight time for the button.
+const HIGHLIGHT_TIME: f32 = 0.25;
+
+impl GameState for StateDifficultySelection {
+    /// The clock option is preselected from the settings.
+    fn enter(&mut self, black_board: &Blackboard) {
+        self.clock_selected = black_board.settings.clock;
+        self.selection_happened = false;
+        self.time_passed_after_selection = 0.0;
+        self.button_press.cancel();
+    }
+
+    /// The update waits for the input signal, configures the ai and waits a short time for the
+    /// highlighted button.
+    fn update(&mut self, delta_time: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        if self.selection_happened {
+            self.time_passed_after_selection += delta_time;
+        }
+
+        if self.time_passed_after_selection >= HIGHLIGHT_TIME {
+            black_board.settings.difficulty = self.difficulty_selected;
+            black_board.settings.clock = self.clock_selected;
+            black_board.save_settings();
+            black_board.search_config = self.difficulty_selected.get_search_config();
+            black_board.configure_ai();
+            black_board.time_control = self.clock_selected.get_time_per_side();
+            black_board.reset_clock();
+            return Some(black_board.begin_turn());
+        }
+
+        None
+    }
+
+    /// Remembers the button the mouse got pressed on.
+    fn mouse_down(&mut self, position: Vec2) {
+        self.button_press.press(get_button_at(position));
+    }
+
+    /// Mouse release on the pressed button sets the information in the state.
+    fn mouse_up(&mut self, position: Vec2) {
+        if self.selection_happened {
+            return;
+        }
+
+        match self.button_press.release(get_button_at(position)) {
+            Some(DifficultyButton::Clock(clock)) => self.clock_selected = clock,
+            Some(DifficultyButton::Difficulty(difficulty)) => {
+                self.selection_happened = true;
+                self.difficulty_selected = difficulty;
+            }
+            None => {}
+        }
+    }
+
+    /// Renders the three buttons, eventually highlighted when just selected, and the clock options, where
+    /// the ones not selected are greyed out.
+    fn draw(&self, _: &Blackboard) {
+        for (button, clock) in CLOCK_BUTTONS.iter().zip(CLOCK_OPTIONS) {
+            draw_button(
+                *button,
+                clock.get_label(),
+                SMALL_TEXT_SIZE,
+                clock == self.clock_selected,
+            );
+        }
+
+        print_text_centered("Choose the difficulty", TITLE_CENTER);
+        for (center, difficulty) in BUTTON_CENTERS.iter().zip(DIFFICULTIES) {
+            let color = if self.selection_happened && (self.difficulty_selected == difficulty) {
+                SymbolColor::LightBlue
+            } else {
+                SymbolColor::Blue
+            };
+            draw_poly(center.x, center.y, 200, RADIUS, 0.0, *get_color(color));
+
+            print_text_centered(
+                difficulty.get_label(),
+                *center - Vec2::new(0.0, LABEL_DISTANCE),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_driver::tests::HeadlessGame;
+    use crate::state_player_start_selection::{COMPUTER_STARTS_BUTTON, PLAYER_STARTS_BUTTON};
+
+    /// Gets a game on the difficulty selection, the indicated side starts.
+    fn get_game_selecting(start_button: Rect) -> HeadlessGame {
+        let mut game = HeadlessGame::new();
+        game.click(start_button.center());
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::DifficultySelection
+        );
+        game
+    }
+
+    #[test]
+    fn test_difficulty_reaches_the_worker() {
+        for (center, difficulty) in BUTTON_CENTERS.into_iter().zip(DIFFICULTIES) {
+            let mut game = get_game_selecting(PLAYER_STARTS_BUTTON);
+            game.click(center);
+            assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+            assert_eq!(game.black_board.settings.difficulty, difficulty);
+            let config = difficulty.get_search_config();
+            assert_eq!(game.black_board.search_config, config);
+            let handler = game.black_board.computer_player.get_ai_handler_mut();
+            assert_eq!(handler.probe_worker_config(), Ok(config));
+        }
+    }
+
+    #[test]
+    fn test_computer_starts_after_the_selection() {
+        let mut game = get_game_selecting(COMPUTER_STARTS_BUTTON);
+        game.click(CLOCK_BUTTONS[1].center());
+        // The clock alone does not leave the selection.
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::DifficultySelection
+        );
+        game.click(BUTTON_CENTERS[1]);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        assert_eq!(game.black_board.settings.clock, ClockSetting::ThreeMinutes);
+        assert!(
+            game.black_board
+                .computer_player
+                .get_ai_handler()
+                .is_move_pending()
+        );
+    }
+
+    #[test]
+    fn test_only_the_buttons_are_hit() {
+        assert_eq!(
+            get_button_at(BUTTON_CENTERS[2]),
+            Some(DifficultyButton::Difficulty(Difficulty::Hard))
+        );
+        assert_eq!(
+            get_button_at(CLOCK_BUTTONS[2].center()),
+            Some(DifficultyButton::Clock(ClockSetting::TenMinutes))
+        );
+        assert_eq!(get_button_at(Vec2::new(350.0, 600.0)), None);
+    }
+}
@@ -0,0 +1,521 @@
+//! Drives the state machine with the input of the frames. The main loop runs it with the input of the
+//! player and renders the current state after every frame. A headless run replays a recorded session
+//! without a window: nothing gets drawn and the board texture is never created, the states only get the
+//! recorded input and the fixed steps. This is enough to check, that a session still runs the same way
+//! after a change of the states, without anyone clicking through the game.
+
+use crate::fixed_timestep::{FIXED_DELTA_TIME, FixedTimestep};
+use crate::game_state::{Blackboard, GameState, GameStateIndex, StateRegistry};
+use crate::render_system::toast::TOAST_DURATION;
+use crate::session_record::{FrameInput, SessionRecorder, SessionReplay};
+use connect_4_rust::selfplay::SplitMix64;
+use macroquad::prelude::KeyCode;
+
+/// A transition of the states, from the first one to the second one.
+pub type Transition = (GameStateIndex, GameStateIndex);
+
+/// The keys, that the main loop handles in every state, they never reach the states.
+pub const GLOBAL_KEYS: [KeyCode; 2] = [KeyCode::F2, KeyCode::F3];
+
+/// Checks, if the transition starts a new game, which is the case, when one of the sides is to move on
+/// an empty board coming from outside of the game.
+fn is_game_start(from: GameStateIndex, to: GameStateIndex, black_board: &Blackboard) -> bool {
+    let is_move_state = |index| {
+        matches!(
+            index,
+            GameStateIndex::ComputerExecutionState | GameStateIndex::PlayerInputState
+        )
+    };
+    is_move_state(to)
+        && !is_move_state(from)
+        && black_board.game_board.get_move_history().is_empty()
+}
+
+/// The states with the one, that is current, and the fixed steps of their logic.
+pub struct StateDriver {
+    states: StateRegistry,
+    current_index: GameStateIndex,
+    timestep: FixedTimestep,
+}
+
+impl StateDriver {
+    /// Creates the states, the game starts in the start selection.
+    pub fn new() -> StateDriver {
+        StateDriver {
+            states: StateRegistry::new(),
+            current_index: GameStateIndex::StartSelection,
+            timestep: FixedTimestep::new(),
+        }
+    }
+
+    /// Gets the index of the current state.
+    pub fn get_current_index(&self) -> GameStateIndex {
+        self.current_index
+    }
+
+    /// Gets the current state, to draw it or to hand input to it.
+    pub fn get_current_state(&mut self) -> &mut dyn GameState {
+        self.states.get_mut(self.current_index)
+    }
+
+    /// Hands the mouse buttons, the key and the pasted text of the frame to the current state. The global
+    /// keys are left to the caller.
+    pub fn dispatch_input(&mut self, frame: &FrameInput) {
+        let state = self.states.get_mut(self.current_index);
+        state.mouse_move(frame.mouse_position);
+        if frame.mouse_pressed {
+            state.mouse_down(frame.mouse_position);
+        }
+        if frame.mouse_released {
+            state.mouse_up(frame.mouse_position);
+        }
+        if frame.right_mouse_pressed {
+            state.right_mouse_down(frame.mouse_position);
+        }
+        if frame.right_mouse_released {
+            state.right_mouse_up(frame.mouse_position);
+        }
+        if let Some(key) = frame.key.filter(|key| !GLOBAL_KEYS.contains(key)) {
+            state.key_pressed(key);
+        }
+        if let Some(text) = &frame.pasted_text {
+            state.text_pasted(text);
+        }
+    }
+
+    /// Runs the worker of the ai and updates the states in the fixed steps, that the time of the frame
+    /// adds up to. The results of the ai recorded in the frame are fed in the step they arrived in. The
+    /// results, that arrive, and the transitions are recorded, if there is a recorder. Returns the
+    /// transitions of the frame.
+    pub fn advance(
+        &mut self,
+        frame: &FrameInput,
+        black_board: &mut Blackboard,
+        mut recorder: Option<&mut SessionRecorder>,
+    ) -> Vec<Transition> {
+        // On the web the worker of the ai runs here, elsewhere it has its own thread.
+        black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .run_worker(frame.frame_time);
+        // A worker of the ai, that has died, has been replaced.
+        if black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .take_worker_restarted()
+        {
+            log::warn!("The ai worker has stopped and got restarted");
+            black_board
+                .toasts
+                .push("The ai had to be restarted", TOAST_DURATION);
+        }
+
+        // A transition takes effect for the next step.
+        let mut transitions = Vec::new();
+        for step in 0..self.timestep.advance(frame.frame_time) {
+            for &(_, result) in frame.ai_results.iter().filter(|&&(at, _)| at == step) {
+                black_board
+                    .computer_player
+                    .get_ai_handler_mut()
+                    .feed_result(result);
+            }
+            black_board.logic_time += FIXED_DELTA_TIME as f64;
+            let update_result = self
+                .states
+                .get_mut(self.current_index)
+                .update(FIXED_DELTA_TIME, black_board);
+            let arrived_results = black_board
+                .computer_player
+                .get_ai_handler_mut()
+                .take_recorded_results();
+            if let Some(recorder) = recorder.as_deref_mut() {
+                for result in arrived_results {
+                    recorder.record_ai_result(step, result);
+                }
+            }
+            if let Some(follow_index) = update_result {
+                log::debug!(
+                    "State transition {:?} -> {follow_index:?}",
+                    self.current_index
+                );
+                transitions.push((self.current_index, follow_index));
+                if let Some(recorder) = recorder.as_deref_mut() {
+                    recorder.record_transition(step, self.current_index, follow_index);
+                    if is_game_start(self.current_index, follow_index, black_board) {
+                        recorder.record_start(black_board.game_board.get_computer_first());
+                    }
+                }
+                self.current_index = follow_index;
+                self.states.get_mut(self.current_index).enter(black_board);
+                black_board.request_redraw();
+            }
+        }
+        // The toasts are independent of the states and survive the transitions.
+        black_board.toasts.update(frame.frame_time);
+        transitions
+    }
+}
+
+/// Replays the recorded session without a window, with the recorded settings and the recorded results of
+/// the ai. Every frame, whose transitions differ from the recorded ones, gets reported. The state and the
+/// score of the session at the end get logged. Returns, if the session could be read and has run the same
+/// way as recorded.
+pub fn run_headless_replay(path: &str) -> bool {
+    let replay = match SessionReplay::load(path) {
+        Ok(replay) => replay,
+        Err(error) => {
+            log::error!("The session {path} can not be replayed: {error}");
+            return false;
+        }
+    };
+    let outcome = replay_session(replay);
+    log::info!(
+        "The session {path} has been replayed in {} frames with {} transitions and {} frames \
+         diverging, it ends in {:?} with the score {}",
+        outcome.frame_count,
+        outcome.transitions.len(),
+        outcome.divergences,
+        outcome.driver.get_current_index(),
+        outcome.black_board.session_score
+    );
+    outcome.divergences == 0
+}
+
+/// The end of a session replayed without a window.
+struct ReplayOutcome {
+    driver: StateDriver,
+    black_board: Blackboard,
+    /// The transitions of the states in the replay.
+    transitions: Vec<Transition>,
+    frame_count: usize,
+    /// The number of frames, whose transitions differ from the recorded ones.
+    divergences: usize,
+}
+
+/// Plays the recorded session back to its end, every frame, that diverges, gets reported.
+fn replay_session(mut replay: SessionReplay) -> ReplayOutcome {
+    let mut black_board = Blackboard::new();
+    black_board.settings = replay.get_settings();
+    black_board.apply_settings();
+    black_board.persist_settings = false;
+    black_board.random = SplitMix64::new(replay.get_seed());
+    black_board
+        .computer_player
+        .get_ai_handler_mut()
+        .set_scripted(true);
+
+    let mut driver = StateDriver::new();
+    let mut all_transitions = Vec::new();
+    let mut frame_count = 0;
+    let mut divergences = 0;
+    while let Some(frame) = replay.take_frame() {
+        driver.dispatch_input(&frame);
+        let transitions = driver.advance(&frame, &mut black_board, None);
+        if transitions != frame.transitions {
+            log::warn!(
+                "Frame {frame_count} diverges, the states went {transitions:?} instead of {:?}",
+                frame.transitions
+            );
+            divergences += 1;
+        }
+        all_transitions.extend(transitions);
+        frame_count += 1;
+    }
+    ReplayOutcome {
+        driver,
+        black_board,
+        transitions: all_transitions,
+        frame_count,
+        divergences,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::render_system::graphics::get_drawing_coordinates;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_player_start_selection::PLAYER_STARTS_BUTTON;
+    use connect_4_rust::board_logic::ai_handler::AiResult;
+    use macroquad::math::Vec2;
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Mutex, Once};
+    use std::thread::{self, ThreadId};
+    use std::time::{Duration, Instant};
+
+    /// A record logged with its level and its message.
+    pub type LoggedRecord = (log::Level, String);
+
+    /// Keeps the records logged by the threads, that capture them. The tests run in parallel, each one
+    /// only gets the records of its own thread.
+    struct CapturingLogger {
+        records: Mutex<Vec<(ThreadId, Vec<LoggedRecord>)>>,
+    }
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let thread_id = thread::current().id();
+            let mut records = self.records.lock().unwrap();
+            if let Some((_, thread_records)) = records.iter_mut().find(|(id, _)| *id == thread_id) {
+                thread_records.push((record.level(), record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CapturingLogger = CapturingLogger {
+        records: Mutex::new(Vec::new()),
+    };
+
+    /// Starts to capture the records logged by the current thread.
+    pub fn start_log_capture() {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            log::set_logger(&LOGGER).expect("No other logger is installed in the tests.");
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+        LOGGER
+            .records
+            .lock()
+            .unwrap()
+            .push((thread::current().id(), Vec::new()));
+    }
+
+    /// Stops the capture of the current thread and gets the records it has logged.
+    pub fn take_logged_records() -> Vec<LoggedRecord> {
+        let thread_id = thread::current().id();
+        let mut records = LOGGER.records.lock().unwrap();
+        let Some(index) = records.iter().position(|(id, _)| *id == thread_id) else {
+            return Vec::new();
+        };
+        records.swap_remove(index).1
+    }
+
+    /// The time of a frame of the headless game, unless it runs at another frame rate.
+    pub const FRAME_TIME: f32 = 1.0 / 60.0;
+
+    /// The time a click gets to take effect, it is enough for a stone to fall over the whole board.
+    pub const SETTLE_TIME: f32 = 1.5;
+
+    /// A game without a window. The input is made up by the test, the moves of the computer get fed in as
+    /// scripted results of the ai, so that every run is the same.
+    pub struct HeadlessGame {
+        pub driver: StateDriver,
+        pub black_board: Blackboard,
+        /// The transitions of the states so far.
+        pub transitions: Vec<Transition>,
+        /// The time of the frames, the waiting and the clicks run with.
+        pub frame_time: f32,
+        /// Records the session, if the recording has been started.
+        pub recorder: Option<SessionRecorder>,
+    }
+
+    impl HeadlessGame {
+        /// Starts on the start screen with the default settings, which are not stored. The computer
+        /// does not hold its moves back.
+        pub fn new() -> HeadlessGame {
+            let mut black_board = Blackboard::new();
+            black_board.persist_settings = false;
+            black_board.settings.sound_enabled = false;
+            black_board.settings.think_time_ms = 0;
+            black_board.apply_settings();
+            black_board
+                .computer_player
+                .get_ai_handler_mut()
+                .set_scripted(true);
+            let mut driver = StateDriver::new();
+            driver.get_current_state().enter(&black_board);
+            HeadlessGame {
+                driver,
+                black_board,
+                transitions: Vec::new(),
+                frame_time: FRAME_TIME,
+                recorder: None,
+            }
+        }
+
+        /// Records the session from now on into the file like the main loop, with the seed of the random
+        /// generator. The results of the ai get recorded as well.
+        pub fn start_recording(&mut self, path: &Path, seed: u64) {
+            self.black_board.random = SplitMix64::new(seed);
+            self.black_board
+                .computer_player
+                .get_ai_handler_mut()
+                .set_recording(true);
+            self.recorder =
+                Some(SessionRecorder::create(path, &self.black_board.settings, seed).unwrap());
+        }
+
+        /// Runs one frame with the input.
+        pub fn run_frame(&mut self, frame: FrameInput) {
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.record_frame(&frame);
+            }
+            self.driver.dispatch_input(&frame);
+            let transitions =
+                self.driver
+                    .advance(&frame, &mut self.black_board, self.recorder.as_mut());
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.flush();
+            }
+            self.transitions.extend(transitions);
+        }
+
+        /// Runs the frames without input for about the indicated time.
+        pub fn wait(&mut self, seconds: f32) {
+            for _ in 0..(seconds / self.frame_time).ceil() as u32 {
+                self.run_frame(FrameInput {
+                    frame_time: self.frame_time,
+                    ..FrameInput::default()
+                });
+            }
+        }
+
+        /// Clicks at the position in layout coordinates and waits for the click to take effect.
+        pub fn click(&mut self, position: Vec2) {
+            for (mouse_pressed, mouse_released) in [(true, false), (false, true)] {
+                self.run_frame(FrameInput {
+                    frame_time: self.frame_time,
+                    mouse_position: position,
+                    mouse_pressed,
+                    mouse_released,
+                    ..FrameInput::default()
+                });
+            }
+            self.wait(SETTLE_TIME);
+        }
+
+        /// Clicks with the right mouse button at the position and waits for the click to take effect.
+        pub fn right_click(&mut self, position: Vec2) {
+            for (right_mouse_pressed, right_mouse_released) in [(true, false), (false, true)] {
+                self.run_frame(FrameInput {
+                    frame_time: self.frame_time,
+                    mouse_position: position,
+                    right_mouse_pressed,
+                    right_mouse_released,
+                    ..FrameInput::default()
+                });
+            }
+            self.wait(SETTLE_TIME);
+        }
+
+        /// Clicks into the column above the bottom row, where a click would pop a stone.
+        pub fn click_column(&mut self, column: u32) {
+            self.click(get_drawing_coordinates(column, 2));
+        }
+
+        /// Presses the key and waits for it to take effect.
+        pub fn press_key(&mut self, key: KeyCode) {
+            self.run_frame(FrameInput {
+                frame_time: self.frame_time,
+                key: Some(key),
+                ..FrameInput::default()
+            });
+            self.wait(SETTLE_TIME);
+        }
+
+        /// Answers the computer move, that is pending, with the column in the next frame, which takes the
+        /// indicated time. The answer is not waited for.
+        pub fn feed_computer_move(&mut self, column: u32, frame_time: f32) {
+            let generation = self
+                .black_board
+                .computer_player
+                .get_ai_handler()
+                .get_pending_move()
+                .expect("A computer move has been requested.");
+            self.run_frame(FrameInput {
+                frame_time,
+                ai_results: vec![(
+                    0,
+                    AiResult {
+                        column: Ok(column),
+                        score: 0.0,
+                        generation,
+                    },
+                )],
+                ..FrameInput::default()
+            });
+        }
+
+        /// Answers the computer move, that is pending, with the column and waits for it to be played.
+        pub fn answer_computer_move(&mut self, column: u32) {
+            self.feed_computer_move(column, self.frame_time);
+            self.wait(SETTLE_TIME);
+        }
+
+        /// Plays the columns in turn, the moves of the player get clicked and the ones of the computer
+        /// answered.
+        pub fn play_columns(&mut self, columns: &[u32]) {
+            for &column in columns {
+                if self.black_board.game_board.side_to_move().is_computer() {
+                    self.answer_computer_move(column);
+                } else {
+                    self.click_column(column);
+                }
+            }
+        }
+
+        /// Gets the state the game is in.
+        pub fn get_current_index(&self) -> GameStateIndex {
+            self.driver.get_current_index()
+        }
+    }
+
+    #[test]
+    fn test_recorded_game_replays_the_same() {
+        // The game is played against the real engine, the replay only gets its recorded results.
+        let path =
+            std::env::temp_dir().join(format!("connect-4-round-trip-{}.txt", std::process::id()));
+        let mut game = HeadlessGame::new();
+        game.black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .set_scripted(false);
+        game.start_recording(&path, 1234);
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        for column in [3, 3, 2, 4, 1, 5, 0, 6, 3, 2, 4, 1] {
+            if game.get_current_index() != GameStateIndex::PlayerInputState {
+                break;
+            }
+            if game
+                .black_board
+                .game_board
+                .get_move_destination(column)
+                .is_some()
+            {
+                game.click_column(column);
+            }
+            // The engine needs some time for its answer.
+            let deadline = Instant::now() + Duration::from_secs(20);
+            while game.get_current_index() == GameStateIndex::ComputerExecutionState
+                && Instant::now() < deadline
+            {
+                game.wait(FRAME_TIME);
+                thread::sleep(Duration::from_millis(5));
+            }
+        }
+        game.wait(SETTLE_TIME);
+        assert!(game.black_board.game_board.stone_count() >= 4);
+
+        let outcome = replay_session(SessionReplay::load(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(outcome.divergences, 0);
+        assert_eq!(outcome.transitions, game.transitions);
+        assert_eq!(outcome.driver.get_current_index(), game.get_current_index());
+        assert_eq!(
+            outcome.black_board.game_board.to_ascii(),
+            game.black_board.game_board.to_ascii()
+        );
+        assert_eq!(
+            outcome.black_board.game_board.get_move_history(),
+            game.black_board.game_board.get_move_history()
+        );
+    }
+}
@@ -1,77 +1,381 @@
 //! This module shows the game over part with the winning situation and an additional text.
-//! On mouse interaction we transfer to the player selection screen.
+//! There are buttons to go back to the player selection screen, to start a rematch with swapped roles
+//! and to look at the replay of the game. During a match the first button leads to the score of the
+//! match instead and there is no rematch. The final board is shown for a short moment, before the
+//! winning stones light up one after another and get connected by a stroke. Below the result a line tells,
+//! how the game has been won, like "A rising diagonal from c2 to f5". In a network game only the host can
+//! start the rematch and a game, that did not end on the board, gets explained. Control and C copy the move
+//! string of the game to the clipboard.
 
-use crate::board_logic::bit_board::GameResult;
-use crate::render_system::graphics::{print_text, render_board, render_winning_stones};
+use crate::render_system::audio::SoundEffect;
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, STANDARD_LAYOUT, draw_button, print_text_centered, print_text_centered_sized,
+    render_board, render_last_move_marker, render_winning_stones, render_winning_stroke,
+};
+use crate::render_system::toast::TOAST_DURATION;
+use crate::state_system::button_press::ButtonPress;
+use crate::state_system::game_event::GameEvent;
 use crate::state_system::game_state::{Blackboard, GameState, GameStateIndex};
-use macroquad::math::Vec2;
+use connect_4_rust::board_logic::bit_board::GameResult;
+use connect_4_rust::board_logic::bit_board_coding::{BoardGeometry, StandardGeometry, WinningLine};
+use connect_4_rust::board_logic::notation::describe_win;
+use macroquad::math::{Rect, Vec2};
+use macroquad::prelude::KeyCode;
 
 pub struct StateGameOver {
     end_result: GameResult,
-    highlighted_stones: Vec<(u32, u32)>,
-    exit_pressed: bool,
+    winning_lines: Vec<WinningLine>,
+    /// The description of the win on the board, none if the game has not been won there.
+    win_description: Option<String>,
+    button_pressed: Option<GameOverButton>,
+    /// The time passed since the game ended, used to reveal the winning stones.
+    reveal_time: f32,
+    /// The button the mouse got pressed on.
+    button_press: ButtonPress<GameOverButton>,
+    /// The game is part of a match.
+    in_match: bool,
+    /// The rematch can be started from here.
+    rematch_possible: bool,
+    /// The move string of the game is to be copied to the clipboard.
+    copy_requested: bool,
 }
 
-const TEXT_POSITION: Vec2 = Vec2 { x: 200.0, y: 640.0 };
+/// The buttons of the game over screen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum GameOverButton {
+    Menu,
+    Rematch,
+    Replay,
+}
+
+/// The center of the result text left of the buttons.
+const TEXT_CENTER: Vec2 = Vec2 { x: 175.0, y: 674.0 };
+/// The center of the description of the win below the result.
+const WIN_DESCRIPTION_CENTER: Vec2 = Vec2 { x: 175.0, y: 647.0 };
+/// The font size of the description of the win, it has to fit left of the buttons.
+const WIN_DESCRIPTION_SIZE: u16 = 20;
+/// The center of the session score below the description of the win.
+const SCORE_CENTER: Vec2 = Vec2 { x: 175.0, y: 628.0 };
+/// The center of the score of the match below the session score.
+const MATCH_SCORE_CENTER: Vec2 = Vec2 { x: 175.0, y: 607.0 };
+/// The time the final board is shown, before the winning stones get highlighted.
+const REVEAL_PAUSE: f32 = 0.6;
+/// The time between highlighting two consecutive stones of a winning line.
+const REVEAL_STEP_TIME: f32 = 0.15;
+/// The button to go back to the start selection.
+pub const MENU_BUTTON: Rect = Rect {
+    x: 355.0,
+    y: 610.0,
+    w: 105.0,
+    h: 80.0,
+};
+/// The button to play again with swapped roles.
+pub const REMATCH_BUTTON: Rect = Rect {
+    x: 470.0,
+    y: 610.0,
+    w: 105.0,
+    h: 80.0,
+};
+/// The button to go to the replay.
+pub const REPLAY_BUTTON: Rect = Rect {
+    x: 585.0,
+    y: 610.0,
+    w: 105.0,
+    h: 80.0,
+};
 
 impl StateGameOver {
     pub fn new() -> StateGameOver {
         StateGameOver {
             end_result: GameResult::Pending,
-            highlighted_stones: Vec::new(),
-            exit_pressed: false,
+            winning_lines: Vec::new(),
+            win_description: None,
+            button_pressed: None,
+            reveal_time: 0.0,
+            button_press: ButtonPress::new(),
+            in_match: false,
+            rematch_possible: false,
+            copy_requested: false,
         }
     }
+
+    /// Gets the amount of stones of every winning line, that are highlighted already. After a pause, in
+    /// which the final board is shown as is, the stones light up one by one along the line.
+    fn get_revealed_stones(&self) -> usize {
+        if self.reveal_time < REVEAL_PAUSE {
+            return 0;
+        }
+        let steps = ((self.reveal_time - REVEAL_PAUSE) / REVEAL_STEP_TIME) as usize + 1;
+        steps.min(StandardGeometry::WIN_LENGTH as usize)
+    }
+}
+
+/// Gets the text with its first letter in upper case.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Gets the button at the indicated position, none if there is no button.
+fn get_button_at(position: Vec2) -> Option<GameOverButton> {
+    if MENU_BUTTON.contains(position) {
+        Some(GameOverButton::Menu)
+    } else if REMATCH_BUTTON.contains(position) {
+        Some(GameOverButton::Rematch)
+    } else if REPLAY_BUTTON.contains(position) {
+        Some(GameOverButton::Replay)
+    } else {
+        None
+    }
 }
 
 impl GameState for StateGameOver {
-    /// On enter we extract the information of why the game is over and eventually highlighted stones.
+    /// On enter we extract the information of why the game is over and eventually the winning lines with
+    /// their description. A resigned game has its result on the blackboard and no winning lines. The end
+    /// of the game gets announced with a jingle.
     fn enter(&mut self, black_board: &Blackboard) {
-        let (state, list) = black_board.game_board.get_winning_status_for_rendering();
+        let state = black_board.get_game_result();
         assert_ne!(
             state,
             GameResult::Pending,
             "The game should have been ended now"
         );
         self.end_result = state;
-        self.highlighted_stones = list.unwrap_or(Vec::new());
-        self.exit_pressed = false;
+        self.winning_lines = black_board.get_winning_lines();
+        self.win_description = describe_win(&self.winning_lines).map(|text| capitalize(&text));
+        self.button_pressed = None;
+        self.reveal_time = 0.0;
+        self.button_press.cancel();
+        self.in_match = black_board.match_state.is_some();
+        self.rematch_possible = !self.in_match && !black_board.is_network_guest();
+        self.copy_requested = false;
+        black_board.play_sound(SoundEffect::Win);
     }
 
-    /// When the exit got triggered we leave and clear the board and go for start selection.
-    fn update(&mut self, _: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
-        if self.exit_pressed {
-            black_board.game_board.reset();
-            Some(GameStateIndex::StartSelection)
-        } else {
-            None
+    /// Counts the game, that has ended, in the scores and the statistics of the player and exports its
+    /// record, runs the reveal of the winning stones and follows up on the button pressed. For the menu
+    /// we clear the board and go for start selection, the replay still needs the board. In a match the
+    /// score of the match comes next, which goes on with the next game or ends the match. The rematch
+    /// clears the board, swaps the roles and starts the game right away, when the computer starts the
+    /// first calculation is kicked off.
+    fn update(&mut self, delta_time: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        for event in black_board.drain_events() {
+            if let GameEvent::GameEnded { result } = event {
+                black_board.record_game(result);
+                black_board.update_player_stats(result);
+                black_board.export_game_record(result);
+            }
+        }
+        self.reveal_time += delta_time;
+        // A game with pops or from a position of the board editor can not be written as a move string.
+        if self.copy_requested {
+            self.copy_requested = false;
+            match black_board.game_board.to_move_string() {
+                Some(moves) => {
+                    black_board.clipboard_text = Some(moves);
+                    black_board
+                        .toasts
+                        .push("Move string copied", TOAST_DURATION);
+                }
+                None => black_board
+                    .toasts
+                    .push("The game has no move string", TOAST_DURATION),
+            }
+        }
+        match self.button_pressed? {
+            GameOverButton::Menu if self.in_match => Some(GameStateIndex::MatchScore),
+            GameOverButton::Menu => {
+                black_board.reset_game();
+                Some(GameStateIndex::StartSelection)
+            }
+            GameOverButton::Rematch => {
+                let computer_first = !black_board.game_board.get_computer_first();
+                Some(black_board.start_game(computer_first))
+            }
+            GameOverButton::Replay => Some(GameStateIndex::ReplayState),
+        }
+    }
+
+    /// Remembers which of the buttons got pressed, clicks elsewhere are ignored.
+    fn mouse_down(&mut self, position: Vec2) {
+        self.button_press.press(get_button_at(position));
+    }
+
+    /// Activates the button, if the mouse got released on the pressed one. There is no rematch during a
+    /// match and for the guest of a network game.
+    fn mouse_up(&mut self, position: Vec2) {
+        match self.button_press.release(get_button_at(position)) {
+            Some(GameOverButton::Rematch) if !self.rematch_possible => {}
+            Some(button) => self.button_pressed = Some(button),
+            None => {}
         }
     }
 
-    /// Checks if mouse button got pressed and flags that we want to leave.
-    fn mouse_click(&mut self, _: Vec2) {
-        self.exit_pressed = true;
+    /// Control and C request the move string to be copied.
+    fn key_pressed(&mut self, key: KeyCode) {
+        if key == KeyCode::C {
+            self.copy_requested = true;
+        }
     }
 
-    /// Renders the board, eventually highlighted winning stones and the game end
-    /// status icon.
+    /// Renders the board, eventually the winning stones highlighted so far, the marker on the last move
+    /// and the game end status icon with the description of the win.
     fn draw(&self, black_board: &Blackboard) {
-        render_board(&black_board.game_board, &black_board.board_texture);
+        render_board(&black_board.game_board, black_board.board_texture.as_ref());
+        let revealed_stones = self.get_revealed_stones();
 
-        // The indicator.
-        match self.end_result {
-            GameResult::Pending => {
-                panic!("Should not be the case")
-            }
-            GameResult::FirstPlayerWon => {
-                print_text("Yellow has won", TEXT_POSITION);
-                render_winning_stones(true, &self.highlighted_stones);
+        // Winning lines only exist, if the game has been won on the board.
+        let first_player_won = self.end_result.is_first_player_win();
+        for line in &self.winning_lines {
+            render_winning_stones(first_player_won, &line.cells[..revealed_stones]);
+            if revealed_stones == line.cells.len() {
+                render_winning_stroke(line, first_player_won, &STANDARD_LAYOUT);
             }
-            GameResult::SecondPlayerWon => {
-                print_text("Blue has won", TEXT_POSITION);
-                render_winning_stones(false, &self.highlighted_stones);
+        }
+        // The indicator.
+        print_text_centered(&black_board.status_text(), TEXT_CENTER);
+        if let Some(win_description) = &self.win_description {
+            print_text_centered_sized(
+                win_description,
+                WIN_DESCRIPTION_CENTER,
+                WIN_DESCRIPTION_SIZE,
+            );
+        }
+        if let Some(coded_move) = black_board.get_most_recent_move() {
+            render_last_move_marker(coded_move);
+        }
+        let menu_label = match &black_board.match_state {
+            Some(match_state) if !match_state.is_decided() => "Next",
+            _ => "Menu",
+        };
+        draw_button(MENU_BUTTON, menu_label, SMALL_TEXT_SIZE, true);
+        draw_button(
+            REMATCH_BUTTON,
+            "Rematch",
+            SMALL_TEXT_SIZE,
+            self.rematch_possible,
+        );
+        draw_button(REPLAY_BUTTON, "Replay", SMALL_TEXT_SIZE, true);
+        print_text_centered_sized(
+            &black_board.session_score.to_string(),
+            SCORE_CENTER,
+            SMALL_TEXT_SIZE,
+        );
+        if let Some(match_state) = &black_board.match_state {
+            print_text_centered_sized(
+                &match_state.to_string(),
+                MATCH_SCORE_CENTER,
+                SMALL_TEXT_SIZE,
+            );
+        } else if let Some(message) = &black_board.game_over_message {
+            print_text_centered_sized(message, MATCH_SCORE_CENTER, SMALL_TEXT_SIZE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::{FRAME_TIME, HeadlessGame, SETTLE_TIME};
+    use crate::state_player_start_selection::{COMPUTER_STARTS_BUTTON, PLAYER_STARTS_BUTTON};
+    use crate::state_system::session_record::FrameInput;
+
+    /// Gets a game, that the starter has won by dropping four stones into the first column.
+    fn get_finished_game(start_button: Rect) -> HeadlessGame {
+        let mut game = HeadlessGame::new();
+        game.click(start_button.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&[0, 1, 0, 1, 0, 1, 0]);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        game
+    }
+
+    #[test]
+    fn test_rematch_lets_the_computer_start() {
+        let mut game = get_finished_game(PLAYER_STARTS_BUTTON);
+        game.click(REMATCH_BUTTON.center());
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        assert!(game.black_board.game_board.get_computer_first());
+        assert_eq!(game.black_board.game_board.stone_count(), 0);
+        assert!(
+            game.black_board
+                .computer_player
+                .get_ai_handler()
+                .is_move_pending()
+        );
+    }
+
+    #[test]
+    fn test_rematch_lets_the_player_start() {
+        let mut game = get_finished_game(COMPUTER_STARTS_BUTTON);
+        game.click(REMATCH_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert!(!game.black_board.game_board.get_computer_first());
+        assert_eq!(game.black_board.game_board.stone_count(), 0);
+    }
+
+    #[test]
+    fn test_only_the_menu_button_leads_to_the_menu() {
+        let mut game = get_finished_game(PLAYER_STARTS_BUTTON);
+        // A stray click on the board keeps the final position.
+        game.click(Vec2::new(350.0, 300.0));
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        // Pressing on the rematch and releasing elsewhere does nothing either.
+        for (position, mouse_pressed) in [
+            (REMATCH_BUTTON.center(), true),
+            (MENU_BUTTON.center(), false),
+        ] {
+            game.run_frame(FrameInput {
+                frame_time: FRAME_TIME,
+                mouse_position: position,
+                mouse_pressed,
+                mouse_released: !mouse_pressed,
+                ..FrameInput::default()
+            });
+        }
+        game.wait(SETTLE_TIME);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        game.click(MENU_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        assert_eq!(game.black_board.game_board.stone_count(), 0);
+        assert_eq!(game.black_board.session_score.player_wins, 1);
+    }
+
+    #[test]
+    fn test_winning_stones_light_up_along_the_line() {
+        let mut game = get_finished_game(PLAYER_STARTS_BUTTON);
+        let mut state = StateGameOver::new();
+        state.enter(&game.black_board);
+        assert_eq!(state.winning_lines.len(), 1);
+        assert_eq!(
+            state.winning_lines[0].cells,
+            [(0, 0), (0, 1), (0, 2), (0, 3)]
+        );
+
+        // The times, at which the next stone lights up, in frames of a hundredth of a second.
+        let mut reveal_frames = Vec::new();
+        let mut revealed = state.get_revealed_stones();
+        assert_eq!(revealed, 0);
+        for frame in 1..=300u32 {
+            state.update(0.01, &mut game.black_board);
+            let now_revealed = state.get_revealed_stones();
+            if now_revealed != revealed {
+                assert_eq!(now_revealed, revealed + 1);
+                reveal_frames.push(frame);
+                revealed = now_revealed;
             }
-            GameResult::Draw => print_text("Draw", TEXT_POSITION),
+        }
+        assert_eq!(revealed, StandardGeometry::WIN_LENGTH as usize);
+        // The final board is shown for the pause, the whole reveal takes a bit more than a second.
+        for (frame, expected) in reveal_frames.iter().zip([60, 75, 90, 105]) {
+            assert!(frame.abs_diff(expected) <= 1, "{reveal_frames:?}");
         }
     }
 }
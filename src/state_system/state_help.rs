@@ -0,0 +1,249 @@
+//! The help state explains the rules and the controls to new players. Below the text a small example board
+//! shows a won game with a diagonal line. Any click or Escape goes back to the start screen.
+
+use crate::game_state::{Blackboard, GameState, GameStateIndex};
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, SymbolColor, TITLE_CENTER, WINDOW_DIMENSION, get_color, print_text_centered,
+    print_text_centered_sized,
+};
+use crate::render_system::theme::get_active_theme;
+use connect_4_rust::board_logic::bit_board_coding::{BOARD_HEIGHT, BOARD_WIDTH};
+use macroquad::prelude::*;
+
+/// The text of the help, one entry per line. The lines have to be short enough to fit into the window
+/// with the small font size.
+pub const HELP_LINES: [&str; 8] = [
+    "Drop your stones into the columns in turns.",
+    "Four in a row wins: across, up or diagonal.",
+    "Click a column to drop your stone there.",
+    "? or the H key ask the computer for a hint.",
+    "Undo takes back your last move.",
+    "The = button lets you resign or restart.",
+    "In the replay the arrow keys step through.",
+    "Esc leaves the settings and the replay.",
+];
+
+/// The center of the first line of the help.
+const FIRST_LINE_CENTER: Vec2 = Vec2 {
+    x: 0.5 * WINDOW_DIMENSION,
+    y: 535.0,
+};
+/// The vertical distance between two lines.
+const LINE_DISTANCE: f32 = 34.0;
+/// The size of a cell of the example board.
+const EXAMPLE_CELL_SIZE: f32 = 30.0;
+/// The lower left corner of the example board.
+const EXAMPLE_ORIGIN: Vec2 = Vec2 {
+    x: 0.5 * (WINDOW_DIMENSION - BOARD_WIDTH as f32 * EXAMPLE_CELL_SIZE),
+    y: 60.0,
+};
+/// The stones of the example board as column, row and the flag, if it belongs to the first player.
+/// Yellow has just won with a diagonal from the second column on.
+const EXAMPLE_STONES: [(u32, u32, bool); 11] = [
+    (0, 0, false),
+    (1, 0, true),
+    (2, 0, false),
+    (2, 1, true),
+    (3, 0, true),
+    (3, 1, false),
+    (3, 2, true),
+    (4, 0, false),
+    (4, 1, true),
+    (4, 2, false),
+    (4, 3, true),
+];
+/// The winning line of the example board.
+const EXAMPLE_LINE: [(u32, u32); 4] = [(1, 0), (2, 1), (3, 2), (4, 3)];
+/// The center of the line telling how to go back.
+const BACK_HINT_CENTER: Vec2 = Vec2 {
+    x: 0.5 * WINDOW_DIMENSION,
+    y: 25.0,
+};
+
+/// Gets the center of the cell of the example board.
+fn get_example_coordinates(column: u32, row: u32) -> Vec2 {
+    EXAMPLE_ORIGIN + (Vec2::new(column as f32, row as f32) + 0.5) * EXAMPLE_CELL_SIZE
+}
+
+/// Draws the example board with the winning line highlighted.
+fn draw_example_board() {
+    draw_rectangle(
+        EXAMPLE_ORIGIN.x,
+        EXAMPLE_ORIGIN.y,
+        BOARD_WIDTH as f32 * EXAMPLE_CELL_SIZE,
+        BOARD_HEIGHT as f32 * EXAMPLE_CELL_SIZE,
+        *get_color(SymbolColor::Brown),
+    );
+    let radius = 0.4 * EXAMPLE_CELL_SIZE;
+    for column in 0..BOARD_WIDTH {
+        for row in 0..BOARD_HEIGHT {
+            let center = get_example_coordinates(column, row);
+            draw_circle(center.x, center.y, radius, get_active_theme().background);
+        }
+    }
+    for (column, row, first) in EXAMPLE_STONES {
+        let color = if EXAMPLE_LINE.contains(&(column, row)) {
+            SymbolColor::LightYellow
+        } else if first {
+            SymbolColor::Yellow
+        } else {
+            SymbolColor::Blue
+        };
+        let center = get_example_coordinates(column, row);
+        draw_circle(center.x, center.y, radius, *get_color(color));
+    }
+    let start = get_example_coordinates(EXAMPLE_LINE[0].0, EXAMPLE_LINE[0].1);
+    let end = get_example_coordinates(EXAMPLE_LINE[3].0, EXAMPLE_LINE[3].1);
+    draw_line(
+        start.x,
+        start.y,
+        end.x,
+        end.y,
+        0.2 * EXAMPLE_CELL_SIZE,
+        get_active_theme().text,
+    );
+}
+
+pub struct StateHelp {
+    back_requested: bool,
+}
+
+impl StateHelp {
+    pub fn new() -> StateHelp {
+        StateHelp {
+            back_requested: false,
+        }
+    }
+}
+
+impl GameState for StateHelp {
+    fn enter(&mut self, _: &Blackboard) {
+        self.back_requested = false;
+    }
+
+    /// Goes back to the start screen, when requested.
+    fn update(&mut self, _: f32, _: &mut Blackboard) -> Option<GameStateIndex> {
+        self.back_requested
+            .then_some(GameStateIndex::StartSelection)
+    }
+
+    /// Any click goes back.
+    fn mouse_down(&mut self, _: Vec2) {
+        self.back_requested = true;
+    }
+
+    /// Goes back with Escape.
+    fn key_pressed(&mut self, key: KeyCode) {
+        if key == KeyCode::Escape {
+            self.back_requested = true;
+        }
+    }
+
+    /// Renders the text and the example board.
+    fn draw(&self, _: &Blackboard) {
+        print_text_centered("How to play", TITLE_CENTER);
+        for (index, line) in HELP_LINES.iter().enumerate() {
+            print_text_centered_sized(
+                line,
+                FIRST_LINE_CENTER - Vec2::new(0.0, LINE_DISTANCE * index as f32),
+                SMALL_TEXT_SIZE,
+            );
+        }
+        draw_example_board();
+        print_text_centered_sized(
+            "Click or press Esc to go back",
+            BACK_HINT_CENTER,
+            SMALL_TEXT_SIZE,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_system::graphics::TEXT_SIZE;
+    use crate::state_driver::tests::HeadlessGame;
+    use crate::state_player_start_selection::HELP_BUTTON_CENTER;
+    use connect_4_rust::board_logic::bit_board::BitBoard;
+    use connect_4_rust::board_logic::bit_board_coding::{BoardGeometry, StandardGeometry};
+
+    /// The share of the font size, that every character of the default font advances. The font is
+    /// monospaced, the advance is 896 of the 2048 units of its em.
+    const CHARACTER_ADVANCE: f32 = 896.0 / 2048.0;
+
+    /// Gets the width of the text in the default font with the font size.
+    fn get_text_width(text: &str, font_size: u16) -> f32 {
+        text.chars().count() as f32 * CHARACTER_ADVANCE * font_size as f32
+    }
+
+    #[test]
+    fn test_text_fits_into_the_window() {
+        let margin = 20.0;
+        for line in HELP_LINES
+            .iter()
+            .chain(["Click or press Esc to go back"].iter())
+        {
+            let width = get_text_width(line, SMALL_TEXT_SIZE);
+            assert!(width <= WINDOW_DIMENSION - 2.0 * margin, "{line}: {width}");
+        }
+        assert!(get_text_width("How to play", TEXT_SIZE) <= WINDOW_DIMENSION - 2.0 * margin);
+        // The lines stay above the example board and the hint below it.
+        let last_line = FIRST_LINE_CENTER.y - LINE_DISTANCE * (HELP_LINES.len() - 1) as f32;
+        let board_top = EXAMPLE_ORIGIN.y + BOARD_HEIGHT as f32 * EXAMPLE_CELL_SIZE;
+        assert!(last_line - 0.5 * SMALL_TEXT_SIZE as f32 > board_top);
+        assert!(BACK_HINT_CENTER.y + 0.5 * (SMALL_TEXT_SIZE as f32) < EXAMPLE_ORIGIN.y);
+    }
+
+    #[test]
+    fn test_example_shows_a_won_diagonal() {
+        let yellow = EXAMPLE_STONES.iter().filter(|&&(_, _, first)| first).fold(
+            0,
+            |board, &(column, row, _)| {
+                board | StandardGeometry::get_bit_representation(column, row)
+            },
+        );
+        let lines = StandardGeometry::get_winning_lines(yellow);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].cells, EXAMPLE_LINE);
+        // The stones lie on each other and yellow has played the last one.
+        let stones = BitBoard::from_ascii(&get_example_ascii()).unwrap();
+        assert_eq!(stones.stone_count(), EXAMPLE_STONES.len() as u32);
+    }
+
+    /// Gets the example board as ascii grid with yellow as X.
+    fn get_example_ascii() -> String {
+        (0..BOARD_HEIGHT)
+            .rev()
+            .map(|row| {
+                (0..BOARD_WIDTH)
+                    .map(|column| {
+                        match EXAMPLE_STONES
+                            .iter()
+                            .find(|&&(x, y, _)| (x, y) == (column, row))
+                        {
+                            Some((_, _, true)) => 'X',
+                            Some((_, _, false)) => 'O',
+                            None => '.',
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_help_is_left_with_a_click_or_escape() {
+        let mut game = HeadlessGame::new();
+        game.click(HELP_BUTTON_CENTER);
+        assert_eq!(game.get_current_index(), GameStateIndex::Help);
+        game.press_key(KeyCode::H);
+        assert_eq!(game.get_current_index(), GameStateIndex::Help);
+        game.press_key(KeyCode::Escape);
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+
+        game.click(HELP_BUTTON_CENTER);
+        game.click(Vec2::new(100.0, 300.0));
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+    }
+}
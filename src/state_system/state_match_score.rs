@@ -0,0 +1,202 @@
+//! The match score state is shown after every game of a match. It lists the games played so far with
+//! the score of the match and the score of the session. While the match is running any click goes on
+//! with the next game, where the other side starts. Once the match is decided, the screen shows the
+//! result of the match and goes back to the start screen.
+
+use crate::game_state::{Blackboard, GameState, GameStateIndex, MatchState};
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, TITLE_CENTER, WINDOW_DIMENSION, print_text_centered, print_text_centered_sized,
+};
+use connect_4_rust::board_logic::bit_board::Side;
+use macroquad::prelude::*;
+
+/// The center of the score of the match.
+const MATCH_SCORE_CENTER: Vec2 = Vec2 {
+    x: 0.5 * WINDOW_DIMENSION,
+    y: 520.0,
+};
+/// The center of the line of the first game listed.
+const FIRST_GAME_CENTER: Vec2 = Vec2 {
+    x: 0.5 * WINDOW_DIMENSION,
+    y: 460.0,
+};
+/// The vertical distance between two listed games.
+const LINE_DISTANCE: f32 = 34.0;
+/// The maximum amount of games listed, with many draws only the latest games are shown.
+const MAX_LISTED_GAMES: usize = 9;
+/// The center of the session score.
+const SESSION_SCORE_CENTER: Vec2 = Vec2 {
+    x: 0.5 * WINDOW_DIMENSION,
+    y: 100.0,
+};
+/// The center of the line telling how to go on.
+const CONTINUE_HINT_CENTER: Vec2 = Vec2 {
+    x: 0.5 * WINDOW_DIMENSION,
+    y: 40.0,
+};
+
+/// Gets the line describing the finished game with the indicated index.
+fn get_game_line(match_state: &MatchState, index: usize) -> String {
+    let outcome = match match_state.get_game_winner(index) {
+        Some(Side::Player) => "You won",
+        Some(Side::Computer) => "Computer won",
+        None => "Draw",
+    };
+    let starter = if match_state.get_starter(index).is_computer() {
+        "Computer"
+    } else {
+        "You"
+    };
+    format!("Game {}: {outcome} ({starter} started)", index + 1)
+}
+
+pub struct StateMatchScore {
+    continue_requested: bool,
+}
+
+impl StateMatchScore {
+    pub fn new() -> StateMatchScore {
+        StateMatchScore {
+            continue_requested: false,
+        }
+    }
+}
+
+impl GameState for StateMatchScore {
+    fn enter(&mut self, black_board: &Blackboard) {
+        assert!(
+            black_board.match_state.is_some(),
+            "The match score is only shown during a match."
+        );
+        self.continue_requested = false;
+    }
+
+    /// Starts the next game of the match or ends the decided match and goes back to the start screen.
+    fn update(&mut self, _: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        if !self.continue_requested {
+            return None;
+        }
+        let match_state = black_board.match_state.as_ref()?;
+        if match_state.is_decided() {
+            black_board.match_state = None;
+            black_board.reset_game();
+            return Some(GameStateIndex::StartSelection);
+        }
+        let computer_first = match_state.next_starter.is_computer();
+        Some(black_board.start_game(computer_first))
+    }
+
+    /// Any click goes on.
+    fn mouse_down(&mut self, _: Vec2) {
+        self.continue_requested = true;
+    }
+
+    /// Goes on with Enter or Space.
+    fn key_pressed(&mut self, key: KeyCode) {
+        if matches!(key, KeyCode::Enter | KeyCode::Space) {
+            self.continue_requested = true;
+        }
+    }
+
+    /// Renders the state of the match, the games played so far and the session score.
+    fn draw(&self, black_board: &Blackboard) {
+        let Some(match_state) = &black_board.match_state else {
+            return;
+        };
+        let title = match match_state.get_winner() {
+            None => "Match",
+            Some(Side::Player) => "You win the match!",
+            Some(Side::Computer) => "Computer wins the match",
+        };
+        print_text_centered(title, TITLE_CENTER);
+        print_text_centered_sized(
+            &match_state.to_string(),
+            MATCH_SCORE_CENTER,
+            SMALL_TEXT_SIZE,
+        );
+        let first_listed = match_state.games.len().saturating_sub(MAX_LISTED_GAMES);
+        for (line, index) in (first_listed..match_state.games.len()).enumerate() {
+            print_text_centered_sized(
+                &get_game_line(match_state, index),
+                FIRST_GAME_CENTER - Vec2::new(0.0, LINE_DISTANCE * line as f32),
+                SMALL_TEXT_SIZE,
+            );
+        }
+        print_text_centered_sized(
+            &format!("Session: {}", black_board.session_score),
+            SESSION_SCORE_CENTER,
+            SMALL_TEXT_SIZE,
+        );
+        let hint = if match_state.is_decided() {
+            "Click to go back to the menu".to_string()
+        } else {
+            format!("Click to play game {}", match_state.games.len() + 1)
+        };
+        print_text_centered_sized(&hint, CONTINUE_HINT_CENTER, SMALL_TEXT_SIZE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::SessionScore;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::HeadlessGame;
+    use crate::state_game_over::MENU_BUTTON;
+    use crate::state_player_start_selection::{MATCH_LENGTH_BUTTON, PLAYER_STARTS_BUTTON};
+
+    /// Plays the running game of the match to a win of the side, that started it. The starter drops
+    /// four stones into the first column, the other side drops into the second one. Goes on to the score
+    /// of the match afterwards and returns the starter.
+    fn play_game_won_by_starter(game: &mut HeadlessGame) -> Side {
+        let starter = if game.black_board.game_board.get_computer_first() {
+            Side::Computer
+        } else {
+            Side::Player
+        };
+        game.play_columns(&[0, 1, 0, 1, 0, 1, 0]);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        game.click(MENU_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::MatchScore);
+        starter
+    }
+
+    #[test]
+    fn test_match_alternates_the_starter_until_decided() {
+        let mut game = HeadlessGame::new();
+        game.click(MATCH_LENGTH_BUTTON.center());
+        game.click(PLAYER_STARTS_BUTTON.center());
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::DifficultySelection
+        );
+        game.click(BUTTON_CENTERS[0]);
+
+        let mut starters = Vec::new();
+        for played in 1..=3 {
+            starters.push(play_game_won_by_starter(&mut game));
+            let match_state = game.black_board.match_state.as_ref().unwrap();
+            assert_eq!(match_state.games.len(), played);
+            assert_eq!(match_state.is_decided(), played == 3);
+            game.click(Vec2::new(350.0, 300.0));
+        }
+
+        assert_eq!(starters, [Side::Player, Side::Computer, Side::Player]);
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        assert!(game.black_board.match_state.is_none());
+        assert_eq!(
+            game.black_board.session_score,
+            SessionScore {
+                player_wins: 2,
+                computer_wins: 1,
+                draws: 0
+            }
+        );
+        // The games of the match follow each other without the start screen.
+        let to_start = game
+            .transitions
+            .iter()
+            .filter(|&&(_, to)| to == GameStateIndex::StartSelection);
+        assert_eq!(to_start.count(), 1);
+    }
+}
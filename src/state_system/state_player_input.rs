@@ -1,36 +1,381 @@
-//! The player input state administrates the players choice, checks for feasibility and it it would
-//! result in an game over it also executes the falling stone animation. If this is not the end of
+//! The player input state feeds the clicks of the player into the human player, checks the choice for
+//! feasibility and it it would result in an game over it also executes the falling stone animation. If this is not the end of
 //! the game, the computer move calculation is kicked off, before the animations starts.
+//! Above the board is an undo button, that takes back the last computer move and the previous move
+//! of the player, and a hint button, that shows the column the ai would choose for the player. In a
+//! network game there is no undo and the opponent gets told, when the player resigns or restarts.
+//! The menu button in the corner opens an overlay, where the player can resign or restart the game.
+//! While the mouse is over the board, a faint stone previews where the stone would land. A click during
+//! the animation lets the stone drop right away. A click into a full column lets the column flash red.
+//! Under the Pop Out rules a click on an own stone in the bottom row pops it out of the board.
+//! In the training mode every move gets checked for blunders first. If the computer can force a win
+//! against it, an overlay asks the player to confirm the move, a click elsewhere takes it back.
 
-use crate::board_logic::bit_board_coding::BOARD_WIDTH;
-use crate::render_system::graphics::{WINDOW_DIMENSION, render_board};
+use crate::render_system::audio::SoundEffect;
+use crate::render_system::graphics::{
+    BOARD_DRAWING_HEIGHT, SMALL_TEXT_SIZE, TEXT_SIZE, WINDOW_DIMENSION, draw_button,
+    draw_ghost_stone_at_coordinates, get_drawing_coordinates, print_text_centered,
+    print_text_centered_sized, render_board, render_column_flash, render_evaluation_bar,
+    render_last_move_marker, screen_to_slot,
+};
 use crate::render_system::stone_animator::StoneAnimator;
-use crate::state_system::game_state::{Blackboard, GameState, GameStateIndex};
-use macroquad::math::Vec2;
+use crate::state_system::button_press::ButtonPress;
+use crate::state_system::game_event::GameEvent;
+use crate::state_system::game_state::{
+    Blackboard, GameState, GameStateIndex, get_first_player_share,
+};
+use connect_4_rust::board_logic::alpha_beta::SearchStats;
+use connect_4_rust::board_logic::bit_board::{GameResult, Side};
+use connect_4_rust::board_logic::bit_board_coding::BOARD_WIDTH;
+use connect_4_rust::board_logic::blunder_check::find_blunder;
+use macroquad::prelude::*;
 
 pub struct StatePlayerInput {
-    /// The choice coming from the user interface.
+    /// The click on the board, it gets fed to the human player in the update.
     slot_picked: Option<u32>,
     /// The stone animator we use.
     animator: StoneAnimator,
     /// A flag whether we want to transition to game over in the end,
     transition_to_game_over: bool,
-    ///  The buffered move we need to execute.
-    buffered_move: u64,
+    ///  The buffered slot we need to play.
+    buffered_slot: u32,
     /// Indicates, that we are waiting for player input.
     waiting_for_player: bool,
+    /// The undo button got pressed.
+    undo_requested: bool,
+    /// The hint button or key got pressed.
+    hint_requested: bool,
+    /// The slot the hint suggests, it is shown until the player moves.
+    hint_slot: Option<u32>,
+    /// The time the hint is shown, used for the pulsing.
+    hint_time: f32,
+    /// The menu button got pressed.
+    menu_requested: bool,
+    /// The menu overlay is shown, the board does not take input then.
+    menu_open: bool,
+    /// The choice made in the menu overlay.
+    menu_choice: Option<MenuChoice>,
+    /// The latest statistics the search for the computer move has reported.
+    progress: Option<SearchStats>,
+    /// The slot the mouse is over.
+    hovered_slot: Option<u32>,
+    /// A click during the animation fast-forwards it.
+    skip_requested: bool,
+    /// The full column the player tried to drop a stone into.
+    flash_column: Option<u32>,
+    /// The remaining time the full column flashes.
+    flash_time: f32,
+    /// The move waiting for the confirmation of the player, because it loses.
+    blunder_warning: Option<BlunderWarning>,
+    /// The answer of the player to the blunder warning.
+    confirmation_choice: Option<bool>,
+    /// The button the mouse got pressed on.
+    button_press: ButtonPress<InputButton>,
 }
 
+/// A move of the player, against which the computer can force a win.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct BlunderWarning {
+    /// The slot the player wants to play.
+    slot: u32,
+    /// The slot the computer starts its win with.
+    refutation: u32,
+}
+
+impl BlunderWarning {
+    /// Gets the text telling the player, why the move loses.
+    fn get_text(&self) -> String {
+        if self.refutation < BOARD_WIDTH {
+            format!("This loses to column {}", self.refutation + 1)
+        } else {
+            format!(
+                "This loses to a pop in column {}",
+                self.refutation - BOARD_WIDTH + 1
+            )
+        }
+    }
+}
+
+/// The choices of the menu overlay.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MenuChoice {
+    Resign,
+    Restart,
+    Cancel,
+}
+
+/// The buttons of the player input, the menu choices are only there, while the menu is open.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum InputButton {
+    Undo,
+    Hint,
+    Menu,
+    Choice(MenuChoice),
+    /// The answer to the blunder warning, true if the move gets played anyway.
+    Confirm(bool),
+}
+
+/// The button to take back the last turn.
+const UNDO_BUTTON: Rect = Rect {
+    x: 560.0,
+    y: 610.0,
+    w: 130.0,
+    h: 80.0,
+};
+/// The button to ask for a hint.
+const HINT_BUTTON: Rect = Rect {
+    x: 460.0,
+    y: 610.0,
+    w: 80.0,
+    h: 80.0,
+};
+/// The button to open the menu.
+pub const MENU_BUTTON: Rect = Rect {
+    x: 10.0,
+    y: 610.0,
+    w: 80.0,
+    h: 80.0,
+};
+/// The button in the menu to resign the game.
+pub const RESIGN_BUTTON: Rect = Rect {
+    x: 130.0,
+    y: 330.0,
+    w: 200.0,
+    h: 80.0,
+};
+/// The button in the menu to restart the game.
+pub const RESTART_BUTTON: Rect = Rect {
+    x: 370.0,
+    y: 330.0,
+    w: 200.0,
+    h: 80.0,
+};
+/// The button in the menu to go back to the game.
+const CANCEL_BUTTON: Rect = Rect {
+    x: 250.0,
+    y: 210.0,
+    w: 200.0,
+    h: 80.0,
+};
+/// The button to play the move anyway, when warned about a blunder.
+const CONFIRM_BUTTON: Rect = Rect {
+    x: 130.0,
+    y: 250.0,
+    w: 200.0,
+    h: 80.0,
+};
+/// The button to take the move back, when warned about a blunder.
+const REJECT_BUTTON: Rect = Rect {
+    x: 370.0,
+    y: 250.0,
+    w: 200.0,
+    h: 80.0,
+};
+/// The center of the text telling, why the move loses.
+const WARNING_TEXT_CENTER: Vec2 = Vec2 { x: 350.0, y: 440.0 };
+/// The center of the question, if the move should be played anyway.
+const QUESTION_CENTER: Vec2 = Vec2 { x: 350.0, y: 380.0 };
+/// The color the board gets darkened with, while the menu or the blunder warning is shown.
+const OVERLAY_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+/// The opacity of the stone previewing the move under the mouse.
+const PREVIEW_ALPHA: f32 = 0.3;
+/// The time a full column flashes, when the player clicks into it.
+const COLUMN_FLASH_TIME: f32 = 0.3;
+/// The opacity of the flash at its start, it fades out over the flash time.
+const COLUMN_FLASH_ALPHA: f32 = 0.4;
+/// The time in seconds a move chosen in advance stays valid, older choices are not played any more.
+const CHOSEN_MOVE_LIFETIME: f64 = 0.5;
+/// The pulses of the hint stone per second.
+const HINT_PULSE_FREQUENCY: f32 = 1.5;
+
 impl StatePlayerInput {
     pub fn new() -> StatePlayerInput {
         StatePlayerInput {
             slot_picked: None,
             animator: StoneAnimator::new(),
             transition_to_game_over: false,
-            buffered_move: 0,
+            buffered_slot: 0,
             waiting_for_player: false,
+            undo_requested: false,
+            hint_requested: false,
+            hint_slot: None,
+            hint_time: 0.0,
+            menu_requested: false,
+            menu_open: false,
+            menu_choice: None,
+            progress: None,
+            hovered_slot: None,
+            skip_requested: false,
+            flash_column: None,
+            flash_time: 0.0,
+            blunder_warning: None,
+            confirmation_choice: None,
+            button_press: ButtonPress::new(),
+        }
+    }
+
+    /// Gets the button at the indicated position. While the menu is open or the player is warned about a
+    /// blunder, only the buttons of the overlay count.
+    fn get_button_at(&self, position: Vec2) -> Option<InputButton> {
+        let buttons: &[(Rect, InputButton)] = if self.blunder_warning.is_some() {
+            &[
+                (CONFIRM_BUTTON, InputButton::Confirm(true)),
+                (REJECT_BUTTON, InputButton::Confirm(false)),
+            ]
+        } else if self.menu_open {
+            &[
+                (RESIGN_BUTTON, InputButton::Choice(MenuChoice::Resign)),
+                (RESTART_BUTTON, InputButton::Choice(MenuChoice::Restart)),
+                (CANCEL_BUTTON, InputButton::Choice(MenuChoice::Cancel)),
+            ]
+        } else {
+            &[
+                (UNDO_BUTTON, InputButton::Undo),
+                (HINT_BUTTON, InputButton::Hint),
+                (MENU_BUTTON, InputButton::Menu),
+            ]
+        };
+        buttons
+            .iter()
+            .find(|(rect, _)| rect.contains(position))
+            .map(|&(_, button)| button)
+    }
+
+    /// Executes the choice made in the menu overlay. Resigning counts as a computer win and ends the
+    /// game, restarting abandons the game together with a running match and goes back to start selection.
+    /// The opponent on the network takes both as a resignation.
+    fn execute_menu_choice(
+        &mut self,
+        choice: MenuChoice,
+        black_board: &mut Blackboard,
+    ) -> Option<GameStateIndex> {
+        self.menu_open = false;
+        if choice != MenuChoice::Cancel
+            && let Some(remote) = black_board.remote_player.as_mut()
+        {
+            remote.resign();
+        }
+        match choice {
+            MenuChoice::Resign => {
+                self.clear_hint(black_board);
+                black_board.game_result_override =
+                    Some(if black_board.game_board.get_computer_first() {
+                        GameResult::FirstPlayerWon
+                    } else {
+                        GameResult::SecondPlayerWon
+                    });
+                black_board.post_game_end();
+                Some(GameStateIndex::GameOverState)
+            }
+            MenuChoice::Restart => {
+                black_board
+                    .computer_player
+                    .get_ai_handler_mut()
+                    .cancel_pending_requests();
+                black_board.reset_game();
+                black_board.match_state = None;
+                Some(GameStateIndex::StartSelection)
+            }
+            MenuChoice::Cancel => None,
+        }
+    }
+
+    /// Gets the cell, in which the preview stone is shown. The area above the board belongs to the
+    /// buttons, so the preview is shown in the cell the stone would land in. Full columns and pops have
+    /// no preview. While the player is warned about a blunder, the preview stays on the move in question.
+    fn get_preview_cell(&self, black_board: &Blackboard) -> Option<(u32, u32)> {
+        if !self.waiting_for_player || self.menu_open {
+            return None;
+        }
+        let column = match self.blunder_warning {
+            Some(warning) => warning.slot,
+            None => black_board.resolve_slot(self.hovered_slot?),
+        };
+        if column >= BOARD_WIDTH {
+            return None;
         }
+        let row = black_board.game_board.get_move_destination(column)?;
+        Some((column, row))
     }
+
+    /// Starts the move in the slot, which has been checked to be possible. It gets animated and played,
+    /// when the animation is over. Unless the game ends or the player moves again, the calculation of
+    /// the computer move gets kicked off.
+    fn start_move(
+        &mut self,
+        slot_choice: u32,
+        black_board: &mut Blackboard,
+    ) -> Option<GameStateIndex> {
+        let mut clon = black_board.game_board.clone();
+        clon.play_slot(slot_choice)
+            .expect("The move has been checked on input.");
+        self.flash_time = 0.0;
+
+        self.waiting_for_player = false;
+        self.clear_hint(black_board);
+        // See if we transition to game over in the end.
+        self.transition_to_game_over = clon.is_game_over();
+        self.buffered_slot = slot_choice;
+        // In instant mode and for pops there is no animation and the move gets played in the next
+        // update.
+        self.skip_requested = false;
+        if slot_choice < BOARD_WIDTH
+            && let Some(speed_multiplier) =
+                black_board.settings.animation_speed.get_speed_multiplier()
+        {
+            self.animator.start_animating(
+                &black_board.game_board,
+                slot_choice,
+                false,
+                speed_multiplier,
+            );
+        }
+        // Kick off calculation, unless the player places another stone for the handicap.
+        if !self.transition_to_game_over && clon.side_to_move() == Side::Computer {
+            black_board.request_computer_move(clon);
+        }
+
+        None
+    }
+
+    /// Drops the hint, whether it is still being computed or already shown.
+    fn clear_hint(&mut self, black_board: &mut Blackboard) {
+        black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .cancel_hint();
+        self.hint_requested = false;
+        self.hint_slot = None;
+    }
+}
+
+/// Takes the latest move the player has chosen shortly before, while the player could not move. Older
+/// choices and the other events are dropped.
+fn take_chosen_move(black_board: &mut Blackboard) -> Option<u32> {
+    let now = black_board.logic_time;
+    black_board
+        .drain_events()
+        .into_iter()
+        .rev()
+        .find_map(|event| match event {
+            GameEvent::MoveChosen {
+                slot,
+                by: Side::Player,
+                chosen_at,
+            } if now - chosen_at <= CHOSEN_MOVE_LIFETIME => Some(slot),
+            _ => None,
+        })
+}
+
+/// Checks if the player has already made a move, that can be taken back. The opponent on the network
+/// has seen the move already, so it can not be taken back.
+fn can_undo(black_board: &Blackboard) -> bool {
+    black_board.remote_player.is_none()
+        && black_board
+            .game_board
+            .get_move_history()
+            .iter()
+            .any(|&(_, is_computer)| !is_computer)
 }
 
 impl GameState for StatePlayerInput {
@@ -38,72 +383,724 @@ impl GameState for StatePlayerInput {
         self.slot_picked = None;
         self.transition_to_game_over = false;
         self.waiting_for_player = true;
+        self.undo_requested = false;
+        self.hint_requested = false;
+        self.hint_slot = None;
+        self.menu_requested = false;
+        self.menu_open = false;
+        self.menu_choice = None;
+        self.skip_requested = false;
+        self.flash_column = None;
+        self.flash_time = 0.0;
+        self.blunder_warning = None;
+        self.confirmation_choice = None;
+        self.button_press.cancel();
     }
 
     /// We handle the stone animation and if not and the player has chosen a slot, we decide
     /// depending on whether it s game over or not to transition to the computer choice state
     /// or start the animation to follow up on game over.
     fn update(&mut self, delta_time: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        self.progress = black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .try_get_progress();
+        self.flash_time = (self.flash_time - delta_time).max(0.0);
         if self.waiting_for_player {
-            let slot_choice = self.slot_picked?;
+            if let Some(follow_index) = black_board.update_remote_player(delta_time) {
+                self.clear_hint(black_board);
+                return Some(follow_index);
+            }
+            if black_board.run_clock(delta_time, false) {
+                self.clear_hint(black_board);
+                return Some(GameStateIndex::GameOverState);
+            }
+            if let Some(warning) = self.blunder_warning {
+                let confirmed = self.confirmation_choice.take()?;
+                self.blunder_warning = None;
+                return if confirmed {
+                    self.start_move(warning.slot, black_board)
+                } else {
+                    None
+                };
+            }
+            if self.menu_open {
+                let choice = self.menu_choice.take()?;
+                return self.execute_menu_choice(choice, black_board);
+            }
+            if self.menu_requested {
+                self.menu_requested = false;
+                self.menu_open = true;
+                self.slot_picked = None;
+                return None;
+            }
 
-            // We have chosen a slot.
-            self.slot_picked = None;
+            // The player is to move, so the last move was made by the computer.
+            if self.undo_requested {
+                self.undo_requested = false;
+                if can_undo(black_board) {
+                    black_board.undo_last_full_turn();
+                    self.clear_hint(black_board);
+                }
+            }
 
-            let coded_move = black_board.game_board.get_possible_move(slot_choice);
-            // Illegal move.
-            if coded_move == 0 {
-                return None;
+            if self.hint_requested {
+                self.hint_requested = false;
+                if self.hint_slot.is_none()
+                    && !black_board
+                        .computer_player
+                        .get_ai_handler()
+                        .is_hint_pending()
+                    && let Err(error) = black_board
+                        .computer_player
+                        .get_ai_handler_mut()
+                        .send_hint_request(black_board.game_board.clone())
+                {
+                    log::warn!("The hint could not be requested: {error}");
+                }
+            }
+            // A hint, that got lost, can simply be asked for again.
+            match black_board
+                .computer_player
+                .get_ai_handler_mut()
+                .try_get_hint_result()
+            {
+                Ok(Some(slot)) => {
+                    self.hint_slot = Some(slot);
+                    self.hint_time = 0.0;
+                }
+                Ok(None) => {}
+                Err(error) => log::warn!("The hint got lost: {error}"),
             }
+            self.hint_time += delta_time;
 
-            self.waiting_for_player = false;
-            let mut clon = black_board.game_board.clone();
-            clon.apply_move(coded_move, false);
-            // See if we transition to game over in the end.
-            self.transition_to_game_over = clon.is_game_over();
-            self.buffered_move = coded_move;
-            self.animator
-                .start_animating(&black_board.game_board, slot_choice, false);
-            // Kick off calculation.
-            if !self.transition_to_game_over {
-                black_board.ai_system.send_analysis_request(clon);
+            // A click made shortly before, while the player could not move, counts as made now.
+            if let Some(slot) = self
+                .slot_picked
+                .take()
+                .or_else(|| take_chosen_move(black_board))
+            {
+                black_board.human_player.feed_slot(slot);
             }
+            let slot_picked = black_board.get_player_mut(Side::Player).poll_move()?;
+            let slot_choice = black_board.resolve_slot(slot_picked);
 
-            return None;
+            // Illegal move, the full column flashes.
+            if black_board
+                .game_board
+                .clone()
+                .play_slot(slot_choice)
+                .is_err()
+            {
+                black_board.play_sound(SoundEffect::IllegalMove);
+                self.flash_column = Some(slot_choice);
+                self.flash_time = COLUMN_FLASH_TIME;
+                return None;
+            }
+            // In the training mode a losing move has to be confirmed first.
+            if black_board.settings.training_mode
+                && let Some(refutation) = find_blunder(&black_board.game_board, slot_choice)
+            {
+                self.blunder_warning = Some(BlunderWarning {
+                    slot: slot_choice,
+                    refutation,
+                });
+                self.confirmation_choice = None;
+                return None;
+            }
+
+            return self.start_move(slot_choice, black_board);
         }
 
         // In this case the stone is falling.
-        // In this case we have some animation going.
-        self.animator.update(delta_time);
+        // In this case we have some animation going, an undo or the menu is not possible now.
+        self.undo_requested = false;
+        self.menu_requested = false;
+        if let Some(slot) = self.slot_picked.take() {
+            black_board.post(GameEvent::MoveChosen {
+                slot,
+                by: Side::Player,
+                chosen_at: black_board.logic_time,
+            });
+        }
         if self.animator.is_animating() {
-            return None;
+            if self.skip_requested {
+                self.animator.finish_now();
+            } else {
+                self.animator.update(delta_time);
+            }
+            if self.animator.is_animating() {
+                return None;
+            }
         }
 
         // Animation is over at that point.
-        black_board.game_board.apply_move(self.buffered_move, false);
+        black_board
+            .commit_move(self.buffered_slot, Side::Player)
+            .expect("The move has been checked on input.");
+        black_board.play_sound(SoundEffect::Drop);
 
         if self.transition_to_game_over {
+            black_board.post_game_end();
             Some(GameStateIndex::GameOverState)
+        } else if black_board.game_board.side_to_move() == Side::Player {
+            // With the double move handicap the player places the second stone of the first turn.
+            Some(black_board.begin_turn())
         } else {
             Some(GameStateIndex::ComputerExecutionState)
         }
     }
 
-    /// Picks the slot, that was chosen by the player.
-    fn mouse_click(&mut self, position: Vec2) {
+    /// Picks the slot, that was chosen by the player, already on the press to keep the game snappy.
+    /// Clicks above the board do not pick a slot but may press the buttons. While the menu is open,
+    /// only its buttons count. A click beside the buttons of the blunder warning takes the move back.
+    fn mouse_down(&mut self, position: Vec2) {
+        let button = self.get_button_at(position);
+        self.button_press.press(button);
+        if self.blunder_warning.is_some() {
+            if button.is_none() {
+                self.confirmation_choice = Some(false);
+            }
+            return;
+        }
+        if self.menu_open {
+            return;
+        }
+        if !self.waiting_for_player {
+            self.skip_requested = true;
+        }
+        if position.y >= BOARD_DRAWING_HEIGHT {
+            return;
+        }
         if self.slot_picked.is_some() {
             return;
         }
-        let slot = (position.x / WINDOW_DIMENSION * BOARD_WIDTH as f32) as u32;
-        self.slot_picked = Some(slot);
+        // Clicks outside of the window do not pick a slot.
+        if let Some(slot) = screen_to_slot(position) {
+            self.slot_picked = Some(slot);
+        }
     }
 
-    /// Draws the board and eventually the falling stone.
+    /// Activates the button, if the mouse got released on the pressed one.
+    fn mouse_up(&mut self, position: Vec2) {
+        match self.button_press.release(self.get_button_at(position)) {
+            Some(InputButton::Undo) => self.undo_requested = true,
+            Some(InputButton::Hint) => self.hint_requested = true,
+            Some(InputButton::Menu) => self.menu_requested = true,
+            Some(InputButton::Choice(choice)) => self.menu_choice = Some(choice),
+            Some(InputButton::Confirm(confirmed)) => self.confirmation_choice = Some(confirmed),
+            None => {}
+        }
+    }
+
+    /// Keeps track of the slot under the mouse for the preview.
+    fn mouse_move(&mut self, position: Vec2) {
+        self.hovered_slot = screen_to_slot(position);
+    }
+
+    /// Asks for a hint with the H key. Escape takes back a move the player got warned about.
+    fn key_pressed(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::H => self.hint_requested = true,
+            KeyCode::Escape if self.blunder_warning.is_some() => {
+                self.confirmation_choice = Some(false)
+            }
+            _ => {}
+        }
+    }
+
+    /// The state is idle, while it waits for the player and nothing moves on the screen. The pulsing hint,
+    /// the flashing column and the input, that has not been processed yet, keep it busy.
+    fn is_idle(&self, _: &Blackboard) -> bool {
+        let input_pending = self.slot_picked.is_some()
+            || self.undo_requested
+            || self.hint_requested
+            || self.menu_requested
+            || self.menu_choice.is_some()
+            || self.confirmation_choice.is_some();
+        self.waiting_for_player
+            && !input_pending
+            && self.hint_slot.is_none()
+            && self.flash_time <= 0.0
+    }
+
+    /// Draws the board, eventually the falling stone or the pulsing hint stone in the cell it would
+    /// land in and the buttons, that are greyed out when they can not be used. A hint to pop lets the
+    /// stone in the bottom row pulse.
     fn draw(&self, black_board: &Blackboard) {
         if self.animator.is_animating() {
             self.animator.draw();
         }
+        if let Some((column, row)) = self.get_preview_cell(black_board) {
+            draw_ghost_stone_at_coordinates(
+                get_drawing_coordinates(column, row),
+                !black_board.game_board.get_computer_first(),
+                PREVIEW_ALPHA,
+            );
+        }
+        let hint_alpha =
+            0.5 + 0.3 * (self.hint_time * HINT_PULSE_FREQUENCY * std::f32::consts::TAU).sin();
+        if let Some(column) = self.hint_slot
+            && column < BOARD_WIDTH
+            && let Some(row) = black_board.game_board.get_move_destination(column)
+        {
+            draw_ghost_stone_at_coordinates(
+                get_drawing_coordinates(column, row),
+                !black_board.game_board.get_computer_first(),
+                hint_alpha,
+            );
+        }
+
+        render_board(&black_board.game_board, black_board.board_texture.as_ref());
+        // The stone to pop is on the board already, so the hint is drawn on top of it.
+        if let Some(slot) = self.hint_slot
+            && slot >= BOARD_WIDTH
+        {
+            draw_ghost_stone_at_coordinates(
+                get_drawing_coordinates(slot - BOARD_WIDTH, 0),
+                !black_board.game_board.get_computer_first(),
+                hint_alpha,
+            );
+        }
+        if let Some(coded_move) = black_board.get_most_recent_move() {
+            render_last_move_marker(coded_move);
+        }
+        if let Some(column) = self.flash_column
+            && self.flash_time > 0.0
+        {
+            render_column_flash(
+                column,
+                COLUMN_FLASH_ALPHA * self.flash_time / COLUMN_FLASH_TIME,
+            );
+        }
+        if let Some(stats) = &self.progress {
+            render_evaluation_bar(get_first_player_share(
+                stats.score,
+                black_board.game_board.get_computer_first(),
+            ));
+        }
+
+        let undo_enabled = self.waiting_for_player && can_undo(black_board);
+        draw_button(UNDO_BUTTON, "Undo", TEXT_SIZE, undo_enabled);
+
+        let hint_enabled = self.waiting_for_player
+            && self.hint_slot.is_none()
+            && !black_board
+                .computer_player
+                .get_ai_handler()
+                .is_hint_pending();
+        draw_button(HINT_BUTTON, "?", TEXT_SIZE, hint_enabled);
+        draw_button(MENU_BUTTON, "=", TEXT_SIZE, self.waiting_for_player);
+        if let Some(clock) = &black_board.game_clock {
+            clock.draw();
+        }
+        black_board.draw_status_line();
+
+        if self.menu_open {
+            draw_rectangle(0.0, 0.0, WINDOW_DIMENSION, WINDOW_DIMENSION, OVERLAY_COLOR);
+            draw_button(RESIGN_BUTTON, "Resign", TEXT_SIZE, true);
+            draw_button(RESTART_BUTTON, "Restart", TEXT_SIZE, true);
+            draw_button(CANCEL_BUTTON, "Cancel", TEXT_SIZE, true);
+        }
+        if let Some(warning) = &self.blunder_warning {
+            draw_rectangle(0.0, 0.0, WINDOW_DIMENSION, WINDOW_DIMENSION, OVERLAY_COLOR);
+            print_text_centered_sized(&warning.get_text(), WARNING_TEXT_CENTER, SMALL_TEXT_SIZE);
+            print_text_centered("Play anyway?", QUESTION_CENTER);
+            draw_button(CONFIRM_BUTTON, "Yes", TEXT_SIZE, true);
+            draw_button(REJECT_BUTTON, "No", TEXT_SIZE, true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::{FRAME_TIME, HeadlessGame, SETTLE_TIME};
+    use crate::state_player_start_selection::{COMPUTER_STARTS_BUTTON, PLAYER_STARTS_BUTTON};
+    use crate::state_system::session_record::FrameInput;
+    use crate::state_system::settings::{AnimationSpeed, Handicap};
+    use connect_4_rust::board_logic::bit_board::RuleSet;
+    use connect_4_rust::board_logic::bit_board_coding::{BoardGeometry, StandardGeometry};
+
+    /// Gets a game against the weakest computer, where the indicated side starts.
+    fn get_started_game(start_button: Rect) -> HeadlessGame {
+        let mut game = HeadlessGame::new();
+        game.click(start_button.center());
+        game.click(BUTTON_CENTERS[0]);
+        game
+    }
+
+    /// Presses and releases the mouse at the position in two frames without waiting afterwards.
+    fn click_at_once(game: &mut HeadlessGame, position: Vec2) {
+        for (mouse_pressed, mouse_released) in [(true, false), (false, true)] {
+            game.run_frame(FrameInput {
+                frame_time: FRAME_TIME,
+                mouse_position: position,
+                mouse_pressed,
+                mouse_released,
+                ..FrameInput::default()
+            });
+        }
+    }
+
+    #[test]
+    fn test_undo_takes_back_a_full_turn() {
+        let mut game = get_started_game(PLAYER_STARTS_BUTTON);
+        game.play_columns(&[3, 2, 4, 5]);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(
+            game.black_board.game_board.to_move_string().unwrap(),
+            "4356"
+        );
+
+        game.click(UNDO_BUTTON.center());
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "43");
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        game.click(UNDO_BUTTON.center());
+        assert_eq!(game.black_board.game_board.stone_count(), 0);
+        // Without a move of the player there is nothing to take back.
+        game.click(UNDO_BUTTON.center());
+        assert_eq!(game.black_board.game_board.stone_count(), 0);
+        assert!(!can_undo(&game.black_board));
+    }
+
+    #[test]
+    fn test_first_computer_move_can_not_be_undone() {
+        let mut game = get_started_game(COMPUTER_STARTS_BUTTON);
+        game.answer_computer_move(3);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert!(!can_undo(&game.black_board));
+        game.click(UNDO_BUTTON.center());
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "4");
+
+        game.play_columns(&[2, 4]);
+        game.click(UNDO_BUTTON.center());
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "4");
+    }
+
+    #[test]
+    fn test_undo_is_ignored_during_the_animation() {
+        let mut game = get_started_game(PLAYER_STARTS_BUTTON);
+        game.play_columns(&[3, 2]);
+        click_at_once(&mut game, get_drawing_coordinates(4, 2));
+        click_at_once(&mut game, UNDO_BUTTON.center());
+        game.wait(SETTLE_TIME);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        game.answer_computer_move(5);
+        assert_eq!(
+            game.black_board.game_board.to_move_string().unwrap(),
+            "4356"
+        );
+    }
+
+    /// Gets a game, where the player started and each side has dropped a stone.
+    fn get_game_in_progress() -> HeadlessGame {
+        let mut game = get_started_game(PLAYER_STARTS_BUTTON);
+        game.play_columns(&[3, 2]);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        game
+    }
+
+    #[test]
+    fn test_resign_counts_as_a_computer_win() {
+        let mut game = get_game_in_progress();
+        game.click(MENU_BUTTON.center());
+        game.click(RESIGN_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        // The board has no four in a row, the result comes from the resignation.
+        assert_eq!(game.black_board.game_board.stone_count(), 2);
+        assert_eq!(
+            game.black_board.get_game_result(),
+            GameResult::SecondPlayerWon
+        );
+        assert_eq!(game.black_board.session_score.computer_wins, 1);
+        assert_eq!(game.black_board.session_score.player_wins, 0);
+    }
+
+    #[test]
+    fn test_restart_cancels_the_hint() {
+        let mut game = get_game_in_progress();
+        game.press_key(KeyCode::H);
+        assert!(
+            game.black_board
+                .computer_player
+                .get_ai_handler()
+                .is_hint_pending()
+        );
+        game.click(MENU_BUTTON.center());
+        game.click(RESTART_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        assert_eq!(game.black_board.game_board.stone_count(), 0);
+        let handler = game.black_board.computer_player.get_ai_handler_mut();
+        assert!(!handler.is_hint_pending());
+        assert!(!handler.is_move_pending());
+        assert_eq!(handler.try_get_hint_result(), Ok(None));
+        assert_eq!(game.black_board.session_score, Default::default());
+    }
+
+    #[test]
+    fn test_cancel_goes_back_to_the_game() {
+        let mut game = get_game_in_progress();
+        game.click(MENU_BUTTON.center());
+        game.click(CANCEL_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 2);
+        game.play_columns(&[4]);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+    }
+
+    #[test]
+    fn test_preview_shows_the_landing_cell() {
+        let mut game = get_game_in_progress();
+        let mut state = StatePlayerInput::new();
+        state.enter(&game.black_board);
+        assert_eq!(state.get_preview_cell(&game.black_board), None);
+        state.mouse_move(get_drawing_coordinates(3, 4));
+        assert_eq!(state.get_preview_cell(&game.black_board), Some((3, 1)));
+        // The bottom row of an empty column has nothing to pop.
+        state.mouse_move(get_drawing_coordinates(0, 0));
+        assert_eq!(state.get_preview_cell(&game.black_board), Some((0, 0)));
+        state.mouse_move(Vec2::new(-1.0, 100.0));
+        assert_eq!(state.get_preview_cell(&game.black_board), None);
+
+        // A full column has no preview.
+        for _ in 0..3 {
+            game.black_board.game_board.play(6).unwrap();
+            game.black_board.game_board.play(6).unwrap();
+        }
+        state.mouse_move(get_drawing_coordinates(6, 3));
+        assert_eq!(state.get_preview_cell(&game.black_board), None);
+    }
+
+    /// Gets a game with the rules, where the player started and each side has dropped a stone.
+    fn get_game_with_rules(rule_set: RuleSet) -> HeadlessGame {
+        let mut game = HeadlessGame::new();
+        game.black_board.settings.rule_set = rule_set;
+        game.black_board.apply_settings();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&[3, 2]);
+        game
+    }
+
+    #[test]
+    fn test_own_bottom_stone_is_popped_under_pop_out() {
+        // With the classic rules the click on the own bottom stone drops another one on top.
+        let mut game = get_game_with_rules(RuleSet::Classic);
+        game.click(get_drawing_coordinates(3, 0));
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "434");
+
+        let mut game = get_game_with_rules(RuleSet::PopOut);
+        game.click(get_drawing_coordinates(3, 0));
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        let board = &game.black_board.game_board;
+        assert_eq!(
+            board.to_ascii(),
+            ".......\n.......\n.......\n.......\n.......\n..O....\n"
+        );
+        assert_eq!(
+            board.get_move_history().last(),
+            Some(&(StandardGeometry::get_pop_move(3), false))
+        );
+
+        // The bottom stone of the computer can not be popped, the click drops a stone into its column.
+        let mut game = get_game_with_rules(RuleSet::PopOut);
+        game.click(get_drawing_coordinates(2, 0));
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "433");
+    }
+
+    #[test]
+    fn test_latest_chosen_move_is_taken_once() {
+        let mut game = get_game_in_progress();
+        let black_board = &mut game.black_board;
+        black_board.drain_events();
+        black_board.logic_time = 10.0;
+        for (slot, by, chosen_at) in [
+            (1, Side::Player, 9.8),
+            (5, Side::Player, 9.9),
+            (6, Side::Computer, 10.0),
+        ] {
+            black_board.post(GameEvent::MoveChosen {
+                slot,
+                by,
+                chosen_at,
+            });
+        }
+        black_board.post(GameEvent::GameEnded {
+            result: GameResult::Draw,
+        });
+        assert_eq!(take_chosen_move(black_board), Some(5));
+        // The older choice has been dropped together with the other events.
+        assert_eq!(take_chosen_move(black_board), None);
+        assert!(black_board.drain_events().is_empty());
+
+        // A choice, that has been made too long ago, is not played.
+        black_board.post(GameEvent::MoveChosen {
+            slot: 5,
+            by: Side::Player,
+            chosen_at: 10.0 - 2.0 * CHOSEN_MOVE_LIFETIME,
+        });
+        assert_eq!(take_chosen_move(black_board), None);
+    }
+
+    #[test]
+    fn test_clicks_off_the_board_are_no_moves() {
+        let mut game = get_started_game(PLAYER_STARTS_BUTTON);
+        let y = get_drawing_coordinates(0, 3).y;
+        // The area of the buttons, the right edge of the window and the margin around it.
+        for position in [
+            Vec2::new(350.0, BOARD_DRAWING_HEIGHT + 1.0),
+            Vec2::new(350.0, BOARD_DRAWING_HEIGHT),
+            Vec2::new(WINDOW_DIMENSION, y),
+            Vec2::new(WINDOW_DIMENSION + 20.0, y),
+            Vec2::new(-0.01, y),
+        ] {
+            game.click(position);
+            assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+            assert_eq!(game.black_board.game_board.stone_count(), 0, "{position}");
+        }
+
+        // The last pixels on both edges still belong to the outer columns.
+        game.click(Vec2::new(WINDOW_DIMENSION - 0.01, y));
+        game.answer_computer_move(3);
+        game.click(Vec2::new(0.0, y));
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "741");
+    }
+
+    #[test]
+    fn test_click_fast_forwards_the_falling_stone() {
+        let mut game = get_started_game(PLAYER_STARTS_BUTTON);
+        click_at_once(&mut game, get_drawing_coordinates(3, 2));
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.stone_count(), 0);
+
+        // A click beside the buttons lets the stone land in the next frame.
+        click_at_once(&mut game, Vec2::new(300.0, 650.0));
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        game.wait(SETTLE_TIME);
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "4");
+    }
+
+    #[test]
+    fn test_instant_stone_is_not_animated() {
+        let mut game = HeadlessGame::new();
+        game.black_board.settings.animation_speed = AnimationSpeed::Instant;
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        click_at_once(&mut game, get_drawing_coordinates(3, 2));
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "4");
+        game.feed_computer_move(4, FRAME_TIME);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "45");
+    }
+
+    /// Clicks into the column of the state in the given game and runs a frame.
+    fn click_column_of(state: &mut StatePlayerInput, game: &mut HeadlessGame, column: u32) {
+        let position = get_drawing_coordinates(column, 3);
+        state.mouse_down(position);
+        state.mouse_up(position);
+        state.update(FRAME_TIME, &mut game.black_board);
+    }
+
+    #[test]
+    fn test_full_column_flashes() {
+        let mut game = get_game_in_progress();
+        for _ in 0..6 {
+            game.black_board.game_board.play(6).unwrap();
+        }
+        let mut state = StatePlayerInput::new();
+        state.enter(&game.black_board);
+
+        click_column_of(&mut state, &mut game, 6);
+        assert_eq!(state.flash_column, Some(6));
+        assert_eq!(state.flash_time, COLUMN_FLASH_TIME);
+        assert!(state.waiting_for_player);
+        assert!(!state.is_idle(&game.black_board));
+        // The flash fades out and the state gets idle again.
+        for _ in 0..(COLUMN_FLASH_TIME / FRAME_TIME).ceil() as u32 {
+            state.update(FRAME_TIME, &mut game.black_board);
+        }
+        assert_eq!(state.flash_time, 0.0);
+        assert!(state.is_idle(&game.black_board));
+
+        // A legal click during the flash gets played.
+        click_column_of(&mut state, &mut game, 6);
+        assert!(state.flash_time > 0.0);
+        click_column_of(&mut state, &mut game, 5);
+        assert_eq!(state.flash_time, 0.0);
+        assert!(!state.waiting_for_player);
+    }
+
+    #[test]
+    fn test_legal_click_does_not_flash() {
+        let mut game = get_game_in_progress();
+        let mut state = StatePlayerInput::new();
+        state.enter(&game.black_board);
+        click_column_of(&mut state, &mut game, 6);
+        assert_eq!(state.flash_column, None);
+        assert_eq!(state.flash_time, 0.0);
+        assert!(!state.waiting_for_player);
+    }
+
+    #[test]
+    fn test_double_move_lets_the_player_place_two_stones() {
+        let mut game = HeadlessGame::new();
+        game.black_board.settings.handicap = Handicap::DoubleMove;
+        game.black_board.apply_settings();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+
+        // The first stone leads back to the input of the player without asking the computer.
+        game.click_column(3);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        assert_eq!(
+            game.black_board
+                .computer_player
+                .get_ai_handler()
+                .get_pending_move(),
+            None
+        );
+        game.click_column(2);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        assert_eq!(game.black_board.game_board.to_move_string().unwrap(), "43");
+        assert!(
+            game.black_board
+                .game_board
+                .get_move_history()
+                .iter()
+                .all(|&(_, is_computer)| !is_computer)
+        );
 
-        render_board(&black_board.game_board, &black_board.board_texture);
+        // After that the turns alternate as usual.
+        game.answer_computer_move(4);
+        game.click_column(5);
+        assert_eq!(
+            game.get_current_index(),
+            GameStateIndex::ComputerExecutionState
+        );
+        assert_eq!(
+            game.black_board.game_board.to_move_string().unwrap(),
+            "4356"
+        );
     }
 }
@@ -1,142 +1,746 @@
 //! Contains the state to administrate the start screen, where the player selects, who will start
-//! the game. When the computer starts the first calculation is kicked off.
+//! the game. Afterwards the difficulty gets selected. The screen also shows the score of the session,
+//! which can be reset here. The gear button leads to the settings and the question mark to the help.
+//! An empty board is shown below the buttons, which sit in the band above it. The coin flip between the
+//! starter buttons lets chance decide, who starts, and the button next to them switches between a single
+//! game and a match over several games. The starter chosen the last time is remembered for the session
+//! and framed, Enter starts the next game with it again. The rating and the record of the player against
+//! the difficulty chosen the last time are shown on the board, above them a button opens the board editor
+//! to set up a position to play from, and a button next to it starts Connect 5 on the larger board. A
+//! position pasted with Control and V gets opened in the editor.
+//! In a network game there are no matches and no difficulty, the host picks the starter and the game
+//! begins right away, while the guest waits for the host to start it.
 
 use crate::game_state::{Blackboard, GameState, GameStateIndex};
-use crate::render_system::graphics::{SymbolColor, get_color, print_text};
+use crate::render_system::audio::SoundEffect;
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, SymbolColor, draw_button, draw_gear, get_color, print_text_centered_sized,
+    print_text_sized, render_board,
+};
+use crate::render_system::theme::get_active_theme;
+use crate::render_system::toast::TOAST_DURATION;
+use crate::state_system::button_press::ButtonPress;
+use crate::state_system::game_state::MatchState;
+use connect_4_rust::board_logic::bit_board::{BitBoard, Side};
 use macroquad::prelude::*;
 
+/// The buttons of the start screen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum StartButton {
+    PlayerStarts,
+    ComputerStarts,
+    CoinFlip,
+    ResetScore,
+    Settings,
+    Help,
+    MatchLength,
+    BoardEditor,
+    ConnectFive,
+}
+
+/// The lengths of a match, that can be chosen, as the amount of games it is played as best of. A single
+/// game is no match.
+const MATCH_LENGTHS: [u32; 4] = [1, 3, 5, 7];
+
 pub struct StatePlayerStartSelection {
-    position_selected: u8,
+    /// The side, that starts, it is the remembered one, until a starter button gets clicked.
+    selected_starter: Side,
+    /// The side chosen the last time, Enter starts with it again.
+    remembered_starter: Option<Side>,
     time_passed_after_selection: f32,
+    /// The time the selection is shown, before the game goes on.
+    selection_duration: f32,
     selection_happened: bool,
+    /// The starter got drawn by the coin flip, the highlight alternates between the starter buttons.
+    coin_flipped: bool,
+    coin_flip_requested: bool,
+    reset_score_requested: bool,
+    settings_requested: bool,
+    help_requested: bool,
+    board_editor_requested: bool,
+    connect_five_requested: bool,
+    /// The text pasted from the clipboard, it gets imported in the next update.
+    pasted_text: Option<String>,
+    /// The index of the chosen entry in [`MATCH_LENGTHS`].
+    match_length_index: usize,
+    /// One of the buttons got clicked, the click sound has to be played.
+    click_pending: bool,
+    /// The button the mouse got pressed on.
+    button_press: ButtonPress<StartButton>,
+    /// The game is played against an opponent on the network, so there are no matches.
+    network_game: bool,
 }
 
 impl StatePlayerStartSelection {
     pub fn new() -> StatePlayerStartSelection {
         StatePlayerStartSelection {
-            position_selected: 0,
+            selected_starter: Side::Player,
+            remembered_starter: None,
             time_passed_after_selection: 0.0,
+            selection_duration: HIGHLIGHT_TIME,
             selection_happened: false,
+            coin_flipped: false,
+            coin_flip_requested: false,
+            reset_score_requested: false,
+            settings_requested: false,
+            help_requested: false,
+            board_editor_requested: false,
+            connect_five_requested: false,
+            pasted_text: None,
+            match_length_index: 0,
+            click_pending: false,
+            button_press: ButtonPress::new(),
+            network_game: false,
+        }
+    }
+
+    /// Selects the starter, the selection is shown for the indicated time.
+    fn select(&mut self, starter: Side, duration: f32) {
+        self.selection_happened = true;
+        self.selected_starter = starter;
+        self.selection_duration = duration;
+        self.time_passed_after_selection = 0.0;
+    }
+
+    /// Gets the side, whose button is highlighted, none if there is no selection. While the coin is
+    /// flipped, the highlight alternates between the sides and stays on the drawn one at the end.
+    fn get_highlighted_starter(&self) -> Option<Side> {
+        if !self.selection_happened {
+            return None;
+        }
+        let flip_time = self.selection_duration - HIGHLIGHT_TIME;
+        if self.coin_flipped && self.time_passed_after_selection < flip_time {
+            let flips = (self.time_passed_after_selection / COIN_FLIP_INTERVAL) as u32;
+            return Some(if flips.is_multiple_of(2) {
+                Side::Player
+            } else {
+                Side::Computer
+            });
         }
+        Some(self.selected_starter)
     }
 }
 
-/// The position where the left element should be drawn-
-const LEFT_CENTER: Vec2 = Vec2 { x: 175.0, y: 350.0 };
-/// The position where the right element should be drawn.
-const RIGHT_CENTER: Vec2 = Vec2 { x: 525.0, y: 350.0 };
-/// The radius of the button.
-const RADIUS: f32 = 100.0;
+/// The button, with which the player starts, it is the first one in the upper row of the band above the
+/// board.
+pub const PLAYER_STARTS_BUTTON: Rect = Rect {
+    x: 10.0,
+    y: 652.0,
+    w: 150.0,
+    h: 40.0,
+};
+/// The button, that lets the coin decide, who starts, it is centered between the starter buttons.
+const COIN_FLIP_BUTTON: Rect = Rect {
+    x: 170.0,
+    y: 652.0,
+    w: 140.0,
+    h: 40.0,
+};
+/// The button, with which the computer starts, it follows in the upper row.
+pub const COMPUTER_STARTS_BUTTON: Rect = Rect {
+    x: 320.0,
+    y: 652.0,
+    w: 176.0,
+    h: 40.0,
+};
+/// The radius of the stone, that shows the color on the starter buttons.
+const STONE_RADIUS: f32 = 14.0;
+/// The distance of the center of the stone from the left edge of the starter buttons.
+const STONE_OFFSET: f32 = 24.0;
+/// The distance of the labels from the left edge of the starter buttons.
+const LABEL_OFFSET: f32 = 46.0;
+/// The thickness of the frame around the starter button, that got chosen the last time.
+const FRAME_THICKNESS: f32 = 3.0;
+/// The distance of the texts above the lower edge of their buttons.
+const TEXT_OFFSET: f32 = 10.0;
 /// The highlight time for the button.
 const HIGHLIGHT_TIME: f32 = 0.25;
+/// The time the coin flip is shown, it ends with the highlight time on the drawn starter.
+const COIN_FLIP_TIME: f32 = 0.8;
+/// The time, after which the highlight of the coin flip changes to the other starter button.
+const COIN_FLIP_INTERVAL: f32 = 0.1;
+/// The position of the session score, it is in the lower row of the band.
+const SCORE_POSITION: Vec2 = Vec2 { x: 10.0, y: 618.0 };
+/// The click target to reset the session score, right of the session score.
+const RESET_SCORE_BUTTON: Rect = Rect {
+    x: 390.0,
+    y: 608.0,
+    w: 165.0,
+    h: 40.0,
+};
+/// The center of the gear button, that opens the settings, at the end of the lower row.
+pub const SETTINGS_BUTTON_CENTER: Vec2 = Vec2 { x: 660.0, y: 628.0 };
+/// The radius of the gear button.
+const SETTINGS_BUTTON_RADIUS: f32 = 18.0;
+/// The center of the button, that opens the help, it has the size of the gear button.
+pub const HELP_BUTTON_CENTER: Vec2 = Vec2 { x: 610.0, y: 628.0 };
+/// The button to switch between a single game and the match lengths, right of the starter buttons.
+pub const MATCH_LENGTH_BUTTON: Rect = Rect {
+    x: 506.0,
+    y: 652.0,
+    w: 184.0,
+    h: 40.0,
+};
+/// The panel with the status of a network game, it is shown on top of the board, as the text does not
+/// fit into the band.
+const NETWORK_STATUS_PANEL: Rect = Rect {
+    x: 100.0,
+    y: 275.0,
+    w: 500.0,
+    h: 50.0,
+};
+
+/// The panel with the statistics of the player, it is shown on the lower part of the board, as the band
+/// is full.
+const STATS_PANEL: Rect = Rect {
+    x: 100.0,
+    y: 20.0,
+    w: 500.0,
+    h: 50.0,
+};
+
+/// The button to open the board editor, it is shown on the board above the statistics of the player.
+pub const BOARD_EDITOR_BUTTON: Rect = Rect {
+    x: 140.0,
+    y: 80.0,
+    w: 200.0,
+    h: 40.0,
+};
+
+/// The button to play Connect 5, it is shown on the board next to the button of the board editor.
+pub const CONNECT_FIVE_BUTTON: Rect = Rect {
+    x: 360.0,
+    y: 80.0,
+    w: 200.0,
+    h: 40.0,
+};
+
+/// Gets the text of the match length button.
+fn get_match_length_label(best_of: u32) -> String {
+    if best_of == 1 {
+        "Single game".to_string()
+    } else {
+        format!("Best of {best_of}")
+    }
+}
+
+/// Gets the text, that tells why no game can be started in a network game, none if the player can start
+/// it.
+fn get_network_status(black_board: &Blackboard) -> Option<&'static str> {
+    let remote = black_board.remote_player.as_ref()?;
+    if remote.has_failed() {
+        Some("The connection is lost")
+    } else if !remote.is_connected() {
+        Some("Waiting for the opponent")
+    } else if black_board.is_network_guest() {
+        Some("Waiting for the host to start")
+    } else {
+        None
+    }
+}
+
+/// Gets the button at the indicated position, none if there is no button.
+fn get_button_at(position: Vec2) -> Option<StartButton> {
+    if PLAYER_STARTS_BUTTON.contains(position) {
+        Some(StartButton::PlayerStarts)
+    } else if COMPUTER_STARTS_BUTTON.contains(position) {
+        Some(StartButton::ComputerStarts)
+    } else if COIN_FLIP_BUTTON.contains(position) {
+        Some(StartButton::CoinFlip)
+    } else if RESET_SCORE_BUTTON.contains(position) {
+        Some(StartButton::ResetScore)
+    } else if SETTINGS_BUTTON_CENTER.distance(position) < SETTINGS_BUTTON_RADIUS {
+        Some(StartButton::Settings)
+    } else if HELP_BUTTON_CENTER.distance(position) < SETTINGS_BUTTON_RADIUS {
+        Some(StartButton::Help)
+    } else if MATCH_LENGTH_BUTTON.contains(position) {
+        Some(StartButton::MatchLength)
+    } else if BOARD_EDITOR_BUTTON.contains(position) {
+        Some(StartButton::BoardEditor)
+    } else if CONNECT_FIVE_BUTTON.contains(position) {
+        Some(StartButton::ConnectFive)
+    } else {
+        None
+    }
+}
+
+/// Draws a button to pick the starter with a stone in the color of the side and the label next to it. The
+/// stone is drawn in the indicated color, the button gets a frame, if it has been chosen the last time.
+fn draw_starter_button(button: Rect, label: &str, stone_color: SymbolColor, is_remembered: bool) {
+    draw_button(button, "", SMALL_TEXT_SIZE, true);
+    draw_circle(
+        button.x + STONE_OFFSET,
+        button.center().y,
+        STONE_RADIUS,
+        *get_color(stone_color),
+    );
+    print_text_sized(
+        label,
+        Vec2::new(button.x + LABEL_OFFSET, button.y + TEXT_OFFSET),
+        SMALL_TEXT_SIZE,
+    );
+    if is_remembered {
+        draw_rectangle_lines(
+            button.x,
+            button.y,
+            button.w,
+            button.h,
+            FRAME_THICKNESS,
+            get_active_theme().text,
+        );
+    }
+}
 
 impl GameState for StatePlayerStartSelection {
-    fn enter(&mut self, _: &Blackboard) {
+    fn enter(&mut self, black_board: &Blackboard) {
+        self.network_game = black_board.remote_player.is_some();
+        self.remembered_starter = black_board.last_starter;
+        self.selected_starter = self.remembered_starter.unwrap_or(Side::Player);
         self.selection_happened = false;
+        self.coin_flipped = false;
+        self.coin_flip_requested = false;
         self.time_passed_after_selection = 0.0;
+        self.reset_score_requested = false;
+        self.settings_requested = false;
+        self.help_requested = false;
+        self.board_editor_requested = false;
+        self.connect_five_requested = false;
+        self.pasted_text = None;
+        self.click_pending = false;
+        self.button_press.cancel();
     }
 
     /// The update waits for the input signal, updates the information on the game board and
-    /// waits a short time for the highlighted button.
+    /// waits a short time for the highlighted button, longer when the coin gets flipped. With a match
+    /// length chosen the match begins.
     fn update(&mut self, delta_time: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
-        if self.selection_happened {
-            self.time_passed_after_selection += delta_time;
+        if self.click_pending {
+            self.click_pending = false;
+            black_board.play_sound(SoundEffect::Click);
+        }
+        if self.reset_score_requested {
+            self.reset_score_requested = false;
+            black_board.session_score.reset();
+        }
+        if self.settings_requested {
+            return Some(GameStateIndex::Settings);
+        }
+        if self.help_requested {
+            return Some(GameStateIndex::Help);
+        }
+        if self.board_editor_requested {
+            return Some(GameStateIndex::BoardEditor);
+        }
+        if self.connect_five_requested {
+            return Some(GameStateIndex::ConnectFive);
+        }
+        if let Some(text) = self.pasted_text.take()
+            && black_board.import_position(&text)
+        {
+            return Some(GameStateIndex::BoardEditor);
         }
 
-        if self.time_passed_after_selection >= HIGHLIGHT_TIME {
-            black_board
-                .game_board
-                .set_computer_first(self.position_selected == 1);
-            if self.position_selected == 1 {
+        if let Some(remote) = black_board.remote_player.as_mut() {
+            remote.update(delta_time);
+            if let Some(error) = remote.take_error() {
                 black_board
-                    .ai_system
-                    .send_analysis_request(black_board.game_board.clone());
-                return Some(GameStateIndex::ComputerExecutionState);
+                    .toasts
+                    .push(format!("Network game over, {error}"), TOAST_DURATION);
+            }
+            if let Some(own_start) = remote.take_started_game() {
+                return Some(black_board.start_game(!own_start));
+            }
+        }
+        // The coin decides with the random generator of the blackboard, so that a replay draws the same.
+        if self.coin_flip_requested {
+            self.coin_flip_requested = false;
+            let starter = if black_board.random.next_below(2) == 0 {
+                Side::Player
             } else {
-                return Some(GameStateIndex::PlayerInputState);
+                Side::Computer
+            };
+            self.select(starter, COIN_FLIP_TIME);
+            self.coin_flipped = true;
+        }
+        // The starter of a network game can only be picked by the host, once the guest is there.
+        if self.selection_happened && get_network_status(black_board).is_some() {
+            self.selection_happened = false;
+        }
+
+        if self.selection_happened {
+            self.time_passed_after_selection += delta_time;
+        }
+
+        if self.time_passed_after_selection >= self.selection_duration {
+            black_board.last_starter = Some(self.selected_starter);
+            let computer_first = self.selected_starter.is_computer();
+            if black_board.remote_player.is_some() {
+                black_board.match_state = None;
+                return Some(black_board.start_game(computer_first));
             }
+            black_board.game_board.set_computer_first(computer_first);
+            let best_of = MATCH_LENGTHS[self.match_length_index];
+            black_board.match_state =
+                (best_of > 1).then(|| MatchState::new(best_of, self.selected_starter));
+            return Some(GameStateIndex::DifficultySelection);
         }
 
         None
     }
 
-    /// Mouse click detects the potential onto one of the buttons and eventually sets
-    /// the information in the state.
-    fn mouse_click(&mut self, position: Vec2) {
-        if self.selection_happened {
+    /// Remembers the button the mouse got pressed on.
+    fn mouse_down(&mut self, position: Vec2) {
+        self.button_press.press(get_button_at(position));
+    }
+
+    /// Mouse release on the pressed button eventually sets the information in the state.
+    fn mouse_up(&mut self, position: Vec2) {
+        if self.selection_happened || self.coin_flip_requested {
+            return;
+        }
+
+        let Some(button) = self.button_press.release(get_button_at(position)) else {
             return;
+        };
+        match button {
+            StartButton::PlayerStarts => self.select(Side::Player, HIGHLIGHT_TIME),
+            StartButton::ComputerStarts => self.select(Side::Computer, HIGHLIGHT_TIME),
+            StartButton::CoinFlip => self.coin_flip_requested = true,
+            StartButton::ResetScore => self.reset_score_requested = true,
+            StartButton::Settings => self.settings_requested = true,
+            StartButton::Help => self.help_requested = true,
+            StartButton::MatchLength | StartButton::BoardEditor | StartButton::ConnectFive
+                if self.network_game =>
+            {
+                return;
+            }
+            StartButton::MatchLength => {
+                self.match_length_index = (self.match_length_index + 1) % MATCH_LENGTHS.len()
+            }
+            StartButton::BoardEditor => self.board_editor_requested = true,
+            StartButton::ConnectFive => self.connect_five_requested = true,
         }
+        self.click_pending = true;
+    }
 
-        if LEFT_CENTER.distance(position) < RADIUS {
-            self.selection_happened = true;
-            self.position_selected = 0;
+    /// Remembers the text to import it in the update, there is no board editor in a network game.
+    fn text_pasted(&mut self, text: &str) {
+        if !self.network_game && !self.selection_happened {
+            self.pasted_text = Some(text.to_string());
         }
+    }
 
-        if RIGHT_CENTER.distance(position) < RADIUS {
-            self.selection_happened = true;
-            self.position_selected = 1;
+    /// Enter starts with the side chosen the last time, if there has been a choice in this session.
+    fn key_pressed(&mut self, key: KeyCode) {
+        if key != KeyCode::Enter || self.selection_happened || self.coin_flip_requested {
+            return;
+        }
+        if let Some(starter) = self.remembered_starter {
+            self.select(starter, HIGHLIGHT_TIME);
+            self.click_pending = true;
         }
     }
 
-    /// Simply renders the two buttons, eventually highlighted when just selected.
-    fn draw(&self, _: &Blackboard) {
-        print_text("Welcome to Connect Four", Vec2::new(100.0, 575.0));
-        if self.selection_happened && (self.position_selected == 0) {
-            draw_poly(
-                LEFT_CENTER.x,
-                LEFT_CENTER.y,
-                200,
-                RADIUS,
-                0.0,
-                *get_color(SymbolColor::LightYellow),
+    /// Renders the empty board with the buttons in the band above it. The upper row holds the two starter
+    /// buttons with the coin flip between them and the match length button. The starter chosen the last
+    /// time is framed and the selected one is highlighted, when just clicked, while the coin is flipped
+    /// the highlight alternates. The lower row holds the session score with the button to reset it, the
+    /// help and the gear button for the settings. The statistics of the player and the buttons of the board
+    /// editor and of Connect 5 are shown on the board. In a network game the status of the connection is
+    /// shown on the board instead of the match length button, the statistics and these buttons.
+    fn draw(&self, black_board: &Blackboard) {
+        render_board(&BitBoard::new(), black_board.board_texture.as_ref());
+
+        let is_highlighted = |starter| self.get_highlighted_starter() == Some(starter);
+        draw_starter_button(
+            PLAYER_STARTS_BUTTON,
+            "I start",
+            if is_highlighted(Side::Player) {
+                SymbolColor::LightYellow
+            } else {
+                SymbolColor::Yellow
+            },
+            self.remembered_starter == Some(Side::Player),
+        );
+        draw_starter_button(
+            COMPUTER_STARTS_BUTTON,
+            "You start",
+            if is_highlighted(Side::Computer) {
+                SymbolColor::LightBlue
+            } else {
+                SymbolColor::Blue
+            },
+            self.remembered_starter == Some(Side::Computer),
+        );
+        draw_button(COIN_FLIP_BUTTON, "Coin flip", SMALL_TEXT_SIZE, true);
+
+        if black_board.remote_player.is_none() {
+            draw_button(
+                MATCH_LENGTH_BUTTON,
+                &get_match_length_label(MATCH_LENGTHS[self.match_length_index]),
+                SMALL_TEXT_SIZE,
+                true,
             );
-        } else {
-            draw_poly(
-                LEFT_CENTER.x,
-                LEFT_CENTER.y,
-                200,
-                RADIUS,
-                0.0,
-                *get_color(SymbolColor::Yellow),
+            draw_button(
+                STATS_PANEL,
+                &black_board
+                    .player_stats
+                    .get_summary(black_board.settings.difficulty),
+                SMALL_TEXT_SIZE,
+                false,
             );
+            draw_button(BOARD_EDITOR_BUTTON, "Edit board", SMALL_TEXT_SIZE, true);
+            draw_button(CONNECT_FIVE_BUTTON, "Connect 5", SMALL_TEXT_SIZE, true);
+        } else if let Some(status) = get_network_status(black_board) {
+            draw_button(NETWORK_STATUS_PANEL, status, SMALL_TEXT_SIZE, false);
         }
+        print_text_sized(
+            &black_board.session_score.to_string(),
+            SCORE_POSITION,
+            SMALL_TEXT_SIZE,
+        );
+        print_text_sized(
+            "Reset score",
+            Vec2::new(
+                RESET_SCORE_BUTTON.x + TEXT_OFFSET,
+                RESET_SCORE_BUTTON.y + TEXT_OFFSET,
+            ),
+            SMALL_TEXT_SIZE,
+        );
+        draw_gear(SETTINGS_BUTTON_CENTER, SETTINGS_BUTTON_RADIUS);
+        draw_circle(
+            HELP_BUTTON_CENTER.x,
+            HELP_BUTTON_CENTER.y,
+            SETTINGS_BUTTON_RADIUS,
+            *get_color(SymbolColor::Grey),
+        );
+        print_text_centered_sized("?", HELP_BUTTON_CENTER, SMALL_TEXT_SIZE);
+    }
 
-        print_text(
-            "I start",
-            LEFT_CENTER
-                - Vec2 {
-                    x: RADIUS,
-                    y: 1.6 * RADIUS,
-                },
+    /// The screen is static, until a button gets clicked.
+    fn is_idle(&self, _: &Blackboard) -> bool {
+        !(self.selection_happened
+            || self.coin_flip_requested
+            || self.click_pending
+            || self.reset_score_requested
+            || self.settings_requested
+            || self.help_requested
+            || self.board_editor_requested
+            || self.connect_five_requested
+            || self.pasted_text.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::SessionScore;
+    use crate::render_system::graphics::{BOARD_DRAWING_HEIGHT, get_drawing_coordinates};
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::HeadlessGame;
+    use crate::state_game_over::{MENU_BUTTON, REPLAY_BUTTON};
+    use connect_4_rust::board_logic::bit_board_coding::{BOARD_HEIGHT, BOARD_WIDTH};
+    use connect_4_rust::selfplay::SplitMix64;
+
+    /// A game, where the starter drops four stones into the first column.
+    const STARTER_WINS: [u32; 7] = [0, 1, 0, 1, 0, 1, 0];
+
+    /// Plays the game with the indicated starter and the columns from the start screen and returns to it.
+    fn play_game(game: &mut HeadlessGame, start_button: Rect, columns: &[u32]) {
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        game.click(start_button.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(columns);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        game.click(MENU_BUTTON.center());
+    }
+
+    #[test]
+    fn test_session_is_counted_over_the_games() {
+        let mut game = HeadlessGame::new();
+        play_game(&mut game, PLAYER_STARTS_BUTTON, &STARTER_WINS);
+        play_game(&mut game, COMPUTER_STARTS_BUTTON, &STARTER_WINS);
+        let draw: Vec<u32> = "547125662261271266215743771576315353334444"
+            .chars()
+            .map(|column| column.to_digit(10).unwrap() - 1)
+            .collect();
+        play_game(&mut game, COMPUTER_STARTS_BUTTON, &draw);
+        play_game(&mut game, PLAYER_STARTS_BUTTON, &STARTER_WINS);
+        assert_eq!(
+            game.black_board.session_score,
+            SessionScore {
+                player_wins: 2,
+                computer_wins: 1,
+                draws: 1
+            }
+        );
+
+        game.click(RESET_SCORE_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        assert_eq!(game.black_board.session_score, SessionScore::default());
+    }
+
+    #[test]
+    fn test_game_is_counted_once() {
+        let mut game = HeadlessGame::new();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&STARTER_WINS);
+        // Waiting on the game over screen and looking at the replay does not count the game again.
+        game.wait(5.0);
+        game.click(REPLAY_BUTTON.center());
+        game.press_key(KeyCode::Escape);
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        assert_eq!(
+            game.black_board.session_score,
+            SessionScore {
+                player_wins: 1,
+                computer_wins: 0,
+                draws: 0
+            }
         );
+    }
+
+    #[test]
+    fn test_starter_buttons_sit_above_the_board() {
+        for (button, expected) in [
+            (PLAYER_STARTS_BUTTON, StartButton::PlayerStarts),
+            (COIN_FLIP_BUTTON, StartButton::CoinFlip),
+            (COMPUTER_STARTS_BUTTON, StartButton::ComputerStarts),
+        ] {
+            assert!(button.y >= BOARD_DRAWING_HEIGHT, "{button:?}");
+            assert_eq!(get_button_at(button.center()), Some(expected));
+        }
+        // On the board there are only the buttons of the board editor and of Connect 5.
+        for column in 0..BOARD_WIDTH {
+            for row in 0..BOARD_HEIGHT {
+                let button = get_button_at(get_drawing_coordinates(column, row));
+                assert!(
+                    matches!(
+                        button,
+                        None | Some(StartButton::BoardEditor | StartButton::ConnectFive)
+                    ),
+                    "{column} {row}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_starter_is_remembered_over_the_games() {
+        let mut game = HeadlessGame::new();
+        // Without a game played before there is nothing to start again with Enter.
+        game.press_key(KeyCode::Enter);
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        assert_eq!(game.black_board.last_starter, None);
 
-        if self.selection_happened && (self.position_selected == 1) {
-            draw_poly(
-                RIGHT_CENTER.x,
-                RIGHT_CENTER.y,
-                200,
-                RADIUS,
-                0.0,
-                *get_color(SymbolColor::LightBlue),
+        for (start_button, starter) in [
+            (COMPUTER_STARTS_BUTTON, Side::Computer),
+            (PLAYER_STARTS_BUTTON, Side::Player),
+        ] {
+            play_game(&mut game, start_button, &STARTER_WINS);
+            assert_eq!(game.black_board.last_starter, Some(starter));
+
+            // Enter picks the starter of the last game, the rematch goes on as with the click.
+            game.press_key(KeyCode::Enter);
+            assert_eq!(
+                game.get_current_index(),
+                GameStateIndex::DifficultySelection
             );
-        } else {
-            draw_poly(
-                RIGHT_CENTER.x,
-                RIGHT_CENTER.y,
-                200,
-                RADIUS,
-                0.0,
-                *get_color(SymbolColor::Blue),
+            assert_eq!(
+                game.black_board.game_board.get_computer_first(),
+                starter.is_computer()
             );
+            game.click(BUTTON_CENTERS[0]);
+            let expected = if starter.is_computer() {
+                GameStateIndex::ComputerExecutionState
+            } else {
+                GameStateIndex::PlayerInputState
+            };
+            assert_eq!(game.get_current_index(), expected);
+            game.play_columns(&STARTER_WINS);
+            game.click(MENU_BUTTON.center());
+            assert_eq!(game.black_board.last_starter, Some(starter));
         }
+    }
 
-        print_text(
-            "You start",
-            RIGHT_CENTER
-                - Vec2 {
-                    x: RADIUS,
-                    y: 1.6 * RADIUS,
-                },
+    #[test]
+    fn test_coin_flip_picks_either_starter() {
+        let mut starters = Vec::new();
+        for seed in 0..8 {
+            let expected = if SplitMix64::new(seed).next_below(2) == 0 {
+                Side::Player
+            } else {
+                Side::Computer
+            };
+            starters.push(expected);
+
+            let mut game = HeadlessGame::new();
+            game.black_board.random = SplitMix64::new(seed);
+            game.click(COIN_FLIP_BUTTON.center());
+            assert_eq!(
+                game.get_current_index(),
+                GameStateIndex::DifficultySelection
+            );
+            assert_eq!(
+                game.black_board.game_board.get_computer_first(),
+                expected.is_computer()
+            );
+            assert_eq!(game.black_board.last_starter, Some(expected));
+
+            // The computer gets asked for its move right away, when it starts.
+            game.click(BUTTON_CENTERS[0]);
+            let pending_move = game
+                .black_board
+                .computer_player
+                .get_ai_handler()
+                .get_pending_move();
+            if expected.is_computer() {
+                assert_eq!(
+                    game.get_current_index(),
+                    GameStateIndex::ComputerExecutionState
+                );
+                assert!(pending_move.is_some());
+            } else {
+                assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+                assert_eq!(pending_move, None);
+            }
+        }
+        assert!(starters.contains(&Side::Player) && starters.contains(&Side::Computer));
+    }
+
+    #[test]
+    fn test_coin_flip_alternates_the_highlight() {
+        let mut black_board = Blackboard::new();
+        black_board.persist_settings = false;
+        black_board.random = SplitMix64::new(1);
+        let mut state = StatePlayerStartSelection::new();
+        state.enter(&black_board);
+        state.mouse_down(COIN_FLIP_BUTTON.center());
+        state.mouse_up(COIN_FLIP_BUTTON.center());
+        assert_eq!(state.update(0.0, &mut black_board), None);
+        let drawn = state.selected_starter;
+
+        // The highlight changes with every interval, for the highlight time at the end it stays on the
+        // drawn starter.
+        let mut highlights = Vec::new();
+        let mut time = COIN_FLIP_INTERVAL / 2.0;
+        assert_eq!(state.update(time, &mut black_board), None);
+        while time < COIN_FLIP_TIME - HIGHLIGHT_TIME {
+            highlights.push(state.get_highlighted_starter().unwrap());
+            assert_eq!(state.update(COIN_FLIP_INTERVAL, &mut black_board), None);
+            time += COIN_FLIP_INTERVAL;
+        }
+        assert!(highlights.len() >= 4);
+        assert!(highlights.windows(2).all(|pair| pair[0] != pair[1]));
+        assert_eq!(state.get_highlighted_starter(), Some(drawn));
+        // Clicks on the other buttons do not change the drawn starter any more.
+        state.mouse_down(PLAYER_STARTS_BUTTON.center());
+        state.mouse_up(PLAYER_STARTS_BUTTON.center());
+        assert_eq!(
+            state.update(HIGHLIGHT_TIME, &mut black_board),
+            Some(GameStateIndex::DifficultySelection)
         );
+        assert_eq!(black_board.last_starter, Some(drawn));
     }
 }
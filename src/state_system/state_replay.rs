@@ -0,0 +1,441 @@
+//! The replay state lets the player step through the finished game move by move. The board is rebuilt
+//! from the move history of the game board on every step, starting with the position the game started
+//! from, which is the empty board unless it has been set up in the board editor. Stepping works with the arrow buttons above
+//! the board or the arrow keys, Home and End jump to the start and the end of the game.
+//! The replay also analyzes the game. All positions get evaluated by the ai in the background and a graph
+//! of the scores over the game is drawn above the board, while the scores arrive. Blunders are marked red
+//! and a vertical line shows the position on the board.
+
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, STANDARD_LAYOUT, SymbolColor, draw_scaled_polyline, get_color,
+    get_graph_point, print_text, print_text_sized, render_board, render_winning_stones,
+    render_winning_stroke,
+};
+use crate::render_system::theme::get_active_theme;
+use crate::state_system::analysis::{EvalEntry, get_final_score, get_first_player_score};
+use crate::state_system::button_press::ButtonPress;
+use crate::state_system::game_state::{Blackboard, GameState, GameStateIndex};
+use connect_4_rust::board_logic::bit_board::BitBoard;
+use macroquad::prelude::*;
+use std::iter;
+
+/// The steps, that can be requested by the player.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ReplayStep {
+    Back,
+    Forward,
+    First,
+    Last,
+    Exit,
+}
+
+pub struct StateReplay {
+    /// The moves of the game as coded move and computer flag in the order played.
+    move_history: Vec<(u64, bool)>,
+    /// The position before the first move of the history, it also carries the rules, who started and the
+    /// handicap.
+    start_board: BitBoard,
+    /// The amount of moves from the history, that are shown on the board.
+    shown_moves: usize,
+    /// The board after the shown moves.
+    board: BitBoard,
+    /// The step requested by the user interface.
+    step_requested: Option<ReplayStep>,
+    /// The button the mouse got pressed on.
+    button_press: ButtonPress<ReplayStep>,
+}
+
+/// The button to step back.
+const BACK_BUTTON: Rect = Rect {
+    x: 10.0,
+    y: 610.0,
+    w: 80.0,
+    h: 80.0,
+};
+/// The button to step forward.
+const FORWARD_BUTTON: Rect = Rect {
+    x: 100.0,
+    y: 610.0,
+    w: 80.0,
+    h: 80.0,
+};
+/// The button to leave the replay.
+const EXIT_BUTTON: Rect = Rect {
+    x: 560.0,
+    y: 610.0,
+    w: 130.0,
+    h: 80.0,
+};
+/// The position of the move counter.
+const COUNTER_POSITION: Vec2 = Vec2 { x: 192.0, y: 640.0 };
+/// The area of the graph of the scores.
+const GRAPH_AREA: Rect = Rect {
+    x: 340.0,
+    y: 614.0,
+    w: 210.0,
+    h: 72.0,
+};
+/// The range of the scores in the graph, from a loss to a win of the first player.
+const GRAPH_RANGE: (f32, f32) = (-1.0, 1.0);
+/// The color of the panel behind the graph.
+const GRAPH_PANEL_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.3);
+/// The color the blunders are marked with.
+const BLUNDER_COLOR: Color = Color::new(0.9, 0.1, 0.1, 1.0);
+/// The position of the exit text.
+const EXIT_TEXT_POSITION: Vec2 = Vec2 { x: 580.0, y: 640.0 };
+
+impl StateReplay {
+    pub fn new() -> StateReplay {
+        StateReplay {
+            move_history: Vec::new(),
+            start_board: BitBoard::new(),
+            shown_moves: 0,
+            board: BitBoard::new(),
+            step_requested: None,
+            button_press: ButtonPress::new(),
+        }
+    }
+
+    /// Gets the board after the indicated amount of moves from the history.
+    fn get_position(&self, moves: usize) -> BitBoard {
+        let mut board = self.start_board.clone();
+        for &(coded_move, is_computer) in &self.move_history[..moves] {
+            board.apply_move(coded_move, is_computer);
+        }
+        board
+    }
+
+    /// Sets up the board with the first shown moves of the history.
+    fn rebuild_board(&mut self) {
+        self.board = self.get_position(self.shown_moves);
+    }
+
+    /// Requests the evaluations of the positions of the game, that are neither in the cache nor requested
+    /// yet. The shown position goes first, so that it gets evaluated early, when the player steps through
+    /// the game. The final position does not need the ai, if the game has ended on the board.
+    fn request_evaluations(&self, black_board: &mut Blackboard) {
+        for ply in iter::once(self.shown_moves).chain(0..=self.move_history.len()) {
+            if !black_board.eval_cache.needs_request(ply) {
+                continue;
+            }
+            let position = self.get_position(ply);
+            if position.is_game_over() {
+                let (result, _) = position.get_winning_status_for_rendering();
+                let entry = EvalEntry {
+                    score: get_final_score(result),
+                    best_move: None,
+                };
+                black_board.eval_cache.insert(ply, entry);
+                continue;
+            }
+            match black_board
+                .computer_player
+                .get_ai_handler_mut()
+                .send_evaluation_request(position, ply)
+            {
+                Ok(generation) => black_board.eval_cache.mark_requested(ply, generation),
+                Err(error) => {
+                    log::warn!("The evaluations could not be requested: {error}");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Stores the evaluations, that have arrived, in the cache. If they got lost, the cache gets cleared,
+    /// so that all positions are requested again. A position, that could not be evaluated, gets requested
+    /// again as well.
+    fn collect_evaluations(&self, black_board: &mut Blackboard) {
+        let results = match black_board
+            .computer_player
+            .get_ai_handler_mut()
+            .try_get_evaluation_results()
+        {
+            Ok(results) => results,
+            Err(error) => {
+                log::warn!("The evaluations got lost: {error}");
+                black_board.eval_cache.clear();
+                return;
+            }
+        };
+        // Results for plies beyond the game can only be left over from another game.
+        for (ply, result) in results {
+            if ply > self.move_history.len() {
+                continue;
+            }
+            let Ok(best_move) = result.column else {
+                black_board.eval_cache.forget(ply, result.generation);
+                continue;
+            };
+            let entry = EvalEntry {
+                score: get_first_player_score(&self.get_position(ply), result.score),
+                best_move: Some(best_move),
+            };
+            black_board.eval_cache.fill(ply, result.generation, entry);
+        }
+    }
+
+    /// Draws the graph of the scores with the blunders and the position shown on the board.
+    fn draw_analysis_graph(&self, black_board: &Blackboard) {
+        draw_rectangle(
+            GRAPH_AREA.x,
+            GRAPH_AREA.y,
+            GRAPH_AREA.w,
+            GRAPH_AREA.h,
+            GRAPH_PANEL_COLOR,
+        );
+        let scores = black_board.eval_cache.get_scores(self.move_history.len());
+        let text_color = get_active_theme().text;
+        let even = get_graph_point(GRAPH_AREA, 0, scores.len(), 0.0, GRAPH_RANGE);
+        draw_line(
+            GRAPH_AREA.left(),
+            even.y,
+            GRAPH_AREA.right(),
+            even.y,
+            1.0,
+            *get_color(SymbolColor::Grey),
+        );
+        let shown = get_graph_point(GRAPH_AREA, self.shown_moves, scores.len(), 0.0, GRAPH_RANGE);
+        draw_line(
+            shown.x,
+            GRAPH_AREA.top(),
+            shown.x,
+            GRAPH_AREA.bottom(),
+            1.0,
+            text_color,
+        );
+        draw_scaled_polyline(&scores, GRAPH_AREA, GRAPH_RANGE, 2.0, text_color);
+        let blunders = black_board
+            .eval_cache
+            .get_blunders(&self.move_history, self.board.get_computer_first());
+        for ply in blunders {
+            if let Some(score) = scores[ply] {
+                let point = get_graph_point(GRAPH_AREA, ply, scores.len(), score, GRAPH_RANGE);
+                draw_circle(point.x, point.y, 4.0, BLUNDER_COLOR);
+            }
+        }
+    }
+}
+
+/// Gets the step of the button at the indicated position, none if there is no button.
+fn get_button_at(position: Vec2) -> Option<ReplayStep> {
+    if BACK_BUTTON.contains(position) {
+        Some(ReplayStep::Back)
+    } else if FORWARD_BUTTON.contains(position) {
+        Some(ReplayStep::Forward)
+    } else if EXIT_BUTTON.contains(position) {
+        Some(ReplayStep::Exit)
+    } else {
+        None
+    }
+}
+
+impl GameState for StateReplay {
+    /// Takes over the moves of the finished game and starts with the final position.
+    fn enter(&mut self, black_board: &Blackboard) {
+        self.move_history = black_board.game_board.get_move_history().to_vec();
+        self.shown_moves = self.move_history.len();
+        // Taking back all moves of the game leaves the position it started from.
+        self.start_board = black_board.game_board.clone();
+        while self.start_board.undo_last_move().is_some() {}
+        self.rebuild_board();
+        self.step_requested = None;
+        self.button_press.cancel();
+    }
+
+    /// Keeps the analysis going and executes the requested step. On exit the game board gets cleared and
+    /// we go for start selection, during a match the score of the match comes next.
+    fn update(&mut self, _: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        self.request_evaluations(black_board);
+        self.collect_evaluations(black_board);
+        let step = self.step_requested.take()?;
+        self.shown_moves = match step {
+            ReplayStep::Back => self.shown_moves.saturating_sub(1),
+            ReplayStep::Forward => (self.shown_moves + 1).min(self.move_history.len()),
+            ReplayStep::First => 0,
+            ReplayStep::Last => self.move_history.len(),
+            ReplayStep::Exit if black_board.match_state.is_some() => {
+                black_board
+                    .computer_player
+                    .get_ai_handler_mut()
+                    .cancel_evaluations();
+                return Some(GameStateIndex::MatchScore);
+            }
+            ReplayStep::Exit => {
+                black_board.reset_game();
+                return Some(GameStateIndex::StartSelection);
+            }
+        };
+        self.rebuild_board();
+        None
+    }
+
+    /// Remembers which of the buttons got pressed.
+    fn mouse_down(&mut self, position: Vec2) {
+        self.button_press.press(get_button_at(position));
+    }
+
+    /// Requests the step of the button, if the mouse got released on the pressed one.
+    fn mouse_up(&mut self, position: Vec2) {
+        if let Some(step) = self.button_press.release(get_button_at(position)) {
+            self.step_requested = Some(step);
+        }
+    }
+
+    /// Maps the arrow keys, Home, End and Escape on the steps.
+    fn key_pressed(&mut self, key: KeyCode) {
+        let step = match key {
+            KeyCode::Left => ReplayStep::Back,
+            KeyCode::Right => ReplayStep::Forward,
+            KeyCode::Home => ReplayStep::First,
+            KeyCode::End => ReplayStep::Last,
+            KeyCode::Escape => ReplayStep::Exit,
+            _ => return,
+        };
+        self.step_requested = Some(step);
+    }
+
+    /// Renders the board after the shown moves, the winning stones in the final position, the buttons and
+    /// the graph of the analysis.
+    fn draw(&self, black_board: &Blackboard) {
+        render_board(&self.board, black_board.board_texture.as_ref());
+        if let (result, Some(lines)) = self.board.get_winning_status_for_rendering() {
+            for line in &lines {
+                render_winning_stones(result.is_first_player_win(), &line.cells);
+                render_winning_stroke(line, result.is_first_player_win(), &STANDARD_LAYOUT);
+            }
+        }
+
+        let color = *get_color(SymbolColor::Yellow);
+        draw_triangle(
+            Vec2::new(BACK_BUTTON.x, BACK_BUTTON.center().y),
+            Vec2::new(BACK_BUTTON.right(), BACK_BUTTON.bottom()),
+            Vec2::new(BACK_BUTTON.right(), BACK_BUTTON.top()),
+            color,
+        );
+        draw_triangle(
+            Vec2::new(FORWARD_BUTTON.right(), FORWARD_BUTTON.center().y),
+            Vec2::new(FORWARD_BUTTON.x, FORWARD_BUTTON.bottom()),
+            Vec2::new(FORWARD_BUTTON.x, FORWARD_BUTTON.top()),
+            color,
+        );
+        print_text_sized(
+            &format!("Move {}/{}", self.shown_moves, self.move_history.len()),
+            COUNTER_POSITION,
+            SMALL_TEXT_SIZE,
+        );
+        self.draw_analysis_graph(black_board);
+        print_text("Exit", EXIT_TEXT_POSITION);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::HeadlessGame;
+    use crate::state_game_over::REPLAY_BUTTON;
+    use crate::state_player_start_selection::PLAYER_STARTS_BUTTON;
+
+    /// The columns of the finished game, the player wins in the first column.
+    const GAME: [u32; 7] = [0, 1, 0, 1, 0, 1, 0];
+
+    /// Gets a headless game, where the player has won the game.
+    fn get_finished_game() -> HeadlessGame {
+        let mut game = HeadlessGame::new();
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        game.play_columns(&GAME);
+        assert_eq!(game.get_current_index(), GameStateIndex::GameOverState);
+        game
+    }
+
+    /// Gets the board after the first moves of the game.
+    fn get_expected_board(moves: usize) -> String {
+        let move_string: String = GAME[..moves]
+            .iter()
+            .map(|column| char::from_digit(column + 1, 10).unwrap())
+            .collect();
+        BitBoard::from_move_string(&move_string).unwrap().to_ascii()
+    }
+
+    /// Requests the step with the key and executes it.
+    fn step(
+        replay: &mut StateReplay,
+        key: KeyCode,
+        black_board: &mut Blackboard,
+    ) -> Option<GameStateIndex> {
+        replay.key_pressed(key);
+        replay.update(0.0, black_board)
+    }
+
+    #[test]
+    fn test_replay_steps_through_the_game() {
+        let mut game = get_finished_game();
+        let mut replay = StateReplay::new();
+        replay.enter(&game.black_board);
+        assert_eq!(replay.shown_moves, GAME.len());
+        assert_eq!(replay.board.to_ascii(), get_expected_board(GAME.len()));
+
+        // Stepping stops at both ends of the game.
+        assert_eq!(
+            step(&mut replay, KeyCode::Right, &mut game.black_board),
+            None
+        );
+        assert_eq!(replay.shown_moves, GAME.len());
+        for moves in (0..GAME.len()).rev() {
+            step(&mut replay, KeyCode::Left, &mut game.black_board);
+            assert_eq!(replay.shown_moves, moves);
+            assert_eq!(replay.board.to_ascii(), get_expected_board(moves));
+        }
+        step(&mut replay, KeyCode::Left, &mut game.black_board);
+        assert_eq!(replay.shown_moves, 0);
+
+        step(&mut replay, KeyCode::End, &mut game.black_board);
+        assert_eq!(replay.board.to_ascii(), get_expected_board(GAME.len()));
+        step(&mut replay, KeyCode::Home, &mut game.black_board);
+        assert_eq!(replay.board.to_ascii(), get_expected_board(0));
+        // Other keys do nothing.
+        step(&mut replay, KeyCode::A, &mut game.black_board);
+        assert_eq!(replay.shown_moves, 0);
+    }
+
+    #[test]
+    fn test_buttons_step_on_release() {
+        let mut game = get_finished_game();
+        let mut replay = StateReplay::new();
+        replay.enter(&game.black_board);
+
+        replay.mouse_down(BACK_BUTTON.center());
+        replay.mouse_up(BACK_BUTTON.center());
+        replay.update(0.0, &mut game.black_board);
+        assert_eq!(replay.shown_moves, GAME.len() - 1);
+        replay.mouse_down(FORWARD_BUTTON.center());
+        replay.mouse_up(FORWARD_BUTTON.center());
+        replay.update(0.0, &mut game.black_board);
+        assert_eq!(replay.shown_moves, GAME.len());
+
+        // Releasing on another button than the pressed one does nothing.
+        replay.mouse_down(BACK_BUTTON.center());
+        replay.mouse_up(FORWARD_BUTTON.center());
+        replay.update(0.0, &mut game.black_board);
+        assert_eq!(replay.shown_moves, GAME.len());
+        assert_eq!(get_button_at(Vec2::new(300.0, 300.0)), None);
+    }
+
+    #[test]
+    fn test_replay_is_left_for_start_selection() {
+        let mut game = get_finished_game();
+        game.click(REPLAY_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::ReplayState);
+        // The replay leaves the game as it is.
+        assert_eq!(
+            game.black_board.game_board.to_ascii(),
+            get_expected_board(GAME.len())
+        );
+
+        game.click(EXIT_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+        assert_eq!(game.black_board.game_board.stone_count(), 0);
+    }
+}
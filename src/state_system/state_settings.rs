@@ -0,0 +1,321 @@
+//! The settings state shows one row per setting, clicking a row cycles through its values. The changes
+//! take effect right away and get stored on disk, when the player goes back to the start screen.
+
+use crate::game_state::{Blackboard, GameState, GameStateIndex};
+use crate::render_system::graphics::{
+    SMALL_TEXT_SIZE, TITLE_CENTER, draw_button, print_text_centered,
+};
+use crate::render_system::toast::TOAST_DURATION;
+use crate::state_system::button_press::ButtonPress;
+use crate::state_system::settings::{SettingValue, Settings, get_rule_set_label};
+use macroquad::prelude::*;
+
+/// The rows of the settings screen from top to bottom.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SettingsRow {
+    AnimationSpeed,
+    Theme,
+    Difficulty,
+    Sound,
+    Clock,
+    Handicap,
+    Rules,
+    Training,
+    LogFile,
+    ExportRecords,
+}
+
+/// All rows in the order they are drawn.
+const ROWS: [SettingsRow; 10] = [
+    SettingsRow::AnimationSpeed,
+    SettingsRow::Theme,
+    SettingsRow::Difficulty,
+    SettingsRow::Sound,
+    SettingsRow::Clock,
+    SettingsRow::Handicap,
+    SettingsRow::Rules,
+    SettingsRow::Training,
+    SettingsRow::LogFile,
+    SettingsRow::ExportRecords,
+];
+
+impl SettingsRow {
+    /// Gets the text of the row with the current value.
+    fn get_label(self, settings: &Settings) -> String {
+        match self {
+            SettingsRow::AnimationSpeed => settings.animation_speed.get_label().to_string(),
+            SettingsRow::Theme => settings.theme.get_label().to_string(),
+            SettingsRow::Difficulty => format!("Difficulty: {}", settings.difficulty.get_label()),
+            SettingsRow::Sound => {
+                format!(
+                    "Sound: {}",
+                    if settings.sound_enabled { "On" } else { "Off" }
+                )
+            }
+            SettingsRow::Clock => format!("Clock: {}", settings.clock.get_label()),
+            SettingsRow::Handicap => settings.handicap.get_label().to_string(),
+            SettingsRow::Rules => get_rule_set_label(settings.rule_set).to_string(),
+            SettingsRow::Training => {
+                format!(
+                    "Training: {}",
+                    if settings.training_mode { "On" } else { "Off" }
+                )
+            }
+            SettingsRow::LogFile => {
+                format!(
+                    "Log file: {}",
+                    if settings.log_to_file { "On" } else { "Off" }
+                )
+            }
+            SettingsRow::ExportRecords => {
+                format!(
+                    "Export games: {}",
+                    if settings.export_records { "On" } else { "Off" }
+                )
+            }
+        }
+    }
+
+    /// Changes the setting of the row to the next value.
+    fn cycle(self, settings: &mut Settings) {
+        match self {
+            SettingsRow::AnimationSpeed => {
+                settings.animation_speed = settings.animation_speed.get_next()
+            }
+            SettingsRow::Theme => settings.theme = settings.theme.get_next(),
+            SettingsRow::Difficulty => settings.difficulty = settings.difficulty.get_next(),
+            SettingsRow::Sound => settings.sound_enabled = settings.sound_enabled.get_next(),
+            SettingsRow::Clock => settings.clock = settings.clock.get_next(),
+            SettingsRow::Handicap => settings.handicap = settings.handicap.get_next(),
+            SettingsRow::Rules => settings.rule_set = settings.rule_set.get_next(),
+            SettingsRow::Training => settings.training_mode = settings.training_mode.get_next(),
+            SettingsRow::LogFile => settings.log_to_file = settings.log_to_file.get_next(),
+            SettingsRow::ExportRecords => {
+                settings.export_records = settings.export_records.get_next()
+            }
+        }
+    }
+
+    /// Gets the click target of the row.
+    fn get_button(self) -> Rect {
+        let index = ROWS.iter().position(|&row| row == self).unwrap_or(0);
+        Rect {
+            x: 150.0,
+            y: FIRST_ROW_Y - ROW_DISTANCE * index as f32,
+            w: 400.0,
+            h: 42.0,
+        }
+    }
+}
+
+/// The lower edge of the first row.
+const FIRST_ROW_Y: f32 = 520.0;
+/// The vertical distance between two rows.
+const ROW_DISTANCE: f32 = 44.0;
+/// The button to go back to the start screen.
+const BACK_BUTTON: Rect = Rect {
+    x: 250.0,
+    y: 40.0,
+    w: 200.0,
+    h: 70.0,
+};
+
+/// The buttons of the settings screen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum SettingsButton {
+    Row(SettingsRow),
+    Back,
+}
+
+/// Gets the button at the indicated position, none if there is no button.
+fn get_button_at(position: Vec2) -> Option<SettingsButton> {
+    if BACK_BUTTON.contains(position) {
+        return Some(SettingsButton::Back);
+    }
+    ROWS.iter()
+        .copied()
+        .find(|row| row.get_button().contains(position))
+        .map(SettingsButton::Row)
+}
+
+pub struct StateSettings {
+    /// The row clicked by the player.
+    row_clicked: Option<SettingsRow>,
+    back_requested: bool,
+    /// The button the mouse got pressed on.
+    button_press: ButtonPress<SettingsButton>,
+}
+
+impl StateSettings {
+    pub fn new() -> StateSettings {
+        StateSettings {
+            row_clicked: None,
+            back_requested: false,
+            button_press: ButtonPress::new(),
+        }
+    }
+}
+
+impl GameState for StateSettings {
+    fn enter(&mut self, _: &Blackboard) {
+        self.row_clicked = None;
+        self.back_requested = false;
+        self.button_press.cancel();
+    }
+
+    /// Changes the clicked setting on the blackboard. When going back the settings get applied and saved.
+    fn update(&mut self, _: f32, black_board: &mut Blackboard) -> Option<GameStateIndex> {
+        if let Some(row) = self.row_clicked.take() {
+            row.cycle(&mut black_board.settings);
+            if row == SettingsRow::LogFile {
+                black_board
+                    .toasts
+                    .push("The log changes with the next start", TOAST_DURATION);
+            }
+        }
+        if self.back_requested {
+            black_board.apply_settings();
+            if black_board.save_settings() {
+                black_board.toasts.push("Settings saved", TOAST_DURATION);
+            }
+            return Some(GameStateIndex::StartSelection);
+        }
+        None
+    }
+
+    /// Remembers the button the mouse got pressed on.
+    fn mouse_down(&mut self, position: Vec2) {
+        self.button_press.press(get_button_at(position));
+    }
+
+    /// Checks which of the rows or if the back button got clicked.
+    fn mouse_up(&mut self, position: Vec2) {
+        match self.button_press.release(get_button_at(position)) {
+            Some(SettingsButton::Row(row)) => self.row_clicked = Some(row),
+            Some(SettingsButton::Back) => self.back_requested = true,
+            None => {}
+        }
+    }
+
+    /// Goes back with Escape.
+    fn key_pressed(&mut self, key: KeyCode) {
+        if key == KeyCode::Escape {
+            self.back_requested = true;
+        }
+    }
+
+    /// Renders the rows with their current values and the back button.
+    fn draw(&self, black_board: &Blackboard) {
+        print_text_centered("Settings", TITLE_CENTER);
+        for row in ROWS {
+            draw_button(
+                row.get_button(),
+                &row.get_label(&black_board.settings),
+                SMALL_TEXT_SIZE,
+                true,
+            );
+        }
+        draw_button(BACK_BUTTON, "Back", SMALL_TEXT_SIZE, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_system::graphics::get_drawing_coordinates;
+    use crate::session_record::FrameInput;
+    use crate::state_difficulty_selection::BUTTON_CENTERS;
+    use crate::state_driver::tests::{FRAME_TIME, HeadlessGame};
+    use crate::state_player_start_selection::{PLAYER_STARTS_BUTTON, SETTINGS_BUTTON_CENTER};
+    use crate::state_system::settings::{AnimationSpeed, Difficulty};
+    use connect_4_rust::board_logic::bit_board_coding::BOARD_WIDTH;
+
+    /// Clicks the rows on the settings screen, reached from the start screen, and goes back.
+    fn change_settings(game: &mut HeadlessGame, rows: &[SettingsRow]) {
+        game.click(SETTINGS_BUTTON_CENTER);
+        assert_eq!(game.get_current_index(), GameStateIndex::Settings);
+        for row in rows {
+            game.click(row.get_button().center());
+        }
+        game.click(BACK_BUTTON.center());
+        assert_eq!(game.get_current_index(), GameStateIndex::StartSelection);
+    }
+
+    /// Gets the number of frames it takes the stone of the player to fall into the empty column with
+    /// the animation speed of the settings.
+    fn get_frames_to_drop(game: &mut HeadlessGame) -> u32 {
+        game.click(PLAYER_STARTS_BUTTON.center());
+        game.click(BUTTON_CENTERS[0]);
+        assert_eq!(game.get_current_index(), GameStateIndex::PlayerInputState);
+        let position = get_drawing_coordinates(BOARD_WIDTH / 2, 2);
+        for (mouse_pressed, mouse_released) in [(true, false), (false, true)] {
+            game.run_frame(FrameInput {
+                frame_time: FRAME_TIME,
+                mouse_position: position,
+                mouse_pressed,
+                mouse_released,
+                ..FrameInput::default()
+            });
+        }
+        let mut frame_count = 0;
+        while game.get_current_index() == GameStateIndex::PlayerInputState {
+            game.wait(FRAME_TIME);
+            frame_count += 1;
+            assert!(frame_count < 1000, "The stone does not arrive.");
+        }
+        frame_count
+    }
+
+    #[test]
+    fn test_difficulty_reaches_the_ai() {
+        let mut game = HeadlessGame::new();
+        let default_config = Difficulty::default().get_search_config();
+        assert_eq!(
+            game.black_board
+                .computer_player
+                .get_ai_handler()
+                .get_config(),
+            default_config
+        );
+        change_settings(&mut game, &[SettingsRow::Difficulty]);
+        let difficulty = game.black_board.settings.difficulty;
+        assert_ne!(difficulty, Difficulty::default());
+        let config = game
+            .black_board
+            .computer_player
+            .get_ai_handler()
+            .get_config();
+        assert_eq!(config, difficulty.get_search_config());
+        assert_ne!(config, default_config);
+    }
+
+    #[test]
+    fn test_animation_speed_reaches_the_animator() {
+        let mut normal_game = HeadlessGame::new();
+        normal_game.black_board.settings.animation_speed = AnimationSpeed::Normal;
+        let normal_frames = get_frames_to_drop(&mut normal_game);
+
+        let mut fast_game = HeadlessGame::new();
+        fast_game.black_board.settings.animation_speed = AnimationSpeed::Normal;
+        change_settings(&mut fast_game, &[SettingsRow::AnimationSpeed]);
+        assert_eq!(
+            fast_game.black_board.settings.animation_speed,
+            AnimationSpeed::Fast
+        );
+        let fast_frames = get_frames_to_drop(&mut fast_game);
+
+        let mut instant_game = HeadlessGame::new();
+        instant_game.black_board.settings.animation_speed = AnimationSpeed::Normal;
+        change_settings(
+            &mut instant_game,
+            &[SettingsRow::AnimationSpeed, SettingsRow::AnimationSpeed],
+        );
+        let instant_frames = get_frames_to_drop(&mut instant_game);
+
+        assert!(
+            instant_frames < fast_frames && 2 * fast_frames < normal_frames,
+            "{instant_frames} {fast_frames} {normal_frames}"
+        );
+        assert!(instant_frames <= 2, "{instant_frames}");
+    }
+}
@@ -0,0 +1,118 @@
+//! Paces the moves of the computer. In the endgame the engine answers within a frame, so its stone would
+//! start falling the moment the one of the player has landed, which feels like the game plays itself.
+//! The pacer holds the move back, until a minimum time has passed since the computer started thinking.
+//! It also watches for a move, that takes suspiciously long, long before the ai handler gives up on it.
+//! The time is the one of the fixed steps, so a replay gets paced the same way.
+
+/// The default of the minimum time in milliseconds the computer seems to think.
+pub const DEFAULT_THINK_TIME_MS: u32 = 500;
+
+/// Holds the move of the computer back for the minimum time and reports, when it takes too long.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ThinkPacer {
+    /// The time in seconds, that has to pass, before the move may be played.
+    minimum_time: f32,
+    /// The time in seconds, after which a missing move gets reported, none if it never does.
+    watchdog_time: Option<f32>,
+    /// The time in seconds waited so far.
+    waited: f32,
+    /// The move has arrived.
+    move_arrived: bool,
+    /// The missing move has been reported, it only gets reported once.
+    watchdog_fired: bool,
+}
+
+impl ThinkPacer {
+    /// Creates the pacer for a move, that has just been requested.
+    pub fn new(minimum_time: f32, watchdog_time: Option<f32>) -> ThinkPacer {
+        ThinkPacer {
+            minimum_time,
+            watchdog_time,
+            waited: 0.0,
+            move_arrived: false,
+            watchdog_fired: false,
+        }
+    }
+
+    /// Advances the time waited. Returns true once, when the watchdog time passes without the move.
+    pub fn update(&mut self, delta_time: f32) -> bool {
+        self.waited += delta_time;
+        let overdue = self
+            .watchdog_time
+            .is_some_and(|watchdog_time| self.waited >= watchdog_time);
+        if overdue && !self.move_arrived && !self.watchdog_fired {
+            self.watchdog_fired = true;
+            return true;
+        }
+        false
+    }
+
+    /// Notes, that the move has arrived.
+    pub fn set_move_arrived(&mut self) {
+        self.move_arrived = true;
+    }
+
+    /// Checks, if the move has arrived, it may still be held back.
+    pub fn has_move_arrived(&self) -> bool {
+        self.move_arrived
+    }
+
+    /// Checks, if the move has arrived and the minimum time has passed, so that it can be played.
+    pub fn is_ready(&self) -> bool {
+        self.move_arrived && self.waited >= self.minimum_time
+    }
+
+    /// Gets the time in seconds waited so far.
+    pub fn get_waited(&self) -> f32 {
+        self.waited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_is_held_back_for_the_minimum_time() {
+        // The move arrives right away, it may only be played after the minimum time.
+        let mut pacer = ThinkPacer::new(0.5, None);
+        pacer.update(0.1);
+        pacer.set_move_arrived();
+        assert!(pacer.has_move_arrived());
+        assert!(!pacer.is_ready());
+        pacer.update(0.3);
+        assert!(!pacer.is_ready());
+        pacer.update(0.1);
+        assert!(pacer.is_ready());
+    }
+
+    #[test]
+    fn test_late_move_is_played_right_away() {
+        let mut pacer = ThinkPacer::new(0.5, None);
+        pacer.update(0.8);
+        assert!(!pacer.is_ready());
+        pacer.set_move_arrived();
+        assert!(pacer.is_ready());
+        assert_eq!(pacer.get_waited(), 0.8);
+    }
+
+    #[test]
+    fn test_watchdog_fires_once() {
+        let mut pacer = ThinkPacer::new(0.0, Some(10.0));
+        assert!(!pacer.update(9.5));
+        assert!(pacer.update(0.5));
+        assert!(!pacer.update(5.0));
+        pacer.set_move_arrived();
+        assert!(pacer.is_ready());
+
+        // A move, that has arrived in time, is not reported.
+        let mut pacer = ThinkPacer::new(0.0, Some(10.0));
+        pacer.update(1.0);
+        pacer.set_move_arrived();
+        assert!(!pacer.update(20.0));
+
+        // Without a watchdog time nothing gets reported.
+        let mut pacer = ThinkPacer::new(0.0, None);
+        assert!(!pacer.update(1000.0));
+    }
+}
@@ -0,0 +1,233 @@
+//! Loader and runner for the public Connect-4 test sets of Pascal Pons ("Test_L3_R1" etc.).
+//! Every line of these files contains a move string and the expected score separated by a space.
+//! The score is positive if the player to move wins, zero for a draw and negative for a loss.
+//! We only compare the sign of the score, as our engine does not use the same score scale.
+
+use crate::board_logic::alpha_beta::{AlphaBeta, GameOutcome};
+use crate::board_logic::bit_board::{BitBoard, MoveStringError};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// One position of a test set.
+#[derive(Clone)]
+pub struct TestPosition {
+    /// The moves as they appear in the file.
+    pub moves: String,
+    /// The board after applying the moves.
+    pub board: BitBoard,
+    /// The expected score from the perspective of the player to move.
+    pub expected_score: i32,
+}
+
+impl TestPosition {
+    /// Gets the outcome the expected score stands for.
+    pub fn get_expected_outcome(&self) -> GameOutcome {
+        match self.expected_score {
+            score if score > 0 => GameOutcome::Win,
+            0 => GameOutcome::Draw,
+            _ => GameOutcome::Loss,
+        }
+    }
+}
+
+/// The errors that can occur when parsing a test set. Lines are counted from 1.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TestSetError {
+    /// The line does not consist of a move string and a score.
+    Format { line: usize },
+    /// The move string of the line could not be parsed.
+    MoveString { line: usize, error: MoveStringError },
+}
+
+impl fmt::Display for TestSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestSetError::Format { line } => write!(f, "line {line}: expected moves and score"),
+            TestSetError::MoveString { line, error } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for TestSetError {}
+
+/// Parses the content of a test set file. Empty lines are skipped.
+pub fn parse_test_set(content: &str) -> Result<Vec<TestPosition>, TestSetError> {
+    let mut positions = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let mut parts = line.split_whitespace();
+        let Some(moves) = parts.next() else {
+            continue;
+        };
+        let expected_score = parts
+            .next()
+            .and_then(|score| score.parse().ok())
+            .ok_or(TestSetError::Format { line: line_number })?;
+        if parts.next().is_some() {
+            return Err(TestSetError::Format { line: line_number });
+        }
+        let board =
+            BitBoard::from_move_string(moves).map_err(|error| TestSetError::MoveString {
+                line: line_number,
+                error,
+            })?;
+
+        positions.push(TestPosition {
+            moves: moves.to_string(),
+            board,
+            expected_score,
+        });
+    }
+
+    Ok(positions)
+}
+
+/// The summary of running a test set.
+#[derive(Clone, Default, Debug)]
+pub struct TestSetReport {
+    /// The amount of positions solved.
+    pub positions: u32,
+    /// The amount of positions, where the outcome matched the expectation.
+    pub correct: u32,
+    /// The nodes searched for all positions.
+    pub nodes: u64,
+    /// The time spent for all positions.
+    pub time: Duration,
+    /// The move strings of the positions, where the outcome did not match.
+    pub failures: Vec<String>,
+}
+
+impl fmt::Display for TestSetReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} positions correct, {} nodes in {:.3} s",
+            self.correct,
+            self.positions,
+            self.nodes,
+            self.time.as_secs_f64()
+        )?;
+        for failure in &self.failures {
+            writeln!(f, "failed: {failure}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Solves all positions of the test set and compares the outcomes with the expected ones.
+pub fn run_test_set(positions: &[TestPosition], engine: &mut AlphaBeta) -> TestSetReport {
+    let mut report = TestSetReport::default();
+    for position in positions {
+        let start = Instant::now();
+        let result = engine.solve(position.board.clone());
+        report.time += start.elapsed();
+        report.nodes += engine.get_nodes_searched();
+        report.positions += 1;
+        if result.outcome == position.get_expected_outcome() {
+            report.correct += 1;
+        } else {
+            report.failures.push(position.moves.clone());
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A subset of the public test sets, that is solved in a few seconds.
+    const FIXTURE: &str = include_str!("../tests/fixtures/solver_positions.txt");
+
+    #[test]
+    fn test_positions_are_parsed() {
+        let positions = parse_test_set("4453 -2\n\n  \n1 0\n44 7\n").unwrap();
+        let moves: Vec<&str> = positions
+            .iter()
+            .map(|position| &position.moves[..])
+            .collect();
+        assert_eq!(moves, ["4453", "1", "44"]);
+        let outcomes: Vec<GameOutcome> = positions
+            .iter()
+            .map(TestPosition::get_expected_outcome)
+            .collect();
+        assert_eq!(
+            outcomes,
+            [GameOutcome::Loss, GameOutcome::Draw, GameOutcome::Win]
+        );
+        assert_eq!(positions[0].board.stone_count(), 4);
+    }
+
+    #[test]
+    fn test_malformed_lines_are_refused() {
+        for (content, line) in [("4453", 1), ("4453 x", 1), ("1 0\n4453 1 2", 2)] {
+            assert_eq!(
+                parse_test_set(content).err(),
+                Some(TestSetError::Format { line }),
+                "{content}"
+            );
+        }
+        assert_eq!(
+            parse_test_set("1 0\n\n48 0").err(),
+            Some(TestSetError::MoveString {
+                line: 3,
+                error: MoveStringError::InvalidCharacter {
+                    position: 1,
+                    character: '8'
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_fixture_gets_solved() {
+        let positions = parse_test_set(FIXTURE).unwrap();
+        let report = run_test_set(&positions, &mut AlphaBeta::new());
+        assert_eq!(report.positions, 20);
+        assert_eq!(report.correct, 20, "{report}");
+        assert!(report.failures.is_empty());
+        assert!(report.nodes > 0);
+    }
+
+    #[test]
+    fn test_wrong_expectation_is_reported() {
+        // The player to move wins at once, the second line claims a loss for a won position.
+        let positions = parse_test_set("414141 1\n214677454114757442122267175123 -1").unwrap();
+        let report = run_test_set(&positions, &mut AlphaBeta::new());
+        assert_eq!(report.positions, 2);
+        assert_eq!(report.failures, ["214677454114757442122267175123"]);
+        let text = report.to_string();
+        assert!(text.starts_with("1 of 2 positions correct, "), "{text}");
+        assert!(
+            text.ends_with("failed: 214677454114757442122267175123\n"),
+            "{text}"
+        );
+    }
+
+    /// Solves the complete public test sets, which takes long. The files get read from the directory
+    /// in the variable CONNECT4_TEST_SETS, run it with
+    /// `CONNECT4_TEST_SETS=<directory> cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_public_test_sets() {
+        let directory = PathBuf::from(
+            std::env::var("CONNECT4_TEST_SETS").expect("CONNECT4_TEST_SETS names the directory."),
+        );
+        let mut engine = AlphaBeta::new();
+        for name in [
+            "Test_L3_R1",
+            "Test_L2_R1",
+            "Test_L2_R2",
+            "Test_L1_R1",
+            "Test_L1_R2",
+            "Test_L1_R3",
+        ] {
+            let content = std::fs::read_to_string(directory.join(name)).unwrap();
+            let report = run_test_set(&parse_test_set(&content).unwrap(), &mut engine);
+            println!("{name}: {report}");
+            assert!(report.failures.is_empty(), "{name}: {report}");
+        }
+    }
+}
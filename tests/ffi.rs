@@ -0,0 +1,145 @@
+//! Builds the engine as a shared library with the C interface, loads it like a program in another
+//! language would and plays a few positions through it.
+
+use libloading::{Library, Symbol};
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::path::PathBuf;
+use std::process::Command;
+use std::ptr;
+
+/// The error codes and outcomes of `include/connect4.h`.
+const C4_ERROR_NULL_POINTER: i32 = -1;
+const C4_ERROR_INVALID_UTF8: i32 = -2;
+const C4_ERROR_INVALID_MOVES: i32 = -3;
+const C4_ERROR_GAME_OVER: i32 = -4;
+const C4_OUTCOME_WIN: i32 = 1;
+
+/// The engine, it is opaque.
+#[repr(C)]
+struct Engine {
+    _private: [u8; 0],
+}
+
+/// The result of solving a position, like the C interface lays it out.
+#[repr(C)]
+#[derive(Default)]
+struct C4SolveResult {
+    outcome: i32,
+    distance: u32,
+    best_move: u32,
+}
+
+type EngineNew = unsafe extern "C" fn(u32) -> *mut Engine;
+type EngineFree = unsafe extern "C" fn(*mut Engine);
+type BestMove = unsafe extern "C" fn(*mut Engine, *const u8, usize) -> i32;
+type Solve = unsafe extern "C" fn(*mut Engine, *const u8, usize, *mut C4SolveResult) -> i32;
+
+/// Builds the shared library the way the documentation of the ffi module tells, without the game. It gets
+/// its own target directory, so that it does not wait for the build of the tests.
+fn build_library() -> PathBuf {
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("cffi");
+    let status = Command::new(env!("CARGO"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .args([
+            "rustc",
+            "--lib",
+            "--no-default-features",
+            "--features",
+            "cffi",
+            "--crate-type",
+            "cdylib",
+        ])
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("Cargo starts.");
+    assert!(status.success());
+    target_dir
+        .join("debug")
+        .join(format!("{DLL_PREFIX}connect_4_rust{DLL_SUFFIX}"))
+}
+
+/// The functions of the loaded library.
+struct Connect4<'a> {
+    engine_new: Symbol<'a, EngineNew>,
+    engine_free: Symbol<'a, EngineFree>,
+    best_move: Symbol<'a, BestMove>,
+    solve: Symbol<'a, Solve>,
+}
+
+impl Connect4<'_> {
+    fn load(library: &Library) -> Connect4<'_> {
+        // SAFETY: The types of the symbols are the ones of the header.
+        unsafe {
+            Connect4 {
+                engine_new: library.get(b"c4_engine_new").unwrap(),
+                engine_free: library.get(b"c4_engine_free").unwrap(),
+                best_move: library.get(b"c4_best_move").unwrap(),
+                solve: library.get(b"c4_solve").unwrap(),
+            }
+        }
+    }
+
+    fn get_best_move(&self, engine: *mut Engine, moves: &[u8]) -> i32 {
+        // SAFETY: The move string has the length handed over.
+        unsafe { (self.best_move)(engine, moves.as_ptr(), moves.len()) }
+    }
+
+    fn solve(&self, engine: *mut Engine, moves: &[u8]) -> (i32, C4SolveResult) {
+        let mut result = C4SolveResult::default();
+        // SAFETY: The move string has the length handed over and the result is writable.
+        let code = unsafe { (self.solve)(engine, moves.as_ptr(), moves.len(), &mut result) };
+        (code, result)
+    }
+}
+
+#[test]
+fn library_plays_the_positions() {
+    let path = build_library();
+    // SAFETY: The library is the engine built above, it has no initialization with side effects.
+    let library = unsafe { Library::new(&path) }.expect("The shared library loads.");
+    let connect4 = Connect4::load(&library);
+
+    // SAFETY: The functions are called as the header documents them.
+    unsafe {
+        assert!((connect4.engine_new)(0).is_null());
+        let engine = (connect4.engine_new)(8);
+        assert!(!engine.is_null());
+
+        // The first player completes the four in the fourth column, the second one has to block it.
+        assert_eq!(connect4.get_best_move(engine, b"414141"), 4);
+        assert_eq!(connect4.get_best_move(engine, b"41414"), 4);
+        assert!((1..=7).contains(&connect4.get_best_move(engine, b"")));
+
+        let (code, result) = connect4.solve(engine, b"737466214122644652113564225334177");
+        assert_eq!(code, 0);
+        assert_eq!(result.outcome, C4_OUTCOME_WIN);
+        assert_eq!((result.best_move, result.distance), (6, 7));
+
+        assert_eq!(
+            connect4.get_best_move(engine, b"448"),
+            C4_ERROR_INVALID_MOVES
+        );
+        assert_eq!(
+            connect4.get_best_move(engine, b"4\xff"),
+            C4_ERROR_INVALID_UTF8
+        );
+        assert_eq!(
+            connect4.get_best_move(engine, b"4141414"),
+            C4_ERROR_GAME_OVER
+        );
+        assert_eq!(
+            (connect4.best_move)(engine, ptr::null(), 3),
+            C4_ERROR_NULL_POINTER
+        );
+        assert_eq!(
+            connect4.get_best_move(ptr::null_mut(), b"4"),
+            C4_ERROR_NULL_POINTER
+        );
+        let code = (connect4.solve)(engine, b"4".as_ptr(), 1, ptr::null_mut());
+        assert_eq!(code, C4_ERROR_NULL_POINTER);
+
+        (connect4.engine_free)(engine);
+        (connect4.engine_free)(ptr::null_mut());
+    }
+}
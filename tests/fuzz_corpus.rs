@@ -0,0 +1,39 @@
+//! Runs the seeds of the fuzz corpus through the checks of the fuzz targets, so that they are kept
+//! working without the fuzzer.
+
+#[path = "../fuzz/checks.rs"]
+mod checks;
+
+use std::fs;
+use std::path::Path;
+
+/// Runs every seed in the corpus of the target through its check. Returns the amount of seeds.
+fn run_corpus(target: &str, check: fn(&[u8])) -> usize {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fuzz/corpus")
+        .join(target);
+    let mut seeds = 0;
+    for entry in fs::read_dir(&corpus).expect("The corpus is there.") {
+        let path = entry.expect("The corpus can be read.").path();
+        let data = fs::read(&path).expect("The seed can be read.");
+        println!("{}", path.display());
+        check(&data);
+        seeds += 1;
+    }
+    seeds
+}
+
+#[test]
+fn move_string_corpus_passes() {
+    assert!(run_corpus("move_string", checks::check_move_string) > 0);
+}
+
+#[test]
+fn ascii_board_corpus_passes() {
+    assert!(run_corpus("ascii_board", checks::check_ascii_board) > 0);
+}
+
+#[test]
+fn game_record_corpus_passes() {
+    assert!(run_corpus("game_record", checks::check_game_record) > 0);
+}
@@ -0,0 +1,90 @@
+//! Replays recorded sessions with the game binary without a window. The sessions in `tests/sessions` got
+//! recorded with scripted moves of the computer, the replay fails, if the states do not take the recorded
+//! transitions any more. The log of the replay tells, in which state the session ends and how the games
+//! ended.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Gets the path of the recorded session.
+fn get_session(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/sessions")
+        .join(name)
+}
+
+/// Replays the session headless and gets, if it ran as recorded, and the last line of the log. The game
+/// runs in a directory of its own, so that it does not find the settings of the player.
+fn replay(session: &Path) -> (bool, String) {
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("headless");
+    fs::create_dir_all(&directory).expect("The directory of the game can be created.");
+    let output = Command::new(env!("CARGO_BIN_EXE_connect-4-rust"))
+        .current_dir(&directory)
+        .env("RUST_LOG", "info")
+        .arg("--headless")
+        .arg("--replay")
+        .arg(session)
+        .output()
+        .expect("The game starts.");
+    let log = String::from_utf8(output.stderr).expect("The log is text.");
+    let last_line = log.lines().last().unwrap_or_default().to_string();
+    (output.status.success(), last_line)
+}
+
+/// Replays the session, which has to run as recorded, and checks how it ends.
+fn assert_session_ends(name: &str, end: &str) {
+    let (success, last_line) = replay(&get_session(name));
+    assert!(success, "{last_line}");
+    assert!(last_line.ends_with(end), "{last_line}");
+}
+
+#[test]
+fn player_starts_and_wins() {
+    assert_session_ends(
+        "player_wins.txt",
+        "it ends in GameOverState with the score You 1, Computer 0, Draws 0",
+    );
+}
+
+#[test]
+fn computer_starts_and_wins() {
+    assert_session_ends(
+        "computer_wins.txt",
+        "it ends in GameOverState with the score You 0, Computer 1, Draws 0",
+    );
+}
+
+#[test]
+fn full_board_is_a_draw() {
+    assert_session_ends(
+        "draw.txt",
+        "it ends in GameOverState with the score You 0, Computer 0, Draws 1",
+    );
+}
+
+/// The player fills the first column with the computer, clicks into it once more, which does nothing,
+/// and restarts the game from the menu.
+#[test]
+fn illegal_click_and_restart() {
+    assert_session_ends(
+        "illegal_click_and_restart.txt",
+        "it ends in StartSelection with the score You 0, Computer 0, Draws 0",
+    );
+}
+
+/// The computer blocks the four of the player with another move than recorded, so the game goes on and
+/// the replay diverges.
+#[test]
+fn changed_session_diverges() {
+    let session =
+        fs::read_to_string(get_session("player_wins.txt")).expect("The session is there.");
+    assert!(session.contains("\nai 0 3 1 0\n"));
+    let changed = session.replace("\nai 0 3 1 0\n", "\nai 0 3 0 0\n");
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("changed_session.txt");
+    fs::write(&path, changed).expect("The changed session can be written.");
+
+    let (success, last_line) = replay(&path);
+    assert!(!success);
+    assert!(!last_line.contains(" 0 diverging"), "{last_line}");
+}
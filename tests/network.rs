@@ -0,0 +1,151 @@
+//! Plays network games between two remote players, whose connections are wired together over pipes
+//! instead of TCP, so that no port has to be opened.
+
+use connect_4_rust::board_logic::bit_board::MoveError;
+use connect_4_rust::board_logic::player::Player;
+use connect_4_rust::network::{Connection, NetworkError, RemotePlayer, Role};
+use std::io::{self, PipeWriter, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Gets the host and the guest connected with each other.
+fn connect_players() -> (RemotePlayer, RemotePlayer) {
+    let (host_reader, guest_writer) = io::pipe().unwrap();
+    let (guest_reader, host_writer) = io::pipe().unwrap();
+    let host = RemotePlayer::new(
+        Connection::from_streams(host_reader, host_writer).unwrap(),
+        Role::Host,
+    );
+    let guest = RemotePlayer::new(
+        Connection::from_streams(guest_reader, guest_writer).unwrap(),
+        Role::Guest,
+    );
+    (host, guest)
+}
+
+/// Updates the players, until the condition holds. The messages take a moment through the pipes.
+fn wait_for(
+    players: &mut [&mut RemotePlayer],
+    mut condition: impl FnMut(&mut [&mut RemotePlayer]) -> bool,
+) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !condition(players) {
+        assert!(Instant::now() < deadline, "The players did not get there.");
+        for player in players.iter_mut() {
+            player.update(0.01);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Waits for the player to receive the move and gets it.
+fn wait_for_move(player: &mut RemotePlayer) -> u32 {
+    let mut column = None;
+    wait_for(&mut [player], |players| {
+        column = players[0].poll_move();
+        column.is_some()
+    });
+    column.unwrap()
+}
+
+/// Gets the guest connected with a host, whose lines are written by the test.
+fn get_guest_of_written_host() -> (RemotePlayer, PipeWriter) {
+    let (guest_reader, host_writer) = io::pipe().unwrap();
+    let guest = RemotePlayer::new(
+        Connection::from_streams(guest_reader, io::sink()).unwrap(),
+        Role::Guest,
+    );
+    (guest, host_writer)
+}
+
+#[test]
+fn scripted_game_is_played_over_the_connection() {
+    let (mut host, mut guest) = connect_players();
+    wait_for(&mut [&mut host, &mut guest], |players| {
+        players.iter().all(|player| player.is_connected())
+    });
+
+    host.start_game(true);
+    let mut started_game = None;
+    wait_for(&mut [&mut guest], |players| {
+        started_game = players[0].take_started_game();
+        started_game.is_some()
+    });
+    assert_eq!(started_game, Some(false));
+
+    // The host wins with a horizontal line in the bottom row.
+    for (host_column, guest_column) in [(0, 6), (1, 6), (2, 6)] {
+        host.send_move(host_column);
+        assert_eq!(wait_for_move(&mut guest), host_column);
+        guest.send_move(guest_column);
+        assert_eq!(wait_for_move(&mut host), guest_column);
+    }
+    host.send_move(3);
+    assert_eq!(wait_for_move(&mut guest), 3);
+    assert!(host.is_connected() && guest.is_connected());
+
+    // After the win no move can be played any more, the guest ends the connection with its error.
+    guest.send_move(6);
+    assert!(guest.has_failed());
+    assert!(matches!(
+        guest.take_error(),
+        Some(NetworkError::UnexpectedMessage { .. })
+    ));
+    wait_for(&mut [&mut host], |players| players[0].has_failed());
+    assert_eq!(host.take_error(), Some(NetworkError::Disconnected));
+}
+
+#[test]
+fn resignation_reaches_the_other_side() {
+    let (mut host, mut guest) = connect_players();
+    wait_for(&mut [&mut host, &mut guest], |players| {
+        players.iter().all(|player| player.is_connected())
+    });
+    host.start_game(false);
+    wait_for(&mut [&mut guest], |players| {
+        players[0].take_started_game().is_some()
+    });
+    guest.send_move(3);
+    assert_eq!(wait_for_move(&mut host), 3);
+
+    host.resign();
+    wait_for(&mut [&mut guest], |players| players[0].take_resigned());
+    assert!(guest.is_connected());
+}
+
+#[test]
+fn illegal_move_is_not_delivered() {
+    let (mut guest, mut host_writer) = get_guest_of_written_host();
+    writeln!(host_writer, "HELLO 1\nSTART host\nMOVE 8\nMOVE 4").unwrap();
+    wait_for(&mut [&mut guest], |players| players[0].has_failed());
+    assert_eq!(
+        guest.take_error(),
+        Some(NetworkError::IllegalMove {
+            column: 7,
+            error: MoveError::InvalidColumn { column: 7 }
+        })
+    );
+    // Nothing is handed out after the illegal move, the connection is over.
+    assert_eq!(guest.poll_move(), None);
+}
+
+#[test]
+fn move_out_of_turn_is_not_delivered() {
+    let (mut guest, mut host_writer) = get_guest_of_written_host();
+    writeln!(host_writer, "HELLO 1\nSTART guest\nMOVE 4").unwrap();
+    wait_for(&mut [&mut guest], |players| players[0].has_failed());
+    assert!(matches!(
+        guest.take_error(),
+        Some(NetworkError::UnexpectedMessage { .. })
+    ));
+    assert_eq!(guest.poll_move(), None);
+}
+
+#[test]
+fn lost_connection_is_reported() {
+    let (mut guest, host_writer) = get_guest_of_written_host();
+    drop(host_writer);
+    wait_for(&mut [&mut guest], |players| players[0].has_failed());
+    assert_eq!(guest.take_error(), Some(NetworkError::Disconnected));
+    assert!(!guest.is_connected());
+}
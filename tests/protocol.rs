@@ -0,0 +1,67 @@
+//! Spawns the protocol binary and plays a scripted dialogue with it, the way a referee would talk to the
+//! engine through its stdin and stdout.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Runs the binary on the dialogue and gets the lines it answered with.
+fn run_dialogue(dialogue: &str) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_protocol"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("The protocol binary starts.");
+    child
+        .stdin
+        .take()
+        .expect("The input is piped.")
+        .write_all(dialogue.as_bytes())
+        .expect("The engine reads the dialogue.");
+    let output = child.wait_with_output().expect("The engine ends.");
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .expect("The answers are text.")
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn protocol_answers_the_dialogue() {
+    let answers = run_dialogue(
+        "position 414141\n\
+         go depth 4\n\
+         \n\
+         bogus\n\
+         position 448\n\
+         go depth 0\n\
+         position 4141414\n\
+         go depth 2\n\
+         newgame\n\
+         position\n\
+         go depth 1\n\
+         quit\n\
+         go depth 1\n",
+    );
+
+    assert_eq!(answers.len(), 8, "{answers:?}");
+    // The first player completes the four in the fourth column.
+    assert!(answers[0].starts_with("info depth "), "{}", answers[0]);
+    assert!(answers[0].ends_with(" square d4"), "{}", answers[0]);
+    assert_eq!(answers[1], "bestmove 4");
+    assert_eq!(answers[2], "error unknown command bogus");
+    assert_eq!(
+        answers[3],
+        "error invalid position: invalid column '8' at position 2"
+    );
+    assert_eq!(answers[4], "error invalid arguments for go");
+    assert_eq!(answers[5], "error the game is already over");
+    // The empty board after the new game, nothing gets answered after quit.
+    assert!(answers[6].starts_with("info depth 1 "), "{}", answers[6]);
+    assert!(answers[7].starts_with("bestmove "), "{}", answers[7]);
+}
+
+#[test]
+fn protocol_ends_with_the_input() {
+    assert!(run_dialogue("position 4\n").is_empty());
+}
@@ -0,0 +1,67 @@
+//! Checks that the search does not allocate per node. A global allocator counts the allocations of the
+//! thread, that runs the search. The transposition table grows in steps, so a search may allocate a few
+//! times, but by far not as often as it visits nodes.
+
+use connect_4_rust::board_logic::alpha_beta::{AlphaBeta, SearchConfig};
+use connect_4_rust::board_logic::bit_board::BitBoard;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// The most allocations a search may do, these are the growth steps of the transposition table and
+/// what the setup of the search needs.
+const MAX_ALLOCATIONS: u64 = 64;
+
+thread_local! {
+    /// The allocations of the thread so far.
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Forwards to the system allocator and counts the allocations and reallocations.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Gets the allocations of the calling thread so far.
+fn get_allocations() -> u64 {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn search_does_not_allocate_per_node() {
+    for moves in ["", "4453", "3344256", "44444361"] {
+        let board = BitBoard::from_move_string(moves).unwrap();
+        let mut engine = AlphaBeta::with_config(SearchConfig {
+            max_depth: 8,
+            move_time: None,
+            ..SearchConfig::default()
+        });
+
+        let allocations_before = get_allocations();
+        engine.get_best_move(board).unwrap();
+        let allocations = get_allocations() - allocations_before;
+
+        let nodes = engine.get_nodes_searched();
+        assert!(nodes > 10 * MAX_ALLOCATIONS, "{moves}: only {nodes} nodes");
+        assert!(
+            allocations <= MAX_ALLOCATIONS,
+            "{moves}: {allocations} allocations for {nodes} nodes"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Runs the solver binary over a fixture in the format of the public test sets, every line holds a move
+//! string and the outcome for the player to move, and checks the table it prints.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// The positions with their outcomes, 1 for a win, 0 for a draw and -1 for a loss.
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/solver_positions.txt"
+);
+
+/// Runs the binary with the arguments on the input.
+fn run_solver(arguments: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_solver"))
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("The solver binary starts.");
+    child
+        .stdin
+        .take()
+        .expect("The input is piped.")
+        .write_all(input.as_bytes())
+        .expect("The solver reads the input.");
+    child.wait_with_output().expect("The solver ends.")
+}
+
+/// Gets the lines of the output.
+fn get_lines(output: &[u8]) -> Vec<String> {
+    String::from_utf8(output.to_vec())
+        .expect("The output is text.")
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn solver_solves_the_fixture() {
+    let fixture = std::fs::read_to_string(FIXTURE).expect("The fixture is there.");
+    let expected: HashMap<&str, i32> = fixture
+        .lines()
+        .map(|line| {
+            let (moves, outcome) = line.split_once(' ').unwrap();
+            (moves, outcome.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(expected.len(), 20);
+
+    let output = run_solver(&["--threads", "4", FIXTURE], "");
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+
+    // The workers finish in any order, but every position comes exactly once.
+    let lines = get_lines(&output.stdout);
+    assert_eq!(lines.len(), expected.len());
+    let mut solved = HashMap::new();
+    for line in &lines {
+        let columns: Vec<&str> = line.split(' ').collect();
+        let [moves, score, best_move, nodes, time_ms] = columns[..] else {
+            panic!("The line {line} has not five columns.");
+        };
+        let score: f32 = score.parse().unwrap();
+        let best_move: u32 = best_move.parse().unwrap();
+        nodes.parse::<u64>().unwrap();
+        time_ms.parse::<u64>().unwrap();
+
+        let outcome = expected[moves];
+        assert_eq!(
+            (score > 0.0) as i32 - (score < 0.0) as i32,
+            outcome,
+            "{line}"
+        );
+        // The best move goes into a column, that is not full.
+        let column = char::from_digit(best_move, 10).unwrap();
+        assert!(moves.chars().filter(|&c| c == column).count() < 6, "{line}");
+        assert!(solved.insert(moves, outcome).is_none(), "{line}");
+    }
+    assert_eq!(solved, expected);
+}
+
+#[test]
+fn solver_reports_the_malformed_lines() {
+    let output = run_solver(&[], "4x\n\n414141 1\n4141414\n");
+    assert!(!output.status.success());
+
+    let lines = get_lines(&output.stdout);
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("414141 1 4 "), "{}", lines[0]);
+    let errors = get_lines(&output.stderr);
+    assert_eq!(
+        errors,
+        [
+            "line 1: invalid column 'x' at position 1",
+            "line 4: the game is already over",
+            "2 lines could not be solved",
+        ]
+    );
+}